@@ -493,15 +493,15 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
             );
 
             let source = TransientValue::new(output.into());
-            let issues = IssueVc::peek_issues_with_path(output)
+            let console_ui = ConsoleUiVc::new(log_options).as_issue_reporter();
+            let budget = *console_ui.issue_budget().await?;
+            let suppressions = console_ui.issue_suppressions();
+            let issues = IssueVc::peek_issues_with_path(output, budget, suppressions)
                 .await?
                 .strongly_consistent()
                 .await?;
 
-            let console_ui = ConsoleUiVc::new(log_options);
-            console_ui
-                .as_issue_reporter()
-                .report_issues(TransientInstance::new(issues), source);
+            console_ui.report_issues(TransientInstance::new(issues), source);
 
             if has_return_value {
                 let output_read_ref = output.await?;
@@ -11,6 +11,7 @@ pub mod glob;
 mod invalidation;
 mod invalidator_map;
 pub mod json;
+pub mod multi_root;
 mod mutex_map;
 mod read_glob;
 mod retry;
@@ -39,6 +40,7 @@ use auto_hash_map::AutoMap;
 use bitflags::bitflags;
 use dunce::simplified;
 use glob::GlobVc;
+pub use invalidation::WatchChange;
 use invalidator_map::InvalidatorMap;
 use jsonc_parser::{parse_to_serde_value, ParseOptions};
 use mime::Mime;
@@ -64,7 +66,7 @@ use util::{extract_disk_access, join_path, normalize_path, sys_to_unix, unix_to_
 use self::{invalidation::WatchStart, json::UnparseableJson, mutex_map::MutexMap};
 use crate::{
     attach::AttachedFileSystemVc,
-    invalidation::WatchChange,
+    multi_root::MultiRootFileSystemVc,
     retry::{retry_blocking, retry_future},
     rope::{Rope, RopeReadRef, RopeReader},
 };
@@ -209,6 +211,34 @@ impl DiskFileSystem {
         }
     }
 
+    /// Invalidates reads of the files or directories at `paths` and listings
+    /// of their parent directories. The paths are absolute system paths.
+    ///
+    /// This is useful when the changes are reported by the embedder instead of
+    /// the watcher.
+    pub fn invalidate_paths(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        let mut invalidator_map = self.invalidator_map.lock().unwrap();
+        let mut dir_invalidator_map = self.dir_invalidator_map.lock().unwrap();
+        for path in paths {
+            let mut invalidators = invalidator_map
+                .remove(&path_to_key(&path))
+                .unwrap_or_default();
+            invalidators.extend(
+                dir_invalidator_map
+                    .remove(&path_to_key(&path))
+                    .unwrap_or_default(),
+            );
+            if let Some(parent) = path.parent() {
+                invalidators.extend(
+                    dir_invalidator_map
+                        .remove(&path_to_key(parent))
+                        .unwrap_or_default(),
+                );
+            }
+            invalidators.into_iter().for_each(|i| i.invalidate());
+        }
+    }
+
     pub fn start_watching(&self) -> Result<()> {
         self.start_watching_internal(false)
     }
@@ -1942,6 +1972,11 @@ pub async fn to_sys_path(mut path: FileSystemPathVc) -> Result<Option<PathBuf>>
             continue;
         }
 
+        if let Some(fs) = MultiRootFileSystemVc::resolve_from(path.fs()).await? {
+            path = fs.get_inner_fs_path(path);
+            continue;
+        }
+
         if let Some(fs) = DiskFileSystemVc::resolve_from(path.fs()).await? {
             let sys_path = fs.await?.to_sys_path(path).await?;
             return Ok(Some(sys_path));
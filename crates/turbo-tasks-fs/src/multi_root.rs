@@ -0,0 +1,261 @@
+use anyhow::Result;
+use auto_hash_map::AutoMap;
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString, ValueToStringVc};
+
+use crate::{
+    to_sys_path,
+    util::{normalize_path, sys_to_unix},
+    DirectoryContent, DirectoryContentVc, DirectoryEntry, DiskFileSystemVc, FileContentVc,
+    FileMetaVc, FileSystem, FileSystemEntryType, FileSystemPathVc, FileSystemVc, LinkContent,
+    LinkContentVc, LinkType,
+};
+
+/// The directory of a [MultiRootFileSystem] that the linked roots are mounted
+/// in, each in a subdirectory named after the root.
+pub const LINKED_ROOTS_DIRECTORY: &str = "__linked__";
+
+/// A [FileSystem] that composes a primary [DiskFileSystem] with linked
+/// [DiskFileSystem]s, e.g. local libraries that are linked into
+/// `node_modules` from outside of the project.
+///
+/// Linked roots are mounted at `__linked__/<name>`. Symlinks that point from
+/// one root into another are followed within this file system, so their
+/// targets aren't outside of the project anymore.
+///
+/// The file system is named after the primary file system, so idents of
+/// files in the primary root don't depend on which roots are linked.
+///
+/// Caveat: `__linked__` is not visible as a directory entry.
+///
+/// [DiskFileSystem]: crate::DiskFileSystem
+#[turbo_tasks::value]
+pub struct MultiRootFileSystem {
+    primary: DiskFileSystemVc,
+    linked: Vec<(String, DiskFileSystemVc)>,
+}
+
+#[turbo_tasks::value_impl]
+impl MultiRootFileSystemVc {
+    /// Creates a [MultiRootFileSystem] without linked roots.
+    #[turbo_tasks::function]
+    pub fn new(primary: DiskFileSystemVc) -> Self {
+        MultiRootFileSystem {
+            primary,
+            linked: Vec::new(),
+        }
+        .cell()
+    }
+
+    /// Returns a [MultiRootFileSystem] which additionally mounts `root` at
+    /// `__linked__/<name>`. Linking another root with the same name replaces
+    /// it.
+    #[turbo_tasks::function]
+    pub async fn link(self, name: &str, root: DiskFileSystemVc) -> Result<Self> {
+        let this = self.await?;
+        let mut linked = this.linked.clone();
+        linked.retain(|(linked_name, _)| linked_name != name);
+        linked.push((name.to_string(), root));
+        Ok(MultiRootFileSystem {
+            primary: this.primary,
+            linked,
+        }
+        .cell())
+    }
+
+    /// Resolves the path of the primary or a linked file system from a path
+    /// on the [MultiRootFileSystem].
+    #[turbo_tasks::function]
+    pub async fn get_inner_fs_path(self, path: FileSystemPathVc) -> Result<FileSystemPathVc> {
+        let this = self.await?;
+        let path = path.await?;
+        if let Some(linked_path) = path
+            .path
+            .strip_prefix(LINKED_ROOTS_DIRECTORY)
+            .and_then(|path| path.strip_prefix('/'))
+        {
+            let (name, inner_path) = linked_path.split_once('/').unwrap_or((linked_path, ""));
+            if let Some((_, fs)) = this.linked.iter().find(|(linked, _)| linked == name) {
+                let fs: FileSystemVc = (*fs).into();
+                return Ok(fs.root().resolve().await?.join(inner_path));
+            }
+        }
+        let primary: FileSystemVc = this.primary.into();
+        Ok(primary.root().resolve().await?.join(&path.path))
+    }
+
+    /// Converts a path of the primary or a linked file system to a path in
+    /// this [FileSystem].
+    #[turbo_tasks::function]
+    pub async fn convert_path(self, inner_path_vc: FileSystemPathVc) -> Result<FileSystemPathVc> {
+        let this = self.await?;
+        let inner_path = inner_path_vc.await?;
+        let self_fs: FileSystemVc = self.into();
+        let root = self_fs.root().resolve().await?;
+        for (name, fs) in &this.linked {
+            let fs: FileSystemVc = (*fs).into();
+            if inner_path.fs == fs {
+                return Ok(root.join(&format!(
+                    "{}/{}/{}",
+                    LINKED_ROOTS_DIRECTORY, name, inner_path.path
+                )));
+            }
+        }
+        Ok(root.join(&inner_path.path))
+    }
+}
+
+impl MultiRootFileSystem {
+    /// Starts watching the primary and all linked roots.
+    pub async fn start_watching(&self) -> Result<()> {
+        self.primary.await?.start_watching()?;
+        for (_, fs) in &self.linked {
+            fs.await?.start_watching()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path in this [FileSystem] of the absolute system path
+    /// `sys_path`, relative to the root, if it is in one of the roots.
+    async fn path_of_sys_path(&self, sys_path: &str) -> Result<Option<String>> {
+        let Some(sys_path) = normalize_path(&sys_to_unix(sys_path)) else {
+            return Ok(None);
+        };
+        let roots = std::iter::once((None, self.primary))
+            .chain(self.linked.iter().map(|(name, fs)| (Some(name), *fs)));
+        for (name, fs) in roots {
+            let Some(root) = normalize_path(&sys_to_unix(&fs.await?.root)) else {
+                continue;
+            };
+            let inner_path = if sys_path == root {
+                ""
+            } else if let Some(inner_path) = sys_path
+                .strip_prefix(&root)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                inner_path
+            } else {
+                continue;
+            };
+            return Ok(Some(match name {
+                None => inner_path.to_string(),
+                Some(name) => format!("{}/{}/{}", LINKED_ROOTS_DIRECTORY, name, inner_path)
+                    .trim_end_matches('/')
+                    .to_string(),
+            }));
+        }
+        Ok(None)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl FileSystem for MultiRootFileSystem {
+    #[turbo_tasks::function]
+    fn read(self_vc: MultiRootFileSystemVc, path: FileSystemPathVc) -> FileContentVc {
+        self_vc.get_inner_fs_path(path).read()
+    }
+
+    #[turbo_tasks::function]
+    async fn read_link(
+        self_vc: MultiRootFileSystemVc,
+        path: FileSystemPathVc,
+    ) -> Result<LinkContentVc> {
+        let inner_path = self_vc.get_inner_fs_path(path);
+        let content = inner_path.read_link();
+        if !matches!(&*content.await?, LinkContent::Invalid) {
+            return Ok(content);
+        }
+
+        // The link points outside of its own root, which is fine as long as the
+        // target is inside of another root. Reading the link above already
+        // registered the invalidation for it.
+        let Some(sys_path) = to_sys_path(inner_path).await? else {
+            return Ok(content);
+        };
+        let Ok(link_path) = tokio::fs::read_link(&sys_path).await else {
+            return Ok(content);
+        };
+        let target = match sys_path.parent() {
+            Some(parent) => parent.join(link_path),
+            None => link_path,
+        };
+        let Some(target) = self_vc
+            .await?
+            .path_of_sys_path(&target.to_string_lossy())
+            .await? else {
+            return Ok(content);
+        };
+
+        let self_fs: FileSystemVc = self_vc.into();
+        let file_type = self_fs.root().join(&target).get_type().await?;
+        let mut link_type = LinkType::ABSOLUTE;
+        if matches!(&*file_type, FileSystemEntryType::Directory) {
+            link_type |= LinkType::DIRECTORY;
+        }
+        Ok(LinkContent::Link { target, link_type }.cell())
+    }
+
+    #[turbo_tasks::function]
+    async fn read_dir(
+        self_vc: MultiRootFileSystemVc,
+        path: FileSystemPathVc,
+    ) -> Result<DirectoryContentVc> {
+        let dir_content = self_vc.get_inner_fs_path(path).read_dir().await?;
+        let entries = match &*dir_content {
+            DirectoryContent::Entries(e) => e,
+            DirectoryContent::NotFound => return Ok(DirectoryContentVc::not_found()),
+        };
+
+        let mut converted_entries = AutoMap::with_capacity(entries.len());
+        for (name, entry) in entries {
+            use DirectoryEntry::*;
+
+            let entry = match *entry {
+                File(path) => File(self_vc.convert_path(path)),
+                Directory(path) => Directory(self_vc.convert_path(path)),
+                Symlink(path) => Symlink(self_vc.convert_path(path)),
+                Other(path) => Other(self_vc.convert_path(path)),
+                Error => Error,
+            };
+
+            converted_entries.insert(name.clone(), entry);
+        }
+
+        Ok(DirectoryContentVc::new(converted_entries))
+    }
+
+    #[turbo_tasks::function]
+    fn track(self_vc: MultiRootFileSystemVc, path: FileSystemPathVc) -> CompletionVc {
+        self_vc.get_inner_fs_path(path).track()
+    }
+
+    #[turbo_tasks::function]
+    fn write(
+        self_vc: MultiRootFileSystemVc,
+        path: FileSystemPathVc,
+        content: FileContentVc,
+    ) -> CompletionVc {
+        self_vc.get_inner_fs_path(path).write(content)
+    }
+
+    #[turbo_tasks::function]
+    fn write_link(
+        self_vc: MultiRootFileSystemVc,
+        path: FileSystemPathVc,
+        target: LinkContentVc,
+    ) -> CompletionVc {
+        self_vc.get_inner_fs_path(path).write_link(target)
+    }
+
+    #[turbo_tasks::function]
+    fn metadata(self_vc: MultiRootFileSystemVc, path: FileSystemPathVc) -> FileMetaVc {
+        self_vc.get_inner_fs_path(path).metadata()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for MultiRootFileSystem {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        self.primary.to_string()
+    }
+}
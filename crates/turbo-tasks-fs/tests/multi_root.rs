@@ -0,0 +1,178 @@
+#![cfg(test)]
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_fs::{
+    multi_root::MultiRootFileSystemVc, DirectoryContent, DirectoryEntry, DiskFileSystemVc,
+    FileContent, FileSystem, FileSystemPathVc, FileSystemVc,
+};
+use turbo_tasks_memory::MemoryBackend;
+
+/// The file system of the project at `primary`, with the library at `linked`
+/// linked as `lib`.
+fn multi_root(primary: &Path, linked: &Path) -> FileSystemVc {
+    let primary =
+        DiskFileSystemVc::new("project".to_string(), primary.to_str().unwrap().to_string());
+    let linked = DiskFileSystemVc::new("lib".to_string(), linked.to_str().unwrap().to_string());
+    MultiRootFileSystemVc::new(primary)
+        .link("lib", linked)
+        .into()
+}
+
+/// The content of the file at `path`, if there is one.
+async fn read(path: FileSystemPathVc) -> Result<Option<String>> {
+    Ok(match &*path.read().await? {
+        FileContent::Content(file) => Some(file.content().to_str()?.into_owned()),
+        FileContent::NotFound => None,
+    })
+}
+
+/// The names of the files in the directory at `path` and their paths.
+async fn files(path: FileSystemPathVc) -> Result<Vec<(String, String)>> {
+    let DirectoryContent::Entries(entries) = &*path.read_dir().await? else {
+        return Ok(Vec::new());
+    };
+    let mut files = Vec::new();
+    for (name, entry) in entries.iter() {
+        if let DirectoryEntry::File(path) = entry {
+            files.push((name.clone(), path.await?.path.clone()));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn write_roots(primary: &Path, linked: &Path) -> Result<()> {
+    fs::write(primary.join("index.js"), "import 'lib';")?;
+    fs::create_dir(linked.join("src"))?;
+    fs::write(linked.join("src/index.js"), "export default 1;")?;
+    fs::write(linked.join("src/util.js"), "export const util = 1;")?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn files_are_read_from_their_root() -> Result<()> {
+    turbo_tasks_fs::register();
+    let primary = tempfile::tempdir()?;
+    let linked = tempfile::tempdir()?;
+    write_roots(primary.path(), linked.path())?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let (primary_path, linked_path) = (primary.path().to_owned(), linked.path().to_owned());
+    tt.run_once(async move {
+        let root = multi_root(&primary_path, &linked_path).root();
+        assert_eq!(
+            read(root.join("index.js")).await?.as_deref(),
+            Some("import 'lib';")
+        );
+        assert_eq!(
+            read(root.join("__linked__/lib/src/index.js"))
+                .await?
+                .as_deref(),
+            Some("export default 1;")
+        );
+        // Roots that aren't linked are looked up in the primary root.
+        assert_eq!(read(root.join("__linked__/other/index.js")).await?, None);
+        assert_eq!(read(root.join("src/index.js")).await?, None);
+
+        // Directory entries of linked roots are paths in the multi-root file
+        // system.
+        assert_eq!(
+            files(root.join("__linked__/lib/src")).await?,
+            [
+                (
+                    "index.js".to_string(),
+                    "__linked__/lib/src/index.js".to_string()
+                ),
+                (
+                    "util.js".to_string(),
+                    "__linked__/lib/src/util.js".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            files(root).await?,
+            [("index.js".to_string(), "index.js".to_string())]
+        );
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_into_linked_roots_are_followed() -> Result<()> {
+    turbo_tasks_fs::register();
+    let primary = tempfile::tempdir()?;
+    let linked = tempfile::tempdir()?;
+    write_roots(primary.path(), linked.path())?;
+    fs::create_dir(primary.path().join("node_modules"))?;
+    std::os::unix::fs::symlink(linked.path(), primary.path().join("node_modules/lib"))?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let (primary_path, linked_path) = (primary.path().to_owned(), linked.path().to_owned());
+    tt.run_once(async move {
+        let root = multi_root(&primary_path, &linked_path).root();
+        let real = root.join("node_modules/lib/src/util.js").realpath();
+        assert_eq!(real.await?.path, "__linked__/lib/src/util.js");
+        assert_eq!(read(real).await?.as_deref(), Some("export const util = 1;"));
+        Ok(())
+    })
+    .await
+}
+
+/// Reads `src/util.js` of the linked root in a new task.
+async fn read_util(
+    tt: &TurboTasks<MemoryBackend>,
+    primary: &Path,
+    linked: &Path,
+) -> Result<Option<String>> {
+    let (primary, linked) = (primary.to_owned(), linked.to_owned());
+    tt.run_once(async move {
+        let root = multi_root(&primary, &linked).root();
+        read(root.join("__linked__/lib/src/util.js")).await
+    })
+    .await
+}
+
+#[tokio::test]
+async fn changes_in_linked_roots_invalidate_reads() -> Result<()> {
+    turbo_tasks_fs::register();
+    let primary = tempfile::tempdir()?;
+    let linked = tempfile::tempdir()?;
+    write_roots(primary.path(), linked.path())?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    assert_eq!(
+        read_util(&tt, primary.path(), linked.path())
+            .await?
+            .as_deref(),
+        Some("export const util = 1;")
+    );
+
+    fs::write(linked.path().join("src/util.js"), "export const util = 2;")?;
+    // The read is cached until the linked root is invalidated.
+    assert_eq!(
+        read_util(&tt, primary.path(), linked.path())
+            .await?
+            .as_deref(),
+        Some("export const util = 1;")
+    );
+    let linked_path = linked.path().to_str().unwrap().to_string();
+    tt.run_once(async move {
+        DiskFileSystemVc::new("lib".to_string(), linked_path)
+            .await?
+            .invalidate();
+        Ok(())
+    })
+    .await?;
+    assert_eq!(
+        read_util(&tt, primary.path(), linked.path())
+            .await?
+            .as_deref(),
+        Some("export const util = 2;")
+    );
+    Ok(())
+}
@@ -161,6 +161,18 @@ impl InvalidationReasonSet {
     pub fn len(&self) -> usize {
         self.map.len()
     }
+
+    /// All reasons of the set, including the ones that were merged by kind,
+    /// in insertion order.
+    pub fn reasons(&self) -> Vec<&dyn InvalidationReason> {
+        self.map
+            .values()
+            .flat_map(|entry| match entry {
+                MapEntry::Single { reason } => vec![&**reason],
+                MapEntry::Multiple { reasons } => reasons.iter().map(|reason| &**reason).collect(),
+            })
+            .collect()
+    }
 }
 
 impl Display for InvalidationReasonSet {
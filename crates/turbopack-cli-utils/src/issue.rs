@@ -16,8 +16,9 @@ use turbo_tasks::{
 };
 use turbo_tasks_fs::{source_context::get_source_context, FileLinesContent};
 use turbopack_core::issue::{
-    CapturedIssues, IssueReporter, IssueReporterVc, IssueSeverity, PlainIssue,
-    PlainIssueProcessingPathItem, PlainIssueProcessingPathItemReadRef, PlainIssueSource,
+    suppression::IssueSuppressionsVc, CapturedIssues, IssueReporter, IssueReporterVc,
+    IssueSeverity, PlainIssue, PlainIssueProcessingPathItem, PlainIssueProcessingPathItemReadRef,
+    PlainIssueSource,
 };
 
 use crate::source_context::format_source_context_lines;
@@ -323,6 +324,7 @@ impl SeenIssues {
 #[derive(Clone)]
 pub struct ConsoleUi {
     options: LogOptions,
+    issue_suppressions: Option<IssueSuppressionsVc>,
 
     #[turbo_tasks(trace_ignore, debug_ignore)]
     seen: Arc<Mutex<SeenIssues>>,
@@ -330,7 +332,7 @@ pub struct ConsoleUi {
 
 impl PartialEq for ConsoleUi {
     fn eq(&self, other: &Self) -> bool {
-        self.options == other.options
+        self.options == other.options && self.issue_suppressions == other.issue_suppressions
     }
 }
 
@@ -340,6 +342,22 @@ impl ConsoleUiVc {
     pub fn new(options: TransientInstance<LogOptions>) -> Self {
         ConsoleUi {
             options: (*options).clone(),
+            issue_suppressions: None,
+            seen: Arc::new(Mutex::new(SeenIssues::new())),
+        }
+        .cell()
+    }
+
+    /// Like [ConsoleUiVc::new], but doesn't report the issues that match
+    /// `issue_suppressions`.
+    #[turbo_tasks::function]
+    pub fn with_issue_suppressions(
+        options: TransientInstance<LogOptions>,
+        issue_suppressions: IssueSuppressionsVc,
+    ) -> Self {
+        ConsoleUi {
+            options: (*options).clone(),
+            issue_suppressions: Some(issue_suppressions),
             seen: Arc::new(Mutex::new(SeenIssues::new())),
         }
         .cell()
@@ -531,6 +549,12 @@ impl IssueReporter for ConsoleUi {
 
         Ok(BoolVc::cell(has_fatal))
     }
+
+    #[turbo_tasks::function]
+    fn issue_suppressions(&self) -> IssueSuppressionsVc {
+        self.issue_suppressions
+            .unwrap_or_else(IssueSuppressionsVc::empty)
+    }
 }
 
 fn make_relative_to_cwd<'a>(path: &'a str, project_dir: &Path, cwd: &Path) -> Cow<'a, str> {
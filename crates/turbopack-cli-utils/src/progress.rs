@@ -0,0 +1,36 @@
+use std::io::{stderr, Write};
+
+use owo_colors::OwoColorize as _;
+use turbo_tasks::util::FormatDuration;
+use turbopack_core::progress::{ProgressEvent, ProgressReceiver};
+
+/// Prints the progress of a build to stderr until the build drops its
+/// sender. The line of the running phase is updated in place, and a line is
+/// kept for each phase once it is done.
+pub async fn render_progress(mut receiver: ProgressReceiver) {
+    while let Some(event) = receiver.recv().await {
+        print_event(&event);
+    }
+}
+
+fn print_event(event: &ProgressEvent) {
+    let mut stderr = stderr().lock();
+    if event.done {
+        let _ = writeln!(
+            stderr,
+            "\x1b[2K{event_type} - {phase} {subject} ({total}) {duration}",
+            event_type = "event".purple(),
+            phase = event.phase,
+            subject = event.subject,
+            total = event.total,
+            duration = FormatDuration(event.elapsed),
+        );
+    } else {
+        let _ = write!(
+            stderr,
+            "\x1b[2K{event_type} - {event}\r",
+            event_type = "event".purple(),
+        );
+    }
+    let _ = stderr.flush();
+}
@@ -22,6 +22,13 @@ pub struct CommonArguments {
     #[clap(long, value_parser)]
     pub root: Option<PathBuf>,
 
+    /// A directory outside of the root directory that is linked into the
+    /// project, e.g. a local library that is symlinked into `node_modules`. It
+    /// is mounted at `__linked__/<name of the directory>`. Can be passed
+    /// multiple times.
+    #[clap(long = "link", value_parser)]
+    pub linked_roots: Vec<PathBuf>,
+
     /// Filter by issue severity.
     #[clap(short, long)]
     pub log_level: Option<IssueSeverityCliOption>,
@@ -34,6 +41,11 @@ pub struct CommonArguments {
     #[clap(long)]
     pub log_detail: bool,
 
+    /// A file listing issues that aren't reported, relative to `dir`.
+    /// Defaults to `turbopack-suppressions.json`.
+    #[clap(long, value_parser)]
+    pub issue_suppressions: Option<PathBuf>,
+
     /// Whether to enable full task stats recording in Turbo Engine.
     #[clap(long)]
     pub full_stats: bool,
@@ -42,6 +54,17 @@ pub struct CommonArguments {
     /// MB.
     #[clap(long)]
     pub memory_limit: Option<usize>,
+
+    /// Record which chunks are rebuilt by each update and why. The records are
+    /// available at `/__turbopack__/`.
+    #[clap(long)]
+    pub record_rebuilds: bool,
+
+    /// Measure the time spent on resolving, transforming and generating chunk
+    /// items per file, and print the slowest packages and files after each
+    /// compilation.
+    #[clap(long)]
+    pub profile_assets: bool,
 }
 
 #[derive(Debug, Args)]
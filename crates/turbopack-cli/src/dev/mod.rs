@@ -4,12 +4,12 @@ use std::{
     future::{join, Future},
     io::{stdout, Write},
     net::{IpAddr, SocketAddr},
-    path::{PathBuf, MAIN_SEPARATOR},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::Arc,
     time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use dunce::canonicalize;
 use owo_colors::OwoColorize;
 use turbo_malloc::TurboMalloc;
@@ -17,13 +17,29 @@ use turbo_tasks::{
     util::{FormatBytes, FormatDuration},
     StatsType, TransientInstance, TurboTasks, TurboTasksBackendApi, UpdateInfo, Value,
 };
-use turbo_tasks_fs::{DiskFileSystemVc, FileSystem, FileSystemVc};
+use turbo_tasks_fs::{
+    multi_root::MultiRootFileSystemVc, DiskFileSystemVc, FileSystem, FileSystemPathVc, FileSystemVc,
+};
 use turbo_tasks_memory::MemoryBackend;
 use turbopack::evaluate_context::node_build_environment;
 use turbopack_cli_utils::issue::{ConsoleUiVc, LogOptions};
 use turbopack_core::{
+    chunk::{CancellationToken, CancellationTokenVc},
     environment::ServerAddr,
-    issue::{IssueReporterVc, IssueSeverity},
+    introspect::{
+        asset_timing::{
+            asset_timing_report, enable_asset_timing, is_asset_timing_enabled, reset_asset_timings,
+            DEFAULT_REPORT_LIMIT,
+        },
+        rebuilds::{
+            changed_files, enable_rebuild_recording, finish_rebuild_update,
+            is_rebuild_recording_enabled, RebuildRecorderIntrospectableVc,
+        },
+    },
+    issue::{
+        suppression::{IssueSuppressionsVc, ISSUE_SUPPRESSIONS_FILE_NAME},
+        IssueReporterVc, IssueSeverity, IssueSink, IssueSinkReporterVc,
+    },
     resolve::{parse::RequestVc, pattern::QueryMapVc},
     server_fs::ServerFileSystemVc,
 };
@@ -35,7 +51,7 @@ use turbopack_dev_server::{
         source_maps::SourceMapContentSourceVc, static_assets::StaticAssetsContentSourceVc,
         ContentSourceVc,
     },
-    DevServer, DevServerBuilder,
+    DevServer, DevServerBuilder, SourceProvider,
 };
 use turbopack_env::dotenv::load_env;
 use turbopack_node::execution_context::ExecutionContextVc;
@@ -56,16 +72,20 @@ pub struct TurbopackDevServerBuilder {
     turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
     project_dir: String,
     root_dir: String,
+    linked_roots: Vec<String>,
     entry_requests: Vec<EntryRequest>,
     eager_compile: bool,
     hostname: Option<IpAddr>,
     issue_reporter: Option<Box<dyn IssueReporterProvider>>,
+    issue_sink: Option<Arc<dyn IssueSink>>,
     port: Option<u16>,
     browserslist_query: String,
     log_level: IssueSeverity,
     show_all: bool,
     log_detail: bool,
     allow_retry: bool,
+    cancellation_token: CancellationToken,
+    issue_suppressions: String,
 }
 
 impl TurbopackDevServerBuilder {
@@ -78,10 +98,12 @@ impl TurbopackDevServerBuilder {
             turbo_tasks,
             project_dir,
             root_dir,
+            linked_roots: vec![],
             entry_requests: vec![],
             eager_compile: false,
             hostname: None,
             issue_reporter: None,
+            issue_sink: None,
             port: None,
             browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 Safari \
                                  versions, last 1 Edge versions"
@@ -90,6 +112,8 @@ impl TurbopackDevServerBuilder {
             show_all: false,
             log_detail: false,
             allow_retry: false,
+            cancellation_token: CancellationToken::new(),
+            issue_suppressions: ISSUE_SUPPRESSIONS_FILE_NAME.to_string(),
         }
     }
 
@@ -98,6 +122,16 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    /// Mounts the directory `dir` outside of the root directory, e.g. a local
+    /// library that is symlinked into `node_modules`, at
+    /// `__linked__/<name of dir>` in the project file system, see
+    /// [MultiRootFileSystem](turbo_tasks_fs::multi_root::MultiRootFileSystem).
+    /// Symlinks into `dir` are followed and changes in it are watched.
+    pub fn link_root(mut self, dir: String) -> TurbopackDevServerBuilder {
+        self.linked_roots.push(dir);
+        self
+    }
+
     pub fn eager_compile(mut self, eager_compile: bool) -> TurbopackDevServerBuilder {
         self.eager_compile = eager_compile;
         self
@@ -138,6 +172,29 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    /// Chunking of the dev server stops early once `cancellation_token` is
+    /// cancelled, e.g. when the embedder shuts the server down. Chunking that
+    /// was aborted this way is redone on the next request after the token is
+    /// [reset](CancellationToken::reset). The server
+    /// [restarts](CancellationToken::restart) the token whenever a new request
+    /// starts, so chunking started for earlier requests doesn't delay it.
+    pub fn cancellation_token(
+        mut self,
+        cancellation_token: CancellationToken,
+    ) -> TurbopackDevServerBuilder {
+        self.cancellation_token = cancellation_token;
+        self
+    }
+
+    /// Issues that match the [suppression
+    /// file](turbopack_core::issue::suppression) at `path`, relative to the
+    /// directory of the application, aren't reported by the default issue
+    /// reporter. Defaults to [ISSUE_SUPPRESSIONS_FILE_NAME].
+    pub fn issue_suppressions(mut self, path: String) -> TurbopackDevServerBuilder {
+        self.issue_suppressions = path;
+        self
+    }
+
     pub fn issue_reporter(
         mut self,
         issue_reporter: Box<dyn IssueReporterProvider>,
@@ -146,6 +203,13 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    /// Passes the issues that the server reports to `issue_sink` as well, see
+    /// [IssueSinkReporterVc].
+    pub fn issue_sink(mut self, issue_sink: impl IssueSink) -> TurbopackDevServerBuilder {
+        self.issue_sink = Some(Arc::new(issue_sink));
+        self
+    }
+
     /// Attempts to find an open port to bind.
     fn find_port(&self, host: IpAddr, port: u16, max_attempts: u16) -> Result<DevServerBuilder> {
         // max_attempts of 1 means we loop 0 times.
@@ -195,6 +259,7 @@ impl TurbopackDevServerBuilder {
         let turbo_tasks = self.turbo_tasks;
         let project_dir = self.project_dir;
         let root_dir = self.root_dir;
+        let linked_roots = self.linked_roots;
         let eager_compile = self.eager_compile;
         let show_all = self.show_all;
         let log_detail = self.log_detail;
@@ -207,33 +272,102 @@ impl TurbopackDevServerBuilder {
             log_level: self.log_level,
         });
         let entry_requests = Arc::new(self.entry_requests);
+        let cancellation_token = Arc::new(self.cancellation_token);
         let tasks = turbo_tasks.clone();
         let issue_provider = self.issue_reporter.unwrap_or_else(|| {
+            let root_dir = root_dir.clone();
+            let linked_roots = linked_roots.clone();
+            let project_dir = project_dir.clone();
+            let issue_suppressions_path = self.issue_suppressions;
             // Initialize a ConsoleUi reporter if no custom reporter was provided
-            Box::new(move || ConsoleUiVc::new(log_args.clone().into()).into())
+            Box::new(move || {
+                let suppressions = issue_suppressions(
+                    &root_dir,
+                    linked_roots.clone(),
+                    &project_dir,
+                    &issue_suppressions_path,
+                );
+                ConsoleUiVc::with_issue_suppressions(log_args.clone().into(), suppressions).into()
+            })
         });
 
-        let source = move || {
-            source(
-                root_dir.clone(),
-                project_dir.clone(),
-                entry_requests.clone().into(),
-                eager_compile,
-                turbo_tasks.clone().into(),
-                browserslist_query.clone(),
-            )
+        let source = CliSourceProvider {
+            cancellation_token: cancellation_token.clone(),
+            source: move || {
+                source(
+                    root_dir.clone(),
+                    linked_roots.clone(),
+                    project_dir.clone(),
+                    entry_requests.clone().into(),
+                    eager_compile,
+                    turbo_tasks.clone().into(),
+                    browserslist_query.clone(),
+                    cancellation_token.clone().into(),
+                )
+            },
         };
 
-        let issue_reporter_arc = Arc::new(move || issue_provider.get_issue_reporter());
+        let issue_sink = self.issue_sink;
+        let issue_reporter_arc = Arc::new(move || {
+            let issue_reporter = issue_provider.get_issue_reporter();
+            match &issue_sink {
+                Some(issue_sink) => IssueSinkReporterVc::new(
+                    TransientInstance::new(issue_sink.clone()),
+                    issue_reporter,
+                )
+                .into(),
+                None => issue_reporter,
+            }
+        });
         Ok(server.serve(tasks, source, issue_reporter_arc))
     }
 }
 
+/// Provides the source of the dev server and supersedes the chunking of
+/// earlier requests when a new request starts.
+#[derive(Clone)]
+struct CliSourceProvider<F> {
+    source: F,
+    cancellation_token: Arc<CancellationToken>,
+}
+
+impl<F> SourceProvider for CliSourceProvider<F>
+where
+    F: Fn() -> ContentSourceVc + Send + Clone + 'static,
+{
+    fn get_source(&self) -> ContentSourceVc {
+        (self.source)()
+    }
+
+    fn request_started(&self) {
+        self.cancellation_token.restart();
+    }
+}
+
+/// The file system of the project root. Linked roots are mounted in it by
+/// the name of their directory.
 #[turbo_tasks::function]
-async fn project_fs(project_dir: &str) -> Result<FileSystemVc> {
+async fn project_fs(project_dir: &str, linked_roots: Vec<String>) -> Result<FileSystemVc> {
     let disk_fs = DiskFileSystemVc::new("project".to_string(), project_dir.to_string());
-    disk_fs.await?.start_watching()?;
-    Ok(disk_fs.into())
+    if linked_roots.is_empty() {
+        disk_fs.await?.start_watching()?;
+        return Ok(disk_fs.into());
+    }
+    let mut fs = MultiRootFileSystemVc::new(disk_fs);
+    for root in linked_roots {
+        let name = linked_root_name(&root)?;
+        fs = fs.link(name, DiskFileSystemVc::new(name.to_string(), root.clone()));
+    }
+    fs.await?.start_watching().await?;
+    Ok(fs.into())
+}
+
+/// The name that the linked root `dir` is mounted by.
+fn linked_root_name(dir: &str) -> Result<&str> {
+    Path::new(dir)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("linked root {dir} has no name"))
 }
 
 #[turbo_tasks::function]
@@ -243,27 +377,49 @@ async fn output_fs(project_dir: &str) -> Result<FileSystemVc> {
     Ok(disk_fs.into())
 }
 
+/// The directory of the application in the file system of the project root.
+#[turbo_tasks::function]
+fn project_path(root_dir: &str, linked_roots: Vec<String>, project_dir: &str) -> FileSystemPathVc {
+    let project_relative = project_dir.strip_prefix(root_dir).unwrap();
+    let project_relative = project_relative
+        .strip_prefix(MAIN_SEPARATOR)
+        .unwrap_or(project_relative)
+        .replace(MAIN_SEPARATOR, "/");
+    project_fs(root_dir, linked_roots)
+        .root()
+        .join(&project_relative)
+}
+
+/// The suppressions of the suppression file at `path` relative to the
+/// directory of the application. A missing file doesn't suppress any issues.
+#[turbo_tasks::function]
+fn issue_suppressions(
+    root_dir: &str,
+    linked_roots: Vec<String>,
+    project_dir: &str,
+    path: &str,
+) -> IssueSuppressionsVc {
+    IssueSuppressionsVc::read(project_path(root_dir, linked_roots, project_dir).join(path))
+}
+
 #[allow(clippy::too_many_arguments)]
 #[turbo_tasks::function]
 async fn source(
     root_dir: String,
+    linked_roots: Vec<String>,
     project_dir: String,
     entry_requests: TransientInstance<Vec<EntryRequest>>,
     eager_compile: bool,
     turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
     browserslist_query: String,
+    cancellation_token: TransientInstance<CancellationToken>,
 ) -> Result<ContentSourceVc> {
     let output_fs = output_fs(&project_dir);
-    let fs = project_fs(&root_dir);
-    let project_relative = project_dir.strip_prefix(&root_dir).unwrap();
-    let project_relative = project_relative
-        .strip_prefix(MAIN_SEPARATOR)
-        .unwrap_or(project_relative)
-        .replace(MAIN_SEPARATOR, "/");
-    let project_path = fs.root().join(&project_relative);
+    let project_path = project_path(&root_dir, linked_roots, &project_dir);
 
     let env = load_env(project_path);
     let build_output_root = output_fs.root().join(".turbopack/build");
+    let cancellation_token = CancellationTokenVc::new((*cancellation_token).clone());
 
     let build_chunking_context = DevChunkingContextVc::builder(
         project_path,
@@ -272,6 +428,7 @@ async fn source(
         build_output_root.join("assets"),
         node_build_environment(),
     )
+    .cancellation_token(cancellation_token)
     .build();
 
     let execution_context = ExecutionContextVc::new(project_path, build_chunking_context, env);
@@ -296,6 +453,7 @@ async fn source(
         env,
         eager_compile,
         &browserslist_query,
+        cancellation_token,
     );
     let viz = turbo_tasks_viz::TurboTasksSource {
         turbo_tasks: turbo_tasks.into(),
@@ -305,8 +463,12 @@ async fn source(
     let static_source =
         StaticAssetsContentSourceVc::new(String::new(), project_path.join("public")).into();
     let main_source = CombinedContentSourceVc::new(vec![static_source, web_source]);
+    let mut introspection_roots = HashSet::from([main_source.into()]);
+    if is_rebuild_recording_enabled() {
+        introspection_roots.insert(RebuildRecorderIntrospectableVc::new());
+    }
     let introspect = IntrospectionSource {
-        roots: HashSet::from([main_source.into()]),
+        roots: introspection_roots,
     }
     .cell()
     .into();
@@ -331,6 +493,19 @@ pub fn register() {
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
 
+/// Prints the slowest packages and files since the previous report, if asset
+/// timing is enabled.
+fn print_asset_timing_report() {
+    if !is_asset_timing_enabled() {
+        return;
+    }
+    let report = asset_timing_report(DEFAULT_REPORT_LIMIT);
+    reset_asset_timings();
+    if !report.files.is_empty() {
+        println!("{} - asset timings\n{}", "event".purple(), report);
+    }
+}
+
 /// Start a devserver with the given args.
 pub async fn start_server(args: &DevArguments) -> Result<()> {
     let start = Instant::now();
@@ -339,6 +514,9 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
     console_subscriber::init();
     register();
 
+    enable_rebuild_recording(args.common.record_rebuilds);
+    enable_asset_timing(args.common.profile_assets);
+
     let dir = args
         .common
         .dir
@@ -360,6 +538,23 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         dir.clone()
     };
 
+    let mut linked_roots = Vec::new();
+    for root in args.common.linked_roots.iter() {
+        let root = canonicalize(root)
+            .context("linked root can't be found")?
+            .to_str()
+            .context("linked root contains invalid characters")?
+            .to_string();
+        let name = linked_root_name(&root)?;
+        if linked_roots
+            .iter()
+            .any(|linked: &String| linked_root_name(linked).ok() == Some(name))
+        {
+            bail!("linked roots need distinct directory names, {name} is linked twice");
+        }
+        linked_roots.push(root);
+    }
+
     let tt = TurboTasks::new(MemoryBackend::new(
         args.common
             .memory_limit
@@ -373,15 +568,24 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
     tt.set_stats_type(stats_type);
 
     let tt_clone = tt.clone();
+    let cancellation_token = CancellationToken::new();
 
-    #[allow(unused_mut)]
     let mut server = TurbopackDevServerBuilder::new(tt, dir, root_dir)
+        .cancellation_token(cancellation_token.clone())
         .entry_request(EntryRequest::Relative("src/index".into()))
         .eager_compile(args.eager_compile)
         .hostname(args.hostname)
         .port(args.port)
         .log_detail(args.common.log_detail)
         .show_all(args.common.show_all)
+        .issue_suppressions(
+            args.common
+                .issue_suppressions
+                .as_deref()
+                .map_or(ISSUE_SUPPRESSIONS_FILE_NAME.to_string(), |path| {
+                    path.to_string_lossy().replace(MAIN_SEPARATOR, "/")
+                }),
+        )
         .log_level(
             args.common
                 .log_level
@@ -392,6 +596,9 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
     {
         server = server.allow_retry(args.allow_retry);
     }
+    for root in linked_roots {
+        server = server.link_root(root);
+    }
 
     let server = server.build().await?;
 
@@ -424,6 +631,9 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
             );
         }
 
+        finish_rebuild_update(&"initial compilation", &[]);
+        print_asset_timing_report();
+
         let mut progress_counter = 0;
         loop {
             let update_future = profile_timeout(
@@ -439,6 +649,8 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
             }) = update_future.await
             {
                 progress_counter = 0;
+                finish_rebuild_update(&reasons, &changed_files(&reasons));
+                print_asset_timing_report();
                 match (args.common.log_detail, !reasons.is_empty()) {
                     (true, true) => {
                         println!(
@@ -495,7 +707,14 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         }
     };
 
-    join!(stats_future, async { server.future.await.unwrap() }).await;
+    tokio::select! {
+        _ = join!(stats_future, async { server.future.await.unwrap() }) => {}
+        _ = tokio::signal::ctrl_c() => {
+            // Chunking that is still in progress bails out instead of
+            // finishing work that nobody will read.
+            cancellation_token.cancel();
+        }
+    }
 
     Ok(())
 }
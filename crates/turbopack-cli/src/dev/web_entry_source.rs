@@ -17,7 +17,9 @@ use turbopack::{
 };
 use turbopack_cli_utils::runtime_entry::{RuntimeEntriesVc, RuntimeEntry};
 use turbopack_core::{
-    chunk::{ChunkableAsset, ChunkableAssetVc, ChunkingContext, ChunkingContextVc},
+    chunk::{
+        CancellationTokenVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext, ChunkingContextVc,
+    },
     compile_time_defines,
     compile_time_info::{CompileTimeDefinesVc, CompileTimeInfo, CompileTimeInfoVc},
     context::AssetContextVc,
@@ -183,6 +185,7 @@ pub fn get_client_chunking_context(
     project_path: FileSystemPathVc,
     server_root: FileSystemPathVc,
     environment: EnvironmentVc,
+    cancellation_token: CancellationTokenVc,
 ) -> ChunkingContextVc {
     DevChunkingContextVc::builder(
         project_path,
@@ -192,6 +195,7 @@ pub fn get_client_chunking_context(
         environment,
     )
     .hot_module_replacement()
+    .cancellation_token(cancellation_token)
     .build()
 }
 
@@ -231,11 +235,16 @@ pub async fn create_web_entry_source(
     _env: ProcessEnvVc,
     eager_compile: bool,
     browserslist_query: &str,
+    cancellation_token: CancellationTokenVc,
 ) -> Result<ContentSourceVc> {
     let compile_time_info = get_client_compile_time_info(browserslist_query);
     let context = get_client_asset_context(project_path, execution_context, compile_time_info);
-    let chunking_context =
-        get_client_chunking_context(project_path, server_root, compile_time_info.environment());
+    let chunking_context = get_client_chunking_context(
+        project_path,
+        server_root,
+        compile_time_info.environment(),
+        cancellation_token,
+    );
     let entries = get_client_runtime_entries(project_path);
 
     let runtime_entries = entries.resolve_entries(context);
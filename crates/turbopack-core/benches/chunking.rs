@@ -0,0 +1,125 @@
+use std::future::Future;
+
+use anyhow::Result;
+use criterion::{BenchmarkId, Criterion};
+use turbo_tasks::{NothingVc, TryJoinIterExt, TurboTasks, Value};
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack_core::{
+    asset::AssetVc,
+    chunk::{availability_info::AvailabilityInfo, Chunk, ChunkingContext},
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    test_utils::{
+        optimize_synthetic_chunks, synthetic_graph, SyntheticChunkVc, SyntheticChunkingContextVc,
+        SyntheticChunksVc, SyntheticGraphOptions,
+    },
+};
+
+use super::register;
+
+/// Module counts of the benchmarked graphs. Throughput is reported in
+/// modules, so results can be compared across changes to the traversal code.
+///
+/// CI stores the results of every push to main on the `benchmark-data`
+/// branch. To compare a change locally, run
+/// `cargo bench -p turbopack-core --features bench_chunking -- --save-baseline
+/// main` on main and `critcmp --export main > main.json`, do the same for the
+/// change, and run `cargo xtask compare-benchmarks main.json change.json`,
+/// which fails when a benchmark got more than 10% slower.
+const MODULE_COUNTS: [u64; 2] = [10_000, 100_000];
+
+const FAN_OUT: u32 = 10;
+
+fn graph_options(modules: u64) -> SyntheticGraphOptions {
+    SyntheticGraphOptions {
+        shared_percent: 10,
+        shared_modules: (modules / 100) as u32,
+        ..SyntheticGraphOptions::with_module_count(modules, FAN_OUT)
+    }
+}
+
+fn run_once<F>(c: &mut Criterion, name: &str, f: fn(SyntheticGraphOptions) -> F)
+where
+    F: Future<Output = Result<()>> + Send + 'static,
+{
+    let mut group = c.benchmark_group("turbopack_core_chunking");
+    group.sample_size(10);
+
+    for modules in MODULE_COUNTS {
+        let options = graph_options(modules);
+        group.throughput(criterion::Throughput::Elements(options.tree_size()));
+        group.bench_with_input(BenchmarkId::new(name, modules), &options, |b, options| {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let options = *options;
+
+            b.to_async(rt).iter_with_large_drop(move || {
+                let tt = TurboTasks::new(MemoryBackend::default());
+                async move {
+                    let task = tt.spawn_once_task(async move {
+                        f(options).await?;
+                        Ok(NothingVc::new().into())
+                    });
+                    tt.wait_task_completion(task, false).await.unwrap();
+                    tt
+                }
+            })
+        });
+    }
+}
+
+pub fn chunk_content(c: &mut Criterion) {
+    register();
+
+    run_once(c, "chunk_content", |options| async move {
+        let chunk = root_chunk(Value::new(options));
+        chunk.chunk_content().await?;
+        Ok(())
+    });
+}
+
+pub fn optimize(c: &mut Criterion) {
+    register();
+
+    run_once(c, "optimize", |options| async move {
+        let chunks = chunk_group(root_chunk(Value::new(options)));
+        optimize_synthetic_chunks(chunks).await?;
+        Ok(())
+    });
+}
+
+#[turbo_tasks::function]
+fn root_chunk(options: Value<SyntheticGraphOptions>) -> SyntheticChunkVc {
+    let fs: NullFileSystemVc = NullFileSystem.into();
+    let root: FileSystemPathVc = fs.root();
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Custom(0)),
+        Value::new(EnvironmentIntention::Client),
+    );
+    let context = SyntheticChunkingContextVc::new(root, environment);
+    let entry = synthetic_graph(root, options);
+    SyntheticChunkVc::new(
+        context.into(),
+        entry,
+        Value::new(AvailabilityInfo::Root {
+            current_availability_root: entry.into(),
+        }),
+    )
+}
+
+#[turbo_tasks::function]
+async fn chunk_group(chunk: SyntheticChunkVc) -> Result<SyntheticChunksVc> {
+    let chunks = chunk
+        .chunking_context()
+        .chunk_group(chunk.into())
+        .await?
+        .iter()
+        .map(|asset: &AssetVc| SyntheticChunkVc::resolve_from(*asset))
+        .try_join()
+        .await?;
+    Ok(SyntheticChunksVc::cell(
+        chunks.into_iter().flatten().collect(),
+    ))
+}
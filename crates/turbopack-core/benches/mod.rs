@@ -0,0 +1,17 @@
+#![feature(min_specialization)]
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+pub(crate) mod chunking;
+
+criterion_group!(
+    name = turbopack_core_chunking;
+    config = Criterion::default();
+    targets = chunking::chunk_content, chunking::optimize
+);
+criterion_main!(turbopack_core_chunking);
+
+pub fn register() {
+    turbopack_core::register();
+    include!(concat!(env!("OUT_DIR"), "/register_benches.rs"));
+}
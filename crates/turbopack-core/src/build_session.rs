@@ -0,0 +1,657 @@
+//! A high-level API to embed turbopack in tools that aren't built on
+//! turbo-tasks, e.g. custom dev servers or SSR frameworks.
+//!
+//! A [BuildSession] owns the turbo-tasks runtime, the file systems of the
+//! project and of the output and the contexts that entrypoints are built with.
+//! Apps with several pages are built with [BuildSession::get_app_assets],
+//! which places modules that several pages use into shared chunks.
+//! Builds are incremental: after [BuildSession::update] only the work that
+//! depends on the changed files is redone. The output of each build is written
+//! with an [EmitTransaction], so a crashed build doesn't leave a half-written
+//! output directory behind. A build fails if different assets would be written
+//! to the same path. Sessions can be made reproducible with
+//! [BuildSession::set_deterministic], and assets with identical content can be
+//! written once with [BuildSession::set_deduplicate]. The progress of builds is
+//! reported to the sender set with [BuildSession::set_progress].
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexSet;
+use turbo_tasks::{
+    backend::Backend, primitives::StringsVc, trace::TraceRawVcs, CompletionVc, TryJoinIterExt,
+    TurboTasks, Value, ValueToString,
+};
+use turbo_tasks_fs::{
+    rope::{Rope, RopeBuilder},
+    DiskFileSystemVc, FileContent, FileSystem, FileSystemPath, FileSystemPathVc, FileSystemVc,
+};
+
+use crate::{
+    asset::{Asset, AssetContent, AssetVc, AssetsVc},
+    chunk::{
+        output_path_collisions, ChunkGroup, ChunkableAsset, ChunkableAssetReference,
+        ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc, SharedChunkPlanVc,
+    },
+    context::{AssetContext, AssetContextVc},
+    deterministic::{
+        changed_assets, content_hash, rewrite_absolute_paths, NondeterministicAssetIssue,
+    },
+    emit_transaction::{DeduplicationReport, EmitTransaction},
+    issue::{budget::IssueBudget, suppression::IssueSuppressionsVc, IssueVc},
+    progress::{BuildPhase, ProgressSender},
+    reference::{all_assets, AssetReferenceVc},
+    reference_type::{EntryReferenceSubType, ReferenceType},
+    source_asset::SourceAssetVc,
+    PROJECT_FILESYSTEM_NAME,
+};
+
+/// The contexts that entrypoints are built with.
+#[derive(Debug, Clone, Copy, TraceRawVcs)]
+pub struct BuildContext {
+    pub asset_context: AssetContextVc,
+    pub chunking_context: ChunkingContextVc,
+}
+
+/// Creates the [BuildContext] of a registered context from the roots of the
+/// project and of the output directory.
+type CreateBuildContext =
+    Arc<dyn Fn(FileSystemPathVc, FileSystemPathVc) -> BuildContext + Send + Sync>;
+
+/// A context registered with [BuildSession::register_context].
+struct RegisteredContext {
+    context: BuildContext,
+    create: CreateBuildContext,
+}
+
+/// The entrypoints of a build.
+#[derive(Debug, Clone)]
+enum BuildEntries {
+    /// A single entrypoint, see [BuildSession::get_entrypoint_assets].
+    Entrypoint(String),
+    /// The entries of the pages of an app, see [BuildSession::get_app_assets].
+    App {
+        entries: Vec<String>,
+        min_shared_entries: usize,
+    },
+}
+
+impl BuildEntries {
+    /// The subject of the progress events of the build.
+    fn subject(&self) -> String {
+        match self {
+            BuildEntries::Entrypoint(entry) => entry.clone(),
+            BuildEntries::App { entries, .. } => entries.join(", "),
+        }
+    }
+}
+
+/// An asset that was generated for an entrypoint.
+#[derive(Debug, Clone, TraceRawVcs)]
+pub struct EntrypointAsset {
+    /// The path relative to the output directory, with `/` separators.
+    pub path: String,
+    /// The content of the asset, or `None` if it isn't a file.
+    pub content: Option<Rope>,
+    /// The path of the asset with identical content that was written instead
+    /// of this one, if assets are deduplicated.
+    pub alias_of: Option<String>,
+}
+
+/// Summarizes the assets that weren't written because an asset with
+/// identical content was written instead.
+pub fn deduplication_report(assets: &[EntrypointAsset]) -> DeduplicationReport {
+    assets.iter().filter(|asset| asset.alias_of.is_some()).fold(
+        DeduplicationReport::default(),
+        |mut report, asset| {
+            report.aliases += 1;
+            report.saved_bytes += asset.content.as_ref().map_or(0, |content| content.len()) as u64;
+            report
+        },
+    )
+}
+
+/// An incremental build of the entrypoints of a project.
+pub struct BuildSession<B: Backend + 'static> {
+    turbo_tasks: Arc<TurboTasks<B>>,
+    project_fs: DiskFileSystemVc,
+    output_fs: DiskFileSystemVc,
+    project_dir: String,
+    output_dir: PathBuf,
+    contexts: HashMap<String, RegisteredContext>,
+    /// Creates the backend of the separate runtime that verifies that builds
+    /// are deterministic, if they should be.
+    deterministic: Option<Arc<dyn Fn() -> B + Send + Sync>>,
+    deduplicate: bool,
+    progress: ProgressSender,
+}
+
+impl<B: Backend + 'static> BuildSession<B> {
+    /// Creates a session that reads sources from `project_dir` and writes
+    /// generated assets to `output_dir`. Both are absolute paths.
+    pub async fn new(
+        turbo_tasks: Arc<TurboTasks<B>>,
+        project_dir: String,
+        output_dir: String,
+    ) -> Result<Self> {
+        let output_dir_path = PathBuf::from(&output_dir);
+        let project_dir_path = project_dir.clone();
+        let (project_fs, output_fs) = turbo_tasks
+            .run_once(async move {
+                let project_fs =
+                    DiskFileSystemVc::new(PROJECT_FILESYSTEM_NAME.to_string(), project_dir)
+                        .resolve()
+                        .await?;
+                let output_fs = DiskFileSystemVc::new("output".to_string(), output_dir)
+                    .resolve()
+                    .await?;
+                Ok((project_fs, output_fs))
+            })
+            .await?;
+        Ok(Self {
+            turbo_tasks,
+            project_fs,
+            output_fs,
+            project_dir: project_dir_path,
+            output_dir: output_dir_path,
+            contexts: HashMap::new(),
+            deterministic: None,
+            deduplicate: false,
+            progress: ProgressSender::default(),
+        })
+    }
+
+    /// Writes assets with identical content, e.g. from duplicated packages,
+    /// only once. The paths of the other assets are recorded as aliases in
+    /// the alias manifest of the output directory and in
+    /// [EntrypointAsset::alias_of].
+    pub fn set_deduplicate(&mut self, deduplicate: bool) {
+        self.deduplicate = deduplicate;
+    }
+
+    /// Reports the progress of builds to `progress`, see
+    /// [crate::progress]. Without a receiver, builds skip the extra walk of
+    /// the module graph that counts the modules.
+    pub fn set_progress(&mut self, progress: ProgressSender) {
+        self.progress = progress;
+    }
+
+    /// Registers the contexts that [BuildSession::get_entrypoint_assets]
+    /// builds entrypoints with under `name`. `create` is called with the
+    /// roots of the project and of the output directory, again for each
+    /// runtime that verifies that builds are deterministic.
+    pub async fn register_context(
+        &mut self,
+        name: impl Into<String>,
+        create: impl Fn(FileSystemPathVc, FileSystemPathVc) -> BuildContext + Send + Sync + 'static,
+    ) -> Result<()> {
+        let create: CreateBuildContext = Arc::new(create);
+        let context = self.create_context(create.clone()).await?;
+        self.contexts
+            .insert(name.into(), RegisteredContext { context, create });
+        Ok(())
+    }
+
+    async fn create_context(&self, create: CreateBuildContext) -> Result<BuildContext> {
+        let project_fs = self.project_fs;
+        let output_fs = self.output_fs;
+        self.turbo_tasks
+            .run_once(async move {
+                let project_fs: FileSystemVc = project_fs.into();
+                let output_fs: FileSystemVc = output_fs.into();
+                let context = create(project_fs.root(), output_fs.root());
+                Ok(BuildContext {
+                    asset_context: context.asset_context.resolve().await?,
+                    chunking_context: context.chunking_context.resolve().await?,
+                })
+            })
+            .await
+    }
+
+    /// Invalidates everything that was computed from the files or
+    /// directories at `changed_paths`, which are absolute paths. The next
+    /// call to [BuildSession::get_entrypoint_assets] recomputes it.
+    pub async fn update(&self, changed_paths: Vec<PathBuf>) -> Result<()> {
+        let project_fs = self.project_fs;
+        self.turbo_tasks
+            .run_once(async move {
+                project_fs.await?.invalidate_paths(changed_paths);
+                Ok(())
+            })
+            .await
+    }
+
+    /// Builds `entry`, a path relative to the project directory, with the
+    /// contexts registered as `context`, writes the generated assets to the
+    /// output directory in a single transaction and returns them.
+    pub async fn get_entrypoint_assets(
+        &self,
+        context: &str,
+        entry: &str,
+    ) -> Result<Vec<EntrypointAsset>> {
+        let mut pages = self
+            .get_assets(context, BuildEntries::Entrypoint(entry.to_string()))
+            .await?;
+        Ok(pages.pop().unwrap_or_default())
+    }
+
+    /// Builds the pages of an app with the entries `entries`, paths relative
+    /// to the project directory, with the contexts registered as `context`.
+    /// Modules that at least `min_shared_entries` of the entries use are
+    /// placed into shared chunks, see [SharedChunkPlanVc]. The generated
+    /// assets of all pages are written to the output directory in a single
+    /// transaction. Returns the assets that the page of each entry loads, in
+    /// the order of `entries`.
+    pub async fn get_app_assets(
+        &self,
+        context: &str,
+        entries: &[&str],
+        min_shared_entries: usize,
+    ) -> Result<Vec<Vec<EntrypointAsset>>> {
+        self.get_assets(
+            context,
+            BuildEntries::App {
+                entries: entries.iter().map(|entry| entry.to_string()).collect(),
+                min_shared_entries,
+            },
+        )
+        .await
+    }
+
+    /// Builds `entries` with the contexts registered as `context`, writes the
+    /// generated assets and returns the assets of each page.
+    async fn get_assets(
+        &self,
+        context: &str,
+        entries: BuildEntries,
+    ) -> Result<Vec<Vec<EntrypointAsset>>> {
+        let Some(registered) = self.contexts.get(context) else {
+            bail!("no build context named {} is registered", context);
+        };
+        let mut pages = self.build(registered.context, entries.clone()).await?;
+
+        if let Some(new_backend) = &self.deterministic {
+            // A separate runtime executes the build again instead of
+            // returning the cached assets.
+            let rebuilt_pages = self
+                .rebuild(new_backend(), registered.create.clone(), entries.clone())
+                .await?;
+            let changed = changed_assets(
+                &content_hashes(pages.iter().flatten())?,
+                &content_hashes(rebuilt_pages.iter().flatten())?,
+            );
+            if !changed.is_empty() {
+                let output_fs = self.output_fs;
+                let paths = changed.clone();
+                self.turbo_tasks
+                    .run_once(async move {
+                        let output_fs: FileSystemVc = output_fs.into();
+                        let reported = report_nondeterministic_assets(
+                            output_fs.root(),
+                            StringsVc::cell(paths),
+                        );
+                        IssueVc::peek_issues_with_path(
+                            reported,
+                            IssueBudget::default(),
+                            IssueSuppressionsVc::empty(),
+                        )
+                        .await?;
+                        Ok(())
+                    })
+                    .await?;
+                bail!(
+                    "the build isn't deterministic, these assets differ between two builds: {}",
+                    changed.join(", ")
+                );
+            }
+        }
+
+        // Pages share assets, which are written once.
+        let mut written = HashSet::new();
+        let files = pages
+            .iter()
+            .flatten()
+            .filter(|asset| asset.content.is_some() && written.insert(asset.path.as_str()))
+            .collect::<Vec<_>>();
+        let emitting = self
+            .progress
+            .start(BuildPhase::Emitting, &entries.subject(), files.len());
+        let mut transaction = EmitTransaction::begin(&self.output_dir)?
+            .zero_timestamps(self.deterministic.is_some())
+            .deduplicate(self.deduplicate);
+        for asset in files {
+            if let Some(content) = &asset.content {
+                transaction.stage(&asset.path, &content.to_bytes()?)?;
+                emitting.advance(1);
+            }
+        }
+        for asset in pages.iter_mut().flatten() {
+            asset.alias_of = transaction.alias_of(&asset.path).map(str::to_string);
+        }
+        transaction.commit()?;
+        emitting.finish();
+        Ok(pages)
+    }
+
+    /// Builds `entries` with `build_context` and reads the generated assets
+    /// of each page.
+    async fn build(
+        &self,
+        build_context: BuildContext,
+        entries: BuildEntries,
+    ) -> Result<Vec<Vec<EntrypointAsset>>> {
+        let BuildContext {
+            asset_context,
+            chunking_context,
+        } = build_context;
+        let project_fs = self.project_fs;
+        let output_fs = self.output_fs;
+        let progress = self.progress.clone();
+        let mut pages = self
+            .turbo_tasks
+            .run_once(async move {
+                let project_fs: FileSystemVc = project_fs.into();
+                let output_fs: FileSystemVc = output_fs.into();
+                let output_root = output_fs.root();
+                let paths = match &entries {
+                    BuildEntries::Entrypoint(entry) => vec![entry.clone()],
+                    BuildEntries::App { entries, .. } => entries.clone(),
+                };
+                let assets = paths
+                    .iter()
+                    .map(|path| entry_asset(asset_context, project_fs.root().join(path)))
+                    .collect::<Vec<_>>();
+                if progress.is_enabled() {
+                    for (asset, path) in assets.iter().zip(paths.iter()) {
+                        walk_module_graph((*asset).into(), path, &progress).await?;
+                    }
+                }
+
+                let subject = entries.subject();
+                let chunking = progress.start(BuildPhase::Chunking, &subject, assets.len());
+                let (page_assets, chunks) = match &entries {
+                    BuildEntries::Entrypoint(_) => {
+                        let root_chunk = assets[0].as_root_chunk(chunking_context);
+                        let chunks = ChunkGroup::new(chunking_context, root_chunk).assets();
+                        chunking.advance(1);
+                        (vec![output_assets(chunks, output_root)], vec![root_chunk])
+                    }
+                    BuildEntries::App {
+                        min_shared_entries, ..
+                    } => {
+                        let plan = SharedChunkPlanVc::new(
+                            chunking_context,
+                            AssetsVc::cell(assets.iter().map(|asset| (*asset).into()).collect()),
+                            *min_shared_entries,
+                        );
+                        let mut page_assets = Vec::new();
+                        for asset in assets.iter() {
+                            let chunks = plan.entry_assets((*asset).into());
+                            page_assets.push(output_assets(chunks, output_root));
+                            chunking.advance(1);
+                        }
+                        let plan = plan.await?;
+                        let chunks = plan
+                            .shared_chunks
+                            .iter()
+                            .chain(plan.entry_chunks.values())
+                            .copied()
+                            .collect::<Vec<_>>();
+                        (page_assets, chunks)
+                    }
+                };
+                for assets in page_assets.iter() {
+                    assets.await?;
+                }
+                chunking.finish();
+
+                // Generates the chunks within the chunk generation limit of the
+                // context before their content is read below.
+                for chunk in chunks {
+                    ChunkGroup::new(chunking_context, chunk).generate().await?;
+                }
+                check_output_paths(&page_assets).await?;
+                let output_root = &*output_root.await?;
+                let mut pages = Vec::new();
+                for assets in page_assets {
+                    pages.push(read_assets(assets, output_root).await?);
+                }
+                Ok(pages)
+            })
+            .await?;
+
+        if self.deterministic.is_some() {
+            for asset in pages.iter_mut().flatten() {
+                let Some(content) = &asset.content else {
+                    continue;
+                };
+                let bytes = content.to_bytes()?;
+                if let Cow::Owned(rewritten) = rewrite_absolute_paths(&bytes, &self.project_dir) {
+                    asset.content = Some(RopeBuilder::from(rewritten).build());
+                }
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Builds `entries` from scratch in a new runtime with `backend`, without
+    /// writing the generated assets.
+    async fn rebuild(
+        &self,
+        backend: B,
+        create: CreateBuildContext,
+        entries: BuildEntries,
+    ) -> Result<Vec<Vec<EntrypointAsset>>> {
+        let mut session = BuildSession::new(
+            TurboTasks::new(backend),
+            self.project_dir.clone(),
+            self.output_dir.to_string_lossy().into_owned(),
+        )
+        .await?;
+        session.deterministic = self.deterministic.clone();
+        let build_context = session.create_context(create).await?;
+        let pages = session.build(build_context, entries).await;
+        session.shutdown().await;
+        pages
+    }
+
+    /// Waits for all running tasks to finish and stops the runtime.
+    pub async fn shutdown(self) {
+        self.turbo_tasks.stop_and_wait().await;
+    }
+}
+
+impl<B: Backend + Default + 'static> BuildSession<B> {
+    /// Makes builds reproducible: absolute paths of the project directory in
+    /// generated assets are replaced with a virtual root, the modification
+    /// times of written files are zeroed, and each entrypoint is built again
+    /// in a separate runtime to verify that the output is identical. Assets
+    /// that differ are emitted as [NondeterministicAssetIssue]s and fail the
+    /// build.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic.then(|| Arc::new(B::default) as Arc<_>);
+    }
+}
+
+/// The content hashes of `assets` by path.
+fn content_hashes<'a>(
+    assets: impl Iterator<Item = &'a EntrypointAsset>,
+) -> Result<HashMap<String, u64>> {
+    assets
+        .map(|asset| {
+            let hash = match &asset.content {
+                Some(content) => content_hash(&content.to_bytes()?),
+                None => 0,
+            };
+            Ok((asset.path.clone(), hash))
+        })
+        .collect()
+}
+
+/// Emits a [NondeterministicAssetIssue] for each of `paths`, which are
+/// relative to `output_root`.
+#[turbo_tasks::function]
+async fn report_nondeterministic_assets(
+    output_root: FileSystemPathVc,
+    paths: StringsVc,
+) -> Result<CompletionVc> {
+    for path in paths.await?.iter() {
+        NondeterministicAssetIssue {
+            path: output_root.join(path),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+    Ok(CompletionVc::new())
+}
+
+/// The module of the entry at `entry_path`.
+#[turbo_tasks::function]
+async fn entry_asset(
+    asset_context: AssetContextVc,
+    entry_path: FileSystemPathVc,
+) -> Result<ChunkableAssetVc> {
+    let asset = asset_context.process(
+        SourceAssetVc::new(entry_path).into(),
+        Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined)),
+    );
+    let Some(asset) = ChunkableAssetVc::resolve_from(asset).await? else {
+        bail!("entry {} can't be chunked", entry_path.to_string().await?);
+    };
+    Ok(asset)
+}
+
+/// All assets in the output directory that are needed to evaluate `chunks`.
+#[turbo_tasks::function]
+async fn output_assets(chunks: AssetsVc, output_root: FileSystemPathVc) -> Result<AssetsVc> {
+    let output_root = output_root.await?;
+    let mut assets = IndexSet::new();
+    for chunk in chunks.await?.iter() {
+        for asset in all_assets(*chunk).await?.iter() {
+            if asset.ident().path().await?.is_inside(&output_root) {
+                assets.insert(*asset);
+            }
+        }
+    }
+    Ok(AssetsVc::cell(assets.into_iter().collect()))
+}
+
+/// Fails if different assets of the pages are written to the same path, see
+/// [output_path_collisions].
+async fn check_output_paths(page_assets: &[AssetsVc]) -> Result<()> {
+    let mut assets = IndexSet::new();
+    for page in page_assets {
+        assets.extend(page.await?.iter().copied());
+    }
+    // The file systems of macOS and Windows are case-insensitive by default.
+    let case_insensitive = cfg!(any(target_os = "macos", target_os = "windows"));
+    let collisions = output_path_collisions(
+        AssetsVc::cell(assets.into_iter().collect()),
+        case_insensitive,
+    )
+    .await?;
+    if let Some(collision) = collisions.first() {
+        bail!(
+            "output path collision: {} is emitted by {}",
+            collision.path,
+            collision.idents.join(" and ")
+        );
+    }
+    Ok(())
+}
+
+/// Reads `assets`, which are inside of `output_root`.
+async fn read_assets(
+    assets: AssetsVc,
+    output_root: &FileSystemPath,
+) -> Result<Vec<EntrypointAsset>> {
+    assets
+        .await?
+        .iter()
+        .map(|&asset| async move {
+            let path = asset.ident().path().await?;
+            let path = output_root
+                .get_path_to(&path)
+                .context("asset is outside of the output directory")?
+                .to_string();
+            let content = match &*asset.content().await? {
+                AssetContent::File(file) => match &*file.await? {
+                    FileContent::Content(file) => Some(file.content().clone()),
+                    FileContent::NotFound => None,
+                },
+                AssetContent::Redirect { .. } => None,
+            };
+            Ok(EntrypointAsset {
+                path,
+                content,
+                alias_of: None,
+            })
+        })
+        .try_join()
+        .await
+}
+
+/// Transforms the modules of the module graph of `entry` and resolves their
+/// references, reporting the progress of both phases. Like chunking, it only
+/// follows references that place their assets in chunks, so chunking the
+/// entry afterwards reuses the cached results.
+async fn walk_module_graph(entry: AssetVc, subject: &str, progress: &ProgressSender) -> Result<()> {
+    let transforming = progress.start(BuildPhase::Transforming, subject, 1);
+    let resolving = progress.start(BuildPhase::Resolving, subject, 0);
+    let mut visited = HashSet::from([entry]);
+    let mut modules = vec![entry];
+    while !modules.is_empty() {
+        let referenced = modules
+            .iter()
+            .map(|module| {
+                let transforming = &transforming;
+                let resolving = &resolving;
+                async move {
+                    let references = chunkable_references(*module).await?;
+                    transforming.advance(1);
+                    resolving.grow(references.len());
+                    references
+                        .iter()
+                        .map(|reference| async move {
+                            let assets = reference.resolve_reference().primary_assets().await?;
+                            resolving.advance(1);
+                            Ok(assets)
+                        })
+                        .try_join()
+                        .await
+                }
+            })
+            .try_join()
+            .await?;
+        modules = referenced
+            .iter()
+            .flatten()
+            .flat_map(|assets| assets.iter().copied())
+            .filter(|asset| visited.insert(*asset))
+            .collect();
+        transforming.grow(modules.len());
+    }
+    resolving.finish();
+    transforming.finish();
+    Ok(())
+}
+
+/// The references of `module` that chunking follows.
+async fn chunkable_references(module: AssetVc) -> Result<Vec<AssetReferenceVc>> {
+    let mut references = Vec::new();
+    for &reference in module.references().await?.iter() {
+        let Some(chunkable) = ChunkableAssetReferenceVc::resolve_from(reference).await? else {
+            continue;
+        };
+        if chunkable.chunking_type().await?.is_some() {
+            references.push(reference);
+        }
+    }
+    Ok(references)
+}
@@ -1,3 +1,5 @@
+use anyhow::Result;
+
 use super::available_assets::AvailableAssetsVc;
 use crate::asset::AssetVc;
 
@@ -37,4 +39,16 @@ impl AvailabilityInfo {
             } => Some(*available_assets),
         }
     }
+
+    /// The roots of the chunk groups that a chunk group with this availability
+    /// is loaded from, outermost first, followed by its own root. Empty if
+    /// availability isn't tracked.
+    pub async fn availability_roots(&self) -> Result<Vec<AssetVc>> {
+        let mut roots = match self.available_assets() {
+            Some(available_assets) => available_assets.roots().await?.clone_value(),
+            None => Vec::new(),
+        };
+        roots.extend(self.current_availability_root());
+        Ok(roots)
+    }
 }
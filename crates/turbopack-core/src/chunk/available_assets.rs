@@ -10,7 +10,7 @@ use turbo_tasks_hash::Xxh3Hash64Hasher;
 
 use super::{ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingType};
 use crate::{
-    asset::{Asset, AssetVc, AssetsSetVc},
+    asset::{Asset, AssetVc, AssetsSetVc, AssetsVc},
     reference::AssetReference,
 };
 
@@ -63,6 +63,19 @@ impl AvailableAssetsVc {
         Ok(U64Vc::cell(hasher.finish()))
     }
 
+    /// All roots, including those of the parents, in the order in which they
+    /// were made available.
+    #[turbo_tasks::function]
+    pub async fn roots(self) -> Result<AssetsVc> {
+        let this = self.await?;
+        let mut roots = match this.parent {
+            Some(parent) => parent.roots().await?.clone_value(),
+            None => Vec::new(),
+        };
+        roots.extend(this.roots.iter().copied());
+        Ok(AssetsVc::cell(roots))
+    }
+
     #[turbo_tasks::function]
     pub async fn includes(self, asset: AssetVc) -> Result<BoolVc> {
         let this = self.await?;
@@ -80,36 +93,59 @@ impl AvailableAssetsVc {
     }
 }
 
+/// The assets that are placed into the chunks of `root`, including `root`, in
+/// reverse topological order.
 #[turbo_tasks::function]
-async fn chunkable_assets_set(root: AssetVc) -> Result<AssetsSetVc> {
+pub(super) async fn chunkable_assets_set(root: AssetVc) -> Result<AssetsSetVc> {
     let assets = ReverseTopological::new()
         .skip_duplicates()
-        .visit(once(root), |&asset: &AssetVc| async move {
-            let mut results = Vec::new();
-            for reference in asset.references().await?.iter() {
-                if let Some(chunkable) = ChunkableAssetReferenceVc::resolve_from(reference).await? {
-                    if matches!(
-                        &*chunkable.chunking_type().await?,
-                        Some(
-                            ChunkingType::Parallel
-                                | ChunkingType::PlacedOrParallel
-                                | ChunkingType::Placed
-                        )
-                    ) {
-                        results.extend(
-                            chunkable
-                                .resolve_reference()
-                                .primary_assets()
-                                .await?
-                                .iter()
-                                .copied(),
-                        );
-                    }
-                }
-            }
-            Ok(results)
+        .visit(once(root), |&asset: &AssetVc| {
+            chunkable_references(asset, false)
         })
         .await
         .completed()?;
     Ok(AssetsSetVc::cell(assets.into_inner().into_iter().collect()))
 }
+
+/// The assets that are reachable from `root` through chunkable references,
+/// including `root` and the assets of the chunk groups that are loaded
+/// separately, e.g. by async imports, in reverse topological order.
+#[turbo_tasks::function]
+pub(super) async fn reachable_chunkable_assets(root: AssetVc) -> Result<AssetsSetVc> {
+    let assets = ReverseTopological::new()
+        .skip_duplicates()
+        .visit(once(root), |&asset: &AssetVc| {
+            chunkable_references(asset, true)
+        })
+        .await
+        .completed()?;
+    Ok(AssetsSetVc::cell(assets.into_inner().into_iter().collect()))
+}
+
+/// The assets that `asset` references through chunkable references that place
+/// them into the same chunk group, or into any chunk group if `separate`.
+async fn chunkable_references(asset: AssetVc, separate: bool) -> Result<Vec<AssetVc>> {
+    let mut results = Vec::new();
+    for reference in asset.references().await?.iter() {
+        if let Some(chunkable) = ChunkableAssetReferenceVc::resolve_from(reference).await? {
+            let included = match &*chunkable.chunking_type().await? {
+                Some(
+                    ChunkingType::Parallel | ChunkingType::PlacedOrParallel | ChunkingType::Placed,
+                ) => true,
+                Some(_) => separate,
+                None => false,
+            };
+            if included {
+                results.extend(
+                    chunkable
+                        .resolve_reference()
+                        .primary_assets()
+                        .await?
+                        .iter()
+                        .copied(),
+                );
+            }
+        }
+    }
+    Ok(results)
+}
@@ -0,0 +1,211 @@
+//! Cooperative cancellation of long running chunking operations.
+//!
+//! A [CancellationToken] is owned by the embedder (e.g. the dev server) and
+//! handed to a chunking context via [CancellationTokenVc::new]. Chunking
+//! operations check the token regularly and bail out with a
+//! [ChunkingCancelled] error once it has been cancelled. Tasks that failed
+//! because of a cancellation are invalidated when the token is
+//! [reset](CancellationToken::reset), so they are recomputed on the next
+//! read instead of returning the cached error.
+//!
+//! Long running operations take a [CancellationGuard] when they start. The
+//! guard is also cancelled once the token is
+//! [restarted](CancellationToken::restart), which supersedes all operations
+//! that started before. Superseded tasks are invalidated right away, so they
+//! are recomputed with the current inputs.
+//!
+//! The turbopack CLI dev server restarts its token whenever a new request or
+//! update starts and cancels it when it is interrupted. Embedders of the dev
+//! server pass their own token to
+//! `TurbopackDevServerBuilder::cancellation_token` and keep a clone of it to
+//! cancel chunking, e.g. when they shut down or know that the current
+//! results are stale, and reset it before they read results again.
+
+use std::{
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, Invalidator};
+
+/// The error returned by chunking operations that were cancelled.
+#[derive(Debug)]
+pub struct ChunkingCancelled;
+
+impl Display for ChunkingCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "chunking operation was cancelled")
+    }
+}
+
+impl std::error::Error for ChunkingCancelled {}
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    /// Incremented by [CancellationToken::restart].
+    generation: AtomicU64,
+    /// Invalidates tasks that observed the cancellation.
+    invalidators: Mutex<Vec<Invalidator>>,
+}
+
+/// A token that signals chunking operations to stop early.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    state: Arc<CancellationState>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests all chunking operations using this token to stop.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Makes the token usable again after a cancellation. Tasks that were
+    /// aborted by the cancellation are invalidated.
+    pub fn reset(&self) {
+        self.state.cancelled.store(false, Ordering::Release);
+        let invalidators = std::mem::take(&mut *self.state.invalidators.lock().unwrap());
+        for invalidator in invalidators {
+            invalidator.invalidate();
+        }
+    }
+
+    /// Supersedes all operations that started before, while operations that
+    /// start afterwards run normally. Also [resets](Self::reset) the token.
+    pub fn restart(&self) {
+        self.state.generation.fetch_add(1, Ordering::AcqRel);
+        self.reset();
+    }
+
+    /// Starts an operation, which is cancelled when the token is cancelled or
+    /// restarted.
+    pub fn guard(&self) -> CancellationGuard {
+        CancellationGuard {
+            token: self.clone(),
+            generation: self.state.generation.load(Ordering::Acquire),
+        }
+    }
+
+    /// Returns a [ChunkingCancelled] error when the token was cancelled.
+    ///
+    /// Must be called from within a turbo task, which will be invalidated
+    /// when the token is reset.
+    pub fn check(&self) -> Result<()> {
+        if !self.is_cancelled() {
+            return Ok(());
+        }
+        Err(self.cancelled())
+    }
+
+    /// Creates a [ChunkingCancelled] error for an operation that observed the
+    /// cancellation. The current task will be invalidated when the token is
+    /// reset.
+    pub fn cancelled(&self) -> anyhow::Error {
+        self.state
+            .invalidators
+            .lock()
+            .unwrap()
+            .push(get_invalidator());
+        ChunkingCancelled.into()
+    }
+}
+
+/// A running operation of a [CancellationToken].
+pub struct CancellationGuard {
+    token: CancellationToken,
+    generation: u64,
+}
+
+impl CancellationGuard {
+    /// Whether the token was cancelled or restarted since the operation
+    /// started.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.is_superseded()
+    }
+
+    fn is_superseded(&self) -> bool {
+        self.token.state.generation.load(Ordering::Acquire) != self.generation
+    }
+
+    /// Returns a [ChunkingCancelled] error when the operation was cancelled.
+    ///
+    /// Must be called from within a turbo task, see
+    /// [CancellationToken::check].
+    pub fn check(&self) -> Result<()> {
+        if !self.is_cancelled() {
+            return Ok(());
+        }
+        Err(self.cancelled())
+    }
+
+    /// Creates a [ChunkingCancelled] error for an operation that observed the
+    /// cancellation. A superseded task is invalidated right away, all others
+    /// when the token is reset.
+    pub fn cancelled(&self) -> anyhow::Error {
+        if self.token.is_cancelled() {
+            return self.token.cancelled();
+        }
+        get_invalidator().invalidate();
+        ChunkingCancelled.into()
+    }
+}
+
+impl CancellationTokenVc {
+    pub fn new(token: CancellationToken) -> Self {
+        Self::cell(token)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CancellationTokenVc {
+    /// A token that is never cancelled.
+    #[turbo_tasks::function]
+    pub fn never() -> Self {
+        Self::cell(CancellationToken::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+
+    #[test]
+    fn test_restart_cancels_previous_operations() {
+        let token = CancellationToken::new();
+        let previous = token.guard();
+        assert!(!previous.is_cancelled());
+
+        token.restart();
+        assert!(previous.is_cancelled());
+        assert!(!token.is_cancelled());
+
+        let current = token.guard();
+        assert!(!current.is_cancelled());
+        token.restart();
+        assert!(current.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancel_cancels_all_operations() {
+        let token = CancellationToken::new();
+        let guard = token.guard();
+        token.cancel();
+        assert!(guard.is_cancelled());
+        assert!(token.guard().is_cancelled());
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+use crate::ident::AssetIdentVc;
+
+/// Number of hex characters of the entries hash that are kept in a
+/// [ChunkIdent].
+const HASH_LENGTH: usize = 8;
+
+/// A stable, human-debuggable identifier of a chunk.
+///
+/// Unlike the chunk path, which may contain a content hash, it is derived from
+/// the primary entries of the chunk only. It stays the same when the content
+/// of the chunk changes, which makes it useful to correlate chunks across
+/// rebuilds in manifests and HMR messages.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug)]
+pub struct ChunkIdent {
+    /// The kind of the chunk, e.g. `ecmascript` or `css`.
+    pub ty: String,
+    /// The first primary entry relative to the context path, followed by the
+    /// number of additional entries, e.g. `pages/index.js (+2)`.
+    pub name: String,
+    /// A short hash of all primary entries, which disambiguates chunks with
+    /// the same name.
+    pub hash: String,
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkIdentVc {
+    /// Creates a [ChunkIdent] for a chunk of kind `ty` with the given primary
+    /// entries. The order of the entries is significant.
+    #[turbo_tasks::function]
+    pub async fn from_entries(
+        ty: String,
+        context_path: FileSystemPathVc,
+        entries: Vec<AssetIdentVc>,
+    ) -> Result<Self> {
+        let context_path = context_path.await?;
+
+        let mut name = None;
+        let mut hashed = String::new();
+        for entry in &entries {
+            let entry_string = entry.to_string().await?;
+            if name.is_none() {
+                let path = entry.path().await?;
+                name = Some(match context_path.get_path_to(&path) {
+                    Some(relative) => relative.to_string(),
+                    None => path.path.clone(),
+                });
+            }
+            hashed.push_str(&entry_string);
+            hashed.push('\0');
+        }
+
+        let mut name = name.unwrap_or_else(|| "<empty>".to_string());
+        if entries.len() > 1 {
+            name = format!("{name} (+{})", entries.len() - 1);
+        }
+        let mut hash = encode_hex(hash_xxh3_hash64(hashed.as_bytes()));
+        hash.truncate(HASH_LENGTH);
+
+        Ok(ChunkIdent { ty, name, hash }.cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ChunkIdent {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        StringVc::cell(format!("[{}] {} #{}", self.ty, self.name, self.hash))
+    }
+}
@@ -1,16 +1,118 @@
 use std::fmt::Debug;
 
 use anyhow::Result;
-use turbo_tasks::primitives::{BoolVc, StringVc};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    Value,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 
-use super::{ChunkVc, EvaluatableAssetsVc};
+use super::{
+    module_id_map::OptionModuleIdMapVc, CancellationTokenVc, ChunkGenerationLimit,
+    ChunkGenerationLimitVc, ChunkVc, EvaluatableAssetsVc, ExternalInputsVc, IssueTolerancePolicy,
+    IssueTolerancePolicyVc, ModuleConcatenation, ModuleConcatenationVc, OptionNamedChunksVc,
+    OptionPublicPathVc,
+};
 use crate::{
     asset::{AssetVc, AssetsVc},
-    environment::EnvironmentVc,
+    code_builder::{ChunkCodeType, CodeWrappersVc},
+    environment::{EnvironmentVc, RuntimeFeature},
     ident::AssetIdentVc,
 };
 
+/// How eagerly chunk optimizers merge chunks that share chunk items.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum MergeAggressiveness {
+    /// Only merge chunks which are almost identical.
+    Conservative,
+    #[default]
+    Balanced,
+    /// Merge chunks even when they only share a small portion of chunk items.
+    Aggressive,
+}
+
+impl MergeAggressiveness {
+    /// Max ratio of shared chunk items between two chunks before they are
+    /// merged.
+    pub fn duplication_threshold(&self) -> f32 {
+        match self {
+            MergeAggressiveness::Conservative => 0.3,
+            MergeAggressiveness::Balanced => 0.1,
+            MergeAggressiveness::Aggressive => 0.05,
+        }
+    }
+
+    /// Max ratio of unshared chunk items on either side before two chunks are
+    /// merged.
+    pub fn contained_threshold(&self) -> f32 {
+        match self {
+            MergeAggressiveness::Conservative => 0.01,
+            MergeAggressiveness::Balanced => 0.05,
+            MergeAggressiveness::Aggressive => 0.15,
+        }
+    }
+}
+
+/// Hints for chunk optimizers about the preferred granularity of a chunk
+/// group.
+///
+/// HTTP/1 deployments want few chunks to limit the number of requests, while
+/// HTTP/2+ can load many chunks in parallel and benefits from finer grained
+/// caching.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct ChunkingHints {
+    /// Optimizers stop merging chunks once a chunk group has this many
+    /// chunks.
+    pub min_chunk_count: usize,
+    /// Optimizers merge chunks until a chunk group has at most this many
+    /// chunks.
+    pub max_chunk_count: usize,
+    pub merge_aggressiveness: MergeAggressiveness,
+}
+
+impl Default for ChunkingHints {
+    fn default() -> Self {
+        ChunkingHints {
+            min_chunk_count: 1,
+            max_chunk_count: 20,
+            merge_aggressiveness: MergeAggressiveness::Balanced,
+        }
+    }
+}
+
+impl ChunkingHints {
+    /// Hints for targets served over HTTP/1, which prefer a few large chunks.
+    pub fn http1() -> Self {
+        ChunkingHints {
+            min_chunk_count: 1,
+            max_chunk_count: 6,
+            merge_aggressiveness: MergeAggressiveness::Aggressive,
+        }
+    }
+
+    /// Hints for targets served over HTTP/2 or later, which tolerate many
+    /// chunks.
+    pub fn http2() -> Self {
+        ChunkingHints {
+            min_chunk_count: 1,
+            max_chunk_count: 50,
+            merge_aggressiveness: MergeAggressiveness::Conservative,
+        }
+    }
+
+    /// Returns the hints with the chunk count range clamped to be valid.
+    pub fn normalized(self) -> Self {
+        let max_chunk_count = self.max_chunk_count.max(1);
+        ChunkingHints {
+            min_chunk_count: self.min_chunk_count.clamp(1, max_chunk_count),
+            max_chunk_count,
+            merge_aggressiveness: self.merge_aggressiveness,
+        }
+    }
+}
+
 /// A context for the chunking that influences the way chunks are created
 #[turbo_tasks::value_trait]
 pub trait ChunkingContext {
@@ -39,12 +141,115 @@ pub trait ChunkingContext {
         BoolVc::cell(false)
     }
 
+    /// Whether references can be split off into separate chunks. When
+    /// disabled, parallel and async references are inlined into the
+    /// referencing chunk where possible, so a chunk group consists of a single
+    /// file. This is needed for environments that can't load additional files.
+    fn is_code_splitting_enabled(&self) -> BoolVc {
+        BoolVc::cell(true)
+    }
+
+    /// Whether the runtime that loads the chunks of this context supports
+    /// `feature`. Chunk and runtime code generation should consult this
+    /// instead of checking runtime versions.
+    fn supports_runtime_feature(&self, feature: Value<RuntimeFeature>) -> BoolVc {
+        self.environment().supports(feature)
+    }
+
     fn layer(&self) -> StringVc {
         StringVc::cell("".to_string())
     }
 
     fn with_layer(&self, layer: &str) -> ChunkingContextVc;
 
+    /// Hints for the chunk optimizer about the preferred number of chunks in
+    /// a chunk group.
+    fn chunking_hints(&self) -> ChunkingHintsVc {
+        ChunkingHints::default().cell()
+    }
+
+    /// Whether chunk formats concatenate the chunk items of a chunk, see
+    /// [ConcatenationGroups]. Disabled by default.
+    ///
+    /// [ConcatenationGroups]: super::ConcatenationGroups
+    fn module_concatenation(&self) -> ModuleConcatenationVc {
+        ModuleConcatenation::default().cell()
+    }
+
+    /// A token that allows the embedder to stop long running chunking
+    /// operations of this context, e.g. when a page request was superseded.
+    fn cancellation_token(&self) -> CancellationTokenVc {
+        CancellationTokenVc::never()
+    }
+
+    /// Limits how many chunks of a chunk group are generated at the same
+    /// time by [ChunkGroup::generate].
+    ///
+    /// [ChunkGroup::generate]: super::ChunkGroup::generate
+    fn chunk_generation_limit(&self) -> ChunkGenerationLimitVc {
+        ChunkGenerationLimitVc::unlimited()
+    }
+
+    /// Limits how many chunk groups of this context are prewarmed at the same
+    /// time by [ChunkGroup::prewarm]. Only one group is prewarmed at a time
+    /// by default.
+    ///
+    /// [ChunkGroup::prewarm]: super::ChunkGroup::prewarm
+    fn prewarm_limit(&self) -> ChunkGenerationLimitVc {
+        ChunkGenerationLimitVc::new(ChunkGenerationLimit::new(1))
+    }
+
+    /// Numeric module ids that are persisted between builds. Modules that are
+    /// not in the map are identified by their ident.
+    fn module_id_map(&self) -> OptionModuleIdMapVc {
+        OptionModuleIdMapVc::none()
+    }
+
+    /// Configured chunk names for assets, see [NamedChunks]. Assets that
+    /// belong to a named chunk are split off from other chunks.
+    ///
+    /// [NamedChunks]: super::NamedChunks
+    fn named_chunks(&self) -> OptionNamedChunksVc {
+        OptionNamedChunksVc::none()
+    }
+
+    /// Whether issues of chunk items abort chunk generation or are tolerated.
+    fn issue_tolerance(&self) -> IssueTolerancePolicyVc {
+        IssueTolerancePolicy::development().cell()
+    }
+
+    /// Banners, footers and other wrappers to apply to the code of chunks of
+    /// `chunk_type`, see [CodeBuilder::push_wrapped].
+    ///
+    /// [CodeBuilder::push_wrapped]: crate::code_builder::CodeBuilder::push_wrapped
+    fn code_wrappers(&self, _chunk_type: Value<ChunkCodeType>) -> CodeWrappersVc {
+        CodeWrappersVc::empty()
+    }
+
+    /// Inputs of all chunks of this context that aren't files and aren't
+    /// recorded where they are read, see [ExternalInputs]. They are part of
+    /// the digests of [ChunkGroup::input_digests].
+    ///
+    /// [ExternalInputs]: super::ExternalInputs
+    /// [ChunkGroup::input_digests]: super::ChunkGroup::input_digests
+    fn external_inputs(&self) -> ExternalInputsVc {
+        ExternalInputsVc::empty()
+    }
+
+    /// Where the output root is served from, see [PublicPath]. Without a
+    /// public path, files are referenced by their path on the server.
+    ///
+    /// [PublicPath]: super::PublicPath
+    fn public_path(&self) -> OptionPublicPathVc {
+        OptionPublicPathVc::none()
+    }
+
+    /// The asset that `chunk` is written as, which is among the assets of the
+    /// chunk groups that contain `chunk`.
+    fn chunk_asset(&self, chunk: ChunkVc) -> AssetVc {
+        chunk.into()
+    }
+
     fn chunk_group(&self, entry: ChunkVc) -> AssetsVc;
 
     fn evaluated_chunk_group(
@@ -0,0 +1,451 @@
+//! Boundaries for module concatenation, also known as scope hoisting.
+//!
+//! Chunk formats can concatenate chunk items into a single function instead
+//! of wrapping each of them into a module factory, which saves the factories
+//! and allows minifiers to optimize across modules. [ConcatenationGroupsVc]
+//! splits the chunk items of a chunk into groups that are safe to
+//! concatenate: every chunk item of a group except its root has a single
+//! consumer in the group, belongs to the same layer as it, and neither of them
+//! accesses modules dynamically. Chunk items opt in by implementing
+//! [ConcatenatableChunkItem], and chunking contexts enable concatenation with
+//! [ModuleConcatenation].
+//!
+//! Each group records the [ConcatenationBlocker] that kept its root from being
+//! concatenated into a consumer, so that it can be reported why a module
+//! wasn't hoisted. Chunk formats list the groups of a chunk and their blockers
+//! in the introspection details of the chunk.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{self, Display},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{debug::ValueDebugFormat, trace::TraceRawVcs, TryJoinIterExt};
+
+use super::{
+    ChunkItem, ChunkItemVc, ChunkItemsVc, ChunkableAssetReferenceVc, ChunkingContext,
+    ChunkingContextVc, ChunkingType,
+};
+use crate::{asset::Asset, reference::AssetReference};
+
+/// Whether and how chunk items are concatenated, see the [module
+/// documentation](self).
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct ModuleConcatenation {
+    pub enabled: bool,
+    /// The maximum number of chunk items in a group, as very large scopes
+    /// slow down minifiers. `None` doesn't limit groups.
+    pub max_group_size: Option<usize>,
+}
+
+/// What [ConcatenationGroupsVc] needs to know about a chunk item to
+/// concatenate it with its consumer.
+#[turbo_tasks::value]
+pub struct ConcatenationInfo {
+    /// Chunk items of different layers are never concatenated.
+    pub layer: String,
+    /// Whether the chunk item accesses modules dynamically or is accessed
+    /// dynamically, e.g. through `eval` or `module.exports`, so that its
+    /// bindings can't be hoisted into a shared scope.
+    pub dynamic_access: bool,
+}
+
+/// A chunk item that a chunk format can concatenate with other chunk items
+/// of the same format.
+#[turbo_tasks::value_trait]
+pub trait ConcatenatableChunkItem: ChunkItem {
+    fn concatenation_info(&self) -> ConcatenationInfoVc;
+}
+
+/// Why a chunk item isn't concatenated into a consumer.
+#[derive(
+    Copy, Clone, Debug, Hash, TraceRawVcs, Serialize, Deserialize, Eq, PartialEq, ValueDebugFormat,
+)]
+pub enum ConcatenationBlocker {
+    /// The chunking context doesn't enable module concatenation.
+    Disabled,
+    /// The chunk item or its consumer doesn't implement
+    /// [ConcatenatableChunkItem].
+    Unsupported,
+    /// Code outside of the chunk accesses the chunk item, e.g. because it's an
+    /// entry.
+    Exposed,
+    /// The chunk item doesn't have exactly one consumer in the chunk.
+    Consumers(usize),
+    /// The chunk item or its consumer accesses modules dynamically, or the
+    /// chunk item is imported dynamically.
+    DynamicAccess,
+    /// The chunk item and its consumer belong to different layers.
+    DifferentLayer,
+    /// The chunk item consumes itself through its consumers.
+    Cycle,
+    /// The group of the consumer reached
+    /// [ModuleConcatenation::max_group_size].
+    GroupSizeLimit,
+}
+
+impl Display for ConcatenationBlocker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConcatenationBlocker::Disabled => write!(f, "module concatenation is disabled"),
+            ConcatenationBlocker::Unsupported => {
+                write!(f, "the chunk format doesn't support concatenation")
+            }
+            ConcatenationBlocker::Exposed => write!(f, "it's accessed from outside of the chunk"),
+            ConcatenationBlocker::Consumers(count) => {
+                write!(f, "it has {count} consumers in the chunk instead of one")
+            }
+            ConcatenationBlocker::DynamicAccess => write!(f, "modules are accessed dynamically"),
+            ConcatenationBlocker::DifferentLayer => {
+                write!(f, "its consumer belongs to a different layer")
+            }
+            ConcatenationBlocker::Cycle => write!(f, "it's part of a cycle"),
+            ConcatenationBlocker::GroupSizeLimit => {
+                write!(f, "the group of its consumer is too large")
+            }
+        }
+    }
+}
+
+/// Chunk items that can be concatenated into a single scope.
+#[derive(Debug, Clone, PartialEq, Eq, TraceRawVcs, Serialize, Deserialize, ValueDebugFormat)]
+pub struct ConcatenationGroup {
+    /// The chunk items in evaluation order: each chunk item comes after the
+    /// chunk items that it references, so the root, which consumes all
+    /// others, comes last.
+    pub items: Vec<ChunkItemVc>,
+    /// Why the root isn't concatenated into a consumer.
+    pub blocker: ConcatenationBlocker,
+}
+
+impl ConcatenationGroup {
+    pub fn root(&self) -> ChunkItemVc {
+        *self.items.last().expect("groups aren't empty")
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct ConcatenationGroups(Vec<ConcatenationGroup>);
+
+#[turbo_tasks::value_impl]
+impl ConcatenationGroupsVc {
+    /// Groups `chunk_items`, the chunk items of a chunk, see the [module
+    /// documentation](self). `exposed` are the chunk items that are accessed
+    /// from outside of the chunk, e.g. its entries, which always are the root
+    /// of a group. Every chunk item is in exactly one group.
+    #[turbo_tasks::function]
+    pub async fn new(
+        chunking_context: ChunkingContextVc,
+        chunk_items: ChunkItemsVc,
+        exposed: ChunkItemsVc,
+    ) -> Result<Self> {
+        let options = *chunking_context.module_concatenation().await?;
+        let chunk_items = chunk_items.await?;
+        if !options.enabled {
+            return Ok(ConcatenationGroupsVc::cell(
+                chunk_items
+                    .iter()
+                    .map(|item| ConcatenationGroup {
+                        items: vec![*item],
+                        blocker: ConcatenationBlocker::Disabled,
+                    })
+                    .collect(),
+            ));
+        }
+
+        // References resolve to assets, so chunk items are found by the ident
+        // of the asset that they were created from.
+        let idents = chunk_items
+            .iter()
+            .map(|item| async move { item.asset_ident().resolve().await })
+            .try_join()
+            .await?;
+        let index_by_ident = idents
+            .iter()
+            .enumerate()
+            .map(|(index, ident)| (*ident, index))
+            .collect::<HashMap<_, _>>();
+        let exposed = exposed
+            .await?
+            .iter()
+            .map(|item| async move { item.asset_ident().resolve().await })
+            .try_join()
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let mut facts = Vec::with_capacity(chunk_items.len());
+        let mut dynamically_referenced = HashSet::new();
+        for (item, ident) in chunk_items.iter().zip(&idents) {
+            let info = match ConcatenatableChunkItemVc::resolve_from(*item).await? {
+                Some(item) => Some(item.concatenation_info().await?),
+                None => None,
+            };
+            let mut references = Vec::new();
+            for reference in item.references().await?.iter() {
+                let Some(chunkable) = ChunkableAssetReferenceVc::resolve_from(*reference).await?
+                else {
+                    continue;
+                };
+                let is_static = matches!(
+                    &*chunkable.chunking_type().await?,
+                    Some(
+                        ChunkingType::Placed
+                            | ChunkingType::PlacedOrParallel
+                            | ChunkingType::Parallel
+                    )
+                );
+                for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                    let Some(&index) = index_by_ident.get(&asset.ident().resolve().await?) else {
+                        continue;
+                    };
+                    if is_static {
+                        references.push(index);
+                    } else {
+                        dynamically_referenced.insert(index);
+                    }
+                }
+            }
+            facts.push(ItemFacts {
+                references,
+                layer: info.as_ref().map(|info| info.layer.clone()),
+                dynamic_access: info.as_ref().map_or(false, |info| info.dynamic_access),
+                exposed: exposed.contains(ident),
+                dynamically_referenced: false,
+            });
+        }
+        for index in dynamically_referenced {
+            facts[index].dynamically_referenced = true;
+        }
+
+        Ok(ConcatenationGroupsVc::cell(
+            plan_groups(&facts, options.max_group_size)
+                .into_iter()
+                .map(|(items, blocker)| ConcatenationGroup {
+                    items: items.into_iter().map(|index| chunk_items[index]).collect(),
+                    blocker,
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// What decides whether a chunk item is concatenated into its consumer.
+#[derive(Debug, Default)]
+struct ItemFacts {
+    /// The chunk items that the chunk item references statically, in order.
+    references: Vec<usize>,
+    /// The layer, or `None` if the chunk item doesn't support concatenation.
+    layer: Option<String>,
+    dynamic_access: bool,
+    exposed: bool,
+    dynamically_referenced: bool,
+}
+
+/// Groups the chunk items described by `items` and returns the indices of the
+/// chunk items of each group in evaluation order, with the blocker of the
+/// root of the group.
+fn plan_groups(
+    items: &[ItemFacts],
+    max_group_size: Option<usize>,
+) -> Vec<(Vec<usize>, ConcatenationBlocker)> {
+    let mut consumers = vec![Vec::new(); items.len()];
+    for (consumer, item) in items.iter().enumerate() {
+        for &reference in &item.references {
+            if !consumers[reference].contains(&consumer) {
+                consumers[reference].push(consumer);
+            }
+        }
+    }
+
+    let mut parents = items
+        .iter()
+        .zip(&consumers)
+        .map(|(item, consumers)| {
+            let Some(layer) = &item.layer else {
+                return Err(ConcatenationBlocker::Unsupported);
+            };
+            if item.exposed {
+                return Err(ConcatenationBlocker::Exposed);
+            }
+            let [consumer] = consumers[..] else {
+                return Err(ConcatenationBlocker::Consumers(consumers.len()));
+            };
+            let consumer_facts = &items[consumer];
+            let Some(consumer_layer) = &consumer_facts.layer else {
+                return Err(ConcatenationBlocker::Unsupported);
+            };
+            if item.dynamic_access || item.dynamically_referenced || consumer_facts.dynamic_access {
+                return Err(ConcatenationBlocker::DynamicAccess);
+            }
+            if layer != consumer_layer {
+                return Err(ConcatenationBlocker::DifferentLayer);
+            }
+            Ok(consumer)
+        })
+        .collect::<Vec<_>>();
+
+    // Chunk items whose consumers lead back to them would end up in no group.
+    // The chunk item where the cycle closes becomes a root instead.
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        Visiting,
+        Done,
+    }
+    let mut states = vec![State::Unvisited; items.len()];
+    for start in 0..items.len() {
+        let mut path = Vec::new();
+        let mut current = start;
+        loop {
+            match states[current] {
+                State::Done => break,
+                State::Visiting => {
+                    parents[current] = Err(ConcatenationBlocker::Cycle);
+                    break;
+                }
+                State::Unvisited => {}
+            }
+            states[current] = State::Visiting;
+            path.push(current);
+            match parents[current] {
+                Ok(parent) => current = parent,
+                Err(_) => break,
+            }
+        }
+        for index in path {
+            states[index] = State::Done;
+        }
+    }
+
+    let mut children = vec![Vec::new(); items.len()];
+    for (consumer, item) in items.iter().enumerate() {
+        for &reference in &item.references {
+            if parents[reference] == Ok(consumer) && !children[consumer].contains(&reference) {
+                children[consumer].push(reference);
+            }
+        }
+    }
+
+    let mut roots = parents
+        .iter()
+        .enumerate()
+        .filter_map(|(index, parent)| parent.err().map(|blocker| (index, blocker)))
+        .collect::<VecDeque<_>>();
+    let max_group_size = max_group_size.unwrap_or(usize::MAX).max(1);
+    let mut groups = Vec::new();
+    while let Some((root, blocker)) = roots.pop_front() {
+        // The chunk items closest to the root stay in the group, the others
+        // start groups of their own.
+        let mut members = HashSet::from([root]);
+        let mut queue = VecDeque::from([root]);
+        while let Some(item) = queue.pop_front() {
+            for &child in &children[item] {
+                if members.len() < max_group_size {
+                    members.insert(child);
+                    queue.push_back(child);
+                } else {
+                    roots.push_back((child, ConcatenationBlocker::GroupSizeLimit));
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(members.len());
+        push_post_order(root, &children, &members, &mut order);
+        groups.push((order, blocker));
+    }
+    groups
+}
+
+/// Pushes the members below `item` in post-order, i.e. each chunk item after
+/// the chunk items that it references.
+fn push_post_order(
+    item: usize,
+    children: &[Vec<usize>],
+    members: &HashSet<usize>,
+    order: &mut Vec<usize>,
+) {
+    for &child in &children[item] {
+        if members.contains(&child) {
+            push_post_order(child, children, members, order);
+        }
+    }
+    order.push(item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(references: &[usize]) -> ItemFacts {
+        ItemFacts {
+            references: references.to_vec(),
+            layer: Some(String::new()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn concatenates_single_consumer_chains() {
+        // 0 imports 1 and 2, 1 imports 3, and 2 and 3 both import 4.
+        let mut items = vec![item(&[1, 2]), item(&[3]), item(&[4]), item(&[4]), item(&[])];
+        items[0].exposed = true;
+        assert_eq!(
+            plan_groups(&items, None),
+            vec![
+                (vec![3, 1, 2, 0], ConcatenationBlocker::Exposed),
+                (vec![4], ConcatenationBlocker::Consumers(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_blockers() {
+        let mut items = vec![
+            item(&[1, 2, 3, 4]),
+            item(&[]),
+            item(&[]),
+            item(&[]),
+            item(&[]),
+        ];
+        items[1].layer = None;
+        items[2].dynamic_access = true;
+        items[3].layer = Some("ssr".to_string());
+        items[4].dynamically_referenced = true;
+        assert_eq!(
+            plan_groups(&items, None),
+            vec![
+                (vec![0], ConcatenationBlocker::Consumers(0)),
+                (vec![1], ConcatenationBlocker::Unsupported),
+                (vec![2], ConcatenationBlocker::DynamicAccess),
+                (vec![3], ConcatenationBlocker::DifferentLayer),
+                (vec![4], ConcatenationBlocker::DynamicAccess),
+            ]
+        );
+    }
+
+    #[test]
+    fn breaks_cycles() {
+        let items = vec![item(&[1]), item(&[0])];
+        assert_eq!(
+            plan_groups(&items, None),
+            vec![(vec![1, 0], ConcatenationBlocker::Cycle)]
+        );
+    }
+
+    #[test]
+    fn limits_group_size() {
+        let mut items = vec![item(&[1]), item(&[2]), item(&[3]), item(&[])];
+        items[0].exposed = true;
+        assert_eq!(
+            plan_groups(&items, Some(2)),
+            vec![
+                (vec![1, 0], ConcatenationBlocker::Exposed),
+                (vec![3, 2], ConcatenationBlocker::GroupSizeLimit),
+            ]
+        );
+    }
+}
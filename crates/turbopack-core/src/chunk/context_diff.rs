@@ -0,0 +1,165 @@
+//! Comparing the configuration of two chunking contexts.
+//!
+//! When the configuration of a build changes, e.g. the public path of the
+//! output, an embedder that caches build artifacts outside of turbo tasks
+//! would have to drop all of them to be safe. [ChunkingContextDiff] tells
+//! which [aspects](ChunkingContextAspect) of the configuration changed, and
+//! which [artifacts](CachedArtifact) are still valid after the change, so
+//! that e.g. the code of chunk items is kept when only the output root moved.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use super::{module_id_map::OptionModuleIdMapVc, ChunkingContext, ChunkingContextVc};
+
+/// An aspect of the configuration of a chunking context.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ChunkingContextAspect {
+    /// The output root, which chunk and asset URLs are relative to.
+    OutputRoot,
+    /// The layer, which is part of the paths of chunks.
+    Layer,
+    /// How module ids are computed, see [ChunkingContext::module_id_map].
+    Hashing,
+    /// Defines, env variables and other inputs that aren't files, see
+    /// [ChunkingContext::external_inputs].
+    Defines,
+}
+
+/// An artifact of chunking that can be cached between builds.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum CachedArtifact {
+    /// The code of chunk items.
+    ChunkItems,
+    /// Which chunk items are placed in which chunks.
+    ChunkComposition,
+    /// The paths of chunks.
+    ChunkPaths,
+    /// The generated content of chunks.
+    ChunkContent,
+}
+
+impl ChunkingContextAspect {
+    pub const ALL: [ChunkingContextAspect; 4] = [
+        ChunkingContextAspect::OutputRoot,
+        ChunkingContextAspect::Layer,
+        ChunkingContextAspect::Hashing,
+        ChunkingContextAspect::Defines,
+    ];
+
+    /// The artifacts that stay valid when only this aspect changed.
+    pub fn keeps(self) -> &'static [CachedArtifact] {
+        match self {
+            // Chunk contents contain the URLs of the chunks they load.
+            ChunkingContextAspect::OutputRoot => {
+                &[CachedArtifact::ChunkItems, CachedArtifact::ChunkComposition]
+            }
+            ChunkingContextAspect::Layer => {
+                &[CachedArtifact::ChunkItems, CachedArtifact::ChunkComposition]
+            }
+            // Chunk items reference each other by module id.
+            ChunkingContextAspect::Hashing => {
+                &[CachedArtifact::ChunkComposition, CachedArtifact::ChunkPaths]
+            }
+            // Defines can remove code and therefore references to modules.
+            ChunkingContextAspect::Defines => &[CachedArtifact::ChunkPaths],
+        }
+    }
+}
+
+/// The aspects that differ between the configurations of two chunking
+/// contexts, see the [module documentation](self).
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default)]
+pub struct ChunkingContextDiff {
+    changed: Vec<ChunkingContextAspect>,
+}
+
+impl ChunkingContextDiff {
+    /// The aspects that changed, in the order of [ChunkingContextAspect::ALL].
+    pub fn changed(&self) -> &[ChunkingContextAspect] {
+        &self.changed
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        self.changed.is_empty()
+    }
+
+    /// Whether `artifact` can be kept, i.e. every aspect that changed keeps
+    /// it.
+    pub fn keeps(&self, artifact: CachedArtifact) -> bool {
+        self.changed
+            .iter()
+            .all(|aspect| aspect.keeps().contains(&artifact))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkingContextDiffVc {
+    /// Compares the configuration of `old` with `new`.
+    #[turbo_tasks::function]
+    pub async fn new(old: ChunkingContextVc, new: ChunkingContextVc) -> Result<Self> {
+        let mut changed = Vec::new();
+        for aspect in ChunkingContextAspect::ALL {
+            let differs = match aspect {
+                ChunkingContextAspect::OutputRoot => {
+                    *old.output_root().await? != *new.output_root().await?
+                }
+                ChunkingContextAspect::Layer => *old.layer().await? != *new.layer().await?,
+                ChunkingContextAspect::Hashing => {
+                    module_ids(old.module_id_map()).await?
+                        != module_ids(new.module_id_map()).await?
+                }
+                ChunkingContextAspect::Defines => {
+                    *old.external_inputs().digest().await?
+                        != *new.external_inputs().digest().await?
+                }
+            };
+            if differs {
+                changed.push(aspect);
+            }
+        }
+        Ok(ChunkingContextDiff { changed }.cell())
+    }
+}
+
+/// The ids of the module id map, if any. Maps with the same ids are equal
+/// even if their next id differs, as that only affects modules that are added
+/// later.
+async fn module_ids(map: OptionModuleIdMapVc) -> Result<Option<BTreeMap<String, u32>>> {
+    Ok(match *map.await? {
+        Some(map) => Some(map.await?.ids.clone()),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachedArtifact, ChunkingContextAspect, ChunkingContextDiff};
+
+    #[test]
+    fn keeps_artifacts_of_all_changed_aspects() {
+        let diff = ChunkingContextDiff::default();
+        assert!(diff.is_unchanged());
+        assert!(diff.keeps(CachedArtifact::ChunkContent));
+
+        let diff = ChunkingContextDiff {
+            changed: vec![ChunkingContextAspect::OutputRoot],
+        };
+        assert!(diff.keeps(CachedArtifact::ChunkItems));
+        assert!(!diff.keeps(CachedArtifact::ChunkPaths));
+        assert!(!diff.keeps(CachedArtifact::ChunkContent));
+
+        let diff = ChunkingContextDiff {
+            changed: vec![
+                ChunkingContextAspect::OutputRoot,
+                ChunkingContextAspect::Hashing,
+            ],
+        };
+        assert!(diff.keeps(CachedArtifact::ChunkComposition));
+        assert!(!diff.keeps(CachedArtifact::ChunkItems));
+    }
+}
@@ -0,0 +1,275 @@
+//! Building a chunk group for two environments, e.g. a modern build with ES
+//! modules that is served with `<script type="module">` and a legacy ES5
+//! build that is served with `<script nomodule>`.
+//!
+//! The entry is processed by two asset contexts that only differ in their
+//! environment, so requests are resolved once for both builds and only the
+//! transforms that depend on the environment are applied twice. The chunks
+//! of each build are created by their own chunking context, which must place
+//! them at different paths, e.g. by using a `legacy` layer for the legacy
+//! build. [DualChunkGroup::manifest] maps the chunks of the modern build to
+//! their legacy variants.
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use turbo_tasks::ValueToString;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{ChunkGroup, ChunkingContext, ChunkingContextVc};
+use crate::{
+    asset::{Asset, AssetContentVc, AssetsVc},
+    ident::{AssetIdentVc, ModifierKind, ModifierKindsVc},
+    version::{manifest::ManifestContentVc, VersionedContent, VersionedContentVc},
+};
+
+/// A chunk group that is built for a modern and a legacy environment, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy)]
+pub struct DualChunkGroup {
+    modern: ChunkGroup,
+    legacy: ChunkGroup,
+}
+
+impl DualChunkGroup {
+    pub fn new(modern: ChunkGroup, legacy: ChunkGroup) -> Self {
+        Self { modern, legacy }
+    }
+
+    pub fn modern(&self) -> ChunkGroup {
+        self.modern
+    }
+
+    pub fn legacy(&self) -> ChunkGroup {
+        self.legacy
+    }
+
+    /// The assets of both builds.
+    pub fn assets(&self) -> AssetsVc {
+        concat_assets(self.modern.assets(), self.legacy.assets())
+    }
+
+    /// A manifest at `path` that maps each chunk to its modern and legacy
+    /// variant.
+    pub fn manifest(&self, path: FileSystemPathVc) -> DualOutputManifestVc {
+        DualOutputManifestVc::new(
+            path,
+            self.modern.chunking_context(),
+            self.modern.assets(),
+            self.legacy.chunking_context(),
+            self.legacy.assets(),
+        )
+    }
+}
+
+#[turbo_tasks::function]
+async fn concat_assets(a: AssetsVc, b: AssetsVc) -> Result<AssetsVc> {
+    let mut assets = a.await?.clone_value();
+    assets.extend(b.await?.iter().copied());
+    Ok(AssetsVc::cell(assets))
+}
+
+/// A JSON manifest that lists the paths of the modern and the legacy variant
+/// of each chunk of a [DualChunkGroup], relative to the output root of their
+/// chunking context, or their public URLs if the chunking context has a
+/// [public path](ChunkingContext::public_path). Chunks are keyed by the path
+/// of their modern variant:
+///
+/// ```json
+/// {
+///   "index.js": { "modern": "index.js", "legacy": "legacy/index.js" },
+///   "legacy/polyfills.js": { "modern": null, "legacy": "legacy/polyfills.js" }
+/// }
+/// ```
+///
+/// Chunks are matched by their ident without layer modifiers. A chunk that
+/// only exists in one of the builds, e.g. because modules were merged
+/// differently, has `null` as the path of the other variant, and chunks that
+/// only exist in the legacy build are keyed by their legacy path. Updates of
+/// the manifest only contain the chunks that changed, see [ManifestContent].
+///
+/// [ManifestContent]: crate::version::manifest::ManifestContent
+#[turbo_tasks::value]
+pub struct DualOutputManifest {
+    path: FileSystemPathVc,
+    modern_chunking_context: ChunkingContextVc,
+    modern_assets: AssetsVc,
+    legacy_chunking_context: ChunkingContextVc,
+    legacy_assets: AssetsVc,
+}
+
+#[turbo_tasks::value_impl]
+impl DualOutputManifestVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        path: FileSystemPathVc,
+        modern_chunking_context: ChunkingContextVc,
+        modern_assets: AssetsVc,
+        legacy_chunking_context: ChunkingContextVc,
+        legacy_assets: AssetsVc,
+    ) -> Self {
+        DualOutputManifest {
+            path,
+            modern_chunking_context,
+            modern_assets,
+            legacy_chunking_context,
+            legacy_assets,
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn manifest_content(self) -> Result<ManifestContentVc> {
+        let this = self.await?;
+        let modern = variant_paths(this.modern_chunking_context, this.modern_assets).await?;
+        let legacy = variant_paths(this.legacy_chunking_context, this.legacy_assets).await?;
+        let entries = pair_variants(modern, legacy)
+            .into_iter()
+            .map(|(path, variants)| Ok((path, serde_json::to_value(variants)?)))
+            .collect::<Result<_>>()?;
+        Ok(ManifestContentVc::new(entries))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for DualOutputManifest {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        AssetIdentVc::from_path(self.path)
+    }
+
+    #[turbo_tasks::function]
+    fn content(self_vc: DualOutputManifestVc) -> AssetContentVc {
+        self_vc.manifest_content().content()
+    }
+
+    #[turbo_tasks::function]
+    fn versioned_content(self_vc: DualOutputManifestVc) -> VersionedContentVc {
+        self_vc.manifest_content().into()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct ChunkVariants {
+    modern: Option<String>,
+    legacy: Option<String>,
+}
+
+/// The keys that match the assets of one build to the other, with the paths
+/// of the assets relative to the output root, or their public URLs. Assets
+/// outside of the output root are skipped.
+async fn variant_paths(
+    chunking_context: ChunkingContextVc,
+    assets: AssetsVc,
+) -> Result<Vec<(String, String)>> {
+    let output_root = chunking_context.output_root().await?;
+    let public_path = match *chunking_context.public_path().await? {
+        Some(public_path) => Some(public_path.await?),
+        None => None,
+    };
+    let layers = ModifierKindsVc::cell(vec![ModifierKind::Layer]);
+    let mut paths = Vec::new();
+    for asset in assets.await?.iter() {
+        let Some(path) = output_root
+            .get_path_to(&*asset.ident().path().await?)
+            .map(str::to_string) else {
+            continue;
+        };
+        let path = match &public_path {
+            Some(public_path) => public_path.url(&path),
+            None => path,
+        };
+        let key = asset
+            .ident()
+            .without_modifiers(layers)
+            .to_string()
+            .await?
+            .clone_value();
+        paths.push((key, path));
+    }
+    Ok(paths)
+}
+
+/// Pairs the paths of the modern and legacy assets with the same key, in the
+/// order of the modern assets followed by legacy assets without a modern
+/// variant. The pairs are keyed by the path of the modern variant, or of the
+/// legacy variant if there's no modern one.
+fn pair_variants(
+    modern: Vec<(String, String)>,
+    legacy: Vec<(String, String)>,
+) -> IndexMap<String, ChunkVariants> {
+    let mut variants = IndexMap::new();
+    for (key, path) in modern {
+        variants.entry(key).or_insert(ChunkVariants {
+            modern: Some(path),
+            legacy: None,
+        });
+    }
+    for (key, path) in legacy {
+        let entry = variants.entry(key).or_insert(ChunkVariants {
+            modern: None,
+            legacy: None,
+        });
+        if entry.legacy.is_none() {
+            entry.legacy = Some(path);
+        }
+    }
+    variants
+        .into_values()
+        .map(|variants| {
+            let path = variants
+                .modern
+                .as_ref()
+                .or(variants.legacy.as_ref())
+                .expect("every chunk has a modern or a legacy variant")
+                .clone();
+            (path, variants)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pair_variants, ChunkVariants};
+
+    fn entry(key: &str, path: &str) -> (String, String) {
+        (key.to_string(), path.to_string())
+    }
+
+    #[test]
+    fn pairs_variants_by_key() {
+        let variants = pair_variants(
+            vec![entry("index", "index.js"), entry("shared", "shared.js")],
+            vec![
+                entry("polyfills", "legacy/polyfills.js"),
+                entry("index", "legacy/index.js"),
+            ],
+        );
+        assert_eq!(
+            variants.into_iter().collect::<Vec<_>>(),
+            [
+                (
+                    "index.js".to_string(),
+                    ChunkVariants {
+                        modern: Some("index.js".to_string()),
+                        legacy: Some("legacy/index.js".to_string()),
+                    }
+                ),
+                (
+                    "shared.js".to_string(),
+                    ChunkVariants {
+                        modern: Some("shared.js".to_string()),
+                        legacy: None,
+                    }
+                ),
+                (
+                    "legacy/polyfills.js".to_string(),
+                    ChunkVariants {
+                        modern: None,
+                        legacy: Some("legacy/polyfills.js".to_string()),
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Limiting how many chunks are generated at the same time.
+//!
+//! Generating a chunk holds its code, source map and intermediate state in
+//! memory. [ChunkGroup::generate] generates all chunks of a group at once,
+//! which can exhaust the memory of small machines for very large groups. It
+//! is used by prewarming and by the emission of a `BuildSession`. A
+//! [ChunkGenerationLimit] is owned by the embedder and handed to a chunking
+//! context, like a [CancellationToken]. It can be shared by several chunking
+//! contexts to limit the memory of the whole process.
+//!
+//! Entry chunks are needed before anything else of the group can run, so they
+//! take the next free slot before other chunks.
+//!
+//! [ChunkGroup::generate]: super::ChunkGroup::generate
+//! [CancellationToken]: super::CancellationToken
+
+use std::{
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+
+/// Which chunks are generated first when the limit is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkGenerationPriority {
+    /// The chunk that an entry of a chunk group was placed in.
+    Entry,
+    Other,
+}
+
+#[derive(Default)]
+struct LimitState {
+    /// `None` if chunks aren't limited.
+    semaphore: Option<Semaphore>,
+    /// The number of entry chunks that wait for a slot. Other chunks don't
+    /// take slots while there are any.
+    waiting_entries: AtomicUsize,
+    entries_started: Notify,
+}
+
+/// The number of chunks that are generated at the same time, see the
+/// [module documentation](self).
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
+#[derive(Clone, Default)]
+pub struct ChunkGenerationLimit {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    state: Arc<LimitState>,
+}
+
+impl ChunkGenerationLimit {
+    /// Generates at most `concurrency` chunks at the same time. A concurrency
+    /// of 0 is treated as 1.
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            state: Arc::new(LimitState {
+                semaphore: Some(Semaphore::new(concurrency.max(1))),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Doesn't limit chunk generation.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Waits for a slot to generate a chunk with `priority`. The slot is
+    /// released when the returned permit is dropped. Returns `None` if chunk
+    /// generation isn't limited.
+    pub async fn acquire(
+        &self,
+        priority: ChunkGenerationPriority,
+    ) -> Result<Option<SemaphorePermit<'_>>> {
+        let state = &*self.state;
+        let Some(semaphore) = &state.semaphore else {
+            return Ok(None);
+        };
+        match priority {
+            ChunkGenerationPriority::Entry => {
+                state.waiting_entries.fetch_add(1, Ordering::AcqRel);
+                let permit = semaphore.acquire().await;
+                if state.waiting_entries.fetch_sub(1, Ordering::AcqRel) == 1 {
+                    state.entries_started.notify_waiters();
+                }
+                Ok(Some(permit?))
+            }
+            ChunkGenerationPriority::Other => loop {
+                let mut entries_started = pin!(state.entries_started.notified());
+                // Register for the notification before checking, so that it
+                // isn't missed in between.
+                entries_started.as_mut().enable();
+                if state.waiting_entries.load(Ordering::Acquire) == 0 {
+                    let permit = semaphore.acquire().await?;
+                    // An entry chunk may have started waiting in the meantime,
+                    // in which case the slot is passed on.
+                    if state.waiting_entries.load(Ordering::Acquire) == 0 {
+                        return Ok(Some(permit));
+                    }
+                    drop(permit);
+                    continue;
+                }
+                entries_started.await;
+            },
+        }
+    }
+}
+
+impl ChunkGenerationLimitVc {
+    pub fn new(limit: ChunkGenerationLimit) -> Self {
+        Self::cell(limit)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkGenerationLimitVc {
+    /// A limit that doesn't limit chunk generation.
+    #[turbo_tasks::function]
+    pub fn unlimited() -> Self {
+        Self::cell(ChunkGenerationLimit::unlimited())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{ChunkGenerationLimit, ChunkGenerationPriority};
+
+    #[tokio::test]
+    async fn test_unlimited() {
+        let limit = ChunkGenerationLimit::unlimited();
+        assert!(limit
+            .acquire(ChunkGenerationPriority::Other)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bounds_concurrency() {
+        let limit = ChunkGenerationLimit::new(2);
+        let running = Arc::new(Mutex::new((0, 0)));
+        let tasks = (0..8)
+            .map(|_| {
+                let limit = limit.clone();
+                let running = running.clone();
+                tokio::spawn(async move {
+                    let _permit = limit.acquire(ChunkGenerationPriority::Other).await.unwrap();
+                    {
+                        let (current, max) = &mut *running.lock().unwrap();
+                        *current += 1;
+                        *max = (*max).max(*current);
+                    }
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.lock().unwrap().0 -= 1;
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*running.lock().unwrap(), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_entries_go_first() {
+        let limit = ChunkGenerationLimit::new(1);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let permit = limit.acquire(ChunkGenerationPriority::Other).await.unwrap();
+
+        let other = tokio::spawn({
+            let limit = limit.clone();
+            let order = order.clone();
+            async move {
+                let _permit = limit.acquire(ChunkGenerationPriority::Other).await.unwrap();
+                order.lock().unwrap().push("other");
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let entry = tokio::spawn({
+            let limit = limit.clone();
+            let order = order.clone();
+            async move {
+                let _permit = limit.acquire(ChunkGenerationPriority::Entry).await.unwrap();
+                order.lock().unwrap().push("entry");
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        drop(permit);
+        entry.await.unwrap();
+        other.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["entry", "other"]);
+    }
+}
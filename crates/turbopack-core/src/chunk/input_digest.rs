@@ -0,0 +1,248 @@
+//! Inputs of chunks that aren't files, e.g. compile time defines, env
+//! variables or configuration values.
+//!
+//! Turbo tasks track these inputs for invalidation, but caches outside of
+//! turbopack, e.g. the task cache of turborepo or a CDN, can't see them. Code
+//! that reads such an input records it with [ExternalInputsVc::emit], e.g. the
+//! ecmascript analysis records every define it substitutes. The records are
+//! collectibles, so the inputs of a chunk are the ones recorded while its
+//! content was computed. Inputs that can't be recorded where they are read
+//! can be declared for a whole chunking context, see
+//! [ChunkingContext::external_inputs].
+//!
+//! [ChunkGroup::input_digests] combines the inputs of each chunk of a group
+//! with its content into a digest that changes whenever the chunk or one of
+//! its inputs changes, and [ChunkGroup::input_digest] does the same for the
+//! group as a whole.
+//!
+//! [ChunkGroup::input_digests]: super::ChunkGroup::input_digests
+//! [ChunkGroup::input_digest]: super::ChunkGroup::input_digest
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::{emit, primitives::StringVc, CollectiblesSource};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
+
+use super::{ChunkingContext, ChunkingContextVc};
+use crate::{
+    asset::{Asset, AssetContentVc, AssetsVc},
+    compile_time_info::{CompileTimeDefineValue, CompileTimeDefinesVc},
+};
+
+/// Named inputs that influenced chunks, see the [module documentation](self).
+/// Names are prefixed with the kind of input, e.g. `env:NODE_ENV` or
+/// `define:process.env.NODE_ENV`. A value of `None` records that an input was
+/// read but not set.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default)]
+pub struct ExternalInputs {
+    inputs: BTreeMap<String, Option<String>>,
+}
+
+impl ExternalInputs {
+    /// The recorded inputs and their values, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.inputs
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+}
+
+impl ExternalInputsVc {
+    /// Records configuration values or other inputs by their name.
+    pub fn new(inputs: impl IntoIterator<Item = (String, Option<String>)>) -> Self {
+        ExternalInputs {
+            inputs: inputs.into_iter().collect(),
+        }
+        .cell()
+    }
+
+    /// Records the compile time define `name` with `value`.
+    pub fn define(name: &[String], value: &CompileTimeDefineValue) -> Result<Self> {
+        Ok(Self::new([define_input(name, value)?]))
+    }
+
+    /// Records that these inputs were read by the current computation. They
+    /// become inputs of every chunk whose content depends on the computation.
+    pub fn emit(self) {
+        emit(self.as_recorded_external_inputs());
+    }
+}
+
+/// The name and value of a define in [ExternalInputs].
+fn define_input(
+    name: &[String],
+    value: &CompileTimeDefineValue,
+) -> Result<(String, Option<String>)> {
+    let value = match value {
+        CompileTimeDefineValue::Bool(value) => value.to_string(),
+        // Quoted so that a string "true" differs from `true`.
+        CompileTimeDefineValue::String(value) => serde_json::to_string(value)?,
+    };
+    Ok((format!("define:{}", name.join(".")), Some(value)))
+}
+
+#[turbo_tasks::value_impl]
+impl ExternalInputsVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        ExternalInputs::default().cell()
+    }
+
+    /// Records the env variables `names` of `env`.
+    #[turbo_tasks::function]
+    pub async fn from_env(env: ProcessEnvVc, names: Vec<String>) -> Result<Self> {
+        let mut inputs = BTreeMap::new();
+        for name in names {
+            let value = env.read(&name).await?.clone_value();
+            inputs.insert(format!("env:{}", name), value);
+        }
+        Ok(ExternalInputs { inputs }.cell())
+    }
+
+    /// Records the values of compile time defines.
+    #[turbo_tasks::function]
+    pub async fn from_defines(defines: CompileTimeDefinesVc) -> Result<Self> {
+        let inputs = defines
+            .await?
+            .iter()
+            .map(|(name, value)| define_input(name, value))
+            .collect::<Result<_>>()?;
+        Ok(ExternalInputs { inputs }.cell())
+    }
+
+    /// The inputs of both `self` and `other`. Values of `other` win when both
+    /// record the same input.
+    #[turbo_tasks::function]
+    pub async fn merge(self, other: ExternalInputsVc) -> Result<Self> {
+        let mut inputs = self.await?.inputs.clone();
+        inputs.extend(other.await?.inputs.clone());
+        Ok(ExternalInputs { inputs }.cell())
+    }
+
+    /// A hex digest of all inputs.
+    #[turbo_tasks::function]
+    pub async fn digest(self) -> Result<StringVc> {
+        let this = self.await?;
+        let mut hasher = Xxh3Hash64Hasher::new();
+        hasher.write_value(this.inputs.len());
+        for (name, value) in &this.inputs {
+            hasher.write_value(name);
+            hasher.write_value(value);
+        }
+        Ok(StringVc::cell(encode_hex(hasher.finish())))
+    }
+}
+
+/// A record of [ExternalInputs] that were read by a computation, emitted as a
+/// collectible by [ExternalInputsVc::emit].
+#[turbo_tasks::value_trait]
+pub trait RecordedExternalInputs {
+    fn recorded(&self) -> ExternalInputsVc;
+}
+
+#[turbo_tasks::value_impl]
+impl RecordedExternalInputs for ExternalInputs {
+    #[turbo_tasks::function]
+    fn recorded(self_vc: ExternalInputsVc) -> ExternalInputsVc {
+        self_vc
+    }
+}
+
+/// The inputs recorded while `source` was computed.
+async fn recorded_inputs(
+    source: impl CollectiblesSource,
+) -> Result<BTreeMap<String, Option<String>>> {
+    let mut inputs = BTreeMap::new();
+    let records = source
+        .peek_collectibles::<RecordedExternalInputsVc>()
+        .strongly_consistent()
+        .await?;
+    for record in records.iter() {
+        inputs.extend(record.recorded().await?.inputs.clone());
+    }
+    Ok(inputs)
+}
+
+/// The inputs recorded while the content of a chunk was computed.
+#[turbo_tasks::function]
+async fn content_inputs(content: AssetContentVc) -> Result<ExternalInputsVc> {
+    Ok(ExternalInputs {
+        inputs: recorded_inputs(content).await?,
+    }
+    .cell())
+}
+
+/// The inputs of a chunk group: the inputs declared by `chunking_context`, the
+/// ones recorded while the group's `assets` were determined and the ones
+/// recorded for the content of each of the assets.
+#[turbo_tasks::function]
+pub(crate) async fn chunk_group_inputs(
+    chunking_context: ChunkingContextVc,
+    assets: AssetsVc,
+) -> Result<ExternalInputsVc> {
+    let mut inputs = chunking_context.external_inputs().await?.inputs.clone();
+    inputs.extend(recorded_inputs(assets).await?);
+    for asset in assets.await?.iter() {
+        inputs.extend(content_inputs(asset.content()).await?.inputs.clone());
+    }
+    Ok(ExternalInputs { inputs }.cell())
+}
+
+/// A digest per chunk, keyed by the path of the chunk relative to the output
+/// root.
+#[turbo_tasks::value(transparent)]
+pub struct ChunkInputDigests(IndexMap<String, String>);
+
+/// Combines the version of each of `assets` with the external inputs
+/// declared by `chunking_context` and the ones recorded for the content of
+/// the asset. Assets outside of the output root are skipped.
+#[turbo_tasks::function]
+pub(crate) async fn input_digests(
+    chunking_context: ChunkingContextVc,
+    assets: AssetsVc,
+) -> Result<ChunkInputDigestsVc> {
+    let output_root = chunking_context.output_root().await?;
+    let declared = chunking_context.external_inputs().digest().await?;
+    let mut digests = IndexMap::new();
+    for asset in assets.await?.iter() {
+        let Some(path) = output_root
+            .get_path_to(&*asset.ident().path().await?)
+            .map(str::to_string) else {
+            continue;
+        };
+        let recorded = content_inputs(asset.content()).digest().await?;
+        let version = asset.versioned_content().version().id().await?;
+        let mut hasher = Xxh3Hash64Hasher::new();
+        hasher.write_value(declared.as_str());
+        hasher.write_value(recorded.as_str());
+        hasher.write_value(path.as_str());
+        hasher.write_value(version.as_str());
+        digests.insert(path, encode_hex(hasher.finish()));
+    }
+    Ok(ChunkInputDigestsVc::cell(digests))
+}
+
+/// A digest of a whole chunk group, which combines the [input_digests] of its
+/// `assets` with the [inputs of the group](chunk_group_inputs).
+#[turbo_tasks::function]
+pub(crate) async fn input_digest(
+    chunking_context: ChunkingContextVc,
+    assets: AssetsVc,
+) -> Result<StringVc> {
+    let digests = input_digests(chunking_context, assets).await?;
+    let inputs = chunk_group_inputs(chunking_context, assets)
+        .digest()
+        .await?;
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_value(inputs.as_str());
+    hasher.write_value(digests.len());
+    for (path, digest) in digests.iter() {
+        hasher.write_value(path.as_str());
+        hasher.write_value(digest.as_str());
+    }
+    Ok(StringVc::cell(encode_hex(hasher.finish())))
+}
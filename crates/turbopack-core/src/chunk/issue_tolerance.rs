@@ -0,0 +1,79 @@
+use anyhow::{bail, Result};
+use turbo_tasks::{CollectiblesSource, ValueToString};
+
+use crate::issue::{
+    budget::IssueBudget, suppression::IssueSuppressionsVc, Issue, IssueSeverity, IssueVc,
+};
+
+/// What chunk generation does about an issue of a chunk item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueTolerance {
+    /// Chunk generation fails.
+    Abort,
+    /// The issue is reported and, if the chunk item couldn't be generated, it
+    /// is replaced with code that throws the error when it's evaluated.
+    Fallback,
+}
+
+/// Decides for each issue severity whether an issue of a chunk item aborts
+/// chunk generation or is tolerated.
+///
+/// Development builds want to keep serving the rest of the app while a module
+/// is broken, while production builds must not emit broken output.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct IssueTolerancePolicy {
+    /// Issues of this severity or a more severe one abort chunk generation.
+    /// `None` tolerates all issues.
+    pub abort_at: Option<IssueSeverity>,
+}
+
+impl IssueTolerancePolicy {
+    /// Tolerates all issues and generates fallback code for chunk items that
+    /// failed to generate.
+    pub fn development() -> Self {
+        IssueTolerancePolicy { abort_at: None }
+    }
+
+    /// Aborts on errors and more severe issues.
+    pub fn production() -> Self {
+        IssueTolerancePolicy {
+            abort_at: Some(IssueSeverity::Error),
+        }
+    }
+
+    pub fn tolerance(&self, severity: IssueSeverity) -> IssueTolerance {
+        match self.abort_at {
+            // More severe issues compare as less.
+            Some(abort_at) if severity <= abort_at => IssueTolerance::Abort,
+            _ => IssueTolerance::Fallback,
+        }
+    }
+
+    /// Fails if any of the issues emitted while computing `source` must abort
+    /// chunk generation.
+    pub async fn check<T: CollectiblesSource + Copy>(&self, source: T) -> Result<()> {
+        if self.abort_at.is_none() {
+            return Ok(());
+        }
+        let issues = IssueVc::peek_issues_with_path(
+            source,
+            IssueBudget::unlimited(),
+            IssueSuppressionsVc::empty(),
+        )
+        .await?
+        .await?;
+        for issue in issues.iter() {
+            let severity = *issue.severity().await?;
+            if self.tolerance(severity) == IssueTolerance::Abort {
+                bail!(
+                    "chunk generation was aborted by {} \"{}\" in {}",
+                    severity,
+                    issue.title().await?,
+                    issue.context().to_string().await?
+                );
+            }
+        }
+        Ok(())
+    }
+}
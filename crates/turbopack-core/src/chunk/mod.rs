@@ -1,12 +1,29 @@
 pub mod availability_info;
 pub mod available_assets;
+pub(crate) mod cancellation;
+pub(crate) mod chunk_ident;
 pub(crate) mod chunking_context;
+pub(crate) mod concatenation;
 pub(crate) mod containment_tree;
+pub mod context_diff;
+pub mod dual_output;
 pub(crate) mod evaluate;
+pub(crate) mod generation_limit;
+pub(crate) mod input_digest;
+pub(crate) mod issue_tolerance;
+pub mod module_id_map;
+pub(crate) mod named_chunks;
 pub mod optimize;
+pub(crate) mod output_path_collisions;
+pub(crate) mod path_sanitization;
+pub(crate) mod prewarm;
+pub(crate) mod public_path;
+pub mod runtime_registry;
+pub(crate) mod shared_chunks;
+pub(crate) mod stylesheets;
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display},
     future::Future,
     hash::Hash,
@@ -14,11 +31,12 @@ use std::{
 };
 
 use anyhow::{anyhow, Result};
+use indexmap::IndexSet;
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{
     debug::ValueDebugFormat,
     graph::{GraphTraversal, GraphTraversalResult, ReverseTopological, Visit, VisitControlFlow},
-    primitives::StringVc,
+    primitives::{BoolVc, OptionStringVc, StringVc},
     trace::TraceRawVcs,
     TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
@@ -27,8 +45,35 @@ use turbo_tasks_hash::DeterministicHash;
 
 use self::availability_info::AvailabilityInfo;
 pub use self::{
-    chunking_context::{ChunkingContext, ChunkingContextVc},
+    cancellation::{CancellationGuard, CancellationToken, CancellationTokenVc, ChunkingCancelled},
+    chunk_ident::{ChunkIdent, ChunkIdentVc},
+    chunking_context::{
+        ChunkingContext, ChunkingContextVc, ChunkingHints, ChunkingHintsVc, MergeAggressiveness,
+    },
+    concatenation::{
+        ConcatenatableChunkItem, ConcatenatableChunkItemVc, ConcatenationBlocker,
+        ConcatenationGroup, ConcatenationGroups, ConcatenationGroupsVc, ConcatenationInfo,
+        ConcatenationInfoVc, ModuleConcatenation, ModuleConcatenationVc,
+    },
     evaluate::{EvaluatableAsset, EvaluatableAssetVc, EvaluatableAssets, EvaluatableAssetsVc},
+    generation_limit::{ChunkGenerationLimit, ChunkGenerationLimitVc, ChunkGenerationPriority},
+    input_digest::{
+        ChunkInputDigests, ChunkInputDigestsVc, ExternalInputs, ExternalInputsVc,
+        RecordedExternalInputs, RecordedExternalInputsVc,
+    },
+    issue_tolerance::{IssueTolerance, IssueTolerancePolicy, IssueTolerancePolicyVc},
+    named_chunks::{
+        group_by_chunk_name, NamedChunks, NamedChunksVc, OptionNamedChunks, OptionNamedChunksVc,
+    },
+    output_path_collisions::{
+        output_path_collisions, OutputPathCollision, OutputPathCollisions, OutputPathCollisionsVc,
+        PathCollisionStrategy,
+    },
+    path_sanitization::PathSanitizationPolicy,
+    prewarm::ChunkGroup,
+    public_path::{OptionPublicPath, OptionPublicPathVc, PublicPath, PublicPathVc},
+    shared_chunks::{SharedAssetStats, SharedAssetStatsVc, SharedChunkPlan, SharedChunkPlanVc},
+    stylesheets::{ChunkGroupStylesheets, ChunkGroupStylesheetsVc},
 };
 use crate::{
     asset::{Asset, AssetVc, AssetsVc},
@@ -124,6 +169,20 @@ pub trait Chunk: Asset {
     fn parallel_chunks(&self) -> ChunksVc {
         ChunksVc::empty()
     }
+    /// A stable identifier of the chunk that is derived from its primary
+    /// entries. Defaults to an identifier derived from the chunk's ident.
+    fn chunk_ident(&self) -> ChunkIdentVc {
+        ChunkIdentVc::from_entries(
+            "chunk".to_string(),
+            self.chunking_context().context_path(),
+            vec![self.ident()],
+        )
+    }
+    /// Whether the chunk is a stylesheet, which pages load with a `<link>`
+    /// instead of a `<script>`.
+    fn is_stylesheet(&self) -> BoolVc {
+        BoolVc::cell(false)
+    }
 }
 
 /// Aggregated information about a chunk content that can be used by the runtime
@@ -189,23 +248,68 @@ pub trait ChunkableAssetReference: AssetReference + ValueToString {
     }
 }
 
+/// When the chunks of a [ChunkGroupReference] are loaded.
+#[derive(
+    Copy,
+    Default,
+    Clone,
+    Debug,
+    Hash,
+    TraceRawVcs,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    ValueDebugFormat,
+)]
+pub enum ChunkGroupLoading {
+    /// The chunks are loaded together with the referencing chunk, so
+    /// stylesheets among them block rendering.
+    #[default]
+    Eager,
+    /// The chunks are loaded on demand, e.g. by an async loader, so they can
+    /// only be preloaded.
+    Lazy,
+}
+
 /// A reference to multiple chunks from a [ChunkGroup]
 #[turbo_tasks::value]
 pub struct ChunkGroupReference {
     chunking_context: ChunkingContextVc,
     entry: ChunkVc,
+    loading: ChunkGroupLoading,
 }
 
 #[turbo_tasks::value_impl]
 impl ChunkGroupReferenceVc {
     #[turbo_tasks::function]
     pub fn new(chunking_context: ChunkingContextVc, entry: ChunkVc) -> Self {
+        Self::new_with_loading(
+            chunking_context,
+            entry,
+            Value::new(ChunkGroupLoading::Eager),
+        )
+    }
+
+    #[turbo_tasks::function]
+    pub fn new_with_loading(
+        chunking_context: ChunkingContextVc,
+        entry: ChunkVc,
+        loading: Value<ChunkGroupLoading>,
+    ) -> Self {
         Self::cell(ChunkGroupReference {
             chunking_context,
             entry,
+            loading: loading.into_value(),
         })
     }
 
+    /// Whether the chunks are loaded on demand, see [ChunkGroupLoading].
+    #[turbo_tasks::function]
+    pub async fn is_lazy(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(self.await?.loading == ChunkGroupLoading::Lazy))
+    }
+
     #[turbo_tasks::function]
     async fn chunks(self) -> Result<AssetsVc> {
         let this = self.await?;
@@ -226,8 +330,12 @@ impl AssetReference for ChunkGroupReference {
 impl ValueToString for ChunkGroupReference {
     #[turbo_tasks::function]
     async fn to_string(&self) -> Result<StringVc> {
+        let kind = match self.loading {
+            ChunkGroupLoading::Eager => "chunk group",
+            ChunkGroupLoading::Lazy => "lazy chunk group",
+        };
         Ok(StringVc::cell(format!(
-            "chunk group ({})",
+            "{kind} ({})",
             self.entry.ident().to_string().await?
         )))
     }
@@ -260,9 +368,16 @@ pub async fn chunk_content_split<I>(
 where
     I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
 {
-    chunk_content_internal_parallel(context, entry, additional_entries, availability_info, true)
-        .await
-        .map(|o| o.unwrap())
+    chunk_content_internal_parallel(
+        context,
+        entry,
+        additional_entries,
+        availability_info,
+        true,
+        ChunkContentLimits::default(),
+    )
+    .await
+    .map(|o| o.unwrap().content)
 }
 
 pub async fn chunk_content<I>(
@@ -274,8 +389,113 @@ pub async fn chunk_content<I>(
 where
     I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
 {
-    chunk_content_internal_parallel(context, entry, additional_entries, availability_info, false)
-        .await
+    Ok(chunk_content_internal_parallel(
+        context,
+        entry,
+        additional_entries,
+        availability_info,
+        false,
+        ChunkContentLimits::default(),
+    )
+    .await?
+    .map(|partial| partial.content))
+}
+
+/// Limits of [chunk_content_limited]. Chunk items beyond the limits are
+/// included in the result, but their references aren't followed. `None`
+/// doesn't limit the traversal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkContentLimits {
+    /// The maximum number of references between an entry and a chunk item
+    /// whose references are followed. With `Some(0)`, only the entries are
+    /// included, and with `Some(1)` also the chunk items they reference.
+    /// Chunk items count at their shortest distance from an entry, regardless
+    /// of the order in which the traversal reaches them.
+    pub max_depth: Option<usize>,
+    /// The maximum number of chunk items whose references are followed. Which
+    /// chunk items these are depends on the order in which references are
+    /// resolved.
+    pub max_nodes: Option<usize>,
+}
+
+/// The content of a chunk that was traversed with [ChunkContentLimits].
+pub struct PartialChunkContent<I> {
+    pub content: ChunkContentResult<I>,
+    /// Continues the traversal where the limits stopped it, see
+    /// [continue_chunk_content]. `None` if the content is complete.
+    pub continuation: Option<ChunkContentContinuation<I>>,
+}
+
+/// Where a limited chunk content traversal stopped, see
+/// [continue_chunk_content].
+pub struct ChunkContentContinuation<I> {
+    context: ChunkContentContext,
+    /// The assets that are already part of the content.
+    processed_assets: HashSet<(ChunkingType, AssetVc)>,
+    /// The chunk items whose references weren't followed.
+    frontier: Vec<I>,
+}
+
+impl<I> ChunkContentContinuation<I> {
+    /// The number of chunk items whose references weren't followed yet.
+    pub fn len(&self) -> usize {
+        self.frontier.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frontier.is_empty()
+    }
+}
+
+/// Like [chunk_content], but stops following references at `limits`, e.g. to
+/// preview the first levels of a large module graph, or to bail out of graphs
+/// that grow without bounds. The chunk items that are found until then are
+/// returned together with a continuation.
+pub async fn chunk_content_limited<I>(
+    context: ChunkingContextVc,
+    entry: AssetVc,
+    additional_entries: Option<AssetsVc>,
+    availability_info: Value<AvailabilityInfo>,
+    limits: ChunkContentLimits,
+) -> Result<Option<PartialChunkContent<I>>>
+where
+    I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
+{
+    chunk_content_internal_parallel(
+        context,
+        entry,
+        additional_entries,
+        availability_info,
+        false,
+        limits,
+    )
+    .await
+}
+
+/// Follows the references that a limited traversal didn't follow, and returns
+/// the chunk items that weren't part of the content yet. `limits` apply
+/// relative to where the traversal stopped, i.e. `max_depth` counts the
+/// references from the chunk items of the previous result.
+pub async fn continue_chunk_content<I>(
+    continuation: ChunkContentContinuation<I>,
+    limits: ChunkContentLimits,
+) -> Result<Option<PartialChunkContent<I>>>
+where
+    I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
+{
+    let ChunkContentContinuation {
+        context,
+        processed_assets,
+        frontier,
+    } = continuation;
+    let root_edges = frontier
+        .iter()
+        .map(|chunk_item| chunk_item_edges(context, chunk_item.clone(), 1))
+        .try_join()
+        .await?
+        .into_iter()
+        .flatten();
+    chunk_content_traversal(context, root_edges, processed_assets, limits).await
 }
 
 #[derive(Eq, PartialEq, Clone, Hash)]
@@ -298,6 +518,10 @@ struct ChunkContentContext {
     entry: AssetVc,
     availability_info: Value<AvailabilityInfo>,
     split: bool,
+    code_splitting: bool,
+    named_chunks: OptionNamedChunksVc,
+    /// The name that [NamedChunks] assign to the entry.
+    entry_chunk_name: OptionStringVc,
 }
 
 async fn reference_to_graph_nodes<I>(
@@ -349,6 +573,27 @@ where
             }
         };
 
+        if !context.code_splitting
+            && matches!(
+                chunking_type,
+                ChunkingType::PlacedOrParallel
+                    | ChunkingType::Parallel
+                    | ChunkingType::IsolatedParallel
+                    | ChunkingType::SeparateAsync
+            )
+        {
+            // Inline the asset into the current chunk instead of creating a separate
+            // chunk. Assets that can't be placed into this chunk type (e.g. CSS
+            // referenced from JS) still need their own chunk.
+            if let Some(chunk_item) = I::from_asset(context.chunking_context, asset).await? {
+                graph_nodes.push((
+                    Some((asset, ChunkingType::Placed)),
+                    ChunkContentGraphNode::ChunkItem(chunk_item),
+                ));
+                continue;
+            }
+        }
+
         match chunking_type {
             ChunkingType::Placed => {
                 if let Some(chunk_item) = I::from_asset(context.chunking_context, asset).await? {
@@ -380,8 +625,17 @@ where
                 ));
             }
             ChunkingType::PlacedOrParallel => {
+                // Assets that belong to another named chunk are split off.
+                let chunk_name = context
+                    .named_chunks
+                    .chunk_name(asset.ident().path())
+                    .await?;
+                let is_named_elsewhere =
+                    chunk_name.is_some() && *chunk_name != *context.entry_chunk_name.await?;
+
                 // heuristic for being in the same chunk
                 if !context.split
+                    && !is_named_elsewhere
                     && *context
                         .chunking_context
                         .can_be_in_same_chunk(context.entry, asset)
@@ -443,40 +697,78 @@ where
 /// into multiple chunks.
 const MAX_CHUNK_ITEMS_COUNT: usize = 5000;
 
+/// The reason why the chunk content traversal was aborted.
+enum ChunkContentAbort {
+    /// The chunk has too many chunk items and needs to be split.
+    TooLarge,
+    /// The [CancellationToken] of the chunking context was cancelled.
+    Cancelled,
+}
+
+/// An edge of the chunk content graph, with the number of references between
+/// an entry and the node.
+type ChunkContentEdge<I> = (
+    Option<(AssetVc, ChunkingType)>,
+    ChunkContentGraphNode<I>,
+    usize,
+);
+
 struct ChunkContentVisit<I> {
     context: ChunkContentContext,
+    cancellation: CancellationGuard,
     chunk_items_count: usize,
     processed_assets: HashSet<(ChunkingType, AssetVc)>,
+    limits: ChunkContentLimits,
+    /// The number of chunk items whose references were followed.
+    expanded_count: usize,
+    /// The shortest known depth of each chunk item, when the depth is
+    /// limited.
+    depths: HashMap<I, usize>,
+    /// The chunk items whose references weren't followed because of the
+    /// limits.
+    frontier: IndexSet<I>,
     _phantom: PhantomData<I>,
 }
 
-type ChunkItemToGraphNodesEdges<I> =
-    impl Iterator<Item = (Option<(AssetVc, ChunkingType)>, ChunkContentGraphNode<I>)>;
+type ChunkItemToGraphNodesEdges<I> = impl Iterator<Item = ChunkContentEdge<I>>;
 
 type ChunkItemToGraphNodesFuture<I: FromChunkableAsset + Eq + std::hash::Hash + Clone> =
     impl Future<Output = Result<ChunkItemToGraphNodesEdges<I>>>;
 
-impl<I> Visit<ChunkContentGraphNode<I>, ()> for ChunkContentVisit<I>
+// Implemented for a reference, so that the frontier and the processed assets
+// can be read after the traversal.
+impl<I> Visit<ChunkContentGraphNode<I>, ChunkContentAbort> for &mut ChunkContentVisit<I>
 where
     I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
 {
-    type Edge = (Option<(AssetVc, ChunkingType)>, ChunkContentGraphNode<I>);
+    type Edge = ChunkContentEdge<I>;
     type EdgesIntoIter = ChunkItemToGraphNodesEdges<I>;
     type EdgesFuture = ChunkItemToGraphNodesFuture<I>;
 
     fn visit(
         &mut self,
-        (option_key, node): (Option<(AssetVc, ChunkingType)>, ChunkContentGraphNode<I>),
-    ) -> VisitControlFlow<ChunkContentGraphNode<I>, ()> {
+        (option_key, node, depth): ChunkContentEdge<I>,
+    ) -> VisitControlFlow<ChunkContentGraphNode<I>, ChunkContentAbort> {
+        if self.cancellation.is_cancelled() {
+            return VisitControlFlow::Abort(ChunkContentAbort::Cancelled);
+        }
+
         let Some((asset, chunking_type)) = option_key else {
             return VisitControlFlow::Continue(node);
         };
 
-        if !self.processed_assets.insert((chunking_type, asset)) {
-            return VisitControlFlow::Skip(node);
-        }
+        let first_visit = self.processed_assets.insert((chunking_type, asset));
 
-        if let ChunkContentGraphNode::ChunkItem(_) = &node {
+        let ChunkContentGraphNode::ChunkItem(chunk_item) = &node else {
+            return if first_visit {
+                VisitControlFlow::Continue(node)
+            } else {
+                VisitControlFlow::Skip(node)
+            };
+        };
+
+        let limits = self.limits;
+        if first_visit {
             self.chunk_items_count += 1;
 
             // Make sure the chunk doesn't become too large.
@@ -484,8 +776,43 @@ where
             if !self.context.split && self.chunk_items_count >= MAX_CHUNK_ITEMS_COUNT {
                 // Chunk is too large, cancel this algorithm and restart with splitting from the
                 // start.
-                return VisitControlFlow::Abort(());
+                return VisitControlFlow::Abort(ChunkContentAbort::TooLarge);
+            }
+        } else if limits.max_depth.is_none()
+            || self
+                .depths
+                .get(chunk_item)
+                .map_or(true, |&min_depth| min_depth <= depth)
+        {
+            return VisitControlFlow::Skip(node);
+        }
+        // Otherwise the chunk item was reached along a shorter path than
+        // before. Its references are followed again, so that the depths of the
+        // chunk items it references shrink as well.
+
+        if limits.max_depth.is_some() {
+            self.depths.insert(chunk_item.clone(), depth);
+        }
+        let on_frontier = !first_visit && self.frontier.contains(chunk_item);
+        let expands = first_visit || on_frontier;
+        if limits
+            .max_depth
+            .map_or(false, |max_depth| depth >= max_depth)
+            || (expands
+                && limits
+                    .max_nodes
+                    .map_or(false, |max_nodes| self.expanded_count >= max_nodes))
+        {
+            if first_visit {
+                self.frontier.insert(chunk_item.clone());
             }
+            return VisitControlFlow::Skip(node);
+        }
+        if on_frontier {
+            self.frontier.shift_remove(chunk_item);
+        }
+        if expands {
+            self.expanded_count += 1;
         }
 
         VisitControlFlow::Continue(node)
@@ -497,6 +824,10 @@ where
         } else {
             None
         };
+        let depth = chunk_item
+            .as_ref()
+            .and_then(|chunk_item| self.depths.get(chunk_item).copied())
+            .unwrap_or_default();
 
         let context = self.context;
 
@@ -505,12 +836,7 @@ where
                 return Ok(vec![].into_iter().flatten());
             };
 
-            Ok(chunk_item
-                .references()
-                .await?
-                .into_iter()
-                .map(|reference| reference_to_graph_nodes::<I>(context, *reference))
-                .try_join()
+            Ok(chunk_item_edges(context, chunk_item, depth + 1)
                 .await?
                 .into_iter()
                 .flatten())
@@ -518,13 +844,37 @@ where
     }
 }
 
+/// The edges of the references of `chunk_item`, which lead to nodes at
+/// `depth`.
+async fn chunk_item_edges<I>(
+    context: ChunkContentContext,
+    chunk_item: I,
+    depth: usize,
+) -> Result<Vec<ChunkContentEdge<I>>>
+where
+    I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
+{
+    Ok(chunk_item
+        .references()
+        .await?
+        .into_iter()
+        .map(|reference| reference_to_graph_nodes::<I>(context, *reference))
+        .try_join()
+        .await?
+        .into_iter()
+        .flatten()
+        .map(|(key, node)| (key, node, depth))
+        .collect())
+}
+
 async fn chunk_content_internal_parallel<I>(
     chunking_context: ChunkingContextVc,
     entry: AssetVc,
     additional_entries: Option<AssetsVc>,
     availability_info: Value<AvailabilityInfo>,
     split: bool,
-) -> Result<Option<ChunkContentResult<I>>>
+    limits: ChunkContentLimits,
+) -> Result<Option<PartialChunkContent<I>>>
 where
     I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
 {
@@ -543,27 +893,61 @@ where
                 ChunkContentGraphNode::ChunkItem(
                     I::from_asset(chunking_context, entry).await?.unwrap(),
                 ),
+                0,
             ))
         })
         .try_join()
         .await?;
 
+    let named_chunks = chunking_context.named_chunks();
     let context = ChunkContentContext {
         chunking_context,
         entry,
         split,
+        code_splitting: *chunking_context.is_code_splitting_enabled().await?,
+        named_chunks,
+        entry_chunk_name: named_chunks.chunk_name(entry.ident().path()),
         availability_info,
     };
 
-    let visit = ChunkContentVisit {
+    chunk_content_traversal(context, root_edges, HashSet::new(), limits).await
+}
+
+/// Traverses the chunk content graph from `root_edges`. Assets in
+/// `processed_assets` were already visited and are skipped.
+async fn chunk_content_traversal<I>(
+    context: ChunkContentContext,
+    root_edges: impl IntoIterator<Item = ChunkContentEdge<I>>,
+    processed_assets: HashSet<(ChunkingType, AssetVc)>,
+    limits: ChunkContentLimits,
+) -> Result<Option<PartialChunkContent<I>>>
+where
+    I: FromChunkableAsset + Eq + std::hash::Hash + Clone,
+{
+    let cancellation = context.chunking_context.cancellation_token().await?.guard();
+    cancellation.check()?;
+
+    let mut visit = ChunkContentVisit {
         context,
+        cancellation,
         chunk_items_count: 0,
-        processed_assets: Default::default(),
+        processed_assets,
+        limits,
+        expanded_count: 0,
+        depths: HashMap::new(),
+        frontier: IndexSet::new(),
         _phantom: PhantomData,
     };
 
-    let GraphTraversalResult::Completed(traversal_result) = ReverseTopological::new().visit(root_edges, visit).await else {
-        return Ok(None);
+    let traversal_result = match ReverseTopological::new()
+        .visit(root_edges, &mut visit)
+        .await
+    {
+        GraphTraversalResult::Completed(traversal_result) => traversal_result,
+        GraphTraversalResult::Aborted(ChunkContentAbort::TooLarge) => return Ok(None),
+        GraphTraversalResult::Aborted(ChunkContentAbort::Cancelled) => {
+            return Err(visit.cancellation.cancelled());
+        }
     };
 
     let graph_nodes: Vec<_> = traversal_result?.into_iter().collect();
@@ -591,12 +975,20 @@ where
         }
     }
 
-    Ok(Some(ChunkContentResult {
-        chunk_items,
-        chunks,
-        async_chunk_group_entries,
-        external_asset_references,
-        availability_info: availability_info.into_value(),
+    let continuation = (!visit.frontier.is_empty()).then(|| ChunkContentContinuation {
+        context,
+        processed_assets: visit.processed_assets,
+        frontier: visit.frontier.into_iter().collect(),
+    });
+    Ok(Some(PartialChunkContent {
+        content: ChunkContentResult {
+            chunk_items,
+            chunks,
+            async_chunk_group_entries,
+            external_asset_references,
+            availability_info: context.availability_info.into_value(),
+        },
+        continuation,
     }))
 }
 
@@ -0,0 +1,147 @@
+//! Numeric module ids that are stable across builds.
+//!
+//! By default module ids are derived from the ident of a module, which is
+//! stable but long. A [ModuleIdMap] assigns short numeric ids instead and is
+//! persisted between builds. Before a build, the map of the previous build is
+//! reconciled with the modules of the current build: unchanged modules keep
+//! their id, new modules get ids that were never used before and ids of
+//! removed modules are retired. Adding a module therefore doesn't shift the id
+//! of every other module, which would invalidate all long-term cached chunks.
+
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{primitives::StringsVc, CompletionVc, ValueToString};
+use turbo_tasks_fs::{File, FileContent, FileJsonContent, FileSystemPathVc};
+
+use super::{ModuleId, ModuleIdVc};
+
+/// Maps module idents to numeric module ids.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleIdMap {
+    pub ids: BTreeMap<String, u32>,
+    /// The id that is assigned to the next new module. Retired ids are below
+    /// it and are never reused.
+    pub next_id: u32,
+}
+
+impl ModuleIdMap {
+    /// Returns a map for `idents` that keeps the ids of idents that are in
+    /// this map and allocates new ids for the others, in the order of
+    /// `idents`. Idents that are not in `idents` are dropped.
+    pub fn reconcile<'a>(&self, idents: impl IntoIterator<Item = &'a str>) -> ModuleIdMap {
+        let mut next_id = self.next_id;
+        let mut new_idents = Vec::new();
+        let mut ids = BTreeMap::new();
+        for ident in idents {
+            match self.ids.get(ident) {
+                Some(id) => {
+                    ids.insert(ident.to_string(), *id);
+                }
+                None => new_idents.push(ident),
+            }
+        }
+        for ident in new_idents {
+            if let Entry::Vacant(entry) = ids.entry(ident.to_string()) {
+                entry.insert(next_id);
+                next_id += 1;
+            }
+        }
+        ModuleIdMap { ids, next_id }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleIdMapVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        ModuleIdMap::default().cell()
+    }
+
+    /// Loads a map that was saved with [ModuleIdMapVc::save]. A missing file
+    /// results in an empty map.
+    #[turbo_tasks::function]
+    pub async fn load(path: FileSystemPathVc) -> Result<Self> {
+        Ok(match &*path.read_json().await? {
+            FileJsonContent::Content(json) => {
+                serde_json::from_value::<ModuleIdMap>(json.clone())?.cell()
+            }
+            FileJsonContent::Unparseable(e) => {
+                bail!(
+                    "unable to parse module id map {}: {}",
+                    path.to_string().await?,
+                    e
+                )
+            }
+            FileJsonContent::NotFound => ModuleIdMap::default().cell(),
+        })
+    }
+
+    #[turbo_tasks::function]
+    pub async fn save(self, path: FileSystemPathVc) -> Result<CompletionVc> {
+        let json = serde_json::to_string_pretty(&*self.await?)?;
+        Ok(path.write(FileContent::Content(File::from(json)).cell()))
+    }
+
+    /// See [ModuleIdMap::reconcile].
+    #[turbo_tasks::function]
+    pub async fn reconcile(self, idents: StringsVc) -> Result<Self> {
+        let idents = idents.await?;
+        Ok(self
+            .await?
+            .reconcile(idents.iter().map(|ident| ident.as_str()))
+            .cell())
+    }
+
+    /// Returns the numeric id of the module with the given `ident`, or the
+    /// ident itself if the module is not in the map.
+    #[turbo_tasks::function]
+    pub async fn module_id(self, ident: &str) -> Result<ModuleIdVc> {
+        Ok(match self.await?.ids.get(ident) {
+            Some(id) => ModuleId::Number(*id),
+            None => ModuleId::String(ident.to_string()),
+        }
+        .cell())
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionModuleIdMap(Option<ModuleIdMapVc>);
+
+#[turbo_tasks::value_impl]
+impl OptionModuleIdMapVc {
+    #[turbo_tasks::function]
+    pub fn none() -> Self {
+        OptionModuleIdMapVc::cell(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_ids_of_unchanged_modules() {
+        let first = ModuleIdMap::default().reconcile(["a", "b", "c"]);
+        assert_eq!(first.ids["a"], 0);
+        assert_eq!(first.ids["c"], 2);
+        assert_eq!(first.next_id, 3);
+
+        let second = first.reconcile(["a", "new", "b", "c"]);
+        assert_eq!(second.ids["a"], 0);
+        assert_eq!(second.ids["b"], 1);
+        assert_eq!(second.ids["c"], 2);
+        assert_eq!(second.ids["new"], 3);
+    }
+
+    #[test]
+    fn retired_ids_are_not_reused() {
+        let first = ModuleIdMap::default().reconcile(["a", "b"]);
+        let second = first.reconcile(["a", "c"]);
+        assert!(!second.ids.contains_key("b"));
+        assert_eq!(second.ids["c"], 2);
+        assert_eq!(second.next_id, 3);
+    }
+}
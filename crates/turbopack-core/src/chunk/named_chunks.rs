@@ -0,0 +1,178 @@
+//! Chunk names that are assigned to assets by configuration.
+//!
+//! [NamedChunks] maps globs over the paths of assets to the names of the
+//! chunks they belong to, e.g. `packages/icons/**` to `icons`. During
+//! chunking, an asset that matches a rule isn't placed into a chunk with a
+//! different name, but split off into a chunk that is loaded in parallel and
+//! named after the rule. Assets that don't match any rule are placed as usual,
+//! also into named chunks. The chunk groups of an entry and of its async
+//! imports share a single chunk per name, see
+//! [OptionNamedChunksVc::named_chunk_assets].
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::{primitives::OptionStringVc, TryJoinIterExt, Value};
+use turbo_tasks_fs::{glob::GlobVc, FileSystemPathVc};
+
+use super::{availability_info::AvailabilityInfo, available_assets::reachable_chunkable_assets};
+use crate::asset::{Asset, AssetsVc};
+
+/// Rules that assign chunk names to assets by their path.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct NamedChunks {
+    root: FileSystemPathVc,
+    rules: Vec<(GlobVc, String)>,
+}
+
+impl NamedChunksVc {
+    /// Creates rules from `(glob, name)` pairs, e.g. from a configuration
+    /// file. The globs are matched against asset paths relative to `root`,
+    /// and the first matching rule wins.
+    pub fn new(root: FileSystemPathVc, rules: Vec<(String, String)>) -> Self {
+        NamedChunks {
+            root,
+            rules: rules
+                .into_iter()
+                .map(|(glob, name)| (GlobVc::new(&glob), name))
+                .collect(),
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl NamedChunksVc {
+    /// The name of the chunk that the asset at `path` belongs to, or `None`
+    /// if no rule matches.
+    #[turbo_tasks::function]
+    pub async fn chunk_name(self, path: FileSystemPathVc) -> Result<OptionStringVc> {
+        let this = self.await?;
+        let root = this.root.await?;
+        let path = path.await?;
+        if let Some(relative) = root.get_path_to(&path) {
+            for (glob, name) in &this.rules {
+                if glob.await?.execute(relative) {
+                    return Ok(OptionStringVc::cell(Some(name.clone())));
+                }
+            }
+        }
+        Ok(OptionStringVc::cell(None))
+    }
+}
+
+impl OptionNamedChunksVc {
+    /// The name that the assets at all of `paths` belong to, or `None` if
+    /// one of them doesn't match a rule, they belong to different chunks or
+    /// there are no `paths`.
+    pub async fn common_chunk_name(
+        self,
+        paths: impl IntoIterator<Item = FileSystemPathVc>,
+    ) -> Result<Option<String>> {
+        let names = paths
+            .into_iter()
+            .map(|path| self.chunk_name(path))
+            .try_join()
+            .await?;
+        let mut names = names.iter().map(|name| name.as_deref());
+        let first = names.next().flatten();
+        Ok(names
+            .all(|name| name == first)
+            .then(|| first.map(str::to_string))
+            .flatten())
+    }
+}
+
+/// Splits `items` into the items that belong to a named chunk, grouped by
+/// name in the order in which the names first appear, and the other items.
+pub fn group_by_chunk_name<T>(
+    items: impl IntoIterator<Item = (T, Option<String>)>,
+) -> (IndexMap<String, Vec<T>>, Vec<T>) {
+    let mut named = IndexMap::<_, Vec<_>>::new();
+    let mut unnamed = Vec::new();
+    for (item, name) in items {
+        match name {
+            Some(name) => named.entry(name).or_default().push(item),
+            None => unnamed.push(item),
+        }
+    }
+    (named, unnamed)
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionNamedChunks(Option<NamedChunksVc>);
+
+#[turbo_tasks::value_impl]
+impl OptionNamedChunksVc {
+    #[turbo_tasks::function]
+    pub fn none() -> Self {
+        OptionNamedChunksVc::cell(None)
+    }
+
+    /// Like [NamedChunksVc::chunk_name], `None` without rules.
+    #[turbo_tasks::function]
+    pub async fn chunk_name(self, path: FileSystemPathVc) -> Result<OptionStringVc> {
+        Ok(match *self.await? {
+            Some(named_chunks) => named_chunks.chunk_name(path),
+            None => OptionStringVc::cell(None),
+        })
+    }
+
+    /// The assets named `name` in all chunk groups that are loaded from the
+    /// same chunk groups as a chunk group with `availability_info`, e.g. the
+    /// chunk groups of the async imports of a page. A chunk of these assets is
+    /// the same in each of these chunk groups, so it's only loaded once.
+    #[turbo_tasks::function]
+    pub async fn named_chunk_assets(
+        self,
+        availability_info: Value<AvailabilityInfo>,
+        name: &str,
+    ) -> Result<AssetsVc> {
+        // Each chunk group is reachable from the chunk groups it's loaded from,
+        // so the chunk groups that are loaded from the same chunk groups end up
+        // with the same assets.
+        let mut assets = IndexSet::new();
+        for root in availability_info.availability_roots().await? {
+            assets.extend(reachable_chunkable_assets(root).await?.iter().copied());
+        }
+        let names = assets
+            .iter()
+            .map(|asset| self.chunk_name(asset.ident().path()))
+            .try_join()
+            .await?;
+        Ok(AssetsVc::cell(
+            assets
+                .into_iter()
+                .zip(names.iter())
+                .filter(|(_, asset_name)| asset_name.as_deref() == Some(name))
+                .map(|(asset, _)| asset)
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::group_by_chunk_name;
+
+    #[test]
+    fn test_group_by_chunk_name() {
+        let name = |name: &str| Some(name.to_string());
+        let (named, unnamed) = group_by_chunk_name([
+            ("icons/a", name("icons")),
+            ("index", None),
+            ("vendor/react", name("vendor")),
+            ("icons/b", name("icons")),
+            ("page", None),
+            ("icons/c", name("icons")),
+        ]);
+        assert_eq!(
+            named.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("icons".to_string(), vec!["icons/a", "icons/b", "icons/c"]),
+                ("vendor".to_string(), vec!["vendor/react"]),
+            ]
+        );
+        assert_eq!(unnamed, vec!["index", "page"]);
+    }
+}
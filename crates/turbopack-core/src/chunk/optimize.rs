@@ -7,7 +7,10 @@ use anyhow::Result;
 use turbo_tasks::TryJoinIterExt;
 use turbo_tasks_fs::{FileSystemPathOptionVc, FileSystemPathVc};
 
-use crate::chunk::containment_tree::{ContainmentTree, ContainmentTreeKey};
+use crate::chunk::{
+    containment_tree::{ContainmentTree, ContainmentTreeKey},
+    CancellationGuard, CancellationToken,
+};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 struct FileSystemPathKey(FileSystemPathVc);
@@ -25,8 +28,12 @@ impl ContainmentTreeKey for FileSystemPathKey {
     }
 }
 
+/// Groups `chunks` by their common parent directory and calls `optimize`
+/// bottom-up on each directory. The optimization stops once the
+/// `cancellation` token is cancelled or restarted.
 pub async fn optimize_by_common_parent<T, Acc, GetCommonParent, Optimize>(
     chunks: &[T],
+    cancellation: &CancellationToken,
     get_common_parent: GetCommonParent,
     optimize: Optimize,
 ) -> Result<Acc>
@@ -35,6 +42,7 @@ where
     GetCommonParent: Fn(T) -> FileSystemPathOptionVc + Clone,
     Optimize: Fn(Option<Vec<T>>, Vec<Acc>) -> Acc,
 {
+    let cancellation = cancellation.guard();
     let tree = ContainmentTree::build(
         chunks
             .iter()
@@ -60,16 +68,20 @@ where
 
     fn optimize_tree<K, V, Acc>(
         tree: ContainmentTree<K, V>,
+        cancellation: &CancellationGuard,
         optimize: &impl Fn(Option<Vec<V>>, Vec<Acc>) -> Acc,
-    ) -> Acc {
+    ) -> Result<Acc> {
+        cancellation.check()?;
+
         let children = tree
             .children
             .into_iter()
-            .map(|tree| optimize_tree(tree, optimize))
-            .collect::<Vec<_>>();
+            .map(|tree| optimize_tree(tree, cancellation, optimize))
+            .collect::<Result<Vec<_>>>()?;
 
-        optimize(tree.values, children)
+        Ok(optimize(tree.values, children))
     }
 
-    Ok(optimize_tree(tree, &optimize))
+    cancellation.check()?;
+    optimize_tree(tree, &cancellation, &optimize)
 }
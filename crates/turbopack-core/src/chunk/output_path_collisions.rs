@@ -0,0 +1,206 @@
+//! Detection of output assets that are written to the same path.
+//!
+//! Different chunks or static assets can end up with the same output path,
+//! e.g. because of hash truncation or because their names only differ in case
+//! on a case-insensitive file system. [output_path_collisions] finds them in
+//! the full set of output assets of a build, so they're detected instead of
+//! one asset silently overwriting the other. Chunking contexts that use
+//! [PathCollisionStrategy::Disambiguate] avoid collisions of chunks
+//! altogether.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use turbo_tasks::{TryJoinIterExt, ValueToString};
+use turbo_tasks_fs::FileContent;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+use crate::asset::{Asset, AssetContent, AssetsVc};
+
+/// How a chunking context deals with chunks that end up with the same path.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum PathCollisionStrategy {
+    /// Keep the paths. Collisions are found by [output_path_collisions].
+    #[default]
+    Error,
+    /// Append a hash of the ident of each chunk to its file name, so chunks
+    /// with different idents never share a path. The path of a chunk only
+    /// depends on its ident.
+    Disambiguate,
+}
+
+/// The length of the ident hash that is appended to disambiguate a path.
+const DISAMBIGUATION_HASH_LENGTH: usize = 16;
+
+impl PathCollisionStrategy {
+    /// The file name of the asset with the given `ident` for `file_name`.
+    pub fn file_name(self, file_name: &str, ident: &str) -> String {
+        if self == PathCollisionStrategy::Error {
+            return file_name.to_string();
+        }
+        let hash = encode_hex(hash_xxh3_hash64(ident.as_bytes()));
+        let extension_start = file_name.find('.').unwrap_or(file_name.len());
+        format!(
+            "{}~{}{}",
+            &file_name[..extension_start],
+            &hash[..DISAMBIGUATION_HASH_LENGTH],
+            &file_name[extension_start..]
+        )
+    }
+}
+
+/// Assets with different content that are written to the same path.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct OutputPathCollision {
+    /// The path of the first of the assets, ordered by path.
+    pub path: String,
+    /// The idents of the assets, in order.
+    pub idents: Vec<String>,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OutputPathCollisions(Vec<OutputPathCollision>);
+
+/// Finds the assets among `assets` that are written to the same path with
+/// different content. With `case_insensitive`, paths that only differ in case
+/// are the same. Assets with identical content, e.g. static assets whose path
+/// is their content hash, don't collide.
+#[turbo_tasks::function]
+pub async fn output_path_collisions(
+    assets: AssetsVc,
+    case_insensitive: bool,
+) -> Result<OutputPathCollisionsVc> {
+    let assets = assets
+        .await?
+        .iter()
+        .map(|asset| async move {
+            let ident = asset.ident();
+            Ok((
+                *asset,
+                ident.path().to_string().await?.clone_value(),
+                ident.to_string().await?.clone_value(),
+            ))
+        })
+        .try_join()
+        .await?;
+    let mut collisions = Vec::new();
+    for group in colliding_paths(
+        assets
+            .iter()
+            .map(|(_, path, ident)| (path.as_str(), ident.as_str())),
+        case_insensitive,
+    ) {
+        // Only the content of the assets whose paths collide is read.
+        let mut contents = BTreeMap::new();
+        for &(path, ident) in group.iter() {
+            let (asset, ..) = assets
+                .iter()
+                .find(|(_, asset_path, asset_ident)| asset_path == path && asset_ident == ident)
+                .expect("colliding paths are paths of assets");
+            let hash = match &*asset.content().await? {
+                AssetContent::File(file) => match &*file.await? {
+                    FileContent::Content(file) => {
+                        Some(hash_xxh3_hash64(file.content().to_bytes()?.as_ref()))
+                    }
+                    FileContent::NotFound => None,
+                },
+                AssetContent::Redirect { .. } => None,
+            };
+            contents.insert(ident, hash);
+        }
+        let first_hash = contents.values().next().copied();
+        if contents.values().all(|hash| Some(*hash) == first_hash) {
+            continue;
+        }
+        collisions.push(OutputPathCollision {
+            path: group[0].0.to_string(),
+            idents: contents.keys().map(|ident| ident.to_string()).collect(),
+        });
+    }
+    Ok(OutputPathCollisionsVc::cell(collisions))
+}
+
+/// Groups the `(path, ident)` pairs whose paths are the same but whose idents
+/// differ, ordered by path and ident.
+fn colliding_paths<'a>(
+    assets: impl IntoIterator<Item = (&'a str, &'a str)>,
+    case_insensitive: bool,
+) -> Vec<Vec<(&'a str, &'a str)>> {
+    let mut by_path = BTreeMap::<String, Vec<(&str, &str)>>::new();
+    for (path, ident) in assets {
+        let key = if case_insensitive {
+            path.to_lowercase()
+        } else {
+            path.to_string()
+        };
+        by_path.entry(key).or_default().push((path, ident));
+    }
+    by_path
+        .into_values()
+        .filter_map(|mut group| {
+            group.sort();
+            group.dedup();
+            let first_ident = group[0].1;
+            group
+                .iter()
+                .any(|(_, ident)| *ident != first_ident)
+                .then_some(group)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_ident_is_not_a_collision() {
+        let assets = [
+            ("out/a.js", "[project]/a.js"),
+            ("out/a.js", "[project]/a.js"),
+        ];
+        assert!(colliding_paths(assets, false).is_empty());
+    }
+
+    #[test]
+    fn collisions_are_grouped_in_order() {
+        let assets = [
+            ("out/b.js", "[project]/b.js"),
+            ("out/a.js", "[project]/a.js"),
+            ("out/A.js", "[project]/A.js"),
+            ("out/c.js", "[project]/c.js"),
+        ];
+        assert!(colliding_paths(assets, false).is_empty());
+        assert_eq!(
+            colliding_paths(assets, true),
+            [vec![
+                ("out/A.js", "[project]/A.js"),
+                ("out/a.js", "[project]/a.js")
+            ]]
+        );
+        // The order of the assets doesn't matter.
+        let mut reversed = assets;
+        reversed.reverse();
+        assert_eq!(
+            colliding_paths(reversed, true),
+            colliding_paths(assets, true)
+        );
+    }
+
+    #[test]
+    fn disambiguated_file_names_only_depend_on_the_ident() {
+        let strategy = PathCollisionStrategy::Disambiguate;
+        let a = strategy.file_name("a._.js", "[project]/a.js");
+        let b = strategy.file_name("a._.js", "[project]/b.js");
+        assert!(a.starts_with("a~"));
+        assert!(a.ends_with("._.js"));
+        assert_ne!(a, b);
+        assert_eq!(strategy.file_name("a._.js", "[project]/b.js"), b);
+        assert_eq!(
+            PathCollisionStrategy::Error.file_name("a._.js", "[project]/a.js"),
+            "a._.js"
+        );
+    }
+}
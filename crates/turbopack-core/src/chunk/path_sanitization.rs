@@ -0,0 +1,144 @@
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+/// Characters that are not allowed in file names on Windows.
+const ILLEGAL_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// File names that are reserved on Windows, regardless of their extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Number of hex characters of the hash that replaces the truncated part of a
+/// file name.
+const TRUNCATION_HASH_LENGTH: usize = 8;
+
+/// Controls how file names of chunks and assets are sanitized before they are
+/// emitted, so that output produced on one platform can be used on another.
+///
+/// The default policy leaves file names untouched.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct PathSanitizationPolicy {
+    /// Replace characters that are illegal in Windows file names, control
+    /// characters and trailing dots and spaces with `_`.
+    pub replace_illegal_chars: bool,
+    /// Prefix file names whose stem is reserved on Windows (e.g. `CON`,
+    /// `NUL`) with `_`.
+    pub escape_reserved_names: bool,
+    /// Maximum length in bytes of an output path relative to the output root.
+    /// Longer file names are truncated and suffixed with a hash of the full
+    /// name.
+    pub max_path_length: Option<usize>,
+}
+
+impl PathSanitizationPolicy {
+    /// A policy that produces file names that are valid on Windows, Linux and
+    /// macOS.
+    pub fn cross_platform() -> Self {
+        PathSanitizationPolicy {
+            replace_illegal_chars: true,
+            escape_reserved_names: true,
+            // Leaves room for the output root within Windows' MAX_PATH of 260.
+            max_path_length: Some(200),
+        }
+    }
+
+    /// Sanitizes the file `name` of a path that is placed in a directory whose
+    /// path relative to the output root is `directory_len` bytes long.
+    pub fn sanitize_file_name(&self, directory_len: usize, name: &str) -> String {
+        let mut name = name.to_string();
+
+        if self.replace_illegal_chars {
+            name = name
+                .chars()
+                .map(|c| {
+                    if c.is_control() || ILLEGAL_CHARS.contains(&c) {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            let trimmed_len = name.trim_end_matches(['.', ' ']).len();
+            let trailing = name.len() - trimmed_len;
+            name.truncate(trimmed_len);
+            name.extend(std::iter::repeat('_').take(trailing));
+        }
+
+        if self.escape_reserved_names {
+            let stem = name.split('.').next().unwrap_or_default();
+            if RESERVED_NAMES
+                .iter()
+                .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+            {
+                name.insert(0, '_');
+            }
+        }
+
+        if let Some(max_path_length) = self.max_path_length {
+            // The directory is separated from the file name by a `/`.
+            let max_len = max_path_length.saturating_sub(directory_len + 1);
+            if name.len() > max_len {
+                name = truncate_with_hash(&name, max_len);
+            }
+        }
+
+        name
+    }
+}
+
+/// Shortens `name` to at most `max_len` bytes (if possible) while keeping its
+/// extension, replacing the removed part with a hash of the full name.
+fn truncate_with_hash(name: &str, max_len: usize) -> String {
+    let hash = encode_hex(hash_xxh3_hash64(name.as_bytes()));
+    let hash = &hash[..TRUNCATION_HASH_LENGTH];
+    let extension = name.rfind('.').map_or("", |i| &name[i..]);
+    let stem = &name[..name.len() - extension.len()];
+
+    let mut keep = max_len.saturating_sub(extension.len() + hash.len() + 1);
+    while !stem.is_char_boundary(keep) {
+        keep -= 1;
+    }
+    if keep == 0 {
+        return format!("{hash}{extension}");
+    }
+    format!("{}_{hash}{extension}", &stem[..keep])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_keeps_names() {
+        let policy = PathSanitizationPolicy::default();
+        assert_eq!(policy.sanitize_file_name(0, "a:b|c.js"), "a:b|c.js");
+    }
+
+    #[test]
+    fn replaces_illegal_chars_and_reserved_names() {
+        let policy = PathSanitizationPolicy::cross_platform();
+        assert_eq!(policy.sanitize_file_name(0, "a:b|c?.js"), "a_b_c_.js");
+        assert_eq!(policy.sanitize_file_name(0, "con.js"), "_con.js");
+        assert_eq!(policy.sanitize_file_name(0, "console.js"), "console.js");
+        assert_eq!(policy.sanitize_file_name(0, "file. "), "file__");
+    }
+
+    #[test]
+    fn truncates_long_names() {
+        let policy = PathSanitizationPolicy {
+            max_path_length: Some(32),
+            ..Default::default()
+        };
+        let name = format!("{}.js", "a".repeat(40));
+        let sanitized = policy.sanitize_file_name(10, &name);
+        assert_eq!(sanitized.len(), 21);
+        assert!(sanitized.ends_with(".js"));
+        assert_eq!(sanitized, policy.sanitize_file_name(10, &name));
+        assert_ne!(
+            sanitized,
+            policy.sanitize_file_name(10, &format!("{}b.js", "a".repeat(40)))
+        );
+    }
+}
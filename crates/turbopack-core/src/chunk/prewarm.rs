@@ -0,0 +1,131 @@
+//! Generating chunk groups ahead of time.
+//!
+//! Dev servers know which routes are likely to be requested next, e.g. the
+//! targets of links on the current page. [ChunkGroup::prewarm] computes the
+//! chunks of such a route and generates their content in the background while
+//! the server is idle, so the route is served from the cache once it's
+//! actually requested.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, TurboTasksApi};
+
+use super::{
+    input_digest::{chunk_group_inputs, input_digest, input_digests},
+    ChunkGenerationPriority, ChunkInputDigestsVc, ChunkVc, ChunkingContext, ChunkingContextVc,
+    ExternalInputsVc,
+};
+use crate::asset::{Asset, AssetContent, AssetsVc};
+
+/// The chunks that are needed to load an entry chunk, see
+/// [ChunkingContext::chunk_group].
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkGroup {
+    chunking_context: ChunkingContextVc,
+    entry: ChunkVc,
+}
+
+impl ChunkGroup {
+    pub fn new(chunking_context: ChunkingContextVc, entry: ChunkVc) -> Self {
+        Self {
+            chunking_context,
+            entry,
+        }
+    }
+
+    pub fn chunking_context(&self) -> ChunkingContextVc {
+        self.chunking_context
+    }
+
+    pub fn entry(&self) -> ChunkVc {
+        self.entry
+    }
+
+    /// The chunks and other assets of the group.
+    pub fn assets(&self) -> AssetsVc {
+        self.chunking_context.chunk_group(self.entry)
+    }
+
+    /// The [external inputs](ExternalInputsVc) of the group: the inputs
+    /// declared by the chunking context and the ones recorded while the
+    /// chunks of the group were computed.
+    pub fn external_inputs(&self) -> ExternalInputsVc {
+        chunk_group_inputs(self.chunking_context, self.assets())
+    }
+
+    /// A digest per chunk of the group that combines the content of the chunk
+    /// with the external inputs recorded for it and the ones declared by the
+    /// chunking context, keyed by the path of the chunk relative to the
+    /// output root.
+    pub fn input_digests(&self) -> ChunkInputDigestsVc {
+        input_digests(self.chunking_context, self.assets())
+    }
+
+    /// A digest of the whole group, which changes whenever one of its chunks
+    /// or one of its [external inputs](ChunkGroup::external_inputs) changes.
+    pub fn input_digest(&self) -> StringVc {
+        input_digest(self.chunking_context, self.assets())
+    }
+
+    /// Computes the assets of the group and generates their content in a
+    /// background task, without waiting for it.
+    ///
+    /// Prewarming runs at low priority: only as many groups of a chunking
+    /// context as its [prewarm limit](ChunkingContext::prewarm_limit) allows
+    /// are prewarmed at a time, one by default, and the assets are generated
+    /// with [ChunkGroup::generate], so they count towards the chunk
+    /// generation limit. The assets are read
+    /// without strong consistency, so prewarming doesn't wait for pending
+    /// invalidations. Errors and issues are discarded, as they are reported
+    /// when the assets are requested.
+    pub fn prewarm(self, turbo_tasks: &Arc<dyn TurboTasksApi>) {
+        turbo_tasks.run_once(Box::pin(async move {
+            let _ = self.generate_in_background().await;
+            Ok(())
+        }));
+    }
+
+    /// Generates the content of all assets of the group, as many at a time as
+    /// the [chunk generation limit](ChunkingContext::chunk_generation_limit)
+    /// of the chunking context allows. The asset of the entry chunk is
+    /// generated first.
+    pub async fn generate(&self) -> Result<()> {
+        let limit = self.chunking_context.chunk_generation_limit().await?;
+        let entry = self
+            .chunking_context
+            .chunk_asset(self.entry)
+            .resolve()
+            .await?;
+        self.assets()
+            .await?
+            .iter()
+            .map(|&asset| {
+                let limit = &limit;
+                async move {
+                    let asset = asset.resolve().await?;
+                    let priority = if asset == entry {
+                        ChunkGenerationPriority::Entry
+                    } else {
+                        ChunkGenerationPriority::Other
+                    };
+                    let _permit = limit.acquire(priority).await?;
+                    if let AssetContent::File(file) = &*asset.content().await? {
+                        file.await?;
+                    }
+                    Ok(())
+                }
+            })
+            .try_join()
+            .await?;
+        Ok(())
+    }
+
+    async fn generate_in_background(self) -> Result<()> {
+        let limit = self.chunking_context.prewarm_limit().await?;
+        let _permit = limit.acquire(ChunkGenerationPriority::Other).await?;
+        // Lets the work that triggered the prewarming go first.
+        tokio::task::yield_now().await;
+        self.generate().await
+    }
+}
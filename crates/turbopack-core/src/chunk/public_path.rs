@@ -0,0 +1,96 @@
+//! Public URLs of emitted files.
+//!
+//! Without configuration, emitted files are referenced by their path on the
+//! server, e.g. `/static/logo.png`. When the output is served from a CDN or
+//! below an asset prefix, the chunking context has a [PublicPath] instead,
+//! which maps the path of a file relative to the output root to its public
+//! URL. Consumers ask the chunking context for the URL, see
+//! [OptionPublicPathVc::url], instead of prepending prefixes themselves.
+
+use anyhow::Result;
+use turbo_tasks::primitives::OptionStringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+/// Where the output root of a chunking context is served from, see the
+/// [module documentation](self).
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum PublicPath {
+    /// A prefix that is known at build time, e.g. `/_next/` or
+    /// `https://cdn.example.com/assets/`.
+    Prefix(String),
+    /// A prefix that is only known at runtime. URLs start with `placeholder`,
+    /// e.g. `__TURBOPACK_PUBLIC_PATH__`, which the embedder replaces with the
+    /// actual prefix when serving the files.
+    Runtime { placeholder: String },
+}
+
+impl PublicPath {
+    /// The URL of the file at `path`, relative to the output root.
+    pub fn url(&self, path: &str) -> String {
+        let prefix = match self {
+            PublicPath::Prefix(prefix) => prefix,
+            PublicPath::Runtime { placeholder } => placeholder,
+        };
+        if prefix.is_empty() || prefix.ends_with('/') {
+            format!("{}{}", prefix, path)
+        } else {
+            format!("{}/{}", prefix, path)
+        }
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionPublicPath(Option<PublicPathVc>);
+
+#[turbo_tasks::value_impl]
+impl OptionPublicPathVc {
+    #[turbo_tasks::function]
+    pub fn none() -> Self {
+        OptionPublicPathVc::cell(None)
+    }
+
+    /// The public URL of the file at `path`, which is emitted below
+    /// `output_root`. `None` without a public path, or if `path` is outside
+    /// of the output root.
+    #[turbo_tasks::function]
+    pub async fn url(
+        self,
+        output_root: FileSystemPathVc,
+        path: FileSystemPathVc,
+    ) -> Result<OptionStringVc> {
+        let Some(public_path) = *self.await? else {
+            return Ok(OptionStringVc::cell(None));
+        };
+        let output_root = output_root.await?;
+        let path = path.await?;
+        Ok(OptionStringVc::cell(match output_root.get_path_to(&path) {
+            Some(path) => Some(public_path.await?.url(path)),
+            None => None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PublicPath;
+
+    #[test]
+    fn joins_prefix_and_path() {
+        assert_eq!(
+            PublicPath::Prefix("/_next/".to_string()).url("static/logo.png"),
+            "/_next/static/logo.png"
+        );
+        assert_eq!(
+            PublicPath::Prefix("https://cdn.example.com/assets".to_string()).url("index.js"),
+            "https://cdn.example.com/assets/index.js"
+        );
+        assert_eq!(
+            PublicPath::Runtime {
+                placeholder: "__TURBOPACK_PUBLIC_PATH__".to_string()
+            }
+            .url("index.js"),
+            "__TURBOPACK_PUBLIC_PATH__/index.js"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! The protocol between chunks and the runtime's module registry.
+//!
+//! Chunks don't execute their modules when they are loaded. They register the
+//! factories of their modules with the registry of the runtime, report that
+//! they finished loading, and evaluated chunks tell the runtime which modules
+//! to execute in which order once the chunks they depend on are loaded. The
+//! runtimes for the browser, Node.js and edge are generated by different
+//! crates, so the messages are defined here and carry a
+//! [RUNTIME_REGISTRY_VERSION]. A runtime rejects messages of another version
+//! instead of misinterpreting them.
+//!
+//! The messages are serialized as JSON:
+//!
+//! ```json
+//! { "version": 1, "type": "chunkLoaded", "chunkPath": "chunks/index.js" }
+//! ```
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::ModuleId;
+
+/// The version of the protocol. It must be increased for every change that
+/// an older runtime can't interpret, e.g. a new message or a renamed field.
+pub const RUNTIME_REGISTRY_VERSION: u32 = 1;
+
+/// A chunk that must be loaded before modules can be executed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RegistryChunk {
+    /// A chunk that only needs to be loaded.
+    Path(String),
+    /// A chunk with information about the modules it contains.
+    #[serde(rename_all = "camelCase")]
+    WithModules {
+        path: String,
+        /// The modules of the chunk, if not all of them are in `module_chunks`.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        included: Vec<ModuleId>,
+        /// Modules that are referenced, but are available in another chunk.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        excluded: Vec<ModuleId>,
+        /// Chunks that contain one module each and can be loaded instead of
+        /// the whole chunk.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        module_chunks: Vec<String>,
+    },
+}
+
+/// A message that is sent to the module registry of a runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RegistryMessage {
+    /// A chunk registers the factories of the modules it contains. Registering
+    /// a factory doesn't execute it.
+    #[serde(rename_all = "camelCase")]
+    RegisterModuleFactories {
+        chunk_path: String,
+        module_ids: Vec<ModuleId>,
+    },
+    /// A chunk finished loading and all of its factories are registered.
+    #[serde(rename_all = "camelCase")]
+    ChunkLoaded { chunk_path: String },
+    /// An evaluated chunk requests to execute `module_ids` in order, after all
+    /// of `other_chunks` are loaded.
+    #[serde(rename_all = "camelCase")]
+    ExecuteModules {
+        chunk_path: String,
+        other_chunks: Vec<RegistryChunk>,
+        module_ids: Vec<ModuleId>,
+    },
+}
+
+/// A [RegistryMessage] together with the version of the protocol it was
+/// created with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEnvelope {
+    pub version: u32,
+    #[serde(flatten)]
+    pub message: RegistryMessage,
+}
+
+impl RegistryEnvelope {
+    /// Wraps `message` with the current [RUNTIME_REGISTRY_VERSION].
+    pub fn new(message: RegistryMessage) -> Self {
+        Self {
+            version: RUNTIME_REGISTRY_VERSION,
+            message,
+        }
+    }
+
+    /// Parses a message and fails if it was created with another version of
+    /// the protocol.
+    pub fn from_json(json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        // Check the version first, so that messages of other versions fail
+        // with a version mismatch rather than an unknown message type.
+        let Version { version } = serde_json::from_str(json)?;
+        if version != RUNTIME_REGISTRY_VERSION {
+            bail!(
+                "runtime registry protocol version {} is not supported, expected version {}",
+                version,
+                RUNTIME_REGISTRY_VERSION
+            );
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_messages() {
+        let message = RegistryEnvelope::new(RegistryMessage::ExecuteModules {
+            chunk_path: "chunks/index.js".to_string(),
+            other_chunks: vec![
+                RegistryChunk::Path("chunks/shared.js".to_string()),
+                RegistryChunk::WithModules {
+                    path: "chunks/lib.js".to_string(),
+                    included: vec![ModuleId::Number(1)],
+                    excluded: vec![],
+                    module_chunks: vec![],
+                },
+            ],
+            module_ids: vec![
+                ModuleId::Number(1),
+                ModuleId::String("./index.js".to_string()),
+            ],
+        });
+        let json = message.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"type":"executeModules","chunkPath":"chunks/index.js","otherChunks":["chunks/shared.js",{"path":"chunks/lib.js","included":[1]}],"moduleIds":[1,"./index.js"]}"#
+        );
+        assert_eq!(RegistryEnvelope::from_json(&json).unwrap(), message);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let json = r#"{"version":2,"type":"unloadChunk","chunkPath":"chunks/index.js"}"#;
+        let error = RegistryEnvelope::from_json(json).unwrap_err();
+        assert!(error.to_string().contains("version 2 is not supported"));
+
+        let json = r#"{"version":1,"type":"chunkLoaded","chunkPath":"chunks/index.js"}"#;
+        assert_eq!(
+            RegistryEnvelope::from_json(json).unwrap().message,
+            RegistryMessage::ChunkLoaded {
+                chunk_path: "chunks/index.js".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Chunking of apps with several entries, e.g. the pages of a multi-page app.
+//!
+//! Chunking each entry on its own places the modules that several entries
+//! use, e.g. vendor code, into the chunks of each entry. [SharedChunkPlanVc]
+//! first counts how many entries use each module, see [SharedAssetStats],
+//! places the modules that enough entries share into shared chunks, and then
+//! chunks each entry with the shared modules as available. Each entry only
+//! loads the shared chunks that contain modules it uses, and the shared chunks
+//! that those depend on.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use turbo_tasks::{TryJoinIterExt, Value};
+
+use super::{
+    availability_info::AvailabilityInfo,
+    available_assets::{chunkable_assets_set, AvailableAssetsVc},
+    ChunkVc, ChunkableAssetVc, ChunkingContextVc,
+};
+use crate::asset::{AssetVc, AssetsSetReadRef, AssetsVc};
+
+/// How many entries use each asset that is reachable from entries through
+/// chunkable references.
+#[turbo_tasks::value]
+pub struct SharedAssetStats {
+    /// The number of entries that use each asset. Assets that are used by an
+    /// entry come before the assets that they reference, unless there are
+    /// cycles.
+    pub entry_counts: IndexMap<AssetVc, usize>,
+}
+
+#[turbo_tasks::value_impl]
+impl SharedAssetStatsVc {
+    #[turbo_tasks::function]
+    pub async fn new(entries: AssetsVc) -> Result<Self> {
+        let sets = entries
+            .await?
+            .iter()
+            .map(|entry| chunkable_assets_set(*entry))
+            .try_join()
+            .await?;
+        let mut entry_counts = IndexMap::new();
+        for set in sets.iter() {
+            // The sets are in reverse topological order.
+            for asset in set.iter().rev() {
+                *entry_counts.entry(*asset).or_insert(0) += 1;
+            }
+        }
+        Ok(SharedAssetStats { entry_counts }.cell())
+    }
+
+    /// The assets that at least `min_entries` entries use.
+    #[turbo_tasks::function]
+    pub async fn shared_assets(self, min_entries: usize) -> Result<AssetsVc> {
+        Ok(AssetsVc::cell(
+            self.await?
+                .entry_counts
+                .iter()
+                .filter(|(_, count)| **count >= min_entries)
+                .map(|(asset, _)| *asset)
+                .collect(),
+        ))
+    }
+}
+
+/// The chunks of the entries of an app, see the [module
+/// documentation](self).
+#[turbo_tasks::value]
+pub struct SharedChunkPlan {
+    chunking_context: ChunkingContextVc,
+    /// The chunks of the shared assets. A shared chunk leaves out the assets
+    /// of earlier shared chunks that it overlaps with.
+    pub shared_chunks: Vec<ChunkVc>,
+    /// The chunk of each entry, without the shared assets.
+    pub entry_chunks: IndexMap<AssetVc, ChunkVc>,
+    /// The shared chunks that each entry loads before its chunk, in the order
+    /// of `shared_chunks`.
+    pub entry_shared_chunks: IndexMap<AssetVc, Vec<ChunkVc>>,
+}
+
+#[turbo_tasks::value_impl]
+impl SharedChunkPlanVc {
+    /// Plans the chunks of `entries`. Assets that at least `min_entries`
+    /// entries use are placed into shared chunks. With a `min_entries` of
+    /// more than the number of entries, each entry is chunked on its own.
+    #[turbo_tasks::function]
+    pub async fn new(
+        chunking_context: ChunkingContextVc,
+        entries: AssetsVc,
+        min_entries: usize,
+    ) -> Result<Self> {
+        let shared_assets = SharedAssetStatsVc::new(entries)
+            .shared_assets(min_entries.max(1))
+            .await?;
+
+        // Assets that are reachable from a shared asset are shared as well, so
+        // the shared assets are covered by the subgraphs of a few roots.
+        let mut covered = IndexSet::new();
+        let mut roots = Vec::new();
+        let mut root_sets = Vec::new();
+        for asset in shared_assets.iter() {
+            if covered.contains(asset) {
+                continue;
+            }
+            if ChunkableAssetVc::resolve_from(*asset).await?.is_none() {
+                continue;
+            }
+            let set = chunkable_assets_set(*asset).await?;
+            covered.extend(set.iter().copied());
+            roots.push(*asset);
+            root_sets.push(set);
+        }
+
+        // The chunk of a root leaves out the assets of the earlier roots whose
+        // subgraphs overlap with its own, so those have to be loaded with it.
+        let mut root_dependencies: Vec<BTreeSet<usize>> = Vec::new();
+        for (index, set) in root_sets.iter().enumerate() {
+            let mut dependencies = BTreeSet::new();
+            for earlier in overlapping_roots(&root_sets[..index], set) {
+                dependencies.extend(root_dependencies[earlier].iter().copied());
+                dependencies.insert(earlier);
+            }
+            root_dependencies.push(dependencies);
+        }
+
+        let mut shared_chunks = Vec::new();
+        for (root, dependencies) in roots.iter().zip(root_dependencies.iter()) {
+            let chunkable = ChunkableAssetVc::resolve_from(*root)
+                .await?
+                .expect("roots are chunkable");
+            let availability_info = chunk_availability(&roots, dependencies, *root);
+            shared_chunks.push(chunkable.as_chunk(chunking_context, Value::new(availability_info)));
+        }
+
+        let mut entry_chunks = IndexMap::new();
+        let mut entry_shared_chunks = IndexMap::new();
+        for entry in entries.await?.iter() {
+            let entry = entry.resolve().await?;
+            let Some(chunkable) = ChunkableAssetVc::resolve_from(entry).await? else {
+                continue;
+            };
+            let set = chunkable_assets_set(entry).await?;
+            let mut loaded = BTreeSet::new();
+            for used in overlapping_roots(&root_sets, &set) {
+                loaded.extend(root_dependencies[used].iter().copied());
+                loaded.insert(used);
+            }
+            let availability_info = chunk_availability(&roots, &loaded, entry);
+            entry_chunks.insert(
+                entry,
+                chunkable.as_chunk(chunking_context, Value::new(availability_info)),
+            );
+            entry_shared_chunks.insert(
+                entry,
+                loaded.iter().map(|index| shared_chunks[*index]).collect(),
+            );
+        }
+
+        Ok(SharedChunkPlan {
+            chunking_context,
+            shared_chunks,
+            entry_chunks,
+            entry_shared_chunks,
+        }
+        .cell())
+    }
+
+    /// The assets that the page of `entry` loads: the chunk groups of the
+    /// shared chunks it needs and the chunk group of the entry. Empty if
+    /// `entry` isn't one of the planned entries.
+    #[turbo_tasks::function]
+    pub async fn entry_assets(self, entry: AssetVc) -> Result<AssetsVc> {
+        let this = self.await?;
+        let entry = entry.resolve().await?;
+        let Some(entry_chunk) = this.entry_chunks.get(&entry) else {
+            return Ok(AssetsVc::cell(Vec::new()));
+        };
+        let groups = this.entry_shared_chunks[&entry]
+            .iter()
+            .chain(std::iter::once(entry_chunk))
+            .map(|chunk| this.chunking_context.chunk_group(*chunk))
+            .try_join()
+            .await?;
+        let assets = groups
+            .iter()
+            .flat_map(|group| group.iter().copied())
+            .collect::<IndexSet<_>>();
+        Ok(AssetsVc::cell(assets.into_iter().collect()))
+    }
+}
+
+/// The indices of the `roots` whose subgraphs contain an asset of `set`.
+fn overlapping_roots<'a>(
+    roots: &'a [AssetsSetReadRef],
+    set: &'a AssetsSetReadRef,
+) -> impl Iterator<Item = usize> + 'a {
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| root.iter().any(|asset| set.contains(asset)))
+        .map(|(index, _)| index)
+}
+
+/// Chunks `root` with the assets of the `available` roots as available.
+fn chunk_availability(
+    roots: &[AssetVc],
+    available: &BTreeSet<usize>,
+    root: AssetVc,
+) -> AvailabilityInfo {
+    if available.is_empty() {
+        return AvailabilityInfo::Root {
+            current_availability_root: root,
+        };
+    }
+    AvailabilityInfo::Inner {
+        available_assets: AvailableAssetsVc::new(
+            available.iter().map(|index| roots[*index]).collect(),
+        ),
+        current_availability_root: root,
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use indexmap::IndexSet;
+use turbo_tasks::TryJoinIterExt;
+
+use super::{Chunk, ChunkGroupReferenceVc, ChunkVc};
+use crate::asset::{Asset, AssetVc, AssetsVc};
+
+/// The stylesheets of a chunk group, split by how a page loads them. Pages
+/// link the blocking stylesheets, which the chunk group needs before it
+/// renders, and can preload the lazy ones, which are loaded on demand by the
+/// lazy chunk groups that the chunks of the chunk group reference.
+#[turbo_tasks::value]
+pub struct ChunkGroupStylesheets {
+    pub blocking: Vec<AssetVc>,
+    pub lazy: Vec<AssetVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkGroupStylesheetsVc {
+    /// Sorts the stylesheets of `chunk_group`, the assets of a chunk group,
+    /// and of the lazy chunk groups that it references directly.
+    #[turbo_tasks::function]
+    pub async fn new(chunk_group: AssetsVc) -> Result<Self> {
+        let chunk_group = chunk_group.await?;
+        let blocking = stylesheets(&chunk_group).await?;
+
+        let mut lazy_chunk_groups = IndexSet::new();
+        for asset in chunk_group.iter() {
+            for reference in asset.references().await?.iter() {
+                let Some(reference) = ChunkGroupReferenceVc::resolve_from(*reference).await? else {
+                    continue;
+                };
+                if *reference.is_lazy().await? {
+                    lazy_chunk_groups.insert(reference);
+                }
+            }
+        }
+        let lazy_assets = lazy_chunk_groups
+            .iter()
+            .map(|reference| async move { Ok(reference.chunks().await?.clone_value()) })
+            .try_join()
+            .await?;
+        let lazy = stylesheets(&lazy_assets.into_iter().flatten().collect::<Vec<_>>())
+            .await?
+            .into_iter()
+            // Stylesheets that block rendering are loaded already.
+            .filter(|asset| !blocking.contains(asset))
+            .collect();
+
+        Ok(ChunkGroupStylesheets { blocking, lazy }.cell())
+    }
+}
+
+/// The chunks of `assets` that are stylesheets, without duplicates.
+async fn stylesheets(assets: &[AssetVc]) -> Result<Vec<AssetVc>> {
+    let mut stylesheets = IndexSet::new();
+    for asset in assets {
+        let Some(chunk) = ChunkVc::resolve_from(*asset).await? else {
+            continue;
+        };
+        if *chunk.is_stylesheet().await? {
+            stylesheets.insert(*asset);
+        }
+    }
+    Ok(stylesheets.into_iter().collect())
+}
@@ -27,6 +27,55 @@ pub struct Code {
     mappings: Vec<(usize, Option<GenerateSourceMapVc>)>,
 }
 
+/// The kinds of chunks that [CodeWrapper]s can be registered for.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ChunkCodeType {
+    /// Chunks containing ecmascript chunk items.
+    Ecmascript,
+    /// Chunks containing the runtime and evaluating the entries.
+    EcmascriptEvaluate,
+    Css,
+}
+
+impl ChunkCodeType {
+    fn is_css(&self) -> bool {
+        matches!(self, ChunkCodeType::Css)
+    }
+}
+
+/// Synthetic code that is added around the content of a chunk, e.g. a license
+/// banner or a runtime prologue.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum CodeWrapper {
+    /// Code placed before the content. It must be valid in the language of the
+    /// chunk, e.g. a comment.
+    Banner(String),
+    /// Code placed after the content.
+    Footer(String),
+    /// Wraps the content in an immediately invoked function expression, so it
+    /// doesn't leak variables into the global scope. Ignored for CSS chunks.
+    Iife,
+    /// Appends an empty export, so the chunk is parsed as an ES module and can
+    /// be loaded with `import()`. Ignored for CSS chunks.
+    EsmExport,
+    /// Appends a `sourceURL` comment naming the chunk, so it's listed under its
+    /// file name in devtools when it's evaluated from a string.
+    SourceUrl,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct CodeWrappers(Vec<CodeWrapper>);
+
+#[turbo_tasks::value_impl]
+impl CodeWrappersVc {
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        CodeWrappersVc::cell(Vec::new())
+    }
+}
+
 /// CodeBuilder provides a mutable container to append source code.
 #[derive(Default)]
 pub struct CodeBuilder {
@@ -89,6 +138,49 @@ impl CodeBuilder {
         self.code += &prebuilt.code;
     }
 
+    /// Pushes `content` surrounded by `wrappers`, in registration order: the
+    /// first wrapper ends up outermost. The wrappers are pushed as synthetic
+    /// code, so the mappings of `content` stay valid.
+    ///
+    /// The `sourceMappingURL` comment should be pushed after this, so it stays
+    /// at the end of the chunk.
+    pub fn push_wrapped(
+        &mut self,
+        content: &Code,
+        wrappers: &[CodeWrapper],
+        chunk_type: ChunkCodeType,
+        file_name: &str,
+    ) -> Result<()> {
+        for wrapper in wrappers {
+            match wrapper {
+                CodeWrapper::Banner(banner) => writeln!(self, "{banner}")?,
+                CodeWrapper::Iife if !chunk_type.is_css() => writeln!(self, "(() => {{")?,
+                _ => {}
+            }
+        }
+
+        self.push_code(content);
+
+        for wrapper in wrappers.iter().rev() {
+            match wrapper {
+                CodeWrapper::Footer(footer) => write!(self, "\n{footer}")?,
+                CodeWrapper::Iife if !chunk_type.is_css() => write!(self, "\n}})();")?,
+                CodeWrapper::EsmExport if !chunk_type.is_css() => write!(self, "\nexport {{}};")?,
+                _ => {}
+            }
+        }
+
+        // sourceURL comments must be at the end of the code.
+        if wrappers.contains(&CodeWrapper::SourceUrl) {
+            if chunk_type.is_css() {
+                write!(self, "\n/*# sourceURL={file_name} */")?;
+            } else {
+                write!(self, "\n//# sourceURL={file_name}")?;
+            }
+        }
+        Ok(())
+    }
+
     /// Setting breakpoints on synthetic code can cause weird behaviors
     /// because Chrome will treat the location as belonging to the previous
     /// original code section. By inserting an empty source map when reaching a
@@ -0,0 +1,188 @@
+//! Defines whose values are not known when the [CompileTimeInfo] is created,
+//! e.g. feature flags that are fetched from a file or an external service.
+//!
+//! [JsonFileDefines] and [EnvDefines] are invalidated by turbo-tasks when the
+//! file or the environment changes. [ExternalDefines] fetch their values
+//! through a [DefinesFetcher] and are only fetched again when the embedder
+//! calls [ExternalDefines::refresh].
+//!
+//! [CompileTimeInfo]: super::CompileTimeInfo
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+use turbo_tasks::{get_invalidator, Invalidator};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{FileJsonContent, FileSystemPathVc};
+
+use super::{CompileTimeDefineValue, CompileTimeDefinesVc};
+
+/// A provider of defines that are computed while building.
+#[turbo_tasks::value_trait]
+pub trait DynamicDefines {
+    fn defines(&self) -> CompileTimeDefinesVc;
+}
+
+/// Defines read from a JSON file. Nested objects define nested names, e.g.
+/// `{ "process": { "env": { "FLAG": true } } }` defines `process.env.FLAG`.
+/// Values must be booleans or strings.
+#[turbo_tasks::value]
+pub struct JsonFileDefines {
+    path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl JsonFileDefinesVc {
+    #[turbo_tasks::function]
+    pub fn new(path: FileSystemPathVc) -> Self {
+        JsonFileDefines { path }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl DynamicDefines for JsonFileDefines {
+    #[turbo_tasks::function]
+    async fn defines(&self) -> Result<CompileTimeDefinesVc> {
+        let mut defines = HashMap::new();
+        match &*self.path.read_json().await? {
+            FileJsonContent::Content(json) => {
+                collect_json_defines(&mut Vec::new(), json, &mut defines)?
+            }
+            FileJsonContent::Unparseable(e) => {
+                bail!("unable to parse {}: {}", self.path.to_string().await?, e)
+            }
+            FileJsonContent::NotFound => {}
+        }
+        Ok(CompileTimeDefinesVc::cell(defines))
+    }
+}
+
+fn collect_json_defines(
+    name: &mut Vec<String>,
+    value: &JsonValue,
+    defines: &mut HashMap<Vec<String>, CompileTimeDefineValue>,
+) -> Result<()> {
+    match value {
+        JsonValue::Object(object) => {
+            for (key, value) in object {
+                name.push(key.clone());
+                collect_json_defines(name, value, defines)?;
+                name.pop();
+            }
+        }
+        JsonValue::Bool(value) => {
+            defines.insert(name.clone(), (*value).into());
+        }
+        JsonValue::String(value) => {
+            defines.insert(name.clone(), value.as_str().into());
+        }
+        _ => bail!(
+            "the define {} must be a boolean or a string, found {}",
+            name.join("."),
+            value
+        ),
+    }
+    Ok(())
+}
+
+/// Defines `process.env.<NAME>` for every environment variable that starts
+/// with `prefix`. The values `true` and `false` are defined as booleans.
+#[turbo_tasks::value]
+pub struct EnvDefines {
+    env: ProcessEnvVc,
+    prefix: String,
+}
+
+#[turbo_tasks::value_impl]
+impl EnvDefinesVc {
+    #[turbo_tasks::function]
+    pub fn new(env: ProcessEnvVc, prefix: &str) -> Self {
+        EnvDefines {
+            env,
+            prefix: prefix.to_string(),
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl DynamicDefines for EnvDefines {
+    #[turbo_tasks::function]
+    async fn defines(&self) -> Result<CompileTimeDefinesVc> {
+        let env = self.env.read_all().await?;
+        let defines = env
+            .iter()
+            .filter(|(name, _)| name.starts_with(&self.prefix))
+            .map(|(name, value)| {
+                let value = match value.as_str() {
+                    "true" => true.into(),
+                    "false" => false.into(),
+                    value => value.into(),
+                };
+                (
+                    vec!["process".to_string(), "env".to_string(), name.clone()],
+                    value,
+                )
+            })
+            .collect();
+        Ok(CompileTimeDefinesVc::cell(defines))
+    }
+}
+
+/// Fetches define values from a source outside of turbo-tasks, e.g. a feature
+/// flag service.
+#[async_trait]
+pub trait DefinesFetcher: Send + Sync {
+    async fn fetch(&self) -> Result<HashMap<Vec<String>, CompileTimeDefineValue>>;
+}
+
+/// Defines that are fetched through a [DefinesFetcher]. The values are cached
+/// until [ExternalDefines::refresh] is called, so a build never sees flags
+/// changing between modules.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
+#[derive(Clone)]
+pub struct ExternalDefines {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    fetcher: Arc<dyn DefinesFetcher>,
+    /// Invalidates the tasks that fetched the current values.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    invalidators: Arc<Mutex<Vec<Invalidator>>>,
+}
+
+impl ExternalDefines {
+    pub fn new(fetcher: Arc<dyn DefinesFetcher>) -> Self {
+        Self {
+            fetcher,
+            invalidators: Default::default(),
+        }
+    }
+
+    /// Fetches the values again on the next read. Everything that was built
+    /// with the previous values is invalidated.
+    pub fn refresh(&self) {
+        let invalidators = std::mem::take(&mut *self.invalidators.lock().unwrap());
+        for invalidator in invalidators {
+            invalidator.invalidate();
+        }
+    }
+}
+
+impl ExternalDefinesVc {
+    pub fn new(defines: ExternalDefines) -> Self {
+        Self::cell(defines)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl DynamicDefines for ExternalDefines {
+    #[turbo_tasks::function]
+    async fn defines(&self) -> Result<CompileTimeDefinesVc> {
+        self.invalidators.lock().unwrap().push(get_invalidator());
+        Ok(CompileTimeDefinesVc::cell(self.fetcher.fetch().await?))
+    }
+}
@@ -5,6 +5,10 @@ use turbo_tasks_fs::FileSystemPathVc;
 
 use crate::environment::EnvironmentVc;
 
+pub mod dynamic_defines;
+
+pub use dynamic_defines::{DynamicDefines, DynamicDefinesVc};
+
 // TODO stringify split map collect could be optimized with a marco
 #[macro_export]
 macro_rules! definable_name_map_internal {
@@ -162,11 +166,55 @@ impl FreeVarReferencesVc {
     }
 }
 
+/// How strictly the interop between EcmaScript modules and CommonJS is
+/// enforced. Format crates consult it when analysing references and emit an
+/// [InteropPolicyIssue] for every violation.
+///
+/// [InteropPolicyIssue]: crate::issue::interop_policy::InteropPolicyIssue
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Default, Copy, Clone, Hash, PartialOrd, Ord)]
+pub enum InteropPolicy {
+    /// CommonJS and EcmaScript modules can be mixed freely, like in webpack.
+    #[default]
+    WebpackLike,
+    /// Follows Node.js: CommonJS is allowed, but EcmaScript modules can't be
+    /// loaded with `require()`.
+    NodeCompatible,
+    /// Only EcmaScript modules are allowed, e.g. for edge runtimes that don't
+    /// support CommonJS.
+    StrictEsm,
+}
+
+impl InteropPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InteropPolicy::WebpackLike => "webpack-like",
+            InteropPolicy::NodeCompatible => "node-compatible",
+            InteropPolicy::StrictEsm => "strict-esm",
+        }
+    }
+
+    /// Whether modules can use CommonJS syntax, e.g. `require()` or
+    /// `module.exports`.
+    pub fn allows_commonjs(&self) -> bool {
+        !matches!(self, InteropPolicy::StrictEsm)
+    }
+
+    /// Whether EcmaScript modules can be loaded with `require()`.
+    pub fn allows_require_of_esm(&self) -> bool {
+        matches!(self, InteropPolicy::WebpackLike)
+    }
+}
+
 #[turbo_tasks::value(shared)]
 pub struct CompileTimeInfo {
     pub environment: EnvironmentVc,
     pub defines: CompileTimeDefinesVc,
     pub free_var_references: FreeVarReferencesVc,
+    /// Providers of additional defines, which are applied in order on top of
+    /// `defines`.
+    pub dynamic_defines: Vec<DynamicDefinesVc>,
+    pub interop_policy: InteropPolicy,
 }
 
 impl CompileTimeInfo {
@@ -175,6 +223,8 @@ impl CompileTimeInfo {
             environment,
             defines: None,
             free_var_references: None,
+            dynamic_defines: Vec::new(),
+            interop_policy: InteropPolicy::default(),
         }
     }
 }
@@ -187,6 +237,8 @@ impl CompileTimeInfoVc {
             environment,
             defines: CompileTimeDefinesVc::empty(),
             free_var_references: FreeVarReferencesVc::empty(),
+            dynamic_defines: Vec::new(),
+            interop_policy: InteropPolicy::default(),
         }
         .cell()
     }
@@ -195,12 +247,51 @@ impl CompileTimeInfoVc {
     pub async fn environment(self) -> Result<EnvironmentVc> {
         Ok(self.await?.environment)
     }
+
+    /// The same compile time info for another environment, e.g. for the
+    /// legacy build of a [DualChunkGroup].
+    ///
+    /// [DualChunkGroup]: crate::chunk::dual_output::DualChunkGroup
+    #[turbo_tasks::function]
+    pub async fn with_environment(self, environment: EnvironmentVc) -> Result<Self> {
+        let this = self.await?;
+        Ok(CompileTimeInfo {
+            environment,
+            defines: this.defines,
+            free_var_references: this.free_var_references,
+            dynamic_defines: this.dynamic_defines.clone(),
+            interop_policy: this.interop_policy,
+        }
+        .cell())
+    }
+
+    #[turbo_tasks::function]
+    pub async fn interop_policy(self) -> Result<InteropPolicyVc> {
+        Ok(self.await?.interop_policy.cell())
+    }
+
+    /// The static defines merged with the values of all dynamic defines
+    /// providers. Later providers override earlier ones.
+    #[turbo_tasks::function]
+    pub async fn defines(self) -> Result<CompileTimeDefinesVc> {
+        let this = self.await?;
+        if this.dynamic_defines.is_empty() {
+            return Ok(this.defines);
+        }
+        let mut defines = this.defines.await?.clone_value();
+        for provider in &this.dynamic_defines {
+            defines.extend(provider.defines().await?.clone_value());
+        }
+        Ok(CompileTimeDefinesVc::cell(defines))
+    }
 }
 
 pub struct CompileTimeInfoBuilder {
     environment: EnvironmentVc,
     defines: Option<CompileTimeDefinesVc>,
     free_var_references: Option<FreeVarReferencesVc>,
+    dynamic_defines: Vec<DynamicDefinesVc>,
+    interop_policy: InteropPolicy,
 }
 
 impl CompileTimeInfoBuilder {
@@ -214,6 +305,18 @@ impl CompileTimeInfoBuilder {
         self
     }
 
+    /// Adds a provider of defines whose values are fetched while building,
+    /// e.g. from a feature flag service.
+    pub fn dynamic_defines(mut self, dynamic_defines: DynamicDefinesVc) -> Self {
+        self.dynamic_defines.push(dynamic_defines);
+        self
+    }
+
+    pub fn interop_policy(mut self, interop_policy: InteropPolicy) -> Self {
+        self.interop_policy = interop_policy;
+        self
+    }
+
     pub fn build(self) -> CompileTimeInfo {
         CompileTimeInfo {
             environment: self.environment,
@@ -221,6 +324,8 @@ impl CompileTimeInfoBuilder {
             free_var_references: self
                 .free_var_references
                 .unwrap_or_else(FreeVarReferencesVc::empty),
+            dynamic_defines: self.dynamic_defines,
+            interop_policy: self.interop_policy,
         }
     }
 
@@ -0,0 +1,198 @@
+//! Support for reproducible builds, where building the same sources on
+//! another machine or at another time produces byte-identical output.
+//!
+//! Generated code refers to files by their path relative to a virtual root
+//! instead of the directory the project was built in, see [VIRTUAL_ROOT].
+//! [rewrite_absolute_paths] replaces absolute paths of the project directory
+//! that still end up in emitted assets, e.g. from transforms or source maps.
+//! [changed_assets] compares the hashes of two builds of the same sources to
+//! detect assets that differ anyway, which are reported with a
+//! [NondeterministicAssetIssue].
+
+use std::{borrow::Cow, collections::HashMap};
+
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+use crate::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// The path that replaces the project directory in emitted assets.
+pub const VIRTUAL_ROOT: &str = "/ROOT";
+
+/// Replaces all occurrences of the absolute path `root` in `content` with
+/// [VIRTUAL_ROOT]. Paths in string literals, where backslashes are escaped,
+/// are replaced as well. Returns the content unchanged if it doesn't contain
+/// `root`.
+pub fn rewrite_absolute_paths<'a>(content: &'a [u8], root: &str) -> Cow<'a, [u8]> {
+    let root = root.trim_end_matches(['/', '\\']);
+    if root.is_empty() {
+        return Cow::Borrowed(content);
+    }
+    let mut variants = vec![root.to_string()];
+    if root.contains('\\') {
+        variants.push(root.replace('\\', "\\\\"));
+        variants.push(root.replace('\\', "/"));
+    }
+
+    let mut content = Cow::Borrowed(content);
+    for variant in variants {
+        if let Some(rewritten) = replace_path(&content, variant.as_bytes()) {
+            content = Cow::Owned(rewritten);
+        }
+    }
+    content
+}
+
+/// Replaces occurrences of `path` that aren't followed by further characters
+/// of a file name, so that `/app` doesn't match in `/apple`.
+fn replace_path(content: &[u8], path: &[u8]) -> Option<Vec<u8>> {
+    let mut rewritten = Vec::new();
+    let mut copied = 0;
+    let mut start = 0;
+    while let Some(offset) = find(&content[start..], path) {
+        let index = start + offset;
+        let end = index + path.len();
+        start = end;
+        if content.get(end).map_or(false, |&c| {
+            c.is_ascii_alphanumeric() || b"-_.@".contains(&c)
+        }) {
+            continue;
+        }
+        rewritten.extend_from_slice(&content[copied..index]);
+        rewritten.extend_from_slice(VIRTUAL_ROOT.as_bytes());
+        copied = end;
+    }
+    if copied == 0 {
+        return None;
+    }
+    rewritten.extend_from_slice(&content[copied..]);
+    Some(rewritten)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The hash of the content of an emitted asset, for comparing builds.
+pub fn content_hash(content: &[u8]) -> u64 {
+    hash_xxh3_hash64(content)
+}
+
+/// Returns the paths of the assets whose content hash differs between two
+/// builds of the same sources, or which were only emitted by one of them, in
+/// order.
+pub fn changed_assets(
+    previous: &HashMap<String, u64>,
+    current: &HashMap<String, u64>,
+) -> Vec<String> {
+    let mut changed = previous
+        .iter()
+        .filter(|(path, hash)| current.get(*path) != Some(hash))
+        .map(|(path, _)| path.clone())
+        .chain(
+            current
+                .keys()
+                .filter(|path| !previous.contains_key(*path))
+                .cloned(),
+        )
+        .collect::<Vec<_>>();
+    changed.sort();
+    changed
+}
+
+/// An asset whose content differs between two builds of the same sources.
+#[turbo_tasks::value(shared)]
+pub struct NondeterministicAssetIssue {
+    pub path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for NondeterministicAssetIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("emit".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Asset is not deterministic".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "Building the same sources twice produced different content for this asset, so the \
+             build isn't reproducible. This is usually caused by code or transforms that depend \
+             on the current time, random values or the iteration order of unordered collections."
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(content: &str, root: &str) -> String {
+        String::from_utf8(rewrite_absolute_paths(content.as_bytes(), root).into_owned()).unwrap()
+    }
+
+    #[test]
+    fn rewrites_project_paths() {
+        assert_eq!(
+            rewrite(
+                r#"require("/home/me/app/node_modules/a"); "file:///home/me/app/src/b.js""#,
+                "/home/me/app/"
+            ),
+            r#"require("/ROOT/node_modules/a"); "file:///ROOT/src/b.js""#
+        );
+        assert_eq!(
+            rewrite("/home/me/apple/a.js /home/me/app", "/home/me/app"),
+            "/home/me/apple/a.js /ROOT"
+        );
+        assert_eq!(
+            rewrite(
+                r#"{"sources":["C:\\app\\src\\a.js"],"file":"C:/app/b.js"}"#,
+                r"C:\app"
+            ),
+            r#"{"sources":["/ROOT\\src\\a.js"],"file":"/ROOT/b.js"}"#
+        );
+        assert!(matches!(
+            rewrite_absolute_paths(b"unrelated", "/home/me/app"),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn compares_builds() {
+        let previous = HashMap::from([
+            ("a.js".to_string(), 1),
+            ("b.js".to_string(), 2),
+            ("c.js".to_string(), 3),
+        ]);
+        let current = HashMap::from([
+            ("a.js".to_string(), 1),
+            ("b.js".to_string(), 4),
+            ("d.js".to_string(), 5),
+        ]);
+        assert_eq!(
+            changed_assets(&previous, &current),
+            ["b.js", "c.js", "d.js"]
+        );
+        assert!(changed_assets(&previous, &previous).is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! The data shown by the error overlays of dev servers.
+//!
+//! An overlay shows the current issues, the status of hot module replacement
+//! and which chunk groups are loaded. [DevOverlayData] combines all three into
+//! one serializable payload, so overlay frontends depend on a single contract
+//! rather than on the internal types of issues, versions and chunks.
+//!
+//! A [DevOverlay] keeps the latest payload of a session. Every change bumps
+//! its revision and yields a [DevOverlayUpdate] that only contains what
+//! changed, which frontends apply with [DevOverlayData::apply]. A frontend that
+//! missed an update, i.e. whose revision isn't the `from_revision` of the
+//! update, requests the full payload again.
+
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use turbo_tasks::TraitRef;
+use turbo_tasks_hash::encode_hex;
+
+use crate::{
+    asset::Asset,
+    chunk::{ChunkGroup, ChunkingContext},
+    issue::{IssueSeverity, PlainIssue, PlainIssueReadRef},
+    source_pos::SourcePos,
+    version::{Update, Version, VersionVc},
+};
+
+/// Everything an overlay shows, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevOverlayData {
+    /// Increases with every change of the data.
+    pub revision: u64,
+    /// The current issues, the most severe first.
+    pub issues: Vec<OverlayIssue>,
+    pub hmr: HmrStatus,
+    /// The chunk groups that are loaded, sorted by their entry.
+    pub chunk_groups: Vec<ChunkGroupInfo>,
+}
+
+/// An issue in an overlay. Unlike [PlainIssue], the fields are stable across
+/// versions of turbopack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayIssue {
+    /// Identifies the issue across updates, see [PlainIssue::internal_hash].
+    pub key: String,
+    pub severity: IssueSeverity,
+    pub category: String,
+    pub context: String,
+    pub title: String,
+    pub description: String,
+    pub detail: String,
+    pub documentation_link: String,
+    pub source: Option<OverlayIssueSource>,
+}
+
+/// The code that an [OverlayIssue] is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayIssueSource {
+    /// The ident of the asset.
+    pub path: String,
+    pub start: SourcePos,
+    pub end: SourcePos,
+}
+
+impl OverlayIssue {
+    pub fn from_plain(issue: &PlainIssue) -> Self {
+        Self {
+            key: encode_hex(issue.internal_hash(false)),
+            severity: issue.severity,
+            category: issue.category.clone(),
+            context: issue.context.clone(),
+            title: issue.title.clone(),
+            description: issue.description.clone(),
+            detail: issue.detail.clone(),
+            documentation_link: issue.documentation_link.clone(),
+            source: issue.source.as_deref().map(|source| OverlayIssueSource {
+                path: source.asset.ident.clone(),
+                start: source.start,
+                end: source.end,
+            }),
+        }
+    }
+}
+
+/// The status of hot module replacement, derived from the latest [Update].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HmrStatus {
+    /// No update was computed yet.
+    #[default]
+    Pending,
+    /// The client has the latest version.
+    UpToDate,
+    /// The client can be updated in place to `version`.
+    Partial { version: String },
+    /// The client must reload to get `version`.
+    Restart { version: String },
+}
+
+impl HmrStatus {
+    pub async fn from_update(update: &Update) -> Result<Self> {
+        Ok(match update {
+            Update::None => HmrStatus::UpToDate,
+            Update::Partial(update) => HmrStatus::Partial {
+                version: version_id(&update.to).await?,
+            },
+            Update::Total(update) => HmrStatus::Restart {
+                version: version_id(&update.to).await?,
+            },
+        })
+    }
+}
+
+async fn version_id(version: &TraitRef<VersionVc>) -> Result<String> {
+    Ok(TraitRef::cell(version.clone()).id().await?.clone_value())
+}
+
+/// A chunk group that is loaded, with the paths of its chunks relative to the
+/// output root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkGroupInfo {
+    /// The ident of the entry chunk.
+    pub entry: String,
+    pub chunks: Vec<String>,
+}
+
+impl ChunkGroupInfo {
+    pub async fn from_chunk_group(chunk_group: ChunkGroup) -> Result<Self> {
+        let output_root = chunk_group.chunking_context().output_root().await?;
+        let mut chunks = Vec::new();
+        for asset in chunk_group.assets().await?.iter() {
+            if let Some(path) = output_root.get_path_to(&*asset.ident().path().await?) {
+                chunks.push(path.to_string());
+            }
+        }
+        Ok(Self {
+            entry: chunk_group.entry().ident().to_string().await?.clone_value(),
+            chunks,
+        })
+    }
+}
+
+/// The changes from one revision of [DevOverlayData] to the next. Fields that
+/// didn't change are omitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevOverlayUpdate {
+    pub from_revision: u64,
+    pub revision: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_issues: Vec<OverlayIssue>,
+    /// The keys of the issues that were resolved.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_issues: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmr: Option<HmrStatus>,
+    /// All chunk groups, if any of them changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_groups: Option<Vec<ChunkGroupInfo>>,
+}
+
+impl DevOverlayData {
+    /// Applies an update that was computed from this revision.
+    pub fn apply(&mut self, update: DevOverlayUpdate) -> Result<()> {
+        if update.from_revision != self.revision {
+            bail!(
+                "the update is from revision {}, but the data is at revision {}",
+                update.from_revision,
+                self.revision
+            );
+        }
+        self.issues
+            .retain(|issue| !update.removed_issues.contains(&issue.key));
+        self.issues.extend(update.added_issues);
+        sort_issues(&mut self.issues);
+        if let Some(hmr) = update.hmr {
+            self.hmr = hmr;
+        }
+        if let Some(chunk_groups) = update.chunk_groups {
+            self.chunk_groups = chunk_groups;
+        }
+        self.revision = update.revision;
+        Ok(())
+    }
+
+    /// The update from this data to `next`, or `None` if nothing changed.
+    fn diff(&self, next: &DevOverlayData) -> Option<DevOverlayUpdate> {
+        let previous = self
+            .issues
+            .iter()
+            .map(|issue| issue.key.as_str())
+            .collect::<BTreeSet<_>>();
+        let current = next
+            .issues
+            .iter()
+            .map(|issue| issue.key.as_str())
+            .collect::<BTreeSet<_>>();
+        let added_issues = next
+            .issues
+            .iter()
+            .filter(|issue| !previous.contains(issue.key.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        let removed_issues = self
+            .issues
+            .iter()
+            .filter(|issue| !current.contains(issue.key.as_str()))
+            .map(|issue| issue.key.clone())
+            .collect::<Vec<_>>();
+        let hmr = (self.hmr != next.hmr).then(|| next.hmr.clone());
+        let chunk_groups =
+            (self.chunk_groups != next.chunk_groups).then(|| next.chunk_groups.clone());
+        if added_issues.is_empty()
+            && removed_issues.is_empty()
+            && hmr.is_none()
+            && chunk_groups.is_none()
+        {
+            return None;
+        }
+        Some(DevOverlayUpdate {
+            from_revision: self.revision,
+            revision: next.revision,
+            added_issues,
+            removed_issues,
+            hmr,
+            chunk_groups,
+        })
+    }
+}
+
+/// Sorts issues by severity, the most severe first, and then by key, and
+/// drops duplicates.
+fn sort_issues(issues: &mut Vec<OverlayIssue>) {
+    issues.sort_by(|a, b| a.severity.cmp(&b.severity).then_with(|| a.key.cmp(&b.key)));
+    issues.dedup_by(|a, b| a.key == b.key);
+}
+
+/// The latest [DevOverlayData] of a dev server session.
+#[derive(Debug, Default)]
+pub struct DevOverlay {
+    data: DevOverlayData,
+}
+
+impl DevOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The full payload, for frontends that connect or that missed an update.
+    pub fn data(&self) -> &DevOverlayData {
+        &self.data
+    }
+
+    /// Replaces the issues. Returns the update for frontends, or `None` if
+    /// the issues didn't change.
+    pub fn set_issues(&mut self, issues: &[PlainIssueReadRef]) -> Option<DevOverlayUpdate> {
+        let mut issues = issues
+            .iter()
+            .map(|issue| OverlayIssue::from_plain(issue))
+            .collect();
+        sort_issues(&mut issues);
+        self.change(|data| data.issues = issues)
+    }
+
+    /// Sets the status of hot module replacement, see
+    /// [HmrStatus::from_update].
+    pub fn set_hmr(&mut self, hmr: HmrStatus) -> Option<DevOverlayUpdate> {
+        self.change(|data| data.hmr = hmr)
+    }
+
+    /// Replaces the chunk groups that are loaded.
+    pub fn set_chunk_groups(
+        &mut self,
+        mut chunk_groups: Vec<ChunkGroupInfo>,
+    ) -> Option<DevOverlayUpdate> {
+        chunk_groups.sort_by(|a, b| a.entry.cmp(&b.entry));
+        self.change(|data| data.chunk_groups = chunk_groups)
+    }
+
+    fn change(&mut self, f: impl FnOnce(&mut DevOverlayData)) -> Option<DevOverlayUpdate> {
+        let mut next = self.data.clone();
+        f(&mut next);
+        next.revision = self.data.revision + 1;
+        let update = self.data.diff(&next)?;
+        self.data = next;
+        Some(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkGroupInfo, DevOverlayData, DevOverlayUpdate, HmrStatus, OverlayIssue};
+    use crate::issue::IssueSeverity;
+
+    fn issue(key: &str, severity: IssueSeverity) -> OverlayIssue {
+        OverlayIssue {
+            key: key.to_string(),
+            severity,
+            category: "parse".to_string(),
+            context: "[project]/src/index.js".to_string(),
+            title: format!("issue {}", key),
+            description: String::new(),
+            detail: String::new(),
+            documentation_link: String::new(),
+            source: None,
+        }
+    }
+
+    fn data(revision: u64, issues: Vec<OverlayIssue>, hmr: HmrStatus) -> DevOverlayData {
+        DevOverlayData {
+            revision,
+            issues,
+            hmr,
+            chunk_groups: vec![ChunkGroupInfo {
+                entry: "[project]/src/index.js".to_string(),
+                chunks: vec!["index.js".to_string()],
+            }],
+        }
+    }
+
+    #[test]
+    fn diff_contains_only_changes() {
+        let previous = data(
+            1,
+            vec![
+                issue("a", IssueSeverity::Error),
+                issue("b", IssueSeverity::Warning),
+            ],
+            HmrStatus::UpToDate,
+        );
+        let next = data(
+            2,
+            vec![
+                issue("a", IssueSeverity::Error),
+                issue("c", IssueSeverity::Warning),
+            ],
+            HmrStatus::Partial {
+                version: "1234".to_string(),
+            },
+        );
+
+        let update = previous.diff(&next).unwrap();
+        assert_eq!(
+            update,
+            DevOverlayUpdate {
+                from_revision: 1,
+                revision: 2,
+                added_issues: vec![issue("c", IssueSeverity::Warning)],
+                removed_issues: vec!["b".to_string()],
+                hmr: Some(HmrStatus::Partial {
+                    version: "1234".to_string()
+                }),
+                chunk_groups: None,
+            }
+        );
+        assert_eq!(previous.diff(&previous), None);
+
+        let mut applied = previous.clone();
+        applied.apply(update.clone()).unwrap();
+        assert_eq!(applied, next);
+        assert!(applied.apply(update).is_err());
+    }
+
+    #[test]
+    fn serializes_hmr_status_with_type_tag() {
+        let json = serde_json::to_value(HmrStatus::Restart {
+            version: "1234".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "type": "restart", "version": "1234" })
+        );
+    }
+}
@@ -0,0 +1,713 @@
+//! Transactional writes of the output of a build iteration.
+//!
+//! Writing the assets of a build one by one leaves a half-written output
+//! directory behind when the build crashes, which subsequent incremental runs
+//! can't tell apart from a complete one. An [EmitTransaction] instead stages
+//! all writes in a separate directory and only moves them into place when the
+//! transaction is committed.
+//!
+//! Before anything is moved, the commit records which files it is going to
+//! replace in a journal and backs up the replaced files. The journal is
+//! written to a temporary file first and renamed into place, so it is either
+//! complete or missing. If a commit is interrupted, the next transaction for
+//! the same output directory restores the previous output from the journal
+//! before it begins.
+//!
+//! With [EmitTransaction::deduplicate], files whose content is identical to a
+//! file that was already staged are only written once. The other paths are
+//! recorded as aliases in the [ALIAS_MANIFEST] in the output directory. The
+//! manifest is merged with the one of previous commits, which may have
+//! written other entries, and files that previous commits wrote at paths
+//! that are now aliases are removed.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    fs::{self, File},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+const STAGING_DIRECTORY: &str = ".turbopack-staging";
+const BACKUP_DIRECTORY: &str = ".turbopack-backup";
+const JOURNAL_FILE: &str = ".turbopack-commit.json";
+const JOURNAL_TEMP_FILE: &str = ".turbopack-commit.json.tmp";
+
+/// The file that maps the paths of deduplicated files to the path of the file
+/// with the same content, relative to the output directory.
+pub const ALIAS_MANIFEST: &str = "turbopack-aliases.json";
+
+/// A file that is written by a commit.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    /// The path relative to the output directory, with `/` separators.
+    path: String,
+    /// Whether the file existed before the commit and was backed up.
+    existed: bool,
+    /// Whether the commit removes the file instead of writing it.
+    #[serde(default)]
+    removed: bool,
+}
+
+/// A set of writes to an output directory that is applied all at once.
+pub struct EmitTransaction {
+    output_dir: PathBuf,
+    staged: Vec<String>,
+    zero_timestamps: bool,
+    /// The staged paths by the hash of their content, if files are
+    /// deduplicated.
+    digests: Option<HashMap<u64, Vec<String>>>,
+    /// Maps deduplicated paths to the staged path with the same content.
+    aliases: BTreeMap<String, String>,
+    /// The number of bytes that weren't written because of deduplication.
+    saved_bytes: u64,
+    finished: bool,
+}
+
+impl EmitTransaction {
+    /// Begins a transaction for `output_dir`. Restores the previous output if
+    /// the last commit was interrupted and discards writes that were staged
+    /// but never committed.
+    pub fn begin(output_dir: impl Into<PathBuf>) -> Result<Self> {
+        let output_dir = output_dir.into();
+        recover(&output_dir)?;
+        remove_dir_if_exists(&output_dir.join(STAGING_DIRECTORY))?;
+        Ok(EmitTransaction {
+            output_dir,
+            staged: Vec::new(),
+            zero_timestamps: false,
+            digests: None,
+            aliases: BTreeMap::new(),
+            saved_bytes: 0,
+            finished: false,
+        })
+    }
+
+    /// Writes files with identical content only once, see the
+    /// [module documentation](self).
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.digests = deduplicate.then(HashMap::new);
+        self
+    }
+
+    /// Sets the modification time of all files written by the transaction to
+    /// the Unix epoch, so that the output doesn't depend on when it was built.
+    pub fn zero_timestamps(mut self, zero_timestamps: bool) -> Self {
+        self.zero_timestamps = zero_timestamps;
+        self
+    }
+
+    /// Stages writing `content` to `path`, which is relative to the output
+    /// directory and uses `/` separators.
+    pub fn stage(&mut self, path: &str, content: &[u8]) -> Result<()> {
+        if path.is_empty() || path.split('/').any(|segment| segment == "..") {
+            bail!("{} is not a path inside of the output directory", path);
+        }
+        if let Some(target) = self.aliases.remove(path) {
+            self.saved_bytes -= fs::metadata(self.staged_path(&target))?.len();
+        }
+        self.release_staged(path, content)?;
+        if let Some(digests) = &mut self.digests {
+            let paths = digests.entry(hash_xxh3_hash64(content)).or_default();
+            let mut target = None;
+            for staged in paths.iter() {
+                if staged != path
+                    && fs::read(self.output_dir.join(STAGING_DIRECTORY).join(staged))? == content
+                {
+                    target = Some(staged.clone());
+                    break;
+                }
+            }
+            if let Some(target) = target {
+                self.unstage(path)?;
+                self.aliases.insert(path.to_string(), target);
+                self.saved_bytes += content.len() as u64;
+                return Ok(());
+            }
+            if !paths.iter().any(|staged| staged == path) {
+                paths.push(path.to_string());
+            }
+        }
+        self.write_staged(path, content)
+    }
+
+    /// The path of the staged file with the same content as `path`, if `path`
+    /// was deduplicated.
+    pub fn alias_of(&self, path: &str) -> Option<&str> {
+        self.aliases.get(path).map(|target| target.as_str())
+    }
+
+    /// Summarizes the files that were deduplicated so far.
+    pub fn deduplication_report(&self) -> DeduplicationReport {
+        DeduplicationReport {
+            aliases: self.aliases.len(),
+            saved_bytes: self.saved_bytes,
+        }
+    }
+
+    /// Prepares restaging `path` with `content` when files are deduplicated.
+    /// `path` is removed from the digests, and if other paths are aliases of
+    /// it, its previous content is written to the first of them, which the
+    /// others become aliases of.
+    fn release_staged(&mut self, path: &str, content: &[u8]) -> Result<()> {
+        let staged_path = self.staged_path(path);
+        let Some(digests) = &mut self.digests else {
+            return Ok(());
+        };
+        let Some((&digest, paths)) = digests
+            .iter_mut()
+            .find(|(_, paths)| paths.iter().any(|staged| staged == path))
+        else {
+            return Ok(());
+        };
+        let previous = fs::read(staged_path)?;
+        if previous == content {
+            return Ok(());
+        }
+        paths.retain(|staged| staged != path);
+
+        let dependents = self
+            .aliases
+            .iter()
+            .filter(|(_, target)| *target == path)
+            .map(|(alias, _)| alias.clone())
+            .collect::<Vec<_>>();
+        let Some((first, rest)) = dependents.split_first() else {
+            return Ok(());
+        };
+        self.aliases.remove(first);
+        self.saved_bytes -= previous.len() as u64;
+        self.write_staged(first, &previous)?;
+        for alias in rest {
+            self.aliases.insert(alias.clone(), first.clone());
+        }
+        if let Some(digests) = &mut self.digests {
+            digests.entry(digest).or_default().push(first.clone());
+        }
+        Ok(())
+    }
+
+    /// Removes the staged file of `path`, which became an alias.
+    fn unstage(&mut self, path: &str) -> Result<()> {
+        if let Some(index) = self.staged.iter().position(|staged| staged == path) {
+            self.staged.remove(index);
+            fs::remove_file(self.staged_path(path))?;
+        }
+        Ok(())
+    }
+
+    fn staged_path(&self, path: &str) -> PathBuf {
+        self.output_dir.join(STAGING_DIRECTORY).join(path)
+    }
+
+    fn write_staged(&mut self, path: &str, content: &[u8]) -> Result<()> {
+        let staged_path = self.staged_path(path);
+        if let Some(parent) = staged_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&staged_path, content)
+            .with_context(|| format!("staging {}", staged_path.display()))?;
+        if self.zero_timestamps {
+            // Moving the file into place keeps its modification time.
+            File::options()
+                .write(true)
+                .open(&staged_path)?
+                .set_modified(SystemTime::UNIX_EPOCH)?;
+        }
+        if !self.staged.iter().any(|staged| staged == path) {
+            self.staged.push(path.to_string());
+        }
+        Ok(())
+    }
+
+    /// Moves all staged files into the output directory. If that fails, the
+    /// previous output is restored.
+    pub fn commit(mut self) -> Result<()> {
+        let mut removed = Vec::new();
+        if self.digests.is_some() {
+            let aliases = self.merge_aliases()?;
+            removed = aliases
+                .keys()
+                .filter(|alias| self.output_dir.join(alias).is_file())
+                .cloned()
+                .collect();
+            let manifest = serde_json::to_vec_pretty(&aliases)?;
+            self.write_staged(ALIAS_MANIFEST, &manifest)?;
+        }
+        self.finished = true;
+        let output_dir = &self.output_dir;
+        let journal = self
+            .staged
+            .iter()
+            .map(|path| JournalEntry {
+                existed: output_dir.join(path).exists(),
+                path: path.clone(),
+                removed: false,
+            })
+            .chain(removed.into_iter().map(|path| JournalEntry {
+                path,
+                existed: true,
+                removed: true,
+            }))
+            .collect::<Vec<_>>();
+        write_journal(output_dir, &journal)?;
+
+        if let Err(err) = apply(output_dir, &journal) {
+            recover(output_dir).context("restoring the previous output")?;
+            return Err(err.context("committing the output"));
+        }
+        fs::remove_file(output_dir.join(JOURNAL_FILE))?;
+        remove_dir_if_exists(&output_dir.join(BACKUP_DIRECTORY))?;
+        remove_dir_if_exists(&output_dir.join(STAGING_DIRECTORY))?;
+        Ok(())
+    }
+
+    /// Merges the aliases of this transaction into the [ALIAS_MANIFEST] of the
+    /// output directory. Previous aliases of paths that are staged now are
+    /// dropped. If the target of a previous alias is staged with different
+    /// content, the previous content is staged for the alias instead.
+    fn merge_aliases(&mut self) -> Result<BTreeMap<String, String>> {
+        let manifest_path = self.output_dir.join(ALIAS_MANIFEST);
+        let previous: BTreeMap<String, String> = match fs::read(&manifest_path) {
+            Ok(manifest) => serde_json::from_slice(&manifest)
+                .with_context(|| format!("reading {}", manifest_path.display()))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut merged = BTreeMap::new();
+        let mut restaged = Vec::new();
+        for (alias, target) in previous {
+            if self.is_staged(&alias) {
+                continue;
+            }
+            if !self.is_staged(&target) {
+                merged.insert(alias, target);
+                continue;
+            }
+            let current_target = self.alias_of(&target).unwrap_or(&target).to_string();
+            let content = fs::read(self.output_dir.join(&target))
+                .with_context(|| format!("reading the target of the alias {}", alias))?;
+            if fs::read(self.staged_path(&current_target))? == content {
+                merged.insert(alias, current_target);
+            } else {
+                restaged.push((alias, content));
+            }
+        }
+        for (alias, content) in restaged {
+            self.stage(&alias, &content)?;
+        }
+        merged.extend(
+            self.aliases
+                .iter()
+                .map(|(alias, target)| (alias.clone(), target.clone())),
+        );
+        Ok(merged)
+    }
+
+    /// Whether `path` is written by this transaction or is an alias of a file
+    /// that is.
+    fn is_staged(&self, path: &str) -> bool {
+        self.aliases.contains_key(path) || self.staged.iter().any(|staged| staged == path)
+    }
+
+    /// Discards all staged files. Dropping the transaction has the same
+    /// effect.
+    pub fn rollback(mut self) -> Result<()> {
+        self.finished = true;
+        remove_dir_if_exists(&self.output_dir.join(STAGING_DIRECTORY))
+    }
+}
+
+/// The files that an [EmitTransaction] deduplicated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeduplicationReport {
+    /// The number of paths that are aliases of another file.
+    pub aliases: usize,
+    /// The number of bytes that weren't written.
+    pub saved_bytes: u64,
+}
+
+impl Display for DeduplicationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deduplicated {} files with identical content, saving {} bytes",
+            self.aliases, self.saved_bytes
+        )
+    }
+}
+
+impl Drop for EmitTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = remove_dir_if_exists(&self.output_dir.join(STAGING_DIRECTORY));
+        }
+    }
+}
+
+/// Backs up the files that are replaced or removed and moves the staged files
+/// into place.
+fn apply(output_dir: &Path, journal: &[JournalEntry]) -> Result<()> {
+    for entry in journal {
+        let target = output_dir.join(&entry.path);
+        if entry.existed {
+            let backup = output_dir.join(BACKUP_DIRECTORY).join(&entry.path);
+            if let Some(parent) = backup.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&target, &backup)
+                .with_context(|| format!("backing up {}", target.display()))?;
+        }
+        if entry.removed {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(
+            output_dir.join(STAGING_DIRECTORY).join(&entry.path),
+            &target,
+        )
+        .with_context(|| format!("writing {}", target.display()))?;
+    }
+    Ok(())
+}
+
+/// Writes the journal of a commit. Nothing is moved before the journal is
+/// complete, so it is renamed into place only once it has been written.
+fn write_journal(output_dir: &Path, journal: &[JournalEntry]) -> Result<()> {
+    let temp_path = output_dir.join(JOURNAL_TEMP_FILE);
+    fs::write(&temp_path, serde_json::to_vec(journal)?)
+        .with_context(|| format!("writing {}", temp_path.display()))?;
+    File::open(&temp_path)?.sync_all()?;
+    fs::rename(&temp_path, output_dir.join(JOURNAL_FILE))?;
+    Ok(())
+}
+
+/// Undoes an interrupted commit of `output_dir`, if there is one.
+///
+/// A journal that can't be parsed wasn't written by a commit, which only
+/// moves files once its journal is complete, so there is nothing to restore.
+fn recover(output_dir: &Path) -> Result<()> {
+    remove_file_if_exists(&output_dir.join(JOURNAL_TEMP_FILE))?;
+    let journal_path = output_dir.join(JOURNAL_FILE);
+    let journal: Vec<JournalEntry> = match fs::read(&journal_path) {
+        Ok(journal) => serde_json::from_slice(&journal).unwrap_or_default(),
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    for entry in &journal {
+        let target = output_dir.join(&entry.path);
+        let backup = output_dir.join(BACKUP_DIRECTORY).join(&entry.path);
+        if !entry.existed {
+            // The file was created by the commit, if it got that far.
+            remove_file_if_exists(&target)?;
+        } else if backup.exists() {
+            remove_file_if_exists(&target)?;
+            fs::rename(&backup, &target)
+                .with_context(|| format!("restoring {}", target.display()))?;
+        }
+        // Otherwise the commit didn't get to this file, so it's unchanged.
+    }
+    fs::remove_file(&journal_path)?;
+    remove_dir_if_exists(&output_dir.join(BACKUP_DIRECTORY))?;
+    remove_dir_if_exists(&output_dir.join(STAGING_DIRECTORY))
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn remove_dir_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(dir: &Path, path: &str) -> Option<String> {
+        fs::read_to_string(dir.join(path)).ok()
+    }
+
+    #[test]
+    fn commit_replaces_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::write(output.join("a.js"), "old").unwrap();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap();
+        transaction.stage("a.js", b"new").unwrap();
+        transaction.stage("chunks/b.js", b"b").unwrap();
+        assert_eq!(read(output, "a.js").as_deref(), Some("old"));
+        assert_eq!(read(output, "chunks/b.js"), None);
+        transaction.commit().unwrap();
+
+        assert_eq!(read(output, "a.js").as_deref(), Some("new"));
+        assert_eq!(read(output, "chunks/b.js").as_deref(), Some("b"));
+        assert!(!output.join(STAGING_DIRECTORY).exists());
+        assert!(!output.join(BACKUP_DIRECTORY).exists());
+    }
+
+    #[test]
+    fn commit_zeroes_timestamps() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+
+        let mut transaction = EmitTransaction::begin(output)
+            .unwrap()
+            .zero_timestamps(true);
+        transaction.stage("chunks/a.js", b"a").unwrap();
+        transaction.commit().unwrap();
+
+        let modified = fs::metadata(output.join("chunks/a.js"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(modified, SystemTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn commit_deduplicates_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a/config.js", b"same").unwrap();
+        transaction.stage("b/config.js", b"same").unwrap();
+        transaction.stage("c/config.js", b"different").unwrap();
+        assert_eq!(transaction.alias_of("b/config.js"), Some("a/config.js"));
+        assert_eq!(transaction.alias_of("c/config.js"), None);
+        assert_eq!(
+            transaction.deduplication_report(),
+            DeduplicationReport {
+                aliases: 1,
+                saved_bytes: 4
+            }
+        );
+        transaction.commit().unwrap();
+
+        assert_eq!(read(output, "a/config.js").as_deref(), Some("same"));
+        assert_eq!(read(output, "b/config.js"), None);
+        assert_eq!(read(output, "c/config.js").as_deref(), Some("different"));
+        let aliases: BTreeMap<String, String> =
+            serde_json::from_str(&read(output, ALIAS_MANIFEST).unwrap()).unwrap();
+        assert_eq!(
+            aliases,
+            BTreeMap::from([("b/config.js".to_string(), "a/config.js".to_string())])
+        );
+    }
+
+    #[test]
+    fn restaging_keeps_aliases_of_previous_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a.js", b"same").unwrap();
+        transaction.stage("b.js", b"same").unwrap();
+        transaction.stage("c.js", b"same").unwrap();
+        transaction.stage("a.js", b"new").unwrap();
+        assert_eq!(transaction.alias_of("a.js"), None);
+        assert_eq!(transaction.alias_of("b.js"), None);
+        assert_eq!(transaction.alias_of("c.js"), Some("b.js"));
+        transaction.stage("d.js", b"same").unwrap();
+        assert_eq!(transaction.alias_of("d.js"), Some("b.js"));
+        // Restaging an alias with different content writes it.
+        transaction.stage("d.js", b"other").unwrap();
+        assert_eq!(transaction.alias_of("d.js"), None);
+        assert_eq!(
+            transaction.deduplication_report(),
+            DeduplicationReport {
+                aliases: 1,
+                saved_bytes: 4
+            }
+        );
+        transaction.commit().unwrap();
+
+        assert_eq!(read(output, "a.js").as_deref(), Some("new"));
+        assert_eq!(read(output, "b.js").as_deref(), Some("same"));
+        assert_eq!(read(output, "c.js"), None);
+        assert_eq!(read(output, "d.js").as_deref(), Some("other"));
+        let aliases: BTreeMap<String, String> =
+            serde_json::from_str(&read(output, ALIAS_MANIFEST).unwrap()).unwrap();
+        assert_eq!(
+            aliases,
+            BTreeMap::from([("c.js".to_string(), "b.js".to_string())])
+        );
+    }
+
+    fn aliases(dir: &Path) -> BTreeMap<String, String> {
+        serde_json::from_str(&read(dir, ALIAS_MANIFEST).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn commits_of_several_entries_merge_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a/index.js", b"a").unwrap();
+        transaction.stage("a/shared.js", b"shared").unwrap();
+        transaction.stage("a/config.js", b"config").unwrap();
+        transaction.stage("a/config.json.js", b"config").unwrap();
+        transaction.commit().unwrap();
+
+        // The second entry is emitted separately and changes the config of the
+        // first one.
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("b/index.js", b"b").unwrap();
+        transaction.stage("b/shared.js", b"shared").unwrap();
+        transaction.stage("b/more.js", b"shared").unwrap();
+        transaction.stage("a/config.js", b"new config").unwrap();
+        transaction.commit().unwrap();
+
+        assert_eq!(
+            aliases(output),
+            BTreeMap::from([("b/more.js".to_string(), "b/shared.js".to_string())])
+        );
+        assert_eq!(read(output, "a/index.js").as_deref(), Some("a"));
+        assert_eq!(read(output, "a/config.js").as_deref(), Some("new config"));
+        assert_eq!(read(output, "a/config.json.js").as_deref(), Some("config"));
+        assert_eq!(read(output, "b/more.js"), None);
+
+        // Restaging a path keeps the aliases of other entries.
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a/shared.js", b"shared").unwrap();
+        transaction.stage("a/copy.js", b"shared").unwrap();
+        transaction.commit().unwrap();
+
+        assert_eq!(
+            aliases(output),
+            BTreeMap::from([
+                ("a/copy.js".to_string(), "a/shared.js".to_string()),
+                ("b/more.js".to_string(), "b/shared.js".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn commit_removes_files_at_aliased_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::write(output.join("a.js"), "old").unwrap();
+        fs::write(output.join("b.js"), "old").unwrap();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a.js", b"same").unwrap();
+        transaction.stage("b.js", b"same").unwrap();
+        transaction.commit().unwrap();
+
+        assert_eq!(read(output, "a.js").as_deref(), Some("same"));
+        assert_eq!(read(output, "b.js"), None);
+        assert_eq!(
+            aliases(output),
+            BTreeMap::from([("b.js".to_string(), "a.js".to_string())])
+        );
+    }
+
+    #[test]
+    fn restaging_an_alias_target_keeps_previous_aliases_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a.js", b"same").unwrap();
+        transaction.stage("b.js", b"same").unwrap();
+        transaction.stage("c.js", b"same").unwrap();
+        transaction.commit().unwrap();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap().deduplicate(true);
+        transaction.stage("a.js", b"new").unwrap();
+        transaction.commit().unwrap();
+
+        assert_eq!(read(output, "a.js").as_deref(), Some("new"));
+        assert_eq!(read(output, "b.js").as_deref(), Some("same"));
+        assert_eq!(read(output, "c.js"), None);
+        assert_eq!(
+            aliases(output),
+            BTreeMap::from([("c.js".to_string(), "b.js".to_string())])
+        );
+    }
+
+    #[test]
+    fn rollback_keeps_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::write(output.join("a.js"), "old").unwrap();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap();
+        transaction.stage("a.js", b"new").unwrap();
+        transaction.rollback().unwrap();
+
+        assert_eq!(read(output, "a.js").as_deref(), Some("old"));
+        assert!(!output.join(STAGING_DIRECTORY).exists());
+    }
+
+    #[test]
+    fn begin_recovers_interrupted_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::write(output.join("a.js"), "old").unwrap();
+        fs::write(output.join("b.js"), "old").unwrap();
+
+        // Simulate a commit that crashed after moving `a.js` and `c.js`.
+        let journal =
+            [("a.js", true), ("b.js", true), ("c.js", false)].map(|(path, existed)| JournalEntry {
+                path: path.to_string(),
+                existed,
+                removed: false,
+            });
+        fs::write(
+            output.join(JOURNAL_FILE),
+            serde_json::to_vec(&journal).unwrap(),
+        )
+        .unwrap();
+        fs::create_dir_all(output.join(BACKUP_DIRECTORY)).unwrap();
+        fs::rename(
+            output.join("a.js"),
+            output.join(BACKUP_DIRECTORY).join("a.js"),
+        )
+        .unwrap();
+        fs::write(output.join("a.js"), "new").unwrap();
+        fs::write(output.join("c.js"), "new").unwrap();
+
+        EmitTransaction::begin(output).unwrap();
+
+        assert_eq!(read(output, "a.js").as_deref(), Some("old"));
+        assert_eq!(read(output, "b.js").as_deref(), Some("old"));
+        assert_eq!(read(output, "c.js"), None);
+        assert!(!output.join(JOURNAL_FILE).exists());
+    }
+
+    #[test]
+    fn begin_ignores_unparseable_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path();
+        fs::write(output.join("a.js"), "old").unwrap();
+        fs::write(output.join(JOURNAL_FILE), "[{\"path\": \"a.js\"").unwrap();
+        fs::write(output.join(JOURNAL_TEMP_FILE), "[").unwrap();
+
+        let mut transaction = EmitTransaction::begin(output).unwrap();
+        assert_eq!(read(output, "a.js").as_deref(), Some("old"));
+        assert!(!output.join(JOURNAL_FILE).exists());
+        assert!(!output.join(JOURNAL_TEMP_FILE).exists());
+
+        transaction.stage("a.js", b"new").unwrap();
+        transaction.commit().unwrap();
+        assert_eq!(read(output, "a.js").as_deref(), Some("new"));
+        assert!(!output.join(JOURNAL_FILE).exists());
+    }
+}
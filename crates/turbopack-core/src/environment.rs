@@ -183,6 +183,23 @@ impl EnvironmentVc {
         })
     }
 
+    /// Whether code running in this environment can use `feature`.
+    #[turbo_tasks::function]
+    pub async fn supports(self, feature: Value<RuntimeFeature>) -> Result<BoolVc> {
+        let this = self.await?;
+        let feature = feature.into_value();
+        Ok(BoolVc::cell(match this.execution {
+            ExecutionEnvironment::NodeJsBuildTime(_)
+            | ExecutionEnvironment::NodeJsLambda(_)
+            | ExecutionEnvironment::Browser(_) => {
+                feature.is_supported_by(&self.runtime_versions().await?)?
+            }
+            // Edge workers run on a recent V8, but don't allow spawning threads.
+            ExecutionEnvironment::EdgeWorker(_) => feature != RuntimeFeature::WasmThreads,
+            ExecutionEnvironment::Custom(_) => false,
+        }))
+    }
+
     #[turbo_tasks::function]
     pub async fn node_externals(self) -> Result<BoolVc> {
         let this = self.await?;
@@ -281,6 +298,116 @@ impl EnvironmentVc {
     }
 }
 
+/// Runtime features whose availability differs between environments. Code
+/// generation can query [EnvironmentVc::supports] instead of checking runtime
+/// versions itself.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(PartialOrd, Ord, Debug, Hash, Clone, Copy)]
+pub enum RuntimeFeature {
+    /// `import.meta` in ES modules.
+    ImportMeta,
+    /// `await` at the top level of ES modules.
+    TopLevelAwait,
+    /// `BigInt` values and literals.
+    BigInt,
+    /// WebAssembly threads, i.e. shared memory and atomics.
+    WasmThreads,
+    /// `import()` within web workers or worker threads.
+    DynamicImportInWorkers,
+}
+
+impl RuntimeFeature {
+    /// How the feature is called in messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuntimeFeature::ImportMeta => "import.meta",
+            RuntimeFeature::TopLevelAwait => "top-level await",
+            RuntimeFeature::BigInt => "BigInt",
+            RuntimeFeature::WasmThreads => "WebAssembly threads",
+            RuntimeFeature::DynamicImportInWorkers => "import() in workers",
+        }
+    }
+
+    /// The first version of each runtime that supports the feature. Runtimes
+    /// that aren't listed are assumed to not support it.
+    fn min_versions(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            RuntimeFeature::ImportMeta => &[
+                ("chrome", "64.0.0"),
+                ("edge", "79.0.0"),
+                ("firefox", "62.0.0"),
+                ("safari", "11.1.0"),
+                ("ios", "12.0.0"),
+                ("opera", "51.0.0"),
+                ("samsung", "9.2.0"),
+                ("node", "12.17.0"),
+            ],
+            RuntimeFeature::TopLevelAwait => &[
+                ("chrome", "89.0.0"),
+                ("edge", "89.0.0"),
+                ("firefox", "89.0.0"),
+                ("safari", "15.0.0"),
+                ("ios", "15.0.0"),
+                ("opera", "75.0.0"),
+                ("samsung", "15.0.0"),
+                ("node", "14.8.0"),
+            ],
+            RuntimeFeature::BigInt => &[
+                ("chrome", "67.0.0"),
+                ("edge", "79.0.0"),
+                ("firefox", "68.0.0"),
+                ("safari", "14.0.0"),
+                ("ios", "14.0.0"),
+                ("opera", "54.0.0"),
+                ("samsung", "9.2.0"),
+                ("node", "10.4.0"),
+            ],
+            RuntimeFeature::WasmThreads => &[
+                ("chrome", "74.0.0"),
+                ("edge", "79.0.0"),
+                ("firefox", "79.0.0"),
+                ("safari", "14.1.0"),
+                ("ios", "14.5.0"),
+                ("opera", "62.0.0"),
+                ("samsung", "11.0.0"),
+                ("node", "16.4.0"),
+            ],
+            RuntimeFeature::DynamicImportInWorkers => &[
+                ("chrome", "80.0.0"),
+                ("edge", "80.0.0"),
+                ("firefox", "114.0.0"),
+                ("safari", "15.0.0"),
+                ("ios", "15.0.0"),
+                ("opera", "67.0.0"),
+                ("samsung", "13.0.0"),
+                ("node", "12.17.0"),
+            ],
+        }
+    }
+
+    /// Whether all runtimes in `versions` support the feature. An empty set of
+    /// versions supports nothing.
+    fn is_supported_by(&self, versions: &Versions) -> Result<bool> {
+        let min_versions = self.min_versions();
+        let mut any = false;
+        for (name, version) in versions.iter() {
+            let Some(version) = version.as_ref().copied() else {
+                continue;
+            };
+            any = true;
+            let Some((_, min_version)) = min_versions.iter().find(|(runtime, _)| *runtime == name) else {
+                return Ok(false);
+            };
+            let min_version = Version::from_str(min_version)
+                .map_err(|_| anyhow!("invalid version {min_version}"))?;
+            if version < min_version {
+                return Ok(false);
+            }
+        }
+        Ok(any)
+    }
+}
+
 pub enum NodeEnvironmentType {
     Server,
 }
@@ -382,3 +509,65 @@ pub async fn get_current_nodejs_version(env: ProcessEnvVc) -> Result<StringVc> {
             .to_owned(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use swc_core::ecma::preset_env::{Version, Versions};
+
+    use super::RuntimeFeature;
+
+    fn version(version: &str) -> Option<Version> {
+        Some(Version::from_str(version).ok().expect("valid version"))
+    }
+
+    #[test]
+    fn test_empty_versions_support_nothing() {
+        let versions = Versions::default();
+        assert!(!RuntimeFeature::ImportMeta
+            .is_supported_by(&versions)
+            .unwrap());
+        assert!(!RuntimeFeature::BigInt.is_supported_by(&versions).unwrap());
+    }
+
+    #[test]
+    fn test_unknown_runtime_supports_nothing() {
+        let versions = Versions {
+            chrome: version("120.0.0"),
+            electron: version("28.0.0"),
+            ..Default::default()
+        };
+        assert!(!RuntimeFeature::BigInt.is_supported_by(&versions).unwrap());
+    }
+
+    #[test]
+    fn test_min_versions_are_inclusive() {
+        let supported = |node: &str| {
+            RuntimeFeature::TopLevelAwait
+                .is_supported_by(&Versions {
+                    node: version(node),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        assert!(!supported("14.7.9"));
+        assert!(supported("14.8.0"));
+        assert!(supported("14.8.1"));
+    }
+
+    #[test]
+    fn test_every_runtime_must_support_the_feature() {
+        let versions = Versions {
+            chrome: version("89.0.0"),
+            safari: version("14.1.0"),
+            ..Default::default()
+        };
+        assert!(!RuntimeFeature::TopLevelAwait
+            .is_supported_by(&versions)
+            .unwrap());
+        assert!(RuntimeFeature::ImportMeta
+            .is_supported_by(&versions)
+            .unwrap());
+    }
+}
@@ -6,6 +6,38 @@ use turbo_tasks_fs::FileSystemPathVc;
 
 use crate::resolve::ModulePartVc;
 
+/// The kind of an [AssetIdent] modifier, which allows to find or remove the
+/// modifiers of a kind without matching their values.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, Hash)]
+pub enum ModifierKind {
+    /// The type the source is interpreted as (e.g. `ecmascript`, `css`)
+    AssetType,
+    /// The asset is derived from another asset (e.g. `manifest chunk`)
+    Derived,
+    /// The kind of chunk the asset represents (e.g. `ecmascript dev chunk`)
+    Chunk,
+    /// The layer of the chunking context (e.g. `ssr`)
+    Layer,
+    /// An asset that is included in a chunk (e.g. an evaluated entry)
+    ChunkMember,
+    /// Any other modifier
+    Custom,
+}
+
+#[turbo_tasks::value(transparent, serialization = "auto_for_input")]
+#[derive(Clone, Debug, PartialOrd, Ord, Hash)]
+pub struct ModifierKinds(Vec<ModifierKind>);
+
+/// A modifier of an [AssetIdent], consisting of its kind and a free-form
+/// value.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, Hash)]
+pub struct Modifier {
+    pub kind: ModifierKind,
+    pub value: StringVc,
+}
+
 #[turbo_tasks::value(serialization = "auto_for_input")]
 #[derive(Clone, Debug, PartialOrd, Ord, Hash)]
 pub struct AssetIdent {
@@ -17,15 +49,18 @@ pub struct AssetIdent {
     pub fragment: Option<StringVc>,
     /// The assets that are nested in this asset
     pub assets: Vec<(StringVc, AssetIdentVc)>,
-    /// The modifiers of this asset (e.g. `client chunks`)
-    pub modifiers: Vec<StringVc>,
+    /// The modifiers of this asset (e.g. `client chunks`), in the order they
+    /// were added. Neither sorted nor deduplicated, since the order can be
+    /// meaningful, e.g. of chunk members, and an asset that was transformed
+    /// twice must not get the ident of one that was transformed once.
+    pub modifiers: Vec<Modifier>,
     /// The part of the asset that is a (ECMAScript) module
     pub part: Option<ModulePartVc>,
 }
 
 impl AssetIdent {
-    pub fn add_modifier(&mut self, modifier: StringVc) {
-        self.modifiers.push(modifier);
+    pub fn add_modifier(&mut self, kind: ModifierKind, value: StringVc) {
+        self.modifiers.push(Modifier { kind, value });
     }
 
     pub fn add_asset(&mut self, key: StringVc, asset: AssetIdentVc) {
@@ -53,7 +88,7 @@ impl ValueToString for AssetIdent {
                 if i > 0 {
                     s.push_str(", ");
                 }
-                s.push_str(&modifier.await?);
+                s.push_str(&modifier.value.await?);
             }
             s.push(')');
         }
@@ -82,9 +117,19 @@ impl AssetIdentVc {
     }
 
     #[turbo_tasks::function]
-    pub async fn with_modifier(self, modifier: StringVc) -> Result<Self> {
+    pub async fn with_modifier(self, kind: Value<ModifierKind>, value: StringVc) -> Result<Self> {
+        let mut this = self.await?.clone_value();
+        this.add_modifier(kind.into_value(), value);
+        Ok(Self::new(Value::new(this)))
+    }
+
+    /// Returns this ident without any modifiers of the given `kinds`.
+    #[turbo_tasks::function]
+    pub async fn without_modifiers(self, kinds: ModifierKindsVc) -> Result<Self> {
+        let kinds = kinds.await?;
         let mut this = self.await?.clone_value();
-        this.add_modifier(modifier);
+        this.modifiers
+            .retain(|modifier| !kinds.contains(&modifier.kind));
         Ok(Self::new(Value::new(this)))
     }
 
@@ -0,0 +1,287 @@
+//! An opt-in profiler that attributes the time spent on resolving,
+//! transforming and generating chunk items to the files it was spent on, and
+//! aggregates it by package to answer "what makes my build slow?".
+//!
+//! The expensive steps call [record_asset_timing] with the path of the file
+//! they processed. Only work that is actually executed is recorded, so cached
+//! results don't show up after the initial compilation.
+//! [asset_timing_report] summarizes everything recorded since the last
+//! [reset_asset_timings].
+//!
+//! Recording is disabled by default and all functions are no-ops until
+//! [enable_asset_timing] is called.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+
+/// Number of entries that [asset_timing_report] lists by default.
+pub const DEFAULT_REPORT_LIMIT: usize = 20;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref TIMINGS: Mutex<HashMap<String, PhaseTimings>> = Mutex::new(HashMap::new());
+}
+
+/// A step of processing a file that is timed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimingPhase {
+    /// Resolving a request to the file.
+    Resolve,
+    /// Parsing and transforming the file.
+    Transform,
+    /// Generating the code of the chunk item of the file.
+    ChunkItem,
+}
+
+/// The time spent in each [TimingPhase].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub resolve: Duration,
+    pub transform: Duration,
+    pub chunk_item: Duration,
+}
+
+impl PhaseTimings {
+    pub fn total(&self) -> Duration {
+        self.resolve + self.transform + self.chunk_item
+    }
+
+    fn add(&mut self, phase: TimingPhase, duration: Duration) {
+        match phase {
+            TimingPhase::Resolve => self.resolve += duration,
+            TimingPhase::Transform => self.transform += duration,
+            TimingPhase::ChunkItem => self.chunk_item += duration,
+        }
+    }
+
+    fn add_all(&mut self, other: &PhaseTimings) {
+        self.resolve += other.resolve;
+        self.transform += other.transform;
+        self.chunk_item += other.chunk_item;
+    }
+}
+
+/// Enables or disables recording of asset timings.
+pub fn enable_asset_timing(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Release);
+}
+
+pub fn is_asset_timing_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// Records that `phase` took `duration` for the file at `path`, e.g.
+/// `[project]/node_modules/react/index.js`.
+pub fn record_asset_timing(phase: TimingPhase, path: impl Into<String>, duration: Duration) {
+    if !is_asset_timing_enabled() {
+        return;
+    }
+    TIMINGS
+        .lock()
+        .unwrap()
+        .entry(path.into())
+        .or_default()
+        .add(phase, duration);
+}
+
+/// Discards all recorded timings.
+pub fn reset_asset_timings() {
+    TIMINGS.lock().unwrap().clear();
+}
+
+/// Returns the package that the file at `path` belongs to: the package name
+/// after the last `node_modules` directory, or the file system prefix (e.g.
+/// `[project]`) for files outside of `node_modules`.
+pub fn package_of(path: &str) -> &str {
+    if let Some(index) = path.rfind("node_modules/") {
+        let rest = &path[index + "node_modules/".len()..];
+        let segments = if rest.starts_with('@') { 2 } else { 1 };
+        let end = rest
+            .match_indices('/')
+            .nth(segments - 1)
+            .map_or(rest.len(), |(end, _)| end);
+        return &rest[..end];
+    }
+    path.split('/').next().unwrap_or(path)
+}
+
+/// A row of an [AssetTimingReport].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimingEntry {
+    /// The path of the file or the name of the package.
+    pub name: String,
+    pub timings: PhaseTimings,
+    /// The number of files that were recorded for this entry.
+    pub files: usize,
+}
+
+/// The slowest packages and files, slowest first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetTimingReport {
+    pub packages: Vec<TimingEntry>,
+    pub files: Vec<TimingEntry>,
+}
+
+/// Summarizes the recorded timings into the `limit` slowest packages and
+/// files.
+pub fn asset_timing_report(limit: usize) -> AssetTimingReport {
+    let timings = TIMINGS.lock().unwrap();
+    build_report(&timings, limit)
+}
+
+fn build_report(timings: &HashMap<String, PhaseTimings>, limit: usize) -> AssetTimingReport {
+    let mut packages: HashMap<&str, TimingEntry> = HashMap::new();
+    for (path, file_timings) in timings {
+        let package = package_of(path);
+        let entry = packages.entry(package).or_insert_with(|| TimingEntry {
+            name: package.to_string(),
+            timings: PhaseTimings::default(),
+            files: 0,
+        });
+        entry.timings.add_all(file_timings);
+        entry.files += 1;
+    }
+    let files = timings
+        .iter()
+        .map(|(path, timings)| TimingEntry {
+            name: path.clone(),
+            timings: *timings,
+            files: 1,
+        })
+        .collect();
+    AssetTimingReport {
+        packages: slowest(packages.into_values().collect(), limit),
+        files: slowest(files, limit),
+    }
+}
+
+fn slowest(mut entries: Vec<TimingEntry>, limit: usize) -> Vec<TimingEntry> {
+    entries.sort_by(|a, b| {
+        b.timings
+            .total()
+            .cmp(&a.timings.total())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    entries.truncate(limit);
+    entries
+}
+
+impl Display for AssetTimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_entries(
+            f: &mut fmt::Formatter<'_>,
+            title: &str,
+            entries: &[TimingEntry],
+            show_files: bool,
+        ) -> fmt::Result {
+            writeln!(f, "{title}:")?;
+            for (i, entry) in entries.iter().enumerate() {
+                let PhaseTimings {
+                    resolve,
+                    transform,
+                    chunk_item,
+                } = entry.timings;
+                write!(
+                    f,
+                    "{:>3}. {:>10.2?}  {} (resolve {:.2?}, transform {:.2?}, chunk item {:.2?}",
+                    i + 1,
+                    entry.timings.total(),
+                    entry.name,
+                    resolve,
+                    transform,
+                    chunk_item,
+                )?;
+                if show_files {
+                    write!(f, ", {} files", entry.files)?;
+                }
+                writeln!(f, ")")?;
+            }
+            Ok(())
+        }
+
+        write_entries(f, "slowest packages", &self.packages, true)?;
+        write_entries(f, "slowest files", &self.files, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packages() {
+        assert_eq!(package_of("[project]/node_modules/react/index.js"), "react");
+        assert_eq!(
+            package_of("[project]/node_modules/@swc/helpers/lib/index.js"),
+            "@swc/helpers"
+        );
+        assert_eq!(
+            package_of("[project]/node_modules/.pnpm/a@1.0.0/node_modules/a/index.js"),
+            "a"
+        );
+        assert_eq!(package_of("[project]/node_modules/a"), "a");
+        assert_eq!(package_of("[project]/src/index.js"), "[project]");
+    }
+
+    #[test]
+    fn report() {
+        let ms = Duration::from_millis;
+        let timings = HashMap::from([
+            (
+                "[project]/src/index.js".to_string(),
+                PhaseTimings {
+                    resolve: ms(1),
+                    transform: ms(5),
+                    chunk_item: ms(2),
+                },
+            ),
+            (
+                "[project]/node_modules/react/index.js".to_string(),
+                PhaseTimings {
+                    resolve: ms(2),
+                    transform: ms(3),
+                    chunk_item: ms(1),
+                },
+            ),
+            (
+                "[project]/node_modules/react/cjs/react.development.js".to_string(),
+                PhaseTimings {
+                    resolve: ms(1),
+                    transform: ms(20),
+                    chunk_item: ms(4),
+                },
+            ),
+        ]);
+
+        let report = build_report(&timings, 2);
+        assert_eq!(
+            report
+                .packages
+                .iter()
+                .map(|entry| (entry.name.as_str(), entry.timings.total(), entry.files))
+                .collect::<Vec<_>>(),
+            [("react", ms(31), 2), ("[project]", ms(8), 1)]
+        );
+        assert_eq!(
+            report
+                .files
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "[project]/node_modules/react/cjs/react.development.js",
+                "[project]/src/index.js"
+            ]
+        );
+    }
+}
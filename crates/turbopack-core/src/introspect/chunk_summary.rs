@@ -0,0 +1,85 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs};
+
+/// Number of items listed in [ChunkSummary::largest_items].
+const MAX_LARGEST_ITEMS: usize = 10;
+
+/// The size of a single chunk item within a chunk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkItemSize {
+    pub ident: String,
+    pub code_bytes: usize,
+}
+
+/// The composition of a generated chunk, which can be inspected without
+/// downloading the chunk itself.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkSummary {
+    pub item_count: usize,
+    /// Total size of the code of all chunk items, without the runtime code
+    /// that wraps them.
+    pub total_code_bytes: usize,
+    /// The largest chunk items, largest first.
+    pub largest_items: Vec<ChunkItemSize>,
+    /// The layer of the chunking context that generated the chunk.
+    pub layer: String,
+}
+
+impl ChunkSummary {
+    pub fn new(layer: String, mut items: Vec<ChunkItemSize>) -> Self {
+        let item_count = items.len();
+        let total_code_bytes = items.iter().map(|item| item.code_bytes).sum();
+        items.sort_by(|a, b| {
+            b.code_bytes
+                .cmp(&a.code_bytes)
+                .then_with(|| a.ident.cmp(&b.ident))
+        });
+        items.truncate(MAX_LARGEST_ITEMS);
+        ChunkSummary {
+            item_count,
+            total_code_bytes,
+            largest_items: items,
+            layer,
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkSummaryVc {
+    /// The summary as JSON, as served by the dev server introspection.
+    #[turbo_tasks::function]
+    pub async fn to_json(self) -> Result<StringVc> {
+        Ok(StringVc::cell(serde_json::to_string(&*self.await?)?))
+    }
+
+    /// The summary as human readable text for introspection details.
+    #[turbo_tasks::function]
+    pub async fn to_details(self) -> Result<StringVc> {
+        let this = self.await?;
+        let mut details = String::new();
+        if !this.layer.is_empty() {
+            writeln!(details, "Layer: {}", this.layer)?;
+        }
+        writeln!(details, "Chunk items: {}", this.item_count)?;
+        writeln!(details, "Code size: {} bytes", this.total_code_bytes)?;
+        if !this.largest_items.is_empty() {
+            details += "\nLargest chunk items:\n\n";
+            for item in &this.largest_items {
+                writeln!(details, "- {} ({} bytes)", item.ident, item.code_bytes)?;
+            }
+        }
+        Ok(StringVc::cell(details))
+    }
+}
+
+/// A chunk that can summarize its composition for introspection.
+#[turbo_tasks::value_trait]
+pub trait IntrospectableChunk {
+    fn summary(&self) -> ChunkSummaryVc;
+}
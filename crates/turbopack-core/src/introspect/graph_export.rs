@@ -0,0 +1,354 @@
+//! A compact binary export of the introspection graph, i.e. of all assets,
+//! chunks and the references between them, for external analysis tools that
+//! need to load graphs which are too large for JSON.
+//!
+//! The format is a stream of records, so the graph can be written while it is
+//! traversed and read without holding the whole file in memory. It starts with
+//! the 8 bytes of [MAGIC] followed by the format version as a little-endian
+//! `u32`. All following integers are unsigned LEB128 varints. Each record
+//! starts with a tag byte:
+//!
+//! * `1` string: length, UTF-8 bytes. Strings are numbered in the order they
+//!   appear, starting at 0, and referenced by that number.
+//! * `2` node: type string, title string. Nodes are numbered in the order they
+//!   appear, starting at 0.
+//! * `3` edge: parent node, child node, name string.
+//! * `0` end of the graph.
+//!
+//! Records only reference strings and nodes that appeared before them.
+//! Readers must reject versions they don't know, as every incompatible change
+//! bumps [VERSION].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Read, Write},
+};
+
+use anyhow::{bail, Context, Result};
+
+use super::IntrospectableVc;
+
+/// The first bytes of an exported graph.
+pub const MAGIC: &[u8; 8] = b"TPGRAPH\0";
+
+/// The version of the format that is written by [GraphWriter].
+pub const VERSION: u32 = 1;
+
+const TAG_END: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_NODE: u8 = 2;
+const TAG_EDGE: u8 = 3;
+
+/// Writes a graph in the export format.
+pub struct GraphWriter<W: Write> {
+    writer: W,
+    strings: HashMap<String, u64>,
+    node_count: u64,
+}
+
+impl<W: Write> GraphWriter<W> {
+    /// Writes the header to `writer`. It's advisable to pass a buffered
+    /// writer, as records are written in many small pieces.
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        Ok(GraphWriter {
+            writer,
+            strings: HashMap::new(),
+            node_count: 0,
+        })
+    }
+
+    /// Writes a node and returns its number.
+    pub fn node(&mut self, ty: &str, title: &str) -> Result<u64> {
+        let ty = self.string(ty)?;
+        let title = self.string(title)?;
+        self.writer.write_all(&[TAG_NODE])?;
+        write_varint(&mut self.writer, ty)?;
+        write_varint(&mut self.writer, title)?;
+        self.node_count += 1;
+        Ok(self.node_count - 1)
+    }
+
+    /// Writes an edge between two nodes that were written before.
+    pub fn edge(&mut self, parent: u64, child: u64, name: &str) -> Result<()> {
+        if parent >= self.node_count || child >= self.node_count {
+            bail!("edge {parent} -> {child} references a node that wasn't written yet");
+        }
+        let name = self.string(name)?;
+        self.writer.write_all(&[TAG_EDGE])?;
+        write_varint(&mut self.writer, parent)?;
+        write_varint(&mut self.writer, child)?;
+        write_varint(&mut self.writer, name)?;
+        Ok(())
+    }
+
+    /// Writes the end of the graph and returns the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.write_all(&[TAG_END])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Returns the number of `string`, writing it first if it's new.
+    fn string(&mut self, string: &str) -> Result<u64> {
+        if let Some(&index) = self.strings.get(string) {
+            return Ok(index);
+        }
+        let index = self.strings.len() as u64;
+        self.writer.write_all(&[TAG_STRING])?;
+        write_varint(&mut self.writer, string.len() as u64)?;
+        self.writer.write_all(string.as_bytes())?;
+        self.strings.insert(string.to_string(), index);
+        Ok(index)
+    }
+}
+
+/// A node or an edge of an exported graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphRecord<'a> {
+    Node {
+        index: u64,
+        ty: &'a str,
+        title: &'a str,
+    },
+    Edge {
+        parent: u64,
+        child: u64,
+        name: &'a str,
+    },
+}
+
+/// Reads a graph in the export format record by record.
+pub struct GraphReader<R: Read> {
+    reader: R,
+    strings: Vec<String>,
+    node_count: u64,
+    finished: bool,
+}
+
+impl<R: Read> GraphReader<R> {
+    /// Reads and validates the header from `reader`.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 8];
+        reader
+            .read_exact(&mut magic)
+            .context("reading the graph header")?;
+        if &magic != MAGIC {
+            bail!("not an exported turbopack graph");
+        }
+        let mut version = [0; 4];
+        reader
+            .read_exact(&mut version)
+            .context("reading the graph header")?;
+        let version = u32::from_le_bytes(version);
+        if version != VERSION {
+            bail!("unsupported graph format version {version}, expected {VERSION}");
+        }
+        Ok(GraphReader {
+            reader,
+            strings: Vec::new(),
+            node_count: 0,
+            finished: false,
+        })
+    }
+
+    /// Returns the next node or edge, or `None` at the end of the graph.
+    pub fn next_record(&mut self) -> Result<Option<GraphRecord<'_>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        loop {
+            let mut tag = [0];
+            self.reader
+                .read_exact(&mut tag)
+                .context("the graph ended unexpectedly")?;
+            match tag[0] {
+                TAG_END => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                TAG_STRING => {
+                    let len = read_varint(&mut self.reader)?;
+                    let mut bytes = Vec::new();
+                    (&mut self.reader).take(len).read_to_end(&mut bytes)?;
+                    if bytes.len() as u64 != len {
+                        bail!("the graph ended unexpectedly");
+                    }
+                    self.strings.push(String::from_utf8(bytes)?);
+                }
+                TAG_NODE => {
+                    let ty = read_varint(&mut self.reader)?;
+                    let title = read_varint(&mut self.reader)?;
+                    self.node_count += 1;
+                    return Ok(Some(GraphRecord::Node {
+                        index: self.node_count - 1,
+                        ty: lookup(&self.strings, ty)?,
+                        title: lookup(&self.strings, title)?,
+                    }));
+                }
+                TAG_EDGE => {
+                    let parent = read_varint(&mut self.reader)?;
+                    let child = read_varint(&mut self.reader)?;
+                    let name = read_varint(&mut self.reader)?;
+                    if parent >= self.node_count || child >= self.node_count {
+                        bail!("edge {parent} -> {child} references an unknown node");
+                    }
+                    return Ok(Some(GraphRecord::Edge {
+                        parent,
+                        child,
+                        name: lookup(&self.strings, name)?,
+                    }));
+                }
+                tag => bail!("unknown record tag {tag}"),
+            }
+        }
+    }
+}
+
+fn lookup(strings: &[String], index: u64) -> Result<&str> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| strings.get(index))
+        .map(|string| string.as_str())
+        .with_context(|| format!("reference to unknown string {index}"))
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    let mut buf = [0; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&buf[..len])
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader
+            .read_exact(&mut byte)
+            .context("the graph ended unexpectedly")?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("varint is too long")
+}
+
+/// Traverses the introspection graph below `roots` breadth-first and writes it
+/// to `writer` while doing so. Unlike the introspection queries, this doesn't
+/// limit the size of the graph.
+pub async fn export_introspection_graph(
+    roots: Vec<IntrospectableVc>,
+    writer: impl Write,
+) -> Result<()> {
+    let mut graph = GraphWriter::new(writer)?;
+    let mut indices = HashMap::new();
+    let mut queue = VecDeque::with_capacity(roots.len());
+    for root in roots {
+        let root = root.resolve().await?;
+        if indices.contains_key(&root) {
+            continue;
+        }
+        let index = graph.node(&root.ty().await?, &root.title().await?)?;
+        indices.insert(root, index);
+        queue.push_back(root);
+    }
+    while let Some(introspectable) = queue.pop_front() {
+        let index = indices[&introspectable];
+        for &(name, child) in introspectable.children().await?.iter() {
+            let child = child.resolve().await?;
+            let child_index = match indices.get(&child) {
+                Some(&child_index) => child_index,
+                None => {
+                    let child_index = graph.node(&child.ty().await?, &child.title().await?)?;
+                    indices.insert(child, child_index);
+                    queue.push_back(child);
+                    child_index
+                }
+            };
+            graph.edge(index, child_index, &name.await?)?;
+        }
+    }
+    graph.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut writer = GraphWriter::new(Vec::new()).unwrap();
+        let entry = writer
+            .node("ecmascript chunk", "[project]/index.js")
+            .unwrap();
+        let module = writer
+            .node("ecmascript asset", "[project]/index.js")
+            .unwrap();
+        let dependency = writer
+            .node("ecmascript asset", "[project]/node_modules/react/index.js")
+            .unwrap();
+        writer.edge(entry, module, "entry").unwrap();
+        writer.edge(module, dependency, "reference").unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = GraphReader::new(bytes.as_slice()).unwrap();
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record().unwrap() {
+            records.push(format!("{record:?}"));
+        }
+        assert_eq!(
+            records,
+            [
+                r#"Node { index: 0, ty: "ecmascript chunk", title: "[project]/index.js" }"#,
+                r#"Node { index: 1, ty: "ecmascript asset", title: "[project]/index.js" }"#,
+                r#"Node { index: 2, ty: "ecmascript asset", title: "[project]/node_modules/react/index.js" }"#,
+                r#"Edge { parent: 0, child: 1, name: "entry" }"#,
+                r#"Edge { parent: 1, child: 2, name: "reference" }"#,
+            ]
+        );
+        assert_eq!(reader.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn varints() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value).unwrap();
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(GraphReader::new(&b"{\"nodes\": []}"[..]).is_err());
+
+        let mut unknown_version = MAGIC.to_vec();
+        unknown_version.extend((VERSION + 1).to_le_bytes());
+        assert!(GraphReader::new(unknown_version.as_slice()).is_err());
+
+        let mut truncated = GraphWriter::new(Vec::new()).unwrap();
+        truncated.node("asset", "a").unwrap();
+        let mut bytes = truncated.finish().unwrap();
+        bytes.pop();
+        let mut reader = GraphReader::new(bytes.as_slice()).unwrap();
+        assert!(reader.next_record().is_ok());
+        assert!(reader.next_record().is_err());
+
+        let mut writer = GraphWriter::new(Vec::new()).unwrap();
+        assert!(writer.edge(0, 1, "reference").is_err());
+    }
+}
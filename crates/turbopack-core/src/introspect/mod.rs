@@ -1,4 +1,9 @@
 pub mod asset;
+pub mod asset_timing;
+pub mod chunk_summary;
+pub mod graph_export;
+pub mod query;
+pub mod rebuilds;
 
 use indexmap::IndexSet;
 use turbo_tasks::primitives::StringVc;
@@ -0,0 +1,420 @@
+//! A small query language over the introspection graph, so dev tools can
+//! answer questions about the asset graph without a dedicated endpoint for
+//! each question.
+//!
+//! A query is a pipeline of steps separated by `->`, e.g.
+//!
+//! ```text
+//! assets(path~"node_modules/react*") -> referencedBy -> chunks
+//! ```
+//!
+//! The first step selects nodes from the whole graph, every following step
+//! maps the current set of nodes to a new one:
+//!
+//! * `all`: all nodes.
+//! * `assets`: nodes whose type ends with `asset`. Later in the pipeline, the
+//!   assets reachable from the current nodes.
+//! * `chunks`: nodes whose type ends with `chunk`. Later in the pipeline, the
+//!   chunks the current nodes are reachable from.
+//! * `references`: the direct children of the current nodes.
+//! * `referencedBy`: the direct parents of the current nodes.
+//! * `filter`: the current nodes.
+//!
+//! Each step can be followed by filters in parentheses, which all have to
+//! match: `ty` and `title` (or its alias `path`) compared with `=` (exact) or
+//! `~` (a pattern that matches anywhere, where `*` matches any characters).
+
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::{anyhow, bail, Result};
+use indexmap::IndexSet;
+use serde_json::json;
+use turbo_tasks::primitives::StringVc;
+
+use super::IntrospectableVc;
+
+/// Max number of nodes that are loaded into the graph for a query.
+const MAX_GRAPH_NODES: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    String(String),
+    Arrow,
+    OpenParen,
+    CloseParen,
+    Comma,
+    Equals,
+    Tilde,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(Token::OpenParen),
+            ')' => tokens.push(Token::CloseParen),
+            ',' => tokens.push(Token::Comma),
+            '=' => tokens.push(Token::Equals),
+            '~' => tokens.push(Token::Tilde),
+            '-' if matches!(chars.peek(), Some((_, '>'))) => {
+                chars.next();
+                tokens.push(Token::Arrow);
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => string.push(c),
+                            None => bail!("unterminated string starting at {pos}"),
+                        },
+                        Some((_, c)) => string.push(c),
+                        None => bail!("unterminated string starting at {pos}"),
+                    }
+                }
+                tokens.push(Token::String(string));
+            }
+            c if c.is_alphabetic() => {
+                let mut ident = c.to_string();
+                while let Some((_, c)) = chars.peek().filter(|(_, c)| c.is_alphanumeric()) {
+                    ident.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => bail!("unexpected character {c:?} at {pos}"),
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepKind {
+    All,
+    Assets,
+    Chunks,
+    References,
+    ReferencedBy,
+    Filter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Ty,
+    Title,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Matcher {
+    Exact(String),
+    Pattern(String),
+}
+
+impl Matcher {
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Matcher::Exact(expected) => value == expected,
+            Matcher::Pattern(pattern) => pattern_matches(pattern, value),
+        }
+    }
+}
+
+/// Whether `pattern` matches anywhere in `value`, with `*` matching any
+/// characters.
+fn pattern_matches(pattern: &str, value: &str) -> bool {
+    let mut rest = value;
+    for part in pattern.split('*').filter(|part| !part.is_empty()) {
+        let Some(index) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[index + part.len()..];
+    }
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    kind: StepKind,
+    filters: Vec<(Field, Matcher)>,
+}
+
+fn parse(query: &str) -> Result<Vec<Step>> {
+    let tokens = tokenize(query)?;
+    let mut tokens = tokens.into_iter().peekable();
+    let mut steps = Vec::new();
+    loop {
+        let kind = match tokens.next() {
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "all" => StepKind::All,
+                "assets" => StepKind::Assets,
+                "chunks" => StepKind::Chunks,
+                "references" => StepKind::References,
+                "referencedBy" => StepKind::ReferencedBy,
+                "filter" => StepKind::Filter,
+                _ => bail!("unknown step {ident}"),
+            },
+            token => bail!("expected a step, found {token:?}"),
+        };
+        let mut filters = Vec::new();
+        if tokens.next_if_eq(&Token::OpenParen).is_some() {
+            while tokens.next_if_eq(&Token::CloseParen).is_none() {
+                if !filters.is_empty() && tokens.next() != Some(Token::Comma) {
+                    bail!("expected , between filters");
+                }
+                let field = match tokens.next() {
+                    Some(Token::Ident(ident)) => match ident.as_str() {
+                        "ty" => Field::Ty,
+                        "title" | "path" => Field::Title,
+                        _ => bail!("unknown field {ident}"),
+                    },
+                    token => bail!("expected a field, found {token:?}"),
+                };
+                let exact = match tokens.next() {
+                    Some(Token::Equals) => true,
+                    Some(Token::Tilde) => false,
+                    token => bail!("expected = or ~, found {token:?}"),
+                };
+                let Some(Token::String(value)) = tokens.next() else {
+                    bail!("expected a string");
+                };
+                filters.push((
+                    field,
+                    if exact {
+                        Matcher::Exact(value)
+                    } else {
+                        Matcher::Pattern(value)
+                    },
+                ));
+            }
+        }
+        steps.push(Step { kind, filters });
+        match tokens.next() {
+            None => break,
+            Some(Token::Arrow) => {}
+            Some(token) => bail!("expected -> between steps, found {token:?}"),
+        }
+    }
+    Ok(steps)
+}
+
+struct Node {
+    introspectable: IntrospectableVc,
+    ty: String,
+    title: String,
+}
+
+impl Node {
+    /// The last word of the type, e.g. `chunk` for `ecmascript chunk`.
+    fn kind(&self) -> &str {
+        self.ty.rsplit(' ').next().unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    children: Vec<Vec<usize>>,
+    parents: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    async fn load(roots: Vec<IntrospectableVc>) -> Result<Self> {
+        let mut graph = Graph::default();
+        let mut indices = HashMap::new();
+        let mut queue = VecDeque::with_capacity(roots.len());
+        for root in roots {
+            let root = root.resolve().await?;
+            if let Some(index) = graph.add(&mut indices, root).await? {
+                queue.push_back(index);
+            }
+        }
+        while let Some(index) = queue.pop_front() {
+            let children = graph.nodes[index].introspectable.children().await?;
+            for (_, child) in children.iter() {
+                let child = child.resolve().await?;
+                let child_index = match indices.get(&child) {
+                    Some(child_index) => *child_index,
+                    None => {
+                        let Some(child_index) = graph.add(&mut indices, child).await? else {
+                            continue;
+                        };
+                        queue.push_back(child_index);
+                        child_index
+                    }
+                };
+                graph.children[index].push(child_index);
+                graph.parents[child_index].push(index);
+            }
+        }
+        Ok(graph)
+    }
+
+    async fn add(
+        &mut self,
+        indices: &mut HashMap<IntrospectableVc, usize>,
+        introspectable: IntrospectableVc,
+    ) -> Result<Option<usize>> {
+        if indices.contains_key(&introspectable) {
+            return Ok(None);
+        }
+        if self.nodes.len() >= MAX_GRAPH_NODES {
+            bail!("the introspection graph has more than {MAX_GRAPH_NODES} nodes");
+        }
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            introspectable,
+            ty: introspectable.ty().await?.to_string(),
+            title: introspectable.title().await?.to_string(),
+        });
+        self.children.push(Vec::new());
+        self.parents.push(Vec::new());
+        indices.insert(introspectable, index);
+        Ok(Some(index))
+    }
+
+    /// All nodes reachable from `start` (including `start`) via `edges`.
+    fn reachable(&self, start: &IndexSet<usize>, edges: &[Vec<usize>]) -> IndexSet<usize> {
+        let mut visited = start.clone();
+        let mut queue = start.iter().copied().collect::<VecDeque<_>>();
+        while let Some(index) = queue.pop_front() {
+            for &next in &edges[index] {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        visited
+    }
+
+    fn evaluate(&self, steps: &[Step]) -> IndexSet<usize> {
+        let mut current: Option<IndexSet<usize>> = None;
+        for step in steps {
+            let all = || (0..self.nodes.len()).collect::<IndexSet<_>>();
+            let neighbors = |current: &IndexSet<usize>, edges: &[Vec<usize>]| {
+                current
+                    .iter()
+                    .flat_map(|&index| edges[index].iter().copied())
+                    .collect::<IndexSet<_>>()
+            };
+            let (nodes, kind) = match (step.kind, &current) {
+                (StepKind::All | StepKind::Filter, None) => (all(), None),
+                (StepKind::Assets, None) => (all(), Some("asset")),
+                (StepKind::Chunks, None) => (all(), Some("chunk")),
+                (StepKind::All | StepKind::Filter, Some(current)) => (current.clone(), None),
+                (StepKind::Assets, Some(current)) => {
+                    (self.reachable(current, &self.children), Some("asset"))
+                }
+                (StepKind::Chunks, Some(current)) => {
+                    (self.reachable(current, &self.parents), Some("chunk"))
+                }
+                (StepKind::References, current) => (
+                    neighbors(&current.clone().unwrap_or_else(all), &self.children),
+                    None,
+                ),
+                (StepKind::ReferencedBy, current) => (
+                    neighbors(&current.clone().unwrap_or_else(all), &self.parents),
+                    None,
+                ),
+            };
+            current = Some(
+                nodes
+                    .into_iter()
+                    .filter(|&index| {
+                        let node = &self.nodes[index];
+                        kind.map_or(true, |kind| node.kind() == kind)
+                            && step.filters.iter().all(|(field, matcher)| match field {
+                                Field::Ty => matcher.matches(&node.ty),
+                                Field::Title => matcher.matches(&node.title),
+                            })
+                    })
+                    .collect(),
+            );
+        }
+        current.unwrap_or_default()
+    }
+}
+
+/// Evaluates `query` against the introspection graph below `roots` and
+/// returns the matching nodes as a JSON array of `{ ty, title, path }`
+/// objects, where `path` is the serialized introspectable as used by the
+/// introspection endpoint.
+#[turbo_tasks::function]
+pub async fn query_introspection(roots: Vec<IntrospectableVc>, query: &str) -> Result<StringVc> {
+    let steps = parse(query).map_err(|e| anyhow!("invalid query {query:?}: {e}"))?;
+    let graph = Graph::load(roots).await?;
+    let results = graph
+        .evaluate(&steps)
+        .into_iter()
+        .map(|index| {
+            let node = &graph.nodes[index];
+            Ok(json!({
+                "ty": node.ty,
+                "title": node.title,
+                "path": serde_json::to_string(&node.introspectable)?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(StringVc::cell(serde_json::to_string_pretty(&results)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pipeline() {
+        let steps =
+            parse(r#"assets(path~"node_modules/react*") -> referencedBy -> chunks"#).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Step {
+                    kind: StepKind::Assets,
+                    filters: vec![(
+                        Field::Title,
+                        Matcher::Pattern("node_modules/react*".to_string())
+                    )],
+                },
+                Step {
+                    kind: StepKind::ReferencedBy,
+                    filters: vec![],
+                },
+                Step {
+                    kind: StepKind::Chunks,
+                    filters: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("assets ->").is_err());
+        assert!(parse("unknown").is_err());
+        assert!(parse(r#"assets(size="1")"#).is_err());
+        assert!(parse(r#"assets(path~"a" ty="b")"#).is_err());
+        assert!(parse(r#"assets(path~"a)"#).is_err());
+    }
+
+    #[test]
+    fn patterns() {
+        assert!(pattern_matches(
+            "node_modules/react*",
+            "[project]/node_modules/react/index.js"
+        ));
+        assert!(pattern_matches(
+            "react*index",
+            "node_modules/react/index.js"
+        ));
+        assert!(!pattern_matches(
+            "react*index",
+            "node_modules/index/react.js"
+        ));
+        assert!(pattern_matches("", "anything"));
+    }
+}
@@ -0,0 +1,295 @@
+//! An opt-in recorder that answers "why did this chunk rebuild?".
+//!
+//! Chunk implementations call [record_chunk_rebuild] whenever their content is
+//! (re)computed, and [record_chunk_item_rebuild] whenever the code of one of
+//! their chunk items is. The embedder calls [finish_rebuild_update] with the
+//! invalidation reasons of each aggregated turbo-tasks update (e.g. file
+//! changes, env var changes) and the files that changed, see [changed_files].
+//! Each chunk recorded since the previous update is attributed the chain of
+//! its chunk items that were regenerated, and the changed files these were
+//! generated from. The recent updates are exposed via
+//! [RebuildRecorderIntrospectableVc].
+//!
+//! Recording is disabled by default and all functions are no-ops until
+//! [enable_rebuild_recording] is called.
+
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Write},
+    mem::take,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use lazy_static::lazy_static;
+use turbo_tasks::{get_invalidator, primitives::StringVc, InvalidationReasonSet, Invalidator};
+use turbo_tasks_fs::WatchChange;
+
+use super::{Introspectable, IntrospectableVc};
+
+/// Number of updates that are kept by the recorder.
+const MAX_RECORDED_UPDATES: usize = 50;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref RECORDER: Mutex<RebuildRecorder> = Mutex::new(RebuildRecorder::default());
+}
+
+#[derive(Default)]
+struct RebuildRecorder {
+    /// Chunks that were rebuilt since the last finished update, with the
+    /// idents of their chunk items.
+    pending: IndexMap<String, Vec<String>>,
+    /// Idents of the chunk items whose code was regenerated since the last
+    /// finished update.
+    pending_items: IndexSet<String>,
+    updates: VecDeque<RebuildUpdate>,
+    /// Invalidates introspection tasks that read the recorded updates.
+    invalidators: Vec<Invalidator>,
+}
+
+/// The chunks that were rebuilt during a single update, together with the
+/// invalidation reasons that caused the update.
+#[derive(Clone, Debug)]
+pub struct RebuildUpdate {
+    /// A human readable description of the invalidated inputs.
+    pub reasons: String,
+    /// The chunks that were rebuilt.
+    pub chunks: Vec<ChunkRebuild>,
+}
+
+/// Why a single chunk was rebuilt during an update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkRebuild {
+    /// Ident of the chunk.
+    pub chunk: String,
+    /// Idents of the chunk items of the chunk whose code was regenerated,
+    /// which links the chunk to its invalidated inputs. Empty if only the
+    /// chunk itself changed, e.g. because items were added or removed.
+    pub items: Vec<String>,
+    /// The changed files that [ChunkRebuild::items] were generated from.
+    /// Empty if the items were invalidated by something else, e.g. an env var
+    /// or a dependency whose exports changed.
+    pub invalidators: Vec<String>,
+}
+
+/// Enables or disables recording of chunk rebuilds.
+pub fn enable_rebuild_recording(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Release);
+}
+
+pub fn is_rebuild_recording_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// Records that the content of the chunk with the given ident was computed.
+/// `items` are the idents of its chunk items.
+pub fn record_chunk_rebuild(chunk: impl Into<String>, items: impl IntoIterator<Item = String>) {
+    if !is_rebuild_recording_enabled() {
+        return;
+    }
+    let items = items.into_iter().collect();
+    RECORDER.lock().unwrap().pending.insert(chunk.into(), items);
+}
+
+/// Records that the code of the chunk item with the given ident was
+/// generated.
+pub fn record_chunk_item_rebuild(item: impl Into<String>) {
+    if !is_rebuild_recording_enabled() {
+        return;
+    }
+    RECORDER.lock().unwrap().pending_items.insert(item.into());
+}
+
+/// The files that were changed according to `reasons`, formatted like the
+/// paths of asset idents, e.g. `[project]/src/index.js`.
+pub fn changed_files(reasons: &InvalidationReasonSet) -> Vec<String> {
+    reasons
+        .reasons()
+        .into_iter()
+        .filter_map(|reason| reason.as_any().downcast_ref::<WatchChange>())
+        .map(|change| change.path.clone())
+        .collect()
+}
+
+/// Assigns the `reasons` of an update to all chunks recorded since the
+/// previous update, and attributes the `changed_files` of the update to the
+/// chunks whose regenerated chunk items were generated from them.
+pub fn finish_rebuild_update(reasons: &impl Display, changed_files: &[String]) {
+    if !is_rebuild_recording_enabled() {
+        return;
+    }
+    let invalidators = {
+        let mut recorder = RECORDER.lock().unwrap();
+        let items = take(&mut recorder.pending_items);
+        if recorder.pending.is_empty() {
+            return;
+        }
+        let chunks = take(&mut recorder.pending)
+            .into_iter()
+            .map(|(chunk, chunk_items)| {
+                attribute_rebuild(chunk, chunk_items, &items, changed_files)
+            })
+            .collect();
+        recorder.updates.push_back(RebuildUpdate {
+            reasons: reasons.to_string(),
+            chunks,
+        });
+        while recorder.updates.len() > MAX_RECORDED_UPDATES {
+            recorder.updates.pop_front();
+        }
+        take(&mut recorder.invalidators)
+    };
+    for invalidator in invalidators {
+        invalidator.invalidate();
+    }
+}
+
+/// Links the rebuilt `chunk` to the chunk items among `chunk_items` that were
+/// regenerated, and to the files among `changed_files` they are generated from.
+fn attribute_rebuild(
+    chunk: String,
+    chunk_items: Vec<String>,
+    rebuilt_items: &IndexSet<String>,
+    changed_files: &[String],
+) -> ChunkRebuild {
+    let items = chunk_items
+        .into_iter()
+        .filter(|item| rebuilt_items.contains(item))
+        .collect::<Vec<_>>();
+    let invalidators = changed_files
+        .iter()
+        .filter(|file| items.iter().any(|item| is_generated_from(item, file)))
+        .cloned()
+        .collect();
+    ChunkRebuild {
+        chunk,
+        items,
+        invalidators,
+    }
+}
+
+/// Whether the asset ident `item` has the path `file`, i.e. it's the path
+/// followed by nothing or by a query, fragment, nested assets or modifiers.
+fn is_generated_from(item: &str, file: &str) -> bool {
+    item.strip_prefix(file).map_or(false, |rest| {
+        rest.is_empty() || rest.starts_with([' ', '?', '#', '/'])
+    })
+}
+
+/// Returns the recorded updates, oldest first.
+pub fn recorded_rebuild_updates() -> Vec<RebuildUpdate> {
+    RECORDER.lock().unwrap().updates.iter().cloned().collect()
+}
+
+/// Exposes the updates recorded by the rebuild recorder via introspection.
+#[turbo_tasks::value]
+pub struct RebuildRecorderIntrospectable;
+
+#[turbo_tasks::value_impl]
+impl RebuildRecorderIntrospectableVc {
+    #[turbo_tasks::function]
+    pub fn new() -> IntrospectableVc {
+        RebuildRecorderIntrospectable.cell().into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for RebuildRecorderIntrospectable {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("rebuild recorder".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("chunk rebuilds".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn details(&self) -> Result<StringVc> {
+        let updates = {
+            let mut recorder = RECORDER.lock().unwrap();
+            recorder.invalidators.push(get_invalidator());
+            recorder.updates.iter().cloned().collect::<Vec<_>>()
+        };
+        let mut details = String::new();
+        if !is_rebuild_recording_enabled() {
+            details.push_str("rebuild recording is disabled\n");
+        }
+        for update in updates.iter().rev() {
+            writeln!(details, "{}:", update.reasons)?;
+            for chunk in &update.chunks {
+                writeln!(details, "  {}", chunk.chunk)?;
+                for invalidator in &chunk.invalidators {
+                    writeln!(details, "    changed: {invalidator}")?;
+                }
+                for item in &chunk.items {
+                    writeln!(details, "    regenerated: {item}")?;
+                }
+            }
+        }
+        Ok(StringVc::cell(details))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexSet;
+
+    use super::{attribute_rebuild, ChunkRebuild};
+
+    #[test]
+    fn test_attribute_rebuild() {
+        let rebuilt_items = IndexSet::from([
+            "[project]/src/a.js (ecmascript)".to_string(),
+            "[project]/src/b.js?raw (ecmascript)".to_string(),
+            "[project]/src/other.js (ecmascript)".to_string(),
+        ]);
+        let changed_files = [
+            "[project]/src/a.js".to_string(),
+            "[project]/src/a.jsx".to_string(),
+            "[project]/src/other.js".to_string(),
+        ];
+        let rebuild = attribute_rebuild(
+            "chunk.js".to_string(),
+            vec![
+                "[project]/src/a.js (ecmascript)".to_string(),
+                "[project]/src/b.js?raw (ecmascript)".to_string(),
+                "[project]/src/c.js (ecmascript)".to_string(),
+            ],
+            &rebuilt_items,
+            &changed_files,
+        );
+        assert_eq!(
+            rebuild,
+            ChunkRebuild {
+                chunk: "chunk.js".to_string(),
+                items: vec![
+                    "[project]/src/a.js (ecmascript)".to_string(),
+                    "[project]/src/b.js?raw (ecmascript)".to_string(),
+                ],
+                // `b.js` was invalidated by something other than a file
+                // change, and `other.js` isn't in the chunk.
+                invalidators: vec!["[project]/src/a.js".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_attribute_rebuild_without_regenerated_items() {
+        let rebuild = attribute_rebuild(
+            "chunk.js".to_string(),
+            vec!["[project]/src/a.js (ecmascript)".to_string()],
+            &IndexSet::new(),
+            &["[project]/src/a.js".to_string()],
+        );
+        assert!(rebuild.items.is_empty());
+        assert!(rebuild.invalidators.is_empty());
+    }
+}
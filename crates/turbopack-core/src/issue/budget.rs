@@ -0,0 +1,338 @@
+//! Limits on the number of issues that are captured at once.
+//!
+//! A build that fails badly, e.g. because a dependency can't be resolved from
+//! any module, can emit hundreds of thousands of issues, and converting all of
+//! them to [PlainIssue](super::PlainIssue)s can run the process out of memory.
+//! Captured issues are therefore limited per kind of issue, i.e. per
+//! [category](super::Issue::category), and in total, see [IssueBudget]. The
+//! issues that are dropped are summarized in a single [SuppressedIssues]
+//! issue.
+//!
+//! The budget is passed to [IssueVc::peek_issues_with_path] and
+//! [IssueVc::take_issues_with_path]. Diagnostics runs that need every issue
+//! pass [IssueBudget::unlimited], e.g. from
+//! [IssueReporter::issue_budget](super::IssueReporter::issue_budget).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use auto_hash_map::AutoSet;
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt};
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// The number of issues whose severity and category are read at once while
+/// the budget is applied.
+const BATCH_SIZE: usize = 1_000;
+
+/// How many issues are captured at most, see the
+/// [module documentation](self).
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Copy)]
+pub struct IssueBudget {
+    /// The maximum number of issues of each category.
+    pub per_kind: usize,
+    /// The maximum number of issues of all categories together.
+    pub total: usize,
+}
+
+impl IssueBudget {
+    pub const DEFAULT_PER_KIND: usize = 1_000;
+    pub const DEFAULT_TOTAL: usize = 10_000;
+
+    /// Captures every issue.
+    pub fn unlimited() -> Self {
+        Self {
+            per_kind: usize::MAX,
+            total: usize::MAX,
+        }
+    }
+
+    /// Selects the issues to keep among issues with the given severity and
+    /// kind. The most severe issues are kept first, and issues with the same
+    /// severity in their given order. Returns the indices of the kept issues
+    /// and the number of suppressed issues per kind.
+    pub fn select(
+        &self,
+        issues: &[(IssueSeverity, String)],
+    ) -> (Vec<usize>, BTreeMap<String, usize>) {
+        let mut selection = BudgetSelection::new(*self);
+        for (index, (severity, kind)) in issues.iter().enumerate() {
+            selection.push(*severity, kind, index);
+        }
+        let (kept, suppressed, _) = selection.finish();
+        (kept, suppressed)
+    }
+}
+
+impl Default for IssueBudget {
+    fn default() -> Self {
+        Self {
+            per_kind: Self::DEFAULT_PER_KIND,
+            total: Self::DEFAULT_TOTAL,
+        }
+    }
+}
+
+/// The issues that are kept within an [IssueBudget] so far. Issues are added
+/// one at a time, and an issue that exceeds the budget is dropped right away,
+/// so at most [IssueBudget::total] issues are held at any time.
+struct BudgetSelection<T> {
+    budget: IssueBudget,
+    /// The kept issues by severity and order of addition.
+    kept: BTreeMap<(IssueSeverity, usize), (String, T)>,
+    /// The keys of the kept issues by kind.
+    kept_per_kind: BTreeMap<String, BTreeSet<(IssueSeverity, usize)>>,
+    suppressed: BTreeMap<String, usize>,
+    first_suppressed: Option<T>,
+    next_index: usize,
+}
+
+impl<T> BudgetSelection<T> {
+    fn new(budget: IssueBudget) -> Self {
+        Self {
+            budget,
+            kept: BTreeMap::new(),
+            kept_per_kind: BTreeMap::new(),
+            suppressed: BTreeMap::new(),
+            first_suppressed: None,
+            next_index: 0,
+        }
+    }
+
+    fn push(&mut self, severity: IssueSeverity, kind: &str, issue: T) {
+        let key = (severity, self.next_index);
+        self.next_index += 1;
+
+        let of_kind = self.kept_per_kind.entry(kind.to_string()).or_default();
+        of_kind.insert(key);
+        self.kept.insert(key, (kind.to_string(), issue));
+        if of_kind.len() > self.budget.per_kind {
+            // The least severe issue of this kind is dropped.
+            let dropped = of_kind.pop_last().unwrap();
+            self.suppress(dropped);
+        }
+        if self.kept.len() > self.budget.total {
+            // The least severe issue of all kinds is dropped.
+            let (&dropped, (kind, _)) = self.kept.last_key_value().unwrap();
+            if let Some(of_kind) = self.kept_per_kind.get_mut(kind) {
+                of_kind.remove(&dropped);
+            }
+            self.suppress(dropped);
+        }
+    }
+
+    fn suppress(&mut self, key: (IssueSeverity, usize)) {
+        let (kind, issue) = self.kept.remove(&key).unwrap();
+        *self.suppressed.entry(kind).or_default() += 1;
+        self.first_suppressed.get_or_insert(issue);
+    }
+
+    /// Returns the kept issues in order of addition, the number of suppressed
+    /// issues per kind and the first suppressed issue.
+    fn finish(self) -> (Vec<T>, BTreeMap<String, usize>, Option<T>) {
+        let mut kept = self
+            .kept
+            .into_iter()
+            .map(|((_, index), (_, issue))| (index, issue))
+            .collect::<Vec<_>>();
+        kept.sort_unstable_by_key(|(index, _)| *index);
+        (
+            kept.into_iter().map(|(_, issue)| issue).collect(),
+            self.suppressed,
+            self.first_suppressed,
+        )
+    }
+}
+
+/// Drops the issues that exceed `budget`, and adds a [SuppressedIssues] issue
+/// in their place. Returns the number of dropped issues.
+///
+/// Severities and categories are read in batches, and issues that exceed the
+/// budget are dropped as they are read, so memory stays bounded by the budget
+/// rather than by the number of issues.
+pub(super) async fn apply_issue_budget(
+    issues: &mut AutoSet<IssueVc>,
+    budget: IssueBudget,
+) -> Result<usize> {
+    if issues.len() <= budget.per_kind.min(budget.total) {
+        return Ok(0);
+    }
+
+    let mut selection = BudgetSelection::new(budget);
+    let candidates = std::mem::take(issues).into_iter().collect::<Vec<_>>();
+    for batch in candidates.chunks(BATCH_SIZE) {
+        let kinds = batch
+            .iter()
+            .map(|issue| async move { Ok((*issue.severity().await?, issue.category().await?)) })
+            .try_join()
+            .await?;
+        for (&issue, (severity, kind)) in batch.iter().zip(kinds) {
+            selection.push(severity, &kind, issue);
+        }
+    }
+
+    let (kept, suppressed, first_suppressed) = selection.finish();
+    *issues = kept.into_iter().collect();
+    let Some(first_suppressed) = first_suppressed else {
+        return Ok(0);
+    };
+    let count = suppressed.values().sum();
+    issues.insert(
+        SuppressedIssues {
+            // The summary needs a context, so it takes that of a dropped issue.
+            context: first_suppressed.context(),
+            counts: suppressed,
+        }
+        .cell()
+        .into(),
+    );
+    Ok(count)
+}
+
+/// A summary of the issues that were dropped because they exceeded the
+/// [IssueBudget].
+#[turbo_tasks::value(shared)]
+pub struct SuppressedIssues {
+    /// The context of one of the dropped issues.
+    pub context: FileSystemPathVc,
+    /// The number of dropped issues per category.
+    pub counts: BTreeMap<String, usize>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for SuppressedIssues {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("issues".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        let count = self.counts.values().sum::<usize>();
+        StringVc::cell(format!("{} more issues were suppressed", count))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        let mut description = "Only the most severe issues of each category are reported. \
+                               Suppressed issues:"
+            .to_string();
+        for (kind, count) in &self.counts {
+            let kind = if kind.is_empty() {
+                "uncategorized"
+            } else {
+                kind
+            };
+            description.push_str(&format!("\n- {}: {}", kind, count));
+        }
+        StringVc::cell(description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(issues: &[(IssueSeverity, &str)]) -> Vec<(IssueSeverity, String)> {
+        issues
+            .iter()
+            .map(|(severity, kind)| (*severity, kind.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_select_keeps_everything_within_budget() {
+        let budget = IssueBudget {
+            per_kind: 2,
+            total: 4,
+        };
+        let issues = issues(&[
+            (IssueSeverity::Warning, "resolve"),
+            (IssueSeverity::Error, "parse"),
+            (IssueSeverity::Error, "resolve"),
+        ]);
+        let (kept, suppressed) = budget.select(&issues);
+        assert_eq!(kept, vec![0, 1, 2]);
+        assert!(suppressed.is_empty());
+    }
+
+    #[test]
+    fn test_select_keeps_most_severe_per_kind() {
+        let budget = IssueBudget {
+            per_kind: 2,
+            total: 10,
+        };
+        let issues = issues(&[
+            (IssueSeverity::Warning, "resolve"),
+            (IssueSeverity::Error, "resolve"),
+            (IssueSeverity::Warning, "parse"),
+            (IssueSeverity::Warning, "resolve"),
+            (IssueSeverity::Fatal, "resolve"),
+        ]);
+        let (kept, suppressed) = budget.select(&issues);
+        assert_eq!(kept, vec![1, 2, 4]);
+        assert_eq!(suppressed, BTreeMap::from([("resolve".to_string(), 2)]));
+    }
+
+    #[test]
+    fn test_select_keeps_most_severe_in_total() {
+        let budget = IssueBudget {
+            per_kind: 10,
+            total: 2,
+        };
+        let issues = issues(&[
+            (IssueSeverity::Warning, "resolve"),
+            (IssueSeverity::Error, "parse"),
+            (IssueSeverity::Warning, "parse"),
+            (IssueSeverity::Error, "resolve"),
+        ]);
+        let (kept, suppressed) = budget.select(&issues);
+        assert_eq!(kept, vec![1, 3]);
+        assert_eq!(
+            suppressed,
+            BTreeMap::from([("parse".to_string(), 1), ("resolve".to_string(), 1)])
+        );
+    }
+
+    #[test]
+    fn test_select_keeps_earlier_issues_of_same_severity() {
+        let budget = IssueBudget {
+            per_kind: 1,
+            total: 2,
+        };
+        let issues = issues(&[
+            (IssueSeverity::Error, "resolve"),
+            (IssueSeverity::Error, "resolve"),
+            (IssueSeverity::Error, "parse"),
+            (IssueSeverity::Error, "code gen"),
+        ]);
+        let (kept, suppressed) = budget.select(&issues);
+        assert_eq!(kept, vec![0, 2]);
+        assert_eq!(
+            suppressed,
+            BTreeMap::from([("code gen".to_string(), 1), ("resolve".to_string(), 1)])
+        );
+    }
+
+    #[test]
+    fn test_select_unlimited() {
+        let issues = (0..100)
+            .map(|index| (IssueSeverity::Warning, format!("kind {}", index % 3)))
+            .collect::<Vec<_>>();
+        let (kept, suppressed) = IssueBudget::unlimited().select(&issues);
+        assert_eq!(kept, (0..100).collect::<Vec<_>>());
+        assert!(suppressed.is_empty());
+    }
+}
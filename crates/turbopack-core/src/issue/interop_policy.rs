@@ -0,0 +1,82 @@
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{Issue, IssueSeverity, IssueSeverityVc, IssueSourceVc, IssueVc, OptionIssueSourceVc};
+use crate::compile_time_info::InteropPolicy;
+
+/// A way in which a module can violate an [InteropPolicy].
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Hash, PartialOrd, Ord)]
+pub enum InteropViolation {
+    /// The module exports values with CommonJS syntax.
+    CommonJsExports,
+    /// The module calls `require()`.
+    Require { request: String },
+    /// The module loads an EcmaScript module with `require()`.
+    RequireOfEsm { request: String },
+}
+
+#[turbo_tasks::value(shared)]
+pub struct InteropPolicyIssue {
+    pub path: FileSystemPathVc,
+    pub policy: InteropPolicy,
+    pub violation: InteropViolation,
+    pub source: Option<IssueSourceVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for InteropPolicyIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("module type".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "Module violates the {} interop policy",
+            self.policy.as_str()
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(match &self.violation {
+            InteropViolation::CommonJsExports => format!(
+                "The module exports values with CommonJS syntax, but the {} interop policy only \
+                 allows EcmaScript modules. Replace module.exports and exports with EcmaScript \
+                 export syntax.",
+                self.policy.as_str()
+            ),
+            InteropViolation::Require { request } => format!(
+                "The module requires {}, but the {} interop policy only allows EcmaScript \
+                 modules. Replace require() with an EcmaScript import.",
+                request,
+                self.policy.as_str()
+            ),
+            InteropViolation::RequireOfEsm { request } => format!(
+                "The module requires {}, which is an EcmaScript module. The {} interop policy \
+                 doesn't allow loading EcmaScript modules with require(). Use an EcmaScript \
+                 import or a dynamic import() instead.",
+                request,
+                self.policy.as_str()
+            ),
+        }))
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(self.source)
+    }
+}
@@ -1,7 +1,12 @@
 pub mod analyze;
+pub mod budget;
 pub mod code_gen;
+pub mod interop_policy;
+pub mod native_addon;
 pub mod package_json;
 pub mod resolve;
+pub mod snapshot;
+pub mod suppression;
 pub mod unsupported_module;
 
 use std::{
@@ -24,6 +29,10 @@ use turbo_tasks_fs::{
 };
 use turbo_tasks_hash::{DeterministicHash, Xxh3Hash64Hasher};
 
+use self::{
+    budget::{apply_issue_budget, IssueBudget, IssueBudgetVc},
+    suppression::{apply_issue_suppressions, IssueSuppressionsVc},
+};
 use crate::{
     asset::{Asset, AssetContent, AssetVc},
     source_pos::SourcePos,
@@ -320,35 +329,53 @@ impl IssueVc {
     }
 
     /// Returns all issues from `source` in a list with their associated
-    /// processing path.
+    /// processing path. Issues that match one of `suppressions` are dropped,
+    /// and issues that exceed `budget` are summarized.
     pub async fn peek_issues_with_path<T: CollectiblesSource + Copy>(
         source: T,
+        budget: IssueBudget,
+        suppressions: IssueSuppressionsVc,
     ) -> Result<CapturedIssuesVc> {
-        Ok(CapturedIssuesVc::cell(CapturedIssues {
-            issues: source.peek_collectibles().strongly_consistent().await?,
+        let mut issues = source.peek_collectibles().strongly_consistent().await?;
+        let silenced = apply_issue_suppressions(&mut issues, suppressions).await?;
+        let suppressed = apply_issue_budget(&mut issues, budget).await?;
+        let captured = CapturedIssues {
+            issues,
+            silenced,
+            suppressed,
             #[cfg(feature = "issue_path")]
             processing_path: ItemIssueProcessingPathVc::cell(ItemIssueProcessingPath(
                 None,
                 source.peek_collectibles().strongly_consistent().await?,
             )),
-        }))
+        };
+        Ok(CapturedIssuesVc::cell(captured))
     }
 
     /// Returns all issues from `source` in a list with their associated
-    /// processing path.
+    /// processing path. Issues that match one of `suppressions` are dropped,
+    /// and issues that exceed `budget` are summarized.
     ///
     /// This unemits the issues. They will not propagate up.
     pub async fn take_issues_with_path<T: CollectiblesSource + Copy>(
         source: T,
+        budget: IssueBudget,
+        suppressions: IssueSuppressionsVc,
     ) -> Result<CapturedIssuesVc> {
-        Ok(CapturedIssuesVc::cell(CapturedIssues {
-            issues: source.take_collectibles().strongly_consistent().await?,
+        let mut issues = source.take_collectibles().strongly_consistent().await?;
+        let silenced = apply_issue_suppressions(&mut issues, suppressions).await?;
+        let suppressed = apply_issue_budget(&mut issues, budget).await?;
+        let captured = CapturedIssues {
+            issues,
+            silenced,
+            suppressed,
             #[cfg(feature = "issue_path")]
             processing_path: ItemIssueProcessingPathVc::cell(ItemIssueProcessingPath(
                 None,
                 source.take_collectibles().strongly_consistent().await?,
             )),
-        }))
+        };
+        Ok(CapturedIssuesVc::cell(captured))
     }
 }
 
@@ -361,6 +388,12 @@ pub struct Issues(Vec<IssueVc>);
 #[turbo_tasks::value]
 pub struct CapturedIssues {
     issues: AutoSet<IssueVc>,
+    /// The number of issues that were dropped because they matched an issue
+    /// suppression.
+    silenced: usize,
+    /// The number of issues that were dropped because they exceeded the
+    /// issue budget.
+    suppressed: usize,
     #[cfg(feature = "issue_path")]
     processing_path: ItemIssueProcessingPathVc,
 }
@@ -384,6 +417,19 @@ impl CapturedIssues {
         self.issues.len()
     }
 
+    /// Returns the number of issues that were dropped because they matched an
+    /// active [issue suppression](suppression).
+    pub fn silenced(&self) -> usize {
+        self.silenced
+    }
+
+    /// Returns the number of issues that were dropped because they exceeded
+    /// the [IssueBudget]. They are summarized by a
+    /// [SuppressedIssues](budget::SuppressedIssues) issue.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
     /// Returns an iterator over the issues.
     pub fn iter(&self) -> impl Iterator<Item = IssueVc> + '_ {
         self.issues.iter().copied()
@@ -673,6 +719,96 @@ pub trait IssueReporter {
         issues: TransientInstance<ReadRef<CapturedIssues>>,
         source: TransientValue<RawVc>,
     ) -> BoolVc;
+
+    /// The [IssueBudget] for the issues that are captured for this reporter.
+    fn issue_budget(&self) -> IssueBudgetVc {
+        IssueBudget::default().cell()
+    }
+
+    /// The [suppressions](suppression) of the issues that are captured for
+    /// this reporter.
+    fn issue_suppressions(&self) -> IssueSuppressionsVc {
+        IssueSuppressionsVc::empty()
+    }
+}
+
+/// Receives the issues that an [IssueSinkReporter] reports, e.g. to stream
+/// diagnostics to an editor while a long build is still running.
+///
+/// The sink sees every issue that is reported at every capture point of the
+/// reporter, such as each request of the dev server. An issue that is reported
+/// multiple times is passed to the sink each time.
+/// [`PlainIssue::internal_hash`] can be used to deduplicate them.
+pub trait IssueSink: Send + Sync + 'static {
+    /// Called for each reported issue. `issue.processing_path` is the shortest
+    /// path that led to the issue.
+    fn on_issue(&self, issue: &PlainIssue);
+}
+
+impl<F> IssueSink for F
+where
+    F: Fn(&PlainIssue) + Send + Sync + 'static,
+{
+    fn on_issue(&self, issue: &PlainIssue) {
+        self(issue)
+    }
+}
+
+/// An [IssueReporter] that passes the reported issues to an [IssueSink] before
+/// reporting them with another reporter.
+///
+/// Issues are passed to the sink when they are reported rather than when they
+/// are captured, so the sink also receives issues of computations that are
+/// cached.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+pub struct IssueSinkReporter {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    sink: Arc<dyn IssueSink>,
+    reporter: IssueReporterVc,
+}
+
+impl PartialEq for IssueSinkReporter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::as_ptr(&self.sink).cast::<()>() == Arc::as_ptr(&other.sink).cast::<()>()
+            && self.reporter == other.reporter
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl IssueSinkReporterVc {
+    #[turbo_tasks::function]
+    pub fn new(sink: TransientInstance<Arc<dyn IssueSink>>, reporter: IssueReporterVc) -> Self {
+        IssueSinkReporter {
+            sink: (*sink).clone(),
+            reporter,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl IssueReporter for IssueSinkReporter {
+    #[turbo_tasks::function]
+    async fn report_issues(
+        &self,
+        issues: TransientInstance<ReadRef<CapturedIssues>>,
+        source: TransientValue<RawVc>,
+    ) -> Result<BoolVc> {
+        for issue in issues.get_plain_issues().await? {
+            self.sink.on_issue(&issue);
+        }
+        Ok(self.reporter.report_issues(issues, source))
+    }
+
+    #[turbo_tasks::function]
+    fn issue_budget(&self) -> IssueBudgetVc {
+        self.reporter.issue_budget()
+    }
+
+    #[turbo_tasks::function]
+    fn issue_suppressions(&self) -> IssueSuppressionsVc {
+        self.reporter.issue_suppressions()
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,41 @@
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+
+use super::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+/// A native addon that is externalized can't be loaded on the compile target,
+/// e.g. because the prebuild for the target is not installed.
+#[turbo_tasks::value(shared)]
+pub struct NativeAddonIssue {
+    pub path: FileSystemPathVc,
+    pub target: String,
+    pub description: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for NativeAddonIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!("Native addon is not available for {}", self.target))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.description.clone())
+    }
+}
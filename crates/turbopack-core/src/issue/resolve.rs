@@ -19,6 +19,8 @@ pub struct ResolvingIssue {
     pub resolve_options: ResolveOptionsVc,
     pub error_message: Option<String>,
     pub source: OptionIssueSourceVc,
+    /// Requests that might have been meant instead, best first.
+    pub candidates: Vec<String>,
 }
 
 #[turbo_tasks::value_impl]
@@ -48,10 +50,14 @@ impl Issue for ResolvingIssue {
 
     #[turbo_tasks::function]
     async fn description(&self) -> Result<StringVc> {
-        Ok(StringVc::cell(format!(
+        let mut description = format!(
             "unable to resolve {module_name}",
             module_name = self.request.to_string().await?
-        )))
+        );
+        if let Some(candidate) = self.candidates.first() {
+            write!(description, ", did you mean '{candidate}'?")?;
+        }
+        Ok(StringVc::cell(description))
     }
 
     #[turbo_tasks::function]
@@ -79,6 +85,13 @@ impl Issue for ResolvingIssue {
             "Type of request: {request_type}",
             request_type = self.request_type,
         )?;
+        if self.candidates.len() > 1 {
+            writeln!(
+                detail,
+                "Similar requests: {candidates}",
+                candidates = self.candidates.join(", ")
+            )?;
+        }
         if let Some(import_map) = &self.resolve_options.await?.import_map {
             let result = import_map.lookup(self.context, self.request);
 
@@ -0,0 +1,209 @@
+//! Stable textual snapshots of issues, for tests.
+//!
+//! [capture_issues] collects the issues that are emitted while computing a
+//! value and returns them as an [IssueSnapshot]. The snapshot renders the
+//! fields of each issue in a fixed format that doesn't depend on any
+//! [IssueReporter](super::IssueReporter): issues are sorted and deduplicated,
+//! and absolute paths registered with [IssueSnapshot::normalize_path] are
+//! replaced with placeholders. This allows tests to compare the issues against
+//! an expected string or a snapshot file.
+//!
+//! Processing paths are omitted, as they depend on the `issue_path` feature.
+
+use std::{
+    fmt::{self, Display, Write},
+    future::Future,
+};
+
+use anyhow::Result;
+use turbo_tasks::CollectiblesSource;
+
+use super::{
+    budget::IssueBudget, suppression::IssueSuppressionsVc, IssueVc, PlainIssue, PlainIssueReadRef,
+};
+
+/// Computes a value with `f` and captures the issues that are emitted while
+/// computing it.
+///
+/// The issues are taken from the value, so they don't propagate to the caller.
+/// All of them are captured, regardless of the default [IssueBudget] and of
+/// issue suppressions.
+/// Issues emitted by the calling task itself, rather than by the tasks that
+/// compute the value, are not captured.
+pub async fn capture_issues<T, F, Fut>(f: F) -> Result<(T, IssueSnapshot)>
+where
+    T: CollectiblesSource + Copy,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let value = f().await?;
+    let captured = IssueVc::take_issues_with_path(
+        value,
+        IssueBudget::unlimited(),
+        IssueSuppressionsVc::empty(),
+    )
+    .await?
+    .await?;
+    let issues = captured.get_plain_issues().await?;
+    Ok((value, IssueSnapshot::new(issues)))
+}
+
+/// A list of issues that renders as stable text, see the [module
+/// documentation](self).
+///
+/// ```text
+/// error [module type] [project]/src/index.js
+///   title: Module violates the strict-esm interop policy
+///   description: The module requires ./a, but the strict-esm interop policy ...
+///   source: [project]/src/index.js:3:1-3:15
+/// ```
+#[derive(Debug, Clone)]
+pub struct IssueSnapshot {
+    issues: Vec<PlainIssueReadRef>,
+    paths: Vec<(String, String)>,
+}
+
+impl IssueSnapshot {
+    pub fn new(issues: Vec<PlainIssueReadRef>) -> Self {
+        Self {
+            issues,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Replaces the absolute path `path`, e.g. the directory of a test
+    /// fixture, with `placeholder` in the rendered issues. Windows paths are
+    /// also replaced where their backslashes are escaped.
+    pub fn normalize_path(
+        mut self,
+        path: impl Into<String>,
+        placeholder: impl Into<String>,
+    ) -> Self {
+        let path = path.into();
+        let placeholder = placeholder.into();
+        if path.contains('\\') {
+            self.paths
+                .push((path.replace('\\', "\\\\"), placeholder.clone()));
+            self.paths
+                .push((path.replace('\\', "/"), placeholder.clone()));
+        }
+        self.paths.push((path, placeholder));
+        self
+    }
+
+    /// The captured issues, in no particular order.
+    pub fn issues(&self) -> &[PlainIssueReadRef] {
+        &self.issues
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    /// Renders the issues, sorted and without duplicates.
+    pub fn render(&self) -> String {
+        let mut rendered = self
+            .issues
+            .iter()
+            .map(|issue| {
+                let mut out = String::new();
+                self.render_issue(issue, 0, &mut out)
+                    .expect("writing to a String can't fail");
+                out
+            })
+            .collect::<Vec<_>>();
+        rendered.sort();
+        rendered.dedup();
+        rendered.join("\n")
+    }
+
+    fn render_issue(&self, issue: &PlainIssue, indent: usize, out: &mut String) -> fmt::Result {
+        let pad = " ".repeat(indent);
+        writeln!(
+            out,
+            "{pad}{} [{}] {}",
+            issue.severity.as_str(),
+            issue.category,
+            self.normalize(&issue.context)
+        )?;
+        self.render_field(out, indent + 2, "title", &issue.title)?;
+        self.render_field(out, indent + 2, "description", &issue.description)?;
+        self.render_field(out, indent + 2, "detail", &issue.detail)?;
+        self.render_field(out, indent + 2, "documentation", &issue.documentation_link)?;
+        if let Some(source) = &issue.source {
+            writeln!(
+                out,
+                "{pad}  source: {}:{}:{}-{}:{}",
+                self.normalize(&source.asset.ident),
+                source.start.line + 1,
+                source.start.column + 1,
+                source.end.line + 1,
+                source.end.column + 1
+            )?;
+        }
+        if !issue.sub_issues.is_empty() {
+            let mut sub_issues = issue
+                .sub_issues
+                .iter()
+                .map(|sub_issue| {
+                    let mut out = String::new();
+                    self.render_issue(sub_issue, indent + 4, &mut out)
+                        .map(|_| out)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            sub_issues.sort();
+            writeln!(out, "{pad}  sub issues:")?;
+            for sub_issue in sub_issues {
+                out.push_str(&sub_issue);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a field on a single line, or indented below its name if it spans
+    /// multiple lines. Empty fields are omitted.
+    fn render_field(
+        &self,
+        out: &mut String,
+        indent: usize,
+        name: &str,
+        value: &str,
+    ) -> fmt::Result {
+        let value = self.normalize(value);
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+        let pad = " ".repeat(indent);
+        if !value.contains('\n') {
+            return writeln!(out, "{pad}{name}: {value}");
+        }
+        writeln!(out, "{pad}{name}:")?;
+        for line in value.lines().map(str::trim_end) {
+            if line.is_empty() {
+                writeln!(out)?;
+            } else {
+                writeln!(out, "{pad}  {line}")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        self.paths
+            .iter()
+            .fold(text.to_string(), |text, (path, placeholder)| {
+                text.replace(path, placeholder)
+            })
+    }
+}
+
+impl Display for IssueSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
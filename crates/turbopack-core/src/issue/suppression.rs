@@ -0,0 +1,488 @@
+//! Suppressing known issues with a checked-in suppression file.
+//!
+//! Raising the severity of an issue category in a large project reports every
+//! existing violation at once. A suppression file lists the violations that
+//! are accepted for now, so that teams can fix them incrementally while new
+//! violations are reported:
+//!
+//! ```json
+//! {
+//!   "suppressions": [
+//!     {
+//!       "code": "resolve",
+//!       "path": "packages/legacy/**",
+//!       "expires": "2024-06-30",
+//!       "reason": "legacy package is being migrated"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `code` is matched against the [category](super::Issue::category) of an
+//! issue, or matches every category when it's `*`. `path` is a glob that is
+//! matched against the [context](super::Issue::context) of an issue, relative
+//! to the root of its file system. Issues that match a suppression aren't
+//! captured, see [CapturedIssues::silenced](super::CapturedIssues::silenced).
+//!
+//! Suppressions with an `expires` date stop suppressing issues after that
+//! day. The issues that they matched are reported again, but as warnings that
+//! point to the expired suppression, so that an expired suppression doesn't
+//! break builds on its own.
+//!
+//! Embedders read the file with [IssueSuppressionsVc::read] and pass it to
+//! [IssueVc::peek_issues_with_path] and [IssueVc::take_issues_with_path],
+//! usually through [IssueReporter::issue_suppressions]. As the file is read
+//! through a [FileSystemPathVc], changing it captures the issues again.
+//!
+//! [IssueReporter::issue_suppressions]: super::IssueReporter::issue_suppressions
+
+use std::{
+    fmt::{self, Display},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+use auto_hash_map::AutoSet;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs, TryJoinIterExt};
+use turbo_tasks_fs::{glob::Glob, FileContent, FileSystemPathVc};
+
+use super::{Issue, IssueSeverity, IssueSeverityVc, IssueVc, IssuesVc, OptionIssueSourceVc};
+
+/// The conventional name of the suppression file in the directory of an
+/// application.
+pub const ISSUE_SUPPRESSIONS_FILE_NAME: &str = "turbopack-suppressions.json";
+
+/// A day of the proleptic Gregorian calendar.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TraceRawVcs,
+)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Parses a `YYYY-MM-DD` date.
+    pub fn parse(date: &str) -> Result<Self> {
+        let invalid = || anyhow!("{} is not a YYYY-MM-DD date", date);
+        let mut parts = date.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let date = Date {
+            year: next()?.parse().map_err(|_| invalid())?,
+            month: next()?.parse().map_err(|_| invalid())?,
+            day: next()?.parse().map_err(|_| invalid())?,
+        };
+        if !(1..=12).contains(&date.month)
+            || date.day == 0
+            || date.day > days_in_month(date.year, date.month)
+        {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+
+    /// The current day in UTC.
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self::from_days_since_epoch((seconds / 86_400) as i64)
+    }
+
+    /// Converts days since 1970-01-01, see
+    /// http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Date {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// An entry of a suppression file, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct IssueSuppression {
+    /// The category of the suppressed issues, or `*` for every category.
+    pub code: String,
+    /// The glob that the context paths of suppressed issues match.
+    pub path: String,
+    glob: Glob,
+    /// The last day on which the suppression applies.
+    pub expires: Option<Date>,
+    pub reason: Option<String>,
+}
+
+impl IssueSuppression {
+    pub fn new(code: impl Into<String>, path: impl Into<String>) -> Result<Self> {
+        let path = path.into();
+        Ok(IssueSuppression {
+            code: code.into(),
+            glob: Glob::parse(&path).with_context(|| format!("invalid path glob {}", path))?,
+            path,
+            expires: None,
+            reason: None,
+        })
+    }
+
+    pub fn expires(mut self, expires: Date) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    pub fn reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    fn matches(&self, category: &str, path: &str) -> bool {
+        (self.code == "*" || self.code == category) && self.glob.execute(path)
+    }
+
+    fn is_expired(&self, today: Date) -> bool {
+        self.expires.map_or(false, |expires| expires < today)
+    }
+}
+
+impl Display for IssueSuppression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}", self.code, self.path)?;
+        if let Some(expires) = self.expires {
+            write!(f, " until {}", expires)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a list of suppressions applies to an issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SuppressionMatch {
+    None,
+    /// A suppression that didn't expire matches the issue.
+    Active,
+    /// Only expired suppressions match the issue. Contains the index of the
+    /// first one.
+    Expired(usize),
+}
+
+/// The suppressions of a suppression file, see the [module
+/// documentation](self).
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default)]
+pub struct IssueSuppressions {
+    suppressions: Vec<IssueSuppression>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuppressionFile {
+    suppressions: Vec<SuppressionEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuppressionEntry {
+    code: String,
+    path: String,
+    expires: Option<String>,
+    reason: Option<String>,
+}
+
+impl IssueSuppressions {
+    pub fn new(suppressions: Vec<IssueSuppression>) -> Self {
+        IssueSuppressions { suppressions }
+    }
+
+    /// Parses the content of a suppression file.
+    pub fn parse(json: &str) -> Result<Self> {
+        let file: SuppressionFile =
+            serde_json::from_str(json).context("invalid issue suppression file")?;
+        let suppressions = file
+            .suppressions
+            .into_iter()
+            .map(|entry| {
+                let mut suppression = IssueSuppression::new(entry.code, entry.path)?;
+                suppression.expires = entry.expires.as_deref().map(Date::parse).transpose()?;
+                suppression.reason = entry.reason;
+                Ok(suppression)
+            })
+            .collect::<Result<_>>()?;
+        Ok(IssueSuppressions { suppressions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.suppressions.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &IssueSuppression> {
+        self.suppressions.iter()
+    }
+
+    fn find(&self, category: &str, path: &str, today: Date) -> SuppressionMatch {
+        let mut result = SuppressionMatch::None;
+        for (index, suppression) in self.suppressions.iter().enumerate() {
+            if !suppression.matches(category, path) {
+                continue;
+            }
+            if !suppression.is_expired(today) {
+                return SuppressionMatch::Active;
+            }
+            if result == SuppressionMatch::None {
+                result = SuppressionMatch::Expired(index);
+            }
+        }
+        result
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl IssueSuppressionsVc {
+    /// Suppresses no issues.
+    #[turbo_tasks::function]
+    pub fn empty() -> Self {
+        IssueSuppressions::default().cell()
+    }
+
+    /// Reads a suppression file. A missing file doesn't suppress any issues.
+    #[turbo_tasks::function]
+    pub async fn read(path: FileSystemPathVc) -> Result<Self> {
+        let FileContent::Content(file) = &*path.read().await? else {
+            return Ok(Self::empty());
+        };
+        let json = file.content().to_str()?;
+        let file_path = path.await?;
+        let suppressions = IssueSuppressions::parse(&json)
+            .with_context(|| format!("failed to read {}", file_path.path))?;
+        Ok(suppressions.cell())
+    }
+}
+
+/// Drops the issues that match an active suppression of `suppressions`, and
+/// replaces the issues that only match expired suppressions with
+/// [ExpiredSuppressionIssue]s. Returns the number of dropped issues.
+pub(super) async fn apply_issue_suppressions(
+    issues: &mut AutoSet<IssueVc>,
+    suppressions: IssueSuppressionsVc,
+) -> Result<usize> {
+    let suppressions = suppressions.await?;
+    if suppressions.is_empty() || issues.is_empty() {
+        return Ok(0);
+    }
+
+    let today = Date::today();
+    let candidates = issues.iter().copied().collect::<Vec<_>>();
+    let matches = candidates
+        .iter()
+        .map(|issue| {
+            let suppressions = &suppressions;
+            async move {
+                let category = issue.category().await?;
+                let context = issue.context().await?;
+                Ok(suppressions.find(&category, &context.path, today))
+            }
+        })
+        .try_join()
+        .await?;
+
+    let mut kept = Vec::with_capacity(candidates.len());
+    for (issue, found) in candidates.iter().zip(matches) {
+        match found {
+            SuppressionMatch::None => kept.push(*issue),
+            SuppressionMatch::Active => {}
+            SuppressionMatch::Expired(index) => kept.push(
+                ExpiredSuppressionIssue {
+                    issue: *issue,
+                    suppression: suppressions.suppressions[index].to_string(),
+                }
+                .cell()
+                .into(),
+            ),
+        }
+    }
+    let silenced = candidates.len() - kept.len();
+    *issues = kept.into_iter().collect();
+    Ok(silenced)
+}
+
+/// An issue that an expired suppression matches. It's reported like the
+/// original issue, but at most as a warning.
+#[turbo_tasks::value(shared)]
+pub struct ExpiredSuppressionIssue {
+    pub issue: IssueVc,
+    /// A description of the expired suppression.
+    pub suppression: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ExpiredSuppressionIssue {
+    #[turbo_tasks::function]
+    async fn severity(&self) -> Result<IssueSeverityVc> {
+        let severity = *self.issue.severity().await?;
+        Ok(severity.max(IssueSeverity::Warning).into())
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        self.issue.category()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.issue.context()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        self.issue.title()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.issue.description()
+    }
+
+    #[turbo_tasks::function]
+    async fn detail(&self) -> Result<StringVc> {
+        let detail = self.issue.detail().await?;
+        let note = format!(
+            "The suppression for {} doesn't apply anymore. Fix the issue or extend the \
+             suppression.",
+            self.suppression
+        );
+        Ok(StringVc::cell(if detail.is_empty() {
+            note
+        } else {
+            format!("{}\n\n{}", detail, note)
+        }))
+    }
+
+    #[turbo_tasks::function]
+    fn documentation_link(&self) -> StringVc {
+        self.issue.documentation_link()
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        self.issue.source()
+    }
+
+    #[turbo_tasks::function]
+    fn sub_issues(&self) -> IssuesVc {
+        self.issue.sub_issues()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> Date {
+        Date::parse(date).unwrap()
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(
+            date("2024-02-29"),
+            Date {
+                year: 2024,
+                month: 2,
+                day: 29
+            }
+        );
+        assert!(Date::parse("2023-02-29").is_err());
+        assert!(Date::parse("2023-13-01").is_err());
+        assert!(Date::parse("2023-01").is_err());
+        assert!(Date::parse("tomorrow").is_err());
+        assert_eq!(date("2023-01-05").to_string(), "2023-01-05");
+    }
+
+    #[test]
+    fn converts_days_since_epoch() {
+        assert_eq!(Date::from_days_since_epoch(0), date("1970-01-01"));
+        assert_eq!(Date::from_days_since_epoch(19_782), date("2024-02-29"));
+        assert_eq!(Date::from_days_since_epoch(-1), date("1969-12-31"));
+    }
+
+    #[test]
+    fn parses_suppression_files() {
+        let suppressions = IssueSuppressions::parse(
+            r#"{
+                "suppressions": [
+                    { "code": "resolve", "path": "packages/legacy/**" },
+                    { "code": "*", "path": "src/old.js", "expires": "2023-06-30", "reason": "x" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let suppressions = suppressions.iter().collect::<Vec<_>>();
+        assert_eq!(suppressions.len(), 2);
+        assert_eq!(suppressions[1].expires, Some(date("2023-06-30")));
+        assert_eq!(suppressions[1].reason.as_deref(), Some("x"));
+
+        assert!(IssueSuppressions::parse(r#"{ "suppressions": [{ "code": "x" }] }"#).is_err());
+        assert!(IssueSuppressions::parse(
+            r#"{ "suppressions": [{ "code": "x", "path": "src/**", "expires": "soon" }] }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn finds_active_and_expired_suppressions() {
+        let suppressions = IssueSuppressions::new(vec![
+            IssueSuppression::new("resolve", "packages/legacy/**")
+                .unwrap()
+                .expires(date("2023-06-30")),
+            IssueSuppression::new("*", "src/old.js").unwrap(),
+        ]);
+        let before = date("2023-06-30");
+        let after = date("2023-07-01");
+        assert_eq!(
+            suppressions.find("resolve", "packages/legacy/index.js", before),
+            SuppressionMatch::Active
+        );
+        assert_eq!(
+            suppressions.find("resolve", "packages/legacy/index.js", after),
+            SuppressionMatch::Expired(0)
+        );
+        assert_eq!(
+            suppressions.find("parse", "packages/legacy/index.js", before),
+            SuppressionMatch::None
+        );
+        assert_eq!(
+            suppressions.find("parse", "src/old.js", after),
+            SuppressionMatch::Active
+        );
+    }
+}
@@ -4,19 +4,25 @@
 #![feature(type_alias_impl_trait)]
 #![feature(assert_matches)]
 #![feature(lint_reasons)]
+#![feature(file_set_times)]
 
 pub mod asset;
+pub mod build_session;
 pub mod changed;
 pub mod chunk;
 pub mod code_builder;
 pub mod compile_time_info;
 pub mod context;
+pub mod deterministic;
+pub mod dev_overlay;
+pub mod emit_transaction;
 pub mod environment;
 pub mod error;
 pub mod ident;
 pub mod introspect;
 pub mod issue;
 pub mod plugin;
+pub mod progress;
 pub mod proxied_asset;
 pub mod reference;
 pub mod reference_type;
@@ -27,6 +33,7 @@ pub mod source_map;
 pub mod source_pos;
 pub mod source_transform;
 pub mod target;
+pub mod test_utils;
 mod utils;
 pub mod version;
 pub mod virtual_asset;
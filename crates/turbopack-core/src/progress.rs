@@ -0,0 +1,244 @@
+//! Progress of long builds, e.g. to render progress bars in a CLI.
+//!
+//! A [BuildSession] reports [ProgressEvent]s to the [ProgressSender] that is
+//! set with [BuildSession::set_progress]. Each event is stamped with the
+//! [BuildPhase] that it belongs to and counts the work that the phase has
+//! completed so far. Phases that walk the module graph only learn about more
+//! work as they go, so their total can grow until they are done.
+//! `turbopack_cli_utils::progress::render_progress` prints the events of a
+//! build to the terminal.
+//!
+//! [BuildSession]: crate::build_session::BuildSession
+//! [BuildSession::set_progress]: crate::build_session::BuildSession::set_progress
+
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// A phase of a build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuildPhase {
+    /// Resolving the references of modules.
+    Resolving,
+    /// Parsing, transforming and analyzing modules.
+    Transforming,
+    /// Placing modules into chunks and collecting the output assets.
+    Chunking,
+    /// Writing output assets to the output directory.
+    Emitting,
+}
+
+impl Display for BuildPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BuildPhase::Resolving => "resolving",
+            BuildPhase::Transforming => "transforming",
+            BuildPhase::Chunking => "chunking",
+            BuildPhase::Emitting => "emitting",
+        })
+    }
+}
+
+/// The progress of a phase of a build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressEvent {
+    pub phase: BuildPhase,
+    /// What the phase works on, e.g. the entry that is built.
+    pub subject: String,
+    /// The number of completed items, e.g. modules or files.
+    pub completed: usize,
+    /// The number of items that are known so far.
+    pub total: usize,
+    /// The time since the phase started.
+    pub elapsed: Duration,
+    /// Whether the phase is done. No more events of the phase follow.
+    pub done: bool,
+}
+
+impl ProgressEvent {
+    /// A hint of how long the phase still takes, assuming that the remaining
+    /// items take as long as the completed ones did on average. `None` before
+    /// any item is completed and once the phase is done.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.done || self.completed == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.completed);
+        Some(
+            self.elapsed
+                .mul_f64(remaining as f64 / self.completed as f64),
+        )
+    }
+}
+
+impl Display for ProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}/{})",
+            self.phase, self.subject, self.completed, self.total
+        )?;
+        if let Some(eta) = self.eta() {
+            write!(f, ", about {}s left", eta.as_secs().max(1))?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the [ProgressEvent]s of a build, e.g. to render them.
+pub type ProgressReceiver = UnboundedReceiver<ProgressEvent>;
+
+/// Creates a channel that a build reports its progress to.
+pub fn progress_channel() -> (ProgressSender, ProgressReceiver) {
+    let (sender, receiver) = unbounded_channel();
+    (ProgressSender(Some(sender)), receiver)
+}
+
+/// Sends [ProgressEvent]s to a receiver created by [progress_channel]. The
+/// default sender drops all events. Events are dropped as well once the
+/// receiver is gone, as progress doesn't affect the build.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressSender(Option<UnboundedSender<ProgressEvent>>);
+
+impl ProgressSender {
+    /// Starts a phase that works on `subject` and has `total` items so far.
+    pub fn start(
+        &self,
+        phase: BuildPhase,
+        subject: impl Into<String>,
+        total: usize,
+    ) -> PhaseProgress {
+        let progress = PhaseProgress {
+            sender: self.clone(),
+            phase,
+            subject: subject.into(),
+            start: Instant::now(),
+            completed: AtomicUsize::new(0),
+            total: AtomicUsize::new(total),
+        };
+        progress.report(false);
+        progress
+    }
+
+    /// Whether events are sent to a receiver. Builds skip work that only
+    /// serves to report progress otherwise.
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn send(&self, event: ProgressEvent) {
+        if let Some(sender) = &self.0 {
+            let _ = sender.send(event);
+        }
+    }
+}
+
+/// Counts the items of a phase that was started with
+/// [ProgressSender::start]. The counters can be updated concurrently, e.g.
+/// from the futures of the modules that are transformed in parallel.
+#[derive(Debug)]
+pub struct PhaseProgress {
+    sender: ProgressSender,
+    phase: BuildPhase,
+    subject: String,
+    start: Instant,
+    completed: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl PhaseProgress {
+    /// Adds `items` that the phase discovered to the total.
+    pub fn grow(&self, items: usize) {
+        self.total.fetch_add(items, Ordering::Relaxed);
+    }
+
+    /// Marks `items` as completed and reports the progress.
+    pub fn advance(&self, items: usize) {
+        self.completed.fetch_add(items, Ordering::Relaxed);
+        self.report(false);
+    }
+
+    /// Reports that the phase is done.
+    pub fn finish(self) {
+        self.report(true);
+    }
+
+    fn report(&self, done: bool) {
+        self.sender.send(ProgressEvent {
+            phase: self.phase,
+            subject: self.subject.clone(),
+            completed: self.completed.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            elapsed: self.start.elapsed(),
+            done,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(completed: usize, total: usize, elapsed: u64, done: bool) -> ProgressEvent {
+        ProgressEvent {
+            phase: BuildPhase::Transforming,
+            subject: "src/index.js".to_string(),
+            completed,
+            total,
+            elapsed: Duration::from_secs(elapsed),
+            done,
+        }
+    }
+
+    #[test]
+    fn eta_extrapolates_completed_items() {
+        assert_eq!(event(0, 10, 2, false).eta(), None);
+        assert_eq!(event(5, 10, 2, false).eta(), Some(Duration::from_secs(2)));
+        assert_eq!(event(10, 10, 2, false).eta(), Some(Duration::ZERO));
+        assert_eq!(event(10, 10, 2, true).eta(), None);
+        assert_eq!(
+            event(5, 10, 2, false).to_string(),
+            "transforming src/index.js (5/10), about 2s left"
+        );
+        assert_eq!(
+            event(10, 10, 2, true).to_string(),
+            "transforming src/index.js (10/10)"
+        );
+    }
+
+    #[test]
+    fn phases_report_events() {
+        let (sender, mut receiver) = progress_channel();
+        let progress = sender.start(BuildPhase::Resolving, "src/index.js", 1);
+        progress.grow(2);
+        progress.advance(1);
+        progress.finish();
+        drop(sender);
+
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push((event.phase, event.completed, event.total, event.done));
+        }
+        assert_eq!(
+            events,
+            vec![
+                (BuildPhase::Resolving, 0, 1, false),
+                (BuildPhase::Resolving, 1, 3, false),
+                (BuildPhase::Resolving, 1, 3, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_sender_drops_events() {
+        assert!(!ProgressSender::default().is_enabled());
+        assert!(progress_channel().0.is_enabled());
+        let progress = ProgressSender::default().start(BuildPhase::Emitting, "out", 1);
+        progress.advance(1);
+        progress.finish();
+    }
+}
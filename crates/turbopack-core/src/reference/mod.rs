@@ -103,6 +103,7 @@ pub async fn all_referenced_assets(asset: AssetVc) -> Result<AssetsVc> {
         let ResolveResult {
             primary,
             references,
+            ..
         } = &*resolve_result.await?;
         for result in primary {
             if let PrimaryResolveResult::Asset(asset) = *result {
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{debug::ValueDebugFormat, trace::TraceRawVcs};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemPathVc};
+
+use super::{
+    parse::{Request, RequestVc},
+    pattern::Pattern,
+};
+
+/// The most candidates that are collected for a request.
+const MAX_CANDIDATES: usize = 5;
+
+/// Why a [ResolveCandidate] might be what an unresolveable request meant.
+/// Candidates are ranked in the order of the variants.
+#[derive(
+    TraceRawVcs,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Debug,
+    Serialize,
+    Deserialize,
+    ValueDebugFormat,
+)]
+pub enum ResolveCandidateKind {
+    /// The file name only differs in casing, e.g. `./button` for `Button.tsx`.
+    WrongCase,
+    /// The file has an extension that isn't resolved implicitly, e.g.
+    /// `./styles` for `styles.css`.
+    MissingExtension,
+    /// The file name is `distance` edits away from the request, e.g. `./Buton`
+    /// for `Button.tsx`.
+    Typo { distance: usize },
+}
+
+/// A near miss of a request that couldn't be resolved, which issues show as
+/// "did you mean ...?" and tools can offer as a fix.
+#[derive(TraceRawVcs, PartialEq, Eq, Clone, Debug, Serialize, Deserialize, ValueDebugFormat)]
+pub struct ResolveCandidate {
+    /// The request as it would need to be written, e.g. `./Button.tsx`.
+    pub request: String,
+    pub path: FileSystemPathVc,
+    pub kind: ResolveCandidateKind,
+}
+
+/// Collects the files next to the path of a relative `request` whose names
+/// are close to the requested name, ranked by [ResolveCandidateKind]. Only
+/// requests without dynamic parts have candidates.
+pub(super) async fn find_candidates(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    extensions: &[String],
+) -> Result<Vec<ResolveCandidate>> {
+    let request_path = match &*request.await? {
+        Request::Relative {
+            path: Pattern::Constant(path),
+            ..
+        }
+        | Request::Raw {
+            path: Pattern::Constant(path),
+            ..
+        } => path.clone(),
+        _ => return Ok(Vec::new()),
+    };
+    let (dir, name) = match request_path.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", request_path.as_str()),
+    };
+    if name.is_empty() || name == "." || name == ".." {
+        return Ok(Vec::new());
+    }
+    let Some(dir_path) = *context.try_join(dir).await? else {
+        return Ok(Vec::new());
+    };
+    let DirectoryContent::Entries(entries) = &*dir_path.read_dir().await? else {
+        return Ok(Vec::new());
+    };
+
+    let mut candidates = entries
+        .iter()
+        .filter_map(|(entry_name, entry)| {
+            let path = match entry {
+                DirectoryEntry::File(path)
+                | DirectoryEntry::Directory(path)
+                | DirectoryEntry::Symlink(path) => *path,
+                DirectoryEntry::Other(_) | DirectoryEntry::Error => return None,
+            };
+            let kind = candidate_kind(name, entry_name, extensions)?;
+            let request = if dir.is_empty() {
+                entry_name.clone()
+            } else {
+                format!("{dir}/{entry_name}")
+            };
+            Some(ResolveCandidate {
+                request,
+                path,
+                kind,
+            })
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.request.cmp(&b.request)));
+    candidates.truncate(MAX_CANDIDATES);
+    Ok(candidates)
+}
+
+/// Whether the directory entry `entry` might be meant by the requested file
+/// `name`, when the request is resolved with `extensions`.
+fn candidate_kind(name: &str, entry: &str, extensions: &[String]) -> Option<ResolveCandidateKind> {
+    if entry == name {
+        return None;
+    }
+    // The request as it is resolved, with each of the extensions.
+    let resolved_names = std::iter::once(name.to_string())
+        .chain(extensions.iter().map(|ext| format!("{name}{ext}")))
+        .collect::<Vec<_>>();
+    if resolved_names.iter().any(|resolved| resolved == entry) {
+        return None;
+    }
+    if resolved_names
+        .iter()
+        .any(|resolved| resolved.eq_ignore_ascii_case(entry))
+    {
+        return Some(ResolveCandidateKind::WrongCase);
+    }
+    if entry
+        .strip_prefix(name)
+        .map_or(false, |ext| ext.starts_with('.'))
+    {
+        return Some(ResolveCandidateKind::MissingExtension);
+    }
+
+    // Extensions that are resolved implicitly don't count as edits.
+    let entry_stem = extensions
+        .iter()
+        .find_map(|ext| entry.strip_suffix(ext.as_str()))
+        .unwrap_or(entry);
+    let distance = edit_distance(name, entry).min(edit_distance(name, entry_stem));
+    // Allow about one typo per three characters, so that short names don't
+    // match everything.
+    if distance <= (name.chars().count() / 3).max(1) {
+        Some(ResolveCandidateKind::Typo { distance })
+    } else {
+        None
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{candidate_kind, edit_distance, ResolveCandidateKind};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("Button", "Button"), 0);
+        assert_eq!(edit_distance("Buton", "Button"), 1);
+        assert_eq!(edit_distance("Butotn", "Button"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_candidate_kind() {
+        let extensions = [".tsx".to_string(), ".ts".to_string(), ".js".to_string()];
+        let kind = |name, entry| candidate_kind(name, entry, &extensions);
+
+        // Entries that the request resolves to aren't candidates.
+        assert_eq!(kind("Button", "Button.tsx"), None);
+        assert_eq!(kind("Button.tsx", "Button.tsx"), None);
+
+        assert_eq!(
+            kind("button", "Button.tsx"),
+            Some(ResolveCandidateKind::WrongCase)
+        );
+        assert_eq!(
+            kind("styles", "styles.css"),
+            Some(ResolveCandidateKind::MissingExtension)
+        );
+        assert_eq!(
+            kind("Buton", "Button.tsx"),
+            Some(ResolveCandidateKind::Typo { distance: 1 })
+        );
+        assert_eq!(
+            kind("Button.js", "Button.tsx"),
+            Some(ResolveCandidateKind::Typo { distance: 2 })
+        );
+        assert_eq!(kind("a", "index.js"), None);
+        assert_eq!(kind("Button", "Header.tsx"), None);
+
+        assert!(
+            ResolveCandidateKind::WrongCase < ResolveCandidateKind::MissingExtension
+                && ResolveCandidateKind::MissingExtension
+                    < ResolveCandidateKind::Typo { distance: 1 }
+                && ResolveCandidateKind::Typo { distance: 1 }
+                    < ResolveCandidateKind::Typo { distance: 2 }
+        );
+    }
+}
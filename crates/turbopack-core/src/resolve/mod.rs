@@ -3,6 +3,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     future::Future,
     pin::Pin,
+    time::Instant,
 };
 
 use anyhow::{anyhow, Result};
@@ -27,6 +28,7 @@ use self::{
 };
 use crate::{
     asset::{Asset, AssetOptionVc, AssetVc, AssetsVc},
+    introspect::asset_timing::{is_asset_timing_enabled, record_asset_timing, TimingPhase},
     issue::{
         package_json::{PackageJsonIssue, PackageJsonIssueVc},
         resolve::{ResolvingIssue, ResolvingIssueVc},
@@ -42,17 +44,21 @@ use crate::{
 };
 
 mod alias_map;
+mod candidates;
 pub(crate) mod exports;
+pub mod native_addon;
 pub mod node;
 pub mod options;
 pub mod origin;
 pub mod parse;
 pub mod pattern;
 pub mod plugin;
+pub mod virtual_modules;
 
 pub use alias_map::{
     AliasMap, AliasMapIntoIter, AliasMapLookupIterator, AliasMatch, AliasPattern, AliasTemplate,
 };
+pub use candidates::{ResolveCandidate, ResolveCandidateKind};
 pub use exports::{ExportsValue, ResolveAliasMap, ResolveAliasMapVc};
 
 use crate::issue::{IssueSeverity, IssueSeverityVc, OptionIssueSourceVc};
@@ -74,6 +80,9 @@ pub enum PrimaryResolveResult {
 pub struct ResolveResult {
     pub primary: Vec<PrimaryResolveResult>,
     pub references: Vec<AssetReferenceVc>,
+    /// Near misses of an unresolveable request, when
+    /// [ResolveOptions::collect_candidates] is enabled. Best candidates first.
+    pub candidates: Vec<ResolveCandidate>,
 }
 
 impl Default for ResolveResult {
@@ -87,6 +96,7 @@ impl ResolveResult {
         ResolveResult {
             primary: Vec::new(),
             references: Vec::new(),
+            candidates: Vec::new(),
         }
     }
 
@@ -94,6 +104,7 @@ impl ResolveResult {
         ResolveResult {
             primary: Vec::new(),
             references,
+            candidates: Vec::new(),
         }
     }
 
@@ -101,6 +112,7 @@ impl ResolveResult {
         ResolveResult {
             primary: vec![result],
             references: Vec::new(),
+            candidates: Vec::new(),
         }
     }
 
@@ -111,6 +123,7 @@ impl ResolveResult {
         ResolveResult {
             primary: vec![result],
             references,
+            candidates: Vec::new(),
         }
     }
 
@@ -118,6 +131,7 @@ impl ResolveResult {
         ResolveResult {
             primary: vec![PrimaryResolveResult::Asset(asset)],
             references: Vec::new(),
+            candidates: Vec::new(),
         }
     }
 
@@ -128,6 +142,7 @@ impl ResolveResult {
         ResolveResult {
             primary: vec![PrimaryResolveResult::Asset(asset)],
             references,
+            candidates: Vec::new(),
         }
     }
 
@@ -138,6 +153,7 @@ impl ResolveResult {
                 .map(PrimaryResolveResult::Asset)
                 .collect(),
             references: Vec::new(),
+            candidates: Vec::new(),
         }
     }
 
@@ -151,6 +167,7 @@ impl ResolveResult {
                 .map(PrimaryResolveResult::Asset)
                 .collect(),
             references,
+            candidates: Vec::new(),
         }
     }
 
@@ -166,12 +183,14 @@ impl ResolveResult {
         ResolveResult {
             primary: self.primary.clone(),
             references,
+            candidates: self.candidates.clone(),
         }
     }
 
     pub fn merge_alternatives(&mut self, other: &ResolveResult) {
         self.primary.extend(other.primary.iter().cloned());
         self.references.extend(other.references.iter().copied());
+        self.candidates.extend(other.candidates.iter().cloned());
     }
 
     pub fn is_unresolveable(&self) -> bool {
@@ -209,6 +228,7 @@ impl ResolveResult {
                 .map(reference_fn)
                 .try_join()
                 .await?,
+            candidates: self.candidates.clone(),
         })
     }
 }
@@ -609,8 +629,24 @@ pub async fn resolve(
     request: RequestVc,
     options: ResolveOptionsVc,
 ) -> Result<ResolveResultVc> {
+    let start = Instant::now();
     let raw_result = resolve_internal(context, request, options);
-    let result = handle_resolve_plugins(context, request, options, raw_result);
+    let mut result = handle_resolve_plugins(context, request, options, raw_result);
+    if options.await?.collect_candidates {
+        result = add_candidates(context, request, options, result);
+    }
+    if is_asset_timing_enabled() {
+        let result = result.resolve().await?;
+        let duration = start.elapsed();
+        if let Some(asset) = *result.first_asset().await? {
+            record_asset_timing(
+                TimingPhase::Resolve,
+                asset.ident().path().to_string().await?.clone_value(),
+                duration,
+            );
+        }
+        return Ok(result);
+    }
     Ok(result)
 }
 
@@ -675,10 +711,34 @@ async fn handle_resolve_plugins(
     Ok(ResolveResult {
         primary: new_primary,
         references,
+        candidates: result_value.candidates.clone(),
     }
     .cell())
 }
 
+/// Adds the [ResolveCandidate]s of `request` to `result` if it's
+/// unresolveable.
+#[turbo_tasks::function]
+async fn add_candidates(
+    context: FileSystemPathVc,
+    request: RequestVc,
+    options: ResolveOptionsVc,
+    result: ResolveResultVc,
+) -> Result<ResolveResultVc> {
+    let result_value = result.await?;
+    if !result_value.is_unresolveable() {
+        return Ok(result);
+    }
+    let candidates =
+        candidates::find_candidates(context, request, &options.await?.extensions).await?;
+    if candidates.is_empty() {
+        return Ok(result);
+    }
+    let mut result_value = result_value.clone_value();
+    result_value.candidates = candidates;
+    Ok(result_value.cell())
+}
+
 #[turbo_tasks::function]
 async fn resolve_internal(
     context: FileSystemPathVc,
@@ -788,6 +848,7 @@ async fn resolve_internal(
                         .to_string(),
                 ),
                 source: OptionIssueSourceVc::none(),
+                candidates: Vec::new(),
             }
             .into();
             issue.as_issue().emit();
@@ -803,6 +864,7 @@ async fn resolve_internal(
                 resolve_options: options,
                 error_message: Some("windows imports are not implemented yet".to_string()),
                 source: OptionIssueSourceVc::none(),
+                candidates: Vec::new(),
             }
             .into();
             issue.as_issue().emit();
@@ -819,6 +881,7 @@ async fn resolve_internal(
                 resolve_options: options,
                 error_message: Some("package internal imports are not implemented yet".to_string()),
                 source: OptionIssueSourceVc::none(),
+                candidates: Vec::new(),
             }
             .into();
             issue.as_issue().emit();
@@ -840,6 +903,7 @@ async fn resolve_internal(
                 resolve_options: options,
                 error_message: None,
                 source: OptionIssueSourceVc::none(),
+                candidates: Vec::new(),
             }
             .into();
             issue.as_issue().emit();
@@ -1141,6 +1205,7 @@ async fn resolve_alias_field_result(
         resolve_options,
         error_message: Some(format!("invalid alias field value: {}", result)),
         source: OptionIssueSourceVc::none(),
+        candidates: Vec::new(),
     }
     .cell();
     issue.as_issue().emit();
@@ -1305,6 +1370,12 @@ pub async fn handle_resolve_error(
     Ok(match result.is_unresolveable().await {
         Ok(unresolveable) => {
             if *unresolveable {
+                let candidates = result
+                    .await?
+                    .candidates
+                    .iter()
+                    .map(|candidate| candidate.request.clone())
+                    .collect();
                 let issue: ResolvingIssueVc = ResolvingIssue {
                     severity,
                     context: origin_path,
@@ -1313,6 +1384,7 @@ pub async fn handle_resolve_error(
                     resolve_options,
                     error_message: None,
                     source,
+                    candidates,
                 }
                 .into();
                 issue.as_issue().emit();
@@ -1328,6 +1400,7 @@ pub async fn handle_resolve_error(
                 resolve_options,
                 error_message: Some(err.to_string()),
                 source,
+                candidates: Vec::new(),
             }
             .into();
             issue.as_issue().emit();
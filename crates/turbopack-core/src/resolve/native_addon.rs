@@ -0,0 +1,155 @@
+use anyhow::Result;
+use turbo_tasks_fs::{glob::GlobVc, FileJsonContent, FileSystemEntryType, FileSystemPathVc};
+
+use super::{
+    find_context_file, package_json,
+    parse::{Request, RequestVc},
+    plugin::{ResolvePlugin, ResolvePluginConditionVc},
+    FindContextFileResult, PrimaryResolveResult, ResolveResult, ResolveResultOptionVc,
+};
+use crate::{
+    issue::native_addon::{NativeAddonIssue, NativeAddonIssueVc},
+    target::CompileTargetVc,
+};
+
+/// A resolve plugin that marks native addons as externals, so they are loaded
+/// from `node_modules` at runtime instead of being bundled:
+///
+/// * `.node` binaries. A binary whose file name carries a napi triple (e.g.
+///   `addon.linux-x64-gnu.node`) for a different target is reported.
+/// * Packages with napi-rs prebuilds, i.e. with a `napi` field in their
+///   `package.json`. The prebuild package for the compile target (e.g.
+///   `@scope/addon-linux-x64-gnu`) is expected to be installed next to the
+///   package, otherwise this is reported.
+#[turbo_tasks::value]
+pub struct NativeAddonExternalsPlugin {
+    root: FileSystemPathVc,
+    compile_target: CompileTargetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NativeAddonExternalsPluginVc {
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc, compile_target: CompileTargetVc) -> Self {
+        NativeAddonExternalsPlugin {
+            root,
+            compile_target,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ResolvePlugin for NativeAddonExternalsPlugin {
+    #[turbo_tasks::function]
+    fn after_resolve_condition(&self) -> ResolvePluginConditionVc {
+        ResolvePluginConditionVc::new(self.root, GlobVc::new("**/*.{node,js,cjs,mjs}"))
+    }
+
+    #[turbo_tasks::function]
+    async fn after_resolve(
+        &self,
+        fs_path: FileSystemPathVc,
+        _context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ResolveResultOptionVc> {
+        let compile_target = self.compile_target.await?;
+        let napi_triple = compile_target.napi_triple();
+        let target = napi_triple
+            .clone()
+            .unwrap_or_else(|| compile_target.triple());
+
+        let path = fs_path.await?;
+        if path.extension() == Some("node") {
+            // A binary named like `addon.<triple>.node` only works on that triple.
+            let stem = path.file_name().trim_end_matches(".node");
+            if let Some((_, triple)) = stem.rsplit_once('.') {
+                if looks_like_napi_triple(triple) && Some(triple) != napi_triple.as_deref() {
+                    NativeAddonIssue {
+                        path: fs_path,
+                        target,
+                        description: format!(
+                            "The native addon {} was built for {}.",
+                            path.path, triple
+                        ),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+            }
+            return Ok(external());
+        }
+
+        // Only requests for a package itself can be externalized.
+        if !matches!(&*request.await?, Request::Module { .. }) {
+            return Ok(ResolveResultOptionVc::none());
+        }
+        let FindContextFileResult::Found(package_json_path, _) =
+            &*find_context_file(fs_path.parent(), package_json()).await?
+        else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+        let FileJsonContent::Content(package_json) = &*package_json_path.read_json().await? else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+        let Some(napi) = package_json.get("napi") else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+        let Some(name) = napi
+            .pointer("/package/name")
+            .or_else(|| napi.get("name"))
+            .or_else(|| package_json.get("name"))
+            .and_then(|name| name.as_str())
+        else {
+            return Ok(ResolveResultOptionVc::none());
+        };
+
+        let missing_prebuild = match &napi_triple {
+            Some(triple) => {
+                let prebuild = format!("{name}-{triple}");
+                // Prebuilds are installed next to the package, which is
+                // located at `node_modules/<name>`.
+                let mut node_modules = package_json_path.parent();
+                for _ in name.split('/') {
+                    node_modules = node_modules.parent();
+                }
+                let prebuild_package_json = node_modules.join(&format!("{prebuild}/package.json"));
+                (*prebuild_package_json.get_type().await? != FileSystemEntryType::File)
+                    .then_some(prebuild)
+            }
+            None => Some(format!("{name} for {target}")),
+        };
+        if let Some(prebuild) = missing_prebuild {
+            NativeAddonIssue {
+                path: *package_json_path,
+                target,
+                description: format!(
+                    "The prebuild package {} is not installed. It might not be published for this \
+                     target or was skipped when installing optional dependencies.",
+                    prebuild
+                ),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+        Ok(external())
+    }
+}
+
+fn external() -> ResolveResultOptionVc {
+    ResolveResultOptionVc::some(
+        ResolveResult::primary(PrimaryResolveResult::OriginalReferenceExternal).cell(),
+    )
+}
+
+/// Whether `name` is a napi triple like `linux-x64-gnu` or `darwin-arm64`, as
+/// opposed to another dot-separated part of a file name.
+fn looks_like_napi_triple(name: &str) -> bool {
+    const PLATFORMS: &[&str] = &[
+        "aix", "android", "darwin", "freebsd", "linux", "openbsd", "sunos", "win32",
+    ];
+    name.split_once('-')
+        .map_or(false, |(platform, _)| PLATFORMS.contains(&platform))
+}
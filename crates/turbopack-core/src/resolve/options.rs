@@ -12,7 +12,11 @@ use super::{
     alias_map::{AliasMap, AliasTemplate},
     AliasPattern, PrimaryResolveResult, ResolveResult, ResolveResultVc,
 };
-use crate::resolve::{parse::RequestVc, plugin::ResolvePluginVc};
+use crate::resolve::{
+    parse::RequestVc,
+    plugin::ResolvePluginVc,
+    virtual_modules::{VirtualModuleMapping, VirtualModuleProviderVc},
+};
 
 #[turbo_tasks::value(shared)]
 #[derive(Hash, Debug)]
@@ -207,6 +211,19 @@ impl ImportMap {
             .insert(AliasPattern::wildcard(prefix, suffix), mapping);
     }
 
+    /// Resolves requests that start with `prefix`, e.g.
+    /// `virtual:route-manifest/`, to the modules generated by `provider`. See
+    /// [virtual_modules](super::virtual_modules).
+    pub fn insert_virtual_modules<'a>(
+        &mut self,
+        prefix: impl Into<String> + 'a,
+        provider: VirtualModuleProviderVc,
+    ) {
+        let prefix = prefix.into();
+        let mapping = VirtualModuleMapping::new(provider, prefix.clone());
+        self.insert_wildcard_alias(prefix, ImportMapping::Dynamic(mapping.cell().into()).cell());
+    }
+
     /// Inserts an alias that resolves an prefix always from a certain location
     /// to create a singleton.
     pub fn insert_singleton_alias<'a>(
@@ -407,6 +424,9 @@ pub struct ResolveOptions {
     pub fallback_import_map: Option<ImportMapVc>,
     pub resolved_map: Option<ResolvedMapVc>,
     pub plugins: Vec<ResolvePluginVc>,
+    /// Collects near misses of unresolveable requests into
+    /// [ResolveResult::candidates], e.g. for "did you mean ...?" hints.
+    pub collect_candidates: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -447,6 +467,54 @@ impl ResolveOptionsVc {
         );
         Ok(resolve_options.into())
     }
+
+    /// Returns a new [ResolveOptionsVc] with the given per-reference
+    /// `overrides` applied on top of it.
+    #[turbo_tasks::function]
+    pub async fn with_overrides(self, overrides: ResolveOptionsOverridesVc) -> Result<Self> {
+        let mut resolve_options = self.await?.clone_value();
+        overrides.await?.apply_to(&mut resolve_options);
+        Ok(resolve_options.into())
+    }
+}
+
+/// Changes to the [ResolveOptions] of a context that only apply to a single
+/// reference, e.g. a `require.resolve` with custom `paths`.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+pub struct ResolveOptionsOverrides {
+    /// Conditions that are added to (or replace existing values in) all
+    /// exports field conditions.
+    pub conditions: BTreeMap<String, ConditionValue>,
+    /// Replaces the list of extensions to try when set.
+    pub extensions: Option<Vec<String>>,
+    /// Replaces the locations where to resolve modules when set.
+    pub modules: Option<Vec<ResolveModules>>,
+}
+
+impl ResolveOptionsOverrides {
+    pub fn apply_to(&self, options: &mut ResolveOptions) {
+        if !self.conditions.is_empty() {
+            for item in options.into_package.iter_mut() {
+                match item {
+                    ResolveIntoPackage::ExportsField { conditions, .. } => {
+                        conditions.extend(
+                            self.conditions
+                                .iter()
+                                .map(|(name, value)| (name.clone(), value.clone())),
+                        );
+                    }
+                    ResolveIntoPackage::MainField(_) | ResolveIntoPackage::Default(_) => {}
+                }
+            }
+        }
+        if let Some(extensions) = &self.extensions {
+            options.extensions = extensions.clone();
+        }
+        if let Some(modules) = &self.modules {
+            options.modules = modules.clone();
+        }
+    }
 }
 
 #[turbo_tasks::value(shared)]
@@ -468,3 +536,59 @@ pub trait ImportMappingReplacement {
     fn replace(&self, capture: &str) -> ImportMappingVc;
     fn result(&self, context: FileSystemPathVc, request: RequestVc) -> ImportMapResultVc;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{ConditionValue, ResolveIntoPackage, ResolveOptions, ResolveOptionsOverrides};
+
+    fn exports_field_conditions(options: &ResolveOptions) -> &BTreeMap<String, ConditionValue> {
+        match &options.into_package[0] {
+            ResolveIntoPackage::ExportsField { conditions, .. } => conditions,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn overrides_are_applied() {
+        let mut options = ResolveOptions {
+            extensions: vec![".js".to_string(), ".ts".to_string()],
+            into_package: vec![
+                ResolveIntoPackage::ExportsField {
+                    field: "exports".to_string(),
+                    conditions: BTreeMap::from([
+                        ("import".to_string(), ConditionValue::Set),
+                        ("browser".to_string(), ConditionValue::Set),
+                    ]),
+                    unspecified_conditions: ConditionValue::Unset,
+                },
+                ResolveIntoPackage::MainField("main".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        ResolveOptionsOverrides::default().apply_to(&mut options);
+        assert_eq!(options.extensions, [".js", ".ts"]);
+        assert_eq!(exports_field_conditions(&options).len(), 2);
+
+        ResolveOptionsOverrides {
+            conditions: BTreeMap::from([
+                ("browser".to_string(), ConditionValue::Unset),
+                ("worker".to_string(), ConditionValue::Set),
+            ]),
+            extensions: Some(vec![".css".to_string()]),
+            ..Default::default()
+        }
+        .apply_to(&mut options);
+        assert_eq!(options.extensions, [".css"]);
+        assert_eq!(
+            exports_field_conditions(&options),
+            &BTreeMap::from([
+                ("browser".to_string(), ConditionValue::Unset),
+                ("import".to_string(), ConditionValue::Set),
+                ("worker".to_string(), ConditionValue::Set),
+            ])
+        );
+    }
+}
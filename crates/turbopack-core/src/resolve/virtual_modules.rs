@@ -0,0 +1,164 @@
+//! Modules whose content is generated on demand, e.g. a route manifest that a
+//! framework computes from the pages of an app, without writing temporary
+//! files.
+//!
+//! A [VirtualModuleProvider] is registered for a specifier prefix with
+//! [ImportMap::insert_virtual_modules]. Requests that start with the prefix,
+//! e.g. `virtual:route-manifest/app` for the prefix `virtual:route-manifest/`,
+//! are resolved to a module whose content is computed by the
+//! [VirtualModuleGenerator] of the provider. The content is generated again
+//! when one of the files it declared as dependencies changes, or when the
+//! embedder calls [VirtualModuleProvider::refresh].
+//!
+//! [ImportMap::insert_virtual_modules]: super::options::ImportMap::insert_virtual_modules
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use turbo_tasks::{get_invalidator, primitives::StringVc, Invalidator, Value};
+use turbo_tasks_fs::{File, FileSystemPathVc};
+
+use super::{
+    options::{
+        ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+        ImportMappingVc,
+    },
+    parse::RequestVc,
+    ResolveResult, ResolveResultVc,
+};
+use crate::{
+    ident::{AssetIdentVc, ModifierKind},
+    virtual_asset::VirtualAssetVc,
+};
+
+/// A module generated by a [VirtualModuleGenerator].
+pub struct GeneratedModule {
+    pub content: String,
+    /// The files the content was computed from. The module is generated again
+    /// when one of them changes.
+    pub dependencies: Vec<FileSystemPathVc>,
+}
+
+/// Generates the content of virtual modules.
+#[async_trait]
+pub trait VirtualModuleGenerator: Send + Sync {
+    /// Generates the module `name`, which is the part of the request after
+    /// the prefix, or returns `None` if there is no such module.
+    async fn generate(&self, name: &str) -> Result<Option<GeneratedModule>>;
+}
+
+/// Provides the virtual modules of a specifier prefix, see the
+/// [module documentation](self).
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new", into = "new")]
+#[derive(Clone)]
+pub struct VirtualModuleProvider {
+    /// The directory the modules are placed in. Requests in the modules are
+    /// resolved relative to it.
+    directory: FileSystemPathVc,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    generator: Arc<dyn VirtualModuleGenerator>,
+    /// Invalidates the tasks that generated the current modules, by the
+    /// specifier of the module. A task replaces its invalidator every time it
+    /// runs, so there is at most one per module.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    invalidators: Arc<Mutex<HashMap<String, Invalidator>>>,
+}
+
+impl VirtualModuleProvider {
+    pub fn new(directory: FileSystemPathVc, generator: Arc<dyn VirtualModuleGenerator>) -> Self {
+        Self {
+            directory,
+            generator,
+            invalidators: Default::default(),
+        }
+    }
+
+    /// Generates all modules again on the next read, e.g. when the generator
+    /// depends on something that isn't a file.
+    pub fn refresh(&self) {
+        let invalidators = std::mem::take(&mut *self.invalidators.lock().unwrap());
+        for invalidator in invalidators.into_values() {
+            invalidator.invalidate();
+        }
+    }
+}
+
+impl VirtualModuleProviderVc {
+    pub fn new(provider: VirtualModuleProvider) -> Self {
+        Self::cell(provider)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VirtualModuleProviderVc {
+    /// Resolves the request `specifier` to the generated module `name`.
+    #[turbo_tasks::function]
+    pub async fn resolve(self, specifier: &str, name: &str) -> Result<ResolveResultVc> {
+        let this = self.await?;
+        this.invalidators
+            .lock()
+            .unwrap()
+            .insert(specifier.to_string(), get_invalidator());
+        let Some(module) = this.generator.generate(name).await? else {
+            return Ok(ResolveResult::unresolveable().into());
+        };
+        for dependency in module.dependencies {
+            dependency.track().await?;
+        }
+
+        let ident = AssetIdentVc::from_path(this.directory.join(specifier)).with_modifier(
+            Value::new(ModifierKind::Custom),
+            StringVc::cell("virtual module".to_string()),
+        );
+        let asset = VirtualAssetVc::new_with_ident(ident, File::from(module.content).into());
+        Ok(ResolveResult::asset(asset.into()).into())
+    }
+}
+
+/// The [ImportMapping] of a [VirtualModuleProvider] for a prefix. `name` is
+/// set once the mapping is matched against a request.
+#[turbo_tasks::value]
+pub(crate) struct VirtualModuleMapping {
+    provider: VirtualModuleProviderVc,
+    prefix: String,
+    name: Option<String>,
+}
+
+impl VirtualModuleMapping {
+    pub(crate) fn new(provider: VirtualModuleProviderVc, prefix: String) -> Self {
+        Self {
+            provider,
+            prefix,
+            name: None,
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for VirtualModuleMapping {
+    #[turbo_tasks::function]
+    fn replace(&self, capture: &str) -> ImportMappingVc {
+        let mapping = VirtualModuleMapping {
+            provider: self.provider,
+            prefix: self.prefix.clone(),
+            name: Some(capture.to_string()),
+        };
+        ImportMapping::Dynamic(mapping.cell().into()).cell()
+    }
+
+    #[turbo_tasks::function]
+    fn result(&self, _context: FileSystemPathVc, _request: RequestVc) -> ImportMapResultVc {
+        match &self.name {
+            Some(name) => ImportMapResult::Result(
+                self.provider
+                    .resolve(&format!("{}{}", self.prefix, name), name),
+            )
+            .cell(),
+            None => ImportMapResult::NoEntry.cell(),
+        }
+    }
+}
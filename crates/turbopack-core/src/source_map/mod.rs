@@ -106,6 +106,12 @@ impl SourceMap {
         SourceMap::Regular(RegularSourceMap::new(map))
     }
 
+    /// Creates a new SourceMap::Regular Vc out of the JSON bytes of a regular
+    /// source map, e.g. the bytes of [SourceMapVc::to_rope].
+    pub fn new_regular_from_slice(bytes: &[u8]) -> Result<Self> {
+        Ok(SourceMap::new_regular(CrateMap::from_slice(bytes)?))
+    }
+
     /// Creates a new SourceMap::Sectioned Vc out of a collection of source map
     /// sections.
     pub fn new_sectioned(sections: Vec<SourceMapSection>) -> Self {
@@ -60,6 +60,37 @@ impl CompileTarget {
         }
     }
 
+    /// The full platform triple, e.g. `x64-linux-glibc-LE`.
+    pub fn triple(&self) -> String {
+        format!(
+            "{}-{}-{}-{}",
+            self.arch, self.platform, self.libc, self.endianness
+        )
+    }
+
+    /// The platform triple that napi-rs uses to name prebuilt packages, e.g.
+    /// `linux-x64-gnu` or `darwin-arm64`. Returns `None` when there is no
+    /// prebuild naming convention for the target.
+    pub fn napi_triple(&self) -> Option<String> {
+        if self.arch == Arch::Unknown || self.platform == Platform::Unknown {
+            return None;
+        }
+        let abi = match (self.platform, self.arch, self.libc) {
+            (Platform::Linux, Arch::Arm, Libc::Glibc) => Some("gnueabihf"),
+            (Platform::Linux, Arch::Arm, Libc::Musl) => Some("musleabihf"),
+            (Platform::Linux, _, Libc::Glibc) => Some("gnu"),
+            (Platform::Linux, _, Libc::Musl) => Some("musl"),
+            (Platform::Linux, _, _) => return None,
+            (Platform::Win32, _, _) => Some("msvc"),
+            (Platform::Android, Arch::Arm, _) => Some("eabi"),
+            _ => None,
+        };
+        Some(match abi {
+            Some(abi) => format!("{}-{}-{}", self.platform, self.arch, abi),
+            None => format!("{}-{}", self.platform, self.arch),
+        })
+    }
+
     fn current_endianness() -> Endianness {
         #[cfg(target_endian = "little")]
         {
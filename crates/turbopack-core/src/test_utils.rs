@@ -0,0 +1,666 @@
+//! Synthetic assets to exercise chunking on generated module graphs.
+//!
+//! None of the assets in this module touch a filesystem: their content is a
+//! fixed-size buffer and their references are provided up front. This allows
+//! downstream crates and benchmarks to run [chunk_content] and
+//! [optimize_by_common_parent] on graphs of a configurable shape.
+//!
+//! Assets created with [synthetic_asset_with_inputs] record external inputs,
+//! like the defines that an ecmascript module substitutes.
+//!
+//! [SyntheticIssue]s and [emit_synthetic_issues] likewise exercise issue
+//! capture without a build that fails.
+//!
+//! [chunk_content]: crate::chunk::chunk_content
+//! [optimize_by_common_parent]: crate::chunk::optimize::optimize_by_common_parent
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use turbo_tasks::{
+    graph::{GraphTraversal, ReverseTopological},
+    primitives::{BoolVc, StringVc},
+    CompletionVc, Value, ValueToString, ValueToStringVc,
+};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathOptionVc, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+
+use crate::{
+    asset::{Asset, AssetContentVc, AssetVc, AssetsVc},
+    chunk::{
+        availability_info::AvailabilityInfo, chunk_content, chunk_content_split,
+        optimize::optimize_by_common_parent, CancellationToken, Chunk, ChunkGenerationLimitVc,
+        ChunkItem, ChunkVc, ChunkableAsset, ChunkableAssetReference, ChunkableAssetReferenceVc,
+        ChunkableAssetVc, ChunkingContext, ChunkingContextVc, ChunksVc, EvaluatableAssetsVc,
+        ExternalInputsVc, FromChunkableAsset,
+    },
+    environment::EnvironmentVc,
+    ident::AssetIdentVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssuesVc},
+    reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
+    resolve::{ResolveResult, ResolveResultVc},
+};
+
+/// An [Asset] with a fixed size and a fixed list of references.
+#[turbo_tasks::value]
+pub struct SyntheticAsset {
+    ident: AssetIdentVc,
+    references: AssetReferencesVc,
+    size: usize,
+    inputs: Option<ExternalInputsVc>,
+}
+
+/// Creates a [SyntheticAsset] with `size` bytes of content which references
+/// `references`.
+#[turbo_tasks::function]
+pub fn synthetic_asset(
+    ident: AssetIdentVc,
+    references: AssetReferencesVc,
+    size: usize,
+) -> SyntheticAssetVc {
+    SyntheticAsset {
+        ident,
+        references,
+        size,
+        inputs: None,
+    }
+    .cell()
+}
+
+/// Creates a [SyntheticAsset] like [synthetic_asset] which records `inputs`
+/// as [external inputs](ExternalInputsVc::emit) of its content.
+#[turbo_tasks::function]
+pub fn synthetic_asset_with_inputs(
+    ident: AssetIdentVc,
+    references: AssetReferencesVc,
+    size: usize,
+    inputs: ExternalInputsVc,
+) -> SyntheticAssetVc {
+    SyntheticAsset {
+        ident,
+        references,
+        size,
+        inputs: Some(inputs),
+    }
+    .cell()
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for SyntheticAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        self.ident
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        if let Some(inputs) = self.inputs {
+            inputs.emit();
+        }
+        File::from(vec![b' '; self.size]).into()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.references
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for SyntheticAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(
+        self_vc: SyntheticAssetVc,
+        context: ChunkingContextVc,
+        availability_info: Value<AvailabilityInfo>,
+    ) -> ChunkVc {
+        SyntheticChunkVc::new(context, self_vc, availability_info).into()
+    }
+}
+
+/// A [ChunkableAssetReference] to a single [Asset] with the default chunking
+/// type.
+#[turbo_tasks::value]
+pub struct SyntheticAssetReference {
+    asset: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl SyntheticAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(asset: AssetVc) -> Self {
+        Self::cell(SyntheticAssetReference { asset })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for SyntheticAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        ResolveResult::asset(self.asset).cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for SyntheticAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "synthetic reference to {}",
+            self.asset.ident().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAssetReference for SyntheticAssetReference {}
+
+/// A [ChunkItem] created from a [SyntheticAsset].
+#[turbo_tasks::value]
+pub struct SyntheticChunkItem {
+    asset: SyntheticAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for SyntheticChunkItem {
+    #[turbo_tasks::function]
+    fn asset_ident(&self) -> AssetIdentVc {
+        self.asset.ident()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.asset.references()
+    }
+}
+
+#[async_trait::async_trait]
+impl FromChunkableAsset for SyntheticChunkItemVc {
+    async fn from_asset(_context: ChunkingContextVc, asset: AssetVc) -> Result<Option<Self>> {
+        let Some(asset) = SyntheticAssetVc::resolve_from(asset).await? else {
+            return Ok(None);
+        };
+        Ok(Some(SyntheticChunkItem { asset }.cell()))
+    }
+
+    async fn from_async_asset(
+        _context: ChunkingContextVc,
+        _asset: ChunkableAssetVc,
+        _availability_info: Value<AvailabilityInfo>,
+    ) -> Result<Option<Self>> {
+        Ok(None)
+    }
+}
+
+/// The result of [chunk_content] for a [SyntheticChunk].
+#[turbo_tasks::value]
+pub struct SyntheticChunkContent {
+    pub chunk_items: Vec<SyntheticChunkItemVc>,
+    pub chunks: Vec<ChunkVc>,
+    pub async_chunk_group_entries: Vec<ChunkVc>,
+    pub external_asset_references: Vec<AssetReferenceVc>,
+}
+
+/// A [Chunk] containing a [SyntheticAsset] and everything placed with it.
+#[turbo_tasks::value]
+pub struct SyntheticChunk {
+    context: ChunkingContextVc,
+    entry: SyntheticAssetVc,
+    availability_info: AvailabilityInfo,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct SyntheticChunks(Vec<SyntheticChunkVc>);
+
+#[turbo_tasks::value_impl]
+impl SyntheticChunkVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        context: ChunkingContextVc,
+        entry: SyntheticAssetVc,
+        availability_info: Value<AvailabilityInfo>,
+    ) -> Self {
+        SyntheticChunk {
+            context,
+            entry,
+            availability_info: availability_info.into_value(),
+        }
+        .cell()
+    }
+
+    /// Computes the content of this chunk with [chunk_content], falling back
+    /// to [chunk_content_split] when the chunk becomes too large.
+    #[turbo_tasks::function]
+    pub async fn chunk_content(self) -> Result<SyntheticChunkContentVc> {
+        let this = self.await?;
+        let entry: AssetVc = this.entry.into();
+        let availability_info = Value::new(this.availability_info);
+        let content = if let Some(content) =
+            chunk_content::<SyntheticChunkItemVc>(this.context, entry, None, availability_info)
+                .await?
+        {
+            content
+        } else {
+            chunk_content_split::<SyntheticChunkItemVc>(
+                this.context,
+                entry,
+                None,
+                availability_info,
+            )
+            .await?
+        };
+        Ok(SyntheticChunkContent {
+            chunk_items: content.chunk_items,
+            chunks: content.chunks,
+            async_chunk_group_entries: content.async_chunk_group_entries,
+            external_asset_references: content.external_asset_references,
+        }
+        .cell())
+    }
+
+    /// The directory containing the entry of this chunk.
+    #[turbo_tasks::function]
+    pub async fn common_parent(self) -> Result<FileSystemPathOptionVc> {
+        let this = self.await?;
+        Ok(FileSystemPathOptionVc::cell(Some(
+            this.entry.ident().path().parent(),
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for SyntheticChunk {
+    #[turbo_tasks::function]
+    fn ident(&self) -> AssetIdentVc {
+        AssetIdentVc::from_path(self.context.chunk_path(self.entry.ident(), ".synthetic"))
+    }
+
+    #[turbo_tasks::function]
+    async fn content(self_vc: SyntheticChunkVc) -> Result<AssetContentVc> {
+        let content = self_vc.chunk_content().await?;
+        let mut code = String::new();
+        for item in content.chunk_items.iter() {
+            writeln!(code, "// {}", item.asset_ident().to_string().await?)?;
+            if let FileContent::Content(file) = &*item.await?.asset.content().file_content().await?
+            {
+                writeln!(code, "{}", file.content().to_str()?)?;
+            }
+        }
+        Ok(File::from(code).into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Chunk for SyntheticChunk {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn parallel_chunks(self_vc: SyntheticChunkVc) -> Result<ChunksVc> {
+        Ok(ChunksVc::cell(
+            self_vc.chunk_content().await?.chunks.clone(),
+        ))
+    }
+}
+
+/// Optimizes [SyntheticChunk]s with [optimize_by_common_parent]. Chunks are
+/// not merged, only regrouped in containment tree order.
+#[turbo_tasks::function]
+pub async fn optimize_synthetic_chunks(chunks: SyntheticChunksVc) -> Result<SyntheticChunksVc> {
+    let chunks = chunks.await?;
+    Ok(optimize_by_common_parent(
+        &chunks,
+        &CancellationToken::new(),
+        get_common_parent,
+        |local, children| concat_synthetic_chunks(local.map(SyntheticChunksVc::cell), children),
+    )
+    .await?)
+}
+
+#[turbo_tasks::function]
+fn get_common_parent(chunk: SyntheticChunkVc) -> FileSystemPathOptionVc {
+    chunk.common_parent()
+}
+
+#[turbo_tasks::function]
+async fn concat_synthetic_chunks(
+    local: Option<SyntheticChunksVc>,
+    children: Vec<SyntheticChunksVc>,
+) -> Result<SyntheticChunksVc> {
+    let mut chunks = Vec::new();
+    if let Some(local) = local {
+        chunks.extend(local.await?.iter().copied());
+    }
+    for child in children {
+        chunks.extend(child.await?.iter().copied());
+    }
+    Ok(SyntheticChunksVc::cell(chunks))
+}
+
+/// A minimal [ChunkingContext] for [SyntheticChunk]s. Chunk paths are derived
+/// from a hash of the entry ident.
+#[turbo_tasks::value]
+pub struct SyntheticChunkingContext {
+    root: FileSystemPathVc,
+    environment: EnvironmentVc,
+    layer: Option<String>,
+    chunk_generation_limit: Option<ChunkGenerationLimitVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl SyntheticChunkingContextVc {
+    #[turbo_tasks::function]
+    pub fn new(root: FileSystemPathVc, environment: EnvironmentVc) -> Self {
+        SyntheticChunkingContext {
+            root,
+            environment,
+            layer: None,
+            chunk_generation_limit: None,
+        }
+        .cell()
+    }
+
+    /// This context with a [ChunkingContext::chunk_generation_limit].
+    #[turbo_tasks::function]
+    pub async fn with_chunk_generation_limit(self, limit: ChunkGenerationLimitVc) -> Result<Self> {
+        let this = self.await?;
+        Ok(SyntheticChunkingContext {
+            root: this.root,
+            environment: this.environment,
+            layer: this.layer.clone(),
+            chunk_generation_limit: Some(limit),
+        }
+        .cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkingContext for SyntheticChunkingContext {
+    #[turbo_tasks::function]
+    fn context_path(&self) -> FileSystemPathVc {
+        self.root
+    }
+
+    #[turbo_tasks::function]
+    fn output_root(&self) -> FileSystemPathVc {
+        self.root
+    }
+
+    #[turbo_tasks::function]
+    fn environment(&self) -> EnvironmentVc {
+        self.environment
+    }
+
+    #[turbo_tasks::function]
+    async fn chunk_path(&self, ident: AssetIdentVc, extension: &str) -> Result<FileSystemPathVc> {
+        let hash = encode_hex(hash_xxh3_hash64(ident.to_string().await?.as_bytes()));
+        Ok(self.root.join(&format!("{hash}{extension}")))
+    }
+
+    #[turbo_tasks::function]
+    fn reference_chunk_source_maps(&self, _chunk: AssetVc) -> BoolVc {
+        BoolVc::cell(false)
+    }
+
+    #[turbo_tasks::function]
+    fn can_be_in_same_chunk(&self, _asset_a: AssetVc, _asset_b: AssetVc) -> BoolVc {
+        BoolVc::cell(true)
+    }
+
+    #[turbo_tasks::function]
+    fn asset_path(&self, content_hash: &str, extension: &str) -> FileSystemPathVc {
+        self.root.join(&format!("{content_hash}.{extension}"))
+    }
+
+    #[turbo_tasks::function]
+    fn layer(&self) -> StringVc {
+        StringVc::cell(self.layer.clone().unwrap_or_default())
+    }
+
+    #[turbo_tasks::function]
+    fn with_layer(&self, layer: &str) -> ChunkingContextVc {
+        SyntheticChunkingContext {
+            root: self.root,
+            environment: self.environment,
+            layer: (!layer.is_empty()).then(|| layer.to_string()),
+            chunk_generation_limit: self.chunk_generation_limit,
+        }
+        .cell()
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn chunk_generation_limit(&self) -> ChunkGenerationLimitVc {
+        self.chunk_generation_limit
+            .unwrap_or_else(ChunkGenerationLimitVc::unlimited)
+    }
+
+    #[turbo_tasks::function]
+    async fn chunk_group(&self, entry: ChunkVc) -> Result<AssetsVc> {
+        let chunks = ReverseTopological::new()
+            .skip_duplicates()
+            .visit([entry], |chunk: ChunkVc| async move {
+                Ok(chunk
+                    .parallel_chunks()
+                    .await?
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .into_iter())
+            })
+            .await
+            .completed()?
+            .into_inner();
+        Ok(AssetsVc::cell(
+            chunks.into_iter().map(|chunk| chunk.into()).collect(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn evaluated_chunk_group(
+        self_vc: SyntheticChunkingContextVc,
+        entry: ChunkVc,
+        _evaluatable_assets: EvaluatableAssetsVc,
+    ) -> AssetsVc {
+        self_vc.chunk_group(entry)
+    }
+}
+
+/// Describes the shape of a graph generated by [synthetic_graph].
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct SyntheticGraphOptions {
+    /// Number of references of each module above the leaf level.
+    pub fan_out: u32,
+    /// Number of levels below the root module.
+    pub depth: u32,
+    /// Percentage (0-100) of references pointing into a pool of modules shared
+    /// by the whole graph instead of to a new module.
+    pub shared_percent: u32,
+    /// Number of modules in the shared pool.
+    pub shared_modules: u32,
+    /// Content size of each module in bytes.
+    pub module_size: u32,
+}
+
+impl Default for SyntheticGraphOptions {
+    fn default() -> Self {
+        SyntheticGraphOptions {
+            fan_out: 10,
+            depth: 3,
+            shared_percent: 0,
+            shared_modules: 0,
+            module_size: 1024,
+        }
+    }
+}
+
+impl SyntheticGraphOptions {
+    /// Returns options with the smallest depth for which a graph with the
+    /// given `fan_out` and no sharing contains at least `modules` modules.
+    pub fn with_module_count(modules: u64, fan_out: u32) -> Self {
+        let mut options = SyntheticGraphOptions {
+            fan_out,
+            depth: 0,
+            ..Default::default()
+        };
+        while options.tree_size() < modules && fan_out > 1 {
+            options.depth += 1;
+        }
+        options
+    }
+
+    /// The number of modules in the graph when nothing is shared.
+    pub fn tree_size(&self) -> u64 {
+        let mut level = 1u64;
+        let mut total = 1u64;
+        for _ in 0..self.depth {
+            level = level.saturating_mul(self.fan_out as u64);
+            total = total.saturating_add(level);
+        }
+        total
+    }
+
+    /// Whether the reference to the module at `index` points into the shared
+    /// pool. This is deterministic for a given index.
+    fn is_shared(&self, index: u64) -> bool {
+        if self.shared_modules == 0 {
+            return false;
+        }
+        (index.wrapping_mul(2654435761) >> 7) % 100 < self.shared_percent as u64
+    }
+}
+
+/// Generates a module graph shaped by `options` below `root` and returns its
+/// root module.
+#[turbo_tasks::function]
+pub fn synthetic_graph(
+    root: FileSystemPathVc,
+    options: Value<SyntheticGraphOptions>,
+) -> SyntheticAssetVc {
+    synthetic_graph_node(root, options, 0, 0)
+}
+
+#[turbo_tasks::function]
+fn synthetic_graph_node(
+    root: FileSystemPathVc,
+    options: Value<SyntheticGraphOptions>,
+    level: u32,
+    index: u64,
+) -> SyntheticAssetVc {
+    let mut references = Vec::new();
+    if level < options.depth {
+        for slot in 0..options.fan_out as u64 {
+            let child = index * options.fan_out as u64 + slot;
+            let asset = if options.is_shared(child) {
+                synthetic_shared_node(root, options, child % options.shared_modules as u64)
+            } else {
+                synthetic_graph_node(root, options, level + 1, child)
+            };
+            references.push(SyntheticAssetReferenceVc::new(asset.into()).into());
+        }
+    }
+    synthetic_asset(
+        AssetIdentVc::from_path(root.join(&format!("{level}/{index}.js"))),
+        AssetReferencesVc::cell(references),
+        options.module_size as usize,
+    )
+}
+
+#[turbo_tasks::function]
+fn synthetic_shared_node(
+    root: FileSystemPathVc,
+    options: Value<SyntheticGraphOptions>,
+    index: u64,
+) -> SyntheticAssetVc {
+    synthetic_asset(
+        AssetIdentVc::from_path(root.join(&format!("shared/{index}.js"))),
+        AssetReferencesVc::empty(),
+        options.module_size as usize,
+    )
+}
+
+/// An [Issue] with a fixed severity, category and title.
+#[turbo_tasks::value(shared)]
+pub struct SyntheticIssue {
+    pub context: FileSystemPathVc,
+    pub severity: IssueSeverity,
+    pub category: String,
+    pub title: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for SyntheticIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity.into()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell(self.category.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(self.title.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::empty()
+    }
+}
+
+/// Emits `issues`. The issues can be captured from the returned completion.
+#[turbo_tasks::function]
+pub async fn emit_synthetic_issues(issues: IssuesVc) -> Result<CompletionVc> {
+    for issue in issues.await?.iter() {
+        issue.emit();
+    }
+    Ok(CompletionVc::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyntheticGraphOptions;
+
+    #[test]
+    fn test_with_module_count() {
+        let options = SyntheticGraphOptions::with_module_count(10_000, 10);
+        assert_eq!(options.depth, 4);
+        assert_eq!(options.tree_size(), 11_111);
+
+        let options = SyntheticGraphOptions::with_module_count(100_000, 10);
+        assert_eq!(options.depth, 5);
+        assert_eq!(options.tree_size(), 111_111);
+
+        let options = SyntheticGraphOptions::with_module_count(1, 10);
+        assert_eq!(options.depth, 0);
+        assert_eq!(options.tree_size(), 1);
+    }
+
+    #[test]
+    fn test_is_shared() {
+        let options = SyntheticGraphOptions {
+            shared_percent: 100,
+            shared_modules: 4,
+            ..Default::default()
+        };
+        assert!((0..100).all(|i| options.is_shared(i)));
+
+        let options = SyntheticGraphOptions {
+            shared_percent: 100,
+            shared_modules: 0,
+            ..Default::default()
+        };
+        assert!((0..100).all(|i| !options.is_shared(i)));
+    }
+}
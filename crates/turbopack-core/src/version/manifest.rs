@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use turbo_tasks::{primitives::StringVc, IntoTraitRef};
+use turbo_tasks_fs::File;
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64, Xxh3Hash64Hasher};
+
+use super::{PartialUpdate, TotalUpdate, Update, UpdateVc, Version, VersionVc, VersionedContent};
+use crate::asset::AssetContentVc;
+
+/// The content of a manifest-style asset (e.g. a route manifest or a chunk
+/// map), which is a JSON object whose entries change independently.
+///
+/// Updates between versions only contain the entries that were added, changed
+/// or removed, so clients can patch their copy of the manifest instead of
+/// reloading it completely.
+#[turbo_tasks::value(shared)]
+pub struct ManifestContent {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub entries: IndexMap<String, serde_json::Value>,
+}
+
+impl ManifestContentVc {
+    pub fn new(entries: IndexMap<String, serde_json::Value>) -> Self {
+        Self::cell(ManifestContent { entries })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ManifestContentVc {
+    /// Computes the version of this manifest.
+    #[turbo_tasks::function]
+    pub async fn version(self) -> Result<ManifestVersionVc> {
+        let entries = hash_entries(&self.await?.entries)?;
+        Ok(ManifestVersion { entries }.cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl VersionedContent for ManifestContent {
+    #[turbo_tasks::function]
+    fn content(&self) -> Result<AssetContentVc> {
+        Ok(File::from(serde_json::to_string_pretty(&self.entries)?).into())
+    }
+
+    #[turbo_tasks::function]
+    fn version(self_vc: ManifestContentVc) -> VersionVc {
+        self_vc.version().into()
+    }
+
+    #[turbo_tasks::function]
+    fn update(self_vc: ManifestContentVc, from_version: VersionVc) -> UpdateVc {
+        update_manifest(self_vc, from_version)
+    }
+}
+
+/// The version of a [ManifestContent], which stores a hash per entry.
+#[turbo_tasks::value(shared)]
+pub struct ManifestVersion {
+    /// A map from manifest key to the hash of its value.
+    pub entries: IndexMap<String, String>,
+}
+
+#[turbo_tasks::value_impl]
+impl Version for ManifestVersion {
+    #[turbo_tasks::function]
+    fn id(&self) -> StringVc {
+        StringVc::cell(version_id(&self.entries))
+    }
+}
+
+/// Hashes the value of each entry of a manifest.
+fn hash_entries(entries: &IndexMap<String, serde_json::Value>) -> Result<IndexMap<String, String>> {
+    entries
+        .iter()
+        .map(|(key, value)| {
+            let hash = hash_xxh3_hash64(serde_json::to_string(value)?.as_bytes());
+            Ok((key.clone(), encode_hex(hash)))
+        })
+        .collect()
+}
+
+/// The id of a [ManifestVersion] with the given entry hashes.
+fn version_id(entries: &IndexMap<String, String>) -> String {
+    // The order of the entries doesn't affect the update, so it must not
+    // affect the id either.
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort();
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_value(entries.len());
+    for (key, hash) in entries {
+        hasher.write_value(key);
+        hasher.write_value(hash);
+    }
+    encode_hex(hasher.finish())
+}
+
+/// Update of a manifest from one version to another.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+struct ManifestUpdate<'a> {
+    /// Entries that were added or whose value changed.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    added: IndexMap<&'a str, &'a serde_json::Value>,
+    /// Keys of entries that were removed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    removed: Vec<&'a str>,
+}
+
+/// Computes the update from a manifest whose entries had the hashes `from` to
+/// the manifest with `entries`, whose hashes are `to`. Returns `None` when no
+/// entry changed.
+fn diff_manifests<'a>(
+    from: &IndexMap<String, String>,
+    to: &IndexMap<String, String>,
+    entries: &'a IndexMap<String, serde_json::Value>,
+) -> Option<ManifestUpdate<'a>> {
+    let added = to
+        .iter()
+        .filter(|(key, hash)| from.get(*key) != Some(*hash))
+        .filter_map(|(key, _)| entries.get_key_value(key))
+        .map(|(key, value)| (key.as_str(), value))
+        .collect::<IndexMap<_, _>>();
+    let removed = from
+        .keys()
+        .filter(|key| !to.contains_key(*key))
+        .map(|key| key.as_str())
+        .collect::<Vec<_>>();
+
+    if added.is_empty() && removed.is_empty() {
+        return None;
+    }
+    Some(ManifestUpdate { added, removed })
+}
+
+/// Computes the key-level update of a manifest from one version to another.
+#[turbo_tasks::function]
+async fn update_manifest(content: ManifestContentVc, from_version: VersionVc) -> Result<UpdateVc> {
+    let to_version = content.version();
+    let Some(from_version) = ManifestVersionVc::resolve_from(from_version).await? else {
+        // It's likely `from_version` is `NotFoundVersion`.
+        return Ok(Update::Total(TotalUpdate {
+            to: to_version.as_version().into_trait_ref().await?,
+        })
+        .cell());
+    };
+
+    let from = from_version.await?;
+    let to = to_version.await?;
+    let content = content.await?;
+
+    let Some(update) = diff_manifests(&from.entries, &to.entries, &content.entries) else {
+        return Ok(Update::None.cell());
+    };
+    Ok(Update::Partial(PartialUpdate {
+        to: to_version.as_version().into_trait_ref().await?,
+        instruction: Arc::new(serde_json::to_value(&update)?),
+    })
+    .cell())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entries(entries: serde_json::Value) -> IndexMap<String, serde_json::Value> {
+        let serde_json::Value::Object(entries) = entries else {
+            panic!("manifest entries must be an object");
+        };
+        entries.into_iter().collect()
+    }
+
+    fn diff(from: serde_json::Value, to: serde_json::Value) -> Option<serde_json::Value> {
+        let from = hash_entries(&entries(from)).unwrap();
+        let to_entries = entries(to);
+        let to = hash_entries(&to_entries).unwrap();
+        diff_manifests(&from, &to, &to_entries).map(|update| serde_json::to_value(update).unwrap())
+    }
+
+    #[test]
+    fn test_hashes_entries_by_value() {
+        let hashes = hash_entries(&entries(json!({
+            "a": { "chunks": ["a.js"] },
+            "b": { "chunks": ["a.js"] },
+            "c": { "chunks": ["c.js"] },
+        })))
+        .unwrap();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes["a"], hashes["b"]);
+        assert_ne!(hashes["a"], hashes["c"]);
+    }
+
+    #[test]
+    fn test_version_id_ignores_entry_order() {
+        let a = hash_entries(&entries(json!({ "a": 1, "b": 2 }))).unwrap();
+        let b = a
+            .iter()
+            .rev()
+            .map(|(key, hash)| (key.clone(), hash.clone()))
+            .collect();
+        assert_eq!(version_id(&a), version_id(&b));
+
+        let c = hash_entries(&entries(json!({ "a": 1, "b": 3 }))).unwrap();
+        assert_ne!(version_id(&a), version_id(&c));
+        let d = hash_entries(&entries(json!({ "a": 1 }))).unwrap();
+        assert_ne!(version_id(&a), version_id(&d));
+    }
+
+    #[test]
+    fn test_diff_of_unchanged_manifest_is_none() {
+        assert_eq!(
+            diff(json!({ "a": 1, "b": [2] }), json!({ "b": [2], "a": 1 })),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diff_contains_added_and_changed_entries() {
+        assert_eq!(
+            diff(json!({ "a": 1, "b": 2 }), json!({ "a": 1, "b": 3, "c": 4 })),
+            Some(json!({ "type": "ManifestUpdate", "added": { "b": 3, "c": 4 } }))
+        );
+    }
+
+    #[test]
+    fn test_diff_contains_removed_keys() {
+        assert_eq!(
+            diff(json!({ "a": 1, "b": 2, "c": 3 }), json!({ "b": 2 })),
+            Some(json!({ "type": "ManifestUpdate", "removed": ["a", "c"] }))
+        );
+        assert_eq!(
+            diff(json!({ "a": 1 }), json!({ "b": 1 })),
+            Some(json!({ "type": "ManifestUpdate", "added": { "b": 1 }, "removed": ["a"] }))
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod manifest;
+
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
@@ -0,0 +1,181 @@
+#![cfg(test)]
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use turbo_tasks::{TryJoinIterExt, Value};
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    asset::AssetVc,
+    chunk::{
+        availability_info::AvailabilityInfo, chunk_content_limited, continue_chunk_content,
+        ChunkContentLimits, ChunkItem, ChunkingContextVc, PartialChunkContent,
+    },
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    test_utils::{
+        synthetic_asset, SyntheticAssetReferenceVc, SyntheticChunkItemVc,
+        SyntheticChunkingContextVc,
+    },
+};
+
+register!();
+
+fn chunking_context(root: FileSystemPathVc) -> ChunkingContextVc {
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Custom(0)),
+        Value::new(EnvironmentIntention::Client),
+    );
+    SyntheticChunkingContextVc::new(root, environment).into()
+}
+
+fn module(root: FileSystemPathVc, name: &str, references: &[AssetVc]) -> AssetVc {
+    synthetic_asset(
+        AssetIdentVc::from_path(root.join(name)),
+        AssetReferencesVc::cell(
+            references
+                .iter()
+                .map(|asset| SyntheticAssetReferenceVc::new(*asset).into())
+                .collect(),
+        ),
+        1,
+    )
+    .into()
+}
+
+/// index.js references shared.js directly and through a.js and b.js, so
+/// shared.js is one reference away from the entry, but also three.
+fn graph(root: FileSystemPathVc) -> AssetVc {
+    let leaf3 = module(root, "leaf3.js", &[]);
+    let leaf2 = module(root, "leaf2.js", &[leaf3]);
+    let leaf = module(root, "leaf.js", &[leaf2]);
+    let shared = module(root, "shared.js", &[leaf]);
+    let b = module(root, "b.js", &[shared]);
+    let a = module(root, "a.js", &[b]);
+    module(root, "index.js", &[a, shared])
+}
+
+async fn limited(
+    root: FileSystemPathVc,
+    entry: AssetVc,
+    limits: ChunkContentLimits,
+) -> Result<PartialChunkContent<SyntheticChunkItemVc>> {
+    Ok(chunk_content_limited(
+        chunking_context(root),
+        entry,
+        None,
+        Value::new(AvailabilityInfo::Root {
+            current_availability_root: entry,
+        }),
+        limits,
+    )
+    .await?
+    .expect("the graph is small enough for a single chunk"))
+}
+
+async fn paths(chunk_items: &[SyntheticChunkItemVc]) -> Result<BTreeSet<String>> {
+    Ok(chunk_items
+        .iter()
+        .map(|chunk_item| async move { Ok(chunk_item.asset_ident().path().await?.path.clone()) })
+        .try_join()
+        .await?
+        .into_iter()
+        .collect())
+}
+
+fn set(paths: &[&str]) -> BTreeSet<String> {
+    paths.iter().map(|path| path.to_string()).collect()
+}
+
+#[tokio::test]
+async fn chunk_content_limited_by_depth() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let entry = graph(root);
+
+        let partial = limited(
+            root,
+            entry,
+            ChunkContentLimits {
+                max_depth: Some(0),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(paths(&partial.content.chunk_items).await?, set(&["index.js"]));
+        assert_eq!(partial.continuation.map(|continuation| continuation.len()), Some(1));
+
+        // shared.js counts at depth 1 even when it's reached through b.js
+        // first, so the references of leaf.js are followed as well.
+        let partial = limited(
+            root,
+            entry,
+            ChunkContentLimits {
+                max_depth: Some(3),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(
+            paths(&partial.content.chunk_items).await?,
+            set(&["index.js", "a.js", "b.js", "shared.js", "leaf.js", "leaf2.js"])
+        );
+        assert_eq!(partial.continuation.map(|continuation| continuation.len()), Some(1));
+
+        let partial = limited(root, entry, ChunkContentLimits::default()).await?;
+        assert_eq!(partial.content.chunk_items.len(), 7);
+        assert!(partial.continuation.is_none());
+    }
+}
+
+#[tokio::test]
+async fn continue_chunk_content_after_limit() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let entry = graph(root);
+
+        let partial = limited(
+            root,
+            entry,
+            ChunkContentLimits {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?;
+        assert_eq!(
+            paths(&partial.content.chunk_items).await?,
+            set(&["index.js", "a.js", "shared.js"])
+        );
+        let continuation = partial.continuation.expect("a.js and shared.js weren't followed");
+        assert_eq!(continuation.len(), 2);
+
+        // Depths count from the chunk items where the traversal stopped.
+        let partial = continue_chunk_content(
+            continuation,
+            ChunkContentLimits {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?
+        .unwrap();
+        assert_eq!(paths(&partial.content.chunk_items).await?, set(&["b.js", "leaf.js"]));
+        let continuation = partial.continuation.expect("b.js and leaf.js weren't followed");
+
+        let partial = continue_chunk_content(continuation, ChunkContentLimits::default())
+            .await?
+            .unwrap();
+        assert_eq!(
+            paths(&partial.content.chunk_items).await?,
+            set(&["leaf2.js", "leaf3.js"])
+        );
+        assert!(partial.continuation.is_none());
+    }
+}
@@ -0,0 +1,58 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use turbo_tasks::Value;
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    chunk::{
+        ChunkGenerationLimit, ChunkGenerationLimitVc, ChunkGenerationPriority, ChunkGroup,
+        ChunkableAsset, ChunkableAssetVc, ChunkingContextVc,
+    },
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    test_utils::{synthetic_asset, SyntheticChunkingContextVc},
+};
+
+register!();
+
+fn chunking_context(root: FileSystemPathVc, limit: ChunkGenerationLimit) -> ChunkingContextVc {
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Custom(0)),
+        Value::new(EnvironmentIntention::Client),
+    );
+    SyntheticChunkingContextVc::new(root, environment)
+        .with_chunk_generation_limit(ChunkGenerationLimitVc::new(limit))
+        .into()
+}
+
+#[tokio::test]
+async fn generate_waits_for_the_chunk_generation_limit() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let limit = ChunkGenerationLimit::new(1);
+        let context = chunking_context(root, limit.clone());
+        let entry: ChunkableAssetVc = synthetic_asset(
+            AssetIdentVc::from_path(root.join("index.js")),
+            AssetReferencesVc::empty(),
+            1,
+        )
+        .into();
+        let group = ChunkGroup::new(context, entry.as_root_chunk(context));
+
+        // The only slot is taken, so nothing of the group is generated.
+        let permit = limit.acquire(ChunkGenerationPriority::Entry).await?;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), group.generate())
+                .await
+                .is_err()
+        );
+
+        drop(permit);
+        group.generate().await?;
+    }
+}
@@ -0,0 +1,136 @@
+#![cfg(test)]
+
+use anyhow::Result;
+use turbo_tasks::Value;
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    chunk::{ChunkGroup, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc, ExternalInputsVc},
+    compile_time_info::CompileTimeDefineValue,
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    test_utils::{
+        synthetic_asset, synthetic_asset_with_inputs, SyntheticAssetReferenceVc,
+        SyntheticChunkingContextVc,
+    },
+};
+
+register!();
+
+fn chunking_context(root: FileSystemPathVc) -> ChunkingContextVc {
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Custom(0)),
+        Value::new(EnvironmentIntention::Client),
+    );
+    SyntheticChunkingContextVc::new(root, environment).into()
+}
+
+fn define(name: &str, value: impl Into<CompileTimeDefineValue>) -> Result<ExternalInputsVc> {
+    let name = name.split('.').map(str::to_string).collect::<Vec<_>>();
+    ExternalInputsVc::define(&name, &value.into())
+}
+
+/// The group of a chunk of `index.js`, which reads `inputs` and references
+/// `references`.
+fn group(
+    root: FileSystemPathVc,
+    inputs: Option<ExternalInputsVc>,
+    references: AssetReferencesVc,
+) -> ChunkGroup {
+    let ident = AssetIdentVc::from_path(root.join("index.js"));
+    let entry: ChunkableAssetVc = match inputs {
+        Some(inputs) => synthetic_asset_with_inputs(ident, references, 1, inputs).into(),
+        None => synthetic_asset(ident, references, 1).into(),
+    };
+    let context = chunking_context(root);
+    ChunkGroup::new(context, entry.as_root_chunk(context))
+}
+
+/// The recorded inputs of `group` and their values.
+async fn inputs(group: &ChunkGroup) -> Result<Vec<(String, Option<String>)>> {
+    Ok(group
+        .external_inputs()
+        .await?
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.map(str::to_string)))
+        .collect())
+}
+
+#[tokio::test]
+async fn inputs_recorded_by_chunk_content_are_part_of_the_digest() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let references = AssetReferencesVc::empty();
+        let without = group(root, None, references);
+        let debug = group(root, Some(define("process.env.DEBUG", true)?), references);
+        let release = group(root, Some(define("process.env.DEBUG", false)?), references);
+
+        assert!(inputs(&without).await?.is_empty());
+        assert_eq!(
+            inputs(&debug).await?,
+            [("define:process.env.DEBUG".to_string(), Some("true".to_string()))]
+        );
+
+        // The content of the chunks is the same, only the inputs differ.
+        let digests = [
+            without.input_digest().await?.clone_value(),
+            debug.input_digest().await?.clone_value(),
+            release.input_digest().await?.clone_value(),
+        ];
+        assert_ne!(digests[0], digests[1]);
+        assert_ne!(digests[0], digests[2]);
+        assert_ne!(digests[1], digests[2]);
+
+        let without = without.input_digests().await?;
+        let debug = debug.input_digests().await?;
+        assert_eq!(without.len(), 1);
+        assert_eq!(
+            without.keys().collect::<Vec<_>>(),
+            debug.keys().collect::<Vec<_>>()
+        );
+        assert_ne!(without.values().next(), debug.values().next());
+    }
+}
+
+#[tokio::test]
+async fn chunk_groups_only_contain_inputs_of_their_modules() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let flag = synthetic_asset_with_inputs(
+            AssetIdentVc::from_path(root.join("flag.js")),
+            AssetReferencesVc::empty(),
+            1,
+            define("FLAG", "on")?,
+        );
+        let with_flag = group(
+            root,
+            None,
+            AssetReferencesVc::cell(vec![SyntheticAssetReferenceVc::new(flag.into()).into()]),
+        );
+        let without_flag = group(root.join("other"), None, AssetReferencesVc::empty());
+
+        // flag.js is placed in the chunk of index.js, so its inputs are inputs
+        // of that chunk.
+        assert_eq!(
+            inputs(&with_flag).await?,
+            [("define:FLAG".to_string(), Some("\"on\"".to_string()))]
+        );
+        assert!(inputs(&without_flag).await?.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn string_and_bool_defines_are_distinct_inputs() {
+    run! {
+        turbopack_core::register();
+        let string = define("FLAG", "true")?.digest().await?;
+        let boolean = define("FLAG", true)?.digest().await?;
+        assert_ne!(*string, *boolean);
+        assert_eq!(*define("FLAG", true)?.digest().await?, *boolean);
+    }
+}
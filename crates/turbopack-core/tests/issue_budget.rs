@@ -0,0 +1,127 @@
+#![cfg(test)]
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use turbo_tasks::CompletionVc;
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    issue::{
+        budget::{IssueBudget, SuppressedIssuesVc},
+        suppression::IssueSuppressionsVc,
+        Issue, IssueSeverity, IssueVc, IssuesVc,
+    },
+    test_utils::{emit_synthetic_issues, SyntheticIssue},
+};
+
+register!();
+
+fn issues(context: FileSystemPathVc, issues: &[(IssueSeverity, &str)]) -> CompletionVc {
+    emit_synthetic_issues(IssuesVc::cell(
+        issues
+            .iter()
+            .enumerate()
+            .map(|(index, (severity, category))| {
+                SyntheticIssue {
+                    context,
+                    severity: *severity,
+                    category: category.to_string(),
+                    title: format!("issue {index}"),
+                }
+                .cell()
+                .into()
+            })
+            .collect(),
+    ))
+}
+
+async fn titles(issues: impl Iterator<Item = IssueVc>) -> Result<Vec<String>> {
+    let mut titles = Vec::new();
+    for issue in issues {
+        titles.push(issue.title().await?.clone_value());
+    }
+    titles.sort();
+    Ok(titles)
+}
+
+#[tokio::test]
+async fn issues_within_budget_are_captured() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let source = issues(
+            fs.root(),
+            &[
+                (IssueSeverity::Error, "resolve"),
+                (IssueSeverity::Warning, "parse"),
+            ],
+        );
+        let captured = IssueVc::peek_issues_with_path(source, IssueBudget::default(), IssueSuppressionsVc::empty())
+            .await?
+            .strongly_consistent()
+            .await?;
+        assert_eq!(captured.suppressed(), 0);
+        assert_eq!(titles(captured.iter()).await?, ["issue 0", "issue 1"]);
+    }
+}
+
+#[tokio::test]
+async fn issues_exceeding_budget_are_summarized() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let source = issues(
+            fs.root(),
+            &[
+                (IssueSeverity::Warning, "resolve"),
+                (IssueSeverity::Error, "resolve"),
+                (IssueSeverity::Warning, "resolve"),
+                (IssueSeverity::Warning, "parse"),
+                (IssueSeverity::Warning, "parse"),
+            ],
+        );
+        let budget = IssueBudget {
+            per_kind: 1,
+            total: 10,
+        };
+        let captured = IssueVc::peek_issues_with_path(source, budget, IssueSuppressionsVc::empty())
+            .await?
+            .strongly_consistent()
+            .await?;
+        assert_eq!(captured.suppressed(), 3);
+        assert_eq!(
+            titles(captured.iter()).await?,
+            ["3 more issues were suppressed", "issue 1", "issue 3"]
+        );
+
+        let mut summaries = Vec::new();
+        for issue in captured.iter() {
+            if let Some(summary) = SuppressedIssuesVc::resolve_from(issue).await? {
+                summaries.push(summary.await?.counts.clone());
+            }
+        }
+        assert_eq!(
+            summaries,
+            [BTreeMap::from([
+                ("parse".to_string(), 1),
+                ("resolve".to_string(), 2)
+            ])]
+        );
+    }
+}
+
+#[tokio::test]
+async fn unlimited_budget_captures_every_issue() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let source = issues(fs.root(), &[(IssueSeverity::Warning, "resolve"); 5]);
+        let captured = IssueVc::peek_issues_with_path(source, IssueBudget::unlimited(), IssueSuppressionsVc::empty())
+            .await?
+            .strongly_consistent()
+            .await?;
+        assert_eq!(captured.suppressed(), 0);
+        assert_eq!(captured.len(), 5);
+    }
+}
@@ -0,0 +1,144 @@
+#![cfg(test)]
+#![feature(min_specialization)]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::BoolVc, CompletionVc, RawVc, ReadRef, TransientInstance, TransientValue,
+};
+use turbo_tasks_fs::{FileSystem, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    issue::{
+        CapturedIssues, IssueReporter, IssueReporterVc, IssueSeverity, IssueSink,
+        IssueSinkReporterVc, IssueVc, IssuesVc, PlainIssue,
+    },
+    test_utils::{emit_synthetic_issues, SyntheticIssue},
+};
+
+register!();
+
+/// Counts the issues it reports.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+struct CountingReporter {
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    reported: Arc<AtomicUsize>,
+}
+
+impl PartialEq for CountingReporter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.reported, &other.reported)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl IssueReporter for CountingReporter {
+    #[turbo_tasks::function]
+    fn report_issues(
+        &self,
+        issues: TransientInstance<ReadRef<CapturedIssues>>,
+        _source: TransientValue<RawVc>,
+    ) -> BoolVc {
+        self.reported.fetch_add(issues.len(), Ordering::SeqCst);
+        BoolVc::cell(false)
+    }
+}
+
+/// Records the titles of the issues it receives.
+#[derive(Default)]
+struct RecordingSink {
+    titles: Mutex<Vec<String>>,
+}
+
+impl IssueSink for RecordingSink {
+    fn on_issue(&self, issue: &PlainIssue) {
+        self.titles.lock().unwrap().push(issue.title.clone());
+    }
+}
+
+fn issues(names: &[&str]) -> CompletionVc {
+    let fs: NullFileSystemVc = NullFileSystem.into();
+    emit_synthetic_issues(IssuesVc::cell(
+        names
+            .iter()
+            .map(|name| {
+                SyntheticIssue {
+                    context: fs.root().join(name),
+                    severity: IssueSeverity::Error,
+                    category: "test".to_string(),
+                    title: name.to_string(),
+                }
+                .cell()
+                .into()
+            })
+            .collect(),
+    ))
+}
+
+async fn report(source: CompletionVc, reporter: IssueReporterVc) -> Result<()> {
+    let captured = IssueVc::peek_issues_with_path(
+        source,
+        *reporter.issue_budget().await?,
+        reporter.issue_suppressions(),
+    )
+    .await?
+    .strongly_consistent()
+    .await?;
+    reporter
+        .report_issues(
+            TransientInstance::new(captured),
+            TransientValue::new(source.into()),
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn sinks_receive_reported_issues() {
+    run! {
+        turbopack_core::register();
+        let reported = Arc::new(AtomicUsize::new(0));
+        let sink = Arc::new(RecordingSink::default());
+        let reporter: IssueReporterVc = IssueSinkReporterVc::new(
+            TransientInstance::new(sink.clone() as Arc<dyn IssueSink>),
+            CountingReporter {
+                reported: reported.clone(),
+            }
+            .cell()
+            .into(),
+        )
+        .into();
+
+        let source = issues(&["a.js", "b.js"]);
+        report(source, reporter).await?;
+        let mut titles = sink.titles.lock().unwrap().clone();
+        titles.sort();
+        assert_eq!(titles, ["a.js", "b.js"]);
+        // The issues are reported by the wrapped reporter as well.
+        assert_eq!(reported.load(Ordering::SeqCst), 2);
+    }
+}
+
+#[tokio::test]
+async fn sinks_receive_issues_of_cached_computations() {
+    run! {
+        turbopack_core::register();
+        let reported = Arc::new(AtomicUsize::new(0));
+        let sink = Arc::new(RecordingSink::default());
+        let reporter: IssueReporterVc = IssueSinkReporterVc::new(
+            TransientInstance::new(sink.clone() as Arc<dyn IssueSink>),
+            CountingReporter { reported }.cell().into(),
+        )
+        .into();
+
+        let source = issues(&["a.js"]);
+        report(source, reporter).await?;
+        // The issues are emitted by a cached computation the second time.
+        report(source, reporter).await?;
+        assert_eq!(*sink.titles.lock().unwrap(), ["a.js", "a.js"]);
+    }
+}
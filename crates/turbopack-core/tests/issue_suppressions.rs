@@ -0,0 +1,119 @@
+#![cfg(test)]
+
+use std::fs;
+
+use anyhow::Result;
+use turbo_tasks::CompletionVc;
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystem, FileSystemPathVc, FileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    issue::{
+        budget::IssueBudget, suppression::IssueSuppressionsVc, CapturedIssuesReadRef, Issue,
+        IssueSeverity, IssueVc, IssuesVc,
+    },
+    test_utils::{emit_synthetic_issues, SyntheticIssue},
+};
+
+register!();
+
+const SUPPRESSIONS: &str = r#"{
+    "suppressions": [
+        { "code": "resolve", "path": "packages/legacy/**" },
+        { "code": "*", "path": "src/old.js", "expires": "2000-01-01" }
+    ]
+}"#;
+
+fn issues(root: FileSystemPathVc) -> CompletionVc {
+    let issue = |path: &str, severity: IssueSeverity, category: &str| {
+        SyntheticIssue {
+            context: root.join(path),
+            severity,
+            category: category.to_string(),
+            title: format!("{category} in {path}"),
+        }
+        .cell()
+        .into()
+    };
+    emit_synthetic_issues(IssuesVc::cell(vec![
+        issue("packages/legacy/index.js", IssueSeverity::Error, "resolve"),
+        issue("packages/legacy/index.js", IssueSeverity::Error, "parse"),
+        issue("src/old.js", IssueSeverity::Error, "resolve"),
+    ]))
+}
+
+async fn capture(
+    source: CompletionVc,
+    suppressions: IssueSuppressionsVc,
+) -> Result<CapturedIssuesReadRef> {
+    IssueVc::peek_issues_with_path(source, IssueBudget::default(), suppressions)
+        .await?
+        .strongly_consistent()
+        .await
+}
+
+/// The titles and severities of the captured issues.
+async fn reported(captured: &CapturedIssuesReadRef) -> Result<Vec<(String, IssueSeverity)>> {
+    let mut reported = Vec::new();
+    for issue in captured.iter() {
+        reported.push((issue.title().await?.clone_value(), *issue.severity().await?));
+    }
+    reported.sort();
+    Ok(reported)
+}
+
+#[tokio::test]
+async fn suppressions_are_read_from_the_suppression_file() {
+    run! {
+        turbopack_core::register();
+        let project = tempfile::tempdir()?;
+        fs::write(project.path().join("suppressions.json"), SUPPRESSIONS)?;
+        let disk_fs = DiskFileSystemVc::new(
+            "project".to_string(),
+            project.path().to_str().unwrap().to_string(),
+        );
+        let fs: FileSystemVc = disk_fs.into();
+        let root = fs.root();
+        let source = issues(root);
+        let suppressions = IssueSuppressionsVc::read(root.join("suppressions.json"));
+
+        let captured = capture(source, suppressions).await?;
+        assert_eq!(captured.silenced(), 1);
+        // The expired suppression reports its issue as a warning.
+        assert_eq!(
+            reported(&captured).await?,
+            [
+                ("parse in packages/legacy/index.js".to_string(), IssueSeverity::Error),
+                ("resolve in src/old.js".to_string(), IssueSeverity::Warning),
+            ]
+        );
+
+        // Changing the file changes the captured issues.
+        fs::write(
+            project.path().join("suppressions.json"),
+            r#"{ "suppressions": [] }"#,
+        )?;
+        disk_fs.await?.invalidate();
+        let captured = capture(source, suppressions).await?;
+        assert_eq!(captured.silenced(), 0);
+        assert_eq!(captured.len(), 3);
+    }
+}
+
+#[tokio::test]
+async fn missing_suppression_file_suppresses_nothing() {
+    run! {
+        turbopack_core::register();
+        let project = tempfile::tempdir()?;
+        let fs: FileSystemVc = DiskFileSystemVc::new(
+            "project".to_string(),
+            project.path().to_str().unwrap().to_string(),
+        )
+        .into();
+        let root = fs.root();
+        let suppressions = IssueSuppressionsVc::read(root.join("suppressions.json"));
+
+        let captured = capture(issues(root), suppressions).await?;
+        assert_eq!(captured.silenced(), 0);
+        assert_eq!(captured.len(), 3);
+    }
+}
@@ -0,0 +1,85 @@
+#![cfg(test)]
+
+use indexmap::IndexMap;
+use serde_json::json;
+use turbo_tasks::TraitRef;
+use turbo_tasks_testing::{register, run};
+use turbopack_core::version::{
+    manifest::ManifestContentVc, NotFoundVersionVc, Update, UpdateVc, Version, VersionVc,
+    VersionedContent, VersionedContentVc,
+};
+
+register!();
+
+fn update_from(content: ManifestContentVc, from: VersionVc) -> UpdateVc {
+    let content: VersionedContentVc = content.into();
+    content.update(from)
+}
+
+fn manifest(entries: serde_json::Value) -> ManifestContentVc {
+    let serde_json::Value::Object(entries) = entries else {
+        panic!("manifest entries must be an object");
+    };
+    ManifestContentVc::new(entries.into_iter().collect::<IndexMap<_, _>>())
+}
+
+#[tokio::test]
+async fn manifest_updates_contain_changed_entries() {
+    run! {
+        turbopack_core::register();
+        let old = manifest(json!({ "/a": ["a.js"], "/b": ["b.js"] }));
+        let new = manifest(json!({ "/a": ["a.js"], "/b": ["b2.js"], "/c": ["c.js"] }));
+        let from: VersionVc = old.version().into();
+
+        let update = update_from(new, from).await?;
+        let Update::Partial(update) = &*update else {
+            panic!("expected a partial update");
+        };
+        assert_eq!(
+            *update.instruction,
+            json!({ "type": "ManifestUpdate", "added": { "/b": ["b2.js"], "/c": ["c.js"] } })
+        );
+        assert_eq!(
+            *TraitRef::cell(update.to.clone()).id().await?,
+            *new.version().as_version().id().await?
+        );
+
+        let update = update_from(old, new.version().into()).await?;
+        let Update::Partial(update) = &*update else {
+            panic!("expected a partial update");
+        };
+        assert_eq!(
+            *update.instruction,
+            json!({ "type": "ManifestUpdate", "added": { "/b": ["b.js"] }, "removed": ["/c"] })
+        );
+    }
+}
+
+#[tokio::test]
+async fn unchanged_manifest_has_no_update() {
+    run! {
+        turbopack_core::register();
+        let old = manifest(json!({ "/a": ["a.js"], "/b": ["b.js"] }));
+        // The order of the entries doesn't matter.
+        let new = manifest(json!({ "/b": ["b.js"], "/a": ["a.js"] }));
+
+        assert!(matches!(*update_from(new, old.version().into()).await?, Update::None));
+        assert_eq!(
+            *old.version().as_version().id().await?,
+            *new.version().as_version().id().await?
+        );
+    }
+}
+
+#[tokio::test]
+async fn manifest_without_previous_version_is_replaced() {
+    run! {
+        turbopack_core::register();
+        let content = manifest(json!({ "/a": ["a.js"] }));
+
+        assert!(matches!(
+            *update_from(content, NotFoundVersionVc::new().into()).await?,
+            Update::Total(_)
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+#![cfg(test)]
+
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    asset::{AssetVc, AssetsVc},
+    chunk::output_path_collisions,
+    ident::{AssetIdentVc, ModifierKind},
+    reference::AssetReferencesVc,
+    test_utils::synthetic_asset,
+};
+
+register!();
+
+/// An asset at `path` with `size` bytes of content. Assets with different
+/// `variant`s have different idents.
+fn asset(path: FileSystemPathVc, variant: &str, size: usize) -> AssetVc {
+    let ident = AssetIdentVc::from_path(path).with_modifier(
+        Value::new(ModifierKind::Custom),
+        StringVc::cell(variant.to_string()),
+    );
+    synthetic_asset(ident, AssetReferencesVc::empty(), size).into()
+}
+
+#[tokio::test]
+async fn assets_with_different_content_collide() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let path = fs.root().join("out/a.js");
+        let a = asset(path, "a", 1);
+        let b = asset(path, "b", 2);
+
+        let collisions = output_path_collisions(AssetsVc::cell(vec![b, a, a]), false).await?;
+        assert_eq!(collisions.len(), 1);
+        assert!(collisions[0].path.ends_with("out/a.js"));
+        assert_eq!(collisions[0].idents.len(), 2);
+        assert!(collisions[0].idents[0] < collisions[0].idents[1]);
+
+        // The result doesn't depend on the order of the assets.
+        let reordered = output_path_collisions(AssetsVc::cell(vec![a, b]), false).await?;
+        assert_eq!(*reordered, *collisions);
+    }
+}
+
+#[tokio::test]
+async fn assets_with_identical_content_do_not_collide() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let path = fs.root().join("out/a.js");
+        let assets = AssetsVc::cell(vec![asset(path, "a", 1), asset(path, "c", 1)]);
+        assert!(output_path_collisions(assets, false).await?.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn paths_that_differ_in_case_collide_on_case_insensitive_file_systems() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let assets = AssetsVc::cell(vec![
+            asset(fs.root().join("out/a.js"), "a", 1),
+            asset(fs.root().join("out/A.js"), "A", 2),
+        ]);
+        assert!(output_path_collisions(assets, false).await?.is_empty());
+        assert_eq!(output_path_collisions(assets, true).await?.len(), 1);
+    }
+}
@@ -0,0 +1,224 @@
+#![cfg(test)]
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use turbo_tasks::{ReadRef, TryJoinIterExt, Value};
+use turbo_tasks_fs::{FileSystem, FileSystemPathVc, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    asset::{Asset, AssetVc, AssetsVc},
+    chunk::{ChunkItem, ChunkVc, ChunkingContextVc, SharedChunkPlan, SharedChunkPlanVc},
+    environment::{EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    ident::AssetIdentVc,
+    reference::AssetReferencesVc,
+    test_utils::{
+        synthetic_asset, SyntheticAssetReferenceVc, SyntheticChunkVc, SyntheticChunkingContextVc,
+    },
+};
+
+register!();
+
+fn chunking_context(root: FileSystemPathVc) -> ChunkingContextVc {
+    let environment = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Custom(0)),
+        Value::new(EnvironmentIntention::Client),
+    );
+    SyntheticChunkingContextVc::new(root, environment).into()
+}
+
+fn module(root: FileSystemPathVc, name: &str, references: &[AssetVc]) -> AssetVc {
+    synthetic_asset(
+        AssetIdentVc::from_path(root.join(name)),
+        AssetReferencesVc::cell(
+            references
+                .iter()
+                .map(|asset| SyntheticAssetReferenceVc::new(*asset).into())
+                .collect(),
+        ),
+        1,
+    )
+    .into()
+}
+
+/// The entries a.js, b.js and c.js of an app. a.js and b.js use react.js,
+/// which uses scheduler.js, and a.js and c.js use util.js.
+fn entries(root: FileSystemPathVc) -> Vec<AssetVc> {
+    let scheduler = module(root, "scheduler.js", &[]);
+    let react = module(root, "react.js", &[scheduler]);
+    let util = module(root, "util.js", &[]);
+    vec![
+        module(root, "a.js", &[react, util]),
+        module(root, "b.js", &[react]),
+        module(root, "c.js", &[util]),
+    ]
+}
+
+async fn plan(
+    root: FileSystemPathVc,
+    entries: &[AssetVc],
+    min_entries: usize,
+) -> Result<ReadRef<SharedChunkPlan>> {
+    SharedChunkPlanVc::new(
+        chunking_context(root),
+        AssetsVc::cell(entries.to_vec()),
+        min_entries,
+    )
+    .await
+}
+
+/// The names of the modules in `chunk`.
+async fn modules(chunk: ChunkVc) -> Result<BTreeSet<String>> {
+    let chunk = SyntheticChunkVc::resolve_from(chunk)
+        .await?
+        .expect("synthetic chunking context creates synthetic chunks");
+    Ok(chunk
+        .chunk_content()
+        .await?
+        .chunk_items
+        .iter()
+        .map(|item| async move { Ok(item.asset_ident().path().await?.path.clone()) })
+        .try_join()
+        .await?
+        .into_iter()
+        .collect())
+}
+
+fn set(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// The paths of `assets`.
+async fn paths(assets: impl IntoIterator<Item = AssetVc>) -> Result<BTreeSet<String>> {
+    Ok(assets
+        .into_iter()
+        .map(|asset| async move { Ok(asset.ident().path().await?.path.clone()) })
+        .try_join()
+        .await?
+        .into_iter()
+        .collect())
+}
+
+#[tokio::test]
+async fn shared_modules_are_chunked_once() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let entries = entries(root);
+        let plan = plan(root, &entries, 2).await?;
+
+        let mut shared = BTreeSet::new();
+        for chunk in plan.shared_chunks.iter() {
+            shared.insert(modules(*chunk).await?);
+        }
+        assert_eq!(
+            shared,
+            BTreeSet::from([set(&["react.js", "scheduler.js"]), set(&["util.js"])])
+        );
+
+        // The chunks of the entries leave out the shared modules.
+        for (entry, name) in entries.iter().zip(["a.js", "b.js", "c.js"]) {
+            let chunk = plan.entry_chunks[&entry.resolve().await?];
+            assert_eq!(modules(chunk).await?, set(&[name]));
+        }
+    }
+}
+
+#[tokio::test]
+async fn entries_only_load_the_shared_chunks_they_use() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let entries = entries(root);
+        let plan_vc = SharedChunkPlanVc::new(
+            chunking_context(root),
+            AssetsVc::cell(entries.clone()),
+            2,
+        );
+        let plan = plan_vc.await?;
+        let chunk_path = |chunk: ChunkVc| async move {
+            let asset: AssetVc = chunk.into();
+            Ok::<_, anyhow::Error>(asset.ident().path().await?.path.clone())
+        };
+        let mut react = None;
+        let mut util = None;
+        for chunk in plan.shared_chunks.iter() {
+            let path = chunk_path(*chunk).await?;
+            if modules(*chunk).await?.contains("react.js") {
+                react = Some(path);
+            } else {
+                util = Some(path);
+            }
+        }
+        let (react, util) = (react.unwrap(), util.unwrap());
+
+        for (entry, shared) in entries.iter().zip([vec![&react, &util], vec![&react], vec![&util]]) {
+            let entry = entry.resolve().await?;
+            let mut expected = shared
+                .into_iter()
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            expected.insert(chunk_path(plan.entry_chunks[&entry]).await?);
+            let loaded = plan_vc.entry_assets(entry).await?;
+            assert_eq!(paths(loaded.iter().copied()).await?, expected);
+        }
+    }
+}
+
+#[tokio::test]
+async fn shared_chunks_load_the_shared_chunks_they_overlap_with() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        // x.js is used by a.js and b.js, y.js by b.js and c.js, and both use
+        // z.js, so the chunk of y.js leaves out z.js.
+        let z = module(root, "z.js", &[]);
+        let x = module(root, "x.js", &[z]);
+        let y = module(root, "y.js", &[z]);
+        let entries = vec![
+            module(root, "a.js", &[x]),
+            module(root, "b.js", &[x, y]),
+            module(root, "c.js", &[y]),
+        ];
+        let plan = plan(root, &entries, 2).await?;
+
+        let mut shared = Vec::new();
+        for chunk in plan.shared_chunks.iter() {
+            shared.push(modules(*chunk).await?);
+        }
+        assert_eq!(shared, [set(&["x.js", "z.js"]), set(&["y.js"])]);
+
+        // c.js only uses y.js, but z.js is in the chunk of x.js.
+        let c = entries[2].resolve().await?;
+        assert_eq!(plan.entry_shared_chunks[&c], plan.shared_chunks);
+        let a = entries[0].resolve().await?;
+        assert_eq!(plan.entry_shared_chunks[&a], plan.shared_chunks[..1].to_vec());
+    }
+}
+
+#[tokio::test]
+async fn modules_used_by_fewer_than_min_entries_stay_in_entry_chunks() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let root = fs.root();
+        let entries = entries(root);
+
+        // No module is used by all three entries.
+        let plan = plan(root, &entries, 3).await?;
+        assert!(plan.shared_chunks.is_empty());
+        let a = plan.entry_chunks[&entries[0].resolve().await?];
+        assert_eq!(
+            modules(a).await?,
+            set(&["a.js", "react.js", "scheduler.js", "util.js"])
+        );
+        let b = plan.entry_chunks[&entries[1].resolve().await?];
+        assert_eq!(modules(b).await?, set(&["b.js", "react.js", "scheduler.js"]));
+        for entry in entries.iter() {
+            assert!(plan.entry_shared_chunks[&entry.resolve().await?].is_empty());
+        }
+    }
+}
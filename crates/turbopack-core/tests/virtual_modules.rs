@@ -0,0 +1,102 @@
+#![cfg(test)]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use turbo_tasks_fs::{FileContent, FileSystem, NullFileSystem, NullFileSystemVc};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    resolve::{
+        virtual_modules::{
+            GeneratedModule, VirtualModuleGenerator, VirtualModuleProvider, VirtualModuleProviderVc,
+        },
+        ResolveResultVc,
+    },
+};
+
+register!();
+
+/// Generates `export default <n>` for the module `counter`, where `n` counts
+/// the generations.
+#[derive(Default)]
+struct CounterGenerator {
+    generations: AtomicUsize,
+}
+
+#[async_trait]
+impl VirtualModuleGenerator for CounterGenerator {
+    async fn generate(&self, name: &str) -> Result<Option<GeneratedModule>> {
+        if name != "counter" {
+            return Ok(None);
+        }
+        let generation = self.generations.fetch_add(1, Ordering::SeqCst);
+        Ok(Some(GeneratedModule {
+            content: format!("export default {generation}"),
+            dependencies: vec![],
+        }))
+    }
+}
+
+async fn content(result: ResolveResultVc) -> Result<String> {
+    let Some(asset) = *result.first_asset().strongly_consistent().await? else {
+        bail!("the virtual module wasn't resolved");
+    };
+    let AssetContent::File(file) = &*asset.content().await? else {
+        bail!("the virtual module isn't a file");
+    };
+    let FileContent::Content(file) = &*file.await? else {
+        bail!("the virtual module wasn't found");
+    };
+    Ok(file.content().to_str()?.to_string())
+}
+
+#[tokio::test]
+async fn generates_modules_on_demand() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let generator = Arc::new(CounterGenerator::default());
+        let provider = VirtualModuleProvider::new(fs.root().join("virtual"), generator.clone());
+        let provider_vc = VirtualModuleProviderVc::new(provider.clone());
+
+        let counter = provider_vc.resolve("virtual:test/counter", "counter");
+        assert_eq!(content(counter).await?, "export default 0");
+        // Reading the module again doesn't generate it again.
+        assert_eq!(content(counter).await?, "export default 0");
+        assert_eq!(generator.generations.load(Ordering::SeqCst), 1);
+
+        assert!(
+            *provider_vc
+                .resolve("virtual:test/missing", "missing")
+                .is_unresolveable()
+                .await?
+        );
+    }
+}
+
+#[tokio::test]
+async fn refresh_generates_modules_again() {
+    run! {
+        turbopack_core::register();
+        let fs: NullFileSystemVc = NullFileSystem.into();
+        let generator = Arc::new(CounterGenerator::default());
+        let provider = VirtualModuleProvider::new(fs.root().join("virtual"), generator.clone());
+        let provider_vc = VirtualModuleProviderVc::new(provider.clone());
+
+        let counter = provider_vc.resolve("virtual:test/counter", "counter");
+        assert_eq!(content(counter).await?, "export default 0");
+
+        for generation in 1..=3 {
+            provider.refresh();
+            assert_eq!(
+                content(counter).await?,
+                format!("export default {generation}")
+            );
+        }
+    }
+}
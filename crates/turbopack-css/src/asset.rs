@@ -16,7 +16,8 @@ use turbopack_core::{
         ChunkableAssetVc, ChunkingContextVc,
     },
     context::AssetContextVc,
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
+    introspect::rebuilds::{is_rebuild_recording_enabled, record_chunk_item_rebuild},
     reference::{AssetReference, AssetReferencesVc},
     resolve::{
         origin::{ResolveOrigin, ResolveOriginVc},
@@ -100,7 +101,9 @@ impl CssModuleAssetVc {
 impl Asset for CssModuleAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source.ident().with_modifier(modifier())
+        self.source
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
@@ -181,6 +184,9 @@ impl ChunkItem for ModuleChunkItem {
 impl CssChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
     async fn content(&self) -> Result<CssChunkItemContentVc> {
+        if is_rebuild_recording_enabled() {
+            record_chunk_item_rebuild(self.module.ident().to_string().await?.as_str());
+        }
         let references = &*self.module.references().await?;
         let mut imports = vec![];
         let context = self.context;
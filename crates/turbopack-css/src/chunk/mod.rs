@@ -6,21 +6,28 @@ use std::fmt::Write;
 
 use anyhow::{anyhow, Result};
 use indexmap::IndexSet;
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    TryJoinIterExt, Value, ValueToString,
+};
 use turbo_tasks_fs::{rope::Rope, File, FileSystemPathOptionVc};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc, AssetsVc},
     chunk::{
         availability_info::AvailabilityInfo, chunk_content, chunk_content_split, Chunk,
-        ChunkContentResult, ChunkGroupReferenceVc, ChunkItem, ChunkItemVc, ChunkVc,
-        ChunkableAssetVc, ChunkingContext, ChunkingContextVc, ChunksVc, FromChunkableAsset,
-        ModuleId, ModuleIdVc, ModuleIdsVc, OutputChunk, OutputChunkRuntimeInfo,
+        ChunkContentResult, ChunkGroupLoading, ChunkGroupReferenceVc, ChunkIdentVc, ChunkItem,
+        ChunkItemVc, ChunkVc, ChunkableAssetVc, ChunkingContext, ChunkingContextVc, ChunksVc,
+        FromChunkableAsset, ModuleId, ModuleIdVc, ModuleIdsVc, OutputChunk, OutputChunkRuntimeInfo,
         OutputChunkRuntimeInfoVc, OutputChunkVc,
     },
-    code_builder::{CodeBuilder, CodeVc},
-    ident::{AssetIdent, AssetIdentVc},
+    code_builder::{ChunkCodeType, CodeBuilder, CodeVc},
+    ident::{AssetIdent, AssetIdentVc, ModifierKind},
     introspect::{
         asset::{children_from_asset_references, content_to_details, IntrospectableAssetVc},
+        chunk_summary::{
+            ChunkItemSize, ChunkSummary, ChunkSummaryVc, IntrospectableChunk, IntrospectableChunkVc,
+        },
+        rebuilds::{is_rebuild_recording_enabled, record_chunk_rebuild},
         Introspectable, IntrospectableChildrenVc, IntrospectableVc,
     },
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
@@ -148,9 +155,13 @@ impl CssChunkContentVc {
 
         let mut body = CodeBuilder::default();
         let mut external_imports = IndexSet::new();
+        let mut items = Vec::new();
         for entry in this.main_entries.await?.iter() {
             let entry_placeable = CssChunkPlaceableVc::cast_from(entry);
             let entry_item = entry_placeable.as_chunk_item(this.context);
+            if is_rebuild_recording_enabled() {
+                items.push(entry_item.asset_ident().to_string().await?.clone_value());
+            }
 
             for external_import in expand_imports(&mut body, entry_item).await? {
                 external_imports.insert(external_import.await?.to_owned());
@@ -165,13 +176,26 @@ impl CssChunkContentVc {
 
         code.push_code(&body.build());
 
+        let chunk_path = this.chunk.path().await?;
+        let wrappers = this
+            .context
+            .code_wrappers(Value::new(ChunkCodeType::Css))
+            .await?;
+        let content = code.build();
+        let mut code = CodeBuilder::default();
+        code.push_wrapped(
+            &content,
+            &wrappers,
+            ChunkCodeType::Css,
+            chunk_path.file_name(),
+        )?;
+
         if *this
             .context
             .reference_chunk_source_maps(this.chunk.into())
             .await?
             && code.has_source_map()
         {
-            let chunk_path = this.chunk.path().await?;
             write!(
                 code,
                 "\n/*# sourceMappingURL={}.map*/",
@@ -179,6 +203,8 @@ impl CssChunkContentVc {
             )?;
         }
 
+        record_chunk_rebuild(chunk_name.await?.as_str(), items);
+
         let c = code.build().cell();
         Ok(c)
     }
@@ -286,6 +312,11 @@ impl Chunk for CssChunk {
         self.context
     }
 
+    #[turbo_tasks::function]
+    fn is_stylesheet(&self) -> BoolVc {
+        BoolVc::cell(true)
+    }
+
     #[turbo_tasks::function]
     async fn parallel_chunks(&self) -> Result<ChunksVc> {
         let content = css_chunk_content(
@@ -300,6 +331,21 @@ impl Chunk for CssChunk {
         }
         Ok(ChunksVc::cell(chunks))
     }
+
+    #[turbo_tasks::function]
+    async fn chunk_ident(&self) -> Result<ChunkIdentVc> {
+        let entries = self
+            .main_entries
+            .await?
+            .iter()
+            .map(|entry| entry.ident())
+            .collect();
+        Ok(ChunkIdentVc::from_entries(
+            "css".to_string(),
+            self.context.context_path(),
+            entries,
+        ))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -418,7 +464,14 @@ impl Asset for CssChunk {
             }
         }
         for entry in content.async_chunk_group_entries.iter() {
-            references.push(ChunkGroupReferenceVc::new(this.context, *entry).into());
+            references.push(
+                ChunkGroupReferenceVc::new_with_loading(
+                    this.context,
+                    *entry,
+                    Value::new(ChunkGroupLoading::Lazy),
+                )
+                .into(),
+            );
         }
         for item in content.chunk_items.iter() {
             references.push(SingleItemCssChunkReferenceVc::new(this.context, *item).into());
@@ -456,12 +509,17 @@ impl CssChunkContextVc {
 
     #[turbo_tasks::function]
     pub async fn chunk_item_id(self, chunk_item: CssChunkItemVc) -> Result<ModuleIdVc> {
-        let layer = self.await?.context.layer();
+        let context = self.await?.context;
+        let layer = context.layer();
         let mut ident = chunk_item.asset_ident();
         if !layer.await?.is_empty() {
-            ident = ident.with_modifier(layer)
+            ident = ident.with_modifier(Value::new(ModifierKind::Layer), layer)
         }
-        Ok(ModuleId::String(ident.to_string().await?.clone_value()).cell())
+        let ident = ident.to_string().await?;
+        if let Some(module_id_map) = *context.module_id_map().await? {
+            return Ok(module_id_map.module_id(&ident));
+        }
+        Ok(ModuleId::String(ident.clone_value()).cell())
     }
 }
 
@@ -568,3 +626,29 @@ impl Introspectable for CssChunk {
         Ok(IntrospectableChildrenVc::cell(children))
     }
 }
+
+#[turbo_tasks::value_impl]
+impl IntrospectableChunk for CssChunk {
+    #[turbo_tasks::function]
+    async fn summary(&self) -> Result<ChunkSummaryVc> {
+        let chunk_content = css_chunk_content(
+            self.context,
+            self.main_entries,
+            Value::new(self.availability_info),
+        )
+        .await?;
+        let items = chunk_content
+            .chunk_items
+            .iter()
+            .map(|item| async move {
+                Ok(ChunkItemSize {
+                    ident: item.asset_ident().to_string().await?.clone_value(),
+                    code_bytes: item.content().await?.inner_code.len(),
+                })
+            })
+            .try_join()
+            .await?;
+        let layer = self.context.layer().await?.clone_value();
+        Ok(ChunkSummary::new(layer, items).cell())
+    }
+}
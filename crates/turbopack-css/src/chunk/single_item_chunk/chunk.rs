@@ -1,13 +1,13 @@
 use std::fmt::Write;
 
 use anyhow::Result;
-use turbo_tasks::{primitives::StringVc, ValueToString};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString};
 use turbo_tasks_fs::File;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{Chunk, ChunkItem, ChunkVc, ChunkingContext, ChunkingContextVc},
     code_builder::{CodeBuilder, CodeVc},
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     introspect::{Introspectable, IntrospectableVc},
     reference::AssetReferencesVc,
     source_map::{GenerateSourceMap, GenerateSourceMapVc, OptionSourceMapVc},
@@ -92,7 +92,7 @@ impl Asset for SingleItemCssChunk {
             self.context.chunk_path(
                 self.item
                     .asset_ident()
-                    .with_modifier(single_item_modifier()),
+                    .with_modifier(Value::new(ModifierKind::Chunk), single_item_modifier()),
                 ".css",
             ),
         ))
@@ -17,7 +17,7 @@ use turbopack_core::{
         ChunkingType, ChunkingTypeOptionVc,
     },
     context::AssetContextVc,
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{
@@ -72,7 +72,9 @@ impl ModuleCssModuleAssetVc {
 impl Asset for ModuleCssModuleAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.inner.source_ident().with_modifier(modifier())
+        self.inner
+            .source_ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
@@ -358,10 +360,13 @@ impl EcmascriptChunkItem for ModuleChunkItem {
             inner_code: code.clone().into(),
             // We generate a minimal map for runtime code so that the filename is
             // displayed in dev tools.
-            source_map: Some(generate_minimal_source_map(
-                self.module.ident().to_string().await?.to_string(),
-                code,
-            )),
+            source_map: Some(
+                generate_minimal_source_map(
+                    self.module.ident().to_string().await?.to_string(),
+                    code,
+                )
+                .into(),
+            ),
             ..Default::default()
         }
         .cell())
@@ -419,7 +424,12 @@ struct CssProxyModuleAsset {
 impl Asset for CssProxyModuleAsset {
     #[turbo_tasks::function]
     async fn ident(&self) -> Result<AssetIdentVc> {
-        Ok(self.module.await?.inner.ident().with_modifier(modifier()))
+        Ok(self
+            .module
+            .await?
+            .inner
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier()))
     }
 
     #[turbo_tasks::function]
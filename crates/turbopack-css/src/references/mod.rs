@@ -18,6 +18,7 @@ use turbopack_core::{
     reference_type::{CssReferenceSubType, ReferenceType},
     resolve::{
         handle_resolve_error,
+        options::{ResolveOptionsOverrides, ResolveOptionsOverridesVc},
         origin::{ResolveOrigin, ResolveOriginVc},
         parse::RequestVc,
         ResolveResultVc,
@@ -168,6 +169,7 @@ impl<'a> VisitAstPath for AssetReferencesVisitor<'a> {
                         issue_span.lo.to_usize(),
                         issue_span.hi.to_usize(),
                     ),
+                    Some(url_resolve_overrides()),
                 )
                 .into(),
             );
@@ -177,6 +179,17 @@ impl<'a> VisitAstPath for AssetReferencesVisitor<'a> {
     }
 }
 
+/// Browsers request the exact file that a `url()` points to, so no extensions
+/// are tried, unlike for imports.
+#[turbo_tasks::function]
+fn url_resolve_overrides() -> ResolveOptionsOverridesVc {
+    ResolveOptionsOverrides {
+        extensions: Some(Vec::new()),
+        ..Default::default()
+    }
+    .cell()
+}
+
 #[turbo_tasks::function]
 pub async fn css_resolve(
     origin: ResolveOriginVc,
@@ -12,12 +12,13 @@ use turbopack_core::{
     reference::{AssetReference, AssetReferenceVc},
     reference_type::UrlReferenceSubType,
     resolve::{
+        options::ResolveOptionsOverridesVc,
         origin::{ResolveOrigin, ResolveOriginVc},
         parse::RequestVc,
         PrimaryResolveResult, ResolveResultVc,
     },
 };
-use turbopack_ecmascript::resolve::url_resolve;
+use turbopack_ecmascript::resolve::url_resolve_with_overrides;
 
 use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
@@ -33,12 +34,14 @@ pub enum ReferencedAsset {
 }
 
 #[turbo_tasks::value]
-#[derive(Hash, Debug)]
+#[derive(Clone, Hash, Debug)]
 pub struct UrlAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub path: AstPathVc,
     pub issue_source: IssueSourceVc,
+    /// Applied on top of the resolve options of the origin
+    pub resolve_overrides: Option<ResolveOptionsOverridesVc>,
 }
 
 #[turbo_tasks::value_impl]
@@ -49,12 +52,14 @@ impl UrlAssetReferenceVc {
         request: RequestVc,
         path: AstPathVc,
         issue_source: IssueSourceVc,
+        resolve_overrides: Option<ResolveOptionsOverridesVc>,
     ) -> Self {
         Self::cell(UrlAssetReference {
             origin,
             request,
             path,
             issue_source,
+            resolve_overrides,
         })
     }
 
@@ -78,10 +83,11 @@ impl UrlAssetReferenceVc {
 impl AssetReference for UrlAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        url_resolve(
+        url_resolve_with_overrides(
             self.origin,
             self.request,
             Value::new(UrlReferenceSubType::CssUrl),
+            self.resolve_overrides,
             self.issue_source,
             IssueSeverity::Error.cell(),
         )
@@ -118,10 +124,20 @@ impl CodeGenerateable for UrlAssetReference {
         if let ReferencedAsset::Some(asset) = &*self_vc.get_referenced_asset(context).await? {
             // TODO(WEB-662) This is not the correct way to get the path of the asset.
             // `asset` is on module-level, but we need the output-level asset instead.
-            let path = asset.ident().path().await?;
-            let relative_path = context_path
-                .get_relative_path_to(&path)
-                .unwrap_or_else(|| format!("/{}", path.path));
+            let path = asset.ident().path();
+            let relative_path = match &*context
+                .public_path()
+                .url(context.output_root(), path)
+                .await?
+            {
+                Some(url) => url.clone(),
+                None => {
+                    let path = path.await?;
+                    context_path
+                        .get_relative_path_to(&path)
+                        .unwrap_or_else(|| format!("/{}", path.path))
+                }
+            };
 
             visitors.push(
                 create_visitor!((&this.path.await?), visit_mut_url(u: &mut Url) {
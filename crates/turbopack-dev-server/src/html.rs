@@ -6,7 +6,8 @@ use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc, AssetsVc},
     chunk::{
-        ChunkableAsset, ChunkableAssetVc, ChunkingContext, ChunkingContextVc, EvaluatableAssetsVc,
+        ChunkGroupStylesheetsVc, ChunkableAsset, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, EvaluatableAssetsVc,
     },
     ident::AssetIdentVc,
     reference::{AssetReferencesVc, SingleAssetReferenceVc},
@@ -15,7 +16,8 @@ use turbopack_core::{
 
 /// The HTML entry point of the dev server.
 ///
-/// Generates an HTML page that includes the ES and CSS chunks.
+/// Generates an HTML page that includes the ES and CSS chunks. Stylesheets of
+/// lazy chunk groups are preloaded.
 #[turbo_tasks::value(shared)]
 #[derive(Clone)]
 pub struct DevHtmlAsset {
@@ -123,17 +125,42 @@ impl DevHtmlAssetVc {
     #[turbo_tasks::function]
     async fn html_content(self) -> Result<DevHtmlAssetContentVc> {
         let this = self.await?;
-        let context_path = this.path.parent().await?;
+        let context_path = &*this.path.parent().await?;
+        let chunks = self.chunks();
+        let stylesheets = ChunkGroupStylesheetsVc::new(chunks).await?;
 
-        let mut chunk_paths = vec![];
-        for chunk in &*self.chunks().await? {
-            let chunk_path = &*chunk.ident().path().await?;
-            if let Some(relative_path) = context_path.get_path_to(chunk_path) {
-                chunk_paths.push(format!("/{relative_path}"));
+        let relative_path = |asset: AssetVc| async move {
+            let path = &*asset.ident().path().await?;
+            Ok::<_, anyhow::Error>(
+                context_path
+                    .get_path_to(path)
+                    .map(|relative_path| format!("/{relative_path}")),
+            )
+        };
+        let mut script_paths = vec![];
+        let mut stylesheet_paths = vec![];
+        for chunk in &*chunks.await? {
+            if let Some(path) = relative_path(*chunk).await? {
+                if stylesheets.blocking.contains(chunk) {
+                    stylesheet_paths.push(path);
+                } else {
+                    script_paths.push(path);
+                }
+            }
+        }
+        let mut preload_paths = vec![];
+        for stylesheet in &stylesheets.lazy {
+            if let Some(path) = relative_path(*stylesheet).await? {
+                preload_paths.push(path);
             }
         }
 
-        Ok(DevHtmlAssetContentVc::new(chunk_paths, this.body.clone()))
+        Ok(DevHtmlAssetContentVc::new(
+            script_paths,
+            stylesheet_paths,
+            preload_paths,
+            this.body.clone(),
+        ))
     }
 
     #[turbo_tasks::function]
@@ -168,13 +195,28 @@ impl DevHtmlAssetVc {
 
 #[turbo_tasks::value]
 struct DevHtmlAssetContent {
-    chunk_paths: Vec<String>,
+    script_paths: Vec<String>,
+    /// Stylesheets that block rendering.
+    stylesheet_paths: Vec<String>,
+    /// Stylesheets of lazy chunk groups.
+    preload_paths: Vec<String>,
     body: Option<String>,
 }
 
 impl DevHtmlAssetContentVc {
-    pub fn new(chunk_paths: Vec<String>, body: Option<String>) -> Self {
-        DevHtmlAssetContent { chunk_paths, body }.cell()
+    pub fn new(
+        script_paths: Vec<String>,
+        stylesheet_paths: Vec<String>,
+        preload_paths: Vec<String>,
+        body: Option<String>,
+    ) -> Self {
+        DevHtmlAssetContent {
+            script_paths,
+            stylesheet_paths,
+            preload_paths,
+            body,
+        }
+        .cell()
     }
 }
 
@@ -187,14 +229,21 @@ impl DevHtmlAssetContentVc {
         let mut scripts = Vec::new();
         let mut stylesheets = Vec::new();
 
-        for relative_path in &*this.chunk_paths {
+        for relative_path in &*this.stylesheet_paths {
+            stylesheets.push(format!(
+                "<link data-turbopack rel=\"stylesheet\" href=\"{}\">",
+                relative_path
+            ));
+        }
+        for relative_path in &*this.preload_paths {
+            stylesheets.push(format!(
+                "<link data-turbopack rel=\"preload\" as=\"style\" href=\"{}\">",
+                relative_path
+            ));
+        }
+        for relative_path in &*this.script_paths {
             if relative_path.ends_with(".js") {
                 scripts.push(format!("<script src=\"{}\"></script>", relative_path));
-            } else if relative_path.ends_with(".css") {
-                stylesheets.push(format!(
-                    "<link data-turbopack rel=\"stylesheet\" href=\"{}\">",
-                    relative_path
-                ));
             } else {
                 return Err(anyhow!("chunk with unknown asset type: {}", relative_path));
             }
@@ -245,8 +294,15 @@ impl Version for DevHtmlAssetVersion {
     #[turbo_tasks::function]
     async fn id(&self) -> Result<StringVc> {
         let mut hasher = Xxh3Hash64Hasher::new();
-        for relative_path in &*self.content.chunk_paths {
-            hasher.write_ref(relative_path);
+        for paths in [
+            &self.content.script_paths,
+            &self.content.stylesheet_paths,
+            &self.content.preload_paths,
+        ] {
+            hasher.write_value(paths.len());
+            for relative_path in paths {
+                hasher.write_ref(relative_path);
+            }
         }
         if let Some(body) = &self.content.body {
             hasher.write_ref(body);
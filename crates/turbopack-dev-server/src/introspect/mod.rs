@@ -8,7 +8,12 @@ use turbo_tasks::{
 use turbo_tasks_fs::{json::parse_json_with_source_context, File, FileContent};
 use turbopack_core::{
     asset::AssetContent,
-    introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc},
+    introspect::{
+        chunk_summary::{IntrospectableChunk, IntrospectableChunkVc},
+        graph_export::export_introspection_graph,
+        query::query_introspection,
+        Introspectable, IntrospectableChildrenVc, IntrospectableVc,
+    },
 };
 use turbopack_ecmascript::utils::FormatIter;
 
@@ -82,6 +87,36 @@ impl ContentSource for IntrospectionSource {
         path: &str,
         _data: turbo_tasks::Value<ContentSourceData>,
     ) -> Result<ContentSourceResultVc> {
+        if let Some(query) = path.strip_prefix("query/") {
+            let roots = self_vc.await?.roots.iter().copied().collect();
+            let results = query_introspection(roots, query).await?;
+            return Ok(json_result(File::from(results)));
+        }
+        if path == "graph.bin" {
+            let roots = self_vc.await?.roots.iter().copied().collect();
+            let mut bytes = Vec::new();
+            export_introspection_graph(roots, &mut bytes).await?;
+            return Ok(ContentSourceResultVc::exact(
+                ContentSourceContentVc::static_content(
+                    AssetContent::File(
+                        FileContent::Content(
+                            File::from(bytes).with_content_type(mime::APPLICATION_OCTET_STREAM),
+                        )
+                        .cell(),
+                    )
+                    .cell()
+                    .into(),
+                )
+                .into(),
+            ));
+        }
+        if let Some(path) = path.strip_prefix("summary/") {
+            let introspectable: IntrospectableVc = parse_json_with_source_context(path)?;
+            let Some(chunk) = IntrospectableChunkVc::resolve_from(introspectable).await? else {
+                return Ok(ContentSourceResultVc::not_found());
+            };
+            return Ok(json_result(File::from(chunk.summary().to_json().await?)));
+        }
         let introspectable = if path.is_empty() {
             let roots = &self_vc.await?.roots;
             if roots.len() == 1 {
@@ -135,6 +170,19 @@ impl ContentSource for IntrospectionSource {
             })
             .try_join()
             .await?;
+        let summary = match IntrospectableChunkVc::resolve_from(introspectable).await? {
+            Some(chunk) => {
+                let summary = chunk.summary().to_details().await;
+                format!(
+                    "<h3>Summary <a href=\"./summary/{path}\">[json]</a></h3><pre>{summary}</pre>",
+                    path = HtmlStringEscaped(urlencoding::encode(&serde_json::to_string(
+                        &introspectable
+                    )?)),
+                    summary = HtmlEscaped(str_or_err(&summary)),
+                )
+            }
+            None => String::new(),
+        };
         let details = if details.is_empty() {
             String::new()
         } else if has_children {
@@ -156,6 +204,7 @@ impl ContentSource for IntrospectionSource {
   <h3>{internal_ty}</h3>
   <h2>{ty}</h2>
   <h1>{title}</h1>
+  {summary}
   {details}
   <ul>{children}</ul>
 </body>
@@ -177,3 +226,16 @@ impl ContentSource for IntrospectionSource {
         ))
     }
 }
+
+fn json_result(json: File) -> ContentSourceResultVc {
+    ContentSourceResultVc::exact(
+        ContentSourceContentVc::static_content(
+            AssetContent::File(
+                FileContent::Content(json.with_content_type(mime::APPLICATION_JSON)).cell(),
+            )
+            .cell()
+            .into(),
+        )
+        .into(),
+    )
+}
@@ -43,6 +43,11 @@ use crate::invalidation::ServerRequest;
 pub trait SourceProvider: Send + Clone + 'static {
     /// must call a turbo-tasks function internally
     fn get_source(&self) -> ContentSourceVc;
+
+    /// Called when a request or an update subscription starts, before the
+    /// source is read for it. Never called from within a turbo-tasks
+    /// function.
+    fn request_started(&self) {}
 }
 
 pub trait ContentProvider: Send + Clone + 'static {
@@ -80,7 +85,9 @@ async fn handle_issues<T: Into<RawVc> + CollectiblesSource + Copy>(
     operation: &str,
     issue_reporter: IssueReporterVc,
 ) -> Result<()> {
-    let issues = IssueVc::peek_issues_with_path(source)
+    let budget = *issue_reporter.issue_budget().await?;
+    let suppressions = issue_reporter.issue_suppressions();
+    let issues = IssueVc::peek_issues_with_path(source, budget, suppressions)
         .await?
         .strongly_consistent()
         .await?;
@@ -149,6 +156,7 @@ impl DevServerBuilder {
                     let tt = tt.clone();
                     let get_issue_reporter = get_issue_reporter.clone();
                     let source_provider = source_provider.clone();
+                    source_provider.request_started();
                     let future = async move {
                         let reason = ServerRequest {
                             method: request.method().clone(),
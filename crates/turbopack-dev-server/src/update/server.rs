@@ -59,6 +59,7 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
                 message = client.try_next() => {
                     match message? {
                         Some(ClientMessage::Subscribe { resource }) => {
+                            self.source_provider.request_started();
                             let get_content = {
                                 let source_provider = self.source_provider.clone();
                                 let request = resource_to_request(&resource)?;
@@ -71,7 +72,7 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
                                     )
                                 }
                             };
-                            match UpdateStream::new(resource.to_string(), TransientInstance::new(Box::new(get_content))).await {
+                            match UpdateStream::new(resource.to_string(), TransientInstance::new(Box::new(get_content)), self.issue_reporter).await {
                                 Ok(stream) => {
                                     streams.insert(resource, stream);
                                 }
@@ -11,8 +11,8 @@ use turbo_tasks_fs::{FileSystem, FileSystemPathVc};
 use turbopack_core::{
     error::PrettyPrintError,
     issue::{
-        Issue, IssueSeverity, IssueSeverityVc, IssueVc, OptionIssueProcessingPathItemsVc,
-        PlainIssueReadRef,
+        Issue, IssueReporter, IssueReporterVc, IssueSeverity, IssueSeverityVc, IssueVc,
+        OptionIssueProcessingPathItemsVc, PlainIssueReadRef,
     },
     server_fs::ServerFileSystemVc,
     version::{
@@ -28,8 +28,15 @@ use crate::source::{
 
 type GetContentFn = Box<dyn Fn() -> ResolveSourceRequestResultVc + Send + Sync>;
 
-async fn peek_issues<T: CollectiblesSource + Copy>(source: T) -> Result<Vec<PlainIssueReadRef>> {
-    let captured = IssueVc::peek_issues_with_path(source).await?.await?;
+async fn peek_issues<T: CollectiblesSource + Copy>(
+    source: T,
+    issue_reporter: IssueReporterVc,
+) -> Result<Vec<PlainIssueReadRef>> {
+    let budget = *issue_reporter.issue_budget().await?;
+    let suppressions = issue_reporter.issue_suppressions();
+    let captured = IssueVc::peek_issues_with_path(source, budget, suppressions)
+        .await?
+        .await?;
 
     captured.get_plain_issues().await
 }
@@ -49,9 +56,10 @@ async fn get_update_stream_item(
     resource: &str,
     from: VersionStateVc,
     get_content: TransientInstance<GetContentFn>,
+    issue_reporter: IssueReporterVc,
 ) -> Result<UpdateStreamItemVc> {
     let content = get_content();
-    let mut plain_issues = peek_issues(content).await?;
+    let mut plain_issues = peek_issues(content, issue_reporter).await?;
 
     let content_value = match content.await {
         Ok(content) => content,
@@ -95,7 +103,10 @@ async fn get_update_stream_item(
             let from = from.get();
             let update = resolved_content.update(from);
 
-            extend_issues(&mut plain_issues, peek_issues(update).await?);
+            extend_issues(
+                &mut plain_issues,
+                peek_issues(update, issue_reporter).await?,
+            );
 
             let update = update.await?;
 
@@ -112,7 +123,10 @@ async fn get_update_stream_item(
                 return Ok(UpdateStreamItem::NotFound.cell());
             }
 
-            extend_issues(&mut plain_issues, peek_issues(proxy_result).await?);
+            extend_issues(
+                &mut plain_issues,
+                peek_issues(proxy_result, issue_reporter).await?,
+            );
 
             let from = from.get();
             if let Some(from) = ProxyResultVc::resolve_from(from).await? {
@@ -166,9 +180,10 @@ async fn compute_update_stream(
     resource: &str,
     from: VersionStateVc,
     get_content: TransientInstance<GetContentFn>,
+    issue_reporter: IssueReporterVc,
     sender: TransientInstance<Sender<Result<UpdateStreamItemReadRef>>>,
 ) {
-    let item = get_update_stream_item(resource, from, get_content)
+    let item = get_update_stream_item(resource, from, get_content, issue_reporter)
         .strongly_consistent()
         .await;
 
@@ -214,6 +229,7 @@ impl UpdateStream {
     pub async fn new(
         resource: String,
         get_content: TransientInstance<GetContentFn>,
+        issue_reporter: IssueReporterVc,
     ) -> Result<UpdateStream> {
         let (sx, rx) = tokio::sync::mpsc::channel(32);
 
@@ -233,6 +249,7 @@ impl UpdateStream {
             &resource,
             version_state,
             get_content,
+            issue_reporter,
             TransientInstance::new(sx),
         );
 
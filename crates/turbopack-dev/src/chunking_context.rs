@@ -12,17 +12,24 @@ use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64, DeterministicHash, Xxh3Hash
 use turbopack_core::{
     asset::{Asset, AssetVc, AssetsVc},
     chunk::{
-        availability_info::AvailabilityInfo, Chunk, ChunkVc, ChunkableAsset, ChunkableAssetVc,
-        ChunkingContext, ChunkingContextVc, ChunksVc, EvaluatableAssetsVc,
+        availability_info::AvailabilityInfo,
+        module_id_map::{ModuleIdMapVc, OptionModuleIdMapVc},
+        CancellationTokenVc, Chunk, ChunkGenerationLimitVc, ChunkVc, ChunkableAsset,
+        ChunkableAssetVc, ChunkingContext, ChunkingContextVc, ChunkingHints, ChunkingHintsVc,
+        ChunksVc, EvaluatableAssetsVc, ExternalInputsVc, IssueTolerancePolicy,
+        IssueTolerancePolicyVc, ModuleConcatenation, ModuleConcatenationVc, NamedChunksVc,
+        OptionNamedChunksVc, OptionPublicPathVc, PathCollisionStrategy, PathSanitizationPolicy,
+        PublicPath,
     },
+    code_builder::{ChunkCodeType, CodeWrapper, CodeWrappersVc},
     environment::EnvironmentVc,
-    ident::{AssetIdent, AssetIdentVc},
+    ident::{AssetIdent, AssetIdentVc, ModifierKind},
     resolve::ModulePart,
 };
 use turbopack_css::chunk::{CssChunkVc, CssChunksVc};
 use turbopack_ecmascript::chunk::{
-    EcmascriptChunkItemVc, EcmascriptChunkVc, EcmascriptChunkingContext,
-    EcmascriptChunkingContextVc, EcmascriptChunksVc,
+    ChunkItemCodeCacheVc, EcmascriptChunkItemVc, EcmascriptChunkVc, EcmascriptChunkingContext,
+    EcmascriptChunkingContextVc, EcmascriptChunksVc, OptionChunkItemCodeCacheVc,
 };
 
 use crate::{
@@ -46,6 +53,13 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    /// Inlines parallel and async references into the referencing chunk, see
+    /// [ChunkingContext::is_code_splitting_enabled].
+    pub fn disable_code_splitting(mut self) -> Self {
+        self.context.disable_code_splitting = true;
+        self
+    }
+
     pub fn layer(mut self, layer: &str) -> Self {
         self.context.layer = (!layer.is_empty()).then(|| layer.to_string());
         self
@@ -61,6 +75,99 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    pub fn chunking_hints(mut self, hints: ChunkingHints) -> Self {
+        self.context.chunking_hints = hints;
+        self
+    }
+
+    pub fn module_concatenation(mut self, concatenation: ModuleConcatenation) -> Self {
+        self.context.module_concatenation = concatenation;
+        self
+    }
+
+    pub fn cancellation_token(mut self, token: CancellationTokenVc) -> Self {
+        self.context.cancellation_token = Some(token);
+        self
+    }
+
+    /// Limits how many chunks of a chunk group are generated at the same
+    /// time, e.g. on machines with little memory.
+    pub fn chunk_generation_limit(mut self, limit: ChunkGenerationLimitVc) -> Self {
+        self.context.chunk_generation_limit = Some(limit);
+        self
+    }
+
+    /// How chunks that end up with the same path are dealt with, see
+    /// [PathCollisionStrategy].
+    pub fn path_collision_strategy(mut self, strategy: PathCollisionStrategy) -> Self {
+        self.context.path_collision_strategy = strategy;
+        self
+    }
+
+    pub fn path_sanitization(mut self, policy: PathSanitizationPolicy) -> Self {
+        self.context.path_sanitization = policy;
+        self
+    }
+
+    /// Uses the numeric module ids of `map` for the modules in it.
+    pub fn module_id_map(mut self, map: ModuleIdMapVc) -> Self {
+        self.context.module_id_map = Some(map);
+        self
+    }
+
+    /// Splits assets that match `named_chunks` into chunks whose file names
+    /// start with the configured name. The ecmascript assets with the same
+    /// name end up in a single chunk, which the chunk groups of an entry and
+    /// of its async imports share. CSS chunks aren't merged, since that could
+    /// change the order of their rules.
+    pub fn named_chunks(mut self, named_chunks: NamedChunksVc) -> Self {
+        self.context.named_chunks = Some(named_chunks);
+        self
+    }
+
+    pub fn issue_tolerance(mut self, policy: IssueTolerancePolicy) -> Self {
+        self.context.issue_tolerance = policy;
+        self
+    }
+
+    /// Wraps the code of chunks of `chunk_type` in `wrapper`. Wrappers
+    /// registered first end up outermost.
+    pub fn code_wrapper(mut self, chunk_type: ChunkCodeType, wrapper: CodeWrapper) -> Self {
+        self.context.code_wrappers.push((chunk_type, wrapper));
+        self
+    }
+
+    /// Declares `inputs` as inputs of all chunks, in addition to the inputs
+    /// that are recorded while chunks are computed, e.g. substituted defines.
+    /// Only needed for inputs that turbopack can't observe. They are part of
+    /// the digests of `ChunkGroup::input_digests`.
+    pub fn external_inputs(mut self, inputs: ExternalInputsVc) -> Self {
+        self.context.external_inputs = Some(inputs);
+        self
+    }
+
+    /// Serves the output root from `public_path`, e.g. a CDN.
+    pub fn public_path(mut self, public_path: PublicPath) -> Self {
+        self.context.public_path = Some(public_path);
+        self
+    }
+
+    /// Reads the code of chunk items from `code_cache` instead of generating
+    /// it, when the cache has an entry for them.
+    pub fn code_cache(mut self, code_cache: ChunkItemCodeCacheVc) -> Self {
+        self.context.code_cache = Some(code_cache);
+        self
+    }
+
+    /// Lists the [ChunkIdent]s of chunks in chunk lists, so dev tools can
+    /// correlate chunks across rebuilds.
+    ///
+    /// [ChunkIdent]: turbopack_core::chunk::ChunkIdent
+    pub fn expose_chunk_idents(mut self) -> Self {
+        self.context.expose_chunk_idents = true;
+        self
+    }
+
     pub fn build(self) -> ChunkingContextVc {
         DevChunkingContextVc::new(Value::new(self.context)).into()
     }
@@ -90,8 +197,38 @@ pub struct DevChunkingContext {
     layer: Option<String>,
     /// Enable HMR for this chunking
     enable_hot_module_replacement: bool,
+    /// Inline parallel and async references into the referencing chunk
+    disable_code_splitting: bool,
     /// The environment chunks will be evaluated in.
     environment: EnvironmentVc,
+    /// Preferred granularity of chunk groups, used by the chunk optimizers
+    chunking_hints: ChunkingHints,
+    /// Whether chunk items are concatenated into shared scopes
+    module_concatenation: ModuleConcatenation,
+    /// Allows the embedder to stop chunking operations of this context
+    cancellation_token: Option<CancellationTokenVc>,
+    /// Limits how many chunks are generated at the same time
+    chunk_generation_limit: Option<ChunkGenerationLimitVc>,
+    /// Avoids chunks that are written to the same path
+    path_collision_strategy: PathCollisionStrategy,
+    /// Makes chunk and asset file names safe to use on other platforms
+    path_sanitization: PathSanitizationPolicy,
+    /// Numeric module ids persisted between builds
+    module_id_map: Option<ModuleIdMapVc>,
+    /// Chunk names configured for assets
+    named_chunks: Option<NamedChunksVc>,
+    /// Decides which issues of chunk items abort chunk generation
+    issue_tolerance: IssueTolerancePolicy,
+    /// Banners, footers and wrappers applied to the code of chunks
+    code_wrappers: Vec<(ChunkCodeType, CodeWrapper)>,
+    /// Inputs of the chunks that aren't files
+    external_inputs: Option<ExternalInputsVc>,
+    /// Where the output root is served from
+    public_path: Option<PublicPath>,
+    /// Generated code of chunk items persisted between builds
+    code_cache: Option<ChunkItemCodeCacheVc>,
+    /// List the idents of chunks in chunk lists
+    expose_chunk_idents: bool,
 }
 
 impl DevChunkingContextVc {
@@ -112,12 +249,39 @@ impl DevChunkingContextVc {
                 asset_root_path,
                 layer: None,
                 enable_hot_module_replacement: false,
+                disable_code_splitting: false,
                 environment,
+                chunking_hints: ChunkingHints::default(),
+                module_concatenation: ModuleConcatenation::default(),
+                cancellation_token: None,
+                chunk_generation_limit: None,
+                path_collision_strategy: PathCollisionStrategy::default(),
+                path_sanitization: PathSanitizationPolicy::default(),
+                module_id_map: None,
+                named_chunks: None,
+                issue_tolerance: IssueTolerancePolicy::default(),
+                code_wrappers: Vec::new(),
+                external_inputs: None,
+                public_path: None,
+                code_cache: None,
+                expose_chunk_idents: false,
             },
         }
     }
 }
 
+impl DevChunkingContext {
+    /// Length of the path of `directory` relative to the output root, used to
+    /// enforce [PathSanitizationPolicy::max_path_length].
+    async fn directory_len(&self, directory: FileSystemPathVc) -> Result<usize> {
+        let output_root = self.output_root.await?;
+        let directory = directory.await?;
+        Ok(output_root
+            .get_path_to(&directory)
+            .map_or(directory.path.len(), |path| path.len()))
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl DevChunkingContextVc {
     #[turbo_tasks::function]
@@ -125,6 +289,13 @@ impl DevChunkingContextVc {
         this.into_value().cell()
     }
 
+    /// Whether chunk lists list the idents of their chunks, see
+    /// [DevChunkingContextBuilder::expose_chunk_idents].
+    #[turbo_tasks::function]
+    pub(crate) async fn exposes_chunk_idents(self) -> Result<BoolVc> {
+        Ok(BoolVc::cell(self.await?.expose_chunk_idents))
+    }
+
     #[turbo_tasks::function]
     pub(crate) async fn generate_chunk(
         self_vc: DevChunkingContextVc,
@@ -183,6 +354,7 @@ impl ChunkingContext for DevChunkingContext {
         fn clean(s: &str) -> String {
             s.replace('/', "_")
         }
+        let ident_vc = ident;
         let ident = &*ident.await?;
 
         // For clippy -- This explicit deref is necessary
@@ -230,14 +402,14 @@ impl ChunkingContext for DevChunkingContext {
             has_hash = true;
         }
         for modifier in modifiers.iter() {
-            let modifier = modifier.await?;
+            let value = modifier.value.await?;
             if let Some(default_modifier) = default_modifier {
-                if *modifier == default_modifier {
+                if modifier.kind == ModifierKind::AssetType && *value == default_modifier {
                     continue;
                 }
             }
             3_u8.deterministic_hash(&mut hasher);
-            modifier.deterministic_hash(&mut hasher);
+            value.deterministic_hash(&mut hasher);
             has_hash = true;
         }
         if let Some(part) = part {
@@ -286,6 +458,25 @@ impl ChunkingContext for DevChunkingContext {
             let truncated_hash = &hash[..5];
             name = format!("{}_{}", truncated_hash, &name[i..]);
         }
+        // Chunks that merge several assets of a named chunk are named after
+        // their entries, since their path is the common parent of the entries.
+        let named_paths = if assets.is_empty() {
+            vec![ident.path]
+        } else {
+            let mut paths = Vec::new();
+            for (key, asset) in assets {
+                if key.await?.is_empty() {
+                    paths.push(asset.path());
+                }
+            }
+            paths
+        };
+        if let Some(chunk_name) = OptionNamedChunksVc::cell(self.named_chunks)
+            .common_chunk_name(named_paths)
+            .await?
+        {
+            name = format!("{}_{}", chunk_name, name);
+        }
         // We need to make sure that `.json` and `.json.js` doesn't end up with the same
         // name. So when we add an extra extension when want to mark that with a "._"
         // suffix.
@@ -299,6 +490,12 @@ impl ChunkingContext for DevChunkingContext {
         } else {
             root_path
         };
+        let name = self
+            .path_sanitization
+            .sanitize_file_name(self.directory_len(root_path).await?, &name);
+        let name = self
+            .path_collision_strategy
+            .file_name(&name, &ident_vc.to_string().await?);
         Ok(root_path.join(&name))
     }
 
@@ -332,9 +529,14 @@ impl ChunkingContext for DevChunkingContext {
     }
 
     #[turbo_tasks::function]
-    fn asset_path(&self, content_hash: &str, extension: &str) -> FileSystemPathVc {
-        self.asset_root_path
-            .join(&format!("{content_hash}.{extension}"))
+    async fn asset_path(&self, content_hash: &str, extension: &str) -> Result<FileSystemPathVc> {
+        let name = self.path_sanitization.sanitize_file_name(
+            self.directory_len(self.asset_root_path).await?,
+            &format!("{content_hash}.{extension}"),
+        );
+        // Assets are named after their content hash, so assets with the same
+        // path have the same content.
+        Ok(self.asset_root_path.join(&name))
     }
 
     #[turbo_tasks::function]
@@ -342,6 +544,11 @@ impl ChunkingContext for DevChunkingContext {
         BoolVc::cell(self.enable_hot_module_replacement)
     }
 
+    #[turbo_tasks::function]
+    fn is_code_splitting_enabled(&self) -> BoolVc {
+        BoolVc::cell(!self.disable_code_splitting)
+    }
+
     #[turbo_tasks::function]
     fn layer(&self) -> StringVc {
         StringVc::cell(self.layer.clone().unwrap_or_default())
@@ -354,6 +561,70 @@ impl ChunkingContext for DevChunkingContext {
         Ok(DevChunkingContextVc::new(Value::new(context)).into())
     }
 
+    #[turbo_tasks::function]
+    fn chunking_hints(&self) -> ChunkingHintsVc {
+        self.chunking_hints.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn module_concatenation(&self) -> ModuleConcatenationVc {
+        self.module_concatenation.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn cancellation_token(&self) -> CancellationTokenVc {
+        self.cancellation_token
+            .unwrap_or_else(CancellationTokenVc::never)
+    }
+
+    #[turbo_tasks::function]
+    fn chunk_generation_limit(&self) -> ChunkGenerationLimitVc {
+        self.chunk_generation_limit
+            .unwrap_or_else(ChunkGenerationLimitVc::unlimited)
+    }
+
+    #[turbo_tasks::function]
+    fn module_id_map(&self) -> OptionModuleIdMapVc {
+        OptionModuleIdMapVc::cell(self.module_id_map)
+    }
+
+    #[turbo_tasks::function]
+    fn named_chunks(&self) -> OptionNamedChunksVc {
+        OptionNamedChunksVc::cell(self.named_chunks)
+    }
+
+    #[turbo_tasks::function]
+    fn issue_tolerance(&self) -> IssueTolerancePolicyVc {
+        self.issue_tolerance.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn code_wrappers(&self, chunk_type: Value<ChunkCodeType>) -> CodeWrappersVc {
+        let chunk_type = chunk_type.into_value();
+        CodeWrappersVc::cell(
+            self.code_wrappers
+                .iter()
+                .filter(|(ty, _)| *ty == chunk_type)
+                .map(|(_, wrapper)| wrapper.clone())
+                .collect(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    fn external_inputs(&self) -> ExternalInputsVc {
+        self.external_inputs.unwrap_or_else(ExternalInputsVc::empty)
+    }
+
+    #[turbo_tasks::function]
+    fn public_path(&self) -> OptionPublicPathVc {
+        OptionPublicPathVc::cell(self.public_path.clone().map(PublicPath::cell))
+    }
+
+    #[turbo_tasks::function]
+    fn chunk_asset(self_vc: DevChunkingContextVc, chunk: ChunkVc) -> AssetVc {
+        self_vc.generate_chunk(chunk)
+    }
+
     #[turbo_tasks::function]
     async fn chunk_group(self_vc: DevChunkingContextVc, entry_chunk: ChunkVc) -> Result<AssetsVc> {
         let parallel_chunks = get_parallel_chunks([entry_chunk]).await?;
@@ -435,6 +706,11 @@ impl EcmascriptChunkingContext for DevChunkingContext {
         let manifest_asset = DevManifestChunkAssetVc::new(asset, self_vc, availability_info);
         DevManifestLoaderItemVc::new(manifest_asset).into()
     }
+
+    #[turbo_tasks::function]
+    fn code_cache(&self) -> OptionChunkItemCodeCacheVc {
+        OptionChunkItemCodeCacheVc::cell(self.code_cache)
+    }
 }
 
 async fn get_parallel_chunks<I>(entries: I) -> Result<impl Iterator<Item = ChunkVc>>
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use indexmap::IndexSet;
 use turbo_tasks::{TryJoinIterExt, Value};
+use turbopack_core::chunk::ChunkingContext;
 use turbopack_css::chunk::{CssChunkPlaceablesVc, CssChunkVc, CssChunksVc};
 
 #[turbo_tasks::function]
@@ -17,7 +18,14 @@ pub async fn optimize_css_chunks(chunks: CssChunksVc) -> Result<CssChunksVc> {
     // information, as chunks are already fully flattened by the
     // time they reach the optimizer.
 
-    merge_adjacent_chunks(chunks).await
+    let Some(first) = chunks.await?.first().copied() else {
+        return Ok(chunks);
+    };
+    let context = first.await?.context;
+    context.cancellation_token().await?.check()?;
+    let max_chunk_count = context.chunking_hints().await?.normalized().max_chunk_count;
+
+    merge_adjacent_chunks(chunks, max_chunk_count).await
 }
 
 async fn merge_chunks(
@@ -40,17 +48,16 @@ async fn merge_chunks(
     ))
 }
 
-/// The maximum number of chunks to exist in a single chunk group. The optimizer
-/// will merge chunks into groups until it has at most this number of chunks.
-const MAX_CHUNK_COUNT: usize = 20;
-
-/// Groups adjacent chunks into at most `MAX_CHUNK_COUNT` groups.
-fn aggregate_adjacent_chunks(chunks: &[CssChunkVc]) -> Vec<Vec<CssChunkVc>> {
+/// Groups adjacent chunks into at most `max_chunk_count` groups.
+fn aggregate_adjacent_chunks(
+    chunks: &[CssChunkVc],
+    max_chunk_count: usize,
+) -> Vec<Vec<CssChunkVc>> {
     // Each of the resulting merged chunks will have `chunks_per_merged_chunk`
     // chunks in them, except for the first `chunks_mod` chunks, which will have
     // one more chunk.
-    let chunks_per_merged_chunk = chunks.len() / MAX_CHUNK_COUNT;
-    let mut chunks_mod = chunks.len() % MAX_CHUNK_COUNT;
+    let chunks_per_merged_chunk = chunks.len() / max_chunk_count;
+    let mut chunks_mod = chunks.len() % max_chunk_count;
 
     let mut chunks_vecs = vec![];
     let mut current_chunks = vec![];
@@ -75,15 +82,18 @@ fn aggregate_adjacent_chunks(chunks: &[CssChunkVc]) -> Vec<Vec<CssChunkVc>> {
     chunks_vecs
 }
 
-/// Merges adjacent chunks into at most `MAX_CHUNK_COUNT` chunks.
-async fn merge_adjacent_chunks(chunks_vc: CssChunksVc) -> Result<CssChunksVc> {
+/// Merges adjacent chunks into at most `max_chunk_count` chunks.
+async fn merge_adjacent_chunks(
+    chunks_vc: CssChunksVc,
+    max_chunk_count: usize,
+) -> Result<CssChunksVc> {
     let chunks = chunks_vc.await?;
 
-    if chunks.len() <= MAX_CHUNK_COUNT {
+    if chunks.len() <= max_chunk_count {
         return Ok(chunks_vc);
     }
 
-    let chunks = aggregate_adjacent_chunks(&chunks);
+    let chunks = aggregate_adjacent_chunks(&chunks, max_chunk_count);
 
     let chunks = chunks
         .into_iter()
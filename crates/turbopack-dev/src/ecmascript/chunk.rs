@@ -1,14 +1,19 @@
 use anyhow::Result;
 use indexmap::IndexSet;
-use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{
-        ChunkingContext, OutputChunk, OutputChunkRuntimeInfo, OutputChunkRuntimeInfoVc,
-        OutputChunkVc,
+        Chunk, ChunkIdentVc, ChunkItem, ChunkingContext, OutputChunk, OutputChunkRuntimeInfo,
+        OutputChunkRuntimeInfoVc, OutputChunkVc,
+    },
+    ident::{AssetIdentVc, ModifierKind},
+    introspect::{
+        chunk_summary::{
+            ChunkItemSize, ChunkSummary, ChunkSummaryVc, IntrospectableChunk, IntrospectableChunkVc,
+        },
+        Introspectable, IntrospectableChildrenVc, IntrospectableVc,
     },
-    ident::AssetIdentVc,
-    introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc},
     reference::AssetReferencesVc,
     source_map::{
         GenerateSourceMap, GenerateSourceMapVc, OptionSourceMapVc, SourceMapAssetReferenceVc,
@@ -37,6 +42,12 @@ impl EcmascriptDevChunkVc {
         }
         .cell()
     }
+
+    /// Returns the [ChunkIdentVc] of the underlying ecmascript chunk.
+    #[turbo_tasks::function]
+    pub fn chunk_ident(&self) -> ChunkIdentVc {
+        self.chunk.chunk_ident()
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -81,7 +92,10 @@ impl EcmascriptDevChunkVc {
 impl Asset for EcmascriptDevChunk {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        let ident = self.chunk.ident().with_modifier(modifier());
+        let ident = self
+            .chunk
+            .ident()
+            .with_modifier(Value::new(ModifierKind::Chunk), modifier());
         AssetIdentVc::from_path(self.chunking_context.chunk_path(ident, ".js"))
     }
 
@@ -161,3 +175,33 @@ impl Introspectable for EcmascriptDevChunk {
         Ok(IntrospectableChildrenVc::cell(children))
     }
 }
+
+#[turbo_tasks::value_impl]
+impl IntrospectableChunk for EcmascriptDevChunk {
+    #[turbo_tasks::function]
+    async fn summary(self_vc: EcmascriptDevChunkVc) -> Result<ChunkSummaryVc> {
+        let this = self_vc.await?;
+        let entries = self_vc.own_content().await?.entries.await?;
+        let entries = &entries;
+        let items = this
+            .chunk
+            .chunk_content()
+            .await?
+            .chunk_items
+            .iter()
+            .map(|item| async move {
+                let code_bytes = match entries.get(&item.id().await?) {
+                    Some(entry) => entry.code.await?.source_code().len(),
+                    None => 0,
+                };
+                Ok(ChunkItemSize {
+                    ident: item.asset_ident().to_string().await?.clone_value(),
+                    code_bytes,
+                })
+            })
+            .try_join()
+            .await?;
+        let layer = this.chunking_context.layer().await?.clone_value();
+        Ok(ChunkSummary::new(layer, items).cell())
+    }
+}
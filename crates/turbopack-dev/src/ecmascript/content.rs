@@ -2,18 +2,23 @@ use std::io::Write;
 
 use anyhow::{bail, Result};
 use indoc::writedoc;
+use turbo_tasks::{TryJoinIterExt, Value, ValueToString};
 use turbo_tasks_fs::File;
 use turbopack_core::{
     asset::{Asset, AssetContentVc},
-    chunk::{ChunkingContext, ModuleId},
-    code_builder::{CodeBuilder, CodeVc},
+    chunk::{ChunkItem, ChunkingContext, ModuleId},
+    code_builder::{ChunkCodeType, CodeBuilder, CodeVc},
+    introspect::rebuilds::{is_rebuild_recording_enabled, record_chunk_rebuild},
     source_map::{GenerateSourceMap, GenerateSourceMapVc, OptionSourceMapVc},
     version::{
         MergeableVersionedContent, MergeableVersionedContentVc, UpdateVc, VersionVc,
         VersionedContent, VersionedContentMergerVc, VersionedContentVc,
     },
 };
-use turbopack_ecmascript::{chunk::EcmascriptChunkContentVc, utils::StringifyJs};
+use turbopack_ecmascript::{
+    chunk::{EcmascriptChunkContentVc, EcmascriptChunkingContext},
+    utils::StringifyJs,
+};
 
 use super::{
     chunk::EcmascriptDevChunkVc, content_entry::EcmascriptDevChunkContentEntriesVc,
@@ -24,6 +29,7 @@ use crate::DevChunkingContextVc;
 #[turbo_tasks::value(serialization = "none")]
 pub(super) struct EcmascriptDevChunkContent {
     pub(super) entries: EcmascriptDevChunkContentEntriesVc,
+    pub(super) content: EcmascriptChunkContentVc,
     pub(super) chunking_context: DevChunkingContextVc,
     pub(super) chunk: EcmascriptDevChunkVc,
 }
@@ -36,11 +42,16 @@ impl EcmascriptDevChunkContentVc {
         chunk: EcmascriptDevChunkVc,
         content: EcmascriptChunkContentVc,
     ) -> Result<Self> {
-        let entries = EcmascriptDevChunkContentEntriesVc::new(content)
-            .resolve()
-            .await?;
+        let entries =
+            EcmascriptDevChunkContentEntriesVc::new(content, chunking_context.issue_tolerance())
+                .resolve()
+                .await?;
+        if let Some(code_cache) = *chunking_context.code_cache().await? {
+            code_cache.save(content).await?;
+        }
         Ok(EcmascriptDevChunkContent {
             entries,
+            content,
             chunking_context,
             chunk,
         }
@@ -99,11 +110,31 @@ impl EcmascriptDevChunkContentVc {
 
         write!(code, "\n}}]);")?;
 
+        let filename = chunk_path.file_name();
+        let wrappers = this
+            .chunking_context
+            .code_wrappers(Value::new(ChunkCodeType::Ecmascript))
+            .await?;
+        let content = code.build();
+        let mut code = CodeBuilder::default();
+        code.push_wrapped(&content, &wrappers, ChunkCodeType::Ecmascript, filename)?;
+
         if code.has_source_map() {
-            let filename = chunk_path.file_name();
             write!(code, "\n\n//# sourceMappingURL={}.map", filename)?;
         }
 
+        if is_rebuild_recording_enabled() {
+            let items = this
+                .content
+                .await?
+                .chunk_items
+                .iter()
+                .map(|item| async move { Ok(item.asset_ident().to_string().await?.clone_value()) })
+                .try_join()
+                .await?;
+            record_chunk_rebuild(chunk_server_path, items);
+        }
+
         Ok(code.build().cell())
     }
 }
@@ -7,13 +7,17 @@ use turbo_tasks::{
     TryJoinIterExt, Value, ValueToString,
 };
 use turbopack_core::{
-    chunk::{availability_info::AvailabilityInfo, ChunkItem, ModuleIdReadRef},
+    chunk::{
+        availability_info::AvailabilityInfo, ChunkItem, IssueTolerance, IssueTolerancePolicyVc,
+        ModuleIdReadRef,
+    },
     code_builder::{CodeBuilder, CodeVc},
     error::PrettyPrintError,
+    introspect::rebuilds::{is_rebuild_recording_enabled, record_chunk_item_rebuild},
     issue::{code_gen::CodeGenerationIssue, IssueSeverity},
 };
 use turbopack_ecmascript::chunk::{
-    EcmascriptChunkContentVc, EcmascriptChunkItem, EcmascriptChunkItemVc,
+    EcmascriptChunkContentVc, EcmascriptChunkItem, EcmascriptChunkItemVc, EcmascriptChunkingContext,
 };
 
 use crate::ecmascript::module_factory::module_factory;
@@ -35,8 +39,9 @@ impl EcmascriptDevChunkContentEntry {
     pub async fn new(
         chunk_item: EcmascriptChunkItemVc,
         availability_info: AvailabilityInfo,
+        issue_tolerance: IssueTolerancePolicyVc,
     ) -> Result<Self> {
-        let code = item_code(chunk_item, Value::new(availability_info))
+        let code = item_code(chunk_item, Value::new(availability_info), issue_tolerance)
             .resolve()
             .await?;
         Ok(EcmascriptDevChunkContentEntry {
@@ -56,6 +61,7 @@ impl EcmascriptDevChunkContentEntriesVc {
     #[turbo_tasks::function]
     pub async fn new(
         chunk_content: EcmascriptChunkContentVc,
+        issue_tolerance: IssueTolerancePolicyVc,
     ) -> Result<EcmascriptDevChunkContentEntriesVc> {
         let chunk_content = chunk_content.await?;
         let availability_info = chunk_content.availability_info;
@@ -66,7 +72,12 @@ impl EcmascriptDevChunkContentEntriesVc {
             .map(|chunk_item| async move {
                 Ok((
                     chunk_item.id().await?,
-                    EcmascriptDevChunkContentEntry::new(*chunk_item, availability_info).await?,
+                    EcmascriptDevChunkContentEntry::new(
+                        *chunk_item,
+                        availability_info,
+                        issue_tolerance,
+                    )
+                    .await?,
                 ))
             })
             .try_join()
@@ -82,36 +93,46 @@ impl EcmascriptDevChunkContentEntriesVc {
 async fn item_code(
     item: EcmascriptChunkItemVc,
     availability_info: Value<AvailabilityInfo>,
+    issue_tolerance: IssueTolerancePolicyVc,
 ) -> Result<CodeVc> {
-    Ok(
-        match module_factory(item.content_with_availability_info(availability_info))
-            .resolve()
-            .await
-        {
-            Ok(factory) => factory,
-            Err(error) => {
-                let id = item.id().to_string().await;
-                let id = id.as_ref().map_or_else(|_| "unknown", |id| &**id);
-                let error = error.context(format!(
-                    "An error occurred while generating the chunk item {}",
-                    id
-                ));
-                let error_message = format!("{}", PrettyPrintError(&error));
-                let js_error_message = serde_json::to_string(&error_message)?;
-                let issue = CodeGenerationIssue {
-                    severity: IssueSeverity::Error.cell(),
-                    path: item.asset_ident().path(),
-                    title: StringVc::cell("Code generation for chunk item errored".to_string()),
-                    message: StringVc::cell(error_message),
-                }
-                .cell();
-                issue.as_issue().emit();
-                let mut code = CodeBuilder::default();
-                code += "(() => {{\n\n";
-                writeln!(code, "throw new Error({error});", error = &js_error_message)?;
-                code += "\n}})";
-                code.build().cell()
+    let issue_tolerance = issue_tolerance.await?;
+    if is_rebuild_recording_enabled() {
+        record_chunk_item_rebuild(item.asset_ident().to_string().await?.as_str());
+    }
+    let content = match *item.chunking_context().code_cache().await? {
+        Some(code_cache) => code_cache.content(item, availability_info),
+        None => item.content_with_availability_info(availability_info),
+    };
+    Ok(match module_factory(content).resolve().await {
+        Ok(factory) => {
+            issue_tolerance.check(content).await?;
+            factory
+        }
+        Err(error) => {
+            let id = item.id().to_string().await;
+            let id = id.as_ref().map_or_else(|_| "unknown", |id| &**id);
+            let error = error.context(format!(
+                "An error occurred while generating the chunk item {}",
+                id
+            ));
+            let error_message = format!("{}", PrettyPrintError(&error));
+            let js_error_message = serde_json::to_string(&error_message)?;
+            let issue = CodeGenerationIssue {
+                severity: IssueSeverity::Error.cell(),
+                path: item.asset_ident().path(),
+                title: StringVc::cell("Code generation for chunk item errored".to_string()),
+                message: StringVc::cell(error_message),
+            }
+            .cell();
+            issue.as_issue().emit();
+            if issue_tolerance.tolerance(IssueSeverity::Error) == IssueTolerance::Abort {
+                return Err(error);
             }
-        },
-    )
+            let mut code = CodeBuilder::default();
+            code += "(() => {{\n\n";
+            writeln!(code, "throw new Error({error});", error = &js_error_message)?;
+            code += "\n}})";
+            code.build().cell()
+        }
+    })
 }
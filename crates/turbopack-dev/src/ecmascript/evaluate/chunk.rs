@@ -8,9 +8,9 @@ use turbo_tasks_fs::{embed_file, File, FileContent};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc, AssetsVc},
     chunk::{ChunkVc, ChunkingContext, EvaluatableAssetsVc, ModuleIdReadRef},
-    code_builder::{CodeBuilder, CodeVc},
+    code_builder::{ChunkCodeType, CodeBuilder, CodeVc},
     environment::ChunkLoading,
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::AssetReferencesVc,
     source_map::{
         GenerateSourceMap, GenerateSourceMapVc, OptionSourceMapVc, SourceMapAssetReferenceVc,
@@ -174,8 +174,21 @@ impl EcmascriptDevEvaluateChunkVc {
             "#
         )?;
 
+        let filename = chunk_path.file_name();
+        let wrappers = this
+            .chunking_context
+            .code_wrappers(Value::new(ChunkCodeType::EcmascriptEvaluate))
+            .await?;
+        let content = code.build();
+        let mut code = CodeBuilder::default();
+        code.push_wrapped(
+            &content,
+            &wrappers,
+            ChunkCodeType::EcmascriptEvaluate,
+            filename,
+        )?;
+
         if code.has_source_map() {
-            let filename = chunk_path.file_name();
             write!(code, "\n\n//# sourceMappingURL={}.map", filename)?;
         }
 
@@ -202,17 +215,14 @@ impl Asset for EcmascriptDevEvaluateChunk {
     async fn ident(&self) -> Result<AssetIdentVc> {
         let mut ident = self.entry_chunk.ident().await?.clone_value();
 
-        ident.add_modifier(modifier());
+        ident.add_modifier(ModifierKind::Chunk, modifier());
 
-        ident.modifiers.extend(
-            self.evaluatable_assets
-                .await?
-                .iter()
-                .map(|entry| entry.ident().to_string()),
-        );
+        for entry in &*self.evaluatable_assets.await? {
+            ident.add_modifier(ModifierKind::ChunkMember, entry.ident().to_string());
+        }
 
         for chunk in &*self.other_chunks.await? {
-            ident.add_modifier(chunk.ident().to_string());
+            ident.add_modifier(ModifierKind::ChunkMember, chunk.ident().to_string());
         }
 
         let ident = AssetIdentVc::new(Value::new(ident));
@@ -4,7 +4,7 @@ use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc, AssetsVc},
     chunk::{ChunkVc, ChunkingContext},
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::{AssetReferencesVc, SingleAssetReferenceVc},
     version::{VersionedContent, VersionedContentVc},
 };
@@ -80,10 +80,10 @@ impl Asset for EcmascriptDevChunkList {
     async fn ident(&self) -> Result<AssetIdentVc> {
         let mut ident = self.entry_chunk.ident().await?.clone_value();
 
-        ident.add_modifier(modifier());
+        ident.add_modifier(ModifierKind::Chunk, modifier());
 
         for chunk in &*self.chunks.await? {
-            ident.add_modifier(chunk.ident().to_string());
+            ident.add_modifier(ModifierKind::ChunkMember, chunk.ident().to_string());
         }
 
         let ident = AssetIdentVc::new(Value::new(ident));
@@ -4,11 +4,11 @@ use anyhow::{Context, Result};
 use indexmap::IndexMap;
 use indoc::writedoc;
 use serde::Serialize;
-use turbo_tasks::{IntoTraitRef, TryJoinIterExt};
+use turbo_tasks::{IntoTraitRef, TryJoinIterExt, ValueToString};
 use turbo_tasks_fs::File;
 use turbopack_core::{
-    asset::{Asset, AssetContentVc},
-    chunk::ChunkingContext,
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{Chunk, ChunkIdentVc, ChunkVc, ChunkingContext},
     code_builder::{CodeBuilder, CodeVc},
     version::{
         MergeableVersionedContent, MergeableVersionedContentVc, UpdateVc, VersionVc,
@@ -22,12 +22,14 @@ use super::{
     update::update_chunk_list,
     version::{EcmascriptDevChunkListVersion, EcmascriptDevChunkListVersionVc},
 };
+use crate::ecmascript::chunk::EcmascriptDevChunkVc;
 
 /// Contents of an [`EcmascriptDevChunkList`].
 #[turbo_tasks::value]
 pub(super) struct EcmascriptDevChunkListContent {
     chunk_list_path: String,
     pub(super) chunks_contents: IndexMap<String, VersionedContentVc>,
+    chunk_idents: IndexMap<String, String>,
     source: EcmascriptDevChunkListSource,
 }
 
@@ -38,14 +40,13 @@ impl EcmascriptDevChunkListContentVc {
     pub async fn new(chunk_list: EcmascriptDevChunkListVc) -> Result<Self> {
         let chunk_list_ref = chunk_list.await?;
         let output_root = chunk_list_ref.chunking_context.output_root().await?;
+        let chunks = chunk_list_ref.chunks.await?;
         Ok(EcmascriptDevChunkListContent {
             chunk_list_path: output_root
                 .get_path_to(&*chunk_list.ident().path().await?)
                 .context("chunk list path not in output root")?
                 .to_string(),
-            chunks_contents: chunk_list_ref
-                .chunks
-                .await?
+            chunks_contents: chunks
                 .iter()
                 .map(|chunk| {
                     let output_root = output_root.clone();
@@ -63,6 +64,35 @@ impl EcmascriptDevChunkListContentVc {
                 .into_iter()
                 .filter_map(|(path, content)| path.map(|path| (path, content)))
                 .collect(),
+            chunk_idents: if *chunk_list_ref
+                .chunking_context
+                .exposes_chunk_idents()
+                .await?
+            {
+                chunks
+                    .iter()
+                    .map(|chunk| {
+                        let output_root = output_root.clone();
+                        async move {
+                            let Some(ident) = chunk_ident(*chunk).await? else {
+                                return Ok(None);
+                            };
+                            let Some(path) = output_root
+                                .get_path_to(&*chunk.ident().path().await?)
+                                .map(|path| path.to_string()) else {
+                                return Ok(None);
+                            };
+                            Ok(Some((path, ident.to_string().await?.clone_value())))
+                        }
+                    })
+                    .try_join()
+                    .await?
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            } else {
+                IndexMap::new()
+            },
             source: chunk_list_ref.source,
         }
         .cell())
@@ -120,6 +150,11 @@ impl EcmascriptDevChunkListContentVc {
         let params = EcmascriptDevChunkListParams {
             path: &this.chunk_list_path,
             chunks: this.chunks_contents.keys().map(|s| s.as_str()).collect(),
+            chunk_idents: this
+                .chunk_idents
+                .iter()
+                .map(|(path, ident)| (path.as_str(), ident.as_str()))
+                .collect(),
             source: this.source,
         };
 
@@ -171,6 +206,21 @@ struct EcmascriptDevChunkListParams<'a> {
     path: &'a str,
     /// All chunks that belong to the chunk list.
     chunks: Vec<&'a str>,
+    /// Stable, entry based identifiers of the chunks, keyed by chunk path.
+    /// Unlike paths, these don't change when the content of a chunk changes.
+    /// Only listed when the chunking context exposes them.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    chunk_idents: IndexMap<&'a str, &'a str>,
     /// Where this chunk list is from.
     source: EcmascriptDevChunkListSource,
 }
+
+/// Returns the [ChunkIdentVc] of a chunk in a chunk list, if it has one.
+async fn chunk_ident(chunk: AssetVc) -> Result<Option<ChunkIdentVc>> {
+    if let Some(chunk) = EcmascriptDevChunkVc::resolve_from(chunk).await? {
+        return Ok(Some(chunk.chunk_ident()));
+    }
+    Ok(ChunkVc::resolve_from(chunk)
+        .await?
+        .map(|chunk| chunk.chunk_ident()))
+}
@@ -6,7 +6,7 @@ use turbopack_core::{
         availability_info::AvailabilityInfo, ChunkVc, ChunkableAsset, ChunkableAssetVc,
         ChunkingContext, ChunkingContextVc,
     },
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::{AssetReferencesVc, SingleAssetReferenceVc},
 };
 use turbopack_ecmascript::chunk::{
@@ -88,7 +88,9 @@ fn dev_manifest_chunk_reference_description() -> StringVc {
 impl Asset for DevManifestChunkAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.asset.ident().with_modifier(modifier())
+        self.asset
+            .ident()
+            .with_modifier(Value::new(ModifierKind::Derived), modifier())
     }
 
     #[turbo_tasks::function]
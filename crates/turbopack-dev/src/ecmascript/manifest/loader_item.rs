@@ -2,11 +2,11 @@ use std::io::Write as _;
 
 use anyhow::{anyhow, bail, Result};
 use indoc::writedoc;
-use turbo_tasks::{primitives::StringVc, ValueToString};
+use turbo_tasks::{primitives::StringVc, Value, ValueToString};
 use turbopack_core::{
     asset::Asset,
     chunk::{ChunkItem, ChunkItemVc, ChunkingContext},
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::{AssetReferencesVc, SingleAssetReferenceVc},
 };
 use turbopack_ecmascript::{
@@ -71,7 +71,9 @@ fn dev_manifest_loader_chunk_reference_description() -> StringVc {
 impl ChunkItem for DevManifestLoaderItem {
     #[turbo_tasks::function]
     fn asset_ident(&self) -> AssetIdentVc {
-        self.manifest.ident().with_modifier(modifier())
+        self.manifest
+            .ident()
+            .with_modifier(Value::new(ModifierKind::Derived), modifier())
     }
 
     #[turbo_tasks::function]
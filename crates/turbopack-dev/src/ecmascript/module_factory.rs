@@ -37,8 +37,7 @@ pub(super) async fn module_factory(content: EcmascriptChunkItemContentVc) -> Res
         write!(code, "(({{ {} }}) => (() => {{\n\n", args,)?;
     }
 
-    let source_map = content.source_map.map(|sm| sm.as_generate_source_map());
-    code.push_source(&content.inner_code, source_map);
+    code.push_source(&content.inner_code, content.source_map);
     if content.options.this {
         code += "\n}.call(this) })";
     } else {
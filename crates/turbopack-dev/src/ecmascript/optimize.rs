@@ -6,9 +6,17 @@ use anyhow::Result;
 use indexmap::{IndexMap, IndexSet};
 use turbo_tasks::{TryJoinIterExt, Value};
 use turbo_tasks_fs::FileSystemPathOptionVc;
-use turbopack_core::chunk::optimize::optimize_by_common_parent;
+use turbopack_core::{
+    asset::Asset,
+    chunk::{
+        availability_info::AvailabilityInfo, group_by_chunk_name,
+        optimize::optimize_by_common_parent, ChunkingContext, ChunkingHints, ChunkingHintsVc,
+        OptionNamedChunksVc,
+    },
+};
 use turbopack_ecmascript::chunk::{
-    EcmascriptChunkPlaceablesVc, EcmascriptChunkVc, EcmascriptChunkingContextVc, EcmascriptChunksVc,
+    EcmascriptChunkPlaceableVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc,
+    EcmascriptChunkingContextVc, EcmascriptChunksVc,
 };
 
 #[turbo_tasks::function]
@@ -36,20 +44,56 @@ pub async fn optimize_ecmascript_chunks(chunks: EcmascriptChunksVc) -> Result<Ec
             });
 
     let optimized_chunks = chunks_by_chunking_context
-        .into_values()
-        .map(|chunks| async move {
-            Ok(
-                optimize_by_common_parent(&chunks, get_common_parent, |local, children| {
-                    optimize_ecmascript(local.map(EcmascriptChunksVc::cell), children)
+        .into_iter()
+        .map(|(chunking_context, chunks)| async move {
+            let hints = chunking_context.chunking_hints();
+            let cancellation = chunking_context.cancellation_token().await?;
+            // All chunks of assets with the same configured name become one
+            // chunk, which isn't merged with other chunks, see
+            // [merge_named_chunks].
+            let named_chunks = chunking_context.named_chunks();
+            let (named, chunks) = group_by_chunk_name(
+                chunks
+                    .into_iter()
+                    .map(|chunk| async move {
+                        let main_entries = chunk.await?.main_entries.await?;
+                        let name = named_chunks
+                            .common_chunk_name(
+                                main_entries.iter().map(|entry| entry.ident().path()),
+                            )
+                            .await?;
+                        Ok((chunk, name))
+                    })
+                    .try_join()
+                    .await?,
+            );
+            let mut optimized = named
+                .into_iter()
+                .map(|(name, chunks)| async move {
+                    merge_named_chunks(named_chunks, &name, &chunks).await
                 })
+                .try_join()
+                .await?;
+            optimized.extend(
+                optimize_by_common_parent(
+                    &chunks,
+                    &cancellation,
+                    get_common_parent,
+                    |local, children| {
+                        optimize_ecmascript(local.map(EcmascriptChunksVc::cell), children, hints)
+                    },
+                )
                 .await?
-                .await?,
-            )
+                .await?
+                .iter()
+                .copied(),
+            );
+            Ok(optimized)
         })
         .try_join()
         .await?
         .into_iter()
-        .flat_map(|chunks| chunks.iter().copied().collect::<Vec<_>>())
+        .flatten()
         .collect::<Vec<_>>();
 
     Ok(EcmascriptChunksVc::cell(optimized_chunks))
@@ -60,6 +104,45 @@ async fn get_common_parent(chunk: EcmascriptChunkVc) -> Result<FileSystemPathOpt
     Ok(chunk.common_parent())
 }
 
+/// Merges the `chunks` of the assets named `name` into a single chunk. The
+/// chunk contains the assets with that name of all chunk groups that are
+/// loaded from the same chunk groups, e.g. of all async imports of a page, so
+/// these chunk groups share it instead of each loading a chunk with the
+/// subset of assets it uses.
+async fn merge_named_chunks(
+    named_chunks: OptionNamedChunksVc,
+    name: &str,
+    chunks: &[EcmascriptChunkVc],
+) -> Result<EcmascriptChunkVc> {
+    let first = chunks[0].await?;
+    // Without tracked availability, the chunk groups can't be related, so only
+    // the chunks of this chunk group are merged.
+    let Some(root) = first.availability_info.availability_roots().await?.first().copied() else {
+        return Ok(match chunks {
+            [chunk] => *chunk,
+            _ => merge_chunks(chunks[0], chunks).await?,
+        });
+    };
+    let main_entries = named_chunks
+        .named_chunk_assets(Value::new(first.availability_info), name)
+        .await?
+        .iter()
+        .map(|asset| EcmascriptChunkPlaceableVc::resolve_from(*asset))
+        .try_join()
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(EcmascriptChunkVc::new_normalized(
+        first.context,
+        EcmascriptChunkPlaceablesVc::cell(main_entries),
+        None,
+        Value::new(AvailabilityInfo::Root {
+            current_availability_root: root,
+        }),
+    ))
+}
+
 /// Merge a few chunks into a single chunk.
 async fn merge_chunks(
     first: EcmascriptChunkVc,
@@ -86,26 +169,23 @@ async fn merge_chunks(
 /// Number of chunks to compare with to chunk for duplication.
 /// This limit restricts the complexity from O(n²) to O(M * n) = O(n)
 const COMPARE_WITH_COUNT: usize = 100;
-/// Max percentage of shared chunk items between two chunk before they are
-/// merged.
-const DUPLICATION_THRESHOLD: f32 = 0.1;
-/// Max percentage of unshared chunk items on either side before chunks are
-/// merged.
-const CONTAINED_THRESHOLD: f32 = 0.05;
 /// Max number of local chunks. Will start to merge into chunks of
 /// MAX_CHUNK_ITEMS_PER_CHUNK.
 const LOCAL_CHUNK_MERGE_THRESHOLD: usize = 20;
-/// Max number of total chunks. Will start to merge into chunks of
-/// MAX_CHUNK_ITEMS_PER_CHUNK.
-const TOTAL_CHUNK_MERGE_THRESHOLD: usize = 20;
 /// Max number of chunk items per chunk to merge.
 const MAX_CHUNK_ITEMS_PER_CHUNK: usize = 3000;
 
-/// Merge chunks with high duplication between them.
+/// Merge chunks with high duplication between them. Thresholds are taken from
+/// the merge aggressiveness of the `hints`, and merging stops once the
+/// minimum chunk count is reached.
 async fn merge_duplicated_and_contained(
     chunks: &mut Vec<(EcmascriptChunkVc, Option<EcmascriptChunksVc>)>,
     mut unoptimized_count: usize,
+    hints: &ChunkingHints,
 ) -> Result<()> {
+    let duplication_threshold = hints.merge_aggressiveness.duplication_threshold();
+    let contained_threshold = hints.merge_aggressiveness.contained_threshold();
+
     struct Comparison {
         /// Index of chunk in the `chunks` vec
         index: usize,
@@ -149,9 +229,9 @@ async fn merge_duplicated_and_contained(
     }
 
     // We compare each unoptimized chunk with COMPARE_WITH_COUNT following chunks to
-    // find duplication greater than DUPLICATION_THRESHOLD.
+    // find duplication greater than the duplication threshold.
     let mut i = 0;
-    while i < unoptimized_count {
+    while i < unoptimized_count && chunks.len() > hints.min_chunk_count {
         let chunk = chunks[i].0;
         // Compare chunk with following chunks
         let mut comparisons = chunks[i + 1..]
@@ -225,11 +305,11 @@ async fn merge_duplicated_and_contained(
                 .min_by_key(|&(_, left, right)| std::cmp::min(FloatOrd(left), FloatOrd(right)))
             {
                 // Merge when right is mostly contained in left or vice versa
-                if right_contained_factor < CONTAINED_THRESHOLD {
+                if right_contained_factor < contained_threshold {
                     merge(j, other, Some((right, &mut comparisons)));
                     // Continue looking for more candidates
                     continue;
-                } else if left_contained_factor < CONTAINED_THRESHOLD {
+                } else if left_contained_factor < contained_threshold {
                     merge(j, other, None);
                     // comparison will be skewed too much, re-compare chunks
                     break;
@@ -250,7 +330,7 @@ async fn merge_duplicated_and_contained(
                 .max_by_key(|&(_, f)| FloatOrd(f))
             {
                 // Merge when there is a lot of duplication
-                if duplication_factor > DUPLICATION_THRESHOLD {
+                if duplication_factor > duplication_threshold {
                     merge(j, other, Some((right, &mut comparisons)));
                     // Continue looking for more candidates
                     continue;
@@ -388,7 +468,9 @@ async fn merge_by_size(
 async fn optimize_ecmascript(
     local: Option<EcmascriptChunksVc>,
     children: Vec<EcmascriptChunksVc>,
+    hints: ChunkingHintsVc,
 ) -> Result<EcmascriptChunksVc> {
+    let hints = hints.await?.normalized();
     let mut chunks = Vec::<(EcmascriptChunkVc, Option<EcmascriptChunksVc>)>::new();
     // TODO optimize
     let mut unoptimized_count = 0;
@@ -424,7 +506,7 @@ async fn optimize_ecmascript(
     // have duplication, but there might be duplication within local chunks or
     // between local chunks and children.
     if unoptimized_count > 0 && chunks.len() > 1 {
-        merge_duplicated_and_contained(&mut chunks, unoptimized_count).await?;
+        merge_duplicated_and_contained(&mut chunks, unoptimized_count, &hints).await?;
     }
 
     // If chunks share chunk items they might be removed from one of them. The
@@ -437,8 +519,8 @@ async fn optimize_ecmascript(
 
     // When there are too many chunks, try hard to reduce the number of chunks to
     // limit the request count.
-    let chunks = if chunks.len() > TOTAL_CHUNK_MERGE_THRESHOLD {
-        merge_to_limit(chunks, TOTAL_CHUNK_MERGE_THRESHOLD).await?
+    let chunks = if chunks.len() > hints.max_chunk_count {
+        merge_to_limit(chunks, hints.max_chunk_count).await?
     } else {
         chunks.into_iter().map(|(c, _)| c).collect()
     };
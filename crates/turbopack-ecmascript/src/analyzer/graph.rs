@@ -257,6 +257,56 @@ pub fn create_graph(m: &Program, eval_context: &EvalContext) -> VarGraph {
     graph
 }
 
+/// Whether `m` resolves names at runtime, through a direct `eval` call or a
+/// `with` statement, or compiles code at runtime with `Function`.
+pub fn accesses_scope_dynamically(m: &Program, eval_context: &EvalContext) -> bool {
+    struct Visitor {
+        unresolved_mark: Mark,
+        found: bool,
+    }
+
+    impl Visitor {
+        fn is_global(&self, callee: &Expr, names: &[&str]) -> bool {
+            matches!(
+                unparen(callee),
+                Expr::Ident(ident)
+                    if names.contains(&&*ident.sym) && is_unresolved(ident, self.unresolved_mark)
+            )
+        }
+    }
+
+    impl Visit for Visitor {
+        fn visit_with_stmt(&mut self, _: &WithStmt) {
+            self.found = true;
+        }
+
+        fn visit_call_expr(&mut self, call: &CallExpr) {
+            if let Callee::Expr(callee) = &call.callee {
+                if self.is_global(callee, &["eval", "Function"]) {
+                    self.found = true;
+                    return;
+                }
+            }
+            call.visit_children_with(self);
+        }
+
+        fn visit_new_expr(&mut self, new: &NewExpr) {
+            if self.is_global(&new.callee, &["Function"]) {
+                self.found = true;
+                return;
+            }
+            new.visit_children_with(self);
+        }
+    }
+
+    let mut visitor = Visitor {
+        unresolved_mark: eval_context.unresolved_mark,
+        found: false,
+    };
+    m.visit_with(&mut visitor);
+    visitor.found
+}
+
 pub struct EvalContext {
     pub(crate) unresolved_mark: Mark,
     pub(crate) imports: ImportMap,
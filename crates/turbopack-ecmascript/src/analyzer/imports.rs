@@ -25,6 +25,10 @@ static ANNOTATION_TRANSITION: Lazy<JsWord> = Lazy::new(|| "transition".into());
 /// Changes the chunking type for the annotated import
 static ANNOTATION_CHUNKING_TYPE: Lazy<JsWord> = Lazy::new(|| "chunking-type".into());
 
+/// Sets export conditions for resolving the annotated import, as a comma
+/// separated list
+static ANNOTATION_CONDITIONS: Lazy<JsWord> = Lazy::new(|| "conditions".into());
+
 impl ImportAnnotations {
     fn insert(&mut self, key: JsWord, value: Option<JsWord>) {
         self.map.insert(key, value);
@@ -47,6 +51,17 @@ impl ImportAnnotations {
             .get(&ANNOTATION_CHUNKING_TYPE)
             .and_then(|w| w.as_ref().map(|w| &**w))
     }
+
+    /// Returns the export conditions listed in the conditions annotation
+    pub fn conditions(&self) -> impl Iterator<Item = &str> {
+        self.map
+            .get(&ANNOTATION_CONDITIONS)
+            .and_then(|w| w.as_ref().map(|w| &**w))
+            .into_iter()
+            .flat_map(|w| w.split(','))
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+    }
 }
 
 impl Display for ImportAnnotations {
@@ -2979,6 +2979,30 @@ pub fn parse_require_context(args: &Vec<JsValue>) -> Result<RequireContextOption
     })
 }
 
+/// The `paths` option of `require.resolve(request, options)`, if it's a list
+/// of constant strings and the only option.
+pub fn parse_require_resolve_paths(options: &JsValue) -> Option<Vec<String>> {
+    let JsValue::Object { parts, .. } = options else {
+        return None;
+    };
+    let mut paths = None;
+    for part in parts {
+        let ObjectPart::KeyValue(key, JsValue::Array { items, .. }) = part else {
+            return None;
+        };
+        if key.as_str() != Some("paths") {
+            return None;
+        }
+        paths = Some(
+            items
+                .iter()
+                .map(|item| item.as_str().map(ToString::to_string))
+                .collect::<Option<Vec<_>>>()?,
+        );
+    }
+    paths
+}
+
 #[turbo_tasks::value(transparent)]
 #[derive(Debug, Clone)]
 pub struct RequireContextValue(IndexMap<String, String>);
@@ -3149,7 +3173,7 @@ mod tests {
     use std::{mem::take, path::PathBuf, time::Instant};
 
     use swc_core::{
-        common::Mark,
+        common::{FileName, Mark},
         ecma::{
             ast::EsVersion, parser::parse_file_as_program, transforms::base::resolver,
             visit::VisitMutWith,
@@ -3166,11 +3190,74 @@ mod tests {
     };
 
     use super::{
-        graph::{create_graph, ConditionalKind, Effect, EffectArg, EvalContext, VarGraph},
+        graph::{
+            accesses_scope_dynamically, create_graph, ConditionalKind, Effect, EffectArg,
+            EvalContext, VarGraph,
+        },
         linker::link,
-        JsValue,
+        parse_require_resolve_paths, JsValue, ObjectPart,
     };
 
+    #[test]
+    fn test_parse_require_resolve_paths() {
+        let options = |paths: Vec<JsValue>| {
+            JsValue::object(vec![ObjectPart::KeyValue(
+                "paths".into(),
+                JsValue::array(paths),
+            )])
+        };
+        assert_eq!(
+            parse_require_resolve_paths(&options(vec!["./lib".into(), "/opt".into()])),
+            Some(vec!["./lib".to_string(), "/opt".to_string()])
+        );
+        assert_eq!(
+            parse_require_resolve_paths(&options(vec![JsValue::unknown_empty("dynamic")])),
+            None
+        );
+        assert_eq!(
+            parse_require_resolve_paths(&JsValue::object(vec![ObjectPart::KeyValue(
+                "conditions".into(),
+                JsValue::array(vec![]),
+            )])),
+            None
+        );
+        assert_eq!(parse_require_resolve_paths(&"./lib".into()), None);
+    }
+
+    #[test]
+    fn test_accesses_scope_dynamically() {
+        fn check(code: &str) -> bool {
+            run_test(false, |cm, handler| {
+                let fm = cm.new_source_file(FileName::Anon, code.to_string());
+                let mut m = parse_file_as_program(
+                    &fm,
+                    Default::default(),
+                    EsVersion::latest(),
+                    None,
+                    &mut vec![],
+                )
+                .map_err(|err| err.into_diagnostic(handler).emit())?;
+                let unresolved_mark = Mark::new();
+                let top_level_mark = Mark::new();
+                m.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+                let eval_context = EvalContext::new(&m, unresolved_mark);
+                Ok(accesses_scope_dynamically(&m, &eval_context))
+            })
+            .unwrap()
+        }
+
+        assert!(check("export const a = eval('a');"));
+        assert!(check("export const a = (eval)('a');"));
+        assert!(check("with (obj) { a; }"));
+        assert!(check("export const f = new Function('return a');"));
+        assert!(check("export const f = Function('return a');"));
+        assert!(!check(
+            "export const a = obj.eval('a') + new obj.Function();"
+        ));
+        // Bindings that shadow the globals are regular functions.
+        assert!(!check("function Function() {}\nnew Function();"));
+    }
+
     #[fixture("tests/analyzer/graph/**/input.js")]
     fn fixture(input: PathBuf) {
         crate::register();
@@ -0,0 +1,275 @@
+//! A persistent cache of the generated code of chunk items.
+//!
+//! Code generation is one of the most expensive steps of a build, and
+//! production builds on CI usually start without the in-memory caches of turbo
+//! tasks. A [ChunkItemCodeCache] stores the code and the source map of chunk
+//! items, after their transforms and before they are concatenated into chunks,
+//! in a directory that outlives the build, e.g. one that CI restores. Chunk
+//! items whose entry is found skip code generation entirely. Entries are
+//! written by [ChunkItemCodeCacheVc::save] when the content of a chunk is
+//! generated, and read when a later build, e.g. in a new process, first needs
+//! the code of a chunk item.
+//!
+//! Entries are content addressed: the file name of an entry is a digest of
+//! the content of the source file of the chunk item, its ident and module id,
+//! the idents and module ids of the assets that its references resolve to,
+//! its availability, the [external inputs] of the chunking context and the
+//! `options` of the cache. Stale entries are never read, so they don't need to
+//! be invalidated. Anything else that influences code generation, e.g. the
+//! transforms of the module options, must be part of `options`.
+//!
+//! Chunk items that aren't backed by a file, and chunk items whose source map
+//! is a sectioned source map, are not cached.
+//!
+//! [external inputs]: turbopack_core::chunk::ChunkingContext::external_inputs
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{
+    primitives::{StringReadRef, StringVc},
+    CompletionVc, CompletionsVc, TryJoinIterExt, Value, ValueToString,
+};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, Xxh3Hash64Hasher};
+use turbopack_core::{
+    asset::Asset,
+    chunk::{availability_info::AvailabilityInfo, ChunkItem, ChunkingContext, ChunkingContextVc},
+    reference::AssetReference,
+    source_map::{GenerateSourceMap, OptionSourceMapVc, SourceMap, SourceMapVc},
+};
+
+use super::{
+    item::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemOptions, EcmascriptChunkItemVc,
+    },
+    EcmascriptChunkContentVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+};
+
+/// A directory of generated chunk item code, see the
+/// [module documentation](self).
+#[turbo_tasks::value(shared)]
+pub struct ChunkItemCodeCache {
+    dir: FileSystemPathVc,
+    /// A digest of the code generation options that aren't known to turbopack.
+    options: StringVc,
+}
+
+/// The content of an entry.
+#[derive(Serialize, Deserialize)]
+struct CachedChunkItemCode {
+    options: EcmascriptChunkItemOptions,
+    code: String,
+    /// The JSON of the regular source map of `code`.
+    source_map: Option<String>,
+}
+
+impl CachedChunkItemCode {
+    fn into_content(self) -> Result<EcmascriptChunkItemContent> {
+        let source_map = match self.source_map {
+            Some(source_map) => {
+                let map = SourceMap::new_regular_from_slice(source_map.as_bytes())?.cell();
+                Some(CachedSourceMap { map }.cell().into())
+            }
+            None => None,
+        };
+        Ok(EcmascriptChunkItemContent {
+            inner_code: self.code.into(),
+            source_map,
+            options: self.options,
+            ..Default::default()
+        })
+    }
+}
+
+/// The source map of an entry.
+#[turbo_tasks::value]
+struct CachedSourceMap {
+    map: SourceMapVc,
+}
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for CachedSourceMap {
+    #[turbo_tasks::function]
+    fn generate_source_map(&self) -> OptionSourceMapVc {
+        OptionSourceMapVc::cell(Some(self.map))
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct OptionEntryPath(Option<FileSystemPathVc>);
+
+#[turbo_tasks::value_impl]
+impl ChunkItemCodeCacheVc {
+    /// A cache whose entries are stored in `dir`.
+    #[turbo_tasks::function]
+    pub fn new(dir: FileSystemPathVc, options: StringVc) -> Self {
+        ChunkItemCodeCache { dir, options }.cell()
+    }
+
+    /// The content of `chunk_item`, which is read from the cache when there
+    /// is an entry for it, and generated otherwise.
+    #[turbo_tasks::function]
+    pub async fn content(
+        self,
+        chunk_item: EcmascriptChunkItemVc,
+        availability_info: Value<AvailabilityInfo>,
+    ) -> Result<EcmascriptChunkItemContentVc> {
+        if let Some(path) = *self.entry_path(chunk_item, availability_info).await? {
+            if let FileContent::Content(file) = &*path.read().await? {
+                // Entries that can't be read, e.g. because they were written by
+                // an older version, are regenerated.
+                if let Ok(content) =
+                    serde_json::from_slice::<CachedChunkItemCode>(&file.content().to_bytes()?)
+                        .map_err(anyhow::Error::from)
+                        .and_then(CachedChunkItemCode::into_content)
+                {
+                    return Ok(content.cell());
+                }
+            }
+        }
+        Ok(chunk_item.content_with_availability_info(availability_info))
+    }
+
+    /// Writes the entries of the chunk items of `chunk_content`. Entries that
+    /// exist already are left as they are.
+    #[turbo_tasks::function]
+    pub async fn save(self, chunk_content: EcmascriptChunkContentVc) -> Result<CompletionVc> {
+        let chunk_content = chunk_content.await?;
+        let availability_info = Value::new(chunk_content.availability_info);
+        let completions = chunk_content
+            .chunk_items
+            .iter()
+            .map(|&chunk_item| async move {
+                let Some(path) = *self.entry_path(chunk_item, availability_info).await? else {
+                    return Ok(None);
+                };
+                if let FileContent::Content(_) = &*path.read().await? {
+                    return Ok(None);
+                }
+                let content = chunk_item
+                    .content_with_availability_info(availability_info)
+                    .await?;
+                let source_map = match content.source_map {
+                    Some(source_map) => match *source_map.generate_source_map().await? {
+                        Some(map) => {
+                            // Sectioned source maps can't be read back.
+                            if !matches!(&*map.await?, SourceMap::Regular(_)) {
+                                return Ok(None);
+                            }
+                            Some(map.to_rope().await?.to_str()?.into_owned())
+                        }
+                        None => None,
+                    },
+                    None => None,
+                };
+                let entry = CachedChunkItemCode {
+                    options: content.options.clone(),
+                    code: content.inner_code.to_str()?.into_owned(),
+                    source_map,
+                };
+                let json = serde_json::to_string(&entry)?;
+                Ok(Some(
+                    path.write(FileContent::Content(File::from(json)).cell()),
+                ))
+            })
+            .try_join()
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        Ok(CompletionsVc::all(completions))
+    }
+
+    /// The path of the entry of `chunk_item`, or `None` if it can't be
+    /// cached.
+    #[turbo_tasks::function]
+    async fn entry_path(
+        self,
+        chunk_item: EcmascriptChunkItemVc,
+        availability_info: Value<AvailabilityInfo>,
+    ) -> Result<OptionEntryPathVc> {
+        let this = self.await?;
+        let ident = chunk_item.asset_ident();
+        let FileContent::Content(source) = &*ident.path().read().await? else {
+            return Ok(OptionEntryPathVc::cell(None));
+        };
+        let chunking_context: ChunkingContextVc = chunk_item.chunking_context().into();
+
+        let mut hasher = Xxh3Hash64Hasher::new();
+        hasher.write_value(this.options.await?.as_str());
+        hasher.write_value(chunking_context.external_inputs().digest().await?.as_str());
+        hasher.write_value(ident.to_string().await?.as_str());
+        hasher.write_value(chunk_item.id().to_string().await?.as_str());
+        hasher.write_value(source.content());
+        // The generated code refers to the module ids of the assets that the
+        // references of the chunk item resolve to.
+        for (ident, id) in dependencies(chunk_item).await? {
+            hasher.write_value(ident.as_str());
+            hasher.write_value(id.as_ref().map_or("", |id| id.as_str()));
+        }
+        match availability_info.current_availability_root() {
+            Some(root) => hasher.write_value(root.ident().to_string().await?.as_str()),
+            None => hasher.write_value(""),
+        }
+        match availability_info.available_assets() {
+            Some(available_assets) => hasher.write_value(*available_assets.hash().await?),
+            None => hasher.write_value(0u64),
+        }
+        let digest = encode_hex(hasher.finish());
+        // Entries are spread over subdirectories, as some file systems are slow
+        // with large directories.
+        Ok(OptionEntryPathVc::cell(Some(
+            this.dir
+                .join(&digest[..2])
+                .join(&format!("{}.json", digest)),
+        )))
+    }
+}
+
+/// The idents of the assets that the references of `chunk_item` resolve to,
+/// in the order of the references, and the module ids of those that are placed
+/// into ecmascript chunks.
+async fn dependencies(
+    chunk_item: EcmascriptChunkItemVc,
+) -> Result<Vec<(StringReadRef, Option<StringReadRef>)>> {
+    let context = chunk_item.chunking_context();
+    Ok(chunk_item
+        .references()
+        .await?
+        .iter()
+        .map(|reference| async move {
+            reference
+                .resolve_reference()
+                .primary_assets()
+                .await?
+                .iter()
+                .map(|&asset| async move {
+                    let id = match EcmascriptChunkPlaceableVc::resolve_from(asset).await? {
+                        Some(placeable) => {
+                            Some(placeable.as_chunk_item(context).id().to_string().await?)
+                        }
+                        None => None,
+                    };
+                    Ok((asset.ident().to_string().await?, id))
+                })
+                .try_join()
+                .await
+        })
+        .try_join()
+        .await?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionChunkItemCodeCache(Option<ChunkItemCodeCacheVc>);
+
+#[turbo_tasks::value_impl]
+impl OptionChunkItemCodeCacheVc {
+    #[turbo_tasks::function]
+    pub fn none() -> Self {
+        OptionChunkItemCodeCacheVc::cell(None)
+    }
+}
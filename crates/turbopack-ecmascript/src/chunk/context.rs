@@ -1,11 +1,14 @@
 use anyhow::Result;
 use turbo_tasks::{Value, ValueToString};
-use turbopack_core::chunk::{
-    availability_info::AvailabilityInfo, ChunkItem, ChunkableAssetVc, ChunkingContext,
-    ChunkingContextVc, ModuleId, ModuleIdVc,
+use turbopack_core::{
+    chunk::{
+        availability_info::AvailabilityInfo, ChunkItem, ChunkableAssetVc, ChunkingContext,
+        ChunkingContextVc, ModuleId, ModuleIdVc,
+    },
+    ident::ModifierKind,
 };
 
-use super::item::EcmascriptChunkItemVc;
+use super::{code_cache::OptionChunkItemCodeCacheVc, item::EcmascriptChunkItemVc};
 
 /// [`EcmascriptChunkingContext`] must be implemented by [`ChunkingContext`]
 /// implementors that want to operate on [`EcmascriptChunk`]s.
@@ -22,8 +25,17 @@ pub trait EcmascriptChunkingContext: ChunkingContext {
         let layer = self.layer();
         let mut ident = chunk_item.asset_ident();
         if !layer.await?.is_empty() {
-            ident = ident.with_modifier(layer)
+            ident = ident.with_modifier(Value::new(ModifierKind::Layer), layer)
         }
-        Ok(ModuleId::String(ident.to_string().await?.clone_value()).cell())
+        let ident = ident.to_string().await?;
+        if let Some(module_id_map) = *self.module_id_map().await? {
+            return Ok(module_id_map.module_id(&ident));
+        }
+        Ok(ModuleId::String(ident.clone_value()).cell())
+    }
+
+    /// The persistent cache of the generated code of chunk items, if any.
+    fn code_cache(&self) -> OptionChunkItemCodeCacheVc {
+        OptionChunkItemCodeCacheVc::none()
     }
 }
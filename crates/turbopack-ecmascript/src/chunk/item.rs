@@ -8,19 +8,19 @@ use turbopack_core::{
         availability_info::AvailabilityInfo, available_assets::AvailableAssetsVc, ChunkItem,
         ChunkItemVc, ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset, ModuleIdVc,
     },
+    source_map::GenerateSourceMapVc,
 };
 
 use super::{
     context::EcmascriptChunkingContextVc, placeable::EcmascriptChunkPlaceableVc,
     EcmascriptChunkPlaceable, EcmascriptChunkingContext,
 };
-use crate::ParseResultSourceMapVc;
 
 #[turbo_tasks::value(shared)]
 #[derive(Default)]
 pub struct EcmascriptChunkItemContent {
     pub inner_code: Rope,
-    pub source_map: Option<ParseResultSourceMapVc>,
+    pub source_map: Option<GenerateSourceMapVc>,
     pub options: EcmascriptChunkItemOptions,
     pub placeholder_for_future_extensions: (),
 }
@@ -1,3 +1,4 @@
+pub(crate) mod code_cache;
 pub(crate) mod content;
 pub(crate) mod context;
 pub(crate) mod item;
@@ -15,8 +16,9 @@ use turbo_tasks_fs::FileSystemPathOptionVc;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
     chunk::{
-        availability_info::AvailabilityInfo, Chunk, ChunkGroupReferenceVc, ChunkItem, ChunkVc,
-        ChunkingContextVc, ChunksVc, ModuleIdsVc,
+        availability_info::AvailabilityInfo, Chunk, ChunkGroupLoading, ChunkGroupReferenceVc,
+        ChunkIdentVc, ChunkItem, ChunkItemVc, ChunkItemsVc, ChunkVc, ChunkingContext,
+        ChunkingContextVc, ChunksVc, ConcatenationGroupsVc, ModuleIdsVc,
     },
     ident::{AssetIdent, AssetIdentVc},
     introspect::{
@@ -28,6 +30,10 @@ use turbopack_core::{
 
 use self::content::ecmascript_chunk_content;
 pub use self::{
+    code_cache::{
+        ChunkItemCodeCache, ChunkItemCodeCacheVc, OptionChunkItemCodeCache,
+        OptionChunkItemCodeCacheVc,
+    },
     content::{EcmascriptChunkContent, EcmascriptChunkContentVc},
     context::{EcmascriptChunkingContext, EcmascriptChunkingContextVc},
     item::{
@@ -165,6 +171,36 @@ impl EcmascriptChunkVc {
         Ok(ModuleIdsVc::cell(entries))
     }
 
+    /// The groups of chunk items of this chunk that the chunk format can
+    /// concatenate into a single scope, see [ConcatenationGroupsVc].
+    #[turbo_tasks::function]
+    pub async fn concatenation_groups(self) -> Result<ConcatenationGroupsVc> {
+        let this = self.await?;
+        let chunk_content = ecmascript_chunk_content(
+            this.context,
+            this.main_entries,
+            this.omit_entries,
+            Value::new(this.availability_info),
+        )
+        .await?;
+        let chunk_items = chunk_content
+            .chunk_items
+            .iter()
+            .map(|&chunk_item| ChunkItemVc::from(chunk_item))
+            .collect();
+        let entries = this
+            .main_entries
+            .await?
+            .iter()
+            .map(|&entry| ChunkItemVc::from(entry.as_chunk_item(this.context)))
+            .collect();
+        Ok(ConcatenationGroupsVc::new(
+            this.context.into(),
+            ChunkItemsVc::cell(chunk_items),
+            ChunkItemsVc::cell(entries),
+        ))
+    }
+
     #[turbo_tasks::function]
     pub async fn compare(
         left: EcmascriptChunkVc,
@@ -238,6 +274,21 @@ impl Chunk for EcmascriptChunk {
         }
         Ok(ChunksVc::cell(chunks))
     }
+
+    #[turbo_tasks::function]
+    async fn chunk_ident(&self) -> Result<ChunkIdentVc> {
+        let entries = self
+            .main_entries
+            .await?
+            .iter()
+            .map(|entry| entry.ident())
+            .collect();
+        Ok(ChunkIdentVc::from_entries(
+            "ecmascript".to_string(),
+            self.context.context_path(),
+            entries,
+        ))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -391,7 +442,14 @@ impl Asset for EcmascriptChunk {
             references.push(*r);
         }
         for entry in content.async_chunk_group_entries.iter() {
-            references.push(ChunkGroupReferenceVc::new(this.context.into(), *entry).into());
+            references.push(
+                ChunkGroupReferenceVc::new_with_loading(
+                    this.context.into(),
+                    *entry,
+                    Value::new(ChunkGroupLoading::Lazy),
+                )
+                .into(),
+            );
         }
 
         Ok(AssetReferencesVc::cell(references))
@@ -436,6 +494,27 @@ impl Introspectable for EcmascriptChunk {
         for chunk_item in chunk_content.chunk_items.iter() {
             writeln!(details, "- {}", chunk_item.asset_ident().to_string().await?)?;
         }
+        if ChunkingContextVc::from(this.context)
+            .module_concatenation()
+            .await?
+            .enabled
+        {
+            details += "\nConcatenation groups:\n\n";
+            for group in self_vc.concatenation_groups().await?.iter() {
+                let items = group
+                    .items
+                    .iter()
+                    .map(|item| async move { item.asset_ident().to_string().await })
+                    .try_join()
+                    .await?;
+                writeln!(
+                    details,
+                    "- {}\n  not concatenated into a consumer because {}",
+                    FormatIter(|| items.iter().map(|item| item.as_str()).intersperse(", ")),
+                    group.blocker
+                )?;
+            }
+        }
         details += "\nContent:\n\n";
         write!(details, "{}", content.await?)?;
         Ok(StringVc::cell(details))
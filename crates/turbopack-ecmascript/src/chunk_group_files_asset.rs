@@ -8,7 +8,7 @@ use turbopack_core::{
         availability_info::AvailabilityInfo, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
         ChunkableAssetVc, ChunkingContext, ChunkingContextVc, EvaluatableAssetsVc,
     },
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     introspect::{
         asset::{content_to_details, IntrospectableAssetVc},
         Introspectable, IntrospectableChildrenVc, IntrospectableVc,
@@ -72,7 +72,9 @@ fn chunk_group_chunk_reference_description() -> StringVc {
 impl Asset for ChunkGroupFilesAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.asset.ident().with_modifier(modifier())
+        self.asset
+            .ident()
+            .with_modifier(Value::new(ModifierKind::Derived), modifier())
     }
 
     #[turbo_tasks::function]
@@ -23,6 +23,8 @@ pub mod typescript;
 pub mod utils;
 pub mod webpack;
 
+use std::time::Instant;
+
 use anyhow::Result;
 use chunk::{
     EcmascriptChunkItem, EcmascriptChunkItemVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc,
@@ -53,11 +55,13 @@ use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetOptionVc, AssetVc},
     chunk::{
         availability_info::AvailabilityInfo, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
-        ChunkableAssetVc, ChunkingContextVc, EvaluatableAsset, EvaluatableAssetVc,
+        ChunkableAssetVc, ChunkingContextVc, ConcatenatableChunkItem, ConcatenatableChunkItemVc,
+        ConcatenationInfo, ConcatenationInfoVc, EvaluatableAsset, EvaluatableAssetVc,
     },
     compile_time_info::CompileTimeInfoVc,
     context::AssetContextVc,
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
+    introspect::asset_timing::{is_asset_timing_enabled, record_asset_timing, TimingPhase},
     reference::{AssetReferencesReadRef, AssetReferencesVc},
     resolve::{
         origin::{ResolveOrigin, ResolveOriginVc},
@@ -69,7 +73,8 @@ pub use self::references::AnalyzeEcmascriptModuleResultVc;
 use self::{
     chunk::{
         placeable::EcmascriptExportsReadRef, EcmascriptChunkItemContent,
-        EcmascriptChunkItemContentVc, EcmascriptChunkItemOptions, EcmascriptExportsVc,
+        EcmascriptChunkItemContentVc, EcmascriptChunkItemOptions, EcmascriptExports,
+        EcmascriptExportsVc,
     },
     code_gen::{
         CodeGen, CodeGenerateableWithAvailabilityInfo, CodeGenerateableWithAvailabilityInfoVc,
@@ -130,6 +135,7 @@ struct MemoizedSuccessfulAnalysis {
     operation: RawVc,
     references: AssetReferencesReadRef,
     exports: EcmascriptExportsReadRef,
+    dynamic_scope: bool,
 }
 
 #[turbo_tasks::value]
@@ -230,11 +236,13 @@ impl EcmascriptModuleAssetVc {
                     // We need to store the ReadRefs since we want to keep a snapshot.
                     references: result_value.references.await?,
                     exports: result_value.exports.await?,
+                    dynamic_scope: result_value.dynamic_scope,
                 }));
         } else if let Some(MemoizedSuccessfulAnalysis {
             operation,
             references,
             exports,
+            dynamic_scope,
         }) = &*this.last_successful_analysis.get()
         {
             // It's important to connect to the last operation here to keep it active, so
@@ -244,6 +252,7 @@ impl EcmascriptModuleAssetVc {
                 references: ReadRef::cell(references.clone()),
                 exports: ReadRef::cell(exports.clone()),
                 code_generation: result_value.code_generation,
+                dynamic_scope: *dynamic_scope,
                 successful: false,
             }
             .cell());
@@ -268,10 +277,13 @@ impl Asset for EcmascriptModuleAsset {
             for (name, asset) in inner_assets.await?.iter() {
                 ident.add_asset(StringVc::cell(name.clone()), asset.ident());
             }
-            ident.add_modifier(modifier());
+            ident.add_modifier(ModifierKind::AssetType, modifier());
             Ok(AssetIdentVc::new(Value::new(ident)))
         } else {
-            Ok(self.source.ident().with_modifier(modifier()))
+            Ok(self
+                .source
+                .ident()
+                .with_modifier(Value::new(ModifierKind::AssetType), modifier()))
         }
     }
 
@@ -373,6 +385,31 @@ impl ChunkItem for ModuleChunkItem {
     }
 }
 
+#[turbo_tasks::value_impl]
+impl ConcatenatableChunkItem for ModuleChunkItem {
+    #[turbo_tasks::function]
+    async fn concatenation_info(&self) -> Result<ConcatenationInfoVc> {
+        let layer = ChunkingContextVc::from(self.context)
+            .layer()
+            .await?
+            .clone_value();
+        let analysis = self.module.failsafe_analyze().await?;
+        // CommonJS modules expose their bindings through `module.exports`,
+        // which can be accessed and mutated at runtime, and `eval` and `with`
+        // look up the bindings of the module by name.
+        let dynamic_access = analysis.dynamic_scope
+            || !matches!(
+                *analysis.exports.await?,
+                EcmascriptExports::EsmExports(_) | EcmascriptExports::None
+            );
+        Ok(ConcatenationInfo {
+            layer,
+            dynamic_access,
+        }
+        .cell())
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
@@ -469,6 +506,7 @@ async fn gen_content(
         ..
     } = &*parsed
     {
+        let start = Instant::now();
         let mut program = program.clone();
 
         GLOBALS.set(globals, || {
@@ -508,9 +546,17 @@ async fn gen_content(
 
         let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap).cell();
 
+        if is_asset_timing_enabled() {
+            record_asset_timing(
+                TimingPhase::ChunkItem,
+                ident.path().to_string().await?.clone_value(),
+                start.elapsed(),
+            );
+        }
+
         Ok(EcmascriptChunkItemContent {
             inner_code: bytes.into(),
-            source_map: Some(srcmap),
+            source_map: Some(srcmap.into()),
             options: if eval_context.is_esm() {
                 EcmascriptChunkItemOptions {
                     ..Default::default()
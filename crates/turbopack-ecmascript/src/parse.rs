@@ -1,4 +1,4 @@
-use std::{future::Future, sync::Arc};
+use std::{future::Future, sync::Arc, time::Instant};
 
 use anyhow::{anyhow, Context, Result};
 use swc_core::{
@@ -27,6 +27,7 @@ use turbo_tasks_fs::{FileContent, FileSystemPath, FileSystemPathVc};
 use turbo_tasks_hash::hash_xxh3_hash64;
 use turbopack_core::{
     asset::{Asset, AssetContent, AssetVc},
+    introspect::asset_timing::{is_asset_timing_enabled, record_asset_timing, TimingPhase},
     source_map::{GenerateSourceMap, GenerateSourceMapVc, OptionSourceMapVc},
     SOURCE_MAP_ROOT_NAME,
 };
@@ -153,7 +154,8 @@ pub async fn parse(
             FileContent::Content(file) => match file.content().to_str() {
                 Ok(string) => {
                     let transforms = &*transforms.await?;
-                    match parse_content(
+                    let start = Instant::now();
+                    let result = parse_content(
                         string.into_owned(),
                         fs_path_vc,
                         fs_path,
@@ -163,8 +165,15 @@ pub async fn parse(
                         ty,
                         transforms,
                     )
-                    .await
-                    {
+                    .await;
+                    if is_asset_timing_enabled() {
+                        record_asset_timing(
+                            TimingPhase::Transform,
+                            fs_path_vc.to_string().await?.clone_value(),
+                            start.elapsed(),
+                        );
+                    }
+                    match result {
                         Ok(result) => result,
                         Err(e) => {
                             return Err(e).context(anyhow!(
@@ -5,19 +5,32 @@ use swc_core::{
 };
 use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
+    asset::Asset,
     chunk::{ChunkableAssetReference, ChunkableAssetReferenceVc},
-    issue::{IssueSourceVc, OptionIssueSourceVc},
+    context::AssetContext,
+    issue::{
+        interop_policy::{InteropPolicyIssue, InteropViolation},
+        IssueSourceVc, OptionIssueSourceVc,
+    },
     reference::{AssetReference, AssetReferenceVc},
-    resolve::{origin::ResolveOriginVc, parse::RequestVc, ResolveResultVc},
+    resolve::{
+        options::ResolveOptionsOverridesVc,
+        origin::{ResolveOrigin, ResolveOriginVc},
+        parse::RequestVc,
+        ResolveResultVc,
+    },
 };
 
 use super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveType::Cjs};
 use crate::{
-    chunk::EcmascriptChunkingContextVc,
+    chunk::{
+        EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptChunkingContextVc,
+        EcmascriptExports,
+    },
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
     references::{util::throw_module_not_found_expr, AstPathVc},
-    resolve::{cjs_resolve, try_to_severity},
+    resolve::{cjs_resolve, cjs_resolve_with_overrides, try_to_severity},
 };
 
 #[turbo_tasks::value]
@@ -138,16 +151,49 @@ impl CodeGenerateable for CjsRequireAssetReference {
         &self,
         context: EcmascriptChunkingContextVc,
     ) -> Result<CodeGenerationVc> {
+        let resolve_result = cjs_resolve(
+            self.origin,
+            self.request,
+            OptionIssueSourceVc::some(self.issue_source),
+            try_to_severity(self.in_try),
+        );
+        let interop_policy = *self
+            .origin
+            .context()
+            .compile_time_info()
+            .interop_policy()
+            .await?;
+        // Under policies that don't allow CommonJS at all, the `require()` call
+        // itself was already reported during analysis.
+        if interop_policy.allows_commonjs() && !interop_policy.allows_require_of_esm() {
+            for asset in resolve_result.primary_assets().await?.iter() {
+                let Some(placeable) = EcmascriptChunkPlaceableVc::resolve_from(*asset).await? else {
+                    continue;
+                };
+                if matches!(
+                    &*placeable.get_exports().await?,
+                    EcmascriptExports::EsmExports(_)
+                ) {
+                    InteropPolicyIssue {
+                        path: self.origin.origin_path(),
+                        policy: interop_policy,
+                        violation: InteropViolation::RequireOfEsm {
+                            request: asset.ident().to_string().await?.clone_value(),
+                        },
+                        source: Some(self.issue_source),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
+            }
+        }
+
         let pm = PatternMappingVc::resolve_request(
             self.request,
             self.origin,
             context.into(),
-            cjs_resolve(
-                self.origin,
-                self.request,
-                OptionIssueSourceVc::some(self.issue_source),
-                try_to_severity(self.in_try),
-            ),
+            resolve_result,
             Value::new(Cjs),
         )
         .await?;
@@ -199,13 +245,15 @@ impl CodeGenerateable for CjsRequireAssetReference {
 }
 
 #[turbo_tasks::value]
-#[derive(Hash, Debug)]
+#[derive(Clone, Hash, Debug)]
 pub struct CjsRequireResolveAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub path: AstPathVc,
     pub issue_source: IssueSourceVc,
     pub in_try: bool,
+    /// Applied on top of the resolve options of the origin
+    pub resolve_overrides: Option<ResolveOptionsOverridesVc>,
 }
 
 #[turbo_tasks::value_impl]
@@ -217,6 +265,7 @@ impl CjsRequireResolveAssetReferenceVc {
         path: AstPathVc,
         issue_source: IssueSourceVc,
         in_try: bool,
+        resolve_overrides: Option<ResolveOptionsOverridesVc>,
     ) -> Self {
         Self::cell(CjsRequireResolveAssetReference {
             origin,
@@ -224,6 +273,7 @@ impl CjsRequireResolveAssetReferenceVc {
             path,
             issue_source,
             in_try,
+            resolve_overrides,
         })
     }
 }
@@ -232,9 +282,10 @@ impl CjsRequireResolveAssetReferenceVc {
 impl AssetReference for CjsRequireResolveAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        cjs_resolve(
+        cjs_resolve_with_overrides(
             self.origin,
             self.request,
+            self.resolve_overrides,
             OptionIssueSourceVc::some(self.issue_source),
             try_to_severity(self.in_try),
         )
@@ -266,9 +317,10 @@ impl CodeGenerateable for CjsRequireResolveAssetReference {
             self.request,
             self.origin,
             context.into(),
-            cjs_resolve(
+            cjs_resolve_with_overrides(
                 self.origin,
                 self.request,
+                self.resolve_overrides,
                 OptionIssueSourceVc::some(self.issue_source),
                 try_to_severity(self.in_try),
             ),
@@ -16,8 +16,8 @@ use turbopack_core::{
     reference::{AssetReference, AssetReferenceVc},
     reference_type::EcmaScriptModulesReferenceSubType,
     resolve::{
-        origin::ResolveOriginVc, parse::RequestVc, ModulePartVc, PrimaryResolveResult,
-        ResolveResultVc,
+        options::ResolveOptionsOverridesVc, origin::ResolveOriginVc, parse::RequestVc,
+        ModulePartVc, PrimaryResolveResult, ResolveResultVc,
     },
 };
 
@@ -27,7 +27,7 @@ use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor, magic_identifier,
     references::util::{request_to_string, throw_module_not_found_expr},
-    resolve::esm_resolve,
+    resolve::esm_resolve_with_overrides,
 };
 
 #[turbo_tasks::value]
@@ -102,6 +102,8 @@ pub struct EsmAssetReference {
     pub annotations: ImportAnnotations,
 
     pub export_name: Option<ModulePartVc>,
+    /// Applied on top of the resolve options of the origin
+    pub resolve_overrides: Option<ResolveOptionsOverridesVc>,
 }
 
 impl EsmAssetReference {
@@ -132,12 +134,14 @@ impl EsmAssetReferenceVc {
         request: RequestVc,
         annotations: Value<ImportAnnotations>,
         export_name: Option<ModulePartVc>,
+        resolve_overrides: Option<ResolveOptionsOverridesVc>,
     ) -> Self {
         Self::cell(EsmAssetReference {
             origin,
             request,
             annotations: annotations.into_value(),
             export_name,
+            resolve_overrides,
         })
     }
 }
@@ -151,10 +155,11 @@ impl AssetReference for EsmAssetReference {
             None => EcmaScriptModulesReferenceSubType::Undefined,
         });
 
-        esm_resolve(
+        esm_resolve_with_overrides(
             self.get_origin(),
             self.request,
             ty,
+            self.resolve_overrides,
             OptionIssueSourceVc::none(),
             IssueSeverity::Error.cell(),
         )
@@ -42,18 +42,25 @@ use swc_core::{
 };
 use turbo_tasks::{
     primitives::{BoolVc, RegexVc},
-    TryJoinIterExt, Value,
+    TryJoinIterExt, Value, ValueToString,
 };
 use turbo_tasks_fs::{FileJsonContent, FileSystemPathVc};
 use turbopack_core::{
     asset::{Asset, AssetVc},
+    chunk::ExternalInputsVc,
     compile_time_info::{CompileTimeInfoVc, FreeVarReference},
     error::PrettyPrintError,
-    issue::{IssueSourceVc, OptionIssueSourceVc},
+    issue::{
+        interop_policy::{InteropPolicyIssue, InteropViolation},
+        IssueSourceVc, OptionIssueSourceVc,
+    },
     reference::{AssetReferenceVc, AssetReferencesVc, SourceMapReferenceVc},
     reference_type::{CommonJsReferenceSubType, ReferenceType},
     resolve::{
         find_context_file,
+        options::{
+            ConditionValue, ResolveModules, ResolveOptionsOverrides, ResolveOptionsOverridesVc,
+        },
         origin::{PlainResolveOriginVc, ResolveOrigin, ResolveOriginVc},
         package_json,
         parse::RequestVc,
@@ -83,7 +90,7 @@ use self::{
 use super::{
     analyzer::{
         builtin::replace_builtin,
-        graph::{create_graph, Effect},
+        graph::{accesses_scope_dynamically, create_graph, Effect},
         linker::link,
         well_known::replace_well_known,
         JsValue, ObjectPart, WellKnownFunctionKind, WellKnownObjectKind,
@@ -103,8 +110,8 @@ use crate::{
     analyzer::{
         builtin::early_replace_builtin,
         graph::{ConditionalKind, EffectArg, EvalContext, VarGraph},
-        imports::{ImportedSymbol, Reexport},
-        parse_require_context, ModuleValue, RequireContextValueVc,
+        imports::{ImportAnnotations, ImportedSymbol, Reexport},
+        parse_require_context, parse_require_resolve_paths, ModuleValue, RequireContextValueVc,
     },
     chunk::{EcmascriptExports, EcmascriptExportsVc},
     code_gen::{
@@ -130,6 +137,8 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub references: AssetReferencesVc,
     pub code_generation: CodeGenerateablesVc,
     pub exports: EcmascriptExportsVc,
+    /// `true` when the module resolves names at runtime, e.g. with `eval`.
+    pub dynamic_scope: bool,
     /// `true` when the analysis was successful.
     pub successful: bool,
 }
@@ -166,6 +175,7 @@ pub(crate) struct AnalyzeEcmascriptModuleResultBuilder {
     references: IndexSet<AssetReferenceVc>,
     code_gens: Vec<CodeGen>,
     exports: EcmascriptExports,
+    dynamic_scope: bool,
     successful: bool,
 }
 
@@ -175,6 +185,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             references: IndexSet::new(),
             code_gens: Vec::new(),
             exports: EcmascriptExports::None,
+            dynamic_scope: false,
             successful: false,
         }
     }
@@ -212,6 +223,12 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.exports = exports;
     }
 
+    /// Sets whether the module resolves names at runtime, see
+    /// [accesses_scope_dynamically].
+    pub fn set_dynamic_scope(&mut self, dynamic_scope: bool) {
+        self.dynamic_scope = dynamic_scope;
+    }
+
     /// Sets whether the analysis was successful.
     pub fn set_successful(&mut self, successful: bool) {
         self.successful = successful;
@@ -239,6 +256,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
                 references: AssetReferencesVc::cell(references),
                 code_generation: CodeGenerateablesVc::cell(self.code_gens),
                 exports: self.exports.into(),
+                dynamic_scope: self.dynamic_scope,
                 successful: self.successful,
             },
         ))
@@ -433,6 +451,7 @@ pub(crate) async fn analyze_ecmascript_module(
                     } else {
                         None
                     },
+                    import_annotations_overrides(&r.annotations),
                 );
                 import_references.push(r);
             }
@@ -577,12 +596,27 @@ pub(crate) async fn analyze_ecmascript_module(
                     .cell(),
                 )
             } else if has_cjs_export(program) {
+                let interop_policy = *compile_time_info.interop_policy().await?;
+                if !interop_policy.allows_commonjs() {
+                    InteropPolicyIssue {
+                        path: source.ident().path(),
+                        policy: interop_policy,
+                        violation: InteropViolation::CommonJsExports,
+                        source: None,
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                }
                 EcmascriptExports::CommonJs
             } else {
                 EcmascriptExports::None
             };
 
             analysis.set_exports(exports);
+            analysis.set_dynamic_scope(GLOBALS.set(globals, || {
+                accesses_scope_dynamically(program, eval_context)
+            }));
 
             fn handle_call_boxed<'a, G: Fn(Vec<Effect>) + Send + Sync + 'a>(
                 ast_path: &'a [AstParentKind],
@@ -714,9 +748,24 @@ pub(crate) async fn analyze_ecmascript_module(
                                     ),
                                 )
                             }
+                            let request = RequestVc::parse(Value::new(pat));
+                            let interop_policy = *compile_time_info.interop_policy().await?;
+                            if !interop_policy.allows_commonjs() {
+                                InteropPolicyIssue {
+                                    path: source.ident().path(),
+                                    policy: interop_policy,
+                                    violation: InteropViolation::Require {
+                                        request: request.to_string().await?.clone_value(),
+                                    },
+                                    source: Some(issue_source(source, span)),
+                                }
+                                .cell()
+                                .as_issue()
+                                .emit();
+                            }
                             analysis.add_reference(CjsRequireAssetReferenceVc::new(
                                 origin,
-                                RequestVc::parse(Value::new(pat)),
+                                request,
                                 AstPathVc::cell(ast_path.to_vec()),
                                 issue_source(source, span),
                                 in_try,
@@ -747,7 +796,20 @@ pub(crate) async fn analyze_ecmascript_module(
 
                     JsValue::WellKnownFunction(WellKnownFunctionKind::RequireResolve) => {
                         let args = linked_args(args).await?;
-                        if args.len() == 1 {
+                        // `require.resolve(request, { paths })` looks up modules from the
+                        // `paths` instead of from the module.
+                        let paths = match &args[..] {
+                            [_] => Some(None),
+                            [_, options] => parse_require_resolve_paths(options).map(Some),
+                            _ => None,
+                        };
+                        if let Some(paths) = paths {
+                            let resolve_overrides = match paths {
+                                Some(paths) => {
+                                    Some(require_resolve_paths_overrides(origin, &paths).await?)
+                                }
+                                None => None,
+                            };
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
                                 let (args, hints) = explain_args(&args);
@@ -766,6 +828,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                 AstPathVc::cell(ast_path.to_vec()),
                                 issue_source(source, span),
                                 in_try,
+                                resolve_overrides,
                             ));
                             return Ok(());
                         }
@@ -1359,6 +1422,7 @@ pub(crate) async fn analyze_ecmascript_module(
                                                 })
                                             })
                                             .flatten(),
+                                        None,
                                     )
                                     .resolve()
                                     .await?;
@@ -1933,6 +1997,54 @@ async fn require_resolve(path: FileSystemPathVc) -> Result<JsValue> {
     Ok(format!("/ROOT/{}", path.await?.path.as_str()).into())
 }
 
+/// Resolve options overrides that set the export conditions of the
+/// `conditions` import annotation, e.g.
+/// `"TURBOPACK { conditions: react-server }";`.
+fn import_annotations_overrides(
+    annotations: &ImportAnnotations,
+) -> Option<ResolveOptionsOverridesVc> {
+    let conditions: BTreeMap<_, _> = annotations
+        .conditions()
+        .map(|condition| (condition.to_string(), ConditionValue::Set))
+        .collect();
+    (!conditions.is_empty()).then(|| {
+        ResolveOptionsOverrides {
+            conditions,
+            ..Default::default()
+        }
+        .cell()
+    })
+}
+
+/// Resolve options overrides for `require.resolve(request, { paths })`. Like
+/// Node.js, modules are looked up in the `node_modules` directory of each of
+/// the `paths` and of their ancestors, instead of those of the origin. Paths
+/// are relative to the directory of the origin.
+async fn require_resolve_paths_overrides(
+    origin: ResolveOriginVc,
+    paths: &[String],
+) -> Result<ResolveOptionsOverridesVc> {
+    let dir = origin.origin_path().parent();
+    let mut modules = Vec::new();
+    for path in paths {
+        let Some(mut current) = *dir.try_join(path).await? else {
+            continue;
+        };
+        loop {
+            modules.push(ResolveModules::Path(current.join("node_modules")));
+            if current.await?.is_root() {
+                break;
+            }
+            current = current.parent().resolve().await?;
+        }
+    }
+    Ok(ResolveOptionsOverrides {
+        modules: Some(modules),
+        ..Default::default()
+    }
+    .cell())
+}
+
 async fn early_value_visitor(mut v: JsValue) -> Result<(JsValue, bool)> {
     let modified = early_replace_builtin(&mut v);
     Ok((v, modified))
@@ -1956,8 +2068,7 @@ async fn value_visitor_inner(
     in_try: bool,
 ) -> Result<(JsValue, bool)> {
     if let Some(def_name_len) = v.get_defineable_name_len() {
-        let compile_time_info = compile_time_info.await?;
-        let defines = compile_time_info.defines.await?;
+        let defines = compile_time_info.defines().await?;
         for (name, value) in defines.iter() {
             if name.len() != def_name_len {
                 continue;
@@ -1965,6 +2076,8 @@ async fn value_visitor_inner(
             if v.iter_defineable_name_rev()
                 .eq(name.iter().map(Cow::Borrowed).rev())
             {
+                // The value ends up in the code of the chunks of this module.
+                ExternalInputsVc::define(name, value)?.emit();
                 return Ok((value.into(), true));
             }
         }
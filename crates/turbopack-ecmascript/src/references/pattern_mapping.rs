@@ -8,7 +8,7 @@ use turbo_tasks::{debug::ValueDebug, primitives::StringVc, Value, ValueToString}
 use turbopack_core::{
     asset::Asset,
     chunk::{
-        availability_info::AvailabilityInfo, ChunkableAssetVc, ChunkingContextVc,
+        availability_info::AvailabilityInfo, ChunkableAssetVc, ChunkingContext, ChunkingContextVc,
         FromChunkableAsset, ModuleId,
     },
     issue::{code_gen::CodeGenerationIssue, IssueSeverity},
@@ -175,7 +175,13 @@ impl PatternMappingVc {
                 } else {
                     false
                 };
-                if !available {
+                // Without code splitting the asset is placed into the same chunk, so it
+                // doesn't need a loader.
+                let inlined = !*context.is_code_splitting_enabled().await?
+                    && EcmascriptChunkItemVc::from_asset(context, asset)
+                        .await?
+                        .is_some();
+                if !available && !inlined {
                     if let Some(loader) = EcmascriptChunkItemVc::from_async_asset(
                         context,
                         chunkable,
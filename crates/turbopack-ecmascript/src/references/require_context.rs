@@ -24,7 +24,7 @@ use turbopack_core::{
         availability_info::AvailabilityInfo, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
         ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkableAssetVc, ChunkingContextVc,
     },
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     issue::{IssueSeverityVc, OptionIssueSourceVc},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     resolve::{
@@ -354,9 +354,10 @@ fn modifier(dir: String, include_subdirs: bool) -> StringVc {
 impl Asset for RequireContextAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source
-            .ident()
-            .with_modifier(modifier(self.dir.clone(), self.include_subdirs))
+        self.source.ident().with_modifier(
+            Value::new(ModifierKind::Derived),
+            modifier(self.dir.clone(), self.include_subdirs),
+        )
     }
 
     #[turbo_tasks::function]
@@ -11,7 +11,10 @@ use turbopack_core::{
     },
     resolve::{
         handle_resolve_error,
-        options::{ConditionValue, ResolveIntoPackage, ResolveOptions, ResolveOptionsVc},
+        options::{
+            ConditionValue, ResolveIntoPackage, ResolveOptions, ResolveOptionsOverridesVc,
+            ResolveOptionsVc,
+        },
         origin::{ResolveOrigin, ResolveOriginVc},
         parse::RequestVc,
         resolve, ResolveResultVc,
@@ -48,42 +51,100 @@ pub async fn apply_cjs_specific_options(options: ResolveOptionsVc) -> Result<Res
     Ok(options.into())
 }
 
+fn apply_overrides(
+    options: ResolveOptionsVc,
+    overrides: Option<ResolveOptionsOverridesVc>,
+) -> ResolveOptionsVc {
+    if let Some(overrides) = overrides {
+        options.with_overrides(overrides)
+    } else {
+        options
+    }
+}
+
+#[turbo_tasks::function]
+pub fn esm_resolve(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    ty: Value<EcmaScriptModulesReferenceSubType>,
+    issue_source: OptionIssueSourceVc,
+    issue_severity: IssueSeverityVc,
+) -> ResolveResultVc {
+    esm_resolve_with_overrides(origin, request, ty, None, issue_source, issue_severity)
+}
+
+/// Like [esm_resolve], but applies the per-reference `overrides` on top of the
+/// resolve options of the origin.
 #[turbo_tasks::function]
-pub async fn esm_resolve(
+pub async fn esm_resolve_with_overrides(
     origin: ResolveOriginVc,
     request: RequestVc,
     ty: Value<EcmaScriptModulesReferenceSubType>,
+    overrides: Option<ResolveOptionsOverridesVc>,
     issue_source: OptionIssueSourceVc,
     issue_severity: IssueSeverityVc,
 ) -> Result<ResolveResultVc> {
     let ty = Value::new(ReferenceType::EcmaScriptModules(ty.into_value()));
-    let options = apply_esm_specific_options(origin.resolve_options(ty.clone()));
+    let options = apply_overrides(
+        apply_esm_specific_options(origin.resolve_options(ty.clone())),
+        overrides,
+    );
     specific_resolve(origin, request, options, ty, issue_source, issue_severity).await
 }
 
 #[turbo_tasks::function]
-pub async fn cjs_resolve(
+pub fn cjs_resolve(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    issue_source: OptionIssueSourceVc,
+    issue_severity: IssueSeverityVc,
+) -> ResolveResultVc {
+    cjs_resolve_with_overrides(origin, request, None, issue_source, issue_severity)
+}
+
+/// Like [cjs_resolve], but applies the per-reference `overrides` on top of the
+/// resolve options of the origin.
+#[turbo_tasks::function]
+pub async fn cjs_resolve_with_overrides(
     origin: ResolveOriginVc,
     request: RequestVc,
+    overrides: Option<ResolveOptionsOverridesVc>,
     issue_source: OptionIssueSourceVc,
     issue_severity: IssueSeverityVc,
 ) -> Result<ResolveResultVc> {
     // TODO pass CommonJsReferenceSubType
     let ty = Value::new(ReferenceType::CommonJs(CommonJsReferenceSubType::Undefined));
-    let options = apply_cjs_specific_options(origin.resolve_options(ty.clone()));
+    let options = apply_overrides(
+        apply_cjs_specific_options(origin.resolve_options(ty.clone())),
+        overrides,
+    );
     specific_resolve(origin, request, options, ty, issue_source, issue_severity).await
 }
 
 #[turbo_tasks::function]
-pub async fn url_resolve(
+pub fn url_resolve(
+    origin: ResolveOriginVc,
+    request: RequestVc,
+    ty: Value<UrlReferenceSubType>,
+    issue_source: IssueSourceVc,
+    issue_severity: IssueSeverityVc,
+) -> ResolveResultVc {
+    url_resolve_with_overrides(origin, request, ty, None, issue_source, issue_severity)
+}
+
+/// Like [url_resolve], but applies the per-reference `overrides` on top of the
+/// resolve options of the origin.
+#[turbo_tasks::function]
+pub async fn url_resolve_with_overrides(
     origin: ResolveOriginVc,
     request: RequestVc,
     ty: Value<UrlReferenceSubType>,
+    overrides: Option<ResolveOptionsOverridesVc>,
     issue_source: IssueSourceVc,
     issue_severity: IssueSeverityVc,
 ) -> Result<ResolveResultVc> {
     let ty = Value::new(ReferenceType::Url(ty.into_value()));
-    let resolve_options = origin.resolve_options(ty.clone());
+    let resolve_options = apply_overrides(origin.resolve_options(ty.clone()), overrides);
     let rel_request = request.as_relative();
     let rel_result = resolve(origin.origin_path().parent(), rel_request, resolve_options);
     let result = if *rel_result.is_unresolveable().await? && rel_request.resolve().await? != request
@@ -1,9 +1,9 @@
 use anyhow::Result;
-use turbo_tasks::primitives::StringVc;
+use turbo_tasks::{primitives::StringVc, Value};
 use turbo_tasks_fs::FileContent;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
 };
 
 use crate::utils::StringifyJs;
@@ -32,7 +32,9 @@ impl TextContentSourceAssetVc {
 impl Asset for TextContentSourceAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source.ident().with_modifier(modifier())
+        self.source
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
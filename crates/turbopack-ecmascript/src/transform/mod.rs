@@ -19,16 +19,19 @@ use swc_core::{
     },
     quote,
 };
-use turbo_tasks::primitives::{OptionStringVc, StringVc, StringsVc};
+use turbo_tasks::{
+    primitives::{OptionStringVc, StringVc, StringsVc},
+    Value,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
-    environment::EnvironmentVc,
+    environment::{EnvironmentVc, RuntimeFeature},
     issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
 };
 
 use self::{
     server_to_client_proxy::create_proxy_module,
-    util::{is_client_module, is_server_module},
+    util::{is_client_module, is_server_module, used_runtime_features},
 };
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -202,6 +205,20 @@ impl EcmascriptInputTransform {
                 ));
             }
             EcmascriptInputTransform::PresetEnv(env) => {
+                // preset-env lowers syntax, but can't lower these features, so
+                // the environment has to support them.
+                for feature in used_runtime_features(program) {
+                    if !*env.supports(Value::new(feature)).await? {
+                        UnsupportedRuntimeFeatureIssue {
+                            context: file_path,
+                            feature,
+                        }
+                        .cell()
+                        .as_issue()
+                        .emit();
+                    }
+                }
+
                 let versions = env.runtime_versions().await?;
                 let config = swc_core::ecma::preset_env::Config {
                     targets: Some(Targets::Versions(*versions)),
@@ -390,3 +407,44 @@ impl Issue for UnsupportedServerActionIssue {
         Ok(StringVc::cell("".to_string()))
     }
 }
+
+#[turbo_tasks::value(shared)]
+pub struct UnsupportedRuntimeFeatureIssue {
+    pub context: FileSystemPathVc,
+    pub feature: RuntimeFeature,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UnsupportedRuntimeFeatureIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("unsupported".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!(
+            "{} is not supported by the target environment",
+            self.feature.name()
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(
+            "The module uses a feature that can't be transformed for the targeted runtimes, so it \
+             will fail to run in some of them."
+                .to_string(),
+        )
+    }
+}
@@ -1,4 +1,10 @@
-use swc_core::ecma::ast::{Lit, Program};
+use std::collections::BTreeSet;
+
+use swc_core::ecma::{
+    ast::{ArrowExpr, AwaitExpr, ForOfStmt, Function, Lit, Program},
+    visit::{Visit, VisitWith},
+};
+use turbopack_core::environment::RuntimeFeature;
 
 macro_rules! has_directive {
     ($stmts:expr, $name:literal) => {
@@ -33,3 +39,55 @@ pub fn is_server_module(program: &Program) -> bool {
         Program::Script(s) => has_directive!(s.body.iter().map(Some), "use server"),
     }
 }
+
+/// The runtime features `program` uses that no transform can lower, so the
+/// environment it runs in has to support them.
+pub fn used_runtime_features(program: &Program) -> BTreeSet<RuntimeFeature> {
+    let mut finder = RuntimeFeatureFinder::default();
+    program.visit_with(&mut finder);
+    finder.features
+}
+
+#[derive(Default)]
+struct RuntimeFeatureFinder {
+    features: BTreeSet<RuntimeFeature>,
+    in_function: bool,
+}
+
+impl RuntimeFeatureFinder {
+    fn visit_function_body(&mut self, visit: impl FnOnce(&mut Self)) {
+        let in_function = std::mem::replace(&mut self.in_function, true);
+        visit(self);
+        self.in_function = in_function;
+    }
+}
+
+impl Visit for RuntimeFeatureFinder {
+    fn visit_lit(&mut self, lit: &Lit) {
+        if let Lit::BigInt(_) = lit {
+            self.features.insert(RuntimeFeature::BigInt);
+        }
+    }
+
+    fn visit_await_expr(&mut self, expr: &AwaitExpr) {
+        if !self.in_function {
+            self.features.insert(RuntimeFeature::TopLevelAwait);
+        }
+        expr.visit_children_with(self);
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        if stmt.is_await && !self.in_function {
+            self.features.insert(RuntimeFeature::TopLevelAwait);
+        }
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.visit_function_body(|this| function.visit_children_with(this));
+    }
+
+    fn visit_arrow_expr(&mut self, expr: &ArrowExpr) {
+        self.visit_function_body(|this| expr.visit_children_with(this));
+    }
+}
@@ -3,7 +3,7 @@ use swc_core::ecma::ast::Lit;
 use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
     reference_type::{CommonJsReferenceSubType, ReferenceType},
     resolve::{
@@ -56,7 +56,9 @@ impl WebpackModuleAssetVc {
 impl Asset for WebpackModuleAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source.ident().with_modifier(modifier())
+        self.source
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
@@ -18,7 +18,7 @@ use turbopack_core::{
         availability_info::AvailabilityInfo, ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset,
         ChunkableAssetVc, ChunkingContextVc,
     },
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::AssetReferencesVc,
 };
 use turbopack_ecmascript::chunk::{
@@ -49,7 +49,9 @@ impl JsonModuleAssetVc {
 impl Asset for JsonModuleAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source.ident().with_modifier(modifier())
+        self.source
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
@@ -11,7 +11,7 @@ use turbopack_core::{
         ChunkableAssetVc, ChunkingContextVc,
     },
     context::{AssetContext, AssetContextVc},
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::AssetReferencesVc,
     resolve::origin::{ResolveOrigin, ResolveOriginVc},
     virtual_asset::VirtualAssetVc,
@@ -164,7 +164,9 @@ impl MdxModuleAssetVc {
 impl Asset for MdxModuleAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source.ident().with_modifier(modifier())
+        self.source
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
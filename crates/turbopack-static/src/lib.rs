@@ -22,7 +22,7 @@ use turbopack_core::{
         ChunkableAssetVc, ChunkingContext, ChunkingContextVc,
     },
     context::AssetContextVc,
-    ident::AssetIdentVc,
+    ident::{AssetIdentVc, ModifierKind},
     reference::{AssetReferencesVc, SingleAssetReferenceVc},
 };
 use turbopack_css::embed::{CssEmbed, CssEmbedVc, CssEmbeddable, CssEmbeddableVc};
@@ -70,7 +70,9 @@ impl StaticModuleAssetVc {
 impl Asset for StaticModuleAsset {
     #[turbo_tasks::function]
     fn ident(&self) -> AssetIdentVc {
-        self.source.ident().with_modifier(modifier())
+        self.source
+            .ident()
+            .with_modifier(Value::new(ModifierKind::AssetType), modifier())
     }
 
     #[turbo_tasks::function]
@@ -199,13 +201,20 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
     #[turbo_tasks::function]
     async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let context: ChunkingContextVc = self.context.into();
+        let path = self.static_asset.ident().path();
+        let url = match &*context
+            .public_path()
+            .url(context.output_root(), path)
+            .await?
+        {
+            Some(url) => url.clone(),
+            None => format!("/{}", &*path.await?),
+        };
         Ok(EcmascriptChunkItemContent {
             inner_code: format!(
-                "__turbopack_export_value__({path});",
-                path = StringifyJs(&format_args!(
-                    "/{}",
-                    &*self.static_asset.ident().path().await?
-                ))
+                "__turbopack_export_value__({url});",
+                url = StringifyJs(&url)
             )
             .into(),
             ..Default::default()
@@ -0,0 +1,443 @@
+#![cfg(test)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, TurboTasks, Value};
+use turbo_tasks_fs::{DiskFileSystemVc, FileSystem, FileSystemPathVc};
+use turbo_tasks_memory::MemoryBackend;
+use turbopack::{
+    ecmascript::chunk::ChunkItemCodeCacheVc, module_options::ModuleOptionsContext,
+    resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
+    ModuleAssetContextVc,
+};
+use turbopack_core::{
+    build_session::{BuildContext, BuildSession, EntrypointAsset},
+    chunk::NamedChunksVc,
+    compile_time_info::CompileTimeInfoVc,
+    environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
+    progress::{progress_channel, BuildPhase},
+};
+use turbopack_dev::DevChunkingContextVc;
+
+fn register() {
+    turbopack::register();
+    turbopack_dev::register();
+}
+
+fn client_context(project_root: FileSystemPathVc, output_root: FileSystemPathVc) -> BuildContext {
+    build_context(project_root, output_root, None, None)
+}
+
+/// Places the modules in `src/icons` into a chunk named `icons`.
+fn icons_context(project_root: FileSystemPathVc, output_root: FileSystemPathVc) -> BuildContext {
+    let named_chunks = NamedChunksVc::new(
+        project_root,
+        vec![("src/icons/**".to_string(), "icons".to_string())],
+    );
+    build_context(project_root, output_root, Some(named_chunks), None)
+}
+
+fn build_context(
+    project_root: FileSystemPathVc,
+    output_root: FileSystemPathVc,
+    named_chunks: Option<NamedChunksVc>,
+    code_cache: Option<ChunkItemCodeCacheVc>,
+) -> BuildContext {
+    let env = EnvironmentVc::new(
+        Value::new(ExecutionEnvironment::Browser(
+            BrowserEnvironment {
+                dom: true,
+                web_worker: false,
+                service_worker: false,
+                browserslist_query: "last 1 Chrome versions".to_string(),
+            }
+            .into(),
+        )),
+        Value::new(EnvironmentIntention::Client),
+    );
+    let asset_context = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        CompileTimeInfoVc::new(env),
+        ModuleOptionsContext::default().cell(),
+        ResolveOptionsContext::default().cell(),
+    );
+    let mut chunking_context = DevChunkingContextVc::builder(
+        project_root,
+        output_root,
+        output_root.join("chunks"),
+        output_root.join("assets"),
+        env,
+    );
+    if let Some(named_chunks) = named_chunks {
+        chunking_context = chunking_context.named_chunks(named_chunks);
+    }
+    if let Some(code_cache) = code_cache {
+        chunking_context = chunking_context.code_cache(code_cache);
+    }
+    let chunking_context = chunking_context.build();
+    BuildContext {
+        asset_context: asset_context.into(),
+        chunking_context,
+    }
+}
+
+/// Writes `src/index.js`, which imports `src/a.js`, which imports `src/b.js`.
+fn write_project(project: &Path) -> Result<()> {
+    fs::create_dir(project.join("src"))?;
+    fs::write(
+        project.join("src/index.js"),
+        "import { a } from './a.js';\nconsole.log(a);\n",
+    )?;
+    fs::write(
+        project.join("src/a.js"),
+        "import { b } from './b.js';\nexport const a = b;\n",
+    )?;
+    fs::write(project.join("src/b.js"), "export const b = 1;\n")?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn build_session_reports_progress() -> Result<()> {
+    register();
+    let project = tempfile::tempdir()?;
+    let output = tempfile::tempdir()?;
+    write_project(project.path())?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut session = BuildSession::new(
+        tt,
+        project.path().to_str().unwrap().to_string(),
+        output.path().to_str().unwrap().to_string(),
+    )
+    .await?;
+    session.register_context("client", client_context).await?;
+    let (sender, mut receiver) = progress_channel();
+    session.set_progress(sender);
+
+    let assets = session
+        .get_entrypoint_assets("client", "src/index.js")
+        .await?;
+
+    let mut done = HashMap::new();
+    while let Ok(event) = receiver.try_recv() {
+        if event.done {
+            done.insert(event.phase, event);
+        }
+    }
+    let finished = |phase: BuildPhase| {
+        let event = done
+            .get(&phase)
+            .unwrap_or_else(|| panic!("{phase} isn't done"));
+        assert_eq!(event.completed, event.total, "{phase}");
+        event.completed
+    };
+    // index.js, a.js and b.js are each transformed once.
+    assert_eq!(finished(BuildPhase::Transforming), 3);
+    // At least the two imports are resolved.
+    assert!(finished(BuildPhase::Resolving) >= 2);
+    assert_eq!(finished(BuildPhase::Chunking), 1);
+    let written = assets
+        .iter()
+        .filter(|asset| asset.content.is_some())
+        .count();
+    assert!(written > 0);
+    assert_eq!(finished(BuildPhase::Emitting), written);
+    for asset in &assets {
+        if asset.content.is_some() {
+            assert!(output.path().join(&asset.path).is_file());
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn deterministic_build_is_verified_in_a_fresh_instance() -> Result<()> {
+    register();
+    let project = tempfile::tempdir()?;
+    let output = tempfile::tempdir()?;
+    write_project(project.path())?;
+    let project_dir = project.path().to_str().unwrap().to_string();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut session = BuildSession::new(
+        tt,
+        project_dir.clone(),
+        output.path().to_str().unwrap().to_string(),
+    )
+    .await?;
+    session.register_context("client", client_context).await?;
+    session.set_deterministic(true);
+
+    // The rebuild isn't served from the cache of the first build, so the
+    // build only succeeds when both produce the same output.
+    let assets = session
+        .get_entrypoint_assets("client", "src/index.js")
+        .await?;
+    for asset in &assets {
+        if asset.content.is_some() {
+            let written = fs::read_to_string(output.path().join(&asset.path))?;
+            assert!(!written.contains(&project_dir), "{}", asset.path);
+        }
+    }
+    session.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn app_pages_share_the_chunks_of_common_modules() -> Result<()> {
+    register();
+    let project = tempfile::tempdir()?;
+    let output = tempfile::tempdir()?;
+    let src = project.path().join("src");
+    fs::create_dir(&src)?;
+    fs::write(
+        src.join("shared.js"),
+        "export const shared = \"shared module\";\n",
+    )?;
+    fs::write(
+        src.join("a.js"),
+        "import { shared } from './shared.js';\nconsole.log('a', shared);\n",
+    )?;
+    fs::write(
+        src.join("b.js"),
+        "import { shared } from './shared.js';\nconsole.log('b', shared);\n",
+    )?;
+    fs::write(src.join("c.js"), "console.log('c');\n")?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut session = BuildSession::new(
+        tt,
+        project.path().to_str().unwrap().to_string(),
+        output.path().to_str().unwrap().to_string(),
+    )
+    .await?;
+    session.register_context("client", client_context).await?;
+    let pages = session
+        .get_app_assets("client", &["src/a.js", "src/b.js", "src/c.js"], 2)
+        .await?;
+
+    let paths = |page: &[EntrypointAsset]| {
+        page.iter()
+            .map(|asset| asset.path.clone())
+            .collect::<HashSet<_>>()
+    };
+    let (a, b, c) = (paths(&pages[0]), paths(&pages[1]), paths(&pages[2]));
+    let shared = a.intersection(&b).cloned().collect::<HashSet<_>>();
+    // shared.js is written once and only loaded by the pages that use it.
+    let shared_module = shared
+        .iter()
+        .filter(|path| {
+            fs::read_to_string(output.path().join(path))
+                .map_or(false, |content| content.contains("shared module"))
+        })
+        .count();
+    assert_eq!(shared_module, 1);
+    assert!(shared.is_disjoint(&c));
+    for path in a.difference(&shared).chain(b.difference(&shared)) {
+        let content = fs::read_to_string(output.path().join(path)).unwrap_or_default();
+        assert!(!content.contains("shared module"), "{path}");
+    }
+    session.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn async_imports_share_the_chunk_of_a_name() -> Result<()> {
+    register();
+    let project = tempfile::tempdir()?;
+    let output = tempfile::tempdir()?;
+    let src = project.path().join("src");
+    fs::create_dir_all(src.join("icons"))?;
+    fs::write(
+        src.join("index.js"),
+        "import('./a.js');\nimport('./b.js');\n",
+    )?;
+    fs::write(
+        src.join("a.js"),
+        "import { x } from './icons/x.js';\nconsole.log('a', x);\n",
+    )?;
+    fs::write(
+        src.join("b.js"),
+        "import { y } from './icons/y.js';\nconsole.log('b', y);\n",
+    )?;
+    fs::write(src.join("icons/x.js"), "export const x = \"icon x\";\n")?;
+    fs::write(src.join("icons/y.js"), "export const y = \"icon y\";\n")?;
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut session = BuildSession::new(
+        tt,
+        project.path().to_str().unwrap().to_string(),
+        output.path().to_str().unwrap().to_string(),
+    )
+    .await?;
+    session.register_context("client", icons_context).await?;
+    let assets = session
+        .get_entrypoint_assets("client", "src/index.js")
+        .await?;
+
+    let chunks_containing = |text: &str| {
+        assets
+            .iter()
+            .filter(|asset| asset.path.ends_with(".js"))
+            .filter(|asset| {
+                asset
+                    .content
+                    .as_ref()
+                    .and_then(|content| content.to_str().ok().map(|c| c.contains(text)))
+                    .unwrap_or(false)
+            })
+            .map(|asset| asset.path.clone())
+            .collect::<Vec<_>>()
+    };
+    // a.js and b.js are loaded by different chunk groups, but their icons are
+    // placed into a single chunk that both of them load.
+    let icons = chunks_containing("icon x");
+    assert_eq!(icons.len(), 1, "{icons:?}");
+    assert_eq!(chunks_containing("icon y"), icons);
+    assert!(icons[0].contains("icons_"), "{}", icons[0]);
+    session.shutdown().await;
+    Ok(())
+}
+
+/// Builds `src/index.js` of `project` into `output` in a new turbo-tasks
+/// instance, with the code of chunk items cached in `cache`.
+async fn build_with_code_cache(
+    project: &Path,
+    output: &Path,
+    cache: &Path,
+) -> Result<Vec<EntrypointAsset>> {
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let mut session = BuildSession::new(
+        tt,
+        project.to_str().unwrap().to_string(),
+        output.to_str().unwrap().to_string(),
+    )
+    .await?;
+    let cache = cache.to_str().unwrap().to_string();
+    session
+        .register_context("client", move |project_root, output_root| {
+            let cache = DiskFileSystemVc::new("cache".to_string(), cache.clone()).root();
+            let code_cache = ChunkItemCodeCacheVc::new(cache, StringVc::cell(String::new()));
+            build_context(project_root, output_root, None, Some(code_cache))
+        })
+        .await?;
+    let assets = session
+        .get_entrypoint_assets("client", "src/index.js")
+        .await?;
+    session.shutdown().await;
+    Ok(assets)
+}
+
+/// The paths of the entries of the code cache in `cache`.
+fn code_cache_entries(cache: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    for dir in fs::read_dir(cache)? {
+        for entry in fs::read_dir(dir?.path())? {
+            entries.push(entry?.path());
+        }
+    }
+    Ok(entries)
+}
+
+/// Replaces `from` with `to` in the code cache entries in `cache` that
+/// contain `from`, and returns the number of changed entries.
+fn change_code_cache_entries(cache: &Path, from: &str, to: &str) -> Result<usize> {
+    let mut changed = 0;
+    for path in code_cache_entries(cache)? {
+        let content = fs::read_to_string(&path)?;
+        if content.contains(from) {
+            fs::write(&path, content.replace(from, to))?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Whether an asset of `assets` whose path ends with `extension` contains
+/// `text`.
+fn contains(assets: &[EntrypointAsset], extension: &str, text: &str) -> bool {
+    assets.iter().any(|asset| {
+        asset.path.ends_with(extension)
+            && asset
+                .content
+                .as_ref()
+                .and_then(|content| content.to_str().ok().map(|c| c.contains(text)))
+                .unwrap_or(false)
+    })
+}
+
+#[tokio::test]
+async fn code_cache_entries_are_read_by_later_builds() -> Result<()> {
+    register();
+    let project = tempfile::tempdir()?;
+    let cache = tempfile::tempdir()?;
+    write_project(project.path())?;
+
+    let first = tempfile::tempdir()?;
+    let assets = build_with_code_cache(project.path(), first.path(), cache.path()).await?;
+    assert!(contains(&assets, ".js", "console.log("));
+
+    // The entry of index.js has a source map, which is cached with the code.
+    let index = code_cache_entries(cache.path())?
+        .into_iter()
+        .map(fs::read_to_string)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|entry| entry.contains("console.log("))
+        .expect("index.js is cached");
+    let index: serde_json::Value = serde_json::from_str(&index)?;
+    assert!(index["source_map"].is_string());
+
+    // A build in a new instance reads the code from the cache instead of
+    // generating it.
+    assert_eq!(
+        change_code_cache_entries(cache.path(), "console.log(", "console.info(")?,
+        1
+    );
+    let second = tempfile::tempdir()?;
+    let assets = build_with_code_cache(project.path(), second.path(), cache.path()).await?;
+    assert!(contains(&assets, ".js", "console.info("));
+    assert!(!contains(&assets, ".js", "console.log("));
+    assert!(contains(&assets, ".js.map", "src/index.js"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn code_cache_entries_depend_on_what_references_resolve_to() -> Result<()> {
+    register();
+    let project = tempfile::tempdir()?;
+    let cache = tempfile::tempdir()?;
+    let src = project.path().join("src");
+    fs::create_dir(&src)?;
+    fs::write(
+        src.join("index.js"),
+        "import { a } from './a.js';\nconsole.log(a);\n",
+    )?;
+    fs::write(
+        src.join("a.js"),
+        "import { b } from './b';\nexport const a = b;\n",
+    )?;
+    fs::write(src.join("b.js"), "export const b = 1;\n")?;
+
+    let first = tempfile::tempdir()?;
+    build_with_code_cache(project.path(), first.path(), cache.path()).await?;
+    assert_eq!(
+        change_code_cache_entries(cache.path(), "const a =", "const stale_a =")?,
+        1
+    );
+
+    // a.js is unchanged, but `./b` now resolves to another module.
+    fs::remove_file(src.join("b.js"))?;
+    fs::create_dir(src.join("b"))?;
+    fs::write(src.join("b/index.js"), "export const b = 2;\n")?;
+    let second = tempfile::tempdir()?;
+    let assets = build_with_code_cache(project.path(), second.path(), cache.path()).await?;
+    assert!(!contains(&assets, ".js", "stale_a"));
+    assert!(contains(&assets, ".js", "const a ="));
+    Ok(())
+}
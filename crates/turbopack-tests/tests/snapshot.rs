@@ -37,7 +37,7 @@ use turbopack_core::{
     compile_time_info::CompileTimeInfo,
     context::{AssetContext, AssetContextVc},
     environment::{BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment},
-    issue::IssueVc,
+    issue::{budget::IssueBudget, suppression::IssueSuppressionsVc, IssueVc},
     reference::all_referenced_assets,
     reference_type::{EntryReferenceSubType, ReferenceType},
     source_asset::SourceAssetVc,
@@ -109,10 +109,14 @@ async fn run(resource: PathBuf) -> Result<()> {
     let tt = TurboTasks::new(MemoryBackend::default());
     let task = tt.spawn_once_task(async move {
         let out = run_test(resource.to_str().unwrap());
-        let captured_issues = IssueVc::peek_issues_with_path(out)
-            .await?
-            .strongly_consistent()
-            .await?;
+        let captured_issues = IssueVc::peek_issues_with_path(
+            out,
+            IssueBudget::unlimited(),
+            IssueSuppressionsVc::empty(),
+        )
+        .await?
+        .strongly_consistent()
+        .await?;
 
         let plain_issues = captured_issues
             .iter_with_shortest_path()
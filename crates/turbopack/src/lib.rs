@@ -33,6 +33,7 @@ use turbopack_core::{
     asset::{Asset, AssetVc},
     compile_time_info::CompileTimeInfoVc,
     context::{AssetContext, AssetContextVc},
+    environment::EnvironmentVc,
     ident::AssetIdentVc,
     issue::{Issue, IssueVc},
     plugin::CustomModuleType,
@@ -257,6 +258,30 @@ impl ModuleAssetContextVc {
         ))
     }
 
+    /// A context that processes modules for `environment` instead, e.g. for
+    /// the legacy build of a [DualChunkGroup]. Only the compile time info and
+    /// the preset-env transform change, so requests are resolved once for
+    /// both contexts.
+    ///
+    /// [DualChunkGroup]: turbopack_core::chunk::dual_output::DualChunkGroup
+    #[turbo_tasks::function]
+    pub async fn with_environment(
+        self,
+        environment: EnvironmentVc,
+    ) -> Result<ModuleAssetContextVc> {
+        let this = self.await?;
+        let mut module_options_context = this.module_options_context.await?.clone_value();
+        module_options_context.preset_env_versions = Some(environment);
+        Ok(ModuleAssetContext {
+            transitions: this.transitions,
+            compile_time_info: this.compile_time_info.with_environment(environment),
+            module_options_context: module_options_context.cell(),
+            resolve_options_context: this.resolve_options_context,
+            transition: this.transition,
+        }
+        .cell())
+    }
+
     #[turbo_tasks::function]
     async fn process_default(
         self_vc: ModuleAssetContextVc,
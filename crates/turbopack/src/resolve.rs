@@ -2,13 +2,17 @@ use std::collections::BTreeMap;
 
 use anyhow::Result;
 use turbo_tasks_fs::{FileSystem, FileSystemPathVc};
-use turbopack_core::resolve::{
-    find_context_file,
-    options::{
-        ConditionValue, ImportMap, ImportMapping, ResolveInPackage, ResolveIntoPackage,
-        ResolveModules, ResolveOptions, ResolveOptionsVc,
+use turbopack_core::{
+    resolve::{
+        find_context_file,
+        native_addon::NativeAddonExternalsPluginVc,
+        options::{
+            ConditionValue, ImportMap, ImportMapping, ResolveInPackage, ResolveIntoPackage,
+            ResolveModules, ResolveOptions, ResolveOptionsVc,
+        },
+        AliasMap, AliasPattern, FindContextFileResult,
     },
-    AliasMap, AliasPattern, FindContextFileResult,
+    target::CompileTargetVc,
 };
 use turbopack_ecmascript::typescript::resolve::{
     apply_tsconfig_resolve_options, tsconfig, tsconfig_resolve_options,
@@ -109,6 +113,16 @@ async fn base_resolve_options(
     }
     let import_map = import_map.cell();
 
+    let mut plugins = opt.plugins.clone();
+    if opt.enable_native_addon_externals {
+        let compile_target = if let Some(environment) = emulating {
+            environment.compile_target()
+        } else {
+            CompileTargetVc::current()
+        };
+        plugins.push(NativeAddonExternalsPluginVc::new(root, compile_target).into());
+    }
+
     Ok(ResolveOptions {
         extensions: if let Some(environment) = emulating {
             environment.resolve_extensions().await?.clone_value()
@@ -220,7 +234,8 @@ async fn base_resolve_options(
         },
         import_map: Some(import_map),
         resolved_map: opt.resolved_map,
-        plugins: opt.plugins.clone(),
+        plugins,
+        collect_candidates: opt.enable_resolve_candidates,
         ..Default::default()
     }
     .into())
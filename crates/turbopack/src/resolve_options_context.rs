@@ -24,6 +24,11 @@ pub struct ResolveOptionsContext {
     #[serde(default)]
     pub enable_node_native_modules: bool,
     #[serde(default)]
+    /// Mark `.node` binaries and packages with napi prebuilds as externals
+    /// for the compile target of the emulated environment, and report
+    /// prebuilds that are missing for it.
+    pub enable_native_addon_externals: bool,
+    #[serde(default)]
     /// Enable resolving of the node_modules folder when within the provided
     /// directory
     pub enable_node_modules: Option<FileSystemPathVc>,
@@ -65,6 +70,10 @@ pub struct ResolveOptionsContext {
     /// resolving.
     pub plugins: Vec<ResolvePluginVc>,
     #[serde(default)]
+    /// Suggests near misses, e.g. files with a similar name, for requests
+    /// that can't be resolved.
+    pub enable_resolve_candidates: bool,
+    #[serde(default)]
     pub placeholder_for_future_extensions: (),
 }
 
@@ -10,16 +10,19 @@ use std::{
     fmt,
     fs::{self, Metadata},
     io::{self, Write},
-    path::{Components, Path, PathBuf},
+    path::{Component, Components, Path, PathBuf},
 };
 
-use serde::Serialize;
+use path_slash::PathBufExt;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
-    AnchoredSystemPathBuf, IntoSystem, PathError, PathValidationError, RelativeSystemPathBuf,
+    debug_assert_system_path, deserialize_system, serialize_unix, AnchoredSystemPath,
+    AnchoredSystemPathBuf, IntoSystem, PathDisplay, PathError, PathValidationError,
+    RelativeSystemPathBuf, RelativeUnixPath, RelativeUnixPathBuf,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct AbsoluteSystemPathBuf(PathBuf);
 
 impl AbsoluteSystemPathBuf {
@@ -61,6 +64,38 @@ impl AbsoluteSystemPathBuf {
         Ok(AbsoluteSystemPathBuf(system_path))
     }
 
+    /// Creates an AbsoluteSystemPathBuf from user input, e.g. a path from a
+    /// config file, repairing common variations instead of rejecting them:
+    ///
+    /// * Surrounding whitespace and trailing separators are removed.
+    /// * Repeated separators are collapsed.
+    /// * On Windows, `/` separators are converted to `\`, and a drive-relative
+    ///   path like `C:folder` is treated as `C:\folder`.
+    ///
+    /// Input that can't be repaired fails with a specific error, e.g.
+    /// [PathValidationError::MissingDrive] for `\folder` on Windows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use turbopath::AbsoluteSystemPathBuf;
+    /// #[cfg(not(windows))]
+    /// {
+    ///   let path = AbsoluteSystemPathBuf::from_unknown(" /Users//user/ ").unwrap();
+    ///   assert_eq!(path.as_path(), Path::new("/Users/user"));
+    /// }
+    /// #[cfg(windows)]
+    /// {
+    ///   let path = AbsoluteSystemPathBuf::from_unknown("C:Users/user/").unwrap();
+    ///   assert_eq!(path.as_path(), Path::new("C:\\Users\\user"));
+    /// }
+    /// ```
+    pub fn from_unknown(input: &str) -> Result<Self, PathValidationError> {
+        let repaired = repair_path(input, cfg!(windows))?;
+        Self::new(repaired)
+    }
+
     /// Anchors `path` at `self`.
     ///
     /// # Arguments
@@ -97,6 +132,108 @@ impl AbsoluteSystemPathBuf {
         AnchoredSystemPathBuf::new(self, path)
     }
 
+    /// Anchors `self` at `root`, like [AbsoluteSystemPathBuf::anchor] with
+    /// the roles swapped, which reads better when one path is anchored at
+    /// several roots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use turbopath::AbsoluteSystemPathBuf;
+    /// #[cfg(not(windows))]
+    /// {
+    ///   let root = AbsoluteSystemPathBuf::new("/Users/user").unwrap();
+    ///   let path = AbsoluteSystemPathBuf::new("/Users/user/Documents").unwrap();
+    ///   assert_eq!(path.anchor_at(&root).unwrap().as_path(), Path::new("Documents"));
+    /// }
+    /// #[cfg(windows)]
+    /// {
+    ///   let root = AbsoluteSystemPathBuf::new("C:\\Users\\user").unwrap();
+    ///   let path = AbsoluteSystemPathBuf::new("C:\\Users\\user\\Documents").unwrap();
+    ///   assert_eq!(path.anchor_at(&root).unwrap().as_path(), Path::new("Documents"));
+    /// }
+    /// ```
+    pub fn anchor_at(
+        &self,
+        root: &AbsoluteSystemPathBuf,
+    ) -> Result<AnchoredSystemPathBuf, PathValidationError> {
+        root.anchor(self)
+    }
+
+    /// The path from `base` to `self` with `/` separators. Unlike
+    /// [AbsoluteSystemPathBuf::anchor_at], `self` doesn't need to be below
+    /// `base`, and the path starts with a `..` for each segment of `base`
+    /// that isn't shared, e.g. for the `sources` of source maps or for
+    /// import specifiers between generated files. Both paths are
+    /// [cleaned](Self::clean) and compared like
+    /// [AbsoluteSystemPathBuf::eq_normalized]. Fails for paths without a
+    /// common root, e.g. on different drives on Windows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use turbopath::AbsoluteSystemPathBuf;
+    /// #[cfg(not(windows))]
+    /// {
+    ///   let base = AbsoluteSystemPathBuf::new("/repo/dist/chunks").unwrap();
+    ///   let path = AbsoluteSystemPathBuf::new("/repo/src/index.js").unwrap();
+    ///   assert_eq!(path.relative_to(&base).unwrap().as_path(), Path::new("../../src/index.js"));
+    /// }
+    /// #[cfg(windows)]
+    /// {
+    ///   let base = AbsoluteSystemPathBuf::new("C:\\repo\\dist\\chunks").unwrap();
+    ///   let path = AbsoluteSystemPathBuf::new("C:\\repo\\src\\index.js").unwrap();
+    ///   assert_eq!(path.relative_to(&base).unwrap().as_path(), Path::new("../../src/index.js"));
+    /// }
+    /// ```
+    pub fn relative_to(
+        &self,
+        base: &AbsoluteSystemPathBuf,
+    ) -> Result<RelativeUnixPathBuf, PathValidationError> {
+        let path = self.clean().normalize();
+        let base = base.clean().normalize();
+        let same = |a: Component, b: Component| {
+            if cfg!(windows) {
+                a.as_os_str().eq_ignore_ascii_case(b.as_os_str())
+            } else {
+                a == b
+            }
+        };
+
+        let mut components = path.0.components().peekable();
+        let mut base_components = base.0.components().peekable();
+        let mut shared = 0;
+        while let (Some(component), Some(base_component)) =
+            (components.peek(), base_components.peek())
+        {
+            if !same(*component, *base_component) {
+                break;
+            }
+            components.next();
+            base_components.next();
+            shared += 1;
+        }
+        if shared == 0 {
+            return Err(PathValidationError::NoCommonRoot(
+                self.to_string_lossy().to_string(),
+                base.to_string_lossy().to_string(),
+            ));
+        }
+
+        let mut segments = base_components.map(|_| "..").collect::<Vec<_>>();
+        for component in components {
+            segments.push(
+                component
+                    .as_os_str()
+                    .to_str()
+                    .ok_or_else(|| PathValidationError::InvalidUnicode(self.0.clone()))?,
+            );
+        }
+        Ok(RelativeUnixPath::new_unchecked(Path::new(&segments.join("/"))).to_owned())
+    }
+
     /// Resolves `path` with `self` as anchor.
     ///
     /// # Arguments
@@ -115,7 +252,7 @@ impl AbsoluteSystemPathBuf {
     /// #[cfg(windows)]
     /// let absolute_path = AbsoluteSystemPathBuf::new("C:\\Users\\user").unwrap();
     ///
-    /// let anchored_path = Path::new("Documents").try_into().unwrap();
+    /// let anchored_path: AnchoredSystemPathBuf = Path::new("Documents").try_into().unwrap();
     /// let resolved_path = absolute_path.resolve(&anchored_path);
     ///
     /// #[cfg(not(windows))]
@@ -123,10 +260,98 @@ impl AbsoluteSystemPathBuf {
     /// #[cfg(windows)]
     /// assert_eq!(resolved_path.as_path(), Path::new("C:\\Users\\user\\Documents"));
     /// ```
-    pub fn resolve(&self, path: &AnchoredSystemPathBuf) -> AbsoluteSystemPathBuf {
+    pub fn resolve(&self, path: &AnchoredSystemPath) -> AbsoluteSystemPathBuf {
         AbsoluteSystemPathBuf(self.0.join(path.as_path()))
     }
 
+    /// Like [resolve](Self::resolve), for a path with `/` separators, e.g. a
+    /// path that git reported relative to the root of a repository.
+    pub fn resolve_unix(&self, path: &RelativeUnixPath) -> AbsoluteSystemPathBuf {
+        AbsoluteSystemPathBuf(self.0.join(PathBuf::from_slash(path.as_str())))
+    }
+
+    /// The path with `.` segments removed, and each `..` removed together with
+    /// the segment before it, without looking at the file system. A `..` at
+    /// the root stays at the root.
+    pub fn clean(&self) -> Self {
+        let mut cleaned = PathBuf::new();
+        for component in self.0.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    // Popping never removes the root.
+                    cleaned.pop();
+                }
+                component => cleaned.push(component),
+            }
+        }
+        AbsoluteSystemPathBuf(cleaned)
+    }
+
+    /// The path without a verbatim `\\?\` prefix, which e.g.
+    /// [fs::canonicalize] adds on Windows, and with an uppercase drive letter.
+    /// Verbatim paths without a drive, like `\\?\UNC\server\share`, become
+    /// regular UNC paths, and other verbatim paths, e.g. of volumes, keep
+    /// their prefix. Paths on other platforms are returned unchanged.
+    pub fn normalize(&self) -> Self {
+        match self.0.to_str() {
+            Some(path) if cfg!(windows) => {
+                AbsoluteSystemPathBuf(PathBuf::from(normalize_windows_path(path)))
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// The path with a verbatim `\\?\` prefix on Windows, which lifts the
+    /// length limit of paths. Windows doesn't resolve `.` and `..` segments of
+    /// verbatim paths, so the path is cleaned first, see
+    /// [AbsoluteSystemPathBuf::clean]. Paths on other platforms are returned
+    /// unchanged.
+    pub fn to_verbatim(&self) -> Self {
+        let cleaned = self.clean().normalize();
+        match cleaned.0.to_str() {
+            Some(path) if cfg!(windows) => {
+                AbsoluteSystemPathBuf(PathBuf::from(to_verbatim_windows_path(path)))
+            }
+            _ => cleaned,
+        }
+    }
+
+    /// Whether `self` and `other` are the same path once both are normalized,
+    /// see [AbsoluteSystemPathBuf::normalize]. Paths are compared case
+    /// insensitively on Windows.
+    pub fn eq_normalized(&self, other: &AbsoluteSystemPathBuf) -> bool {
+        self.strip_prefix_normalized(other)
+            .map_or(false, |rest| rest.as_os_str().is_empty())
+    }
+
+    /// Like [AbsoluteSystemPathBuf::starts_with], but with both paths
+    /// normalized and compared like [AbsoluteSystemPathBuf::eq_normalized].
+    pub fn starts_with_normalized(&self, base: &AbsoluteSystemPathBuf) -> bool {
+        self.strip_prefix_normalized(base).is_some()
+    }
+
+    /// The rest of the normalized path after the normalized `base`, or `None`
+    /// if `base` isn't a prefix of the path.
+    pub(crate) fn strip_prefix_normalized(&self, base: &AbsoluteSystemPathBuf) -> Option<PathBuf> {
+        let path = self.normalize();
+        let base = base.normalize();
+        if !cfg!(windows) {
+            return path.0.strip_prefix(&base.0).ok().map(Path::to_path_buf);
+        }
+        let mut components = path.0.components();
+        for base_component in base.0.components() {
+            let component = components.next()?;
+            if !component
+                .as_os_str()
+                .eq_ignore_ascii_case(base_component.as_os_str())
+            {
+                return None;
+            }
+        }
+        Some(components.as_path().to_path_buf())
+    }
+
     pub fn as_path(&self) -> &Path {
         self.0.as_path()
     }
@@ -210,6 +435,12 @@ impl AbsoluteSystemPathBuf {
             .ok_or_else(|| PathValidationError::InvalidUnicode(self.0.clone()))
     }
 
+    /// Displays the path with `/` separators, optionally relative to a root
+    /// and with the home directory redacted, see [PathDisplay].
+    pub fn display_normalized(&self) -> PathDisplay<'_> {
+        PathDisplay::new(self)
+    }
+
     pub fn to_string_lossy(&self) -> Cow<'_, str> {
         self.0.to_string_lossy()
     }
@@ -227,9 +458,40 @@ impl AbsoluteSystemPathBuf {
     }
 
     pub fn metadata(&self) -> Result<Metadata, PathError> {
+        self.symlink_metadata()
+    }
+
+    /// Returns the metadata of the path itself, i.e. of the symlink instead of
+    /// its target (`lstat`).
+    pub fn symlink_metadata(&self) -> Result<Metadata, PathError> {
         Ok(fs::symlink_metadata(&self.0)?)
     }
 
+    /// Returns the target of the symlink at this path as it's stored in the
+    /// link, which may be relative to the directory of the link.
+    pub fn read_link(&self) -> Result<PathBuf, PathError> {
+        Ok(fs::read_link(&self.0)?)
+    }
+
+    /// Whether the path is a symlink, without following it. A path that
+    /// doesn't exist isn't a symlink.
+    pub fn is_symlink(&self) -> Result<bool, PathError> {
+        match self.symlink_metadata() {
+            Ok(metadata) => Ok(metadata.file_type().is_symlink()),
+            Err(err) if err.is_io_error(io::ErrorKind::NotFound) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Resolves all symlinks and `.` and `..` components of the path, which
+    /// must exist. Unlike [fs::canonicalize], the result is
+    /// [normalized](Self::normalize), so it doesn't have a verbatim prefix
+    /// on Windows and compares equal to paths that weren't canonicalized.
+    pub fn realpath(&self) -> Result<Self, PathError> {
+        let real = fs::canonicalize(&self.0)?;
+        Ok(AbsoluteSystemPathBuf::new(real)?.normalize())
+    }
+
     // note that this is *not* lstat. If this is a symlink, it
     // will return metadata for the target.
     pub fn stat(&self) -> Result<Metadata, PathError> {
@@ -239,6 +501,91 @@ impl AbsoluteSystemPathBuf {
     pub fn open(&self) -> Result<fs::File, PathError> {
         Ok(fs::File::open(&self.0)?)
     }
+
+    /// Checks the invariants of this type in debug builds: the path is
+    /// absolute, valid unicode and uses system separators.
+    pub fn debug_assert_valid(&self) {
+        debug_assert!(self.0.is_absolute(), "{:?} is not absolute", self.0);
+        debug_assert_system_path(&self.0);
+    }
+}
+
+/// Repairs `input` as described in [AbsoluteSystemPathBuf::from_unknown]. The
+/// platform is a parameter so both variants can be tested everywhere.
+fn repair_path(input: &str, windows: bool) -> Result<String, PathValidationError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(PathValidationError::Empty);
+    }
+    let separator = if windows { '\\' } else { '/' };
+    let is_separator = |c: char| c == '/' || (windows && c == '\\');
+
+    let (prefix, rest) = if windows {
+        let bytes = input.as_bytes();
+        if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            // `C:folder` is relative to the current directory of drive C, which
+            // is almost never what a config file means.
+            (format!("{}:", input[..1].to_ascii_uppercase()), &input[2..])
+        } else if input.starts_with(is_separator) && input[1..].starts_with(is_separator) {
+            // A UNC path like `\\server\share`.
+            (separator.to_string(), &input[1..])
+        } else if input.starts_with(is_separator) {
+            return Err(PathValidationError::MissingDrive(PathBuf::from(input)));
+        } else {
+            return Err(PathValidationError::NotAbsolute(PathBuf::from(input)));
+        }
+    } else if input.starts_with('/') {
+        (String::new(), input)
+    } else {
+        return Err(PathValidationError::NotAbsolute(PathBuf::from(input)));
+    };
+
+    let segments = rest
+        .split(is_separator)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let mut repaired = prefix;
+    repaired.push(separator);
+    repaired.push_str(&segments.join(&separator.to_string()));
+    Ok(repaired)
+}
+
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Whether a Windows path starts with a drive letter, like `C:`.
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// Normalizes a Windows path as described in
+/// [AbsoluteSystemPathBuf::normalize].
+fn normalize_windows_path(path: &str) -> String {
+    let mut normalized = if let Some(rest) = path.strip_prefix(VERBATIM_UNC_PREFIX) {
+        format!(r"\\{rest}")
+    } else {
+        match path.strip_prefix(VERBATIM_PREFIX) {
+            Some(rest) if has_drive(rest) => rest.to_string(),
+            _ => path.to_string(),
+        }
+    };
+    if has_drive(&normalized) {
+        normalized[..1].make_ascii_uppercase();
+    }
+    normalized
+}
+
+/// Adds a verbatim prefix to a normalized Windows path. Device paths like
+/// `\\.\pipe` have no verbatim form and are returned unchanged.
+fn to_verbatim_windows_path(path: &str) -> String {
+    if path.starts_with(VERBATIM_PREFIX) || path.starts_with(r"\\.\") {
+        path.to_string()
+    } else if let Some(rest) = path.strip_prefix(r"\\") {
+        format!("{VERBATIM_UNC_PREFIX}{rest}")
+    } else {
+        format!("{VERBATIM_PREFIX}{path}")
+    }
 }
 
 impl From<AbsoluteSystemPathBuf> for PathBuf {
@@ -259,11 +606,73 @@ impl AsRef<Path> for AbsoluteSystemPathBuf {
     }
 }
 
+impl Serialize for AbsoluteSystemPathBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_unix(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AbsoluteSystemPathBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::new(deserialize_system(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::{assert_matches::assert_matches, io, path::Path};
+
+    use super::{normalize_windows_path, repair_path, to_verbatim_windows_path};
+    use crate::{AbsoluteSystemPathBuf, PathError, PathValidationError, RelativeUnixPath};
+
+    #[test]
+    fn test_repair_path() {
+        assert_eq!(repair_path(" /a//b/ ", false).unwrap(), "/a/b");
+        assert_eq!(repair_path("/", false).unwrap(), "/");
+        assert_matches!(
+            repair_path("a/b", false),
+            Err(PathValidationError::NotAbsolute(_))
+        );
+        assert_matches!(repair_path("  ", false), Err(PathValidationError::Empty));
+
+        assert_eq!(repair_path("C:/a/b/", true).unwrap(), "C:\\a\\b");
+        assert_eq!(repair_path("c:a\\/b", true).unwrap(), "C:\\a\\b");
+        assert_eq!(repair_path("C:", true).unwrap(), "C:\\");
+        assert_eq!(
+            repair_path("//server/share/dir", true).unwrap(),
+            "\\\\server\\share\\dir"
+        );
+        assert_matches!(
+            repair_path("/a/b", true),
+            Err(PathValidationError::MissingDrive(_))
+        );
+        assert_matches!(
+            repair_path("a/b", true),
+            Err(PathValidationError::NotAbsolute(_))
+        );
+    }
+
+    #[test]
+    fn test_windows_path_prefixes() {
+        assert_eq!(normalize_windows_path(r"\\?\c:\repo"), r"C:\repo");
+        assert_eq!(normalize_windows_path(r"d:\repo"), r"D:\repo");
+        assert_eq!(
+            normalize_windows_path(r"\\?\UNC\server\share\repo"),
+            r"\\server\share\repo"
+        );
+        assert_eq!(
+            normalize_windows_path(r"\\?\Volume{1234}\repo"),
+            r"\\?\Volume{1234}\repo"
+        );
 
-    use crate::{AbsoluteSystemPathBuf, PathValidationError};
+        assert_eq!(to_verbatim_windows_path(r"C:\repo"), r"\\?\C:\repo");
+        assert_eq!(
+            to_verbatim_windows_path(r"\\server\share\repo"),
+            r"\\?\UNC\server\share\repo"
+        );
+        assert_eq!(to_verbatim_windows_path(r"\\?\C:\repo"), r"\\?\C:\repo");
+        assert_eq!(to_verbatim_windows_path(r"\\.\pipe\a"), r"\\.\pipe\a");
+    }
 
     #[cfg(not(windows))]
     #[test]
@@ -280,6 +689,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_serde() {
+        #[cfg(not(windows))]
+        let (path, json) = ("/repo/packages/a", r#""/repo/packages/a""#);
+        #[cfg(windows)]
+        let (path, json) = ("C:\\repo\\packages\\a", r#""C:/repo/packages/a""#);
+        let path = AbsoluteSystemPathBuf::new(path).unwrap();
+        assert_eq!(serde_json::to_string(&path).unwrap(), json);
+        assert_eq!(
+            serde_json::from_str::<AbsoluteSystemPathBuf>(json).unwrap(),
+            path
+        );
+        assert_matches!(
+            serde_json::from_str::<AbsoluteSystemPathBuf>(r#""repo/packages/a""#),
+            Err(_)
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_anchor_at_on_unix() {
+        let anchor_at = |path: &str, root: &str| {
+            AbsoluteSystemPathBuf::new(path)
+                .unwrap()
+                .anchor_at(&AbsoluteSystemPathBuf::new(root).unwrap())
+        };
+        assert_eq!(
+            anchor_at("/repo/packages/a", "/repo").unwrap().as_path(),
+            Path::new("packages/a")
+        );
+        assert_eq!(
+            anchor_at("/repo", "/repo").unwrap().as_path(),
+            Path::new("")
+        );
+        assert_eq!(
+            anchor_at("/repo/a", "/").unwrap().as_path(),
+            Path::new("repo/a")
+        );
+        assert_matches!(
+            anchor_at("/repo", "/repo/packages"),
+            Err(PathValidationError::NotParent(..))
+        );
+        // Segments are compared as a whole.
+        assert_matches!(
+            anchor_at("/repository", "/repo"),
+            Err(PathValidationError::NotParent(..))
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_relative_to_on_unix() {
+        let relative_to = |path: &str, base: &str| {
+            AbsoluteSystemPathBuf::new(path)
+                .unwrap()
+                .relative_to(&AbsoluteSystemPathBuf::new(base).unwrap())
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(relative_to("/repo/src/index.js", "/repo"), "src/index.js");
+        assert_eq!(
+            relative_to("/repo/src/index.js", "/repo/dist"),
+            "../src/index.js"
+        );
+        assert_eq!(
+            relative_to("/repo/src/index.js", "/repo/dist/chunks"),
+            "../../src/index.js"
+        );
+        assert_eq!(relative_to("/repo", "/repo/dist/chunks"), "../..");
+        assert_eq!(relative_to("/repo", "/repo"), "");
+        assert_eq!(relative_to("/repo/a", "/"), "repo/a");
+        // Segments are compared as a whole, and `..` is resolved first.
+        assert_eq!(relative_to("/repository/a", "/repo"), "../repository/a");
+        assert_eq!(relative_to("/repo/dist/../src", "/repo/./dist"), "../src");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_clean_on_unix() {
+        let clean = |path| AbsoluteSystemPathBuf::new(path).unwrap().clean();
+        assert_eq!(
+            clean("/repo/./packages/a/../b").as_path(),
+            Path::new("/repo/packages/b")
+        );
+        assert_eq!(clean("/repo/../..").as_path(), Path::new("/"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_normalized_comparison_on_unix() {
+        let path = |path| AbsoluteSystemPathBuf::new(path).unwrap();
+        assert_eq!(path("/repo").normalize(), path("/repo"));
+        assert_eq!(path("/repo").to_verbatim(), path("/repo"));
+        assert!(path("/repo").eq_normalized(&path("/repo")));
+        assert!(!path("/repo").eq_normalized(&path("/Repo")));
+        assert!(path("/repo/a").starts_with_normalized(&path("/repo")));
+        assert!(!path("/repo").starts_with_normalized(&path("/repo/a")));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_symlink_helpers() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dir.path())
+            .unwrap()
+            .realpath()
+            .unwrap();
+        let file = root.join_literal("file.txt");
+        file.create_with_contents("contents").unwrap();
+        let link = root.join_literal("link.txt");
+        link.symlink_to_file("file.txt").unwrap();
+        let missing = root.join_literal("missing.txt");
+
+        assert!(link.is_symlink().unwrap());
+        assert!(!file.is_symlink().unwrap());
+        assert!(!missing.is_symlink().unwrap());
+        assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        assert_eq!(link.read_link().unwrap(), Path::new("file.txt"));
+        assert_matches!(file.read_link(), Err(PathError::IO(_)));
+        assert_eq!(link.realpath().unwrap(), file);
+        let unix = RelativeUnixPath::new(Path::new("./file.txt")).unwrap();
+        assert_eq!(root.resolve_unix(unix).realpath().unwrap(), file);
+        assert_matches!(
+            missing.realpath(),
+            Err(err) if err.is_io_error(io::ErrorKind::NotFound)
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_relative_to_on_windows() {
+        let path = |path| AbsoluteSystemPathBuf::new(path).unwrap();
+        assert_eq!(
+            path(r"C:\repo\src\index.js")
+                .relative_to(&path(r"\\?\c:\Repo\dist"))
+                .unwrap()
+                .as_path(),
+            Path::new("../src/index.js")
+        );
+        assert_matches!(
+            path(r"D:\repo").relative_to(&path(r"C:\repo")),
+            Err(PathValidationError::NoCommonRoot(..))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_normalized_comparison_on_windows() {
+        let path = |path| AbsoluteSystemPathBuf::new(path).unwrap();
+        assert_eq!(path(r"\\?\c:\repo").normalize(), path(r"C:\repo"));
+        assert_eq!(path(r"c:\repo\.\a").to_verbatim(), path(r"\\?\C:\repo\a"));
+        assert!(path(r"\\?\C:\Repo").eq_normalized(&path(r"c:\repo")));
+        assert!(path(r"C:\repo\a").starts_with_normalized(&path(r"\\?\c:\REPO")));
+        assert_eq!(
+            path(r"\\?\C:\repo")
+                .anchor(&path(r"c:\Repo\a"))
+                .unwrap()
+                .as_path(),
+            Path::new("a")
+        );
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_absolute_system_path_buf_on_windows() {
@@ -0,0 +1,238 @@
+use std::path::{Component, Path, PathBuf};
+
+use path_slash::PathExt;
+use serde::{Serialize, Serializer};
+
+use crate::{
+    debug_assert_system_path, serialize_unix, AbsoluteSystemPathBuf, AnchoredSystemPathBuf,
+    PathValidationError, RelativeUnixPath, RelativeUnixPathBuf,
+};
+
+/// A borrowed [AnchoredSystemPathBuf](crate::AnchoredSystemPathBuf), like
+/// [Path] is to [PathBuf](std::path::PathBuf).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct AnchoredSystemPath(Path);
+
+impl AnchoredSystemPath {
+    /// Borrows `path` as an anchored path, without converting it. Fails if
+    /// `path` is absolute or isn't valid unicode.
+    pub fn new(path: &Path) -> Result<&Self, PathValidationError> {
+        if path.has_root() {
+            return Err(PathValidationError::NotRelative(path.to_path_buf()));
+        }
+        path.to_str()
+            .ok_or_else(|| PathValidationError::InvalidUnicode(path.to_path_buf()))?;
+        Ok(Self::new_unchecked(path))
+    }
+
+    /// Borrows `path`, which must be relative and use system separators.
+    pub(crate) fn new_unchecked(path: &Path) -> &Self {
+        // SAFETY: `AnchoredSystemPath` is a `repr(transparent)` wrapper around
+        // `Path`, so both have the same layout.
+        unsafe { &*(path as *const Path as *const AnchoredSystemPath) }
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn to_str(&self) -> Result<&str, PathValidationError> {
+        self.0
+            .to_str()
+            .ok_or_else(|| PathValidationError::InvalidUnicode(self.0.to_path_buf()))
+    }
+
+    /// The path with `/` separators, e.g. to compare it with the paths that
+    /// git reports.
+    pub fn to_unix(&self) -> RelativeUnixPathBuf {
+        // Anchored paths are valid unicode, so nothing is lost.
+        RelativeUnixPath::new_unchecked(Path::new(self.0.to_slash_lossy().as_ref())).to_owned()
+    }
+
+    /// Anchors the path, which is anchored at `from`, at `to` instead, e.g. to
+    /// turn a path in the repository into a path in a package. Fails if `to`
+    /// doesn't contain the path.
+    pub fn reanchor(
+        &self,
+        from: &AbsoluteSystemPathBuf,
+        to: &AbsoluteSystemPathBuf,
+    ) -> Result<AnchoredSystemPathBuf, PathValidationError> {
+        from.resolve(self).anchor_at(to)
+    }
+
+    /// The path and the directories that contain it, from the path itself up
+    /// to the empty path, which is the anchor.
+    pub fn ancestors(&self) -> impl Iterator<Item = &AnchoredSystemPath> {
+        self.0.ancestors().map(Self::new_unchecked)
+    }
+
+    /// The nearest of [ancestors](Self::ancestors) that matches `predicate`,
+    /// e.g. the package that contains a file, or the nearest directory with a
+    /// `package.json`.
+    pub fn nearest_matching(
+        &self,
+        mut predicate: impl FnMut(&AnchoredSystemPath) -> bool,
+    ) -> Option<&AnchoredSystemPath> {
+        self.ancestors().find(|ancestor| predicate(ancestor))
+    }
+
+    /// The path with `.` segments removed, and each `..` removed together with
+    /// the segment before it, without looking at the file system. Fails with
+    /// [PathValidationError::EscapesAnchor] if a `..` has no segment before
+    /// it, i.e. if the path leaves the anchor.
+    pub fn clean(&self) -> Result<AnchoredSystemPathBuf, PathValidationError> {
+        let mut cleaned = PathBuf::new();
+        for component in self.0.components() {
+            match component {
+                Component::Normal(segment) => cleaned.push(segment),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !cleaned.pop() {
+                        return Err(PathValidationError::EscapesAnchor(self.0.to_path_buf()));
+                    }
+                }
+                // e.g. `C:dir` on Windows, which isn't anchored.
+                Component::Prefix(_) | Component::RootDir => {
+                    return Err(PathValidationError::NotRelative(self.0.to_path_buf()));
+                }
+            }
+        }
+        Ok(Self::new_unchecked(&cleaned).to_owned())
+    }
+
+    /// Checks the invariants of this type in debug builds: the path is
+    /// relative, valid unicode and uses system separators.
+    pub fn debug_assert_valid(&self) {
+        debug_assert!(!self.0.has_root(), "{:?} is not relative", &self.0);
+        debug_assert_system_path(&self.0);
+    }
+}
+
+impl Serialize for AnchoredSystemPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_unix(&self.0, serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{assert_matches::assert_matches, collections::HashSet, path::Path};
+
+    use super::AnchoredSystemPath;
+    use crate::{
+        AbsoluteSystemPathBuf, AnchoredSystemPathBuf, PathValidationError, RelativeUnixPathBuf,
+    };
+
+    fn anchored(path: &str) -> &AnchoredSystemPath {
+        AnchoredSystemPath::new(Path::new(path)).unwrap()
+    }
+
+    #[test]
+    fn test_ancestors() {
+        assert_eq!(
+            anchored("packages/a/src/index.js")
+                .ancestors()
+                .collect::<Vec<_>>(),
+            vec![
+                anchored("packages/a/src/index.js"),
+                anchored("packages/a/src"),
+                anchored("packages/a"),
+                anchored("packages"),
+                anchored(""),
+            ]
+        );
+        assert_eq!(
+            anchored("").ancestors().collect::<Vec<_>>(),
+            vec![anchored("")]
+        );
+    }
+
+    #[test]
+    fn test_nearest_matching() {
+        let packages = ["packages/a", "packages/a/nested", ""]
+            .map(|path| AnchoredSystemPathBuf::try_from(Path::new(path)).unwrap());
+        let packages = packages.iter().map(|path| &**path).collect::<HashSet<_>>();
+        let file = anchored("packages/a/src/index.js");
+        assert_eq!(
+            file.nearest_matching(|dir| packages.contains(dir)),
+            Some(anchored("packages/a"))
+        );
+        assert_eq!(
+            anchored("packages/ab/index.js").nearest_matching(|dir| packages.contains(dir)),
+            Some(anchored(""))
+        );
+        assert_eq!(
+            file.nearest_matching(|dir| dir.as_path().ends_with("b")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_to_unix() {
+        let unix = |path: &str| RelativeUnixPathBuf::new(path).unwrap();
+        let system = |path: &str| path.replace('/', &std::path::MAIN_SEPARATOR.to_string());
+        assert_eq!(
+            anchored(&system("packages/a/src/index.js")).to_unix(),
+            unix("packages/a/src/index.js")
+        );
+        assert_eq!(anchored("package.json").to_unix(), unix("package.json"));
+        assert_eq!(anchored("").to_unix(), unix(""));
+    }
+
+    #[test]
+    fn test_reanchor() {
+        #[cfg(not(windows))]
+        let (repo, package) = ("/repo", "/repo/packages/a");
+        #[cfg(windows)]
+        let (repo, package) = ("C:\\repo", "C:\\repo\\packages\\a");
+        let repo = AbsoluteSystemPathBuf::new(repo).unwrap();
+        let package = AbsoluteSystemPathBuf::new(package).unwrap();
+        let file = package.join_literal("index.js").anchor_at(&repo).unwrap();
+
+        let in_package = file.reanchor(&repo, &package).unwrap();
+        assert_eq!(in_package.as_path(), Path::new("index.js"));
+        assert_eq!(in_package.reanchor(&package, &repo).unwrap(), file);
+        assert_eq!(file.reanchor(&repo, &repo).unwrap(), file);
+        assert_matches!(
+            in_package.reanchor(&repo, &package),
+            Err(PathValidationError::NotParent(..))
+        );
+    }
+
+    #[test]
+    fn test_clean() {
+        let clean = |path| anchored(path).clean().unwrap();
+        assert_eq!(&*clean("packages/a"), anchored("packages/a"));
+        assert_eq!(&*clean("./packages/a/./src/.."), anchored("packages/a"));
+        assert_eq!(&*clean("packages/a/../../b"), anchored("b"));
+        assert_eq!(&*clean("packages/.."), anchored(""));
+        assert_matches!(
+            anchored("packages/../..").clean(),
+            Err(PathValidationError::EscapesAnchor(_))
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        // Paths are serialized with `/` on every platform.
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, r#""packages/a""#);
+        assert_eq!(serde_json::to_string(&*path).unwrap(), json);
+        assert_eq!(
+            serde_json::from_str::<AnchoredSystemPathBuf>(&json).unwrap(),
+            path
+        );
+        #[cfg(not(windows))]
+        assert!(serde_json::from_str::<AnchoredSystemPathBuf>(r#""/packages/a""#).is_err());
+    }
+
+    #[test]
+    fn test_borrowed_and_owned() {
+        let owned = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let borrowed: &AnchoredSystemPath = &owned;
+        assert_eq!(borrowed, anchored("packages/a"));
+        assert_eq!(borrowed.to_owned(), owned);
+    }
+}
@@ -1,10 +1,17 @@
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Borrow,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
 
-use serde::Serialize;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{AbsoluteSystemPathBuf, IntoSystem, PathValidationError};
+use crate::{
+    deserialize_system, serialize_unix, AbsoluteSystemPathBuf, AnchoredSystemPath, IntoSystem,
+    PathValidationError,
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct AnchoredSystemPathBuf(PathBuf);
 
 impl TryFrom<&Path> for AnchoredSystemPathBuf {
@@ -24,23 +31,41 @@ impl AnchoredSystemPathBuf {
         root: &AbsoluteSystemPathBuf,
         path: &AbsoluteSystemPathBuf,
     ) -> Result<Self, PathValidationError> {
+        // Paths may mix verbatim and regular forms, or differ in casing on
+        // Windows.
         let stripped_path = path
-            .as_path()
-            .strip_prefix(root.as_path())
-            .map_err(|_| PathValidationError::NotParent(root.to_string(), path.to_string()))?
-            .to_path_buf();
+            .strip_prefix_normalized(root)
+            .ok_or_else(|| PathValidationError::NotParent(root.to_string(), path.to_string()))?;
 
         Ok(AnchoredSystemPathBuf(stripped_path))
     }
+}
+
+impl Deref for AnchoredSystemPathBuf {
+    type Target = AnchoredSystemPath;
+
+    fn deref(&self) -> &AnchoredSystemPath {
+        AnchoredSystemPath::new_unchecked(&self.0)
+    }
+}
+
+impl AsRef<AnchoredSystemPath> for AnchoredSystemPathBuf {
+    fn as_ref(&self) -> &AnchoredSystemPath {
+        self
+    }
+}
 
-    pub fn as_path(&self) -> &Path {
-        self.0.as_path()
+impl Borrow<AnchoredSystemPath> for AnchoredSystemPathBuf {
+    fn borrow(&self) -> &AnchoredSystemPath {
+        self
     }
+}
+
+impl ToOwned for AnchoredSystemPath {
+    type Owned = AnchoredSystemPathBuf;
 
-    pub fn to_str(&self) -> Result<&str, PathValidationError> {
-        self.0
-            .to_str()
-            .ok_or_else(|| PathValidationError::InvalidUnicode(self.0.clone()))
+    fn to_owned(&self) -> AnchoredSystemPathBuf {
+        AnchoredSystemPathBuf(self.as_path().to_path_buf())
     }
 }
 
@@ -49,3 +74,15 @@ impl From<AnchoredSystemPathBuf> for PathBuf {
         path.0
     }
 }
+
+impl Serialize for AnchoredSystemPathBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_unix(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnchoredSystemPathBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(deserialize_system(deserializer)?.as_path()).map_err(de::Error::custom)
+    }
+}
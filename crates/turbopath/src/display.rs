@@ -0,0 +1,193 @@
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use path_slash::PathExt;
+
+use crate::AbsoluteSystemPathBuf;
+
+/// Displays an [AbsoluteSystemPathBuf] for users, e.g. in logs, the same way
+/// on every platform.
+///
+/// Paths are rendered with `/` separators. Paths inside the root set with
+/// [PathDisplay::relative_to] are rendered relative to it, and with
+/// [PathDisplay::redact_home], the home directory of the user is rendered as
+/// `~` so that logs can be shared without revealing it.
+///
+/// ```
+/// use turbopath::AbsoluteSystemPathBuf;
+/// #[cfg(windows)]
+/// let (root, path) = ("C:\\repo", "C:\\repo\\packages\\ui");
+/// #[cfg(not(windows))]
+/// let (root, path) = ("/repo", "/repo/packages/ui");
+///
+/// let root = AbsoluteSystemPathBuf::new(root).unwrap();
+/// let path = AbsoluteSystemPathBuf::new(path).unwrap();
+/// assert_eq!(
+///     path.display_normalized().relative_to(&root).to_string(),
+///     "packages/ui"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathDisplay<'a> {
+    path: &'a Path,
+    root: Option<&'a Path>,
+    home: Option<PathBuf>,
+}
+
+impl<'a> PathDisplay<'a> {
+    pub(crate) fn new(path: &'a AbsoluteSystemPathBuf) -> Self {
+        Self {
+            path: path.as_path(),
+            root: None,
+            home: None,
+        }
+    }
+
+    /// Renders the path relative to `root` if it's inside of it, e.g. relative
+    /// to the root of the repository. `root` itself is rendered as `.`.
+    pub fn relative_to(mut self, root: &'a AbsoluteSystemPathBuf) -> Self {
+        self.root = Some(root.as_path());
+        self
+    }
+
+    /// Renders the home directory of the current user as `~`. Does nothing if
+    /// the home directory isn't known.
+    pub fn redact_home(self) -> Self {
+        match home_dir() {
+            Some(home) => self.redact(home),
+            None => self,
+        }
+    }
+
+    /// Renders `home` as `~`, like [PathDisplay::redact_home] does for the
+    /// home directory of the current user.
+    pub fn redact(mut self, home: impl Into<PathBuf>) -> Self {
+        self.home = Some(home.into());
+        self
+    }
+}
+
+impl fmt::Display for PathDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(relative) = self.root.and_then(|root| self.path.strip_prefix(root).ok()) {
+            return write_slashed(f, None, relative);
+        }
+        if let Some(relative) = self
+            .home
+            .as_deref()
+            .and_then(|home| self.path.strip_prefix(home).ok())
+        {
+            return write_slashed(f, Some("~"), relative);
+        }
+        f.write_str(&self.path.to_slash_lossy())
+    }
+}
+
+/// Writes `relative` with `/` separators after `prefix`, or `prefix` alone,
+/// or `.` if both are empty.
+fn write_slashed(f: &mut fmt::Formatter<'_>, prefix: Option<&str>, relative: &Path) -> fmt::Result {
+    let relative = relative.to_slash_lossy();
+    match (prefix, relative.is_empty()) {
+        (None, true) => f.write_str("."),
+        (None, false) => f.write_str(&relative),
+        (Some(prefix), true) => f.write_str(prefix),
+        (Some(prefix), false) => write!(f, "{}/{}", prefix, relative),
+    }
+}
+
+/// The home directory of the current user, from `HOME`, or `USERPROFILE` on
+/// Windows.
+fn home_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let home = env::var_os("USERPROFILE").or_else(|| env::var_os("HOME"));
+    #[cfg(not(windows))]
+    let home = env::var_os("HOME");
+    home.map(PathBuf::from)
+        .filter(|home| home.is_absolute() && home.parent().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AbsoluteSystemPathBuf;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_path_display() {
+        let root = AbsoluteSystemPathBuf::new("/home/user/repo").unwrap();
+        let path = AbsoluteSystemPathBuf::new("/home/user/repo/packages/ui/index.js").unwrap();
+        let outside = AbsoluteSystemPathBuf::new("/home/user/.npmrc").unwrap();
+        let elsewhere = AbsoluteSystemPathBuf::new("/tmp/cache").unwrap();
+
+        assert_eq!(
+            path.display_normalized().to_string(),
+            "/home/user/repo/packages/ui/index.js"
+        );
+        assert_eq!(
+            path.display_normalized().relative_to(&root).to_string(),
+            "packages/ui/index.js"
+        );
+        assert_eq!(
+            root.display_normalized().relative_to(&root).to_string(),
+            "."
+        );
+        assert_eq!(
+            outside.display_normalized().relative_to(&root).to_string(),
+            "/home/user/.npmrc"
+        );
+        assert_eq!(
+            outside
+                .display_normalized()
+                .relative_to(&root)
+                .redact("/home/user")
+                .to_string(),
+            "~/.npmrc"
+        );
+        assert_eq!(
+            root.display_normalized().redact("/home/user").to_string(),
+            "~/repo"
+        );
+        assert_eq!(
+            elsewhere
+                .display_normalized()
+                .redact("/home/user")
+                .to_string(),
+            "/tmp/cache"
+        );
+        // Only whole components are redacted.
+        let other_user = AbsoluteSystemPathBuf::new("/home/username").unwrap();
+        assert_eq!(
+            other_user
+                .display_normalized()
+                .redact("/home/user")
+                .to_string(),
+            "/home/username"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_path_display() {
+        let root = AbsoluteSystemPathBuf::new("C:\\Users\\user\\repo").unwrap();
+        let path = AbsoluteSystemPathBuf::new("C:\\Users\\user\\repo\\packages\\ui").unwrap();
+        let outside = AbsoluteSystemPathBuf::new("C:\\Users\\user\\.npmrc").unwrap();
+
+        assert_eq!(
+            path.display_normalized().to_string(),
+            "C:/Users/user/repo/packages/ui"
+        );
+        assert_eq!(
+            path.display_normalized().relative_to(&root).to_string(),
+            "packages/ui"
+        );
+        assert_eq!(
+            outside
+                .display_normalized()
+                .relative_to(&root)
+                .redact("C:\\Users\\user")
+                .to_string(),
+            "~/.npmrc"
+        );
+    }
+}
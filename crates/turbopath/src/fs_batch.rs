@@ -0,0 +1,114 @@
+//! Querying the metadata of many paths at once.
+//!
+//! Hashing a package or validating the outputs of a task stats thousands of
+//! files, and each stat blocks on the file system. The functions of this
+//! module spread the stats over a few threads, which helps most on network
+//! file systems and on Windows, where a stat is comparatively slow. Small
+//! batches are stated on the calling thread.
+
+use std::{collections::HashMap, fs::Metadata, thread};
+
+use crate::{AbsoluteSystemPathBuf, PathError};
+
+/// Batches with fewer paths per thread aren't worth spawning threads for.
+const MIN_PATHS_PER_THREAD: usize = 256;
+/// File systems don't get faster with more concurrent stats than this.
+const MAX_THREADS: usize = 16;
+
+/// The metadata of each path, or the error of its stat, keyed by the path.
+pub type MetadataMap = HashMap<AbsoluteSystemPathBuf, Result<Metadata, PathError>>;
+
+/// Stats `paths` concurrently and follows symlinks, like
+/// [AbsoluteSystemPathBuf::stat].
+pub fn stat_many(paths: &[AbsoluteSystemPathBuf]) -> MetadataMap {
+    query_many(paths, AbsoluteSystemPathBuf::stat)
+}
+
+/// Stats `paths` concurrently without following symlinks, like
+/// [AbsoluteSystemPathBuf::symlink_metadata].
+pub fn symlink_stat_many(paths: &[AbsoluteSystemPathBuf]) -> MetadataMap {
+    query_many(paths, AbsoluteSystemPathBuf::symlink_metadata)
+}
+
+fn query_many(
+    paths: &[AbsoluteSystemPathBuf],
+    query: fn(&AbsoluteSystemPathBuf) -> Result<Metadata, PathError>,
+) -> MetadataMap {
+    let query_chunk = |chunk: &[AbsoluteSystemPathBuf]| {
+        chunk
+            .iter()
+            .map(|path| (path.clone(), query(path)))
+            .collect::<Vec<_>>()
+    };
+    let threads = thread::available_parallelism()
+        .map_or(1, |threads| threads.get())
+        .min(MAX_THREADS)
+        .min(paths.len() / MIN_PATHS_PER_THREAD);
+    if threads <= 1 {
+        return query_chunk(paths).into_iter().collect();
+    }
+
+    let chunk_size = (paths.len() + threads - 1) / threads;
+    thread::scope(|scope| {
+        let handles = paths
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || query_chunk(chunk)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("stat thread panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{stat_many, symlink_stat_many};
+    use crate::AbsoluteSystemPathBuf;
+
+    #[test]
+    fn test_stat_many() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dir.path()).unwrap();
+        // Enough files to be stated on several threads.
+        let mut paths = (0..1_000)
+            .map(|index| {
+                let path = root.join_literal(&format!("{index}.txt"));
+                path.create_with_contents(&"x".repeat(index)).unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+        let missing = root.join_literal("missing.txt");
+        paths.push(missing.clone());
+
+        let metadata = stat_many(&paths);
+        assert_eq!(metadata.len(), paths.len());
+        for (index, path) in paths[..1_000].iter().enumerate() {
+            assert_eq!(metadata[path].as_ref().unwrap().len(), index as u64);
+        }
+        assert!(metadata[&missing]
+            .as_ref()
+            .unwrap_err()
+            .is_io_error(io::ErrorKind::NotFound));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_symlink_stat_many() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dir.path()).unwrap();
+        let file = root.join_literal("file.txt");
+        file.create_with_contents("contents").unwrap();
+        let link = root.join_literal("link.txt");
+        link.symlink_to_file("file.txt").unwrap();
+
+        let paths = [file.clone(), link.clone()];
+        let lstat = symlink_stat_many(&paths);
+        assert!(lstat[&link].as_ref().unwrap().file_type().is_symlink());
+        assert!(lstat[&file].as_ref().unwrap().is_file());
+        let stat = stat_many(&paths);
+        assert!(stat[&link].as_ref().unwrap().is_file());
+    }
+}
@@ -0,0 +1,510 @@
+//! Matching [RelativeUnixPath]s against globs.
+//!
+//! Globs are compiled once into a [GlobSet] and match paths segment by
+//! segment, without converting them to system paths. The syntax follows
+//! doublestar, which turbo uses for `inputs` and `outputs`:
+//!
+//! * `*` matches any characters in a segment, `?` a single character.
+//! * `**` as a whole segment matches any number of segments, including none.
+//! * `[abc]`, `[a-z]` and `[!abc]` or `[^abc]` match a character of a class.
+//! * `{a,b}` matches either alternative, and alternatives can be nested.
+//! * `\` escapes the next character.
+//! * A glob that ends with `/` matches everything in the directory.
+//!
+//! Globs that start with `!` exclude the paths that they match.
+//!
+//! Matching takes at most quadratic time in the length of the glob and the
+//! path, whatever the number of wildcards.
+
+use crate::RelativeUnixPath;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum GlobError {
+    #[error("Glob {0} has an unclosed character class")]
+    UnclosedClass(String),
+    #[error("Glob {0} has an unclosed alternative")]
+    UnclosedAlternative(String),
+    #[error("Glob {0} ends with an escape")]
+    TrailingEscape(String),
+}
+
+/// A compiled set of include and exclude globs, see the [module
+/// documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobSet {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    case_sensitive: bool,
+}
+
+impl GlobSet {
+    /// Compiles `globs`, which include the paths they match, or exclude them if
+    /// they start with `!`. Paths are matched case sensitively.
+    pub fn new<S: AsRef<str>>(globs: &[S]) -> Result<Self, GlobError> {
+        let mut set = Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            case_sensitive: true,
+        };
+        for glob in globs {
+            let glob = glob.as_ref();
+            match glob.strip_prefix('!') {
+                Some(exclude) => set.exclude.push(Glob::new(exclude)?),
+                None => set.include.push(Glob::new(glob)?),
+            }
+        }
+        Ok(set)
+    }
+
+    /// Matches paths that only differ in casing from the globs, e.g. on
+    /// case-insensitive file systems.
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Whether there are include globs. A set without them matches no path.
+    pub fn has_includes(&self) -> bool {
+        !self.include.is_empty()
+    }
+
+    /// Whether there are exclude globs.
+    pub fn has_excludes(&self) -> bool {
+        !self.exclude.is_empty()
+    }
+
+    /// Whether an include glob matches `path` and no exclude glob does.
+    pub fn is_match(&self, path: &RelativeUnixPath) -> bool {
+        let segments = self.segments(path);
+        self.include
+            .iter()
+            .any(|glob| glob.is_match(&segments, self.case_sensitive))
+            && !self
+                .exclude
+                .iter()
+                .any(|glob| glob.is_match(&segments, self.case_sensitive))
+    }
+
+    /// Whether an include glob matches `path`, whatever the exclude globs.
+    pub fn is_included(&self, path: &RelativeUnixPath) -> bool {
+        let segments = self.segments(path);
+        self.include
+            .iter()
+            .any(|glob| glob.is_match(&segments, self.case_sensitive))
+    }
+
+    /// Whether an exclude glob matches `path`.
+    pub fn is_excluded(&self, path: &RelativeUnixPath) -> bool {
+        let segments = self.segments(path);
+        self.exclude
+            .iter()
+            .any(|glob| glob.is_match(&segments, self.case_sensitive))
+    }
+
+    fn segments(&self, path: &RelativeUnixPath) -> Vec<Vec<char>> {
+        path.components()
+            .map(|segment| {
+                if self.case_sensitive {
+                    segment.chars().collect()
+                } else {
+                    segment.to_lowercase().chars().collect()
+                }
+            })
+            .collect()
+    }
+}
+
+/// A single glob, expanded into its alternatives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Glob {
+    alternatives: Vec<Vec<Segment>>,
+    /// The alternatives of the glob in lowercase, which match paths in
+    /// lowercase if paths are matched case insensitively.
+    lowercase: Vec<Vec<Segment>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// `**`, any number of segments.
+    Any,
+    Pattern(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyChars,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    fn new(glob: &str) -> Result<Self, GlobError> {
+        let mut normalized = glob.trim_start_matches("./").to_string();
+        if normalized.ends_with('/') {
+            normalized.push_str("**");
+        }
+        let alternatives = expand_alternatives(&normalized)
+            .ok_or_else(|| GlobError::UnclosedAlternative(glob.to_string()))?
+            .iter()
+            .map(|alternative| parse_segments(alternative, glob))
+            .collect::<Result<Vec<_>, _>>()?;
+        let lowercase = expand_alternatives(&normalized.to_lowercase())
+            .ok_or_else(|| GlobError::UnclosedAlternative(glob.to_string()))?
+            .iter()
+            .map(|alternative| parse_segments(alternative, glob))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            alternatives,
+            lowercase,
+        })
+    }
+
+    /// Whether the glob matches `segments`, which are in lowercase unless the
+    /// glob is matched case sensitively.
+    fn is_match(&self, segments: &[Vec<char>], case_sensitive: bool) -> bool {
+        let alternatives = if case_sensitive {
+            &self.alternatives
+        } else {
+            &self.lowercase
+        };
+        alternatives
+            .iter()
+            .any(|pattern| match_segments(pattern, segments))
+    }
+}
+
+/// Expands the `{a,b}` alternatives of `glob` into globs without them, or
+/// returns `None` if an alternative isn't closed.
+fn expand_alternatives(glob: &str) -> Option<Vec<String>> {
+    let chars = glob.chars().collect::<Vec<_>>();
+    let mut start = None;
+    let mut depth = 0;
+    let mut in_class = false;
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '[' if !in_class => in_class = true,
+            ']' if in_class => in_class = false,
+            '{' if !in_class => {
+                if depth == 0 {
+                    start = Some(index);
+                }
+                depth += 1;
+            }
+            '}' if !in_class && depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let start = start.expect("alternatives have a start");
+                    let prefix = chars[..start].iter().collect::<String>();
+                    let suffix = chars[index + 1..].iter().collect::<String>();
+                    let mut expanded = Vec::new();
+                    for alternative in split_alternatives(&chars[start + 1..index]) {
+                        expanded.extend(expand_alternatives(&format!(
+                            "{prefix}{alternative}{suffix}"
+                        ))?);
+                    }
+                    return Some(expanded);
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    if depth > 0 {
+        None
+    } else {
+        Some(vec![glob.to_string()])
+    }
+}
+
+/// Splits the content of `{...}` at the commas that aren't nested.
+fn split_alternatives(chars: &[char]) -> Vec<String> {
+    let mut alternatives = vec![String::new()];
+    let mut depth = 0;
+    let mut chars = chars.iter();
+    while let Some(&c) = chars.next() {
+        match c {
+            '\\' => {
+                let current = alternatives.last_mut().expect("there is an alternative");
+                current.push(c);
+                if let Some(&escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                continue;
+            }
+            ',' if depth == 0 => {
+                alternatives.push(String::new());
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        alternatives
+            .last_mut()
+            .expect("there is an alternative")
+            .push(c);
+    }
+    alternatives
+}
+
+fn parse_segments(glob: &str, original: &str) -> Result<Vec<Segment>, GlobError> {
+    glob.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .map(|segment| {
+            if segment == "**" {
+                Ok(Segment::Any)
+            } else {
+                parse_tokens(segment, original).map(Segment::Pattern)
+            }
+        })
+        .collect()
+}
+
+fn parse_tokens(segment: &str, original: &str) -> Result<Vec<Token>, GlobError> {
+    let mut tokens = Vec::new();
+    let mut chars = segment.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\\' => Token::Char(
+                chars
+                    .next()
+                    .ok_or_else(|| GlobError::TrailingEscape(original.to_string()))?,
+            ),
+            '?' => Token::AnyChar,
+            '*' => {
+                // `**` within a segment is the same as `*`.
+                while chars.peek() == Some(&'*') {
+                    chars.next();
+                }
+                Token::AnyChars
+            }
+            '[' => {
+                let negated = matches!(chars.peek(), Some('!' | '^'));
+                if negated {
+                    chars.next();
+                }
+                let mut ranges = Vec::new();
+                loop {
+                    let start = match chars.next() {
+                        Some(']') if !ranges.is_empty() => break,
+                        Some('\\') => chars.next(),
+                        start => start,
+                    }
+                    .ok_or_else(|| GlobError::UnclosedClass(original.to_string()))?;
+                    let end = if chars.peek() == Some(&'-') {
+                        chars.next();
+                        match chars.next() {
+                            Some(']') => {
+                                // A trailing `-` is a literal.
+                                ranges.push((start, start));
+                                ranges.push(('-', '-'));
+                                break;
+                            }
+                            Some(end) => end,
+                            None => return Err(GlobError::UnclosedClass(original.to_string())),
+                        }
+                    } else {
+                        start
+                    };
+                    ranges.push((start, end));
+                }
+                Token::Class { negated, ranges }
+            }
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn match_segments(pattern: &[Segment], segments: &[Vec<char>]) -> bool {
+    match_sequence(
+        pattern,
+        segments,
+        |segment| *segment == Segment::Any,
+        |segment, chars| match segment {
+            Segment::Pattern(tokens) => match_tokens(tokens, chars),
+            Segment::Any => unreachable!("`**` is matched by match_sequence"),
+        },
+    )
+}
+
+fn match_tokens(tokens: &[Token], chars: &[char]) -> bool {
+    match_sequence(
+        tokens,
+        chars,
+        |token| *token == Token::AnyChars,
+        |token, c| token_matches(token, *c),
+    )
+}
+
+/// Whether `pattern` matches `items`, where each element of the pattern is
+/// either a wildcard for which `is_wildcard` is true, which matches any number
+/// of items, or matches a single item for which `matches` is true.
+///
+/// Only the last wildcard is backtracked to: once the rest of the pattern
+/// after a wildcard is matched, the items that an earlier wildcard matches can
+/// as well be matched by the later one. Matching takes at most
+/// `pattern.len() * items.len()` steps, while trying every split at each
+/// wildcard takes exponential time, e.g. for `*a*a*a*b`.
+fn match_sequence<P, I>(
+    pattern: &[P],
+    items: &[I],
+    is_wildcard: impl Fn(&P) -> bool,
+    matches: impl Fn(&P, &I) -> bool,
+) -> bool {
+    let mut pattern_index = 0;
+    let mut item_index = 0;
+    // The index of the last wildcard, and of the first item that it doesn't
+    // match yet.
+    let mut backtrack = None;
+    while item_index < items.len() {
+        match pattern.get(pattern_index) {
+            Some(element) if is_wildcard(element) => {
+                backtrack = Some((pattern_index, item_index));
+                pattern_index += 1;
+            }
+            Some(element) if matches(element, &items[item_index]) => {
+                pattern_index += 1;
+                item_index += 1;
+            }
+            _ => match backtrack {
+                // The wildcard matches one more item.
+                Some((wildcard_index, wildcard_item_index)) => {
+                    backtrack = Some((wildcard_index, wildcard_item_index + 1));
+                    pattern_index = wildcard_index + 1;
+                    item_index = wildcard_item_index + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_index..].iter().all(is_wildcard)
+}
+
+fn token_matches(token: &Token, c: char) -> bool {
+    match token {
+        Token::Char(expected) => *expected == c,
+        Token::AnyChar => true,
+        Token::AnyChars => unreachable!("`*` is matched by match_sequence"),
+        Token::Class { negated, ranges } => {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c))
+                != *negated
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{GlobError, GlobSet};
+    use crate::RelativeUnixPath;
+
+    fn is_match(globs: &[&str], path: &str) -> bool {
+        GlobSet::new(globs)
+            .unwrap()
+            .is_match(RelativeUnixPath::new(Path::new(path)).unwrap())
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(is_match(&["*.js"], "index.js"));
+        assert!(is_match(&["*"], ".env"));
+        assert!(!is_match(&["*.js"], "src/index.js"));
+        assert!(is_match(&["src/?.js"], "src/a.js"));
+        assert!(!is_match(&["src/?.js"], "src/ab.js"));
+        assert!(is_match(&["a**b"], "axxb"));
+        assert!(!is_match(&["a**b"], "ax/xb"));
+    }
+
+    #[test]
+    fn test_doublestar() {
+        assert!(is_match(&["**/*.js"], "index.js"));
+        assert!(is_match(&["**/*.js"], "src/lib/index.js"));
+        assert!(is_match(&["src/**/index.js"], "src/index.js"));
+        assert!(is_match(&["src/**"], "src/a/b"));
+        assert!(is_match(&["src/**"], "src"));
+        assert!(!is_match(&["src/**"], "srcs/a"));
+        assert!(is_match(&["src/"], "src/a/b"));
+        assert!(is_match(&["./src/*.js"], "src/a.js"));
+        assert!(is_match(&["**"], "a/b/c"));
+    }
+
+    #[test]
+    fn test_classes_and_alternatives() {
+        assert!(is_match(&["[abc].js"], "b.js"));
+        assert!(!is_match(&["[!abc].js"], "b.js"));
+        assert!(is_match(&["[^abc].js"], "d.js"));
+        assert!(is_match(&["v[0-9]"], "v7"));
+        assert!(is_match(&["[a-]"], "-"));
+        assert!(is_match(&["{src,lib}/*.js"], "lib/a.js"));
+        assert!(is_match(&["*.{js,{ts,tsx}}"], "a.tsx"));
+        assert!(!is_match(&["*.{js,ts}"], "a.tsx"));
+        assert!(is_match(&["\\*.js"], "*.js"));
+        assert!(!is_match(&["\\*.js"], "a.js"));
+    }
+
+    #[test]
+    fn test_negations() {
+        let globs = ["src/**", "!src/**/*.test.js", "!src/generated/"];
+        assert!(is_match(&globs, "src/index.js"));
+        assert!(!is_match(&globs, "src/lib/index.test.js"));
+        assert!(!is_match(&globs, "src/generated/schema.js"));
+        assert!(!is_match(&globs, "lib/index.js"));
+        // Without includes, nothing matches.
+        assert!(!is_match(&["!src/**"], "lib/index.js"));
+    }
+
+    #[test]
+    fn test_case_sensitivity() {
+        let path = RelativeUnixPath::new(Path::new("Src/Index.JS")).unwrap();
+        let globs = GlobSet::new(&["src/*.js"]).unwrap();
+        assert!(!globs.is_match(path));
+        assert!(globs.case_sensitive(false).is_match(path));
+        let globs = GlobSet::new(&["SRC/[A-Z]ndex.js"])
+            .unwrap()
+            .case_sensitive(false);
+        assert!(globs.is_match(path));
+    }
+
+    #[test]
+    fn test_many_wildcards() {
+        // Takes exponential time if every split at every wildcard is tried.
+        let chars = "a".repeat(40);
+        assert!(!is_match(&["*a*a*a*a*a*a*a*a*a*a*b"], &chars));
+        assert!(is_match(&["*a*a*a*a*a*a*a*a*a*a"], &chars));
+        let segments = vec!["a"; 40].join("/");
+        assert!(!is_match(
+            &["**/a/**/a/**/a/**/a/**/a/**/a/**/b"],
+            &segments
+        ));
+        assert!(is_match(&["**/a/**/a/**/a/**/a/**/a/**/a/**"], &segments));
+    }
+
+    #[test]
+    fn test_invalid_globs() {
+        assert_eq!(
+            GlobSet::new(&["src/[ab"]).unwrap_err(),
+            GlobError::UnclosedClass("src/[ab".to_string())
+        );
+        assert_eq!(
+            GlobSet::new(&["src/{a,b"]).unwrap_err(),
+            GlobError::UnclosedAlternative("src/{a,b".to_string())
+        );
+        assert_eq!(
+            GlobSet::new(&["src\\"]).unwrap_err(),
+            GlobError::TrailingEscape("src\\".to_string())
+        );
+    }
+}
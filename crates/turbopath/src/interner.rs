@@ -0,0 +1,211 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use crate::{RelativeUnixPath, RelativeUnixPathBuf};
+
+/// A handle to a path interned by a [PathInterner]. Ids are cheap to copy,
+/// hash and compare, but they are only meaningful for the interner that
+/// created them. They are ordered by when their path was interned, not by the
+/// path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PathId(u32);
+
+impl PathId {
+    /// The empty path, which every interner contains.
+    pub const ROOT: PathId = PathId(0);
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    parent: PathId,
+    segment: u32,
+}
+
+/// Interns [RelativeUnixPath]s as [PathId]s, for maps and sets with millions
+/// of paths, e.g. the files of a large repository.
+///
+/// Paths are stored as trees of segments, so paths that share a directory
+/// share its segments, and every distinct segment is stored once. Interning a
+/// path that was interned before returns the same id, so ids of equal paths
+/// are equal. Paths are interned as they are and aren't cleaned, i.e. `..`
+/// stays a segment.
+#[derive(Debug, Clone)]
+pub struct PathInterner {
+    segments: Vec<Arc<str>>,
+    segment_ids: HashMap<Arc<str>, u32>,
+    nodes: Vec<Node>,
+    children: HashMap<(PathId, u32), PathId>,
+}
+
+impl Default for PathInterner {
+    fn default() -> Self {
+        Self {
+            segments: Vec::new(),
+            segment_ids: HashMap::new(),
+            // The root doesn't have a parent or segment, its node is never read.
+            nodes: vec![Node {
+                parent: PathId::ROOT,
+                segment: u32::MAX,
+            }],
+            children: HashMap::new(),
+        }
+    }
+}
+
+impl PathInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of `path`, interning it and its ancestors if needed.
+    pub fn intern(&mut self, path: &RelativeUnixPath) -> PathId {
+        path.components()
+            .fold(PathId::ROOT, |parent, segment| self.join(parent, segment))
+    }
+
+    /// Returns the id of the path `segment` in the directory `parent`, e.g.
+    /// while walking a directory tree, without building the path.
+    pub fn join(&mut self, parent: PathId, segment: &str) -> PathId {
+        debug_assert!(
+            !segment.is_empty() && !segment.contains('/'),
+            "{segment:?} isn't a single segment"
+        );
+        let segment = match self.segment_ids.get(segment) {
+            Some(&segment) => segment,
+            None => {
+                let id = self.next_id(self.segments.len());
+                let segment: Arc<str> = segment.into();
+                self.segments.push(segment.clone());
+                self.segment_ids.insert(segment, id);
+                id
+            }
+        };
+        if let Some(&child) = self.children.get(&(parent, segment)) {
+            return child;
+        }
+        let child = PathId(self.next_id(self.nodes.len()));
+        self.nodes.push(Node { parent, segment });
+        self.children.insert((parent, segment), child);
+        child
+    }
+
+    /// Returns the id of `path` if it was interned, without interning it.
+    pub fn get(&self, path: &RelativeUnixPath) -> Option<PathId> {
+        path.components().try_fold(PathId::ROOT, |parent, segment| {
+            let segment = *self.segment_ids.get(segment)?;
+            self.children.get(&(parent, segment)).copied()
+        })
+    }
+
+    /// The id of the directory that contains `id`, or `None` for
+    /// [PathId::ROOT].
+    pub fn parent(&self, id: PathId) -> Option<PathId> {
+        (id != PathId::ROOT).then(|| self.node(id).parent)
+    }
+
+    /// The last segment of `id`, or `None` for [PathId::ROOT].
+    pub fn file_name(&self, id: PathId) -> Option<&str> {
+        (id != PathId::ROOT).then(|| &*self.segments[self.node(id).segment as usize])
+    }
+
+    /// Whether `base` is `id` or one of its ancestors, compared by id instead
+    /// of by segments.
+    pub fn starts_with(&self, id: PathId, base: PathId) -> bool {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if id == base {
+                return true;
+            }
+            current = self.parent(id);
+        }
+        false
+    }
+
+    /// Builds the path of `id`.
+    pub fn resolve(&self, id: PathId) -> RelativeUnixPathBuf {
+        let mut segments = Vec::new();
+        let mut current = id;
+        while let Some(segment) = self.file_name(current) {
+            segments.push(segment);
+            current = self.node(current).parent;
+        }
+        segments.reverse();
+        RelativeUnixPath::new_unchecked(Path::new(&segments.join("/"))).to_owned()
+    }
+
+    /// The number of interned paths, including [PathId::ROOT].
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether no path but [PathId::ROOT] was interned.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    fn node(&self, id: PathId) -> Node {
+        *self
+            .nodes
+            .get(id.0 as usize)
+            .expect("path id belongs to another interner")
+    }
+
+    fn next_id(&self, len: usize) -> u32 {
+        u32::try_from(len).expect("too many paths to intern")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{PathId, PathInterner};
+    use crate::RelativeUnixPath;
+
+    fn unix(path: &str) -> &RelativeUnixPath {
+        RelativeUnixPath::new(Path::new(path)).unwrap()
+    }
+
+    #[test]
+    fn test_intern_deduplicates_paths() {
+        let mut interner = PathInterner::new();
+        assert!(interner.is_empty());
+        let index = interner.intern(unix("packages/a/src/index.js"));
+        let util = interner.intern(unix("packages/a/src/util.js"));
+        assert_ne!(index, util);
+        assert_eq!(interner.intern(unix("packages/a/src/index.js")), index);
+        assert_eq!(interner.intern(unix("./packages//a/src/index.js")), index);
+        // The root, the three shared directories and both files.
+        assert_eq!(interner.len(), 6);
+        assert_eq!(interner.intern(unix("")), PathId::ROOT);
+    }
+
+    #[test]
+    fn test_get_and_resolve() {
+        let mut interner = PathInterner::new();
+        let index = interner.intern(unix("packages/a/src/index.js"));
+        assert_eq!(interner.get(unix("packages/a/src/index.js")), Some(index));
+        assert_eq!(interner.get(unix("packages/a/src/util.js")), None);
+        assert_eq!(interner.get(unix("")), Some(PathId::ROOT));
+        assert_eq!(
+            interner.resolve(index).as_path(),
+            Path::new("packages/a/src/index.js")
+        );
+        assert_eq!(interner.resolve(PathId::ROOT).as_path(), Path::new(""));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let mut interner = PathInterner::new();
+        let packages = interner.intern(unix("packages"));
+        let a = interner.join(packages, "a");
+        let index = interner.intern(unix("packages/a/index.js"));
+        assert_eq!(interner.parent(index), Some(a));
+        assert_eq!(interner.parent(packages), Some(PathId::ROOT));
+        assert_eq!(interner.parent(PathId::ROOT), None);
+        assert_eq!(interner.file_name(index), Some("index.js"));
+        assert_eq!(interner.file_name(PathId::ROOT), None);
+        assert!(interner.starts_with(index, packages));
+        assert!(interner.starts_with(index, PathId::ROOT));
+        assert!(!interner.starts_with(packages, index));
+    }
+}
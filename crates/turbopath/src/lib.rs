@@ -1,9 +1,17 @@
 #![feature(assert_matches)]
 
 mod absolute_system_path_buf;
+mod anchored_system_path;
 mod anchored_system_path_buf;
+mod display;
+pub mod fs_batch;
+pub mod glob;
+mod interner;
 mod relative_system_path_buf;
+mod relative_unix_path;
 mod relative_unix_path_buf;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 use std::{
     io,
@@ -11,10 +19,15 @@ use std::{
 };
 
 pub use absolute_system_path_buf::AbsoluteSystemPathBuf;
+pub use anchored_system_path::AnchoredSystemPath;
 pub use anchored_system_path_buf::AnchoredSystemPathBuf;
+pub use display::PathDisplay;
+pub use interner::{PathId, PathInterner};
 use path_slash::{PathBufExt, PathExt};
 pub use relative_system_path_buf::RelativeSystemPathBuf;
+pub use relative_unix_path::RelativeUnixPath;
 pub use relative_unix_path_buf::RelativeUnixPathBuf;
+use serde::{ser, Deserialize, Deserializer, Serializer};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PathError {
@@ -44,6 +57,45 @@ pub enum PathValidationError {
     NotRelative(PathBuf),
     #[error("Path {0} is not parent of {1}")]
     NotParent(String, String),
+    #[error("Paths {0} and {1} have no common root")]
+    NoCommonRoot(String, String),
+    #[error("Path has system separators: {0}")]
+    NotUnix(PathBuf),
+    #[error("Path leaves the directory it's relative to: {0}")]
+    EscapesAnchor(PathBuf),
+    #[error("Path is empty")]
+    Empty,
+    #[error("Path is missing a drive letter: {0}")]
+    MissingDrive(PathBuf),
+}
+
+/// Checks that `path` is valid unicode and, on Windows, doesn't contain `/`
+/// separators.
+fn debug_assert_system_path(path: &Path) {
+    let path_str = path.to_str();
+    debug_assert!(path_str.is_some(), "{:?} is not valid unicode", path);
+    #[cfg(windows)]
+    debug_assert!(
+        !path_str.unwrap_or_default().contains('/'),
+        "{:?} contains a unix separator",
+        path
+    );
+}
+
+/// Serializes `path` as a string with `/` separators on every platform, so
+/// that serialized paths can be compared and used across platforms.
+fn serialize_unix<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    let path_str = path
+        .to_slash()
+        .ok_or_else(|| ser::Error::custom(PathValidationError::InvalidUnicode(path.to_owned())))?;
+    serializer.serialize_str(&path_str)
+}
+
+/// Deserializes a path that was serialized by [serialize_unix], with system
+/// separators.
+fn deserialize_system<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    let path = String::deserialize(deserializer)?;
+    Ok(PathBuf::from_slash(path))
 }
 
 trait IntoSystem {
@@ -6,7 +6,7 @@ use std::{
 
 use serde::Serialize;
 
-use crate::{IntoSystem, PathValidationError};
+use crate::{debug_assert_system_path, IntoSystem, PathValidationError};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize)]
 pub struct RelativeSystemPathBuf(PathBuf);
@@ -86,6 +86,13 @@ impl RelativeSystemPathBuf {
     pub fn extension(&self) -> Option<&OsStr> {
         self.0.extension()
     }
+
+    /// Checks the invariants of this type in debug builds: the path is
+    /// relative, valid unicode and uses system separators.
+    pub fn debug_assert_valid(&self) {
+        debug_assert!(!self.0.has_root(), "{:?} is not relative", self.0);
+        debug_assert_system_path(&self.0);
+    }
 }
 
 impl fmt::Display for RelativeSystemPathBuf {
@@ -0,0 +1,259 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, Serializer};
+
+use crate::{serialize_unix, PathValidationError, RelativeUnixPathBuf};
+
+/// A borrowed [RelativeUnixPathBuf], like [Path] is to [PathBuf]. Functions
+/// that only read a path take `&RelativeUnixPath`, so that callers can pass
+/// slices of other paths without allocating.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct RelativeUnixPath(Path);
+
+impl RelativeUnixPath {
+    /// Borrows `path` as a relative unix path, without converting it. Fails if
+    /// `path` is absolute, isn't valid unicode or, on Windows, contains `\`.
+    pub fn new(path: &Path) -> Result<&Self, PathValidationError> {
+        if path.has_root() {
+            return Err(PathValidationError::NotRelative(path.to_path_buf()));
+        }
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| PathValidationError::InvalidUnicode(path.to_path_buf()))?;
+        if cfg!(windows) && path_str.contains('\\') {
+            return Err(PathValidationError::NotUnix(path.to_path_buf()));
+        }
+        Ok(Self::new_unchecked(path))
+    }
+
+    /// Borrows `path`, which must be relative, valid unicode and use `/`
+    /// separators.
+    pub(crate) fn new_unchecked(path: &Path) -> &Self {
+        // SAFETY: `RelativeUnixPath` is a `repr(transparent)` wrapper around
+        // `Path`, so both have the same layout.
+        unsafe { &*(path as *const Path as *const RelativeUnixPath) }
+    }
+
+    fn from_str_unchecked(path: &str) -> &Self {
+        Self::new_unchecked(Path::new(path))
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// The components of the path, split at `/`. Empty components and `.`
+    /// are skipped.
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.as_str()
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+    }
+
+    /// The path without its last component, or `None` if the path is empty.
+    /// The parent of a path with a single component is the empty path.
+    pub fn parent(&self) -> Option<&RelativeUnixPath> {
+        let path = self.as_str().trim_end_matches('/');
+        if path.is_empty() {
+            return None;
+        }
+        let parent = path.rfind('/').map_or("", |index| &path[..index]);
+        Some(Self::from_str_unchecked(parent))
+    }
+
+    /// Whether `base` is a prefix of the path, compared by whole components.
+    /// The empty path is a prefix of every path.
+    pub fn starts_with(&self, base: &RelativeUnixPath) -> bool {
+        self.strip_prefix(base).is_ok()
+    }
+
+    pub fn ends_with<P: AsRef<Path>>(&self, child: P) -> bool {
+        self.0.ends_with(child.as_ref())
+    }
+
+    /// Appends `path` to the path with a `/`.
+    pub fn join(&self, path: &RelativeUnixPath) -> RelativeUnixPathBuf {
+        let base = self.as_str().trim_end_matches('/');
+        let path = path.as_str();
+        let joined = if base.is_empty() {
+            path.to_string()
+        } else if path.is_empty() {
+            base.to_string()
+        } else {
+            format!("{}/{}", base, path)
+        };
+        Self::new_unchecked(Path::new(&joined)).to_owned()
+    }
+
+    /// The path with `.` and empty segments removed, and each `..` removed
+    /// together with the segment before it, without looking at the file
+    /// system. Fails with [PathValidationError::EscapesAnchor] if a `..` has
+    /// no segment before it, e.g. for `../shared/**`.
+    pub fn clean(&self) -> Result<RelativeUnixPathBuf, PathValidationError> {
+        let mut segments = Vec::new();
+        for component in self.components() {
+            if component != ".." {
+                segments.push(component);
+            } else if segments.pop().is_none() {
+                return Err(PathValidationError::EscapesAnchor(self.to_path_buf()));
+            }
+        }
+        Ok(Self::from_str_unchecked(&segments.join("/")).to_owned())
+    }
+
+    /// The rest of the path after `prefix`, compared by whole components.
+    pub fn strip_prefix(
+        &self,
+        prefix: &RelativeUnixPath,
+    ) -> Result<&RelativeUnixPath, PathValidationError> {
+        let path = self.as_str();
+        let prefix_str = prefix.as_str().trim_end_matches('/');
+        let rest = if prefix_str.is_empty() {
+            Some(path)
+        } else {
+            path.strip_prefix(prefix_str).and_then(|rest| {
+                if rest.is_empty() {
+                    Some(rest)
+                } else {
+                    rest.strip_prefix('/')
+                }
+            })
+        };
+        rest.map(Self::from_str_unchecked)
+            .ok_or_else(|| PathValidationError::NotParent(prefix_str.to_string(), path.to_string()))
+    }
+
+    /// The path as a string. Paths are checked to be valid unicode when
+    /// they're created.
+    pub(crate) fn as_str(&self) -> &str {
+        self.0.to_str().unwrap_or_default()
+    }
+
+    pub fn to_str(&self) -> Result<&str, PathValidationError> {
+        self.0
+            .to_str()
+            .ok_or_else(|| PathValidationError::InvalidUnicode(self.0.to_path_buf()))
+    }
+
+    pub fn file_name(&self) -> Option<&OsStr> {
+        self.0.file_name()
+    }
+
+    pub fn extension(&self) -> Option<&OsStr> {
+        self.0.extension()
+    }
+
+    pub fn to_path_buf(&self) -> PathBuf {
+        self.0.to_path_buf()
+    }
+
+    /// Checks the invariants of this type in debug builds: the path is
+    /// relative, valid unicode and uses `/` separators.
+    pub fn debug_assert_valid(&self) {
+        debug_assert!(!self.0.has_root(), "{:?} is not relative", &self.0);
+        let path = self.0.to_str();
+        debug_assert!(path.is_some(), "{:?} is not valid unicode", &self.0);
+        #[cfg(windows)]
+        debug_assert!(
+            !path.unwrap_or_default().contains('\\'),
+            "{:?} contains a system separator",
+            &self.0
+        );
+    }
+}
+
+impl Serialize for RelativeUnixPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_unix(&self.0, serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::RelativeUnixPath;
+    use crate::{PathValidationError, RelativeUnixPathBuf};
+
+    fn unix(path: &str) -> &RelativeUnixPath {
+        RelativeUnixPath::new(Path::new(path)).unwrap()
+    }
+
+    #[test]
+    fn test_relative_unix_path_operations() {
+        let path = unix("packages/a/src/index.js");
+        assert_eq!(
+            path.components().collect::<Vec<_>>(),
+            vec!["packages", "a", "src", "index.js"]
+        );
+        assert_eq!(unix("").components().count(), 0);
+
+        assert_eq!(path.parent(), Some(unix("packages/a/src")));
+        assert_eq!(unix("packages").parent(), Some(unix("")));
+        assert_eq!(unix("").parent(), None);
+
+        assert!(path.starts_with(unix("packages/a")));
+        assert!(path.starts_with(unix("")));
+        assert!(!path.starts_with(unix("packages/ab")));
+        assert!(!path.starts_with(unix("packages/a/src/index.js/x")));
+
+        assert_eq!(&*unix("").join(unix("a")), unix("a"));
+        assert_eq!(&*unix("a").join(unix("")), unix("a"));
+        assert_eq!(&*unix("packages/a").join(unix("src/index.js")), path);
+
+        assert_eq!(
+            path.strip_prefix(unix("packages/a")).unwrap(),
+            unix("src/index.js")
+        );
+        assert_eq!(path.strip_prefix(path).unwrap(), unix(""));
+        assert_eq!(path.strip_prefix(unix("")).unwrap(), path);
+        assert!(matches!(
+            path.strip_prefix(unix("packages/ab")),
+            Err(PathValidationError::NotParent(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_clean() {
+        let clean = |path| unix(path).clean().unwrap();
+        assert_eq!(clean("packages/a/src"), unix("packages/a/src").to_owned());
+        assert_eq!(
+            clean("./packages//a/./src/"),
+            unix("packages/a/src").to_owned()
+        );
+        assert_eq!(
+            clean("packages/a/../b/src"),
+            unix("packages/b/src").to_owned()
+        );
+        assert_eq!(clean("packages/a/.."), unix("packages").to_owned());
+        assert_eq!(clean("packages/.."), unix("").to_owned());
+        assert_eq!(clean(""), unix("").to_owned());
+        assert!(matches!(
+            unix("../shared/**").clean(),
+            Err(PathValidationError::EscapesAnchor(_))
+        ));
+        assert!(matches!(
+            unix("packages/../../shared").clean(),
+            Err(PathValidationError::EscapesAnchor(_))
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_and_owned() {
+        let owned = RelativeUnixPathBuf::new("packages/a").unwrap();
+        let borrowed: &RelativeUnixPath = &owned;
+        assert_eq!(borrowed, unix("packages/a"));
+        assert_eq!(borrowed.to_owned(), owned);
+
+        // Maps with owned keys can be read with borrowed keys.
+        let map = std::collections::HashMap::from([(owned.clone(), 1)]);
+        assert_eq!(map.get(unix("packages/a")), Some(&1));
+
+        #[cfg(not(windows))]
+        assert!(RelativeUnixPath::new(Path::new("/packages/a")).is_err());
+    }
+}
@@ -1,13 +1,10 @@
-use std::{
-    ffi::OsStr,
-    path::{Components, Path, PathBuf},
-};
+use std::{borrow::Borrow, ops::Deref, path::PathBuf};
 
-use serde::Serialize;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{IntoUnix, PathValidationError};
+use crate::{serialize_unix, IntoUnix, PathValidationError, RelativeUnixPath};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct RelativeUnixPathBuf(PathBuf);
 
 impl RelativeUnixPathBuf {
@@ -36,64 +33,70 @@ impl RelativeUnixPathBuf {
         Ok(RelativeUnixPathBuf(path.into_unix()?))
     }
 
-    pub fn as_path(&self) -> &Path {
-        &self.0
-    }
-
-    pub fn components(&self) -> Components<'_> {
-        self.0.components()
-    }
-
-    pub fn parent(&self) -> Option<Self> {
+    pub fn into_path_buf(self) -> PathBuf {
         self.0
-            .parent()
-            .map(|p| RelativeUnixPathBuf(p.to_path_buf()))
     }
+}
 
-    pub fn starts_with<P: AsRef<Path>>(&self, base: P) -> bool {
-        self.0.starts_with(base.as_ref())
-    }
+impl Deref for RelativeUnixPathBuf {
+    type Target = RelativeUnixPath;
 
-    pub fn ends_with<P: AsRef<Path>>(&self, child: P) -> bool {
-        self.0.ends_with(child.as_ref())
+    fn deref(&self) -> &RelativeUnixPath {
+        RelativeUnixPath::new_unchecked(&self.0)
     }
+}
 
-    pub fn join<P: AsRef<Path>>(&self, path: P) -> RelativeUnixPathBuf {
-        RelativeUnixPathBuf(self.0.join(path))
+impl AsRef<RelativeUnixPath> for RelativeUnixPathBuf {
+    fn as_ref(&self) -> &RelativeUnixPath {
+        self
     }
+}
 
-    pub fn to_str(&self) -> Result<&str, PathValidationError> {
-        self.0
-            .to_str()
-            .ok_or_else(|| PathValidationError::InvalidUnicode(self.0.clone()))
+impl Borrow<RelativeUnixPath> for RelativeUnixPathBuf {
+    fn borrow(&self) -> &RelativeUnixPath {
+        self
     }
+}
+
+impl ToOwned for RelativeUnixPath {
+    type Owned = RelativeUnixPathBuf;
 
-    pub fn file_name(&self) -> Option<&OsStr> {
-        self.0.file_name()
+    fn to_owned(&self) -> RelativeUnixPathBuf {
+        RelativeUnixPathBuf(self.to_path_buf())
     }
+}
 
-    pub fn extension(&self) -> Option<&OsStr> {
-        self.0.extension()
+impl Serialize for RelativeUnixPathBuf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_unix(&self.0, serializer)
     }
+}
 
-    pub fn into_path_buf(self) -> PathBuf {
-        self.0
+impl<'de> Deserialize<'de> for RelativeUnixPathBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::new(String::deserialize(deserializer)?).map_err(de::Error::custom)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{ffi::OsStr, path::Path};
+
     use super::*;
 
+    fn unix(path: &str) -> RelativeUnixPathBuf {
+        RelativeUnixPathBuf::new(path).unwrap()
+    }
+
     #[test]
     fn test_relative_unix_path_buf() {
         let path = RelativeUnixPathBuf::new(PathBuf::from("foo/bar")).unwrap();
         assert_eq!(path.as_path(), Path::new("foo/bar"));
         assert_eq!(path.components().count(), 2);
         assert_eq!(path.parent().unwrap().as_path(), Path::new("foo"));
-        assert!(path.starts_with("foo"));
+        assert!(path.starts_with(&unix("foo")));
         assert!(path.ends_with("bar"));
-        assert_eq!(path.join("baz").as_path(), Path::new("foo/bar/baz"));
+        assert_eq!(path.join(&unix("baz")).as_path(), Path::new("foo/bar/baz"));
         assert_eq!(path.to_str().unwrap(), "foo/bar");
         assert_eq!(path.file_name(), Some(OsStr::new("bar")));
         assert_eq!(path.extension(), None);
@@ -105,9 +108,12 @@ mod tests {
         assert_eq!(path.as_path(), Path::new("foo/bar.txt"));
         assert_eq!(path.components().count(), 2);
         assert_eq!(path.parent().unwrap().as_path(), Path::new("foo"));
-        assert!(path.starts_with("foo"));
+        assert!(path.starts_with(&unix("foo")));
         assert!(path.ends_with("bar.txt"));
-        assert_eq!(path.join("baz").as_path(), Path::new("foo/bar.txt/baz"));
+        assert_eq!(
+            path.join(&unix("baz")).as_path(),
+            Path::new("foo/bar.txt/baz")
+        );
         assert_eq!(path.to_str().unwrap(), "foo/bar.txt");
         assert_eq!(path.file_name(), Some(OsStr::new("bar.txt")));
         assert_eq!(path.extension(), Some(OsStr::new("txt")));
@@ -121,6 +127,20 @@ mod tests {
         assert!(RelativeUnixPathBuf::new(PathBuf::from("C:\\foo\\bar")).is_err());
     }
 
+    #[test]
+    fn test_serde() {
+        let path = unix("packages/a/package.json");
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, r#""packages/a/package.json""#);
+        assert_eq!(
+            serde_json::from_str::<RelativeUnixPathBuf>(&json).unwrap(),
+            path
+        );
+        assert_eq!(serde_json::to_string(&*path).unwrap(), json);
+        #[cfg(not(windows))]
+        assert!(serde_json::from_str::<RelativeUnixPathBuf>(r#""/packages/a""#).is_err());
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_convert_from_windows_path() {
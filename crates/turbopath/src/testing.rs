@@ -0,0 +1,135 @@
+//! [proptest] strategies that generate valid values of each path type, so
+//! path manipulations can be tested for round-trips on all platforms.
+//!
+//! Enabled by the `testing` feature.
+
+use std::path::MAIN_SEPARATOR;
+
+use proptest::{collection::vec, prelude::*};
+
+use crate::{
+    AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeSystemPathBuf, RelativeUnixPathBuf,
+};
+
+/// A single path segment. Segments never start with a `.`, so `.` and `..`
+/// are never generated.
+pub fn segment() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9_][a-zA-Z0-9_.-]{0,7}"
+}
+
+/// One to five segments, joined with `separator`.
+fn segments(separator: char) -> impl Strategy<Value = String> {
+    vec(segment(), 1..=5).prop_map(move |segments| segments.join(&separator.to_string()))
+}
+
+pub fn relative_unix_path_buf() -> impl Strategy<Value = RelativeUnixPathBuf> {
+    segments('/').prop_map(|path| RelativeUnixPathBuf::new(path).unwrap())
+}
+
+pub fn relative_system_path_buf() -> impl Strategy<Value = RelativeSystemPathBuf> {
+    segments(MAIN_SEPARATOR).prop_map(|path| RelativeSystemPathBuf::new(path).unwrap())
+}
+
+pub fn anchored_system_path_buf() -> impl Strategy<Value = AnchoredSystemPathBuf> {
+    segments(MAIN_SEPARATOR)
+        .prop_map(|path| AnchoredSystemPathBuf::try_from(std::path::Path::new(&path)).unwrap())
+}
+
+pub fn absolute_system_path_buf() -> impl Strategy<Value = AbsoluteSystemPathBuf> {
+    let root = if cfg!(windows) { "C:\\" } else { "/" };
+    segments(MAIN_SEPARATOR)
+        .prop_map(move |path| AbsoluteSystemPathBuf::new(format!("{root}{path}")).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PathInterner;
+
+    proptest! {
+        #[test]
+        fn generated_paths_are_valid(
+            absolute in absolute_system_path_buf(),
+            anchored in anchored_system_path_buf(),
+            relative in relative_system_path_buf(),
+            unix in relative_unix_path_buf(),
+        ) {
+            absolute.debug_assert_valid();
+            anchored.debug_assert_valid();
+            relative.debug_assert_valid();
+            unix.debug_assert_valid();
+        }
+
+        #[test]
+        fn anchor_and_resolve_round_trip(
+            base in absolute_system_path_buf(),
+            anchored in anchored_system_path_buf(),
+        ) {
+            let resolved = base.resolve(&anchored);
+            resolved.debug_assert_valid();
+            let reanchored = base.anchor(&resolved).unwrap();
+            reanchored.debug_assert_valid();
+            prop_assert_eq!(reanchored, anchored);
+        }
+
+        #[test]
+        fn anchor_at_and_reanchor_round_trip(
+            base in absolute_system_path_buf(),
+            package in anchored_system_path_buf(),
+            anchored in anchored_system_path_buf(),
+        ) {
+            let resolved = base.resolve(&anchored);
+            prop_assert_eq!(resolved.anchor_at(&base).unwrap(), anchored.clone());
+            let package_root = base.resolve(&package);
+            let in_repo = package.as_path().join(anchored.as_path());
+            let in_repo = AnchoredSystemPathBuf::try_from(in_repo.as_path()).unwrap();
+            let in_package = in_repo.reanchor(&base, &package_root).unwrap();
+            in_package.debug_assert_valid();
+            prop_assert_eq!(&in_package, &anchored);
+            prop_assert_eq!(in_package.reanchor(&package_root, &base).unwrap(), in_repo);
+        }
+
+        #[test]
+        fn relative_to_and_resolve_round_trip(
+            base in absolute_system_path_buf(),
+            path in absolute_system_path_buf(),
+        ) {
+            let relative = path.relative_to(&base).unwrap();
+            relative.debug_assert_valid();
+            prop_assert!(base.resolve_unix(&relative).clean().eq_normalized(&path));
+        }
+
+        #[test]
+        fn anchored_to_unix_round_trip(anchored in anchored_system_path_buf()) {
+            let unix = anchored.to_unix();
+            unix.debug_assert_valid();
+            let round_tripped = AnchoredSystemPathBuf::try_from(unix.as_path()).unwrap();
+            prop_assert_eq!(round_tripped, anchored);
+        }
+
+        #[test]
+        fn system_and_unix_round_trip(unix in relative_unix_path_buf()) {
+            let system = RelativeSystemPathBuf::new(unix.as_path()).unwrap();
+            system.debug_assert_valid();
+            let round_tripped = RelativeUnixPathBuf::new(system.as_path()).unwrap();
+            round_tripped.debug_assert_valid();
+            prop_assert_eq!(round_tripped, unix);
+        }
+
+        #[test]
+        fn from_unknown_keeps_valid_paths(absolute in absolute_system_path_buf()) {
+            let repaired = AbsoluteSystemPathBuf::from_unknown(absolute.to_str().unwrap());
+            prop_assert_eq!(repaired.unwrap(), absolute);
+        }
+
+        #[test]
+        fn intern_and_resolve_round_trip(paths in vec(relative_unix_path_buf(), 1..8)) {
+            let mut interner = PathInterner::new();
+            let ids = paths.iter().map(|path| interner.intern(path)).collect::<Vec<_>>();
+            for (path, id) in paths.iter().zip(&ids) {
+                prop_assert_eq!(&interner.resolve(*id), path);
+                prop_assert_eq!(interner.get(path), Some(*id));
+            }
+        }
+    }
+}
@@ -80,6 +80,7 @@ pub extern "C" fn changed_files(buffer: Buffer) -> Buffer {
         req.turbo_root.into(),
         req.from_commit.as_deref(),
         &req.to_commit,
+        true,
     ) {
         Ok(files) => {
             let files: Vec<_> = files.into_iter().collect();
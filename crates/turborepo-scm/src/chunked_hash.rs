@@ -0,0 +1,424 @@
+//! Incremental hashing of large files using content-defined chunking.
+//!
+//! Files are split into chunks with [FastCDC](https://www.usenix.org/conference/atc16/technical-sessions/presentation/xia),
+//! whose boundaries depend on the content around them rather than on their
+//! offset. An edit therefore only changes the chunks it touches, and the
+//! digests of all other chunks can be reused from the previous hash of the
+//! file. Files whose mtime and size didn't change are not read at all.
+//!
+//! The resulting hashes are *not* git object hashes. Pass `verify: true` to
+//! [ChunkedHasher::hash_file] to get the exact git blob hash instead.
+
+use std::{
+    collections::HashMap,
+    fs::{File, Metadata},
+    io::{ErrorKind, Read},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use git2::{ObjectType, Oid};
+use turbopath::AbsoluteSystemPathBuf;
+
+use crate::Error;
+
+/// Random values that are mixed into the rolling hash for every byte.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64, so the table doesn't need to be spelled out.
+    let mut table = [0; 256];
+    let mut state: u64 = 0x2d35_8dcc_aa6c_78a5;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Sizes that control how files are split into chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingConfig {
+    /// Files smaller than this are always hashed as a whole.
+    pub min_file_size: u64,
+    /// The size that chunks are normalized towards. Must be a power of two.
+    pub avg_chunk_size: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            min_file_size: 16 * 1024 * 1024,
+            avg_chunk_size: 1024 * 1024,
+        }
+    }
+}
+
+/// Finds chunk boundaries with normalized chunking as described in the FastCDC
+/// paper.
+#[derive(Debug, Clone, Copy)]
+struct Chunker {
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    /// Mask used before reaching `avg_size`, which makes cuts less likely.
+    mask_small: u64,
+    /// Mask used after reaching `avg_size`, which makes cuts more likely.
+    mask_large: u64,
+}
+
+impl Chunker {
+    fn new(avg_size: usize) -> Self {
+        assert!(
+            avg_size.is_power_of_two() && avg_size >= 64,
+            "average chunk size must be a power of two of at least 64 bytes"
+        );
+        let bits = avg_size.trailing_zeros();
+        // The gear hash shifts left, so its high bits depend on the most bytes.
+        let mask = |bits: u32| !(u64::MAX >> bits);
+        Self {
+            min_size: avg_size / 4,
+            avg_size,
+            max_size: avg_size * 8,
+            mask_small: mask(bits + 1),
+            mask_large: mask(bits - 1),
+        }
+    }
+
+    /// Returns the length of the first chunk of `data`. `data` must contain
+    /// at least `max_size` bytes unless it's the end of the file.
+    fn cut(&self, data: &[u8]) -> usize {
+        if data.len() <= self.min_size {
+            return data.len();
+        }
+        let max = data.len().min(self.max_size);
+        let normal = max.min(self.avg_size);
+        let mut hash = 0u64;
+        for (i, byte) in data.iter().enumerate().take(max).skip(self.min_size) {
+            hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+            let mask = if i < normal {
+                self.mask_small
+            } else {
+                self.mask_large
+            };
+            if hash & mask == 0 {
+                return i + 1;
+            }
+        }
+        max
+    }
+}
+
+/// Identity of a chunk's content, used to look up its digest. BLAKE3 is
+/// collision resistant like the digest itself, so a chunk is never mistaken
+/// for another one, but it's much faster than the collision detecting SHA-1
+/// of git.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ChunkKey([u8; blake3::OUT_LEN]);
+
+impl ChunkKey {
+    fn new(data: &[u8]) -> Self {
+        Self(*blake3::hash(data).as_bytes())
+    }
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    mtime: SystemTime,
+    size: u64,
+    hash: String,
+    chunks: HashMap<ChunkKey, Oid>,
+}
+
+/// Hashes files, caching chunk digests between calls so that only the changed
+/// regions of a large file are rehashed.
+///
+/// The cache is keyed by path and assumes that a file whose mtime and size
+/// didn't change still has the same content.
+#[derive(Debug)]
+pub struct ChunkedHasher {
+    config: ChunkingConfig,
+    chunker: Chunker,
+    files: HashMap<PathBuf, CachedFile>,
+    /// Number of chunks whose digest was computed, for tests.
+    hashed_chunks: usize,
+}
+
+impl ChunkedHasher {
+    pub fn new(config: ChunkingConfig) -> Self {
+        Self {
+            config,
+            chunker: Chunker::new(config.avg_chunk_size),
+            files: HashMap::new(),
+            hashed_chunks: 0,
+        }
+    }
+
+    /// Returns the hash of the file at `path`.
+    ///
+    /// With `verify`, or for files smaller than
+    /// [ChunkingConfig::min_file_size], this is the git blob hash of the
+    /// file. Otherwise it's a hash of the file's chunk digests.
+    pub fn hash_file(
+        &mut self,
+        path: &AbsoluteSystemPathBuf,
+        verify: bool,
+    ) -> Result<String, Error> {
+        let metadata = path.as_path().metadata()?;
+        if verify || metadata.len() < self.config.min_file_size {
+            return Ok(Oid::hash_file(ObjectType::Blob, path.as_path())?.to_string());
+        }
+        self.hash_chunks(path, metadata)
+    }
+
+    /// Returns the chunked hash of the file at `path`, or `None` if it isn't
+    /// a regular file of at least [ChunkingConfig::min_file_size] bytes and
+    /// should be hashed like any other file. Doesn't follow symlinks.
+    pub fn hash_large_file(
+        &mut self,
+        path: &AbsoluteSystemPathBuf,
+    ) -> Result<Option<String>, Error> {
+        let metadata = path.symlink_metadata()?;
+        if !metadata.is_file() || metadata.len() < self.config.min_file_size {
+            return Ok(None);
+        }
+        self.hash_chunks(path, metadata).map(Some)
+    }
+
+    fn hash_chunks(
+        &mut self,
+        path: &AbsoluteSystemPathBuf,
+        metadata: Metadata,
+    ) -> Result<String, Error> {
+        let size = metadata.len();
+        let mtime = metadata.modified()?;
+        if let Some(cached) = self.files.get(path.as_path()) {
+            if cached.mtime == mtime && cached.size == size {
+                return Ok(cached.hash.clone());
+            }
+        }
+        let previous_chunks = self
+            .files
+            .remove(path.as_path())
+            .map(|cached| cached.chunks)
+            .unwrap_or_default();
+
+        let mut file = File::open(path.as_path())?;
+        let mut chunks = HashMap::new();
+        let mut digests = Vec::new();
+        let mut buffer = Vec::with_capacity(self.chunker.max_size);
+        let mut eof = false;
+        loop {
+            if !eof {
+                eof = fill(&mut file, &mut buffer, self.chunker.max_size)?;
+            }
+            if buffer.is_empty() {
+                break;
+            }
+            let len = self.chunker.cut(&buffer);
+            let data = &buffer[..len];
+            let key = ChunkKey::new(data);
+            let digest = match previous_chunks.get(&key).or_else(|| chunks.get(&key)) {
+                Some(digest) => *digest,
+                None => {
+                    self.hashed_chunks += 1;
+                    Oid::hash_object(ObjectType::Blob, data)?
+                }
+            };
+            chunks.insert(key, digest);
+            digests.extend_from_slice(digest.as_bytes());
+            buffer.drain(..len);
+        }
+
+        let hash = Oid::hash_object(ObjectType::Blob, &digests)?.to_string();
+        self.files.insert(
+            path.as_path().to_path_buf(),
+            CachedFile {
+                mtime,
+                size,
+                hash: hash.clone(),
+                chunks,
+            },
+        );
+        Ok(hash)
+    }
+
+    /// Drops the cached chunk digests of `path`.
+    pub fn forget(&mut self, path: &AbsoluteSystemPathBuf) {
+        self.files.remove(path.as_path());
+    }
+}
+
+/// Reads from `file` until `buffer` holds `len` bytes. Returns whether the end
+/// of the file was reached.
+fn fill(file: &mut File, buffer: &mut Vec<u8>, len: usize) -> Result<bool, Error> {
+    let start = buffer.len();
+    buffer.resize(len, 0);
+    let mut filled = start;
+    while filled < len {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => {
+                buffer.truncate(filled);
+                return Ok(true);
+            }
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn config() -> ChunkingConfig {
+        ChunkingConfig {
+            min_file_size: 1024,
+            avg_chunk_size: 1024,
+        }
+    }
+
+    fn pseudo_random(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn file(dir: &tempfile::TempDir, name: &str, contents: &[u8]) -> AbsoluteSystemPathBuf {
+        let path = dunce::canonicalize(dir.path()).unwrap().join(name);
+        fs::write(&path, contents).unwrap();
+        AbsoluteSystemPathBuf::new(path).unwrap()
+    }
+
+    #[test]
+    fn test_cut_respects_bounds() {
+        let chunker = Chunker::new(1024);
+        let data = pseudo_random(100_000, 1);
+        let mut rest = data.as_slice();
+        while !rest.is_empty() {
+            let len = chunker.cut(rest);
+            assert!(len <= chunker.max_size);
+            assert!(len >= chunker.min_size || len == rest.len());
+            rest = &rest[len..];
+        }
+    }
+
+    #[test]
+    fn test_only_changed_chunks_are_rehashed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut contents = pseudo_random(200_000, 2);
+        let path = file(&dir, "large.bin", &contents);
+
+        let mut hasher = ChunkedHasher::new(config());
+        let first = hasher.hash_file(&path, false).unwrap();
+        let initial_chunks = hasher.hashed_chunks;
+        assert!(initial_chunks > 10);
+
+        // Unchanged files are served from the cache.
+        assert_eq!(hasher.hash_file(&path, false).unwrap(), first);
+        assert_eq!(hasher.hashed_chunks, initial_chunks);
+
+        // Inserting bytes in the middle only affects the surrounding chunks.
+        contents.splice(100_000..100_000, *b"inserted");
+        fs::write(path.as_path(), &contents).unwrap();
+        let second = hasher.hash_file(&path, false).unwrap();
+        assert_ne!(first, second);
+        assert!(hasher.hashed_chunks - initial_chunks <= 3);
+
+        // A fresh hasher arrives at the same hash.
+        let mut fresh = ChunkedHasher::new(config());
+        assert_eq!(fresh.hash_file(&path, false).unwrap(), second);
+    }
+
+    #[test]
+    fn test_same_size_edits_are_rehashed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut contents = pseudo_random(50_000, 4);
+        let path = file(&dir, "large.bin", &contents);
+
+        let mut hasher = ChunkedHasher::new(config());
+        let first = hasher.hash_file(&path, false).unwrap();
+        let initial_chunks = hasher.hashed_chunks;
+
+        // The edited chunk keeps its length and boundaries, so only its
+        // content tells it apart from the cached chunk.
+        contents[25_000] ^= 0xff;
+        fs::write(path.as_path(), &contents).unwrap();
+        hasher.forget(&path);
+        let second = hasher.hash_file(&path, false).unwrap();
+        assert_ne!(first, second);
+        assert!(hasher.hashed_chunks > initial_chunks);
+        assert_eq!(
+            ChunkedHasher::new(config())
+                .hash_file(&path, false)
+                .unwrap(),
+            second
+        );
+    }
+
+    #[test]
+    fn test_repeated_chunks_are_hashed_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let block = pseudo_random(20_000, 5);
+        let path = file(&dir, "repeated.bin", &[&block[..], &block[..]].concat());
+
+        let mut hasher = ChunkedHasher::new(config());
+        hasher.hash_file(&path, false).unwrap();
+        let repeated_chunks = hasher.hashed_chunks;
+        let mut fresh = ChunkedHasher::new(config());
+        fresh
+            .hash_file(&file(&dir, "block.bin", &block), false)
+            .unwrap();
+        // Apart from the chunks around the seam, the second copy reuses the
+        // digests of the first.
+        assert!(repeated_chunks <= fresh.hashed_chunks + 2);
+    }
+
+    #[test]
+    fn test_hash_large_file_skips_small_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = file(&dir, "small.txt", b"hello\n");
+        let large = file(&dir, "large.bin", &pseudo_random(10_000, 6));
+
+        let mut hasher = ChunkedHasher::new(config());
+        assert_eq!(hasher.hash_large_file(&small).unwrap(), None);
+        assert_eq!(
+            hasher.hash_large_file(&large).unwrap(),
+            Some(hasher.hash_file(&large, false).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_verify_and_small_files_use_git_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = file(&dir, "small.txt", b"hello\n");
+        let large = file(&dir, "large.bin", &pseudo_random(10_000, 3));
+
+        let mut hasher = ChunkedHasher::new(config());
+        assert_eq!(
+            hasher.hash_file(&small, false).unwrap(),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+        assert_eq!(
+            hasher.hash_file(&large, true).unwrap(),
+            Oid::hash_file(ObjectType::Blob, large.as_path())
+                .unwrap()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Paths of git subprocesses on Windows.
+//!
+//! Packages in deep monorepos can have paths that are longer than `MAX_PATH`
+//! (260 characters), and roots can be verbatim paths like `\\?\C:\repo` or
+//! `\\?\UNC\server\share\repo`, e.g. when they were canonicalized with std.
+//! Both need care when spawning git:
+//!
+//! * The working directory of a process can only exceed `MAX_PATH` when it's a
+//!   verbatim path, see [current_dir].
+//! * git doesn't understand verbatim paths in its arguments or input, but
+//!   handles long paths itself when `core.longpaths` is enabled, see [argument]
+//!   and [git_command].
+//!
+//! On other platforms, paths are passed to git as they are.
+
+use std::{borrow::Cow, path::Path, process::Command};
+
+/// The maximum length of the working directory of a process without the
+/// verbatim prefix, which is `MAX_PATH` minus room for a file name in 8.3
+/// format.
+#[cfg_attr(not(windows), allow(dead_code))]
+const MAX_DIR_PATH: usize = 248;
+
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Creates a git command that runs in `cwd`.
+pub(crate) fn git_command(cwd: &Path) -> Command {
+    let mut command = Command::new("git");
+    #[cfg(windows)]
+    command.args(["-c", "core.longpaths=true"]);
+    command.current_dir(current_dir(cwd));
+    command
+}
+
+/// `path` as the working directory of a process: verbatim if it's too long
+/// to be used otherwise, and without the verbatim prefix if it isn't.
+pub(crate) fn current_dir(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    if let Some(path_str) = path.to_str() {
+        let converted = if path_str.len() >= MAX_DIR_PATH {
+            verbatim(path_str)
+        } else {
+            strip_verbatim(path_str)
+        };
+        if let Some(converted) = converted {
+            return Cow::Owned(converted.into());
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// `path` as an argument or in the input of git, without the verbatim
+/// prefix.
+pub(crate) fn argument(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    if let Some(path) = path.to_str().and_then(strip_verbatim) {
+        return Cow::Owned(path.into());
+    }
+    Cow::Borrowed(path)
+}
+
+/// Adds the verbatim prefix to an absolute Windows path, e.g. `C:\repo` or
+/// `\\server\share\repo`. Returns `None` for paths that are already verbatim
+/// or that are relative.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn verbatim(path: &str) -> Option<String> {
+    if path.starts_with(VERBATIM_PREFIX) {
+        return None;
+    }
+    // Verbatim paths are passed to the file system as they are, so they must
+    // only contain backslashes.
+    let path = path.replace('/', "\\");
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return Some(format!("{}{}", VERBATIM_UNC_PREFIX, unc));
+    }
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\" {
+        return Some(format!("{}{}", VERBATIM_PREFIX, path));
+    }
+    None
+}
+
+/// Removes the verbatim prefix from a Windows path, e.g. `\\?\C:\repo` or
+/// `\\?\UNC\server\share\repo`. Returns `None` for paths that aren't
+/// verbatim, or that have no equivalent without the prefix, like
+/// `\\?\Volume{...}\repo`.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn strip_verbatim(path: &str) -> Option<String> {
+    if let Some(unc) = path.strip_prefix(VERBATIM_UNC_PREFIX) {
+        return Some(format!(r"\\{}", unc));
+    }
+    let path = path.strip_prefix(VERBATIM_PREFIX)?;
+    let bytes = path.as_bytes();
+    (bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == b":\\")
+        .then(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip_verbatim, verbatim};
+
+    #[test]
+    fn test_verbatim() {
+        assert_eq!(verbatim(r"C:\repo").as_deref(), Some(r"\\?\C:\repo"));
+        assert_eq!(
+            verbatim("C:/repo/packages/a").as_deref(),
+            Some(r"\\?\C:\repo\packages\a")
+        );
+        assert_eq!(
+            verbatim(r"\\server\share\repo").as_deref(),
+            Some(r"\\?\UNC\server\share\repo")
+        );
+        assert_eq!(verbatim(r"\\?\C:\repo"), None);
+        assert_eq!(verbatim(r"repo\packages"), None);
+    }
+
+    #[test]
+    fn test_strip_verbatim() {
+        assert_eq!(strip_verbatim(r"\\?\C:\repo").as_deref(), Some(r"C:\repo"));
+        assert_eq!(
+            strip_verbatim(r"\\?\UNC\server\share\repo").as_deref(),
+            Some(r"\\server\share\repo")
+        );
+        assert_eq!(strip_verbatim(r"C:\repo"), None);
+        assert_eq!(strip_verbatim(r"\\?\Volume{1234}\repo"), None);
+    }
+
+    #[test]
+    fn test_round_trip_long_path() {
+        let long = format!(r"C:\repo\{}", "nested\\".repeat(40));
+        assert!(long.len() > super::MAX_DIR_PATH);
+        assert_eq!(strip_verbatim(&verbatim(&long).unwrap()), Some(long));
+    }
+}
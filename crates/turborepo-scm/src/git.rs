@@ -1,17 +1,66 @@
 use std::{
     backtrace::Backtrace,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
     process::Command,
+    sync::{Arc, Mutex},
 };
 
-use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf};
 
-use crate::Error;
+use crate::{
+    command_path::git_command,
+    nul_records::{decode_path, unquote_path},
+    package_deps::invalid_output,
+    process::{self, ProcessLimits},
+    repository::GitRepository,
+    Error,
+};
+
+/// The oldest version of git that is supported, as `(major, minor)`. `git
+/// status --no-renames`, which hashing relies on, was added in git 2.18.
+pub const MIN_GIT_VERSION: (u32, u32) = (2, 18);
+
+/// Checks that git is installed and at least [MIN_GIT_VERSION], and returns
+/// its version, e.g. `2.39.5`.
+pub fn check_version() -> Result<String, Error> {
+    let cwd = std::env::current_dir()?;
+    let stdout = process::run(
+        Command::new("git").arg("--version"),
+        &cwd,
+        &ProcessLimits::none(),
+    )?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    let Some((version, major, minor)) = parse_version(&stdout) else {
+        return Err(invalid_output("--version", stdout.trim()));
+    };
+    if (major, minor) < MIN_GIT_VERSION {
+        return Err(Error::UnsupportedGitVersion(
+            version.to_string(),
+            Backtrace::capture(),
+        ));
+    }
+    Ok(version.to_string())
+}
+
+/// Parses the output of `git --version`, e.g. `git version 2.39.5` or
+/// `git version 2.37.1.windows.1`, into the version and its major and minor
+/// components.
+fn parse_version(output: &str) -> Option<(&str, u32, u32)> {
+    let version = output
+        .trim()
+        .strip_prefix("git version ")?
+        .split_whitespace()
+        .next()?;
+    let mut components = version.split('.');
+    let major = components.next()?.parse().ok()?;
+    let minor = components.next()?.parse().ok()?;
+    Some((version, major, minor))
+}
 
 /// Finds the changed files in a repository between index and working directory
-/// (unstaged changes) and between two commits. Includes untracked files,
-/// i.e. files not yet in git.
+/// (unstaged changes) and between two commits. Optionally includes untracked
+/// files, i.e. files not yet in git.
 ///
 /// We shell out to git instead of using a git2 library because git2 doesn't
 /// support shallow clones, and therefore errors on repositories that
@@ -24,6 +73,8 @@ use crate::Error;
 ///   changes
 /// * `monorepo_root`: The path to which the results should be relative. Must be
 ///   an absolute path
+/// * `include_untracked`: Whether files that aren't tracked by git yet are
+///   included
 ///
 /// returns: Result<HashSet<String, RandomState>, Error>
 pub fn changed_files(
@@ -31,8 +82,15 @@ pub fn changed_files(
     turbo_root: PathBuf,
     from_commit: Option<&str>,
     to_commit: &str,
+    include_untracked: bool,
 ) -> Result<HashSet<String>, Error> {
     let git_root = AbsoluteSystemPathBuf::new(git_root)?;
+    if GitRepository::discover(&git_root)?.is_none() {
+        return Err(Error::NotARepository(
+            git_root.as_path().to_path_buf(),
+            Backtrace::capture(),
+        ));
+    }
     let turbo_root = AbsoluteSystemPathBuf::new(turbo_root)?;
     let turbo_root_relative_to_git_root = git_root.anchor(&turbo_root)?;
     let pathspec = turbo_root_relative_to_git_root.to_str()?;
@@ -44,48 +102,315 @@ pub fn changed_files(
     add_files_from_stdout(&mut files, &git_root, &turbo_root, output);
 
     if let Some(from_commit) = from_commit {
+        // Compare against the fork point, so that changes which were made on
+        // `from_commit` after branching off aren't reported.
+        let base = merge_base(&git_root, from_commit, to_commit)?;
         let output = execute_git_command(
             &git_root,
-            &[
-                "diff",
-                "--name-only",
-                &format!("{}...{}", from_commit, to_commit),
-            ],
+            &["diff", "--name-only", &base, to_commit],
             pathspec,
         )?;
 
         add_files_from_stdout(&mut files, &git_root, &turbo_root, output);
     }
 
-    let output = execute_git_command(
-        &git_root,
-        &["ls-files", "--others", "--exclude-standard"],
-        pathspec,
-    )?;
+    if include_untracked {
+        let output = execute_git_command(
+            &git_root,
+            &["ls-files", "--others", "--exclude-standard"],
+            pathspec,
+        )?;
 
-    add_files_from_stdout(&mut files, &git_root, &turbo_root, output);
+        add_files_from_stdout(&mut files, &git_root, &turbo_root, output);
+    }
 
     Ok(files)
 }
 
+/// Finds the packages that contain files which changed according to
+/// [changed_files].
+///
+/// # Arguments
+///
+/// * `package_dirs`: The directories of the workspace packages, relative to
+///   `turbo_root`. A changed file belongs to the innermost package that
+///   contains it. Pass an empty path for the root workspace to attribute files
+///   outside of all other packages to it; otherwise those files are ignored.
+///
+/// See [changed_files] for the remaining arguments.
+///
+/// returns: The directories of the changed packages, as passed in
+/// `package_dirs`
+pub fn changed_packages(
+    git_root: PathBuf,
+    turbo_root: PathBuf,
+    package_dirs: &[AnchoredSystemPathBuf],
+    from_commit: Option<&str>,
+    to_commit: &str,
+    include_untracked: bool,
+) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
+    let files = changed_files(
+        git_root,
+        turbo_root,
+        from_commit,
+        to_commit,
+        include_untracked,
+    )?;
+
+    let package_dirs = package_dirs
+        .iter()
+        .map(|dir| &**dir)
+        .collect::<HashSet<&AnchoredSystemPath>>();
+    Ok(files
+        .iter()
+        .filter_map(|file| {
+            AnchoredSystemPathBuf::try_from(Path::new(file))
+                .ok()?
+                .nearest_matching(|dir| package_dirs.contains(dir))
+                .map(ToOwned::to_owned)
+        })
+        .collect())
+}
+
+/// Finds the best common ancestor of `a` and `b`, i.e. the commit that one
+/// branched off from the other.
+///
+/// # Arguments
+///
+/// * `git_root`: The root of the repository
+/// * `a`, `b`: The commits, which can be any revision that git understands,
+///   e.g. a branch name or `HEAD~1`
+///
+/// returns: The hash of the merge base, or `Error::ShallowRepo` if the
+/// repository is a shallow clone that doesn't contain the merge base
+pub fn merge_base(git_root: &AbsoluteSystemPathBuf, a: &str, b: &str) -> Result<String, Error> {
+    let mut command = git_command(git_root.as_path());
+    command.args(["merge-base", a, b]);
+    let output = process::output(&mut command, git_root.as_path(), &ProcessLimits::none())?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+    // Without a merge base, git exits with 1 and doesn't print an error. In a
+    // shallow clone that usually means that the history is cut off.
+    if output.status.code() == Some(1) && output.stderr.is_empty() && is_shallow(git_root)? {
+        return Err(Error::ShallowRepo(
+            format!("{}...{}", a, b),
+            Backtrace::capture(),
+        ));
+    }
+    if output.status.code() == Some(1) && output.stderr.is_empty() {
+        return Err(Error::NoMergeBase(
+            a.to_string(),
+            b.to_string(),
+            Backtrace::capture(),
+        ));
+    }
+    Err(process::exit_status(&command, &output))
+}
+
+fn is_shallow(git_root: &AbsoluteSystemPathBuf) -> Result<bool, Error> {
+    let output = execute_git_command(git_root, &["rev-parse", "--is-shallow-repository"], "")?;
+    Ok(String::from_utf8_lossy(&output).trim() == "true")
+}
+
+/// The state of a repository that is recorded with a run, e.g. in run
+/// summaries and in the metadata of remote cache artifacts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoState {
+    /// The hash of the checked out commit, or `None` if the branch doesn't
+    /// have a commit yet.
+    pub sha: Option<String>,
+    /// The short name of the checked out branch, or `None` if `HEAD` is
+    /// detached.
+    pub branch: Option<String>,
+    /// The checked out commit described relative to the closest tag, like
+    /// `git describe --tags`, e.g. `v1.2.0-3-g1a2b3c4`, or `None` if no tag
+    /// is reachable.
+    pub tag: Option<String>,
+    /// Whether tracked files have uncommitted changes. Untracked files aren't
+    /// taken into account.
+    pub dirty: bool,
+}
+
+/// Reads the state of the repository that contains `path`.
+///
+/// `HEAD` is read from the git directory, so git only runs to describe the
+/// commit and to check for changes.
+pub fn repo_state(path: &AbsoluteSystemPathBuf) -> Result<RepoState, Error> {
+    let Some(repository) = GitRepository::discover(path)? else {
+        return Err(Error::NotARepository(
+            path.as_path().to_path_buf(),
+            Backtrace::capture(),
+        ));
+    };
+    let head = repository.head()?;
+    let tag = match head.sha() {
+        Some(_) => describe_tags(&repository)?,
+        None => None,
+    };
+    Ok(RepoState {
+        sha: head.sha().map(str::to_string),
+        branch: head.branch().map(str::to_string),
+        tag,
+        dirty: is_dirty(&repository)?,
+    })
+}
+
+fn describe_tags(repository: &GitRepository) -> Result<Option<String>, Error> {
+    let work_tree = repository.work_tree();
+    let output = process::output(
+        repository.command(work_tree).args(["describe", "--tags"]),
+        work_tree.as_path(),
+        &ProcessLimits::none(),
+    )?;
+    // git fails if no tag is reachable from `HEAD`.
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+fn is_dirty(repository: &GitRepository) -> Result<bool, Error> {
+    let work_tree = repository.work_tree();
+    let stdout = process::run(
+        repository
+            .command(work_tree)
+            .args(["status", "--porcelain", "--untracked-files=no"]),
+        work_tree.as_path(),
+        &ProcessLimits::none(),
+    )?;
+    Ok(!stdout.is_empty())
+}
+
+/// The metadata of a commit, e.g. to annotate run summaries and analytics
+/// events with the commit that was built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    /// The full hash of the commit.
+    pub sha: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// When the commit was authored, in seconds since the Unix epoch.
+    pub timestamp: i64,
+    /// The first line of the commit message.
+    pub subject: String,
+    /// The full hashes of the parents, which is empty for a root commit.
+    pub parents: Vec<String>,
+}
+
+/// The format of `git show` for [CommitInfo], with NUL separated fields.
+const COMMIT_INFO_FORMAT: &str = "--format=%H%x00%an%x00%ae%x00%at%x00%P%x00%s";
+
+/// Reads the metadata of the commit `sha`, which can be any revision that git
+/// understands, in the repository that contains `path`. See [CommitInfoCache]
+/// to read each commit once per run.
+pub fn commit_info(path: &AbsoluteSystemPathBuf, sha: &str) -> Result<CommitInfo, Error> {
+    let Some(repository) = GitRepository::discover(path)? else {
+        return Err(Error::NotARepository(
+            path.as_path().to_path_buf(),
+            Backtrace::capture(),
+        ));
+    };
+    read_commit_info(&repository, sha)
+}
+
+fn read_commit_info(repository: &GitRepository, sha: &str) -> Result<CommitInfo, Error> {
+    let work_tree = repository.work_tree();
+    let stdout = process::run(
+        repository
+            .command(work_tree)
+            .args([
+                "show",
+                "--no-patch",
+                "--no-show-signature",
+                COMMIT_INFO_FORMAT,
+            ])
+            .arg(format!("{}^{{commit}}", sha)),
+        work_tree.as_path(),
+        &ProcessLimits::none(),
+    )?;
+    let stdout = String::from_utf8_lossy(&stdout);
+    let record = stdout.trim_end_matches('\n');
+    let fields = record.split('\0').collect::<Vec<_>>();
+    let [sha, author_name, author_email, timestamp, parents, subject] = fields[..] else {
+        return Err(invalid_output("show", record));
+    };
+    let Ok(timestamp) = timestamp.parse() else {
+        return Err(invalid_output("show", record));
+    };
+    Ok(CommitInfo {
+        sha: sha.to_string(),
+        author_name: author_name.to_string(),
+        author_email: author_email.to_string(),
+        timestamp,
+        subject: subject.to_string(),
+        parents: parents.split_whitespace().map(str::to_string).collect(),
+    })
+}
+
+/// Caches [commit_info] for the duration of a run, so that run summaries,
+/// analytics and other consumers can ask for the same commit without running
+/// git again.
+///
+/// Commits are cached by the revision they are requested with, so a cache
+/// must not outlive a run, during which e.g. `HEAD` isn't expected to move.
+#[derive(Debug, Default)]
+pub struct CommitInfoCache {
+    /// Keyed by the common git directory of the repository and the revision.
+    commits: Mutex<HashMap<(PathBuf, String), Arc<CommitInfo>>>,
+}
+
+impl CommitInfoCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like [commit_info], but reads each commit only once.
+    pub fn commit_info(
+        &self,
+        path: &AbsoluteSystemPathBuf,
+        sha: &str,
+    ) -> Result<Arc<CommitInfo>, Error> {
+        let Some(repository) = GitRepository::discover(path)? else {
+            return Err(Error::NotARepository(
+                path.as_path().to_path_buf(),
+                Backtrace::capture(),
+            ));
+        };
+        let key = (
+            repository.common_dir().as_path().to_path_buf(),
+            sha.to_string(),
+        );
+        if let Some(info) = self
+            .commits
+            .lock()
+            .expect("commit cache is poisoned")
+            .get(&key)
+        {
+            return Ok(info.clone());
+        }
+        // Read the commit without holding the lock, so that other commits can
+        // be served in the meantime.
+        let info = Arc::new(read_commit_info(&repository, sha)?);
+        self.commits
+            .lock()
+            .expect("commit cache is poisoned")
+            .insert(key, info.clone());
+        Ok(info)
+    }
+}
+
 fn execute_git_command(
     git_root: &AbsoluteSystemPathBuf,
     args: &[&str],
     pathspec: &str,
 ) -> Result<Vec<u8>, Error> {
-    let mut command = Command::new("git");
-    command.args(args).current_dir(git_root);
+    let mut command = git_command(git_root.as_path());
+    command.args(args);
 
     add_pathspec(&mut command, pathspec);
 
-    let output = command.output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8(output.stderr).unwrap();
-        Err(Error::Git(stderr, Backtrace::capture()))
-    } else {
-        Ok(output.stdout)
-    }
+    process::run(&mut command, git_root.as_path(), &ProcessLimits::none())
 }
 
 fn add_pathspec(command: &mut Command, pathspec: &str) {
@@ -100,9 +425,13 @@ fn add_files_from_stdout(
     turbo_root: &AbsoluteSystemPathBuf,
     stdout: Vec<u8>,
 ) {
-    let stdout = String::from_utf8(stdout).unwrap();
-    for line in stdout.lines() {
-        let path = Path::new(line);
+    // Paths with unusual characters are quoted, see `core.quotepath`.
+    for line in stdout.split(|byte| *byte == b'\n') {
+        if line.is_empty() {
+            continue;
+        }
+        let line = decode_path(&unquote_path(line));
+        let path = Path::new(&line);
         let anchored_to_turbo_root_file_path =
             reanchor_path_from_git_root_to_turbo_root(git_root, turbo_root, path).unwrap();
         files.insert(
@@ -134,7 +463,10 @@ fn reanchor_path_from_git_root_to_turbo_root(
 /// * `from_commit`: The commit hash to checkout
 /// * `file_path`: The path to the file
 ///
-/// returns: Result<String, Error>
+/// Returns [Error::PathNotInRef] if `from_commit` exists but doesn't contain
+/// the file.
+///
+/// returns: Result<Vec<u8>, Error>
 pub fn previous_content(
     git_root: PathBuf,
     from_commit: &str,
@@ -152,24 +484,37 @@ pub fn previous_content(
         file_path.as_path().try_into()?
     };
 
-    let mut command = Command::new("git");
-    let command = command
-        .arg("show")
-        .arg(format!(
-            "{}:{}",
-            from_commit,
-            anchored_file_path.to_str().unwrap()
-        ))
-        .current_dir(&git_root);
+    let mut command = git_command(git_root.as_path());
+    command.arg("show").arg(format!(
+        "{}:{}",
+        from_commit,
+        anchored_file_path.to_str().unwrap()
+    ));
 
-    let output = command.output()?;
+    let output = process::output(&mut command, git_root.as_path(), &ProcessLimits::none())?;
     if output.status.success() {
-        Ok(output.stdout)
-    } else {
-        Err(Error::Git(
-            String::from_utf8_lossy(&output.stderr).to_string(),
+        return Ok(output.stdout);
+    }
+
+    // git reports a missing path and an unknown commit with the same exit
+    // code, so check whether the commit exists to tell them apart.
+    let commit_exists = process::output(
+        git_command(git_root.as_path())
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(format!("{}^{{commit}}", from_commit)),
+        git_root.as_path(),
+        &ProcessLimits::none(),
+    )?
+    .status
+    .success();
+    if commit_exists {
+        Err(Error::PathNotInRef(
+            anchored_file_path.to_str()?.to_string(),
+            from_commit.to_string(),
             Backtrace::capture(),
         ))
+    } else {
+        Err(process::exit_status(&command, &output))
     }
 }
 
@@ -185,10 +530,15 @@ mod tests {
 
     use git2::{Oid, Repository};
     use tempfile::TempDir;
-    use turbopath::PathValidationError;
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, PathValidationError};
 
-    use super::previous_content;
-    use crate::{git::changed_files, Error};
+    use super::{
+        commit_info, merge_base, parse_version, previous_content, repo_state, CommitInfoCache,
+    };
+    use crate::{
+        git::{changed_files, changed_packages},
+        Error,
+    };
 
     fn setup_repository() -> Result<(TempDir, Repository), Error> {
         let repo_root = tempfile::tempdir()?;
@@ -265,6 +615,7 @@ mod tests {
             tmp_dir.path().to_owned(),
             Some("HEAD~1"),
             "HEAD",
+            true,
         )
         .is_ok());
 
@@ -273,6 +624,7 @@ mod tests {
             tmp_dir.path().to_owned(),
             None,
             "HEAD",
+            true,
         )
         .is_ok());
 
@@ -295,7 +647,13 @@ mod tests {
         let first_commit_sha = first_commit_oid.to_string();
         let git_root = repo_root.path().to_owned();
         let turborepo_root = repo_root.path().to_owned();
-        let files = changed_files(git_root, turborepo_root, Some(&first_commit_sha), "HEAD")?;
+        let files = changed_files(
+            git_root,
+            turborepo_root,
+            Some(&first_commit_sha),
+            "HEAD",
+            true,
+        )?;
 
         assert_eq!(files, HashSet::from(["foo.js".to_string()]));
         Ok(())
@@ -336,6 +694,7 @@ mod tests {
             repo_root.path().to_path_buf(),
             Some(&third_commit_oid.to_string()),
             &fourth_commit_oid.to_string(),
+            true,
         )?;
 
         assert_eq!(
@@ -367,6 +726,7 @@ mod tests {
             turbo_root.to_path_buf(),
             None,
             "HEAD",
+            true,
         )?;
         assert_eq!(files, HashSet::from(["bar.js".to_string()]));
 
@@ -380,6 +740,7 @@ mod tests {
             turbo_root.to_path_buf(),
             None,
             "HEAD",
+            true,
         )?;
         assert_eq!(files, HashSet::from(["bar.js".to_string()]));
 
@@ -392,6 +753,7 @@ mod tests {
             turbo_root.to_path_buf(),
             Some(first_commit_oid.to_string().as_str()),
             second_commit_oid.to_string().as_str(),
+            true,
         )?;
         assert_eq!(files, HashSet::from(["bar.js".to_string()]));
 
@@ -406,6 +768,7 @@ mod tests {
             repo_root.path().join("subdir"),
             Some(first_commit_oid.to_string().as_str()),
             second_commit_oid.to_string().as_str(),
+            true,
         )?;
         assert_eq!(files, HashSet::from(["baz.js".to_string()]));
 
@@ -432,6 +795,7 @@ mod tests {
             repo_root.path().to_path_buf(),
             None,
             "HEAD",
+            true,
         )?;
         assert_eq!(files, HashSet::from(["bar.js".to_string()]));
 
@@ -461,6 +825,7 @@ mod tests {
             repo_root.path().join("subdir"),
             None,
             "HEAD",
+            true,
         )?;
 
         #[cfg(unix)]
@@ -480,6 +845,7 @@ mod tests {
             repo_root.path().join("subdir"),
             Some(first_commit.to_string().as_str()),
             repo.head()?.peel_to_commit()?.id().to_string().as_str(),
+            true,
         )?;
 
         #[cfg(unix)]
@@ -552,6 +918,7 @@ mod tests {
             repo_root.path().to_path_buf(),
             Some("HEAD^"),
             "HEAD",
+            true,
         )?;
         assert_eq!(files, HashSet::from(["foo.js".to_string()]));
 
@@ -569,12 +936,244 @@ mod tests {
             repo_root.path().to_path_buf(),
             Some("HEAD~1"),
             "release-1",
+            true,
         )?;
         assert_eq!(files, HashSet::from(["bar.js".to_string()]));
 
         Ok(())
     }
 
+    #[test]
+    fn test_repo_state() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(repo_root.path())?)?;
+
+        let state = repo_state(&root)?;
+        assert_eq!(state.sha, None);
+        assert!(state.branch.is_some());
+        assert_eq!(state.tag, None);
+        assert!(!state.dirty);
+
+        let file = repo_root.path().join("foo.js");
+        fs::write(&file, "let z = 0;")?;
+        let first_commit_oid = commit_file(&repo, Path::new("foo.js"), None)?;
+        let state = repo_state(&root)?;
+        assert_eq!(state.sha, Some(first_commit_oid.to_string()));
+        assert_eq!(state.tag, None);
+        assert!(!state.dirty);
+
+        repo.tag_lightweight("v1.0.0", &repo.find_object(first_commit_oid, None)?, false)?;
+        fs::write(&file, "let z = 1;")?;
+        let state = repo_state(&root)?;
+        assert_eq!(state.tag.as_deref(), Some("v1.0.0"));
+        assert!(state.dirty);
+
+        let second_commit_oid = commit_file(&repo, Path::new("foo.js"), Some(first_commit_oid))?;
+        repo.set_head_detached(second_commit_oid)?;
+        let state = repo_state(&root)?;
+        assert_eq!(state.sha, Some(second_commit_oid.to_string()));
+        assert_eq!(state.branch, None);
+        assert!(state.tag.unwrap().starts_with("v1.0.0-1-g"));
+        assert!(!state.dirty);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_info() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(repo_root.path())?)?;
+
+        fs::write(repo_root.path().join("foo.js"), "let z = 0;")?;
+        let first_commit_oid = commit_file(&repo, Path::new("foo.js"), None)?;
+        fs::write(repo_root.path().join("foo.js"), "let z = 1;")?;
+        let second_commit_oid = commit_file(&repo, Path::new("foo.js"), Some(first_commit_oid))?;
+
+        let info = commit_info(&root, "HEAD")?;
+        let commit = repo.find_commit(second_commit_oid)?;
+        assert_eq!(info.sha, second_commit_oid.to_string());
+        assert_eq!(info.author_name, "test");
+        assert_eq!(info.author_email, "test@example.com");
+        assert_eq!(info.timestamp, commit.author().when().seconds());
+        assert_eq!(info.subject, "Commit");
+        assert_eq!(info.parents, vec![first_commit_oid.to_string()]);
+
+        let info = commit_info(&root, &first_commit_oid.to_string())?;
+        assert!(info.parents.is_empty());
+
+        assert_matches!(
+            commit_info(&root, "does-not-exist"),
+            Err(Error::ExitStatus { .. })
+        );
+
+        let cache = CommitInfoCache::new();
+        let cached = cache.commit_info(&root, "HEAD")?;
+        assert_eq!(cached.sha, second_commit_oid.to_string());
+        assert!(std::sync::Arc::ptr_eq(
+            &cached,
+            &cache.commit_info(&root, "HEAD")?
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_changed_files_without_untracked() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        fs::write(repo_root.path().join("foo.js"), "let z = 0;")?;
+        commit_file(&repo, Path::new("foo.js"), None)?;
+
+        fs::write(repo_root.path().join("foo.js"), "let z = 1;")?;
+        fs::write(repo_root.path().join("bar.js"), "let y = 1;")?;
+
+        let files = changed_files(
+            repo_root.path().to_path_buf(),
+            repo_root.path().to_path_buf(),
+            None,
+            "HEAD",
+            false,
+        )?;
+        assert_eq!(files, HashSet::from(["foo.js".to_string()]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_changed_packages() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        for dir in ["packages/ui", "packages/ui-utils", "apps/web/nested"] {
+            fs::create_dir_all(repo_root.path().join(dir))?;
+        }
+        fs::write(repo_root.path().join("package.json"), "{}")?;
+        let first_commit = commit_file(&repo, Path::new("package.json"), None)?;
+
+        fs::write(repo_root.path().join("packages/ui/index.js"), "")?;
+        let second_commit =
+            commit_file(&repo, Path::new("packages/ui/index.js"), Some(first_commit))?;
+        fs::write(repo_root.path().join("apps/web/nested/page.js"), "")?;
+
+        let package_dirs = ["packages/ui", "packages/ui-utils", "apps/web"]
+            .map(|dir| AnchoredSystemPathBuf::try_from(Path::new(dir)).unwrap());
+        let packages = changed_packages(
+            repo_root.path().to_path_buf(),
+            repo_root.path().to_path_buf(),
+            &package_dirs,
+            Some(&first_commit.to_string()),
+            &second_commit.to_string(),
+            true,
+        )?;
+        assert_eq!(
+            packages,
+            HashSet::from([package_dirs[0].clone(), package_dirs[2].clone()])
+        );
+
+        // Files outside of all packages belong to the root workspace, if it's
+        // passed.
+        fs::write(repo_root.path().join("turbo.json"), "{}")?;
+        let root = AnchoredSystemPathBuf::default();
+        let packages = changed_packages(
+            repo_root.path().to_path_buf(),
+            repo_root.path().to_path_buf(),
+            &[root.clone(), package_dirs[0].clone()],
+            Some(&first_commit.to_string()),
+            &second_commit.to_string(),
+            false,
+        )?;
+        assert_eq!(packages, HashSet::from([package_dirs[0].clone()]));
+
+        let packages = changed_packages(
+            repo_root.path().to_path_buf(),
+            repo_root.path().to_path_buf(),
+            &[root.clone(), package_dirs[0].clone()],
+            Some(&first_commit.to_string()),
+            &second_commit.to_string(),
+            true,
+        )?;
+        assert_eq!(packages, HashSet::from([root, package_dirs[0].clone()]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_base_of_branches() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        fs::write(repo_root.path().join("foo.js"), "let z = 0;")?;
+        let base = commit_file(&repo, Path::new("foo.js"), None)?;
+        fs::write(repo_root.path().join("bar.js"), "let y = 1;")?;
+        let branch_tip = commit_file(&repo, Path::new("bar.js"), Some(base))?;
+        repo.branch("feature", &repo.find_commit(branch_tip)?, false)?;
+
+        // Check out the base commit again.
+        repo.set_head_detached(base)?;
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        fs::write(repo_root.path().join("baz.js"), "let x = 2;")?;
+        let main_tip = commit_file(&repo, Path::new("baz.js"), Some(base))?;
+
+        let git_root = AbsoluteSystemPathBuf::new(repo_root.path())?;
+        assert_eq!(
+            merge_base(&git_root, "feature", &main_tip.to_string())?,
+            base.to_string()
+        );
+
+        // Only the changes since the fork point are reported, not `bar.js`
+        // which was added on the branch that we compare against.
+        let files = changed_files(
+            repo_root.path().to_path_buf(),
+            repo_root.path().to_path_buf(),
+            Some("feature"),
+            "HEAD",
+            true,
+        )?;
+        assert_eq!(files, HashSet::from(["baz.js".to_string()]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_base_in_shallow_clone() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        fs::write(repo_root.path().join("foo.js"), "let z = 0;")?;
+        let base = commit_file(&repo, Path::new("foo.js"), None)?;
+        fs::write(repo_root.path().join("bar.js"), "let y = 1;")?;
+        let branch_tip = commit_file(&repo, Path::new("bar.js"), Some(base))?;
+        repo.branch("feature", &repo.find_commit(branch_tip)?, false)?;
+        repo.set_head_detached(base)?;
+        fs::write(repo_root.path().join("baz.js"), "let x = 2;")?;
+        let main_tip = commit_file(&repo, Path::new("baz.js"), Some(base))?;
+        repo.branch("main-line", &repo.find_commit(main_tip)?, false)?;
+
+        let clone_dir = tempfile::tempdir()?;
+        let output = Command::new("git")
+            .args([
+                "clone",
+                "--depth",
+                "1",
+                "--no-single-branch",
+                &format!("file://{}", repo_root.path().display()),
+                clone_dir.path().to_str().unwrap(),
+            ])
+            .output()?;
+        assert!(output.status.success());
+
+        let git_root = AbsoluteSystemPathBuf::new(clone_dir.path())?;
+        assert_matches!(
+            merge_base(&git_root, "origin/feature", "origin/main-line"),
+            Err(Error::ShallowRepo(_, _))
+        );
+        assert_matches!(
+            changed_files(
+                clone_dir.path().to_path_buf(),
+                clone_dir.path().to_path_buf(),
+                Some("origin/feature"),
+                "origin/main-line",
+                true,
+            ),
+            Err(Error::ShallowRepo(_, _))
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_error_cases() -> Result<(), Error> {
         let repo_dir = tempfile::tempdir()?;
@@ -583,27 +1182,42 @@ mod tests {
             repo_dir.path().to_path_buf(),
             None,
             "HEAD",
+            true,
         );
 
-        assert_matches!(repo_does_not_exist, Err(Error::Git(_, _)));
+        assert_matches!(repo_does_not_exist, Err(Error::NotARepository(_, _)));
 
-        let (repo_root, _repo) = setup_repository()?;
+        let (repo_root, repo) = setup_repository()?;
 
         let commit_does_not_exist = changed_files(
             repo_root.path().to_path_buf(),
             repo_root.path().to_path_buf(),
             None,
             "does-not-exist",
+            true,
         );
 
-        assert_matches!(commit_does_not_exist, Err(Error::Git(_, _)));
+        assert_matches!(commit_does_not_exist, Err(Error::ExitStatus { .. }));
 
         let file_does_not_exist = previous_content(
             repo_root.path().to_path_buf(),
             "HEAD",
             repo_root.path().join("does-not-exist"),
         );
-        assert_matches!(file_does_not_exist, Err(Error::Git(_, _)));
+        assert_matches!(file_does_not_exist, Err(Error::ExitStatus { .. }));
+
+        fs::write(repo_root.path().join("foo.js"), "let z = 0;")?;
+        commit_file(&repo, Path::new("foo.js"), None)?;
+
+        let file_not_in_commit = previous_content(
+            repo_root.path().to_path_buf(),
+            "HEAD",
+            repo_root.path().join("does-not-exist"),
+        );
+        assert_matches!(
+            file_not_in_commit,
+            Err(Error::PathNotInRef(path, rev, _)) if path == "does-not-exist" && rev == "HEAD"
+        );
 
         let turbo_root = tempfile::tempdir()?;
         let turbo_root_is_not_subdir_of_git_root = changed_files(
@@ -611,6 +1225,7 @@ mod tests {
             turbo_root.path().to_path_buf(),
             None,
             "HEAD",
+            true,
         );
 
         assert_matches!(
@@ -620,4 +1235,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("git version 2.39.5\n"),
+            Some(("2.39.5", 2, 39))
+        );
+        assert_eq!(
+            parse_version("git version 2.37.1.windows.1\n"),
+            Some(("2.37.1.windows.1", 2, 37))
+        );
+        assert_eq!(
+            parse_version("git version 2.37.1 (Apple Git-137.1)\n"),
+            Some(("2.37.1", 2, 37))
+        );
+        assert_eq!(parse_version("hub version 2.14.2"), None);
+        assert!(super::check_version().is_ok());
+    }
 }
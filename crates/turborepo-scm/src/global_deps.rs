@@ -0,0 +1,388 @@
+//! Hashes the files of a monorepo that affect the hashes of all tasks.
+//!
+//! Lockfiles, `turbo.json` and the files matched by the `globalDependencies`
+//! globs of `turbo.json` are hashed like the files of a package at the root of
+//! the monorepo, see [get_package_deps](crate::package_deps::get_package_deps),
+//! and combined into a single [GlobalDeps::digest]. Files that the globs match
+//! are hashed even if git ignores them, e.g. `.env` files.
+//!
+//! The digest is computed once per run, but tools that stay alive between
+//! runs, e.g. a daemon, can keep a [GlobalDepsCache]. It reuses the digest as
+//! long as the `HEAD` commit, the fingerprint of `git status` and the size
+//! and modification time of each hashed file stay the same. The fingerprint
+//! includes the size and modification time of each changed file, so further
+//! changes to a file that is already modified are picked up.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use sha2::{Digest, Sha256};
+use turbopath::{
+    glob::GlobSet, AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPath,
+    RelativeUnixPathBuf,
+};
+
+use crate::{
+    package_deps::{hash_files_without_git, parse_status, HashingOptions, PackageDepsHasher},
+    process::{self, ProcessLimits},
+    repository::GitRepository,
+    walk::Walker,
+    Error,
+};
+
+/// The lockfiles of the package managers that turbo supports.
+pub const LOCKFILES: &[&str] = &[
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+];
+
+pub const TURBO_JSON: &str = "turbo.json";
+
+/// The global inputs of a monorepo, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobalDeps {
+    /// The git object hashes of the files, with paths relative to the root of
+    /// the monorepo.
+    pub files: BTreeMap<RelativeUnixPathBuf, String>,
+    /// The SHA-256 of the paths and hashes of all files, in hex.
+    pub digest: String,
+}
+
+/// Hashes the global inputs of the monorepo at `turbo_root`, which are the
+/// lockfiles, `turbo.json` and the files that the `global_dependencies` globs
+/// match.
+pub fn global_deps(
+    turbo_root: &AbsoluteSystemPathBuf,
+    global_dependencies: &[&str],
+) -> Result<GlobalDeps, Error> {
+    hash_global_deps(&PackageDepsHasher::new(), turbo_root, global_dependencies)
+}
+
+fn hash_global_deps(
+    hasher: &PackageDepsHasher,
+    turbo_root: &AbsoluteSystemPathBuf,
+    global_dependencies: &[&str],
+) -> Result<GlobalDeps, Error> {
+    let includes = global_dependencies
+        .iter()
+        .filter(|glob| !glob.starts_with('!'))
+        .collect::<Vec<_>>();
+    let mut inputs = LOCKFILES.to_vec();
+    inputs.push(TURBO_JSON);
+    // Excluding globs only apply to the files of `global_dependencies`, not to
+    // lockfiles and `turbo.json`.
+    inputs.extend(includes.iter().copied());
+    let root = AnchoredSystemPathBuf::try_from(Path::new(""))?;
+    let mut files = hasher
+        .get_package_deps(turbo_root, &root, &inputs)?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    // The files that git ignores aren't listed by git, so they are found by
+    // walking the monorepo.
+    let includes = GlobSet::new(&includes)?;
+    if includes.has_includes() {
+        let walker = Walker::new(turbo_root.clone()).gitignore(false);
+        let ignored = hash_files_without_git(&walker, |path| {
+            RelativeUnixPath::new(Path::new(path)).map_or(false, |path| {
+                includes.is_included(path) && !files.contains_key(path)
+            })
+        })?;
+        files.extend(ignored);
+    }
+    let excludes = GlobSet::new(
+        &global_dependencies
+            .iter()
+            .filter(|glob| glob.starts_with('!'))
+            .collect::<Vec<_>>(),
+    )?;
+    if excludes.has_excludes() {
+        files.retain(|path, _| {
+            is_required(path.to_str().unwrap_or_default()) || !excludes.is_excluded(path)
+        });
+    }
+
+    let mut digest = Sha256::new();
+    for (path, hash) in &files {
+        digest.update(path.to_str()?.as_bytes());
+        digest.update([0]);
+        digest.update(hash.as_bytes());
+        digest.update([b'\n']);
+    }
+    Ok(GlobalDeps {
+        files,
+        digest: digest
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+    })
+}
+
+/// Whether `path` is always a global input, whatever `globalDependencies`
+/// excludes.
+fn is_required(path: &str) -> bool {
+    path == TURBO_JSON || LOCKFILES.contains(&path)
+}
+
+/// Caches [GlobalDeps] between runs, see the [module documentation](self).
+#[derive(Debug, Default)]
+pub struct GlobalDepsCache {
+    entry: Mutex<Option<CacheEntry>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    turbo_root: AbsoluteSystemPathBuf,
+    global_dependencies: Vec<String>,
+    hashing_options: HashingOptions,
+    fingerprint: String,
+    /// The size and modification time of the files of `global_deps`.
+    files_fingerprint: String,
+    global_deps: Arc<GlobalDeps>,
+}
+
+impl GlobalDepsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like [global_deps], with files hashed by `hasher`. Returns the cached
+    /// global inputs if they were hashed with the same options, and neither
+    /// `HEAD`, the status of the files nor the hashed files changed since.
+    /// Outside of git repositories and before the first commit, the files are
+    /// hashed every time.
+    pub fn global_deps(
+        &self,
+        hasher: &PackageDepsHasher,
+        turbo_root: &AbsoluteSystemPathBuf,
+        global_dependencies: &[&str],
+    ) -> Result<Arc<GlobalDeps>, Error> {
+        let Some(fingerprint) = fingerprint(turbo_root)? else {
+            return Ok(Arc::new(hash_global_deps(
+                hasher,
+                turbo_root,
+                global_dependencies,
+            )?));
+        };
+        let hashing_options = hasher.hashing_options();
+        if let Some(entry) = &*self.entry.lock().expect("global deps cache is poisoned") {
+            if entry.turbo_root == *turbo_root
+                && entry.global_dependencies == global_dependencies
+                && entry.hashing_options == hashing_options
+                && entry.fingerprint == fingerprint
+                && entry.files_fingerprint == files_fingerprint(turbo_root, &entry.global_deps)
+            {
+                return Ok(entry.global_deps.clone());
+            }
+        }
+
+        let global_deps = Arc::new(hash_global_deps(hasher, turbo_root, global_dependencies)?);
+        *self.entry.lock().expect("global deps cache is poisoned") = Some(CacheEntry {
+            turbo_root: turbo_root.clone(),
+            global_dependencies: global_dependencies
+                .iter()
+                .map(|glob| glob.to_string())
+                .collect(),
+            hashing_options,
+            fingerprint,
+            files_fingerprint: files_fingerprint(turbo_root, &global_deps),
+            global_deps: global_deps.clone(),
+        });
+        Ok(global_deps)
+    }
+}
+
+/// A digest of the `HEAD` commit and of the status of the files below
+/// `turbo_root`, or `None` if `turbo_root` isn't in a git repository with a
+/// commit.
+fn fingerprint(turbo_root: &AbsoluteSystemPathBuf) -> Result<Option<String>, Error> {
+    let Some(repository) = GitRepository::discover(turbo_root)? else {
+        return Ok(None);
+    };
+    let Some(head) = repository.head()?.sha().map(str::to_string) else {
+        return Ok(None);
+    };
+    let stdout = process::run(
+        repository.command(turbo_root).args([
+            "status",
+            "--untracked-files",
+            "--no-renames",
+            "-z",
+            "--",
+            ".",
+        ]),
+        turbo_root.as_path(),
+        &ProcessLimits::none(),
+    )?;
+
+    let mut digest = Sha256::new();
+    digest.update(head.as_bytes());
+    digest.update([0]);
+    digest.update(&stdout);
+    for entry in parse_status(&stdout)? {
+        // Paths are relative to the root of the working tree.
+        let path = RelativeUnixPath::new(Path::new(&entry.path))?;
+        update_metadata(&mut digest, &repository.work_tree().resolve_unix(path));
+    }
+    Ok(Some(
+        digest
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+    ))
+}
+
+/// A digest of the size and modification time of the files of
+/// `global_deps`, which covers the files that git ignores and thus aren't in
+/// the [fingerprint].
+fn files_fingerprint(turbo_root: &AbsoluteSystemPathBuf, global_deps: &GlobalDeps) -> String {
+    let mut digest = Sha256::new();
+    for path in global_deps.files.keys() {
+        digest.update(path.to_str().unwrap_or_default().as_bytes());
+        digest.update([0]);
+        update_metadata(&mut digest, &turbo_root.resolve_unix(path));
+    }
+    digest
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Adds the size and modification time of the file at `path` to `digest`.
+/// Deleted files have no metadata and add nothing.
+fn update_metadata(digest: &mut Sha256, path: &AbsoluteSystemPathBuf) {
+    if let Ok(metadata) = path.symlink_metadata() {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |mtime| mtime.as_nanos());
+        digest.update(metadata.len().to_le_bytes());
+        digest.update(mtime.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use turbopath::RelativeUnixPathBuf;
+
+    use super::{global_deps, GlobalDepsCache};
+    use crate::{
+        package_deps::{LfsMode, PackageDepsHasher},
+        testing::{git, setup_repository, write},
+    };
+
+    fn paths(global_deps: &super::GlobalDeps) -> Vec<&str> {
+        global_deps
+            .files
+            .keys()
+            .map(|path: &RelativeUnixPathBuf| path.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_global_deps() {
+        let (_tmp, root) = setup_repository();
+        write(&root, "turbo.json", "{}\n");
+        write(&root, "pnpm-lock.yaml", "lockfileVersion: 6\n");
+        write(&root, ".env", "A=1\n");
+        write(&root, "config/tsconfig.json", "{}\n");
+        write(&root, "config/local.json", "{}\n");
+        write(&root, "packages/a/package.json", "{}\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let deps = global_deps(&root, &[".env", "config/**", "!config/local.json"]).unwrap();
+        assert_eq!(
+            paths(&deps),
+            vec![
+                ".env",
+                "config/tsconfig.json",
+                "pnpm-lock.yaml",
+                "turbo.json"
+            ]
+        );
+        // Lockfiles and `turbo.json` can't be excluded.
+        let deps_without_lockfile = global_deps(&root, &["!pnpm-lock.yaml"]).unwrap();
+        assert_eq!(
+            paths(&deps_without_lockfile),
+            vec!["pnpm-lock.yaml", "turbo.json"]
+        );
+
+        // The digest only changes when a global input changes.
+        write(&root, "packages/a/package.json", "{\"name\": \"a\"}\n");
+        let unchanged = global_deps(&root, &[".env", "config/**", "!config/local.json"]).unwrap();
+        assert_eq!(unchanged.digest, deps.digest);
+        write(&root, ".env", "A=2\n");
+        let changed = global_deps(&root, &[".env", "config/**", "!config/local.json"]).unwrap();
+        assert_ne!(changed.digest, deps.digest);
+    }
+
+    #[test]
+    fn test_global_deps_cache() {
+        let (_tmp, root) = setup_repository();
+        write(&root, "turbo.json", "{}\n");
+        write(&root, "yarn.lock", "# yarn lockfile v1\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let hasher = PackageDepsHasher::new();
+        let cache = GlobalDepsCache::new();
+        let first = cache.global_deps(&hasher, &root, &[]).unwrap();
+        let second = cache.global_deps(&hasher, &root, &[]).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        write(&root, "yarn.lock", "# yarn lockfile v1\n\nchanged\n");
+        let changed = cache.global_deps(&hasher, &root, &[]).unwrap();
+        assert_ne!(changed.digest, first.digest);
+
+        // Other globs are hashed again.
+        write(&root, ".env", "A=1\n");
+        let with_env = cache.global_deps(&hasher, &root, &[".env"]).unwrap();
+        assert!(with_env
+            .files
+            .keys()
+            .any(|path| path.to_str().unwrap() == ".env"));
+
+        // Hashers with other options hash again.
+        let again = cache.global_deps(&hasher, &root, &[".env"]).unwrap();
+        assert!(Arc::ptr_eq(&again, &with_env));
+        let lfs_hasher = PackageDepsHasher::new().lfs(LfsMode::Oid);
+        let with_lfs = cache.global_deps(&lfs_hasher, &root, &[".env"]).unwrap();
+        assert!(!Arc::ptr_eq(&with_lfs, &with_env));
+    }
+
+    #[test]
+    fn test_global_deps_gitignored() {
+        let (_tmp, root) = setup_repository();
+        write(&root, ".gitignore", ".env\n");
+        write(&root, "turbo.json", "{}\n");
+        write(&root, ".env", "A=1\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let deps = global_deps(&root, &[".env"]).unwrap();
+        assert_eq!(paths(&deps), vec![".env", "turbo.json"]);
+
+        // Changes to ignored files don't show up in `git status`, but
+        // invalidate the cache.
+        let hasher = PackageDepsHasher::new();
+        let cache = GlobalDepsCache::new();
+        let first = cache.global_deps(&hasher, &root, &[".env"]).unwrap();
+        assert_eq!(first.digest, deps.digest);
+        write(&root, ".env", "A=22\n");
+        let changed = cache.global_deps(&hasher, &root, &[".env"]).unwrap();
+        assert_ne!(changed.digest, first.digest);
+    }
+}
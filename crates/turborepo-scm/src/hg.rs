@@ -0,0 +1,319 @@
+//! Support for Mercurial and Sapling repositories.
+//!
+//! Both are driven through their command line interface with `HGPLAIN` set,
+//! so that user configuration like aliases or localized output doesn't change
+//! what is parsed. Paths are printed with the `{path}` template keyword, which
+//! is always relative to the root of the repository.
+//!
+//! Files are hashed in-process with the same algorithm as git, so the hashes
+//! of a package don't depend on the version control system it's checked out
+//! with.
+
+use std::{backtrace::Backtrace, collections::HashSet, path::Path, process::Command};
+
+use turbopath::{
+    AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPathBuf,
+};
+
+use crate::{
+    ignore::{IgnoreFile, PackageIgnores},
+    package_deps::{default_concurrency, hash_file, hash_in_parallel, GitHashes, InputGlobs},
+    scm::Scm,
+    walk::Walker,
+    Error,
+};
+
+/// The executable of Mercurial.
+pub const MERCURIAL: &str = "hg";
+/// The executable of Sapling, whose command line interface is compatible with
+/// Mercurial's.
+pub const SAPLING: &str = "sl";
+
+/// A Mercurial or Sapling repository.
+#[derive(Debug, Clone)]
+pub struct Hg {
+    executable: &'static str,
+    root: AbsoluteSystemPathBuf,
+}
+
+impl Hg {
+    /// The Mercurial repository whose root is `root`.
+    pub fn mercurial(root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            executable: MERCURIAL,
+            root,
+        }
+    }
+
+    /// The Sapling repository whose root is `root`.
+    pub fn sapling(root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            executable: SAPLING,
+            root,
+        }
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Vec<u8>, Error> {
+        let output = Command::new(self.executable)
+            .args(args)
+            .current_dir(&self.root)
+            .env("HGPLAIN", "1")
+            .output()?;
+        if !output.status.success() {
+            return Err(Error::Hg(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+                Backtrace::capture(),
+            ));
+        }
+        Ok(output.stdout)
+    }
+
+    /// Runs `status` with `args` and returns the paths it reports, relative to
+    /// the root of the repository.
+    fn status(&self, args: &[&str], dir: &AnchoredSystemPath) -> Result<Vec<String>, Error> {
+        let pattern = path_pattern(dir)?;
+        let mut command = vec!["status", "--template", "{path}\\n"];
+        command.extend_from_slice(args);
+        command.extend_from_slice(&["--", &pattern]);
+        let stdout = String::from_utf8(self.run(&command)?).map_err(|e| {
+            Error::Hg(
+                format!("invalid utf-8 in status: {}", e),
+                Backtrace::capture(),
+            )
+        })?;
+        Ok(stdout.lines().map(str::to_string).collect())
+    }
+
+    /// Finds the best common ancestor of `a` and `b`.
+    fn ancestor(&self, a: &str, b: &str) -> Result<String, Error> {
+        let revset = format!("ancestor({}, {})", revision(a), revision(b));
+        let stdout = self.run(&["log", "--rev", &revset, "--template", "{node}"])?;
+        let node = String::from_utf8_lossy(&stdout).trim().to_string();
+        if node.is_empty() {
+            return Err(Error::Hg(
+                format!("{} and {} have no common history", a, b),
+                Backtrace::capture(),
+            ));
+        }
+        Ok(node)
+    }
+}
+
+impl Scm for Hg {
+    fn name(&self) -> &'static str {
+        self.executable
+    }
+
+    fn root(&self) -> &AbsoluteSystemPathBuf {
+        &self.root
+    }
+
+    fn get_package_deps(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        let full_pkg_path = turbo_root.resolve(package_path);
+        let package_dir = self.root.anchor(&full_pkg_path)?;
+        let package_prefix = package_path.to_unix();
+        let inputs = InputGlobs::new(inputs)?;
+        let ignores = PackageIgnores::new(
+            IgnoreFile::read(turbo_root)?,
+            package_prefix.to_str()?,
+            IgnoreFile::read(&full_pkg_path)?,
+        );
+
+        // Files that are tracked and still exist, and untracked files that
+        // aren't ignored.
+        let files = self.status(
+            &["--modified", "--added", "--clean", "--unknown"],
+            &package_dir,
+        )?;
+        let repo_prefix = package_dir.to_unix();
+        let repo_prefix = repo_prefix.to_str()?;
+        let mut to_hash = Vec::new();
+        for file in files {
+            let path = if repo_prefix.is_empty() {
+                file.as_str()
+            } else {
+                match file
+                    .strip_prefix(repo_prefix)
+                    .and_then(|path| path.strip_prefix('/'))
+                {
+                    Some(path) => path,
+                    None => continue,
+                }
+            };
+            if inputs.matches(path) && !ignores.is_ignored(path) {
+                to_hash.push(RelativeUnixPathBuf::new(path)?);
+            }
+        }
+
+        let mut hashes = GitHashes::new();
+        hash_in_parallel(
+            &to_hash,
+            default_concurrency(),
+            &mut hashes,
+            |chunk, hashes| {
+                for path in chunk {
+                    let full_path = full_pkg_path.resolve_unix(path);
+                    let file_type = full_path.symlink_metadata()?.file_type();
+                    if let Some(hash) = hash_file(&full_path, file_type)? {
+                        hashes.insert(path.clone(), hash.to_string());
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        let walker = Walker::new(full_pkg_path.clone());
+        inputs.add_included_files(&walker, &ignores, &mut hashes)?;
+        Ok(hashes)
+    }
+
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        from_commit: Option<&str>,
+        to_commit: &str,
+        include_untracked: bool,
+    ) -> Result<HashSet<String>, Error> {
+        let turbo_dir = self.root.anchor(turbo_root)?;
+        let changes = ["--modified", "--added", "--removed", "--deleted"];
+
+        let to_commit_rev = revision(to_commit);
+        let mut files = self.status(
+            &[&["--rev", &to_commit_rev], &changes[..]].concat(),
+            &turbo_dir,
+        )?;
+        if let Some(from_commit) = from_commit {
+            // Compare against the fork point, like git does.
+            let base = self.ancestor(from_commit, to_commit)?;
+            files.extend(self.status(
+                &[&["--rev", &base, "--rev", &to_commit_rev], &changes[..]].concat(),
+                &turbo_dir,
+            )?);
+        }
+        if include_untracked {
+            files.extend(self.status(&["--unknown"], &turbo_dir)?);
+        }
+
+        files
+            .into_iter()
+            .map(|file| {
+                let path = AnchoredSystemPathBuf::try_from(Path::new(&file))?
+                    .reanchor(&self.root, turbo_root)?;
+                Ok(path.to_str()?.to_string())
+            })
+            .collect()
+    }
+
+    fn previous_content(&self, from_commit: &str, file_path: &Path) -> Result<Vec<u8>, Error> {
+        let anchored_file_path = if file_path.is_absolute() {
+            self.root.anchor(&AbsoluteSystemPathBuf::new(file_path)?)?
+        } else {
+            AnchoredSystemPathBuf::try_from(file_path)?
+        };
+        let pattern = path_pattern(&anchored_file_path)?;
+        self.run(&["cat", "--rev", &revision(from_commit), "--", &pattern])
+    }
+}
+
+/// A pattern that matches the file or directory `path`, relative to the root
+/// of the repository, literally.
+fn path_pattern(path: &AnchoredSystemPath) -> Result<String, Error> {
+    let path = path.to_unix();
+    let path = path.to_str()?;
+    Ok(if path.is_empty() {
+        "path:.".to_string()
+    } else {
+        format!("path:{}", path)
+    })
+}
+
+/// Translates a git revision that refers to the checked out commit, like
+/// `HEAD` or `HEAD~2`, to the equivalent Mercurial revision. Other revisions,
+/// like hashes or bookmarks, are passed through.
+fn revision(rev: &str) -> String {
+    match rev.strip_prefix("HEAD") {
+        Some(rest) if rest.is_empty() || rest.starts_with(['~', '^']) => format!(".{}", rest),
+        _ => rev.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+
+    use super::{revision, Hg, MERCURIAL};
+    use crate::{
+        package_deps::GitHashes,
+        scm::Scm,
+        testing::{unix, HELLO, WORLD},
+    };
+
+    #[test]
+    fn test_get_package_deps() {
+        // Mercurial isn't installed everywhere the tests run.
+        if Command::new(MERCURIAL).arg("--version").output().is_err() {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(tmp.path()).unwrap()).unwrap();
+        let hg = |args: &[&str]| {
+            let output = Command::new(MERCURIAL)
+                .args(["--config", "ui.username=test"])
+                .args(args)
+                .current_dir(root.as_path())
+                .env("HGPLAIN", "1")
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "hg {:?} failed", args);
+        };
+        let write = |path: &str, contents: &str| {
+            let path = root.as_path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        hg(&["init"]);
+        write(".hgignore", "syntax: glob\n*.log\n");
+        write("packages/a/committed.txt", "hello\n");
+        write("packages/a/deleted.txt", "hello\n");
+        write("packages/a/modified.txt", "hello\n");
+        write("packages/b/other.txt", "hello\n");
+        hg(&["add", "--quiet"]);
+        hg(&["commit", "--quiet", "-m", "initial"]);
+        fs::remove_file(root.as_path().join("packages/a/deleted.txt")).unwrap();
+        write("packages/a/modified.txt", "world\n");
+        write("packages/a/dir/untracked.txt", "world\n");
+        write("packages/a/debug.log", "hello\n");
+
+        let scm = Hg::mercurial(root.clone());
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let hashes = scm.get_package_deps(&root, &package_path, &[]).unwrap();
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("modified.txt"), WORLD.to_string()),
+            (unix("dir/untracked.txt"), WORLD.to_string()),
+        ]);
+        assert_eq!(hashes, expected);
+
+        let hashes = scm
+            .get_package_deps(&root, &package_path, &["*.txt", "!modified.txt"])
+            .unwrap();
+        let expected = GitHashes::from([(unix("committed.txt"), HELLO.to_string())]);
+        assert_eq!(hashes, expected);
+    }
+
+    #[test]
+    fn test_revision() {
+        assert_eq!(revision("HEAD"), ".");
+        assert_eq!(revision("HEAD~2"), ".~2");
+        assert_eq!(revision("HEAD^"), ".^");
+        assert_eq!(revision("HEADLESS"), "HEADLESS");
+        assert_eq!(revision("main"), "main");
+        assert_eq!(revision("0123abcd"), "0123abcd");
+    }
+}
@@ -0,0 +1,246 @@
+//! Support for `.turboignore` files, which exclude files from package hashes.
+//!
+//! A `.turboignore` file can be placed at the root of the monorepo and in each
+//! package. Each line is a glob relative to the directory of the file, using
+//! a subset of the `.gitignore` syntax:
+//!
+//! * Blank lines and lines starting with `#` are skipped.
+//! * A pattern without a `/` matches files at any depth, e.g. `*.snap`.
+//! * A pattern ending with `/` matches everything in a directory.
+//! * A leading `!` includes files again that an earlier pattern excluded.
+//!
+//! Patterns of the package's file are applied after the ones of the root file,
+//! and the last matching pattern decides whether a file is ignored.
+//!
+//! When git is unavailable, `.gitignore` files are read with the same rules
+//! while walking the package, see [crate::walk].
+//!
+//! Patterns are matched with [GlobSet], like the `inputs` of packages.
+//! Patterns that aren't valid globs, e.g. with an unclosed `[`, are skipped.
+
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use turbopath::{glob::GlobSet, AbsoluteSystemPathBuf, RelativeUnixPath};
+
+use crate::Error;
+
+pub const TURBOIGNORE: &str = ".turboignore";
+pub const GITIGNORE: &str = ".gitignore";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnorePattern {
+    /// Matches the files that the pattern applies to.
+    glob: GlobSet,
+    /// Matches the directories that the pattern applies to, which is only the
+    /// directory itself for a pattern like `dist/`, and nothing for a pattern
+    /// like `dist/**`.
+    dir_glob: GlobSet,
+    negated: bool,
+}
+
+/// The patterns of a single `.turboignore` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IgnoreFile {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreFile {
+    pub fn parse(contents: &str) -> Self {
+        let patterns = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (negated, pattern) = match line.strip_prefix('!') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, line),
+                };
+                let dir_only = pattern.ends_with('/');
+                let glob = match pattern.strip_prefix('/') {
+                    Some(anchored) => anchored.trim_end_matches('/').to_string(),
+                    None if !pattern.trim_end_matches('/').contains('/') => {
+                        format!("**/{}", pattern.trim_end_matches('/'))
+                    }
+                    None => pattern.trim_end_matches('/').to_string(),
+                };
+                // `**` at the end of a glob also matches the directory before
+                // it, while in ignore files it only matches its contents.
+                let glob = match glob.strip_suffix("/**") {
+                    Some(dir) => format!("{}/**/*", dir),
+                    None => glob,
+                };
+                let (glob, dir_glob) = if dir_only {
+                    (format!("{}/**/*", glob), glob)
+                } else {
+                    (glob.clone(), glob)
+                };
+                Some(IgnorePattern {
+                    glob: GlobSet::new(&[glob]).ok()?,
+                    dir_glob: GlobSet::new(&[dir_glob]).ok()?,
+                    negated,
+                })
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Reads the `.turboignore` file in `dir`. A missing file ignores nothing.
+    pub fn read(dir: &AbsoluteSystemPathBuf) -> Result<Self, Error> {
+        Self::read_named(dir, TURBOIGNORE)
+    }
+
+    /// Reads the ignore file called `name` in `dir`, e.g. [GITIGNORE]. A
+    /// missing file ignores nothing.
+    pub fn read_named(dir: &AbsoluteSystemPathBuf, name: &str) -> Result<Self, Error> {
+        match fs::read_to_string(dir.as_path().join(name)) {
+            Ok(contents) => Ok(Self::parse(&contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Reads the global excludes file of git, which is configured with
+    /// `core.excludesFile` and defaults to `$XDG_CONFIG_HOME/git/ignore` or
+    /// `~/.config/git/ignore`. A missing file ignores nothing.
+    pub fn read_global_excludes() -> Result<Self, Error> {
+        let configured = git2::Config::open_default()
+            .and_then(|config| config.get_path("core.excludesFile"))
+            .ok();
+        let path = configured.or_else(|| {
+            let config_dir = env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+            Some(config_dir.join("git").join("ignore"))
+        });
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Self::parse(&contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns whether the last pattern that matches `path`, relative to the
+    /// directory of the file, ignores it, or `None` if no pattern matches.
+    pub(crate) fn matches(&self, path: &str) -> Option<bool> {
+        let path = RelativeUnixPath::new(Path::new(path)).ok()?;
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.glob.is_included(path))
+            .map(|pattern| !pattern.negated)
+    }
+
+    /// Like [IgnoreFile::matches], but for the directory at `path`. A pattern
+    /// like `dist/` matches the directory itself, while `dist/**` only matches
+    /// its contents, which allows negated patterns to include files in it
+    /// again.
+    pub(crate) fn matches_dir(&self, path: &str) -> Option<bool> {
+        let path = RelativeUnixPath::new(Path::new(path)).ok()?;
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.dir_glob.is_included(path))
+            .map(|pattern| !pattern.negated)
+    }
+}
+
+/// The `.turboignore` files that apply to a package.
+#[derive(Debug, Default, Clone)]
+pub struct PackageIgnores {
+    root: IgnoreFile,
+    /// The path of the package relative to the root, with `/` separators.
+    package_prefix: String,
+    package: IgnoreFile,
+}
+
+impl PackageIgnores {
+    pub fn new(root: IgnoreFile, package_prefix: &str, package: IgnoreFile) -> Self {
+        let package_prefix = package_prefix.trim_matches('/');
+        Self {
+            root,
+            package_prefix: if package_prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", package_prefix)
+            },
+            package,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty() && self.package.is_empty()
+    }
+
+    /// Whether `path`, relative to the package, is excluded from hashing.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.package
+            .matches(path)
+            .or_else(|| {
+                self.root
+                    .matches(&format!("{}{}", self.package_prefix, path))
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns() {
+        let file = IgnoreFile::parse("# generated\n\n*.snap\n/docs/\nsrc/gen/*.ts\n!keep.snap\n");
+        assert_eq!(file.matches("a.snap"), Some(true));
+        assert_eq!(file.matches("deep/dir/a.snap"), Some(true));
+        assert_eq!(file.matches("keep.snap"), Some(false));
+        assert_eq!(file.matches("docs/api/index.md"), Some(true));
+        assert_eq!(file.matches("other/docs/index.md"), None);
+        assert_eq!(file.matches("src/gen/types.ts"), Some(true));
+        assert_eq!(file.matches("src/index.ts"), None);
+    }
+
+    #[test]
+    fn test_directory_patterns() {
+        let file = IgnoreFile::parse(
+            "dist/
+out/**
+*.log
+",
+        );
+        assert_eq!(file.matches_dir("dist"), Some(true));
+        assert_eq!(file.matches_dir("src/dist"), Some(true));
+        assert_eq!(file.matches_dir("out"), None);
+        assert_eq!(file.matches_dir("logs.log"), Some(true));
+        assert_eq!(file.matches_dir("src"), None);
+        // `dist/` only matches directories, `out/**` only their contents.
+        assert_eq!(file.matches("dist"), None);
+        assert_eq!(file.matches("src/dist/index.js"), Some(true));
+        assert_eq!(file.matches("out"), None);
+        assert_eq!(file.matches("out/a/index.js"), Some(true));
+    }
+
+    #[test]
+    fn test_package_overrides_root() {
+        let ignores = PackageIgnores::new(
+            IgnoreFile::parse("packages/a/fixtures/\n*.log\n"),
+            "packages/a",
+            IgnoreFile::parse("!debug.log\n"),
+        );
+        assert!(ignores.is_ignored("fixtures/data.json"));
+        assert!(ignores.is_ignored("out/build.log"));
+        assert!(!ignores.is_ignored("debug.log"));
+        assert!(!ignores.is_ignored("src/index.ts"));
+    }
+}
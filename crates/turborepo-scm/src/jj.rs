@@ -0,0 +1,114 @@
+//! Support for Jujutsu repositories that are colocated with git.
+//!
+//! In a colocated repository, the `.jj` and `.git` directories are next to
+//! each other and jj stores its commits in the git repository. jj snapshots
+//! the working copy into a working-copy commit `@` and checks out its parent
+//! as a detached `HEAD` in git. For git, the changes of the working-copy
+//! commit are therefore uncommitted changes, which is how turbo treats them as
+//! well: [Jj] hashes files and finds changed files with git, and only
+//! translates jj revisions to git revisions.
+
+use std::{collections::HashSet, io::ErrorKind, path::Path, process::Command};
+
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
+
+use crate::{
+    package_deps::GitHashes,
+    scm::{Git, Scm},
+    Error,
+};
+
+/// A Jujutsu repository that is colocated with a git repository.
+#[derive(Debug, Clone)]
+pub struct Jj {
+    git: Git,
+}
+
+impl Jj {
+    /// The colocated repository whose root is `root`.
+    pub fn new(root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            git: Git::new(root),
+        }
+    }
+
+    /// Translates the jj revision `rev`, e.g. a change id or a revset like
+    /// `main-`, to a git revision. The working-copy commit translates to
+    /// `HEAD`, as its changes are uncommitted for git.
+    ///
+    /// Revisions that jj can't resolve, e.g. because jj isn't installed, are
+    /// passed to git unchanged. This works for commit hashes and for
+    /// bookmarks, which jj exports as git branches.
+    fn git_revision(&self, rev: &str) -> Result<String, Error> {
+        if rev == "HEAD" {
+            return Ok(rev.to_string());
+        }
+        let output = match Command::new("jj")
+            .args([
+                "log",
+                "--no-graph",
+                // Don't snapshot the working copy, which would create a new
+                // working-copy commit.
+                "--ignore-working-copy",
+                "--revisions",
+                rev,
+                "--template",
+                r#"if(current_working_copy, "HEAD", commit_id) ++ "\n""#,
+            ])
+            .current_dir(self.git.root())
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(rev.to_string()),
+            Err(e) => return Err(e.into()),
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut commits = stdout.lines();
+        match (output.status.success(), commits.next(), commits.next()) {
+            (true, Some(commit), None) => Ok(commit.to_string()),
+            _ => Ok(rev.to_string()),
+        }
+    }
+}
+
+impl Scm for Jj {
+    fn name(&self) -> &'static str {
+        "jj"
+    }
+
+    fn root(&self) -> &AbsoluteSystemPathBuf {
+        self.git.root()
+    }
+
+    fn get_package_deps(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        self.git.get_package_deps(turbo_root, package_path, inputs)
+    }
+
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        from_commit: Option<&str>,
+        to_commit: &str,
+        include_untracked: bool,
+    ) -> Result<HashSet<String>, Error> {
+        let from_commit = from_commit
+            .map(|from_commit| self.git_revision(from_commit))
+            .transpose()?;
+        self.git.changed_files(
+            turbo_root,
+            from_commit.as_deref(),
+            &self.git_revision(to_commit)?,
+            include_untracked,
+        )
+    }
+
+    fn previous_content(&self, from_commit: &str, file_path: &Path) -> Result<Vec<u8>, Error> {
+        self.git
+            .previous_content(&self.git_revision(from_commit)?, file_path)
+    }
+}
@@ -0,0 +1,101 @@
+//! Support for files that are tracked by Git LFS.
+//!
+//! git stores a small pointer file for each file that is tracked by LFS,
+//! which contains the SHA-256 of the content, the LFS object id. The content
+//! is stored in `lfs/objects` of the git directory and replaces the pointer
+//! in the working tree when the file is smudged. Whether a file is tracked by
+//! LFS is configured with the `filter=lfs` attribute.
+//!
+//! A pointer file looks like this:
+//!
+//! ```text
+//! version https://git-lfs.github.com/spec/v1
+//! oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393
+//! size 12345
+//! ```
+
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::Error;
+
+/// The first line of a pointer file.
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+
+/// Pointer files are at most 1024 bytes, which allows skipping larger files
+/// without reading them.
+const MAX_POINTER_SIZE: u64 = 1024;
+
+/// The value of the `filter` attribute of files that are tracked by LFS.
+pub(crate) const LFS_FILTER: &str = "lfs";
+
+/// Parses the pointer file `content` and returns the LFS object id of the
+/// file, or `None` if it isn't a pointer file.
+pub fn parse_pointer(content: &[u8]) -> Option<&str> {
+    let content = std::str::from_utf8(content).ok()?;
+    let mut lines = content.lines();
+    if lines.next()? != POINTER_VERSION {
+        return None;
+    }
+    lines.find_map(|line| {
+        let oid = line.strip_prefix("oid sha256:")?;
+        (oid.len() == 64 && oid.bytes().all(|b| b.is_ascii_hexdigit())).then_some(oid)
+    })
+}
+
+/// Returns the LFS object id of the file at `path` if it's a pointer file,
+/// i.e. if the file isn't smudged.
+pub(crate) fn read_pointer(path: &Path) -> Result<Option<String>, Error> {
+    if fs::metadata(path)?.len() > MAX_POINTER_SIZE {
+        return Ok(None);
+    }
+    let content = fs::read(path)?;
+    Ok(parse_pointer(&content).map(str::to_string))
+}
+
+/// The path of the LFS object `oid` in the git directory `common_dir`.
+pub(crate) fn object_path(common_dir: &Path, oid: &str) -> PathBuf {
+    common_dir
+        .join("lfs")
+        .join("objects")
+        .join(&oid[0..2])
+        .join(&oid[2..4])
+        .join(oid)
+}
+
+/// Computes the LFS object id of the content of the file at `path`.
+pub(crate) fn object_id(path: &Path) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_pointer;
+
+    #[test]
+    fn test_parse_pointer() {
+        let oid = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+        let pointer = format!(
+            "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize 12345\n",
+            oid
+        );
+        assert_eq!(parse_pointer(pointer.as_bytes()), Some(oid));
+        assert_eq!(parse_pointer(b"hello\n"), None);
+        assert_eq!(
+            parse_pointer(b"version https://git-lfs.github.com/spec/v1\noid sha256:abc\n"),
+            None
+        );
+        assert_eq!(parse_pointer(&[0xff, 0xfe]), None);
+    }
+}
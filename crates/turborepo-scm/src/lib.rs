@@ -2,19 +2,75 @@
 #![feature(provide_any)]
 #![feature(assert_matches)]
 
-use std::backtrace;
+use std::{
+    backtrace, io,
+    path::{Path, PathBuf},
+};
 
 use thiserror::Error;
-use turbopath::PathValidationError;
+use turbopath::{glob::GlobError, PathError, PathValidationError, RelativeUnixPathBuf};
 
+pub mod chunked_hash;
+pub(crate) mod command_path;
 pub mod git;
+pub mod global_deps;
+pub mod hg;
+pub mod ignore;
+pub mod jj;
+pub mod lfs;
+pub mod metadata;
+pub mod nul_records;
+pub mod observer;
+pub mod package_deps;
+pub mod process;
+pub mod repository;
+pub mod scm;
+#[cfg(test)]
+mod testing;
+pub mod tree_cache;
+pub mod walk;
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("git error: {0}")]
     Git2(#[from] git2::Error, #[backtrace] backtrace::Backtrace),
-    #[error("git error: {0}")]
-    Git(String, #[backtrace] backtrace::Backtrace),
+    #[error("git {command} {}{}", format_exit_code(*.code), format_stderr(.stderr))]
+    ExitStatus {
+        /// The subcommand and arguments of git, e.g. `merge-base main HEAD`.
+        command: String,
+        /// The exit code of git, or `None` if it was terminated by a signal.
+        code: Option<i32>,
+        stderr: String,
+        #[backtrace]
+        backtrace: backtrace::Backtrace,
+    },
+    #[error("git {command} timed out after {timeout:?}{}", format_stderr(.stderr))]
+    Timeout {
+        command: String,
+        timeout: std::time::Duration,
+        /// What git wrote to stderr before it was killed.
+        stderr: String,
+        #[backtrace]
+        backtrace: backtrace::Backtrace,
+    },
+    #[error("git {command} was cancelled")]
+    Cancelled {
+        command: String,
+        #[backtrace]
+        backtrace: backtrace::Backtrace,
+    },
+    #[error("unexpected output from git {command}: {record:?}")]
+    ParseError {
+        command: String,
+        /// The record, usually a line, that couldn't be parsed.
+        record: String,
+        #[backtrace]
+        backtrace: backtrace::Backtrace,
+    },
+    #[error("{0} does not exist in {1}")]
+    PathNotInRef(String, String, #[backtrace] backtrace::Backtrace),
+    #[error("mercurial error: {0}")]
+    Hg(String, #[backtrace] backtrace::Backtrace),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error, #[backtrace] backtrace::Backtrace),
     #[error("path error: {0}")]
@@ -22,4 +78,139 @@ pub enum Error {
         #[from] PathValidationError,
         #[backtrace] backtrace::Backtrace,
     ),
+    #[error("invalid glob: {0}")]
+    Glob(#[from] GlobError, #[backtrace] backtrace::Backtrace),
+    #[error(
+        "cannot compare against {0}: the repository is a shallow clone that doesn't contain the \
+         history to find the merge base"
+    )]
+    ShallowRepo(String, #[backtrace] backtrace::Backtrace),
+    #[error("unresolved merge conflicts in: {}", format_paths(.0))]
+    Unmerged(Vec<RelativeUnixPathBuf>, #[backtrace] backtrace::Backtrace),
+    #[error(
+        "paths only differ in case and collide on case-insensitive file systems: {}",
+        format_path_groups(.0)
+    )]
+    CaseCollision(
+        Vec<Vec<RelativeUnixPathBuf>>,
+        #[backtrace] backtrace::Backtrace,
+    ),
+    #[error("{} is not in a git repository", .0.display())]
+    NotARepository(PathBuf, #[backtrace] backtrace::Backtrace),
+    #[error("git is not installed")]
+    GitNotFound(#[backtrace] backtrace::Backtrace),
+    #[error(
+        "git {0} is not supported, the oldest supported version is {}.{}",
+        git::MIN_GIT_VERSION.0,
+        git::MIN_GIT_VERSION.1
+    )]
+    UnsupportedGitVersion(String, #[backtrace] backtrace::Backtrace),
+    #[error("the git repository at {} is corrupt: {1}", .0.display())]
+    RepositoryCorrupt(PathBuf, String, #[backtrace] backtrace::Backtrace),
+    #[error("{} doesn't have a working tree", .0.display())]
+    BareRepository(PathBuf, #[backtrace] backtrace::Backtrace),
+    #[error("{0} and {1} have no common history")]
+    NoMergeBase(String, String, #[backtrace] backtrace::Backtrace),
+    #[error("the LFS object of {} wasn't fetched", .0.display())]
+    LfsObjectMissing(PathBuf, #[backtrace] backtrace::Backtrace),
+}
+
+impl Error {
+    /// Whether the error means that git can't be used at all, in which case
+    /// files can be hashed without git instead, see
+    /// [package_deps::get_package_deps].
+    pub fn can_hash_without_git(&self) -> bool {
+        matches!(
+            self,
+            Error::NotARepository(..) | Error::GitNotFound(..) | Error::UnsupportedGitVersion(..)
+        )
+    }
+
+    /// A suggestion for the user to fix the error, if there is one.
+    pub fn remediation(&self) -> Option<String> {
+        match self {
+            Error::NotARepository(path, _) => Some(format!(
+                "run `git init` in {} or one of its parent directories to enable change detection",
+                path.display()
+            )),
+            Error::GitNotFound(_) => {
+                Some("install git and make sure that it's on the PATH".to_string())
+            }
+            Error::UnsupportedGitVersion(..) => Some(format!(
+                "upgrade git to version {}.{} or newer",
+                git::MIN_GIT_VERSION.0,
+                git::MIN_GIT_VERSION.1
+            )),
+            Error::RepositoryCorrupt(path, ..) => Some(format!(
+                "run `git fsck` in {} to find the damaged objects, or clone the repository again",
+                path.display()
+            )),
+            Error::ShallowRepo(..) => Some(
+                "fetch more history, e.g. with `git fetch --deepen` or `git fetch --unshallow`"
+                    .to_string(),
+            ),
+            Error::Timeout { .. } => Some(
+                "check that the repository is on a responsive file system, or raise the timeout"
+                    .to_string(),
+            ),
+            Error::LfsObjectMissing(..) => Some("run `git lfs pull`".to_string()),
+            Error::CaseCollision(..) => Some(
+                "rename the paths with `git mv` so that they differ in more than case, or remove \
+                 all but one of them with `git rm`"
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Converts an error of spawning git in `cwd`. Spawning fails with
+    /// [io::ErrorKind::NotFound] if git isn't installed or if `cwd` doesn't
+    /// exist.
+    pub(crate) fn from_spawn(error: io::Error, cwd: &Path) -> Self {
+        if error.kind() == io::ErrorKind::NotFound && cwd.is_dir() {
+            Error::GitNotFound(backtrace::Backtrace::capture())
+        } else {
+            error.into()
+        }
+    }
+}
+
+impl From<PathError> for Error {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::PathValidationError(error) => error.into(),
+            PathError::IO(error) => error.into(),
+        }
+    }
+}
+
+fn format_exit_code(code: Option<i32>) -> String {
+    match code {
+        Some(code) => format!("exited with code {}", code),
+        None => "was terminated by a signal".to_string(),
+    }
+}
+
+fn format_stderr(stderr: &str) -> String {
+    if stderr.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", stderr)
+    }
+}
+
+fn format_paths(paths: &[RelativeUnixPathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.as_path().display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_path_groups(groups: &[Vec<RelativeUnixPathBuf>]) -> String {
+    groups
+        .iter()
+        .map(|paths| format!("[{}]", format_paths(paths)))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
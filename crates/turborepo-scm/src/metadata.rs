@@ -0,0 +1,244 @@
+//! Cheap questions about a git repository that are answered by reading the
+//! git directory directly, without spawning git.
+//!
+//! The daemon polls the checked out commit and the index frequently, and
+//! spawning a process for each poll is expensive, especially on Windows. The
+//! common formats are parsed here:
+//!
+//! * `HEAD`, which either contains a commit hash or a `ref: <name>` line.
+//! * Loose refs, which are files in the `refs` directory containing a hash.
+//! * The header of the index, which contains its version and the number of
+//!   entries.
+//!
+//! Anything else, e.g. refs that were moved to `packed-refs`, repositories
+//! that use the reftable format or symbolic refs pointing to symbolic refs,
+//! falls back to the git executable.
+
+use std::{
+    backtrace::Backtrace,
+    fs::{self, File},
+    io::{ErrorKind, Read},
+    time::SystemTime,
+};
+
+use crate::{
+    process::{self, ProcessLimits},
+    repository::GitRepository,
+    Error,
+};
+
+/// What `HEAD` of a working tree points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Head {
+    /// A branch, e.g. `refs/heads/main`, which doesn't have a commit yet if
+    /// `sha` is `None`.
+    Branch { name: String, sha: Option<String> },
+    /// A commit that is checked out directly.
+    Detached { sha: String },
+}
+
+impl Head {
+    /// The hash of the checked out commit, or `None` if the branch doesn't
+    /// have a commit yet.
+    pub fn sha(&self) -> Option<&str> {
+        match self {
+            Head::Branch { sha, .. } => sha.as_deref(),
+            Head::Detached { sha } => Some(sha),
+        }
+    }
+
+    /// The short name of the checked out branch, e.g. `main`, or `None` if
+    /// `HEAD` is detached.
+    pub fn branch(&self) -> Option<&str> {
+        match self {
+            Head::Branch { name, .. } => Some(name.strip_prefix("refs/heads/").unwrap_or(name)),
+            Head::Detached { .. } => None,
+        }
+    }
+}
+
+/// The header of the index file of a working tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    /// The version of the index format, usually 2.
+    pub version: u32,
+    /// The number of entries in the index file. With a split index, entries
+    /// of the shared index aren't counted.
+    pub entries: u32,
+    /// The last modification time, if the platform supports it.
+    pub mtime: Option<SystemTime>,
+}
+
+/// The ref that `HEAD` points to in repositories that use the reftable
+/// format, so that older versions of git don't mistake them for repositories
+/// with loose refs.
+const REFTABLE_HEAD: &str = "refs/heads/.invalid";
+
+impl GitRepository {
+    /// Reads what `HEAD` points to and resolves it to a commit hash.
+    pub fn head(&self) -> Result<Head, Error> {
+        let content = fs::read_to_string(self.git_dir().as_path().join("HEAD"))?;
+        let content = content.trim_end();
+        if let Some(name) = content.strip_prefix("ref:") {
+            let name = name.trim();
+            if name != REFTABLE_HEAD {
+                if let Some(sha) = self.read_loose_ref(name)? {
+                    return Ok(Head::Branch {
+                        name: name.to_string(),
+                        sha,
+                    });
+                }
+            }
+        } else if is_hash(content) {
+            return Ok(Head::Detached {
+                sha: content.to_string(),
+            });
+        }
+        self.head_with_git()
+    }
+
+    /// Reads the header of the index, or returns `None` if the working tree
+    /// doesn't have an index yet.
+    pub fn index_info(&self) -> Result<Option<IndexInfo>, Error> {
+        let path = self.git_dir().as_path().join("index");
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut header = [0; 12];
+        file.read_exact(&mut header)?;
+        if &header[0..4] != b"DIRC" {
+            return Err(Error::RepositoryCorrupt(
+                self.git_dir().as_path().to_path_buf(),
+                "the index has an invalid signature".to_string(),
+                Backtrace::capture(),
+            ));
+        }
+        let word = |offset: usize| {
+            u32::from_be_bytes(header[offset..offset + 4].try_into().expect("4 bytes"))
+        };
+        Ok(Some(IndexInfo {
+            version: word(4),
+            entries: word(8),
+            mtime: file.metadata()?.modified().ok(),
+        }))
+    }
+
+    /// Resolves the ref `name` from its file in the `refs` directory.
+    ///
+    /// Returns `Some(None)` if the ref doesn't exist and the repository
+    /// doesn't have packed refs, which means that it's an unborn branch, and
+    /// `None` if the ref can't be resolved without git.
+    fn read_loose_ref(&self, name: &str) -> Result<Option<Option<String>>, Error> {
+        if !name.starts_with("refs/") {
+            return Ok(None);
+        }
+        let common_dir = self.common_dir().as_path();
+        match fs::read_to_string(common_dir.join(name)) {
+            Ok(content) => {
+                let content = content.trim_end();
+                Ok(is_hash(content).then(|| Some(content.to_string())))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Ok((!common_dir.join("packed-refs").exists()).then_some(None))
+            }
+            // E.g. a directory, which is the case for `refs/heads` with
+            // reftable.
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn head_with_git(&self) -> Result<Head, Error> {
+        let run = |args: &[&str]| -> Result<Option<String>, Error> {
+            let output = process::output(
+                self.command(self.work_tree()).args(args),
+                self.work_tree().as_path(),
+                &ProcessLimits::none(),
+            )?;
+            Ok(output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+        };
+        let sha = run(&["rev-parse", "--verify", "--quiet", "HEAD"])?;
+        match (run(&["symbolic-ref", "--quiet", "HEAD"])?, sha) {
+            (Some(name), sha) => Ok(Head::Branch { name, sha }),
+            (None, Some(sha)) => Ok(Head::Detached { sha }),
+            (None, None) => Err(Error::RepositoryCorrupt(
+                self.git_dir().as_path().to_path_buf(),
+                "HEAD can't be resolved".to_string(),
+                Backtrace::capture(),
+            )),
+        }
+    }
+}
+
+/// Whether `value` is a SHA-1 or SHA-256 hash in hex.
+fn is_hash(value: &str) -> bool {
+    matches!(value.len(), 40 | 64) && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::Head;
+    use crate::repository::GitRepository;
+
+    fn git(cwd: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {:?}",
+            args,
+            output
+        );
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn test_head() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(tmp.path()).unwrap()).unwrap();
+        let dir = root.as_path();
+        git(dir, &["init", "--quiet", "--initial-branch", "main"]);
+        git(dir, &["config", "user.name", "test"]);
+        git(dir, &["config", "user.email", "test@example.com"]);
+        let repository = GitRepository::discover(&root).unwrap().unwrap();
+
+        assert_eq!(
+            repository.head().unwrap(),
+            Head::Branch {
+                name: "refs/heads/main".to_string(),
+                sha: None
+            }
+        );
+        assert_eq!(repository.index_info().unwrap(), None);
+
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "--quiet", "-m", "initial"]);
+        let sha = git(dir, &["rev-parse", "HEAD"]);
+        let head = repository.head().unwrap();
+        assert_eq!(head.sha(), Some(sha.as_str()));
+        assert_eq!(head.branch(), Some("main"));
+        let index = repository.index_info().unwrap().unwrap();
+        assert_eq!(index.entries, 2);
+
+        // Packed refs are resolved with git.
+        git(dir, &["pack-refs", "--all"]);
+        assert_eq!(repository.head().unwrap(), head);
+
+        git(dir, &["checkout", "--quiet", "--detach"]);
+        assert_eq!(repository.head().unwrap(), Head::Detached { sha });
+        assert_eq!(repository.head().unwrap().branch(), None);
+    }
+}
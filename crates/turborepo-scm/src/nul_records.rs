@@ -0,0 +1,286 @@
+//! Parsing the output of git commands that are run with `-z`, which
+//! terminates records, or the fields of records, with NUL bytes.
+//!
+//! [NulRecordReader] reads the output incrementally and hands it to a nom
+//! parser one record at a time, reading more output whenever the parser needs
+//! it. Records are parsed with the field parsers of this module, e.g. [path],
+//! combined with the parsers of nom.
+//!
+//! Paths are written by git as they are stored in the repository, which is
+//! usually but not necessarily UTF-8, see [decode_path]. Without `-z`, git
+//! quotes paths with unusual characters unless `core.quotepath` is disabled,
+//! see [unquote_path].
+
+use std::{borrow::Cow, io::Read};
+
+use nom::{
+    bytes::streaming::{tag, take_until},
+    combinator::map,
+    sequence::terminated,
+    IResult,
+};
+
+use crate::{package_deps::invalid_output, Error};
+
+/// How much output is read at once.
+const READ_SIZE: usize = 16 * 1024;
+
+/// Reads the records of NUL separated output, see the
+/// [module documentation](self).
+pub struct NulRecordReader<'a, R> {
+    /// The git command that wrote the output, for error messages.
+    command: &'a str,
+    reader: R,
+    buffer: Vec<u8>,
+    /// The start of the input in `buffer` that wasn't parsed yet.
+    position: usize,
+    eof: bool,
+}
+
+impl<'a, R: Read> NulRecordReader<'a, R> {
+    /// Reads the output of `git <command>` from `reader`.
+    pub fn new(command: &'a str, reader: R) -> Self {
+        Self {
+            command,
+            reader,
+            buffer: Vec::new(),
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// Parses the next record with `parser`, or returns `None` at the end of
+    /// the output. The parser returns [nom::Err::Incomplete] when it needs
+    /// more input, like the streaming parsers of nom do.
+    ///
+    /// Returns an error if the record is malformed or the output ends in the
+    /// middle of it.
+    pub fn next_record<O>(
+        &mut self,
+        mut parser: impl FnMut(&[u8]) -> IResult<&[u8], O>,
+    ) -> Result<Option<O>, Error> {
+        loop {
+            let input = &self.buffer[self.position..];
+            if !input.is_empty() {
+                match parser(input) {
+                    Ok((rest, output)) => {
+                        self.position += input.len() - rest.len();
+                        return Ok(Some(output));
+                    }
+                    Err(nom::Err::Incomplete(_)) if !self.eof => {}
+                    Err(_) => {
+                        // Report the field the record starts with.
+                        let record = input.split(|byte| *byte == 0).next().unwrap_or(input);
+                        return Err(invalid_output(
+                            self.command,
+                            &String::from_utf8_lossy(record),
+                        ));
+                    }
+                }
+            } else if self.eof {
+                return Ok(None);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Reads more output after the input that wasn't parsed yet.
+    fn fill(&mut self) -> Result<(), Error> {
+        // Drop parsed records, so the buffer only grows to the longest record.
+        self.buffer.drain(..self.position);
+        self.position = 0;
+        let len = self.buffer.len();
+        self.buffer.resize(len + READ_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[len..]) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    return Err(e.into());
+                }
+            }
+        };
+        self.buffer.truncate(len + read);
+        self.eof = read == 0;
+        Ok(())
+    }
+}
+
+/// A NUL terminated field, without the NUL.
+pub fn field(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    terminated(take_until("\0"), tag("\0"))(input)
+}
+
+/// A NUL terminated path, see [decode_path].
+pub fn path(input: &[u8]) -> IResult<&[u8], String> {
+    map(field, decode_path)(input)
+}
+
+/// Decodes a path written by git. Paths that aren't valid UTF-8, e.g. paths
+/// that were committed on a system with a latin1 locale, are decoded as
+/// latin1, so that every path can be listed and compared, but such paths
+/// can't be used to open the file.
+pub fn decode_path(path: &[u8]) -> String {
+    match std::str::from_utf8(path) {
+        Ok(path) => path.to_string(),
+        Err(_) => path.iter().map(|byte| char::from(*byte)).collect(),
+    }
+}
+
+/// Removes the quotes that git adds to paths with unusual characters when
+/// `core.quotepath` is enabled and the output isn't NUL separated, e.g.
+/// `"\303\251t\303\251.txt"` for `été.txt`. Paths that aren't quoted, or that
+/// aren't quoted the way git quotes them, are returned as they are.
+pub fn unquote_path(path: &[u8]) -> Cow<'_, [u8]> {
+    let Some(quoted) = path
+        .strip_prefix(b"\"")
+        .and_then(|path| path.strip_suffix(b"\"")) else {
+        return Cow::Borrowed(path);
+    };
+    let mut unquoted = Vec::with_capacity(quoted.len());
+    let mut bytes = quoted.iter().copied();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            unquoted.push(byte);
+            continue;
+        }
+        let escaped = match bytes.next() {
+            Some(b'a') => 0x07,
+            Some(b'b') => 0x08,
+            Some(b't') => b'\t',
+            Some(b'n') => b'\n',
+            Some(b'v') => 0x0b,
+            Some(b'f') => 0x0c,
+            Some(b'r') => b'\r',
+            Some(b'"') => b'"',
+            Some(b'\\') => b'\\',
+            // Bytes are escaped as three octal digits.
+            Some(first @ b'0'..=b'3') => {
+                let (Some(second @ b'0'..=b'7'), Some(third @ b'0'..=b'7')) =
+                    (bytes.next(), bytes.next()) else {
+                    return Cow::Borrowed(path);
+                };
+                ((first - b'0') << 6) | ((second - b'0') << 3) | (third - b'0')
+            }
+            _ => return Cow::Borrowed(path),
+        };
+        unquoted.push(escaped);
+    }
+    Cow::Owned(unquoted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use nom::{combinator::cond, IResult};
+
+    use super::*;
+
+    /// Reads at most `size` bytes at once.
+    struct SmallReads<'a> {
+        input: &'a [u8],
+        size: usize,
+    }
+
+    impl Read for SmallReads<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.size.min(buf.len()).min(self.input.len());
+            buf[..len].copy_from_slice(&self.input[..len]);
+            self.input = &self.input[len..];
+            Ok(len)
+        }
+    }
+
+    /// A record like the ones of `git status -z`: a path that is followed by
+    /// a second path if it starts with `R`.
+    fn record(input: &[u8]) -> IResult<&[u8], (String, Option<String>)> {
+        let (input, first) = path(input)?;
+        let (input, second) = cond(first.starts_with('R'), path)(input)?;
+        Ok((input, (first, second)))
+    }
+
+    fn read_all(input: &[u8], size: usize) -> Result<Vec<(String, Option<String>)>, Error> {
+        let mut reader = NulRecordReader::new("status", SmallReads { input, size });
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record(record)? {
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    #[test]
+    fn test_read_records() {
+        for size in [1, 2, 7, READ_SIZE] {
+            let records = read_all(b"a.txt\0Rb.txt\0c.txt\0d.txt\0", size).unwrap();
+            assert_eq!(
+                records,
+                [
+                    ("a.txt".to_string(), None),
+                    ("Rb.txt".to_string(), Some("c.txt".to_string())),
+                    ("d.txt".to_string(), None),
+                ]
+            );
+        }
+        assert_eq!(read_all(b"", 1).unwrap(), []);
+        // The output ends in the middle of a record.
+        assert!(read_all(b"a.txt\0Rb.txt\0", 3).is_err());
+        assert!(read_all(b"a.txt", 3).is_err());
+    }
+
+    #[test]
+    fn test_decode_path() {
+        assert_eq!(decode_path("été.txt".as_bytes()), "été.txt");
+        assert_eq!(decode_path(b"\xe9t\xe9.txt"), "été.txt");
+    }
+
+    #[test]
+    fn test_unquote_path() {
+        assert_eq!(&*unquote_path(b"plain.txt"), b"plain.txt");
+        assert_eq!(
+            &*unquote_path(br#""\303\251t\303\251.txt""#),
+            "été.txt".as_bytes()
+        );
+        assert_eq!(
+            &*unquote_path(br#""tab\there \"quoted\" back\\slash""#),
+            b"tab\there \"quoted\" back\\slash"
+        );
+        // Not quoted by git.
+        assert_eq!(&*unquote_path(br#""\q""#), br#""\q""#);
+        assert_eq!(&*unquote_path(br#""\30""#), br#""\30""#);
+        assert_eq!(&*unquote_path(b"\""), b"\"");
+    }
+
+    #[test]
+    fn test_fuzz_malformed_records() {
+        // Parsing random output must not panic, and must not depend on how
+        // the output is split into reads.
+        const ALPHABET: &[u8] = b"\0\0\0 \tR\"\\a\xe9";
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..2000 {
+            let len = (next() % 48) as usize;
+            let input = (0..len)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect::<Vec<_>>();
+            let expected = read_all(&input, READ_SIZE).ok();
+            for size in [1, 3] {
+                assert_eq!(
+                    read_all(&input, size).ok(),
+                    expected,
+                    "input: {:?}",
+                    String::from_utf8_lossy(&input)
+                );
+            }
+            for field in input.split(|byte| *byte == 0) {
+                unquote_path(field);
+            }
+        }
+    }
+}
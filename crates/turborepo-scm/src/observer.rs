@@ -0,0 +1,148 @@
+//! Hooks that report the progress of hashing packages, e.g. to render
+//! progress in the CLI or to find out why hashing is slow in a large
+//! repository.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use turbopath::{AnchoredSystemPath, RelativeUnixPathBuf};
+
+/// A git process that was run while hashing a package.
+#[derive(Debug, Clone, Copy)]
+pub struct Subprocess<'a> {
+    /// The arguments of git, e.g. `["ls-tree", "-r", "-z", "HEAD"]`.
+    pub args: &'a [&'a str],
+    /// How long the process ran, including writing its input and reading its
+    /// output.
+    pub duration: Duration,
+    /// Whether the process exited successfully. Processes that couldn't be
+    /// spawned aren't reported.
+    pub success: bool,
+}
+
+/// How the files of a package were hashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashingMethod {
+    /// With the git executable.
+    Git,
+    /// In-process with libgit2.
+    Libgit2,
+    /// By walking the files of the package, because git was unavailable, see
+    /// [crate::walk].
+    WithoutGit,
+}
+
+/// What it took to hash a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageHashingStats {
+    pub method: HashingMethod,
+    pub duration: Duration,
+    /// The number of files that were hashed.
+    pub files: usize,
+    /// The number of git processes that were run.
+    pub subprocesses: usize,
+}
+
+/// Receives events while packages are hashed by a
+/// [PackageDepsHasher](crate::package_deps::PackageDepsHasher). Packages may
+/// be hashed on multiple threads at once, and the files of a package are
+/// hashed on multiple threads, so the methods can be called concurrently.
+///
+/// All methods do nothing by default.
+pub trait HashingObserver: Send + Sync {
+    /// Hashing `package_path` started.
+    fn package_started(&self, _package_path: &AnchoredSystemPath) {}
+
+    /// Hashing `package_path` finished successfully.
+    fn package_finished(&self, _package_path: &AnchoredSystemPath, _stats: &PackageHashingStats) {}
+
+    /// A git process finished while hashing `package_path`.
+    fn subprocess(&self, _package_path: &AnchoredSystemPath, _subprocess: &Subprocess<'_>) {}
+
+    /// `package_path` is hashed without git because of `reason`, e.g. that
+    /// git isn't installed, see [HashingMethod::WithoutGit].
+    fn hashed_without_git(&self, _package_path: &AnchoredSystemPath, _reason: &str) {}
+
+    /// Committed files of `package_path` only differ in case, see
+    /// [CaseCollisionPolicy::Warn](crate::package_deps::CaseCollisionPolicy::Warn).
+    /// Each group contains the paths that collide with each other.
+    fn case_collisions(
+        &self,
+        _package_path: &AnchoredSystemPath,
+        _collisions: &[Vec<RelativeUnixPathBuf>],
+    ) {
+    }
+}
+
+/// The observer of a
+/// [PackageDepsHasher](crate::package_deps::PackageDepsHasher), which can't
+/// derive `Debug` otherwise.
+#[derive(Clone)]
+pub(crate) struct SharedObserver(pub(crate) Arc<dyn HashingObserver>);
+
+impl fmt::Debug for SharedObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HashingObserver")
+    }
+}
+
+/// Forwards the subprocesses of a package to the observer of the hasher, if
+/// any, and counts them.
+pub(crate) struct PackageObserver<'a> {
+    observer: Option<&'a dyn HashingObserver>,
+    package_path: &'a AnchoredSystemPath,
+    subprocesses: AtomicUsize,
+}
+
+impl<'a> PackageObserver<'a> {
+    pub(crate) fn new(
+        observer: Option<&'a dyn HashingObserver>,
+        package_path: &'a AnchoredSystemPath,
+    ) -> Self {
+        if let Some(observer) = observer {
+            observer.package_started(package_path);
+        }
+        Self {
+            observer,
+            package_path,
+            subprocesses: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn subprocess(&self, subprocess: &Subprocess<'_>) {
+        self.subprocesses.fetch_add(1, Ordering::Relaxed);
+        if let Some(observer) = self.observer {
+            observer.subprocess(self.package_path, subprocess);
+        }
+    }
+
+    pub(crate) fn hashed_without_git(&self, reason: &str) {
+        if let Some(observer) = self.observer {
+            observer.hashed_without_git(self.package_path, reason);
+        }
+    }
+
+    pub(crate) fn case_collisions(&self, collisions: &[Vec<RelativeUnixPathBuf>]) {
+        if let Some(observer) = self.observer {
+            observer.case_collisions(self.package_path, collisions);
+        }
+    }
+
+    pub(crate) fn finished(&self, method: HashingMethod, duration: Duration, files: usize) {
+        if let Some(observer) = self.observer {
+            let stats = PackageHashingStats {
+                method,
+                duration,
+                files,
+                subprocesses: self.subprocesses.load(Ordering::Relaxed),
+            };
+            observer.package_finished(self.package_path, &stats);
+        }
+    }
+}
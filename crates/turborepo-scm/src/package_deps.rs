@@ -0,0 +1,2865 @@
+use std::{
+    backtrace::Backtrace,
+    collections::HashMap,
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use git2::{
+    AttrCheckFlags, ErrorCode, IndexEntryExtendedFlag, ObjectType, Oid, Repository, Status,
+    StatusOptions, TreeWalkMode, TreeWalkResult,
+};
+use nom::{
+    bytes::complete::{is_not, tag},
+    combinator::{all_consuming, cond, map_parser, rest},
+    error::{Error as ParseError, ErrorKind},
+    sequence::{terminated, tuple},
+    IResult,
+};
+use turbopath::{
+    fs_batch::symlink_stat_many, glob::GlobSet, AbsoluteSystemPathBuf, AnchoredSystemPath,
+    PathValidationError, RelativeUnixPath, RelativeUnixPathBuf,
+};
+
+use crate::{
+    chunked_hash::ChunkedHasher,
+    command_path, git,
+    ignore::{IgnoreFile, PackageIgnores},
+    lfs,
+    nul_records::{self, NulRecordReader},
+    observer::{HashingMethod, HashingObserver, PackageObserver, SharedObserver, Subprocess},
+    process::{self, CancellationToken, ProcessLimits},
+    repository::{GitRepository, SparseCheckout},
+    scm,
+    tree_cache::TreeCache,
+    walk::Walker,
+    Error,
+};
+
+/// Maps file paths, relative to the package directory, to their git object
+/// hash.
+pub type GitHashes = HashMap<RelativeUnixPathBuf, String>;
+
+/// Computes the git hashes of all files in a package, taking uncommitted
+/// changes into account.
+///
+/// The hashes of committed files are read from `HEAD`, and files that are
+/// modified or untracked according to `git status` are hashed from the working
+/// tree. Fails with [Error::Unmerged] if the package contains paths with
+/// unresolved merge conflicts, since their content doesn't correspond to any
+/// meaningful state of the package.
+///
+/// Files that are ignored by the `.turboignore` file of the monorepo or of the
+/// package are excluded, see [crate::ignore].
+///
+/// The repository is discovered like git does, see [crate::repository], so
+/// packages in linked worktrees, submodules and repositories selected with
+/// `GIT_DIR` are supported. Submodules inside of the package are hashed as
+/// part of it, see [SubmoduleMode].
+///
+/// In sparse checkouts, only the files that are checked out are hashed. Files
+/// outside of the checkout are committed in `HEAD` but don't exist in the
+/// working tree, and they are never read, so partial clones don't fetch their
+/// content.
+///
+/// If git is unavailable or older than [git::MIN_GIT_VERSION], or the package
+/// isn't in a git repository with a commit, the files of the package are walked
+/// and hashed in-process instead, which results in the same hashes. Files
+/// ignored by `.gitignore` files or by the global excludes of git and
+/// `node_modules` directories are skipped, see [crate::walk].
+///
+/// Symlinks are hashed as their target path, like git does, see
+/// [SymlinkPolicy] for alternatives.
+///
+/// Packages in Mercurial, Sapling and Jujutsu repositories are hashed with the
+/// matching [Scm](crate::scm::Scm), see [scm::discover]. Packages in git
+/// repositories are hashed with the git executable, see [PackageDepsHasher]
+/// for an alternative.
+///
+/// # Arguments
+///
+/// * `turbo_root`: The root of the monorepo.
+/// * `package_path`: The path of the package, relative to `turbo_root`.
+/// * `inputs`: Glob patterns, relative to the package, of the files to hash.
+///   Patterns starting with `!` exclude files. If empty, all files in the
+///   package are hashed. With [TURBO_DEFAULT] among them, all files are hashed
+///   as if there were no inputs, and the other patterns add files to them, even
+///   files that git ignores.
+pub fn get_package_deps(
+    turbo_root: &AbsoluteSystemPathBuf,
+    package_path: &AnchoredSystemPath,
+    inputs: &[&str],
+) -> Result<GitHashes, Error> {
+    match scm::discover(&turbo_root.resolve(package_path))? {
+        Some(scm) => scm.get_package_deps(turbo_root, package_path, inputs),
+        None => PackageDepsHasher::new().get_package_deps(turbo_root, package_path, inputs),
+    }
+}
+
+/// How [PackageDepsHasher] reads the state of the git repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GitBackend {
+    /// Runs the git executable.
+    Executable,
+    /// Reads the repository in-process with libgit2.
+    Libgit2,
+}
+
+/// How [PackageDepsHasher] hashes git submodules in a package.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubmoduleMode {
+    /// Hashes the files of checked out submodules like the files of the
+    /// package, including their uncommitted changes. The paths of the files
+    /// are prefixed with the path of the submodule.
+    #[default]
+    Recurse,
+    /// Hashes each submodule as a single entry whose hash is the commit that
+    /// is checked out. Uncommitted changes in the submodule are ignored.
+    Pointer,
+}
+
+/// How [PackageDepsHasher] hashes files that are tracked by Git LFS, see
+/// [crate::lfs].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LfsMode {
+    /// Hashes the pointer files that git stores for the files, like any
+    /// other file. Files that aren't smudged and uncommitted changes to
+    /// smudged files are hashed as their working tree content instead.
+    #[default]
+    Pointer,
+    /// Hashes each file as its LFS object id, the SHA-256 of its content,
+    /// which is the same whether the file is smudged or not.
+    Oid,
+    /// Hashes the content of each file like a file that isn't tracked by LFS.
+    /// The content of files that aren't smudged is read from the LFS objects
+    /// of the repository, and hashing fails if it wasn't fetched.
+    Content,
+}
+
+/// How [PackageDepsHasher] hashes symlinks in a package.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Hashes the target path of each symlink, like git does. Dangling
+    /// symlinks are hashed like any other.
+    #[default]
+    HashLinkText,
+    /// Hashes the content of the file a symlink points to, so that changes
+    /// to the file are picked up when it's outside of the package. Symlinks
+    /// to directories and dangling symlinks are hashed as their target path,
+    /// since there is no single file to hash.
+    HashTarget,
+    /// Leaves symlinks out of the hashes.
+    Skip,
+}
+
+/// What [PackageDepsHasher] does when committed files of a package only
+/// differ in case, e.g. `Foo.js` and `foo.js`. Only one of them can exist in
+/// the working tree on case-insensitive file systems, which are the default on
+/// macOS and Windows, so the hashes don't match what is checked out there.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseCollisionPolicy {
+    /// Hashes all paths without checking.
+    Ignore,
+    /// Hashes all paths and reports the collisions to the
+    /// [HashingObserver], if there is one.
+    #[default]
+    Warn,
+    /// Fails with [Error::CaseCollision].
+    Error,
+}
+
+/// Computes package hashes, see [get_package_deps].
+#[derive(Debug, Clone)]
+pub struct PackageDepsHasher {
+    backend: GitBackend,
+    submodules: SubmoduleMode,
+    concurrency: usize,
+    detect_renames: bool,
+    lfs: LfsMode,
+    symlinks: SymlinkPolicy,
+    case_collisions: CaseCollisionPolicy,
+    tree_cache: Option<Arc<TreeCache>>,
+    observer: Option<SharedObserver>,
+    limits: ProcessLimits,
+    chunked_hasher: Option<Arc<Mutex<ChunkedHasher>>>,
+    git_detection: Arc<Mutex<GitDetection>>,
+}
+
+/// The options of a [PackageDepsHasher] that affect the hashes it computes,
+/// to tell whether hashes that another hasher computed can be reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HashingOptions {
+    backend: GitBackend,
+    submodules: SubmoduleMode,
+    lfs: LfsMode,
+    symlinks: SymlinkPolicy,
+    chunked: bool,
+}
+
+impl Default for PackageDepsHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PackageDepsHasher {
+    /// Reads the repository by running the git executable.
+    pub fn new() -> Self {
+        Self {
+            backend: GitBackend::Executable,
+            submodules: SubmoduleMode::default(),
+            concurrency: default_concurrency(),
+            detect_renames: false,
+            lfs: LfsMode::default(),
+            symlinks: SymlinkPolicy::default(),
+            case_collisions: CaseCollisionPolicy::default(),
+            tree_cache: None,
+            observer: None,
+            limits: ProcessLimits::none(),
+            chunked_hasher: None,
+            git_detection: Default::default(),
+        }
+    }
+
+    /// Reads the repository with libgit2 and hashes files in-process, which
+    /// avoids spawning a process per package on large monorepos.
+    pub fn libgit2() -> Self {
+        Self {
+            backend: GitBackend::Libgit2,
+            submodules: SubmoduleMode::default(),
+            concurrency: default_concurrency(),
+            detect_renames: false,
+            lfs: LfsMode::default(),
+            symlinks: SymlinkPolicy::default(),
+            case_collisions: CaseCollisionPolicy::default(),
+            tree_cache: None,
+            observer: None,
+            limits: ProcessLimits::none(),
+            chunked_hasher: None,
+            git_detection: Default::default(),
+        }
+    }
+
+    /// Sets how submodules in a package are hashed. Submodules that aren't
+    /// checked out are always hashed as the commit recorded in `HEAD`.
+    pub fn submodules(mut self, submodules: SubmoduleMode) -> Self {
+        self.submodules = submodules;
+        self
+    }
+
+    /// Sets the maximum number of threads that hash modified and untracked
+    /// files, which defaults to the available parallelism. With the git
+    /// executable, each thread runs its own `git hash-object` process. `1`
+    /// hashes all files on the calling thread.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Runs `git status` with rename and copy detection. Renamed files are
+    /// reported as a single entry instead of a deleted and an untracked file,
+    /// which results in the same hashes. Only affects the git executable.
+    pub fn detect_renames(mut self, detect_renames: bool) -> Self {
+        self.detect_renames = detect_renames;
+        self
+    }
+
+    /// Sets how files that are tracked by Git LFS are hashed. Only affects
+    /// packages in git repositories.
+    pub fn lfs(mut self, lfs: LfsMode) -> Self {
+        self.lfs = lfs;
+        self
+    }
+
+    /// Sets how symlinks in a package are hashed, whether they are committed
+    /// or not.
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Sets what happens when committed files of a package only differ in
+    /// case.
+    pub fn case_collisions(mut self, case_collisions: CaseCollisionPolicy) -> Self {
+        self.case_collisions = case_collisions;
+        self
+    }
+
+    /// Lists the files committed in `HEAD` from `tree_cache`, which is shared
+    /// by all packages that are hashed with it, instead of once per package.
+    pub fn tree_cache(mut self, tree_cache: Arc<TreeCache>) -> Self {
+        self.tree_cache = Some(tree_cache);
+        self
+    }
+
+    /// Reports the progress of hashing each package to `observer`.
+    pub fn observer(mut self, observer: Arc<dyn HashingObserver>) -> Self {
+        self.observer = Some(SharedObserver(observer));
+        self
+    }
+
+    /// Kills git processes that run longer than `timeout`, e.g. `git status`
+    /// on a cold network file system, and fails with [Error::Timeout].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.limits = self.limits.timeout(timeout);
+        self
+    }
+
+    /// Kills running git processes once `cancellation` is cancelled, and
+    /// fails with [Error::Cancelled].
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.limits = self.limits.cancellation(cancellation);
+        self
+    }
+
+    /// Hashes modified and untracked files that are large enough with
+    /// `chunked_hasher`, which only rehashes the changed regions of files it
+    /// hashed before. Their hashes aren't git object hashes, see
+    /// [crate::chunked_hash].
+    pub fn chunked_hasher(mut self, chunked_hasher: Arc<Mutex<ChunkedHasher>>) -> Self {
+        self.chunked_hasher = Some(chunked_hasher);
+        self
+    }
+
+    pub(crate) fn hashing_options(&self) -> HashingOptions {
+        HashingOptions {
+            backend: self.backend,
+            submodules: self.submodules,
+            lfs: self.lfs,
+            symlinks: self.symlinks,
+            chunked: self.chunked_hasher.is_some(),
+        }
+    }
+
+    /// See [get_package_deps].
+    pub fn get_package_deps(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        let start = Instant::now();
+        let observer = PackageObserver::new(
+            self.observer.as_ref().map(|observer| &*observer.0),
+            package_path,
+        );
+        let full_pkg_path = turbo_root.resolve(package_path);
+        let package_prefix = package_path.to_unix();
+        let inputs = InputGlobs::new(inputs)?;
+        let ignores = PackageIgnores::new(
+            IgnoreFile::read(turbo_root)?,
+            package_prefix.to_str()?,
+            IgnoreFile::read(&full_pkg_path)?,
+        );
+        let is_included = |path: &str| inputs.matches(path) && !ignores.is_ignored(path);
+        // Walks the package for the files that git doesn't list: all of them
+        // without git, and the files that the inputs include even if git
+        // ignores them.
+        let walker = Walker::new(full_pkg_path.clone());
+
+        let git_repository = GitRepository::discover(&full_pkg_path)?;
+        if git_repository.is_none() {
+            observer.hashed_without_git("it isn't in a git repository");
+        }
+        if let Some(git_repository) = git_repository {
+            let filter: Option<&dyn Fn(&str) -> bool> =
+                (!ignores.is_empty() || !inputs.is_empty()).then_some(&is_included);
+            if let Some(mut hashes) =
+                self.hash_with_git(&git_repository, &full_pkg_path, filter, &observer)?
+            {
+                inputs.add_included_files(&walker, &ignores, &mut hashes)?;
+                self.apply_symlink_policy(&full_pkg_path, &mut hashes)?;
+                let method = match self.backend {
+                    GitBackend::Executable => HashingMethod::Git,
+                    GitBackend::Libgit2 => HashingMethod::Libgit2,
+                };
+                observer.finished(method, start.elapsed(), hashes.len());
+                return Ok(hashes);
+            }
+        }
+
+        let walker = walker
+            .parents(turbo_root)?
+            .global_excludes(IgnoreFile::read_global_excludes()?);
+        let mut hashes = hash_files_without_git(&walker, is_included)?;
+        inputs.add_included_files(&walker, &ignores, &mut hashes)?;
+        self.apply_symlink_policy(&full_pkg_path, &mut hashes)?;
+        observer.finished(HashingMethod::WithoutGit, start.elapsed(), hashes.len());
+        Ok(hashes)
+    }
+
+    /// Hashes the files below `root_path`, which is in `git_repository`, for
+    /// which `is_included` returns true, or all files if it's `None`. Returns
+    /// `None` if git can't read the repository.
+    fn hash_with_git(
+        &self,
+        git_repository: &GitRepository,
+        root_path: &AbsoluteSystemPathBuf,
+        is_included: Option<&dyn Fn(&str) -> bool>,
+        observer: &PackageObserver,
+    ) -> Result<Option<GitHashes>, Error> {
+        let repository = match self.backend {
+            GitBackend::Executable => {
+                if let Err(reason) = detect_git(
+                    &self.git_detection,
+                    git_repository,
+                    root_path,
+                    &self.limits,
+                    observer,
+                )? {
+                    observer.hashed_without_git(&reason);
+                    return Ok(None);
+                }
+                None
+            }
+            GitBackend::Libgit2 => match open_repository(git_repository, root_path) {
+                Some(repository) => Some(repository),
+                None => {
+                    observer.hashed_without_git("libgit2 can't read the HEAD commit");
+                    return Ok(None);
+                }
+            },
+        };
+
+        let cached = match &self.tree_cache {
+            Some(tree_cache) => tree_cache.ls_tree(
+                git_repository,
+                repository.as_ref().map(|(repository, _)| repository),
+                root_path,
+                &self.limits,
+                observer,
+            )?,
+            None => None,
+        };
+        let (mut hashes, submodule_paths, mut to_hash, staged) = match &repository {
+            None => {
+                let (mut hashes, submodule_paths) = match cached {
+                    Some(cached) => cached,
+                    None => git_ls_tree(git_repository, root_path, &[], &self.limits, observer)?,
+                };
+                let (to_hash, staged) = append_git_status(
+                    git_repository,
+                    root_path,
+                    self.detect_renames,
+                    &mut hashes,
+                    &self.limits,
+                    observer,
+                )?;
+                (hashes, submodule_paths, to_hash, staged)
+            }
+            Some((repository, prefix)) => {
+                let (mut hashes, submodule_paths) = match cached {
+                    Some(cached) => cached,
+                    None => libgit2_ls_tree(repository, prefix, &[])?,
+                };
+                let (to_hash, staged) = libgit2_status(repository, prefix, &mut hashes)?;
+                (hashes, submodule_paths, to_hash, staged)
+            }
+        };
+
+        if self.case_collisions != CaseCollisionPolicy::Ignore {
+            let collisions = find_case_collisions(hashes.keys());
+            if !collisions.is_empty() {
+                if self.case_collisions == CaseCollisionPolicy::Error {
+                    return Err(Error::CaseCollision(collisions, Backtrace::capture()));
+                }
+                observer.case_collisions(&collisions);
+            }
+        }
+
+        // Files outside of a sparse checkout are listed by `ls-tree`, but
+        // `status` doesn't report them as deleted.
+        if git_repository.sparse_checkout()? != SparseCheckout::Disabled {
+            let skipped = match &repository {
+                None => git_skip_worktree(git_repository, root_path, &self.limits, observer)?,
+                Some((repository, prefix)) => libgit2_skip_worktree(repository, prefix)?,
+            };
+            for path in skipped {
+                hashes.remove(&path);
+            }
+        }
+
+        // Files whose working tree content is staged are hashed from the
+        // index, so that their hashes don't change if the files are written
+        // to while the package is hashed. Staged submodules aren't in the
+        // index hashes and are hashed from the working tree below.
+        if !staged.is_empty() {
+            let mut index_hashes = match &repository {
+                None => git_index_hashes(git_repository, root_path, &self.limits, observer)?,
+                Some((repository, prefix)) => libgit2_index_hashes(repository, prefix)?,
+            };
+            for path in staged {
+                match index_hashes.remove(&path) {
+                    Some(hash) => {
+                        hashes.insert(path, hash);
+                    }
+                    None => to_hash.push(path),
+                }
+            }
+        }
+
+        // Submodules are listed as a single entry whose hash is the commit
+        // recorded in `HEAD`, and as modified when their working tree differs
+        // from it. Submodules that were added since `HEAD` are only reported
+        // as a modified directory.
+        let mut submodules = submodule_paths
+            .into_iter()
+            .filter_map(|path| hashes.remove(&path).map(|hash| (path, Some(hash))))
+            .collect::<Vec<_>>();
+        to_hash.retain(|path| {
+            if submodules.iter().any(|(submodule, _)| submodule == path) {
+                return false;
+            }
+            // Symlinks to directories are reported like files.
+            let full_path = root_path.resolve_unix(path);
+            if full_path
+                .symlink_metadata()
+                .map_or(false, |metadata| metadata.is_dir())
+            {
+                submodules.push((path.clone(), None));
+                return false;
+            }
+            true
+        });
+
+        if let Some(is_included) = is_included {
+            let is_included = |path: &RelativeUnixPathBuf| path.to_str().map_or(false, is_included);
+            hashes.retain(|path, _| is_included(path));
+            to_hash.retain(is_included);
+        }
+        if let Some(chunked_hasher) = &self.chunked_hasher {
+            let mut chunked_hasher = chunked_hasher.lock().expect("chunked hasher is poisoned");
+            let mut small_files = Vec::with_capacity(to_hash.len());
+            for path in to_hash {
+                match chunked_hasher.hash_large_file(&root_path.resolve_unix(&path))? {
+                    Some(hash) => {
+                        hashes.insert(path, hash);
+                    }
+                    None => small_files.push(path),
+                }
+            }
+            to_hash = small_files;
+        }
+        match repository {
+            None => {
+                // `git hash-object` reads the file a symlink points to, and
+                // fails for dangling symlinks, so symlinks are hashed
+                // in-process as their target path, like git stores them.
+                let mut files = Vec::with_capacity(to_hash.len());
+                for path in to_hash {
+                    let full_path = root_path.resolve_unix(&path);
+                    let file_type = full_path.symlink_metadata()?.file_type();
+                    if !file_type.is_symlink() {
+                        files.push(path);
+                    } else if let Some(hash) = hash_file(&full_path, file_type)? {
+                        hashes.insert(path, hash.to_string());
+                    }
+                }
+                hash_in_parallel(&files, self.concurrency, &mut hashes, |chunk, hashes| {
+                    git_hash_object(
+                        git_repository,
+                        root_path,
+                        chunk,
+                        hashes,
+                        &self.limits,
+                        observer,
+                    )
+                })?
+            }
+            Some(_) => {
+                hash_in_parallel(&to_hash, self.concurrency, &mut hashes, |chunk, hashes| {
+                    for path in chunk {
+                        let full_path = root_path.resolve_unix(path);
+                        let file_type = full_path.symlink_metadata()?.file_type();
+                        if let Some(hash) = hash_file(&full_path, file_type)? {
+                            hashes.insert(path.clone(), hash.to_string());
+                        }
+                    }
+                    Ok(())
+                })?
+            }
+        }
+
+        if self.lfs != LfsMode::Pointer {
+            let lfs_files = match &repository {
+                None => git_lfs_files(
+                    git_repository,
+                    root_path,
+                    hashes.keys(),
+                    &self.limits,
+                    observer,
+                )?,
+                Some((repository, prefix)) => libgit2_lfs_files(repository, prefix, hashes.keys())?,
+            };
+            for path in lfs_files {
+                let hash = self.hash_lfs_file(git_repository, root_path, &path)?;
+                hashes.insert(path, hash);
+            }
+        }
+
+        for (path, pointer) in submodules {
+            self.hash_submodule(root_path, path, pointer, is_included, &mut hashes, observer)?;
+        }
+        Ok(Some(hashes))
+    }
+
+    /// Rehashes or removes the symlinks among `hashes`, which are hashed as
+    /// their target path, according to [PackageDepsHasher::symlinks].
+    fn apply_symlink_policy(
+        &self,
+        root_path: &AbsoluteSystemPathBuf,
+        hashes: &mut GitHashes,
+    ) -> Result<(), Error> {
+        if self.symlinks == SymlinkPolicy::HashLinkText {
+            return Ok(());
+        }
+        let full_paths = hashes
+            .keys()
+            .map(|path| root_path.resolve_unix(path))
+            .collect::<Vec<_>>();
+        let mut metadata = symlink_stat_many(&full_paths);
+        let mut symlinks = Vec::new();
+        for (path, full_path) in hashes.keys().zip(full_paths) {
+            let metadata = metadata
+                .remove(&full_path)
+                .expect("every path was stated")?;
+            if metadata.file_type().is_symlink() {
+                symlinks.push((path.clone(), full_path));
+            }
+        }
+        for (path, full_path) in symlinks {
+            match self.symlinks {
+                SymlinkPolicy::Skip => {
+                    hashes.remove(&path);
+                }
+                SymlinkPolicy::HashTarget => match full_path.stat() {
+                    Ok(metadata) if metadata.is_file() => {
+                        let hash = Oid::hash_file(ObjectType::Blob, &full_path)?;
+                        hashes.insert(path, hash.to_string());
+                    }
+                    // A symlink to a directory.
+                    Ok(_) => {}
+                    Err(e) if e.is_io_error(io::ErrorKind::NotFound) => {}
+                    Err(e) => return Err(e.into()),
+                },
+                SymlinkPolicy::HashLinkText => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Hashes the file at `path`, relative to `root_path`, which is tracked by
+    /// LFS, according to [PackageDepsHasher::lfs].
+    fn hash_lfs_file(
+        &self,
+        git_repository: &GitRepository,
+        root_path: &AbsoluteSystemPathBuf,
+        path: &RelativeUnixPath,
+    ) -> Result<String, Error> {
+        let full_path = root_path.as_path().join(path.as_path());
+        let pointer = lfs::read_pointer(&full_path)?;
+        Ok(match (self.lfs, pointer) {
+            (LfsMode::Oid, Some(oid)) => oid,
+            (LfsMode::Oid, None) => lfs::object_id(&full_path)?,
+            (LfsMode::Content, Some(oid)) => {
+                let object = lfs::object_path(git_repository.common_dir().as_path(), &oid);
+                if !object.exists() {
+                    return Err(Error::LfsObjectMissing(full_path, Backtrace::capture()));
+                }
+                Oid::hash_file(ObjectType::Blob, object)?.to_string()
+            }
+            (LfsMode::Content, None) | (LfsMode::Pointer, _) => {
+                Oid::hash_file(ObjectType::Blob, &full_path)?.to_string()
+            }
+        })
+    }
+
+    /// Adds the hashes of the submodule at `path`, relative to `root_path`, to
+    /// `hashes`. `pointer` is the commit recorded for the submodule in `HEAD`,
+    /// if any.
+    fn hash_submodule(
+        &self,
+        root_path: &AbsoluteSystemPathBuf,
+        path: RelativeUnixPathBuf,
+        pointer: Option<String>,
+        is_included: Option<&dyn Fn(&str) -> bool>,
+        hashes: &mut GitHashes,
+        observer: &PackageObserver,
+    ) -> Result<(), Error> {
+        let full_path = AbsoluteSystemPathBuf::new(root_path.as_path().join(path.as_path()))?;
+        // A submodule that isn't checked out is an empty directory in the
+        // working tree of the parent repository.
+        let submodule = GitRepository::discover(&full_path)?.filter(|submodule| {
+            full_path
+                .realpath()
+                .map_or(false, |full_path| &full_path == submodule.work_tree())
+        });
+
+        if let (SubmoduleMode::Recurse, Some(submodule)) = (self.submodules, &submodule) {
+            let prefix = format!("{}/", path.to_str()?);
+            let is_included_in_submodule = |file: &str| {
+                is_included.map_or(true, |is_included| {
+                    is_included(&format!("{}{}", prefix, file))
+                })
+            };
+            if let Some(submodule_hashes) = self.hash_with_git(
+                submodule,
+                &full_path,
+                Some(&is_included_in_submodule),
+                observer,
+            )? {
+                for (file, hash) in submodule_hashes {
+                    hashes.insert(path.join(&file), hash);
+                }
+                return Ok(());
+            }
+        }
+
+        let hash = match &submodule {
+            Some(submodule) => Some(self.head_commit(submodule, &full_path, observer)?),
+            None => pointer,
+        };
+        if let Some(hash) = hash {
+            let path_str = path.to_str()?;
+            if is_included.map_or(true, |is_included| is_included(path_str)) {
+                hashes.insert(path, hash);
+            }
+        }
+        Ok(())
+    }
+
+    /// The commit that is checked out in `repository`.
+    fn head_commit(
+        &self,
+        repository: &GitRepository,
+        root_path: &AbsoluteSystemPathBuf,
+        observer: &PackageObserver,
+    ) -> Result<String, Error> {
+        match self.backend {
+            GitBackend::Executable => {
+                let stdout = run_git(
+                    repository,
+                    root_path,
+                    &["rev-parse", "HEAD"],
+                    &self.limits,
+                    observer,
+                )?;
+                Ok(utf8_output("rev-parse", stdout)?.trim_end().to_string())
+            }
+            GitBackend::Libgit2 => Ok(repository
+                .open()?
+                .head()?
+                .peel_to_commit()?
+                .id()
+                .to_string()),
+        }
+    }
+}
+
+pub(crate) fn default_concurrency() -> usize {
+    thread::available_parallelism().map_or(1, |parallelism| parallelism.get())
+}
+
+/// The number of files that a thread of [hash_in_parallel] takes at a time.
+const HASH_CHUNK_SIZE: usize = 256;
+
+/// Hashes `to_hash` with `hash_chunk` on up to `concurrency` threads and adds
+/// the hashes to `hashes`. The files are split into chunks that idle threads
+/// take from a shared queue, so a thread that hits slow files doesn't hold up
+/// the others.
+pub(crate) fn hash_in_parallel(
+    to_hash: &[RelativeUnixPathBuf],
+    concurrency: usize,
+    hashes: &mut GitHashes,
+    hash_chunk: impl Fn(&[RelativeUnixPathBuf], &mut GitHashes) -> Result<(), Error> + Sync,
+) -> Result<(), Error> {
+    let chunks = to_hash.chunks(HASH_CHUNK_SIZE).collect::<Vec<_>>();
+    let threads = concurrency.min(chunks.len());
+    if threads <= 1 {
+        return chunks
+            .into_iter()
+            .try_for_each(|chunk| hash_chunk(chunk, hashes));
+    }
+
+    let next_chunk = AtomicUsize::new(0);
+    let results = thread::scope(|scope| {
+        let handles = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut hashes = GitHashes::new();
+                    while let Some(chunk) = chunks.get(next_chunk.fetch_add(1, Ordering::Relaxed)) {
+                        hash_chunk(chunk, &mut hashes)?;
+                    }
+                    Ok(hashes)
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("hashing files panicked"))
+            .collect::<Result<Vec<_>, Error>>()
+    })?;
+    for result in results {
+        hashes.extend(result);
+    }
+    Ok(())
+}
+
+/// Groups the paths among `paths` that only differ in case. The groups and
+/// the paths in them are sorted.
+pub fn find_case_collisions<'a>(
+    paths: impl IntoIterator<Item = &'a RelativeUnixPathBuf>,
+) -> Vec<Vec<RelativeUnixPathBuf>> {
+    let mut by_lowercase = HashMap::<String, Vec<RelativeUnixPathBuf>>::new();
+    for path in paths {
+        let key = path.as_path().to_string_lossy().to_lowercase();
+        by_lowercase.entry(key).or_default().push(path.clone());
+    }
+    let mut collisions = by_lowercase
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|mut paths| {
+            paths.sort();
+            paths
+        })
+        .collect::<Vec<_>>();
+    collisions.sort();
+    collisions
+}
+
+/// An input of [get_package_deps] that stands for the files that are hashed
+/// without inputs, so that other inputs add files to them instead of
+/// replacing them.
+pub const TURBO_DEFAULT: &str = "$TURBO_DEFAULT$";
+
+/// The `inputs` of [get_package_deps]. Patterns starting with `!` exclude
+/// files that other patterns include, and [TURBO_DEFAULT] includes the
+/// default files.
+#[derive(Debug)]
+pub(crate) struct InputGlobs {
+    default: bool,
+    globs: GlobSet,
+}
+
+impl InputGlobs {
+    pub(crate) fn new(inputs: &[&str]) -> Result<Self, Error> {
+        let globs = inputs
+            .iter()
+            .filter(|input| **input != TURBO_DEFAULT)
+            .collect::<Vec<_>>();
+        Ok(Self {
+            default: inputs.contains(&TURBO_DEFAULT),
+            globs: GlobSet::new(&globs)?,
+        })
+    }
+
+    /// Whether all default files match.
+    pub(crate) fn is_empty(&self) -> bool {
+        (self.default || !self.globs.has_includes()) && !self.globs.has_excludes()
+    }
+
+    /// Whether `path`, relative to the package, matches. Without include
+    /// patterns, or with [TURBO_DEFAULT], all files that aren't excluded
+    /// match.
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        let Ok(path) = RelativeUnixPath::new(Path::new(path)) else {
+            return false;
+        };
+        (self.default || !self.globs.has_includes() || self.globs.is_included(path))
+            && !self.globs.is_excluded(path)
+    }
+
+    /// Adds the files that `walker` finds and the include patterns match to
+    /// `hashes`, which has the hashes of the default files, if the inputs
+    /// contain [TURBO_DEFAULT]. Files that git ignores are added as well, but
+    /// not files that are excluded or ignored by `ignores`, nor files in
+    /// `node_modules` directories. Files that are already in `hashes` keep
+    /// their hash.
+    pub(crate) fn add_included_files(
+        &self,
+        walker: &Walker,
+        ignores: &PackageIgnores,
+        hashes: &mut GitHashes,
+    ) -> Result<(), Error> {
+        if !self.default || !self.globs.has_includes() {
+            return Ok(());
+        }
+        let walker = walker.clone().gitignore(false);
+        let added = hash_files_without_git(&walker, |path| {
+            RelativeUnixPath::new(Path::new(path)).map_or(false, |path| {
+                self.globs.is_match(path) && !hashes.contains_key(path)
+            }) && !ignores.is_ignored(path)
+        })?;
+        hashes.extend(added);
+        Ok(())
+    }
+}
+
+/// The hash of a file together with metadata that is useful for heuristics,
+/// e.g. to schedule recently changed packages first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    pub hash: String,
+    /// The last modification time, if the platform supports it.
+    pub mtime: Option<SystemTime>,
+    /// The size in bytes.
+    pub size: u64,
+}
+
+/// Like [get_package_deps], but also returns the modification time and size of
+/// each file.
+pub fn get_package_deps_with_metadata(
+    turbo_root: &AbsoluteSystemPathBuf,
+    package_path: &AnchoredSystemPath,
+    inputs: &[&str],
+) -> Result<HashMap<RelativeUnixPathBuf, FileInfo>, Error> {
+    let full_pkg_path = turbo_root.resolve(package_path);
+    let hashes = get_package_deps(turbo_root, package_path, inputs)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let full_paths = hashes
+        .iter()
+        .map(|(path, _)| full_pkg_path.resolve_unix(path))
+        .collect::<Vec<_>>();
+    let mut metadata = symlink_stat_many(&full_paths);
+    hashes
+        .into_iter()
+        .zip(&full_paths)
+        .map(|((path, hash), full_path)| {
+            let metadata = metadata.remove(full_path).expect("every path was stated")?;
+            let info = FileInfo {
+                hash,
+                mtime: metadata.modified().ok(),
+                size: metadata.len(),
+            };
+            Ok((path, info))
+        })
+        .collect()
+}
+
+/// Whether the git executable can be used, which is detected once per
+/// [PackageDepsHasher] instead of once per package: the version of git once,
+/// and the `HEAD` commit once per repository.
+#[derive(Debug, Default)]
+struct GitDetection {
+    /// Why git can't be used at all, if it can't.
+    version: Option<Result<(), String>>,
+    /// Why git can't read each repository, if it can't, by git directory.
+    repositories: HashMap<AbsoluteSystemPathBuf, Result<(), String>>,
+}
+
+/// Checks that a supported version of git is installed, and that
+/// `root_path`, which is in `repository`, has a `HEAD` commit that git can
+/// read, or returns why it isn't so. Fails with the errors for which
+/// [Error::can_hash_without_git] is false, e.g. if git was killed with
+/// [Error::Timeout] or [Error::Cancelled], as they don't mean that git is
+/// unavailable, and they aren't remembered in `detection`.
+fn detect_git(
+    detection: &Mutex<GitDetection>,
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<Result<(), String>, Error> {
+    // Packages that are hashed concurrently wait for the first one to detect
+    // git, instead of detecting it at the same time.
+    let mut detection = detection.lock().unwrap();
+    let version = match &detection.version {
+        Some(version) => version.clone(),
+        None => {
+            let version = match git::check_version() {
+                Ok(_) => Ok(()),
+                Err(error) if error.can_hash_without_git() => Err(error.to_string()),
+                Err(error) => return Err(error),
+            };
+            detection.version = Some(version.clone());
+            version
+        }
+    };
+    if let Err(reason) = version {
+        return Ok(Err(reason));
+    }
+    if let Some(result) = detection.repositories.get(repository.git_dir()) {
+        return Ok(result.clone());
+    }
+
+    let args = ["rev-parse", "--verify", "--quiet", "HEAD"];
+    let start = Instant::now();
+    let result = match process::output(
+        repository.command(root_path).args(args),
+        root_path.as_path(),
+        limits,
+    ) {
+        Ok(output) => {
+            observer.subprocess(&Subprocess {
+                args: &args,
+                duration: start.elapsed(),
+                success: output.status.success(),
+            });
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err("the repository has no commit".to_string())
+            }
+        }
+        Err(error) if error.can_hash_without_git() => Err(error.to_string()),
+        Err(error) => return Err(error),
+    };
+    detection
+        .repositories
+        .insert(repository.git_dir().clone(), result.clone());
+    Ok(result)
+}
+
+/// Directories that are never hashed without git.
+const IGNORED_DIRECTORIES: &[&str] = &["node_modules"];
+
+/// Hashes the files that `walker` finds and for which `is_included` returns
+/// true in-process, for when git is unavailable, e.g. in exported tarballs or
+/// Docker builds without `.git`. The hashes are identical to the git object
+/// hashes of the files.
+pub(crate) fn hash_files_without_git(
+    walker: &Walker,
+    is_included: impl Fn(&str) -> bool,
+) -> Result<GitHashes, Error> {
+    let walker = IGNORED_DIRECTORIES
+        .iter()
+        .fold(walker.clone(), |walker, dir| walker.skip_directory(dir));
+    let mut hashes = GitHashes::new();
+    for (path, file_type) in walker.walk()? {
+        if !is_included(path.to_str()?) {
+            continue;
+        }
+        if let Some(hash) = hash_file(&walker.root().resolve_unix(&path), file_type)? {
+            hashes.insert(path, hash.to_string());
+        }
+    }
+    Ok(hashes)
+}
+
+/// Computes the git object hash of the file at `full_path` in-process, or
+/// `None` if it's neither a regular file nor a symlink.
+pub(crate) fn hash_file(
+    full_path: &AbsoluteSystemPathBuf,
+    file_type: fs::FileType,
+) -> Result<Option<Oid>, Error> {
+    Ok(if file_type.is_symlink() {
+        // git stores the target of a symlink as the content of the blob.
+        let target = full_path.read_link()?;
+        let target = target
+            .to_str()
+            .ok_or_else(|| PathValidationError::InvalidUnicode(target.clone()))?
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        Some(Oid::hash_object(ObjectType::Blob, target.as_bytes())?)
+    } else if file_type.is_file() {
+        Some(Oid::hash_file(ObjectType::Blob, full_path.as_path())?)
+    } else {
+        None
+    })
+}
+
+/// Opens `repository`, which contains `root_path`, if it has a `HEAD` commit,
+/// and returns it with the path of `root_path` relative to its working
+/// directory.
+fn open_repository(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+) -> Option<(Repository, String)> {
+    let repository = repository.open().ok()?;
+    repository.head().ok()?.peel_to_commit().ok()?;
+    let workdir =
+        AbsoluteSystemPathBuf::new(dunce::canonicalize(repository.workdir()?).ok()?).ok()?;
+    let root_path = AbsoluteSystemPathBuf::new(dunce::canonicalize(root_path).ok()?).ok()?;
+    let prefix = root_path.anchor_at(&workdir).ok()?.to_unix();
+    Some((repository, prefix.to_str().ok()?.to_string()))
+}
+
+/// Like [git_ls_tree], for the directory `prefix` of `repository`. Subtrees
+/// that are neither below nor above one of `pathspecs` are skipped without
+/// being read.
+pub(crate) fn libgit2_ls_tree(
+    repository: &Repository,
+    prefix: &str,
+    pathspecs: &[&str],
+) -> Result<(GitHashes, Vec<RelativeUnixPathBuf>), Error> {
+    let head = repository.head()?.peel_to_tree()?;
+    let tree = if prefix.is_empty() {
+        head
+    } else {
+        match head.get_path(Path::new(prefix)) {
+            Ok(entry) => entry.to_object(repository)?.peel_to_tree()?,
+            // The package has no committed files.
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok((GitHashes::new(), Vec::new())),
+            Err(e) => return Err(e.into()),
+        }
+    };
+    let mut hashes = GitHashes::new();
+    let mut submodules = Vec::new();
+    let mut error = None;
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        let Some(name) = entry.name() else {
+            return TreeWalkResult::Ok;
+        };
+        let path = format!("{}{}", dir, name);
+        if entry.kind() == Some(ObjectType::Tree) {
+            return if pathspecs.is_empty()
+                || pathspecs
+                    .iter()
+                    .any(|pathspec| is_below(&path, pathspec) || is_below(pathspec, &path))
+            {
+                TreeWalkResult::Ok
+            } else {
+                TreeWalkResult::Skip
+            };
+        }
+        if !pathspecs.is_empty() && !pathspecs.iter().any(|pathspec| is_below(&path, pathspec)) {
+            return TreeWalkResult::Ok;
+        }
+        match RelativeUnixPathBuf::new(path) {
+            Ok(path) => {
+                if entry.kind() == Some(ObjectType::Commit) {
+                    submodules.push(path.clone());
+                }
+                hashes.insert(path, entry.id().to_string());
+                TreeWalkResult::Ok
+            }
+            Err(e) => {
+                error = Some(e);
+                TreeWalkResult::Abort
+            }
+        }
+    })?;
+    match error {
+        Some(e) => Err(e.into()),
+        None => Ok((hashes, submodules)),
+    }
+}
+
+/// Whether `path` is `dir` or a path below it. Both are relative unix paths,
+/// and the empty path is the root.
+pub(crate) fn is_below(path: &str, dir: &str) -> bool {
+    dir.is_empty()
+        || path
+            .strip_prefix(dir)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Like [append_git_status], for the directory `prefix` of `repository`.
+fn libgit2_status(
+    repository: &Repository,
+    prefix: &str,
+    hashes: &mut GitHashes,
+) -> Result<(Vec<RelativeUnixPathBuf>, Vec<RelativeUnixPathBuf>), Error> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(false)
+        .renames_index_to_workdir(false);
+    if !prefix.is_empty() {
+        options.pathspec(prefix);
+    }
+
+    let mut unmerged = Vec::new();
+    let mut to_hash = Vec::new();
+    let mut staged = Vec::new();
+    for entry in repository.statuses(Some(&mut options))?.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        let path = if prefix.is_empty() {
+            path
+        } else {
+            match path
+                .strip_prefix(prefix)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        let path = RelativeUnixPathBuf::new(path)?;
+        let status = entry.status();
+        if status.is_conflicted() {
+            unmerged.push(path);
+        } else if status.intersects(Status::INDEX_DELETED | Status::WT_DELETED) {
+            hashes.remove(&path);
+        } else if !status.intersects(
+            Status::WT_NEW | Status::WT_MODIFIED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+        ) {
+            staged.push(path);
+        } else {
+            to_hash.push(path);
+        }
+    }
+
+    // Unlike git, libgit2 leaves files that are marked with `update-index
+    // --assume-unchanged` out of the status entirely, even when they were
+    // added since `HEAD`. git hashes them from the index.
+    for entry in repository.index()?.iter() {
+        if entry.flags & INDEX_ENTRY_ASSUME_UNCHANGED == 0 {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path);
+        let path = if prefix.is_empty() {
+            &*path
+        } else {
+            match path
+                .strip_prefix(prefix)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        let path = RelativeUnixPathBuf::new(path)?;
+        if !staged.contains(&path) {
+            staged.push(path);
+        }
+    }
+
+    if !unmerged.is_empty() {
+        unmerged.sort();
+        return Err(Error::Unmerged(unmerged, Backtrace::capture()));
+    }
+    Ok((to_hash, staged))
+}
+
+/// The flag of index entries that are marked with `update-index
+/// --assume-unchanged`.
+const INDEX_ENTRY_ASSUME_UNCHANGED: u16 = 0x8000;
+
+/// Like [git_index_hashes], for the directory `prefix` of `repository`.
+fn libgit2_index_hashes(repository: &Repository, prefix: &str) -> Result<GitHashes, Error> {
+    let mut hashes = GitHashes::new();
+    for entry in repository.index()?.iter() {
+        // Skip submodules, whose mode is 160000, and the stages of unmerged
+        // files.
+        if entry.mode == 0o160000 || (entry.flags >> 12) & 0x3 != 0 {
+            continue;
+        }
+        let path = String::from_utf8(entry.path)
+            .map_err(|e| invalid_output("index", &String::from_utf8_lossy(e.as_bytes())))?;
+        let path = if prefix.is_empty() {
+            path.as_str()
+        } else {
+            match path
+                .strip_prefix(prefix)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        hashes.insert(RelativeUnixPathBuf::new(path)?, entry.id.to_string());
+    }
+    Ok(hashes)
+}
+
+/// Reads the hashes of all files committed in `HEAD` below `root_path`, and
+/// the paths of the submodules among them, whose hash is a commit.
+///
+/// If `pathspecs` isn't empty, only the files below these paths, which are
+/// relative to `root_path`, are listed. git doesn't descend into the other
+/// directories at all, which saves most of the time in repositories with large
+/// directories that no package needs, e.g. vendored toolchains.
+pub(crate) fn git_ls_tree(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    pathspecs: &[&str],
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<(GitHashes, Vec<RelativeUnixPathBuf>), Error> {
+    let mut hashes = GitHashes::new();
+    let mut submodules = Vec::new();
+    let mut chunks = pathspec_chunks(pathspecs);
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for chunk in chunks {
+        let mut args = vec!["ls-tree", "-r", "-z", "HEAD"];
+        if !chunk.is_empty() {
+            args.push("--");
+            args.extend_from_slice(chunk);
+        }
+        let stdout = run_git(repository, root_path, &args, limits, observer)?;
+        let mut records = NulRecordReader::new("ls-tree", stdout.as_slice());
+        while let Some((is_submodule, hash, path)) = records.next_record(ls_tree_entry)? {
+            let path = RelativeUnixPathBuf::new(path)?;
+            if is_submodule {
+                submodules.push(path.clone());
+            }
+            hashes.insert(path, hash);
+        }
+    }
+    Ok((hashes, submodules))
+}
+
+/// The total length of the pathspecs that are passed to one invocation of
+/// git. Windows limits command lines to 32767 characters.
+const MAX_PATHSPECS_LEN: usize = 16 * 1024;
+
+/// Splits `pathspecs` into chunks that are passed to separate invocations of
+/// git, so that command lines stay below the limits of the platform.
+fn pathspec_chunks<'a>(pathspecs: &'a [&'a str]) -> Vec<&'a [&'a str]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut len = 0;
+    for (i, pathspec) in pathspecs.iter().enumerate() {
+        if i > start && len + pathspec.len() + 1 > MAX_PATHSPECS_LEN {
+            chunks.push(&pathspecs[start..i]);
+            start = i;
+            len = 0;
+        }
+        len += pathspec.len() + 1;
+    }
+    if start < pathspecs.len() {
+        chunks.push(&pathspecs[start..]);
+    }
+    chunks
+}
+
+/// An entry of `git ls-tree -z`: whether it's a submodule, its hash and its
+/// path.
+fn ls_tree_entry(input: &[u8]) -> IResult<&[u8], (bool, String, String)> {
+    // <mode> SP <type> SP <object> TAB <file> NUL
+    let (input, (_, object_type, hash, path)) = map_parser(
+        nul_records::field,
+        all_consuming(tuple((
+            terminated(is_not(" "), tag(" ")),
+            terminated(is_not(" "), tag(" ")),
+            terminated(is_not("\t"), tag("\t")),
+            rest,
+        ))),
+    )(input)?;
+    Ok((
+        input,
+        (
+            object_type == b"commit",
+            String::from_utf8_lossy(hash).into_owned(),
+            nul_records::decode_path(path),
+        ),
+    ))
+}
+
+/// Lists the files below `root_path` that have the skip-worktree bit set in
+/// the index, which are the files outside of a sparse checkout.
+fn git_skip_worktree(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+    // `ls-files` reports paths relative to the working directory.
+    let stdout = run_git(
+        repository,
+        root_path,
+        &["ls-files", "-t", "-z", "--", "."],
+        limits,
+        observer,
+    )?;
+    let mut records = NulRecordReader::new("ls-files", stdout.as_slice());
+    let mut skipped = Vec::new();
+    while let Some((tag, path)) = records.next_record(ls_files_entry)? {
+        // The tag of skip-worktree files is `S`.
+        if tag == "S" {
+            skipped.push(RelativeUnixPathBuf::new(path)?);
+        }
+    }
+    Ok(skipped)
+}
+
+/// An entry of `git ls-files -t -z`: its tag and its path.
+fn ls_files_entry(input: &[u8]) -> IResult<&[u8], (String, String)> {
+    // <tag> SP <file> NUL
+    let (input, (tag, path)) = map_parser(
+        nul_records::field,
+        all_consuming(tuple((terminated(is_not(" "), tag(" ")), rest))),
+    )(input)?;
+    Ok((
+        input,
+        (
+            String::from_utf8_lossy(tag).into_owned(),
+            nul_records::decode_path(path),
+        ),
+    ))
+}
+
+/// Returns the regular files among `paths`, relative to `root_path`, that
+/// are tracked by LFS according to their `filter` attribute.
+fn git_lfs_files<'a>(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    paths: impl Iterator<Item = &'a RelativeUnixPathBuf>,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+    let mut input = String::new();
+    for path in paths {
+        input.push_str(path.to_str()?);
+        input.push('\0');
+    }
+    if input.is_empty() {
+        return Ok(Vec::new());
+    }
+    let stdout = run_git_with_input(
+        repository,
+        root_path,
+        &["check-attr", "-z", "--stdin", "filter"],
+        input,
+        limits,
+        observer,
+    )?;
+    let mut records = NulRecordReader::new("check-attr", stdout.as_slice());
+    let mut lfs_files = Vec::new();
+    while let Some((path, is_lfs)) = records.next_record(check_attr_entry)? {
+        if is_lfs && is_regular_file(root_path, &path) {
+            lfs_files.push(RelativeUnixPathBuf::new(path)?);
+        }
+    }
+    Ok(lfs_files)
+}
+
+/// An entry of `git check-attr -z filter`: the path and whether its filter
+/// is LFS.
+fn check_attr_entry(input: &[u8]) -> IResult<&[u8], (String, bool)> {
+    // <path> NUL <attribute> NUL <value> NUL
+    let (input, (path, _, value)) =
+        tuple((nul_records::path, nul_records::field, nul_records::field))(input)?;
+    Ok((input, (path, value == lfs::LFS_FILTER.as_bytes())))
+}
+
+/// Like [git_lfs_files], for the directory `prefix` of `repository`.
+fn libgit2_lfs_files<'a>(
+    repository: &Repository,
+    prefix: &str,
+    paths: impl Iterator<Item = &'a RelativeUnixPathBuf>,
+) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+    let workdir = repository.workdir().ok_or_else(|| {
+        Error::BareRepository(repository.path().to_path_buf(), Backtrace::capture())
+    })?;
+    let root_path = AbsoluteSystemPathBuf::new(workdir.join(prefix))?;
+    let mut lfs_files = Vec::new();
+    for path in paths {
+        let repo_path = if prefix.is_empty() {
+            path.to_str()?.to_string()
+        } else {
+            format!("{}/{}", prefix, path.to_str()?)
+        };
+        let filter =
+            repository.get_attr(Path::new(&repo_path), "filter", AttrCheckFlags::default())?;
+        if filter == Some(lfs::LFS_FILTER) && is_regular_file(&root_path, path.to_str()?) {
+            lfs_files.push(path.clone());
+        }
+    }
+    Ok(lfs_files)
+}
+
+/// Whether `path`, relative to `root_path`, is a regular file in the working
+/// tree. Symlinks and submodules are never tracked by LFS.
+fn is_regular_file(root_path: &AbsoluteSystemPathBuf, path: &str) -> bool {
+    RelativeUnixPath::new(Path::new(path)).map_or(false, |path| {
+        root_path
+            .resolve_unix(path)
+            .symlink_metadata()
+            .map_or(false, |metadata| metadata.is_file())
+    })
+}
+
+/// Like [git_skip_worktree], for the directory `prefix` of `repository`.
+fn libgit2_skip_worktree(
+    repository: &Repository,
+    prefix: &str,
+) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+    let mut skipped = Vec::new();
+    for entry in repository.index()?.iter() {
+        if !IndexEntryExtendedFlag::from_bits_truncate(entry.flags_extended).is_skip_worktree() {
+            continue;
+        }
+        let path = String::from_utf8(entry.path)
+            .map_err(|e| invalid_output("index", &String::from_utf8_lossy(e.as_bytes())))?;
+        let path = if prefix.is_empty() {
+            path.as_str()
+        } else {
+            match path
+                .strip_prefix(prefix)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        skipped.push(RelativeUnixPathBuf::new(path)?);
+    }
+    Ok(skipped)
+}
+
+/// The two-letter status code of a `git status --porcelain` entry. See
+/// `git help status` for the meaning of each combination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusCode {
+    /// Status of the index.
+    pub x: u8,
+    /// Status of the working tree.
+    pub y: u8,
+}
+
+impl StatusCode {
+    /// Whether the path has unresolved merge conflicts (`DD`, `AU`, `UD`,
+    /// `UA`, `DU`, `AA` or `UU`).
+    pub fn is_unmerged(&self) -> bool {
+        matches!(
+            (self.x, self.y),
+            (b'D', b'D')
+                | (b'A', b'U')
+                | (b'U', b'D')
+                | (b'U', b'A')
+                | (b'D', b'U')
+                | (b'A', b'A')
+                | (b'U', b'U')
+        )
+    }
+
+    pub fn is_delete(&self) -> bool {
+        !self.is_unmerged() && (self.x == b'D' || self.y == b'D')
+    }
+
+    /// Whether the path was renamed from another path.
+    pub fn is_rename(&self) -> bool {
+        self.x == b'R' || self.y == b'R'
+    }
+
+    /// Whether the path was renamed or copied from another path, which is
+    /// reported in an additional field.
+    pub fn has_original_path(&self) -> bool {
+        self.is_rename() || self.x == b'C' || self.y == b'C'
+    }
+
+    /// Whether the index differs from `HEAD` and the working tree matches
+    /// the index, i.e. the changes of the path are staged.
+    pub fn is_staged(&self) -> bool {
+        matches!(self.x, b'A' | b'M' | b'R' | b'C' | b'T') && self.y == b' '
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StatusEntry {
+    /// The path, relative to the repository root.
+    pub(crate) path: String,
+    /// The path that a renamed or copied path was renamed or copied from.
+    original_path: Option<String>,
+    code: StatusCode,
+}
+
+pub(crate) fn parse_status(stdout: &[u8]) -> Result<Vec<StatusEntry>, Error> {
+    let mut records = NulRecordReader::new("status", stdout);
+    let mut entries = Vec::new();
+    while let Some(entry) = records.next_record(status_entry)? {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// An entry of `git status -z`.
+fn status_entry(input: &[u8]) -> IResult<&[u8], StatusEntry> {
+    // XY SP <path> NUL
+    let (input, entry) = nul_records::field(input)?;
+    let [x, y, b' ', path @ ..] = entry else {
+        return Err(nom::Err::Error(ParseError::new(entry, ErrorKind::Verify)));
+    };
+    if path.is_empty() {
+        return Err(nom::Err::Error(ParseError::new(entry, ErrorKind::Verify)));
+    }
+    let code = StatusCode { x: *x, y: *y };
+    // The original path of a rename or copy follows as a separate field.
+    let (input, original_path) = cond(code.has_original_path(), nul_records::path)(input)?;
+    Ok((
+        input,
+        StatusEntry {
+            path: nul_records::decode_path(path),
+            original_path,
+            code,
+        },
+    ))
+}
+
+/// Applies uncommitted changes below `root_path` to `hashes`. Deleted files
+/// and the original paths of renamed files are removed, and the paths of
+/// modified, renamed, copied and untracked files are returned so they can be
+/// hashed: first the files that are hashed from the working tree, then the
+/// [staged](StatusCode::is_staged) files, which are hashed from the index.
+fn append_git_status(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    detect_renames: bool,
+    hashes: &mut GitHashes,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<(Vec<RelativeUnixPathBuf>, Vec<RelativeUnixPathBuf>), Error> {
+    // `git status -z` reports paths relative to the repository root.
+    let prefix = nul_records::decode_path(&run_git(
+        repository,
+        root_path,
+        &["rev-parse", "--show-prefix"],
+        limits,
+        observer,
+    )?);
+    let prefix = prefix.trim_end();
+    let stdout = run_git(
+        repository,
+        root_path,
+        &[
+            "status",
+            "--untracked-files",
+            if detect_renames {
+                "--find-renames"
+            } else {
+                "--no-renames"
+            },
+            "-z",
+            "--",
+            ".",
+        ],
+        limits,
+        observer,
+    )?;
+
+    let mut unmerged = Vec::new();
+    let mut to_hash = Vec::new();
+    let mut staged = Vec::new();
+    for entry in parse_status(&stdout)? {
+        if entry.code.is_rename() {
+            // The original path is outside of the package if the file was
+            // moved into it.
+            if let Some(original_path) = entry
+                .original_path
+                .as_deref()
+                .and_then(|path| path.strip_prefix(prefix))
+            {
+                hashes.remove(&RelativeUnixPathBuf::new(original_path)?);
+            }
+        }
+        let path = entry
+            .path
+            .strip_prefix(prefix)
+            .ok_or_else(|| invalid_output("status", &entry.path))?;
+        let path = RelativeUnixPathBuf::new(path)?;
+        if entry.code.is_unmerged() {
+            unmerged.push(path);
+        } else if entry.code.is_delete() {
+            hashes.remove(&path);
+        } else if entry.code.is_staged() {
+            staged.push(path);
+        } else {
+            to_hash.push(path);
+        }
+    }
+
+    if !unmerged.is_empty() {
+        unmerged.sort();
+        return Err(Error::Unmerged(unmerged, Backtrace::capture()));
+    }
+    Ok((to_hash, staged))
+}
+
+/// Reads the hashes of the files in the index below `root_path`. Submodules
+/// and unmerged files are left out.
+fn git_index_hashes(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<GitHashes, Error> {
+    // `ls-files` reports paths relative to the working directory.
+    let stdout = run_git(
+        repository,
+        root_path,
+        &["ls-files", "--stage", "-z", "--", "."],
+        limits,
+        observer,
+    )?;
+    let mut records = NulRecordReader::new("ls-files", stdout.as_slice());
+    let mut hashes = GitHashes::new();
+    while let Some((mode, hash, stage, path)) = records.next_record(ls_files_stage_entry)? {
+        if mode != "160000" && stage == "0" {
+            hashes.insert(RelativeUnixPathBuf::new(path)?, hash);
+        }
+    }
+    Ok(hashes)
+}
+
+/// An entry of `git ls-files --stage -z`: its mode, hash, stage and path.
+fn ls_files_stage_entry(input: &[u8]) -> IResult<&[u8], (String, String, String, String)> {
+    // <mode> SP <object> SP <stage> TAB <file> NUL
+    let (input, (mode, hash, stage, path)) = map_parser(
+        nul_records::field,
+        all_consuming(tuple((
+            terminated(is_not(" "), tag(" ")),
+            terminated(is_not(" "), tag(" ")),
+            terminated(is_not("\t"), tag("\t")),
+            rest,
+        ))),
+    )(input)?;
+    let lossy = |field: &[u8]| String::from_utf8_lossy(field).into_owned();
+    Ok((
+        input,
+        (
+            lossy(mode),
+            lossy(hash),
+            lossy(stage),
+            nul_records::decode_path(path),
+        ),
+    ))
+}
+
+/// Hashes the working tree content of `to_hash`, relative to `root_path`, and
+/// adds the hashes to `hashes`.
+///
+/// The paths are streamed to `git hash-object --stdin-paths`, so the number of
+/// files isn't limited by the maximum length of a command line.
+fn git_hash_object(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    to_hash: &[RelativeUnixPathBuf],
+    hashes: &mut GitHashes,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<(), Error> {
+    if to_hash.is_empty() {
+        return Ok(());
+    }
+    // Unlike paths passed as arguments, paths read from stdin are resolved
+    // against the root of the repository rather than the working directory.
+    let mut input = String::new();
+    for path in to_hash {
+        let full_path = root_path.as_path().join(path.as_path());
+        let full_path = command_path::argument(&full_path);
+        input.push_str(
+            full_path
+                .to_str()
+                .ok_or_else(|| PathValidationError::InvalidUnicode(full_path.to_path_buf()))?,
+        );
+        input.push('\n');
+    }
+
+    let stdout = utf8_output(
+        "hash-object",
+        run_git_with_input(
+            repository,
+            root_path,
+            &["hash-object", "--stdin-paths"],
+            input,
+            limits,
+            observer,
+        )?,
+    )?;
+
+    let mut lines = stdout.lines();
+    for path in to_hash {
+        let hash = lines
+            .next()
+            .ok_or_else(|| invalid_output("hash-object", &stdout))?;
+        hashes.insert(path.clone(), hash.to_string());
+    }
+    if lines.next().is_some() {
+        return Err(invalid_output("hash-object", &stdout));
+    }
+    Ok(())
+}
+
+fn run_git(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    args: &[&str],
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<Vec<u8>, Error> {
+    let start = Instant::now();
+    let mut command = repository.command(root_path);
+    command.args(args);
+    let output = process::output(&mut command, root_path.as_path(), limits)?;
+    observer.subprocess(&Subprocess {
+        args,
+        duration: start.elapsed(),
+        success: output.status.success(),
+    });
+    process::check_output(&command, output)
+}
+
+/// Like [run_git], with `input` written to the standard input of git.
+fn run_git_with_input(
+    repository: &GitRepository,
+    root_path: &AbsoluteSystemPathBuf,
+    args: &[&str],
+    input: String,
+    limits: &ProcessLimits,
+    observer: &PackageObserver,
+) -> Result<Vec<u8>, Error> {
+    let start = Instant::now();
+    let mut command = repository.command(root_path);
+    command.args(args);
+    let output =
+        process::output_with_input(&mut command, root_path.as_path(), Some(input), limits)?;
+    observer.subprocess(&Subprocess {
+        args,
+        duration: start.elapsed(),
+        success: output.status.success(),
+    });
+    process::check_output(&command, output)
+}
+
+/// The output of `git <command>` as a string, for commands that don't output
+/// paths.
+fn utf8_output(command: &str, stdout: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(stdout)
+        .map_err(|e| invalid_output(command, &String::from_utf8_lossy(e.as_bytes())))
+}
+
+/// An [Error::ParseError] for a `record` in the output of `git <command>`.
+pub(crate) fn invalid_output(command: &str, record: &str) -> Error {
+    Error::ParseError {
+        command: command.to_string(),
+        record: record.to_string(),
+        backtrace: Backtrace::capture(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{assert_matches::assert_matches, fs, path::Path, process::Command};
+
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+
+    use super::*;
+    use crate::testing::{git, setup_repository, unix, write, HELLO, WORLD};
+
+    #[test]
+    fn test_get_package_deps() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/a/deleted.txt", "hello\n");
+        write(&root, "packages/a/modified.txt", "hello\n");
+        write(&root, "packages/b/other.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        fs::remove_file(root.as_path().join("packages/a/deleted.txt")).unwrap();
+        write(&root, "packages/a/modified.txt", "world\n");
+        write(&root, "packages/a/dir/untracked.txt", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let hashes = get_package_deps(&root, &package_path, &[]).unwrap();
+
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("modified.txt"), WORLD.to_string()),
+            (unix("dir/untracked.txt"), WORLD.to_string()),
+        ]);
+        assert_eq!(hashes, expected);
+    }
+
+    #[test]
+    fn test_get_package_deps_long_path() {
+        let (_repo_root, root) = setup_repository();
+        // For the git commands of the test itself on Windows.
+        git(root.as_path(), &["config", "core.longpaths", "true"]);
+        let package = format!("packages/{}a", "deeply-nested-directory/".repeat(12));
+        assert!(root.as_path().join(&package).as_os_str().len() > 260);
+        write(&root, &format!("{}/committed.txt", package), "hello\n");
+        write(&root, &format!("{}/modified.txt", package), "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        write(&root, &format!("{}/modified.txt", package), "world\n");
+        write(&root, &format!("{}/src/untracked.txt", package), "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new(&package)).unwrap();
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("modified.txt"), WORLD.to_string()),
+            (unix("src/untracked.txt"), WORLD.to_string()),
+        ]);
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            assert_eq!(
+                hasher.get_package_deps(&root, &package_path, &[]).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_package_deps_libgit2() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/a/deleted.txt", "hello\n");
+        write(&root, "packages/a/staged-deleted.txt", "hello\n");
+        write(&root, "packages/a/modified.txt", "hello\n");
+        write(&root, "packages/ab/other.txt", "hello\n");
+        write(&root, ".gitignore", "*.log\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        fs::remove_file(root.as_path().join("packages/a/deleted.txt")).unwrap();
+        git(
+            root.as_path(),
+            &["rm", "--quiet", "packages/a/staged-deleted.txt"],
+        );
+        write(&root, "packages/a/modified.txt", "world\n");
+        write(&root, "packages/a/dir/untracked.txt", "world\n");
+        write(&root, "packages/a/ignored.log", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let hashes = PackageDepsHasher::libgit2()
+            .get_package_deps(&root, &package_path, &[])
+            .unwrap();
+
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("modified.txt"), WORLD.to_string()),
+            (unix("dir/untracked.txt"), WORLD.to_string()),
+        ]);
+        assert_eq!(hashes, expected);
+        assert_eq!(
+            get_package_deps(&root, &package_path, &[]).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_in_parallel() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        // Enough files for several chunks.
+        let mut expected = GitHashes::from([(unix("committed.txt"), HELLO.to_string())]);
+        for i in 0..HASH_CHUNK_SIZE * 3 + 1 {
+            let path = format!("untracked/{}.txt", i);
+            write(&root, &format!("packages/a/{}", path), "world\n");
+            expected.insert(unix(&path), WORLD.to_string());
+        }
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            for concurrency in [1, 2, 8] {
+                assert_eq!(
+                    hasher
+                        .clone()
+                        .concurrency(concurrency)
+                        .get_package_deps(&root, &package_path, &[])
+                        .unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_package_deps_in_linked_worktree() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/a/modified.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let worktree_dir = tempfile::tempdir().unwrap();
+        let worktree = AbsoluteSystemPathBuf::new(
+            dunce::canonicalize(worktree_dir.path())
+                .unwrap()
+                .join("linked"),
+        )
+        .unwrap();
+        git(
+            root.as_path(),
+            &["worktree", "add", "--quiet", worktree.to_str().unwrap()],
+        );
+        write(&worktree, "packages/a/modified.txt", "world\n");
+        write(&worktree, "packages/a/untracked.txt", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("modified.txt"), WORLD.to_string()),
+            (unix("untracked.txt"), WORLD.to_string()),
+        ]);
+        assert_eq!(
+            get_package_deps(&worktree, &package_path, &[]).unwrap(),
+            expected
+        );
+        assert_eq!(
+            PackageDepsHasher::libgit2()
+                .get_package_deps(&worktree, &package_path, &[])
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_with_submodule() {
+        let (_submodule_root, submodule) = setup_repository();
+        write(&submodule, "committed.txt", "hello\n");
+        write(&submodule, "modified.txt", "hello\n");
+        git(submodule.as_path(), &["add", "."]);
+        git(submodule.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        git(
+            root.as_path(),
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "--quiet",
+                submodule.to_str().unwrap(),
+                "packages/a/sub",
+            ],
+        );
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let checkout = root.join_literal("packages/a/sub");
+        write(&checkout, "modified.txt", "world\n");
+        write(&checkout, "untracked.txt", "world\n");
+        let output = Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(checkout.as_path())
+            .output()
+            .unwrap();
+        let submodule_head = String::from_utf8(output.stdout).unwrap().trim().to_string();
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let recursed = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("sub/committed.txt"), HELLO.to_string()),
+            (unix("sub/modified.txt"), WORLD.to_string()),
+            (unix("sub/untracked.txt"), WORLD.to_string()),
+        ]);
+        let pointer = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("sub"), submodule_head),
+        ]);
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            assert_eq!(
+                hasher.get_package_deps(&root, &package_path, &[]).unwrap(),
+                recursed
+            );
+            assert_eq!(
+                hasher
+                    .submodules(SubmoduleMode::Pointer)
+                    .get_package_deps(&root, &package_path, &[])
+                    .unwrap(),
+                pointer
+            );
+        }
+        assert_eq!(
+            get_package_deps(&root, &package_path, &["sub/*.txt", "!sub/untracked.txt"]).unwrap(),
+            GitHashes::from([
+                (unix("sub/committed.txt"), HELLO.to_string()),
+                (unix("sub/modified.txt"), WORLD.to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_in_sparse_checkout() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/a/src/index.txt", "hello\n");
+        write(&root, "packages/a/docs/guide.txt", "hello\n");
+        write(&root, "packages/b/other.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        git(
+            root.as_path(),
+            &["sparse-checkout", "set", "--cone", "packages/a/src"],
+        );
+        assert!(!root.as_path().join("packages/a/docs").exists());
+        write(&root, "packages/a/src/index.txt", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("src/index.txt"), WORLD.to_string()),
+        ]);
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            assert_eq!(
+                hasher.get_package_deps(&root, &package_path, &[]).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_package_deps_staged() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        write(&root, "packages/a/staged.txt", "hello\n");
+        write(&root, "packages/a/staged-modified.txt", "hello\n");
+        write(&root, "packages/a/frozen.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        write(&root, "packages/a/staged-modified.txt", "world\n");
+        // git doesn't look at the working tree copy of the file anymore, so
+        // it's hashed as staged even though it changed.
+        git(
+            root.as_path(),
+            &[
+                "update-index",
+                "--assume-unchanged",
+                "packages/a/frozen.txt",
+            ],
+        );
+        write(&root, "packages/a/frozen.txt", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("staged.txt"), HELLO.to_string()),
+            (unix("staged-modified.txt"), WORLD.to_string()),
+            (unix("frozen.txt"), HELLO.to_string()),
+        ]);
+        assert_eq!(
+            get_package_deps(&root, &package_path, &[]).unwrap(),
+            expected
+        );
+        assert_eq!(
+            PackageDepsHasher::libgit2()
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_inputs() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/src/index.js", "hello\n");
+        write(&root, "packages/a/src/index.test.js", "hello\n");
+        write(&root, "packages/a/docs/readme.md", "hello\n");
+        write(&root, "packages/a/package.json", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        write(&root, "packages/a/src/new.js", "world\n");
+        write(&root, "packages/a/docs/new.md", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let hashes = get_package_deps(
+            &root,
+            &package_path,
+            &["src/**/*.js", "package.json", "!**/*.test.js"],
+        )
+        .unwrap();
+
+        let expected = GitHashes::from([
+            (unix("src/index.js"), HELLO.to_string()),
+            (unix("src/new.js"), WORLD.to_string()),
+            (unix("package.json"), HELLO.to_string()),
+        ]);
+        assert_eq!(hashes, expected);
+    }
+
+    #[test]
+    fn test_get_package_deps_turbo_default() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, ".gitignore", ".env\ndist/\n");
+        write(&root, "packages/a/src/index.js", "hello\n");
+        write(&root, "packages/a/docs/readme.md", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        write(&root, "packages/a/src/new.js", "world\n");
+        write(&root, "packages/a/.env", "world\n");
+        write(&root, "packages/a/dist/index.js", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let expected = GitHashes::from([
+            (unix("src/index.js"), HELLO.to_string()),
+            (unix("src/new.js"), WORLD.to_string()),
+            (unix(".env"), WORLD.to_string()),
+        ]);
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let hashes = hasher
+                .get_package_deps(
+                    &root,
+                    &package_path,
+                    &[TURBO_DEFAULT, ".env", "src/**", "!docs/**"],
+                )
+                .unwrap();
+            assert_eq!(hashes, expected);
+        }
+
+        // Without other patterns, the default files are hashed.
+        let hashes = get_package_deps(&root, &package_path, &[TURBO_DEFAULT]).unwrap();
+        assert_eq!(hashes, get_package_deps(&root, &package_path, &[]).unwrap());
+    }
+
+    #[test]
+    fn test_get_package_deps_with_metadata() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        write(&root, "packages/a/untracked.txt", "hello world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let files = get_package_deps_with_metadata(&root, &package_path, &[]).unwrap();
+
+        let committed = &files[&unix("committed.txt")];
+        assert_eq!(committed.hash, HELLO);
+        assert_eq!(committed.size, 6);
+        assert!(committed.mtime.is_some());
+        assert_eq!(files[&unix("untracked.txt")].size, 12);
+    }
+
+    #[test]
+    fn test_git_hash_object_many_files() {
+        let (_repo_root, root) = setup_repository();
+        // Enough paths to exceed the pipe buffers of stdin and stdout.
+        let to_hash: Vec<_> = (0..5000)
+            .map(|i| {
+                let path = format!("some/long/directory/name/file-{}.txt", i);
+                write(&root, &path, if i % 2 == 0 { "hello\n" } else { "world\n" });
+                unix(&path)
+            })
+            .collect();
+
+        let repository = GitRepository::discover(&root).unwrap().unwrap();
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("")).unwrap();
+        let observer = PackageObserver::new(None, &package_path);
+        let mut hashes = GitHashes::new();
+        git_hash_object(
+            &repository,
+            &root,
+            &to_hash,
+            &mut hashes,
+            &ProcessLimits::none(),
+            &observer,
+        )
+        .unwrap();
+
+        assert_eq!(hashes.len(), to_hash.len());
+        assert_eq!(hashes[&to_hash[0]], HELLO);
+        assert_eq!(hashes[&to_hash[4999]], WORLD);
+    }
+
+    #[test]
+    fn test_get_package_deps_turboignore() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, ".turboignore", "*.snap\n");
+        write(&root, "packages/a/.turboignore", "docs/\n!keep.snap\n");
+        write(&root, "packages/a/index.js", "hello\n");
+        write(&root, "packages/a/docs/readme.md", "hello\n");
+        write(&root, "packages/a/test.snap", "hello\n");
+        write(&root, "packages/a/keep.snap", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        write(&root, "packages/a/docs/untracked.md", "world\n");
+        write(&root, "packages/a/new.snap", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let hashes = get_package_deps(&root, &package_path, &[]).unwrap();
+
+        let mut paths = hashes.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![unix(".turboignore"), unix("index.js"), unix("keep.snap")]
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_lfs() {
+        // The LFS object id of "hello\n".
+        const HELLO_OID: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+        let pointer = format!(
+            "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize 6\n",
+            HELLO_OID
+        );
+
+        let (_repo_root, root) = setup_repository();
+        write(
+            &root,
+            ".gitattributes",
+            "*.bin filter=lfs diff=lfs merge=lfs -text\n",
+        );
+        // A file that isn't smudged and one that is.
+        write(&root, "packages/a/pointer.bin", &pointer);
+        write(&root, "packages/a/smudged.bin", "hello\n");
+        write(&root, "packages/a/pointer.txt", &pointer);
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let pointer_hash =
+            get_package_deps(&root, &package_path, &[]).unwrap()[&unix("pointer.bin")].clone();
+
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let hashes = hasher
+                .clone()
+                .lfs(LfsMode::Oid)
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap();
+            assert_eq!(hashes[&unix("pointer.bin")], HELLO_OID);
+            assert_eq!(hashes[&unix("smudged.bin")], HELLO_OID);
+            // Only files with the `filter=lfs` attribute are tracked by LFS.
+            assert_eq!(hashes[&unix("pointer.txt")], pointer_hash);
+
+            let result = hasher
+                .lfs(LfsMode::Content)
+                .get_package_deps(&root, &package_path, &[]);
+            assert_matches!(result, Err(Error::LfsObjectMissing(..)));
+        }
+
+        write(
+            &root,
+            &format!(".git/lfs/objects/58/91/{}", HELLO_OID),
+            "hello\n",
+        );
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let hashes = hasher
+                .lfs(LfsMode::Content)
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap();
+            assert_eq!(hashes[&unix("pointer.bin")], HELLO);
+            assert_eq!(hashes[&unix("smudged.bin")], HELLO);
+            assert_eq!(hashes[&unix("pointer.txt")], pointer_hash);
+        }
+    }
+
+    #[test]
+    fn test_get_package_deps_with_tree_cache() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/ab/committed.txt", "hello\n");
+        write(&root, "packages/b/committed.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let package_paths = ["packages/a", "packages/ab", "packages/b", ""]
+            .map(|path| AnchoredSystemPathBuf::try_from(Path::new(path)).unwrap());
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let tree_cache = Arc::new(TreeCache::new());
+            let cached = hasher.clone().tree_cache(tree_cache.clone());
+            for package_path in &package_paths {
+                assert_eq!(
+                    cached.get_package_deps(&root, package_path, &[]).unwrap(),
+                    hasher.get_package_deps(&root, package_path, &[]).unwrap()
+                );
+            }
+
+            // A new commit invalidates the cached tree.
+            write(&root, "packages/a/committed.txt", "world\n");
+            git(root.as_path(), &["commit", "--quiet", "-am", "change"]);
+            let hashes = cached
+                .get_package_deps(&root, &package_paths[0], &[])
+                .unwrap();
+            assert_eq!(hashes[&unix("committed.txt")], WORLD);
+
+            write(&root, "packages/a/committed.txt", "hello\n");
+            git(root.as_path(), &["commit", "--quiet", "-am", "revert"]);
+        }
+    }
+
+    #[test]
+    fn test_get_package_deps_with_limited_tree_cache() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/a/nested/committed.txt", "hello\n");
+        write(&root, "packages/ab/committed.txt", "hello\n");
+        write(&root, "packages/b/committed.txt", "hello\n");
+        write(&root, "vendor/toolchain/bin/tool", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let package_paths = [
+            "packages/a",
+            "packages/a/nested",
+            "packages/ab",
+            "packages/b",
+            "",
+        ]
+        .map(|path| AnchoredSystemPathBuf::try_from(Path::new(path)).unwrap());
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let tree_cache = Arc::new(TreeCache::new().pathspecs(vec![
+                unix("packages/a/nested"),
+                unix("packages/a"),
+                unix("packages/ab"),
+            ]));
+            let cached = hasher.clone().tree_cache(tree_cache.clone());
+            for package_path in &package_paths {
+                assert_eq!(
+                    cached.get_package_deps(&root, package_path, &[]).unwrap(),
+                    hasher.get_package_deps(&root, package_path, &[]).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_package_deps_with_chunked_hasher() {
+        use crate::chunked_hash::ChunkingConfig;
+
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.bin", &"a".repeat(4096));
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        let large = (0..4096)
+            .map(|i| (b'a' + (i % 26) as u8) as char)
+            .collect::<String>();
+        write(&root, "packages/a/committed.bin", &large);
+        write(&root, "packages/a/untracked.txt", "hello\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let chunked_hasher = Arc::new(Mutex::new(ChunkedHasher::new(ChunkingConfig {
+            min_file_size: 1024,
+            avg_chunk_size: 1024,
+        })));
+        let large_hash = chunked_hasher
+            .lock()
+            .unwrap()
+            .hash_file(&root.resolve_unix(&unix("packages/a/committed.bin")), false)
+            .unwrap();
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let hashes = hasher
+                .chunked_hasher(chunked_hasher.clone())
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap();
+            // Small files fall back to git hashes.
+            let expected = GitHashes::from([
+                (unix("committed.bin"), large_hash.clone()),
+                (unix("untracked.txt"), HELLO.to_string()),
+            ]);
+            assert_eq!(hashes, expected);
+        }
+    }
+
+    #[test]
+    fn test_pathspec_chunks() {
+        let long = "a".repeat(MAX_PATHSPECS_LEN / 2);
+        let pathspecs = [long.as_str(), "b", long.as_str(), "c"];
+        assert_eq!(
+            pathspec_chunks(&pathspecs),
+            vec![&pathspecs[..2], &pathspecs[2..]]
+        );
+        assert!(pathspec_chunks(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_is_below() {
+        assert!(is_below("packages/a", "packages/a"));
+        assert!(is_below("packages/a/src", "packages/a"));
+        assert!(is_below("packages/a", ""));
+        assert!(!is_below("packages/ab", "packages/a"));
+        assert!(!is_below("packages", "packages/a"));
+    }
+
+    #[test]
+    fn test_get_package_deps_with_observer() {
+        #[derive(Default)]
+        struct Recorder {
+            events: std::sync::Mutex<Vec<String>>,
+        }
+
+        impl HashingObserver for Recorder {
+            fn package_started(&self, package_path: &AnchoredSystemPath) {
+                let event = format!("started {}", package_path.to_str().unwrap());
+                self.events.lock().unwrap().push(event);
+            }
+
+            fn package_finished(
+                &self,
+                package_path: &AnchoredSystemPath,
+                stats: &crate::observer::PackageHashingStats,
+            ) {
+                let event = format!(
+                    "finished {} {:?} files={} subprocesses={}",
+                    package_path.to_str().unwrap(),
+                    stats.method,
+                    stats.files,
+                    stats.subprocesses
+                );
+                self.events.lock().unwrap().push(event);
+            }
+
+            fn subprocess(&self, _package_path: &AnchoredSystemPath, subprocess: &Subprocess) {
+                assert!(subprocess.success);
+                let event = format!("git {}", subprocess.args[0]);
+                self.events.lock().unwrap().push(event);
+            }
+
+            fn hashed_without_git(&self, package_path: &AnchoredSystemPath, reason: &str) {
+                let event = format!("without git {}: {}", package_path.to_str().unwrap(), reason);
+                self.events.lock().unwrap().push(event);
+            }
+        }
+
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/b/committed.txt", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        write(&root, "packages/a/untracked.txt", "world\n");
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let recorder = Arc::new(Recorder::default());
+        let hasher = PackageDepsHasher::new().observer(recorder.clone());
+        hasher.get_package_deps(&root, &package_path, &[]).unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                "started packages/a",
+                "git rev-parse",
+                "git ls-tree",
+                "git rev-parse",
+                "git status",
+                "git hash-object",
+                "finished packages/a Git files=2 subprocesses=5",
+            ]
+        );
+
+        // Git is only detected once per hasher.
+        recorder.events.lock().unwrap().clear();
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/b")).unwrap();
+        hasher.get_package_deps(&root, &package_path, &[]).unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                "started packages/b",
+                "git ls-tree",
+                "git rev-parse",
+                "git status",
+                "finished packages/b Git files=1 subprocesses=3",
+            ]
+        );
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+
+        let recorder = Arc::new(Recorder::default());
+        let hasher = PackageDepsHasher::libgit2().observer(recorder.clone());
+        hasher.get_package_deps(&root, &package_path, &[]).unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                "started packages/a",
+                "finished packages/a Libgit2 files=2 subprocesses=0",
+            ]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(dir.path()).unwrap()).unwrap();
+        write(&root, "packages/a/index.js", "hello\n");
+        let recorder = Arc::new(Recorder::default());
+        let hasher = PackageDepsHasher::new().observer(recorder.clone());
+        hasher.get_package_deps(&root, &package_path, &[]).unwrap();
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            [
+                "started packages/a",
+                "without git packages/a: it isn't in a git repository",
+                "finished packages/a WithoutGit files=1 subprocesses=0",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_get_package_deps_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let (_repo_root, root) = setup_repository();
+        write(&root, "shared/config.json", "hello\n");
+        write(&root, "packages/a/index.js", "hello\n");
+        write(&root, "packages/a/lib/util.js", "world\n");
+        let package = root.as_path().join("packages/a");
+        symlink("../../shared/config.json", package.join("committed.json")).unwrap();
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        symlink("index.js", package.join("untracked.js")).unwrap();
+        symlink("missing.js", package.join("dangling.js")).unwrap();
+        symlink("lib", package.join("lib-link")).unwrap();
+
+        let link_text = |target: &str| {
+            Oid::hash_object(ObjectType::Blob, target.as_bytes())
+                .unwrap()
+                .to_string()
+        };
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        for hasher in [PackageDepsHasher::new(), PackageDepsHasher::libgit2()] {
+            let hashes = hasher
+                .clone()
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap();
+            assert_eq!(
+                hashes,
+                GitHashes::from([
+                    (unix("index.js"), HELLO.to_string()),
+                    (unix("lib/util.js"), WORLD.to_string()),
+                    (
+                        unix("committed.json"),
+                        link_text("../../shared/config.json")
+                    ),
+                    (unix("untracked.js"), link_text("index.js")),
+                    (unix("dangling.js"), link_text("missing.js")),
+                    (unix("lib-link"), link_text("lib")),
+                ])
+            );
+
+            let hashes = hasher
+                .clone()
+                .symlinks(SymlinkPolicy::HashTarget)
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap();
+            assert_eq!(
+                hashes,
+                GitHashes::from([
+                    (unix("index.js"), HELLO.to_string()),
+                    (unix("lib/util.js"), WORLD.to_string()),
+                    (unix("committed.json"), HELLO.to_string()),
+                    (unix("untracked.js"), HELLO.to_string()),
+                    (unix("dangling.js"), link_text("missing.js")),
+                    (unix("lib-link"), link_text("lib")),
+                ])
+            );
+
+            let hashes = hasher
+                .symlinks(SymlinkPolicy::Skip)
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap();
+            assert_eq!(
+                hashes,
+                GitHashes::from([
+                    (unix("index.js"), HELLO.to_string()),
+                    (unix("lib/util.js"), WORLD.to_string()),
+                ])
+            );
+        }
+    }
+
+    // The colliding files can't both be written on the case-insensitive file
+    // systems that macOS and Windows use by default.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_package_deps_case_collisions() {
+        #[derive(Default)]
+        struct Recorder {
+            collisions: std::sync::Mutex<Vec<Vec<RelativeUnixPathBuf>>>,
+        }
+
+        impl HashingObserver for Recorder {
+            fn case_collisions(
+                &self,
+                _package_path: &AnchoredSystemPath,
+                collisions: &[Vec<RelativeUnixPathBuf>],
+            ) {
+                self.collisions
+                    .lock()
+                    .unwrap()
+                    .extend_from_slice(collisions);
+            }
+        }
+
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/index.js", "hello\n");
+        write(&root, "packages/a/src/Button.js", "hello\n");
+        write(&root, "packages/a/src/button.js", "world\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let expected = vec![vec![unix("src/Button.js"), unix("src/button.js")]];
+
+        let recorder = Arc::new(Recorder::default());
+        let hashes = PackageDepsHasher::new()
+            .observer(recorder.clone())
+            .get_package_deps(&root, &package_path, &[])
+            .unwrap();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(*recorder.collisions.lock().unwrap(), expected);
+
+        let recorder = Arc::new(Recorder::default());
+        PackageDepsHasher::libgit2()
+            .observer(recorder.clone())
+            .case_collisions(CaseCollisionPolicy::Ignore)
+            .get_package_deps(&root, &package_path, &[])
+            .unwrap();
+        assert!(recorder.collisions.lock().unwrap().is_empty());
+
+        let result = PackageDepsHasher::libgit2()
+            .case_collisions(CaseCollisionPolicy::Error)
+            .get_package_deps(&root, &package_path, &[]);
+        assert_matches!(result, Err(Error::CaseCollision(collisions, _)) if collisions == expected);
+    }
+
+    #[test]
+    fn test_get_package_deps_without_git() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(dir.path()).unwrap()).unwrap();
+        write(&root, ".gitignore", "node_modules/\n*.log\n");
+        write(&root, "packages/a/.gitignore", "dist/\n");
+        write(&root, "packages/a/src/index.js", "hello\n");
+        write(&root, "packages/a/readme.md", "world\n");
+        write(&root, "packages/a/debug.log", "hello\n");
+        write(&root, "packages/a/dist/index.js", "hello\n");
+        write(&root, "packages/a/node_modules/b/index.js", "hello\n");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("src/index.js", root.as_path().join("packages/a/link.js"))
+            .unwrap();
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let without_git = get_package_deps(&root, &package_path, &[]).unwrap();
+        assert_eq!(without_git[&unix("src/index.js")], HELLO);
+        assert_eq!(without_git[&unix("readme.md")], WORLD);
+        assert!(!without_git.contains_key(&unix("debug.log")));
+        assert!(!without_git.contains_key(&unix("dist/index.js")));
+        assert!(!without_git.contains_key(&unix("node_modules/b/index.js")));
+
+        git(root.as_path(), &["init", "--quiet"]);
+        git(root.as_path(), &["config", "user.name", "test"]);
+        git(
+            root.as_path(),
+            &["config", "user.email", "test@example.com"],
+        );
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+        let with_git = get_package_deps(&root, &package_path, &[]).unwrap();
+        assert_eq!(without_git, with_git);
+    }
+
+    #[test]
+    fn test_get_package_deps_cancelled() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/index.js", "hello\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        // A cancelled build fails instead of falling back to hashing without
+        // git.
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let result = PackageDepsHasher::new()
+            .cancellation(cancellation)
+            .get_package_deps(&root, &package_path, &[]);
+        assert_matches!(result, Err(Error::Cancelled { .. }));
+    }
+
+    #[test]
+    fn test_get_package_deps_unmerged() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/conflict.txt", "base\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "base"]);
+        git(root.as_path(), &["checkout", "--quiet", "-b", "other"]);
+        write(&root, "packages/a/conflict.txt", "other\n");
+        git(root.as_path(), &["commit", "--quiet", "-am", "other"]);
+        git(root.as_path(), &["checkout", "--quiet", "-"]);
+        write(&root, "packages/a/conflict.txt", "main\n");
+        git(root.as_path(), &["commit", "--quiet", "-am", "main"]);
+        // The merge is expected to fail with a conflict.
+        Command::new("git")
+            .args(["merge", "other"])
+            .current_dir(root.as_path())
+            .output()
+            .unwrap();
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let result = get_package_deps(&root, &package_path, &[]);
+        assert_matches!(result, Err(Error::Unmerged(paths, _)) if paths == vec![unix("conflict.txt")]);
+
+        let result = PackageDepsHasher::libgit2().get_package_deps(&root, &package_path, &[]);
+        assert_matches!(result, Err(Error::Unmerged(paths, _)) if paths == vec![unix("conflict.txt")]);
+    }
+
+    #[test]
+    fn test_get_package_deps_with_renames() {
+        let (_repo_root, root) = setup_repository();
+        write(&root, "packages/a/committed.txt", "hello\n");
+        write(&root, "packages/a/renamed.txt", "hello\n");
+        write(&root, "packages/a/staged-renamed.txt", "world\n");
+        git(root.as_path(), &["add", "."]);
+        git(root.as_path(), &["commit", "--quiet", "-m", "initial"]);
+
+        git(
+            root.as_path(),
+            &[
+                "mv",
+                "packages/a/staged-renamed.txt",
+                "packages/a/moved.txt",
+            ],
+        );
+        fs::rename(
+            root.as_path().join("packages/a/renamed.txt"),
+            root.as_path().join("packages/a/new-name.txt"),
+        )
+        .unwrap();
+        git(root.as_path(), &["add", "--intent-to-add", "."]);
+
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("packages/a")).unwrap();
+        let expected = GitHashes::from([
+            (unix("committed.txt"), HELLO.to_string()),
+            (unix("new-name.txt"), HELLO.to_string()),
+            (unix("moved.txt"), WORLD.to_string()),
+        ]);
+        assert_eq!(
+            PackageDepsHasher::new()
+                .detect_renames(true)
+                .get_package_deps(&root, &package_path, &[])
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            get_package_deps(&root, &package_path, &[]).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let entries = parse_status(b"UU a.txt\0AA b.txt\0 D c.txt\0?? d.txt\0").unwrap();
+        let codes = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.path.as_str(),
+                    entry.code.is_unmerged(),
+                    entry.code.is_delete(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            vec![
+                ("a.txt", true, false),
+                ("b.txt", true, false),
+                ("c.txt", false, true),
+                ("d.txt", false, false),
+            ]
+        );
+        assert!(parse_status(b"U\0").is_err());
+
+        let entries = parse_status(b"R  new.txt\0old.txt\0C  copy.txt\0a.txt\0 M b.txt\0").unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (
+                    entry.path.as_str(),
+                    entry.original_path.as_deref(),
+                    entry.code.is_rename()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("new.txt", Some("old.txt"), true),
+                ("copy.txt", Some("a.txt"), false),
+                ("b.txt", None, false),
+            ]
+        );
+        assert!(parse_status(b"R  new.txt\0").is_err());
+    }
+}
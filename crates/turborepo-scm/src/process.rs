@@ -0,0 +1,457 @@
+//! Running git subprocesses.
+//!
+//! Git subprocesses are run with [output] or [run], so that failures are
+//! reported the same way everywhere: git that can't be spawned is
+//! [Error::GitNotFound], and git that fails is [Error::ExitStatus] with the
+//! stderr of git, which usually says what went wrong.
+//!
+//! Git can hang, e.g. `git status` on a cold network file system. With
+//! [ProcessLimits], git is killed once it runs longer than a timeout or once a
+//! [CancellationToken] is cancelled.
+
+use std::{
+    backtrace::Backtrace,
+    io::{self, Read, Write},
+    path::Path,
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// Global options of git that are followed by a value, and that are added by
+/// [git_command](crate::command_path::git_command) and
+/// [GitRepository::command](crate::repository::GitRepository::command).
+const GLOBAL_OPTIONS: &[&str] = &["-c", "--git-dir", "--work-tree"];
+
+/// The longest time between two checks of the [ProcessLimits] of a running
+/// git process.
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long the threads that read from and write to a killed git process are
+/// waited for.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// Cancels the git processes that it's passed to, e.g. when a build is
+/// interrupted. Clones share their state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kills the git processes that are running with this token, and fails
+    /// the processes that are started with it from now on.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// When a git process is killed, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct ProcessLimits {
+    timeout: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl ProcessLimits {
+    /// Lets git run until it exits.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Kills git once it runs longer than `timeout`, and fails with
+    /// [Error::Timeout].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Kills git once `cancellation` is cancelled, and fails with
+    /// [Error::Cancelled].
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    fn is_none(&self) -> bool {
+        self.timeout.is_none() && self.cancellation.is_none()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .map_or(false, CancellationToken::is_cancelled)
+    }
+}
+
+/// Runs `command`, which runs git in `cwd`, and returns its output whether git
+/// succeeds or not.
+pub(crate) fn output(
+    command: &mut Command,
+    cwd: &Path,
+    limits: &ProcessLimits,
+) -> Result<Output, Error> {
+    output_with_input(command, cwd, None, limits)
+}
+
+/// Like [output], with `input` written to the standard input of git.
+pub(crate) fn output_with_input(
+    command: &mut Command,
+    cwd: &Path,
+    input: Option<String>,
+    limits: &ProcessLimits,
+) -> Result<Output, Error> {
+    if input.is_none() && limits.is_none() {
+        return command.output().map_err(|e| Error::from_spawn(e, cwd));
+    }
+    if limits.is_cancelled() {
+        return Err(Error::Cancelled {
+            command: subcommand(command),
+            backtrace: Backtrace::capture(),
+        });
+    }
+
+    // git is started in its own process group, so that the processes that it
+    // starts, e.g. hooks or `ssh`, are killed with it and release its pipes.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let start = Instant::now();
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::from_spawn(e, cwd))?;
+    // Write from another thread, as git may block on writing to stdout until
+    // it is read.
+    let writer = input.map(|input| {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+    let stdout = Capture::start(child.stdout.take().expect("stdout is piped"));
+    let stderr = Capture::start(child.stderr.take().expect("stderr is piped"));
+
+    let status = wait(&mut child, start, limits).map_err(|error| {
+        let deadline = Instant::now() + KILL_GRACE_PERIOD;
+        if let Some(writer) = &writer {
+            join_until(writer, deadline);
+        }
+        join_until(&stdout.reader, deadline);
+        join_until(&stderr.reader, deadline);
+        let stderr = stderr.partial();
+        match error {
+            Stopped::Timeout(timeout) => Error::Timeout {
+                command: subcommand(command),
+                timeout,
+                stderr,
+                backtrace: Backtrace::capture(),
+            },
+            Stopped::Cancelled => Error::Cancelled {
+                command: subcommand(command),
+                backtrace: Backtrace::capture(),
+            },
+            Stopped::Io(error) => error.into(),
+        }
+    })?;
+    let output = Output {
+        status,
+        stdout: stdout.finish()?,
+        stderr: stderr.finish()?,
+    };
+    if let Some(writer) = writer {
+        match writer.join().expect("writing to git panicked") {
+            // git stops reading its input when it fails, which its exit status
+            // and stderr report better than the broken pipe.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    Ok(output)
+}
+
+/// Why git was stopped before it exited.
+enum Stopped {
+    Timeout(Duration),
+    Cancelled,
+    Io(io::Error),
+}
+
+/// Waits for `child`, which was started at `start`, to exit, and kills it if
+/// it exceeds `limits`.
+fn wait(child: &mut Child, start: Instant, limits: &ProcessLimits) -> Result<ExitStatus, Stopped> {
+    let mut interval = Duration::from_millis(1);
+    loop {
+        if let Some(status) = child.try_wait().map_err(Stopped::Io)? {
+            return Ok(status);
+        }
+        let stopped = if limits.is_cancelled() {
+            Some(Stopped::Cancelled)
+        } else {
+            limits
+                .timeout
+                .filter(|timeout| start.elapsed() >= *timeout)
+                .map(Stopped::Timeout)
+        };
+        if let Some(stopped) = stopped {
+            kill(child);
+            return Err(stopped);
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    }
+}
+
+/// Kills `child` and, on Unix, the processes in its process group.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: `kill` has no memory safety requirements. The process group
+    // still exists, as `child` hasn't been waited for yet.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    // git may have exited in the meantime, in which case killing it fails.
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Waits until `thread` finishes or `deadline` passes. Processes that escaped
+/// the process group of a killed git, or that run on Windows, where only git
+/// itself is killed, may keep its pipes open, which must not block forever.
+fn join_until<T>(thread: &JoinHandle<T>, deadline: Instant) {
+    while !thread.is_finished() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// The output of a pipe of git, which is read on another thread.
+struct Capture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    reader: JoinHandle<io::Result<()>>,
+}
+
+impl Capture {
+    fn start(mut pipe: impl Read + Send + 'static) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let reader = thread::spawn({
+            let buffer = buffer.clone();
+            move || {
+                let mut chunk = [0; 8192];
+                loop {
+                    match pipe.read(&mut chunk) {
+                        Ok(0) => return Ok(()),
+                        Ok(len) => buffer.lock().unwrap().extend_from_slice(&chunk[..len]),
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        });
+        Self { buffer, reader }
+    }
+
+    /// What was read so far, as text.
+    fn partial(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap())
+            .trim_end()
+            .to_string()
+    }
+
+    /// Waits for the end of the output and returns it.
+    fn finish(self) -> io::Result<Vec<u8>> {
+        self.reader.join().expect("reading from git panicked")?;
+        Ok(std::mem::take(&mut *self.buffer.lock().unwrap()))
+    }
+}
+
+/// Runs `command`, which runs git in `cwd`, and returns its stdout.
+pub(crate) fn run(
+    command: &mut Command,
+    cwd: &Path,
+    limits: &ProcessLimits,
+) -> Result<Vec<u8>, Error> {
+    let output = output(command, cwd, limits)?;
+    check_output(command, output)
+}
+
+/// The stdout of `output`, the output of `command`, or [Error::ExitStatus] if
+/// git failed.
+pub(crate) fn check_output(command: &Command, output: Output) -> Result<Vec<u8>, Error> {
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(exit_status(command, &output))
+    }
+}
+
+/// The [Error::ExitStatus] of `command`, which failed with `output`.
+pub(crate) fn exit_status(command: &Command, output: &Output) -> Error {
+    Error::ExitStatus {
+        command: subcommand(command),
+        code: output.status.code(),
+        stderr: String::from_utf8_lossy(&output.stderr)
+            .trim_end()
+            .to_string(),
+        backtrace: Backtrace::capture(),
+    }
+}
+
+/// The subcommand of git and its arguments, without the global options that
+/// only select the repository.
+fn subcommand(command: &Command) -> String {
+    let mut args = command.get_args().map(|arg| arg.to_string_lossy());
+    let mut words = Vec::new();
+    while let Some(arg) = args.next() {
+        if words.is_empty() && GLOBAL_OPTIONS.contains(&&*arg) {
+            args.next();
+            continue;
+        }
+        words.push(arg);
+    }
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        assert_matches::assert_matches,
+        fs,
+        path::Path,
+        process::Command,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{
+        output, output_with_input, subcommand, CancellationToken, ProcessLimits, KILL_GRACE_PERIOD,
+    };
+    use crate::Error;
+
+    #[test]
+    fn test_subcommand() {
+        let mut command = Command::new("git");
+        command
+            .args(["-c", "core.longpaths=true"])
+            .args(["--git-dir", "/repo/.git", "--work-tree", "/repo"])
+            .args(["merge-base", "main", "HEAD"]);
+        assert_eq!(subcommand(&command), "merge-base main HEAD");
+
+        let mut command = Command::new("git");
+        command.args(["show", "HEAD:-c"]);
+        assert_eq!(subcommand(&command), "show HEAD:-c");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo partial >&2; exec sleep 10"]);
+        let start = Instant::now();
+        let result = output(
+            &mut command,
+            Path::new("."),
+            &ProcessLimits::none().timeout(Duration::from_millis(500)),
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_matches!(result, Err(Error::Timeout { stderr, .. }) if stderr == "partial");
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo done"]);
+        let result = output(
+            &mut command,
+            Path::new("."),
+            &ProcessLimits::none().timeout(Duration::from_secs(10)),
+        )
+        .unwrap();
+        assert!(result.status.success());
+        assert_eq!(result.stdout, b"done\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        let limits = ProcessLimits::none().cancellation(token.clone());
+        let cancel = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            token.cancel();
+        });
+        let start = Instant::now();
+        let mut command = Command::new("sh");
+        command.args(["-c", "exec sleep 10"]);
+        let result = output(&mut command, Path::new("."), &limits);
+        cancel.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_matches!(result, Err(Error::Cancelled { .. }));
+
+        // Processes aren't started once the token is cancelled.
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo done"]);
+        assert_matches!(
+            output(&mut command, Path::new("."), &limits),
+            Err(Error::Cancelled { .. })
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_timeout_kills_child_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        // The background process inherits the pipes of the shell.
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            &format!("sleep 10 & echo $! > {}; exec sleep 10", pid_file.display()),
+        ]);
+        let start = Instant::now();
+        let result = output(
+            &mut command,
+            Path::new("."),
+            &ProcessLimits::none().timeout(Duration::from_millis(500)),
+        );
+        assert_matches!(result, Err(Error::Timeout { .. }));
+        // The pipes were closed before the grace period for the threads that
+        // read them passed.
+        assert!(start.elapsed() < Duration::from_millis(500) + KILL_GRACE_PERIOD);
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        // The process is gone, or a zombie if nothing reaped it yet.
+        assert!(stat.map_or(true, |stat| stat.contains(") Z ")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_before_reading_input() {
+        // More input than fits into a pipe, so writing it fails once the
+        // process exits.
+        let input = "x".repeat(1 << 20);
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo failed >&2; exit 3"]);
+        let result = output_with_input(
+            &mut command,
+            Path::new("."),
+            Some(input),
+            &ProcessLimits::none(),
+        )
+        .unwrap();
+        assert_eq!(result.status.code(), Some(3));
+        assert_eq!(result.stderr, b"failed\n");
+    }
+}
@@ -0,0 +1,375 @@
+//! Discovery of the git repository that contains a directory.
+//!
+//! The git directory isn't necessarily a `.git` directory in the root of the
+//! working tree:
+//!
+//! * In linked worktrees and submodules, `.git` is a file that points to the
+//!   actual git directory with a `gitdir: <path>` line.
+//! * Linked worktrees share objects and refs with the main repository, whose
+//!   git directory is referenced by a `commondir` file.
+//! * `GIT_DIR` and `GIT_WORK_TREE` override discovery entirely.
+//!
+//! Commands created with [GitRepository::command] pass the discovered
+//! directories to git explicitly, so they operate on the same repository as
+//! [GitRepository::open].
+//!
+//! Working trees can be sparse checkouts that only contain some of the
+//! committed files, see [GitRepository::sparse_checkout].
+
+use std::{
+    backtrace::Backtrace,
+    env,
+    ffi::OsString,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use git2::{ConfigLevel, ErrorCode, Repository};
+use turbopath::AbsoluteSystemPathBuf;
+
+use crate::{command_path, Error};
+
+/// Whether a working tree is a sparse checkout, see
+/// `git help sparse-checkout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseCheckout {
+    /// All committed files are checked out.
+    Disabled,
+    /// Only the files in the directories of the sparse-checkout file, and the
+    /// files directly in their parent directories, are checked out.
+    Cone,
+    /// Only the files matching the gitignore-style patterns of the
+    /// sparse-checkout file are checked out.
+    Patterns,
+}
+
+/// The directories of a git repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitRepository {
+    git_dir: AbsoluteSystemPathBuf,
+    common_dir: AbsoluteSystemPathBuf,
+    work_tree: AbsoluteSystemPathBuf,
+}
+
+impl GitRepository {
+    /// Finds the repository that contains `path`, honoring the `GIT_DIR` and
+    /// `GIT_WORK_TREE` environment variables. Returns `None` if `path` isn't
+    /// in a repository.
+    pub fn discover(path: &AbsoluteSystemPathBuf) -> Result<Option<Self>, Error> {
+        Self::discover_with_overrides(
+            path,
+            env::var_os("GIT_DIR"),
+            env::var_os("GIT_WORK_TREE"),
+            &env::current_dir()?,
+        )
+    }
+
+    /// Like [GitRepository::discover], with the values of `GIT_DIR` and
+    /// `GIT_WORK_TREE` passed explicitly. Relative overrides are resolved
+    /// against `cwd`.
+    fn discover_with_overrides(
+        path: &AbsoluteSystemPathBuf,
+        git_dir: Option<OsString>,
+        work_tree: Option<OsString>,
+        cwd: &Path,
+    ) -> Result<Option<Self>, Error> {
+        if let Some(git_dir) = git_dir.filter(|dir| !dir.is_empty()) {
+            let git_dir = cwd.join(git_dir);
+            // Like git, use the current directory as the root of the working
+            // tree if it isn't set explicitly.
+            let work_tree = work_tree.map_or_else(|| cwd.to_path_buf(), |dir| cwd.join(dir));
+            return Self::from_dirs(&git_dir, &work_tree).map(Some);
+        }
+
+        for dir in path.as_path().ancestors() {
+            let dot_git = dir.join(".git");
+            let metadata = match fs::metadata(&dot_git) {
+                Ok(metadata) => metadata,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let git_dir = if metadata.is_dir() {
+                dot_git
+            } else {
+                dir.join(read_pointer(&dot_git, "gitdir")?)
+            };
+            return Self::from_dirs(&git_dir, dir).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn from_dirs(git_dir: &Path, work_tree: &Path) -> Result<Self, Error> {
+        let git_dir = canonicalize(git_dir)?;
+        if !git_dir.join("HEAD").exists() {
+            return Err(Error::RepositoryCorrupt(
+                git_dir,
+                "HEAD is missing".to_string(),
+                Backtrace::capture(),
+            ));
+        }
+        let commondir_file = git_dir.join("commondir");
+        let common_dir = if commondir_file.exists() {
+            canonicalize(&git_dir.join(fs::read_to_string(&commondir_file)?.trim_end()))?
+        } else {
+            git_dir.clone()
+        };
+        Ok(GitRepository {
+            git_dir: AbsoluteSystemPathBuf::new(git_dir)?,
+            common_dir: AbsoluteSystemPathBuf::new(common_dir)?,
+            work_tree: AbsoluteSystemPathBuf::new(canonicalize(work_tree)?)?,
+        })
+    }
+
+    /// The git directory of the working tree, e.g. `.git` or
+    /// `.git/worktrees/<name>` for a linked worktree.
+    pub fn git_dir(&self) -> &AbsoluteSystemPathBuf {
+        &self.git_dir
+    }
+
+    /// The git directory that contains the objects and refs, which is shared
+    /// by all worktrees of a repository.
+    pub fn common_dir(&self) -> &AbsoluteSystemPathBuf {
+        &self.common_dir
+    }
+
+    /// The root of the working tree.
+    pub fn work_tree(&self) -> &AbsoluteSystemPathBuf {
+        &self.work_tree
+    }
+
+    /// Creates a git command for this repository that runs in `cwd`.
+    pub fn command(&self, cwd: &AbsoluteSystemPathBuf) -> Command {
+        let mut command = command_path::git_command(cwd.as_path());
+        command
+            .arg("--git-dir")
+            .arg(&*command_path::argument(self.git_dir.as_path()))
+            .arg("--work-tree")
+            .arg(&*command_path::argument(self.work_tree.as_path()));
+        command
+    }
+
+    /// Opens this repository with libgit2.
+    pub fn open(&self) -> Result<Repository, Error> {
+        let repository = Repository::open(self.git_dir.as_path())?;
+        repository.set_workdir(self.work_tree.as_path(), false)?;
+        Ok(repository)
+    }
+
+    /// Reads whether the working tree is a sparse checkout from the
+    /// `core.sparseCheckout` and `core.sparseCheckoutCone` settings.
+    ///
+    /// `git sparse-checkout` stores the settings in the `config.worktree` file
+    /// of the git directory, so that each worktree can be sparse on its own.
+    pub fn sparse_checkout(&self) -> Result<SparseCheckout, Error> {
+        let mut config = self.open()?.config()?;
+        let worktree_config = self.git_dir.as_path().join("config.worktree");
+        if worktree_config.exists() {
+            config.add_file(&worktree_config, ConfigLevel::App, false)?;
+        }
+        let get_bool = |name| match config.get_bool(name) {
+            Ok(value) => Ok(value),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(false),
+            Err(e) => Err(e),
+        };
+        Ok(if !get_bool("core.sparseCheckout")? {
+            SparseCheckout::Disabled
+        } else if get_bool("core.sparseCheckoutCone")? {
+            SparseCheckout::Cone
+        } else {
+            SparseCheckout::Patterns
+        })
+    }
+}
+
+/// Reads the path from a file with a single `<key>: <path>` line, like the
+/// `.git` file of a worktree.
+fn read_pointer(file: &Path, key: &str) -> Result<PathBuf, Error> {
+    let content = fs::read_to_string(file)?;
+    content
+        .trim_end()
+        .strip_prefix(key)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .map(|path| PathBuf::from(path.trim()))
+        .ok_or_else(|| {
+            Error::RepositoryCorrupt(
+                file.to_path_buf(),
+                format!("it doesn't contain a `{}:` line", key),
+                Backtrace::capture(),
+            )
+        })
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, Error> {
+    dunce::canonicalize(path).map_err(|err| {
+        Error::RepositoryCorrupt(
+            path.to_path_buf(),
+            format!("it can't be resolved: {}", err),
+            Backtrace::capture(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{GitRepository, SparseCheckout};
+
+    fn git(cwd: &Path, args: &[&str]) {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {:?}",
+            args,
+            output
+        );
+    }
+
+    fn absolute(path: &Path) -> AbsoluteSystemPathBuf {
+        AbsoluteSystemPathBuf::new(dunce::canonicalize(path).unwrap()).unwrap()
+    }
+
+    fn init(dir: &Path) {
+        git(dir, &["init", "--quiet"]);
+        git(dir, &["config", "user.name", "test"]);
+        git(dir, &["config", "user.email", "test@example.com"]);
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "--quiet", "-m", "initial"]);
+    }
+
+    #[test]
+    fn test_discover_from_subdirectory() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = absolute(tmp.path());
+        init(root.as_path());
+        fs::create_dir(root.as_path().join("packages")).unwrap();
+
+        let repository = GitRepository::discover_with_overrides(
+            &root.join_literal("packages"),
+            None,
+            None,
+            Path::new("/"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(repository.work_tree(), &root);
+        assert_eq!(repository.git_dir().as_path(), root.as_path().join(".git"));
+        assert_eq!(repository.common_dir(), repository.git_dir());
+    }
+
+    #[test]
+    fn test_discover_linked_worktree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let main = tmp.path().join("main");
+        fs::create_dir(&main).unwrap();
+        init(&main);
+        git(&main, &["worktree", "add", "--quiet", "../linked"]);
+        let main = absolute(&main);
+        let linked = absolute(&tmp.path().join("linked"));
+
+        let repository =
+            GitRepository::discover_with_overrides(&linked, None, None, Path::new("/"))
+                .unwrap()
+                .unwrap();
+        assert_eq!(repository.work_tree(), &linked);
+        assert_eq!(
+            repository.git_dir().as_path(),
+            main.as_path().join(".git").join("worktrees").join("linked")
+        );
+        assert_eq!(
+            repository.common_dir().as_path(),
+            main.as_path().join(".git")
+        );
+
+        let output = repository
+            .command(&linked)
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .unwrap();
+        assert_eq!(
+            absolute(Path::new(String::from_utf8(output.stdout).unwrap().trim())),
+            linked
+        );
+        assert!(repository.open().unwrap().head().is_ok());
+    }
+
+    #[test]
+    fn test_discover_with_git_dir_override() {
+        let tmp = tempfile::tempdir().unwrap();
+        let work_tree = tmp.path().join("work-tree");
+        fs::create_dir(&work_tree).unwrap();
+        init(&work_tree);
+        fs::rename(work_tree.join(".git"), tmp.path().join("repo.git")).unwrap();
+        let work_tree = absolute(&work_tree);
+
+        assert_eq!(
+            GitRepository::discover_with_overrides(&work_tree, None, None, tmp.path()).unwrap(),
+            None
+        );
+        let repository = GitRepository::discover_with_overrides(
+            &work_tree,
+            Some("repo.git".into()),
+            Some("work-tree".into()),
+            tmp.path(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(repository.work_tree(), &work_tree);
+        assert_eq!(
+            repository.git_dir(),
+            &absolute(&tmp.path().join("repo.git"))
+        );
+    }
+
+    #[test]
+    fn test_sparse_checkout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = absolute(tmp.path());
+        init(root.as_path());
+        let repository = GitRepository::discover_with_overrides(&root, None, None, Path::new("/"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            repository.sparse_checkout().unwrap(),
+            SparseCheckout::Disabled
+        );
+
+        git(
+            root.as_path(),
+            &["sparse-checkout", "set", "--cone", "packages"],
+        );
+        assert_eq!(repository.sparse_checkout().unwrap(), SparseCheckout::Cone);
+
+        git(
+            root.as_path(),
+            &["sparse-checkout", "set", "--no-cone", "/*.txt"],
+        );
+        assert_eq!(
+            repository.sparse_checkout().unwrap(),
+            SparseCheckout::Patterns
+        );
+
+        git(root.as_path(), &["sparse-checkout", "disable"]);
+        assert_eq!(
+            repository.sparse_checkout().unwrap(),
+            SparseCheckout::Disabled
+        );
+    }
+
+    #[test]
+    fn test_invalid_gitdir_pointer() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = absolute(tmp.path());
+        fs::write(root.as_path().join(".git"), "gitdir: does-not-exist\n").unwrap();
+        assert!(GitRepository::discover_with_overrides(&root, None, None, Path::new("/")).is_err());
+    }
+}
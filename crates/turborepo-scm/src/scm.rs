@@ -0,0 +1,315 @@
+//! The version control systems that turbo reads inputs from.
+//!
+//! [Scm] abstracts over the operations that turbo needs from a repository:
+//! hashing the files of a package, finding changed files and reading the
+//! content of a file at a previous revision. [Git] implements them with the
+//! functions of [crate::git] and [crate::package_deps],
+//! [Hg](crate::hg::Hg) for Mercurial and Sapling repositories, and
+//! [Jj](crate::jj::Jj) for Jujutsu repositories that are colocated with git.
+//!
+//! [discover] finds the repository that contains a directory and returns the
+//! matching implementation.
+
+use std::{collections::HashSet, env, path::Path};
+
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
+
+use crate::{
+    git,
+    hg::Hg,
+    jj::Jj,
+    package_deps::{GitHashes, PackageDepsHasher},
+    repository::GitRepository,
+    Error,
+};
+
+/// A repository of a version control system.
+pub trait Scm: Send + Sync {
+    /// The name of the version control system, e.g. `git`.
+    fn name(&self) -> &'static str;
+
+    /// The root of the working tree.
+    fn root(&self) -> &AbsoluteSystemPathBuf;
+
+    /// Computes the hashes of the files in a package, including uncommitted
+    /// changes. See [crate::package_deps::get_package_deps] for the
+    /// arguments. The hashes are git object hashes for all implementations.
+    fn get_package_deps(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error>;
+
+    /// Finds the files below `turbo_root` that changed since `to_commit`,
+    /// including uncommitted changes, and between the fork point of
+    /// `from_commit` and `to_commit`. The paths are relative to `turbo_root`.
+    /// See [crate::git::changed_files].
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        from_commit: Option<&str>,
+        to_commit: &str,
+        include_untracked: bool,
+    ) -> Result<HashSet<String>, Error>;
+
+    /// Reads the content of the file at `file_path`, which is absolute or
+    /// relative to the root, at `from_commit`.
+    fn previous_content(&self, from_commit: &str, file_path: &Path) -> Result<Vec<u8>, Error>;
+}
+
+/// A git repository.
+#[derive(Debug, Clone)]
+pub struct Git {
+    root: AbsoluteSystemPathBuf,
+    hasher: PackageDepsHasher,
+}
+
+impl Git {
+    /// The git repository whose working tree is `root`.
+    pub fn new(root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            root,
+            hasher: PackageDepsHasher::new(),
+        }
+    }
+
+    /// Sets the [PackageDepsHasher] that hashes the files of packages.
+    pub fn hasher(mut self, hasher: PackageDepsHasher) -> Self {
+        self.hasher = hasher;
+        self
+    }
+}
+
+impl Scm for Git {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn root(&self) -> &AbsoluteSystemPathBuf {
+        &self.root
+    }
+
+    fn get_package_deps(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        self.hasher
+            .get_package_deps(turbo_root, package_path, inputs)
+    }
+
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPathBuf,
+        from_commit: Option<&str>,
+        to_commit: &str,
+        include_untracked: bool,
+    ) -> Result<HashSet<String>, Error> {
+        git::changed_files(
+            self.root.as_path().to_path_buf(),
+            turbo_root.as_path().to_path_buf(),
+            from_commit,
+            to_commit,
+            include_untracked,
+        )
+    }
+
+    fn previous_content(&self, from_commit: &str, file_path: &Path) -> Result<Vec<u8>, Error> {
+        git::previous_content(
+            self.root.as_path().to_path_buf(),
+            from_commit,
+            file_path.to_path_buf(),
+        )
+    }
+}
+
+/// Finds the repository that contains `path`, or `None` if it isn't in a
+/// repository.
+///
+/// The innermost directory with a `.git`, `.sl` or `.hg` entry is the root of
+/// the repository. A `.jj` directory next to `.git` selects a colocated
+/// Jujutsu repository. Git repositories are discovered like git does, see
+/// [crate::repository], and `GIT_DIR` always selects a git repository.
+pub fn discover(path: &AbsoluteSystemPathBuf) -> Result<Option<Box<dyn Scm>>, Error> {
+    if env::var_os("GIT_DIR").map_or(true, |dir| dir.is_empty()) {
+        for dir in path.as_path().ancestors() {
+            if dir.join(".git").exists() {
+                if dir.join(".jj").is_dir() {
+                    return Ok(Some(Box::new(Jj::new(AbsoluteSystemPathBuf::new(dir)?))));
+                }
+                break;
+            }
+            if dir.join(".sl").is_dir() {
+                return Ok(Some(Box::new(Hg::sapling(AbsoluteSystemPathBuf::new(
+                    dir,
+                )?))));
+            }
+            if dir.join(".hg").is_dir() {
+                return Ok(Some(Box::new(Hg::mercurial(AbsoluteSystemPathBuf::new(
+                    dir,
+                )?))));
+            }
+        }
+    }
+    Ok(GitRepository::discover(path)?
+        .map(|repository| Box::new(Git::new(repository.work_tree().clone())) as Box<dyn Scm>))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+
+    use super::discover;
+
+    fn absolute(path: &Path) -> AbsoluteSystemPathBuf {
+        AbsoluteSystemPathBuf::new(dunce::canonicalize(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_discover() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = absolute(tmp.path());
+        assert!(discover(&root).unwrap().is_none());
+
+        fs::create_dir_all(root.as_path().join("hg/.hg")).unwrap();
+        fs::create_dir_all(root.as_path().join("hg/packages/a")).unwrap();
+        let scm = discover(&root.join_literal("hg/packages/a"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(scm.name(), "hg");
+        assert_eq!(scm.root(), &root.join_literal("hg"));
+
+        fs::create_dir_all(root.as_path().join("sl/.sl")).unwrap();
+        let scm = discover(&root.join_literal("sl")).unwrap().unwrap();
+        assert_eq!(scm.name(), "sl");
+
+        // A git repository nested in a Mercurial repository.
+        let git_root = root.join_literal("hg/packages/a");
+        let output = Command::new("git")
+            .args(["init", "--quiet"])
+            .current_dir(git_root.as_path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let scm = discover(&git_root).unwrap().unwrap();
+        assert_eq!(scm.name(), "git");
+        assert_eq!(scm.root(), &git_root);
+
+        fs::create_dir(git_root.as_path().join(".jj")).unwrap();
+        let scm = discover(&git_root).unwrap().unwrap();
+        assert_eq!(scm.name(), "jj");
+        assert_eq!(scm.root(), &git_root);
+    }
+
+    #[test]
+    fn test_git() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = absolute(tmp.path());
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(root.as_path())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed", args);
+        };
+        git(&["init", "--quiet"]);
+        git(&["config", "user.name", "test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        fs::create_dir(root.as_path().join("a")).unwrap();
+        fs::write(root.as_path().join("a/file.txt"), "hello\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+        fs::write(root.as_path().join("a/file.txt"), "world\n").unwrap();
+
+        let git_scm = discover(&root).unwrap().unwrap();
+        // A colocated Jujutsu repository reads the git repository as well.
+        // Without jj, revisions are passed to git unchanged.
+        fs::create_dir(root.as_path().join(".jj")).unwrap();
+        let jj_scm = discover(&root).unwrap().unwrap();
+        for scm in [git_scm, jj_scm] {
+            let package_path = AnchoredSystemPathBuf::try_from(Path::new("a")).unwrap();
+            assert_eq!(
+                scm.get_package_deps(&root, &package_path, &[])
+                    .unwrap()
+                    .len(),
+                1
+            );
+            assert_eq!(
+                scm.changed_files(&root, None, "HEAD", true).unwrap(),
+                ["a/file.txt".to_string()].into()
+            );
+            assert_eq!(
+                scm.previous_content("HEAD", Path::new("a/file.txt"))
+                    .unwrap(),
+                b"hello\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_hg() {
+        // Mercurial isn't installed everywhere the tests run.
+        if Command::new("hg").arg("--version").output().is_err() {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let root = absolute(tmp.path());
+        let hg = |args: &[&str]| {
+            let output = Command::new("hg")
+                .args(["--config", "ui.username=test"])
+                .args(args)
+                .current_dir(root.as_path())
+                .env("HGPLAIN", "1")
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "hg {:?} failed", args);
+        };
+        hg(&["init"]);
+        fs::create_dir(root.as_path().join("a")).unwrap();
+        fs::write(root.as_path().join("a/file.txt"), "hello\n").unwrap();
+        hg(&["add", "--quiet"]);
+        hg(&["commit", "--quiet", "-m", "initial"]);
+        fs::write(root.as_path().join("a/file.txt"), "world\n").unwrap();
+        fs::write(root.as_path().join("a/untracked.txt"), "hello\n").unwrap();
+
+        let scm = discover(&root.join_literal("a")).unwrap().unwrap();
+        assert_eq!(scm.name(), "hg");
+        assert_eq!(scm.root(), &root);
+        let package_path = AnchoredSystemPathBuf::try_from(Path::new("a")).unwrap();
+        let hashes = scm.get_package_deps(&root, &package_path, &[]).unwrap();
+        // The hashes are git object hashes of "world\n" and "hello\n".
+        assert_eq!(
+            hashes
+                .iter()
+                .map(|(path, hash)| (path.to_str().unwrap(), hash.as_str()))
+                .collect::<std::collections::BTreeMap<_, _>>(),
+            [
+                ("file.txt", "cc628ccd10742baea8241c5924df992b5c019f71"),
+                ("untracked.txt", "ce013625030ba8dba906f756967f9e9ca394464a"),
+            ]
+            .into()
+        );
+        assert_eq!(
+            crate::package_deps::get_package_deps(&root, &package_path, &[]).unwrap(),
+            hashes
+        );
+        assert_eq!(
+            scm.changed_files(&root, None, "HEAD", false).unwrap(),
+            ["a/file.txt".to_string()].into()
+        );
+        assert_eq!(
+            scm.changed_files(&root, None, "HEAD", true).unwrap(),
+            ["a/file.txt".to_string(), "a/untracked.txt".to_string()].into()
+        );
+        assert_eq!(
+            scm.previous_content("HEAD", Path::new("a/file.txt"))
+                .unwrap(),
+            b"hello\n"
+        );
+    }
+}
@@ -0,0 +1,53 @@
+//! Fixtures that are shared by the tests of the modules that read
+//! repositories.
+
+use std::{fs, path::Path, process::Command};
+
+use tempfile::TempDir;
+use turbopath::{AbsoluteSystemPathBuf, RelativeUnixPathBuf};
+
+/// `git hash-object` of "hello\n".
+pub(crate) const HELLO: &str = "ce013625030ba8dba906f756967f9e9ca394464a";
+/// `git hash-object` of "world\n".
+pub(crate) const WORLD: &str = "cc628ccd10742baea8241c5924df992b5c019f71";
+
+/// Runs git with `args` in `root` and fails the test if git fails.
+pub(crate) fn git(root: &Path, args: &[&str]) {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Creates an empty git repository with a committer in a temporary directory,
+/// which is deleted when the returned [TempDir] is dropped.
+pub(crate) fn setup_repository() -> (TempDir, AbsoluteSystemPathBuf) {
+    let repo_root = tempfile::tempdir().unwrap();
+    let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(repo_root.path()).unwrap()).unwrap();
+    git(root.as_path(), &["init", "--quiet"]);
+    git(root.as_path(), &["config", "user.name", "test"]);
+    git(
+        root.as_path(),
+        &["config", "user.email", "test@example.com"],
+    );
+    (repo_root, root)
+}
+
+/// Writes `contents` to `path`, relative to `root`, creating its parent
+/// directories.
+pub(crate) fn write(root: &AbsoluteSystemPathBuf, path: &str, contents: &str) {
+    let path = root.as_path().join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+pub(crate) fn unix(path: &str) -> RelativeUnixPathBuf {
+    RelativeUnixPathBuf::new(path).unwrap()
+}
@@ -0,0 +1,196 @@
+//! A cache of the files that are committed in `HEAD`, shared by the packages
+//! of a repository.
+//!
+//! Hashing a package lists the files of `HEAD` below the package, which runs
+//! `git ls-tree` or walks the tree with libgit2 once per package. With a
+//! [TreeCache], the tree of the whole repository is listed once per `HEAD`
+//! commit instead, and each package is served the slice below its directory.
+//! When `HEAD` changes, e.g. after a commit or a checkout, the tree is listed
+//! again.
+//!
+//! In repositories with large directories that no package needs, e.g. vendored
+//! toolchains, listing the whole tree costs more than it saves. The listing can
+//! be limited to the directories of the packages with [TreeCache::pathspecs].
+//! Packages outside of these directories are listed on their own.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use git2::Repository;
+use turbopath::{AbsoluteSystemPathBuf, RelativeUnixPathBuf};
+
+use crate::{
+    observer::PackageObserver,
+    package_deps::{git_ls_tree, is_below, libgit2_ls_tree, GitHashes},
+    process::ProcessLimits,
+    repository::GitRepository,
+    Error,
+};
+
+/// The files of the `HEAD` commit of a repository.
+#[derive(Debug)]
+struct CachedTree {
+    head: String,
+    /// The hashes of the files, with paths relative to the root of the
+    /// working tree.
+    files: BTreeMap<String, String>,
+    submodules: Vec<String>,
+}
+
+/// Caches the files that are committed in `HEAD` per repository, see the
+/// [module documentation](self). The cache can be shared between threads and
+/// [PackageDepsHasher](crate::package_deps::PackageDepsHasher)s.
+#[derive(Debug, Default)]
+pub struct TreeCache {
+    /// The cached trees by git directory, which is different for each working
+    /// tree of a repository.
+    trees: Mutex<HashMap<PathBuf, Arc<CachedTree>>>,
+    /// The directories that trees are limited to, or `None` for the whole
+    /// tree.
+    pathspecs: Option<Vec<String>>,
+}
+
+impl TreeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lists the files below `pathspecs`, which are directories relative
+    /// to the root of the working tree, usually the directories of the
+    /// packages. Directories below other directories of `pathspecs` are
+    /// redundant and dropped.
+    pub fn pathspecs(mut self, pathspecs: Vec<RelativeUnixPathBuf>) -> Self {
+        // Packages below pathspecs that aren't UTF-8 are listed on their own.
+        let mut pathspecs = pathspecs
+            .iter()
+            .filter_map(|pathspec| pathspec.to_str().ok())
+            .map(|pathspec| pathspec.trim_end_matches('/').to_string())
+            .collect::<Vec<_>>();
+        // Sorted, a directory comes before the directories below it.
+        pathspecs.sort();
+        let mut kept: Vec<String> = Vec::new();
+        for pathspec in pathspecs {
+            if !kept.iter().any(|dir| is_below(&pathspec, dir)) {
+                kept.push(pathspec);
+            }
+        }
+        self.pathspecs = Some(kept);
+        self
+    }
+
+    /// Drops all cached trees.
+    pub fn clear(&self) {
+        self.trees.lock().expect("tree cache is poisoned").clear();
+    }
+
+    /// Lists the files committed in `HEAD` below `root_path`, like
+    /// `git_ls_tree`, from the cached tree of `git_repository`. `repository`
+    /// is the repository opened with libgit2, if it's read in-process. If the
+    /// tree is listed again, git is reported to `observer`.
+    ///
+    /// Returns `None` if the cache can't be used, e.g. because `HEAD` doesn't
+    /// have a commit yet or `root_path` isn't below the pathspecs.
+    pub(crate) fn ls_tree(
+        &self,
+        git_repository: &GitRepository,
+        repository: Option<&Repository>,
+        root_path: &AbsoluteSystemPathBuf,
+        limits: &ProcessLimits,
+        observer: &PackageObserver,
+    ) -> Result<Option<(GitHashes, Vec<RelativeUnixPathBuf>)>, Error> {
+        let Ok(prefix) = root_path.anchor_at(git_repository.work_tree()) else {
+            return Ok(None);
+        };
+        let prefix = prefix.to_unix();
+        let prefix = prefix.to_str()?;
+        if let Some(pathspecs) = &self.pathspecs {
+            if !pathspecs.iter().any(|pathspec| is_below(prefix, pathspec)) {
+                return Ok(None);
+            }
+        }
+        let Some(head) = git_repository.head()?.sha().map(str::to_string) else {
+            return Ok(None);
+        };
+        let tree = self.tree(git_repository, repository, head, limits, observer)?;
+
+        let (range_start, strip) = if prefix.is_empty() {
+            (String::new(), 0)
+        } else {
+            (format!("{}/", prefix), prefix.len() + 1)
+        };
+        let mut hashes = GitHashes::new();
+        for (path, hash) in tree.files.range(range_start.clone()..) {
+            if !path.starts_with(&range_start) {
+                break;
+            }
+            hashes.insert(RelativeUnixPathBuf::new(&path[strip..])?, hash.clone());
+        }
+        let submodules = tree
+            .submodules
+            .iter()
+            .filter(|path| path.starts_with(&range_start))
+            .map(|path| RelativeUnixPathBuf::new(&path[strip..]))
+            .collect::<Result<_, _>>()?;
+        Ok(Some((hashes, submodules)))
+    }
+
+    /// Returns the cached tree of `git_repository` if it's the tree of `head`,
+    /// or lists the tree again.
+    fn tree(
+        &self,
+        git_repository: &GitRepository,
+        repository: Option<&Repository>,
+        head: String,
+        limits: &ProcessLimits,
+        observer: &PackageObserver,
+    ) -> Result<Arc<CachedTree>, Error> {
+        let key = git_repository.git_dir().as_path().to_path_buf();
+        if let Some(tree) = self.trees.lock().expect("tree cache is poisoned").get(&key) {
+            if tree.head == head {
+                return Ok(tree.clone());
+            }
+        }
+
+        // List the tree without holding the lock, so that other repositories
+        // can be served in the meantime.
+        let pathspecs = self
+            .pathspecs
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        // An empty pathspec is the whole tree.
+        let pathspecs = if pathspecs.contains(&"") {
+            Vec::new()
+        } else {
+            pathspecs
+        };
+        let (files, submodules) = match repository {
+            None => git_ls_tree(
+                git_repository,
+                git_repository.work_tree(),
+                &pathspecs,
+                limits,
+                observer,
+            )?,
+            Some(repository) => libgit2_ls_tree(repository, "", &pathspecs)?,
+        };
+        let to_string = |path: &RelativeUnixPathBuf| path.to_str().map(str::to_string);
+        let tree = Arc::new(CachedTree {
+            head,
+            files: files
+                .iter()
+                .map(|(path, hash)| Ok((to_string(path)?, hash.clone())))
+                .collect::<Result<_, Error>>()?,
+            submodules: submodules.iter().map(to_string).collect::<Result<_, _>>()?,
+        });
+        self.trees
+            .lock()
+            .expect("tree cache is poisoned")
+            .insert(key, tree.clone());
+        Ok(tree)
+    }
+}
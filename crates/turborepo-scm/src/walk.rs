@@ -0,0 +1,290 @@
+//! Walks the files of a directory and skips the ones that git ignores, for
+//! when git can't list them.
+//!
+//! The `.gitignore` file of every directory that is walked applies to the
+//! files below it, with patterns relative to its directory, like in git. A
+//! file is ignored according to the last matching pattern of the deepest
+//! `.gitignore` file that has one, so nested files can include files again
+//! with negated patterns. Files in an ignored directory can't be included
+//! again, since the directory isn't walked at all. The `.gitignore` files of
+//! directories above the walked directory, e.g. of the monorepo, and the
+//! global excludes file of git apply with a lower precedence.
+
+use std::fs;
+
+use turbopath::{AbsoluteSystemPathBuf, PathValidationError, RelativeUnixPathBuf};
+
+use crate::{
+    ignore::{IgnoreFile, GITIGNORE},
+    Error,
+};
+
+/// The `.gitignore` file of a directory, with the path of the directory
+/// relative to the root of the walk.
+#[derive(Debug, Clone)]
+struct ScopedIgnoreFile {
+    /// The prefix that turns paths relative to the root of the walk into paths
+    /// relative to the directory of the file. Empty for the root and for
+    /// directories above it.
+    prefix: String,
+    /// For directories above the root, the path of the root relative to the
+    /// directory, with a trailing `/`.
+    root_prefix: String,
+    file: IgnoreFile,
+}
+
+impl ScopedIgnoreFile {
+    fn relative(&self, path: &str) -> Option<String> {
+        let path = path.strip_prefix(&self.prefix)?;
+        Some(format!("{}{}", self.root_prefix, path))
+    }
+}
+
+/// Walks a directory recursively, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Walker {
+    root: AbsoluteSystemPathBuf,
+    /// The `.gitignore` files of directories above `root`, outermost first.
+    parents: Vec<ScopedIgnoreFile>,
+    global_excludes: IgnoreFile,
+    skipped_directories: Vec<String>,
+    gitignore: bool,
+}
+
+impl Walker {
+    pub fn new(root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            root,
+            parents: Vec::new(),
+            global_excludes: IgnoreFile::default(),
+            skipped_directories: Vec::new(),
+            gitignore: true,
+        }
+    }
+
+    /// The directory that is walked.
+    pub fn root(&self) -> &AbsoluteSystemPathBuf {
+        &self.root
+    }
+
+    /// Also applies the `.gitignore` files of `top` and of each directory
+    /// between it and the root of the walk, e.g. of the monorepo when walking
+    /// a package. Fails if `top` doesn't contain the root.
+    pub fn parents(mut self, top: &AbsoluteSystemPathBuf) -> Result<Self, Error> {
+        let anchored = self.root.anchor_at(top)?.to_unix();
+        let mut parents = Vec::new();
+        let mut dir = top.clone();
+        let mut root_prefix = anchored.to_str()?;
+        while !root_prefix.is_empty() {
+            parents.push(ScopedIgnoreFile {
+                prefix: String::new(),
+                root_prefix: format!("{}/", root_prefix),
+                file: IgnoreFile::read_named(&dir, GITIGNORE)?,
+            });
+            let (name, rest) = root_prefix.split_once('/').unwrap_or((root_prefix, ""));
+            dir = dir.join_literal(name);
+            root_prefix = rest;
+        }
+        self.parents = parents;
+        Ok(self)
+    }
+
+    /// Applies the global excludes of git with the lowest precedence, see
+    /// [IgnoreFile::read_global_excludes].
+    pub fn global_excludes(mut self, global_excludes: IgnoreFile) -> Self {
+        self.global_excludes = global_excludes;
+        self
+    }
+
+    /// Never walks directories called `name`, e.g. `node_modules`.
+    pub fn skip_directory(mut self, name: &str) -> Self {
+        self.skipped_directories.push(name.to_string());
+        self
+    }
+
+    /// Whether `.gitignore` files and the global excludes apply, which they
+    /// do by default. Without them, ignored files are walked as well.
+    pub fn gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// Returns the files and symlinks below the root that aren't ignored, with
+    /// paths relative to the root, in no particular order. `.git` entries are
+    /// always skipped.
+    pub fn walk(&self) -> Result<Vec<(RelativeUnixPathBuf, fs::FileType)>, Error> {
+        let mut files = Vec::new();
+        // Directories to walk, with the `.gitignore` files of the directories
+        // above them, innermost last.
+        let mut dirs = vec![(String::new(), Vec::new())];
+        while let Some((dir, mut ignores)) = dirs.pop() {
+            let full_dir = if dir.is_empty() {
+                self.root.clone()
+            } else {
+                self.root.join_literal(&dir)
+            };
+            let gitignore = if self.gitignore {
+                IgnoreFile::read_named(&full_dir, GITIGNORE)?
+            } else {
+                IgnoreFile::default()
+            };
+            if !gitignore.is_empty() {
+                ignores.push(ScopedIgnoreFile {
+                    prefix: if dir.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{}/", dir)
+                    },
+                    root_prefix: String::new(),
+                    file: gitignore,
+                });
+            }
+
+            for entry in fs::read_dir(full_dir.as_path())? {
+                let entry = entry?;
+                let file_name = entry.file_name();
+                let Some(name) = file_name.to_str() else {
+                    return Err(PathValidationError::InvalidUnicode(
+                        full_dir.as_path().join(&file_name),
+                    )
+                    .into());
+                };
+                if name == ".git" {
+                    // In linked worktrees and submodules, `.git` is a file.
+                    continue;
+                }
+                let path = if dir.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", dir, name)
+                };
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    if !self
+                        .skipped_directories
+                        .iter()
+                        .any(|skipped| skipped == name)
+                        && !self.is_ignored(&ignores, &path, true)
+                    {
+                        dirs.push((path, ignores.clone()));
+                    }
+                } else if !self.is_ignored(&ignores, &path, false) {
+                    files.push((RelativeUnixPathBuf::new(path)?, file_type));
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Whether `path`, relative to the root, is ignored by the innermost
+    /// `.gitignore` file with a matching pattern.
+    fn is_ignored(&self, ignores: &[ScopedIgnoreFile], path: &str, is_dir: bool) -> bool {
+        if !self.gitignore {
+            return false;
+        }
+        let matches = |ignore: &ScopedIgnoreFile| {
+            let path = ignore.relative(path)?;
+            if is_dir {
+                ignore.file.matches_dir(&path)
+            } else {
+                ignore.file.matches(&path)
+            }
+        };
+        ignores
+            .iter()
+            .rev()
+            .chain(self.parents.iter().rev())
+            .find_map(matches)
+            .or_else(|| {
+                // Global excludes are relative to the outermost directory.
+                let path = match self.parents.first() {
+                    Some(outermost) => format!("{}{}", outermost.root_prefix, path),
+                    None => path.to_string(),
+                };
+                if is_dir {
+                    self.global_excludes.matches_dir(&path)
+                } else {
+                    self.global_excludes.matches(&path)
+                }
+            })
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::Walker;
+    use crate::ignore::IgnoreFile;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn walk(walker: &Walker) -> Vec<String> {
+        let mut files = walker
+            .walk()
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path.to_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_nested_gitignores() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", "*.log\n/packages/a/fixtures/\n");
+        write(root, "packages/.gitignore", "dist/\n");
+        write(root, "packages/a/.gitignore", "!keep.log\ngenerated/**\n");
+        write(root, "packages/a/src/.gitignore", "*.snap\n!keep.snap\n");
+        write(root, "packages/a/index.js", "");
+        write(root, "packages/a/debug.log", "");
+        write(root, "packages/a/keep.log", "");
+        write(root, "packages/a/dist/index.js", "");
+        write(root, "packages/a/fixtures/data.json", "");
+        write(root, "packages/a/generated/types.ts", "");
+        write(root, "packages/a/src/a.snap", "");
+        write(root, "packages/a/src/keep.snap", "");
+        write(root, "packages/a/src/index.test.js", "");
+        write(root, "packages/a/node_modules/b/index.js", "");
+        write(root, "packages/a/temp.tmp", "");
+
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(root).unwrap()).unwrap();
+        let walker = Walker::new(root.join_literal("packages/a"))
+            .parents(&root)
+            .unwrap()
+            .global_excludes(IgnoreFile::parse("*.tmp\n"))
+            .skip_directory("node_modules");
+        assert_eq!(
+            walk(&walker),
+            [
+                ".gitignore",
+                "index.js",
+                "keep.log",
+                "src/.gitignore",
+                "src/index.test.js",
+                "src/keep.snap",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignored_directory_is_not_walked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", "build/\n!build/keep.js\n");
+        write(root, "build/keep.js", "");
+        write(root, "index.js", "");
+
+        let root = AbsoluteSystemPathBuf::new(dunce::canonicalize(root).unwrap()).unwrap();
+        assert_eq!(walk(&Walker::new(root)), [".gitignore", "index.js"]);
+    }
+}
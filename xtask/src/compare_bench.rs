@@ -0,0 +1,49 @@
+use std::{fs::File, path::Path, time::Duration};
+
+use anyhow::{bail, Context, Result};
+
+use crate::summarize_bench::data::BaseBenchmarks;
+
+fn read(path: &Path) -> Result<BaseBenchmarks> {
+    let file = File::open(path).with_context(|| format!("unable to open {}", path.display()))?;
+    serde_json::from_reader(file).with_context(|| format!("unable to parse {}", path.display()))
+}
+
+/// Compares the mean times of the benchmarks in `changes` with those in
+/// `baseline`, both exported with `critcmp --export`, and fails if a
+/// benchmark got slower by more than `threshold_percent`.
+pub fn compare(baseline: &Path, changes: &Path, threshold_percent: f64) -> Result<()> {
+    let baseline = read(baseline)?;
+    let changes = read(changes)?;
+    let mut regressions = Vec::new();
+    for (name, benchmark) in &changes.benchmarks {
+        let after = benchmark.estimates.mean.point_estimate;
+        let Some(base) = baseline.benchmarks.get(name) else {
+            println!("{name}: {:?} (no baseline)", Duration::from_nanos(after as u64));
+            continue;
+        };
+        let before = base.estimates.mean.point_estimate;
+        let change = (after / before - 1.0) * 100.0;
+        println!(
+            "{name}: {:?} -> {:?} ({change:+.1}%)",
+            Duration::from_nanos(before as u64),
+            Duration::from_nanos(after as u64),
+        );
+        if change > threshold_percent {
+            regressions.push(name.as_str());
+        }
+    }
+    for name in baseline.benchmarks.keys() {
+        if !changes.benchmarks.contains_key(name) {
+            println!("{name}: missing in {}", changes.name);
+        }
+    }
+    if !regressions.is_empty() {
+        bail!(
+            "{} got slower by more than {threshold_percent}% compared to {}",
+            regressions.join(", "),
+            baseline.name
+        );
+    }
+    Ok(())
+}
@@ -1,13 +1,14 @@
 use std::{
     collections::{HashMap, HashSet},
     env::{current_dir, var_os},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process,
 };
 
 use clap::{arg, Command};
 
 mod command;
+mod compare_bench;
 mod nft_bench;
 mod publish;
 mod summarize_bench;
@@ -51,6 +52,16 @@ fn cli() -> Command {
                 )
                 .arg(arg!(<PATH> "the path to the benchmark data directory")),
         )
+        .subcommand(
+            Command::new("compare-benchmarks")
+                .about(
+                    "Compare two benchmark runs exported with `critcmp --export` and fail if a \
+                     benchmark got slower",
+                )
+                .arg(arg!(<BASELINE> "the exported results of the baseline, e.g. of main"))
+                .arg(arg!(<CHANGES> "the exported results of the changes to compare"))
+                .arg(arg!(--threshold <PERCENT> "the allowed slowdown in percent, defaults to 10")),
+        )
         .subcommand(
             Command::new("visualize-bundler-benchmarks")
                 .about("Generate visualizations of bundler benchmarks")
@@ -163,6 +174,25 @@ fn main() {
             let path = path.canonicalize().unwrap();
             summarize_bench::process_all(path);
         }
+        Some(("compare-benchmarks", sub_matches)) => {
+            let baseline = sub_matches
+                .get_one::<String>("BASELINE")
+                .expect("BASELINE is required");
+            let changes = sub_matches
+                .get_one::<String>("CHANGES")
+                .expect("CHANGES is required");
+            let threshold = sub_matches
+                .get_one::<String>("threshold")
+                .map_or(10.0, |threshold| {
+                    threshold.parse().expect("threshold must be a number")
+                });
+            if let Err(err) =
+                compare_bench::compare(Path::new(baseline), Path::new(changes), threshold)
+            {
+                eprintln!("{err:?}");
+                process::exit(1);
+            }
+        }
         Some(("visualize-bundler-benchmarks", sub_matches)) => {
             let path = sub_matches
                 .get_one::<String>("PATH_TO_SUMMARY_JSON")